@@ -345,6 +345,14 @@ function sendScrapeResult(data) {
       }
     }
 
+    // Raw html is handed over as is, the main content is extracted on the Rust side
+    if (generic_content_rule.format === 'html') {
+      if (!hasSentResult) {
+        sendScrapeResult({ success: document.documentElement.outerHTML })
+      }
+      return
+    }
+
     const maxTry = 3
     let lastError = 'Unknown error'
     let useReadabilityFirst = true
@@ -575,8 +583,9 @@ function sendScrapeResult(data) {
     logger.debug('generic_content_rule:', JSON.stringify(generic_content_rule))
 
     try {
-      // Wait for TurndownService to be available (only if NOT links)
-      if (generic_content_rule?.format !== 'links') {
+      // Wait for TurndownService to be available (only if NOT links or raw html)
+      const format = generic_content_rule?.format
+      if (format !== 'links' && format !== 'html') {
         await new Promise((resolve, reject) => {
           const interval = setInterval(() => {
             if (typeof TurndownService !== 'undefined' && isFinalHttp()) {
@@ -592,7 +601,7 @@ function sendScrapeResult(data) {
           }, 3000)
         })
       } else {
-        // For links, just ensure we are on the final URL
+        // For links and raw html, just ensure we are on the final URL
         await new Promise((resolve) => {
           const interval = setInterval(() => {
             if (isFinalHttp()) {
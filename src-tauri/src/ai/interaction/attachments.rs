@@ -0,0 +1,283 @@
+//! Files attached to chat messages.
+//!
+//! A message may carry an `attachments` array next to its content, with the entries the chat
+//! window stores in the message metadata. Before the request is sent the attachments are
+//! turned into content blocks: text files are inlined as context ahead of the message text
+//! and images are appended as `image_url` blocks. An attachment refers to its data inline
+//! (`content` for text, a data or http URL for images) or to a file on disk by `path`.
+
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use rust_i18n::t;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::ai::error::AiError;
+
+/// Characters of a text attachment sent to the model, the rest is cut with a note.
+pub const MAX_TEXT_ATTACHMENT_CHARS: usize = 100_000;
+/// Largest image sent to the model, larger images are rejected.
+pub const MAX_IMAGE_ATTACHMENT_BYTES: usize = 20 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AttachmentKind {
+    Text,
+    Image,
+}
+
+/// A file attached to a message.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageAttachment {
+    #[serde(rename = "type")]
+    pub kind: AttachmentKind,
+    pub name: String,
+    /// Text of a text attachment
+    pub content: Option<String>,
+    /// Data or http URL of an image attachment
+    pub source_url: Option<String>,
+    pub url: Option<String>,
+    /// File on disk, read when the message is sent
+    pub path: Option<String>,
+}
+
+/// What an attachment becomes in the request.
+#[derive(Debug, PartialEq)]
+enum AttachmentBlock {
+    Text(String),
+    Image(String),
+}
+
+/// Replaces the `attachments` of every message by content blocks.
+///
+/// Messages without attachments are left as they are.
+pub fn inject_attachments(messages: &mut [Value]) -> Result<(), AiError> {
+    for message in messages.iter_mut() {
+        let Some(attachments) = message
+            .as_object_mut()
+            .and_then(|object| object.remove("attachments"))
+        else {
+            continue;
+        };
+        let attachments: Vec<MessageAttachment> = serde_json::from_value(attachments)
+            .map_err(|e| AiError::InvalidInput(e.to_string()))?;
+        if attachments.is_empty() {
+            continue;
+        }
+
+        let mut context = Vec::new();
+        let mut images = Vec::new();
+        for attachment in &attachments {
+            match attachment_block(attachment)? {
+                AttachmentBlock::Text(text) => context.push(json!({"type": "text", "text": text})),
+                AttachmentBlock::Image(url) => {
+                    images.push(json!({"type": "image_url", "image_url": {"url": url}}))
+                }
+            }
+        }
+
+        let content = match message.get_mut("content").map(Value::take) {
+            Some(Value::String(text)) if !text.is_empty() => {
+                vec![json!({"type": "text", "text": text})]
+            }
+            Some(Value::Array(blocks)) => blocks,
+            _ => Vec::new(),
+        };
+        message["content"] =
+            Value::Array(context.into_iter().chain(content).chain(images).collect());
+    }
+    Ok(())
+}
+
+fn attachment_block(attachment: &MessageAttachment) -> Result<AttachmentBlock, AiError> {
+    let name = attachment.name.as_str();
+    match attachment.kind {
+        AttachmentKind::Text => {
+            if let Some(content) = &attachment.content {
+                return Ok(AttachmentBlock::Text(text_context(name, content)));
+            }
+            let bytes = read_attachment(attachment)?;
+            if let Ok(format) = image::guess_format(&bytes) {
+                return image_data_url(name, format.to_mime_type(), &bytes)
+                    .map(AttachmentBlock::Image);
+            }
+            match String::from_utf8(bytes) {
+                Ok(text) if !text.contains('\0') => {
+                    Ok(AttachmentBlock::Text(text_context(name, &text)))
+                }
+                _ => Err(AiError::InvalidInput(
+                    t!("chat.attachment_binary_unsupported", name = name).to_string(),
+                )),
+            }
+        }
+        AttachmentKind::Image => {
+            if let Some(url) = attachment.source_url.as_ref().or(attachment.url.as_ref()) {
+                // base64 grows the data by a third
+                let size = url
+                    .strip_prefix("data:")
+                    .and_then(|data| data.split_once(','))
+                    .map_or(0, |(_, encoded)| encoded.len() / 4 * 3);
+                check_image_size(name, size)?;
+                return Ok(AttachmentBlock::Image(url.clone()));
+            }
+            let bytes = read_attachment(attachment)?;
+            let format = image::guess_format(&bytes).map_err(|_| {
+                AiError::InvalidInput(
+                    t!("chat.attachment_binary_unsupported", name = name).to_string(),
+                )
+            })?;
+            image_data_url(name, format.to_mime_type(), &bytes).map(AttachmentBlock::Image)
+        }
+    }
+}
+
+fn read_attachment(attachment: &MessageAttachment) -> Result<Vec<u8>, AiError> {
+    let path = attachment.path.as_deref().ok_or_else(|| {
+        AiError::InvalidInput(t!("chat.attachment_empty", name = &attachment.name).to_string())
+    })?;
+    std::fs::read(path).map_err(|e| {
+        AiError::InvalidInput(
+            t!(
+                "chat.attachment_read_failed",
+                name = &attachment.name,
+                error = e.to_string()
+            )
+            .to_string(),
+        )
+    })
+}
+
+fn check_image_size(name: &str, size: usize) -> Result<(), AiError> {
+    if size > MAX_IMAGE_ATTACHMENT_BYTES {
+        return Err(AiError::InvalidInput(
+            t!(
+                "chat.attachment_image_too_large",
+                name = name,
+                size = size,
+                limit = MAX_IMAGE_ATTACHMENT_BYTES
+            )
+            .to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn image_data_url(name: &str, mime_type: &str, bytes: &[u8]) -> Result<String, AiError> {
+    check_image_size(name, bytes.len())?;
+    Ok(format!(
+        "data:{};base64,{}",
+        mime_type,
+        STANDARD.encode(bytes)
+    ))
+}
+
+/// The text of a file as context, cut to `MAX_TEXT_ATTACHMENT_CHARS` characters.
+fn text_context(name: &str, text: &str) -> String {
+    let total = text.chars().count();
+    if total <= MAX_TEXT_ATTACHMENT_CHARS {
+        return format!("<file name=\"{}\">\n{}\n</file>", name, text);
+    }
+    log::warn!(
+        "Attachment '{}' has {} characters, only the first {} are sent",
+        name,
+        total,
+        MAX_TEXT_ATTACHMENT_CHARS
+    );
+    let kept: String = text.chars().take(MAX_TEXT_ATTACHMENT_CHARS).collect();
+    format!(
+        "<file name=\"{}\">\n{}\n[Truncated: the first {} of {} characters of the file are shown.]\n</file>",
+        name, kept, MAX_TEXT_ATTACHMENT_CHARS, total
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{inject_attachments, MAX_TEXT_ATTACHMENT_CHARS};
+    use serde_json::json;
+    use tempfile::tempdir;
+
+    // A 1x1 PNG
+    const PNG: &[u8] = &[
+        0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A, 0x00, 0x00, 0x00, 0x0D, 0x49, 0x48, 0x44,
+        0x52, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x08, 0x06, 0x00, 0x00, 0x00, 0x1F,
+        0x15, 0xC4, 0x89, 0x00, 0x00, 0x00, 0x0D, 0x49, 0x44, 0x41, 0x54, 0x78, 0x9C, 0x63, 0x00,
+        0x01, 0x00, 0x00, 0x05, 0x00, 0x01, 0x0D, 0x0A, 0x2D, 0xB4, 0x00, 0x00, 0x00, 0x00, 0x49,
+        0x45, 0x4E, 0x44, 0xAE, 0x42, 0x60, 0x82,
+    ];
+
+    #[test]
+    fn test_text_file_is_injected_as_context_and_image_as_image_block() {
+        let dir = tempdir().expect("failed to create temp dir");
+        let code = dir.path().join("main.rs");
+        std::fs::write(&code, "fn main() {}").expect("failed to write code file");
+        let image = dir.path().join("screenshot.png");
+        std::fs::write(&image, PNG).expect("failed to write image");
+
+        let mut messages = vec![
+            json!({"role": "system", "content": "You are helpful"}),
+            json!({
+                "role": "user",
+                "content": "What does this do?",
+                "attachments": [
+                    {"type": "text", "name": "main.rs", "path": code},
+                    {"type": "image", "name": "screenshot.png", "path": image},
+                    {"type": "text", "name": "notes.md", "content": "# Notes"}
+                ]
+            }),
+        ];
+        inject_attachments(&mut messages).expect("attachments injected");
+
+        assert_eq!(
+            messages[0],
+            json!({"role": "system", "content": "You are helpful"})
+        );
+        assert!(messages[1].get("attachments").is_none());
+        let content = messages[1]["content"].as_array().expect("content blocks");
+        assert_eq!(content.len(), 4);
+        assert_eq!(
+            content[0]["text"],
+            "<file name=\"main.rs\">\nfn main() {}\n</file>"
+        );
+        assert_eq!(
+            content[1]["text"],
+            "<file name=\"notes.md\">\n# Notes\n</file>"
+        );
+        assert_eq!(
+            content[2],
+            json!({"type": "text", "text": "What does this do?"})
+        );
+        assert_eq!(content[3]["type"], "image_url");
+        let url = content[3]["image_url"]["url"].as_str().expect("image url");
+        assert!(url.starts_with("data:image/png;base64,iVBORw0KGgo"));
+    }
+
+    #[test]
+    fn test_binary_and_oversized_attachments() {
+        let dir = tempdir().expect("failed to create temp dir");
+        let binary = dir.path().join("app.bin");
+        std::fs::write(&binary, [0x7F, 0x45, 0x4C, 0x46, 0x00, 0x01]).expect("failed to write");
+        let mut messages = vec![json!({
+            "role": "user",
+            "content": "Explain",
+            "attachments": [{"type": "text", "name": "app.bin", "path": binary}]
+        })];
+        assert!(inject_attachments(&mut messages).is_err());
+
+        let mut messages = vec![json!({
+            "role": "user",
+            "content": "Summarize",
+            "attachments": [{
+                "type": "text",
+                "name": "big.log",
+                "content": "x".repeat(MAX_TEXT_ATTACHMENT_CHARS + 10)
+            }]
+        })];
+        inject_attachments(&mut messages).expect("attachments injected");
+        let text = messages[0]["content"][0]["text"].as_str().expect("text");
+        assert!(text.contains(&format!(
+            "[Truncated: the first {} of {} characters",
+            MAX_TEXT_ATTACHMENT_CHARS,
+            MAX_TEXT_ATTACHMENT_CHARS + 10
+        )));
+    }
+}
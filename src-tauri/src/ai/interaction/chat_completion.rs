@@ -9,9 +9,10 @@ use tokio::sync::{
     Mutex,
 };
 
+use super::message_persistence::StreamingMessage;
 use crate::ccproxy::ChatProtocol;
 use crate::search::SearchResult;
-use crate::tools::ToolManager;
+use crate::tools::{ToolManager, ToolOutputSink};
 use crate::{
     ai::{
         chat::openai::OpenAIChat,
@@ -82,6 +83,8 @@ pub struct ChatState {
     pub workflow_keys: Arc<DashMap<String, String>>,
     /// Session-level guard preventing duplicate workflow title generation tasks.
     pub workflow_title_generation_in_flight: Arc<DashMap<String, ()>>,
+    /// Assistant messages persisted while they stream, mapping chat_id -> message
+    pub streaming_messages: Arc<DashMap<String, StreamingMessage>>,
 }
 
 impl ChatState {
@@ -105,6 +108,7 @@ impl ChatState {
             main_store,
             workflow_keys: Arc::new(DashMap::new()),
             workflow_title_generation_in_flight: Arc::new(DashMap::new()),
+            streaming_messages: Arc::new(DashMap::new()),
         });
 
         let processor_state_clone = Arc::clone(&state);
@@ -748,6 +752,7 @@ async fn global_message_processor_loop(
                         let t_name_clone = tool_name.clone();
                         let tc_id_clone = tool_call_id.clone();
                         let metadata_clone = response_chunk.metadata.clone(); // The metadata include chat_param
+                        let output_window_label = window_label.clone();
 
                         tokio::spawn(async move {
                             let args_value = arguments_str_opt
@@ -768,9 +773,46 @@ async fn global_message_processor_loop(
                                 cid_clone
                             );
 
+                            // Stream output of long-running tools to the UI while they run,
+                            // the final result below still goes to the model.
+                            let (output_sink, mut output_rx) = ToolOutputSink::channel();
+                            let output_forwarder = tokio::spawn({
+                                let chat_state = cs_arc_clone.clone();
+                                let chat_id = cid_clone.clone();
+                                let metadata = json!({
+                                    "tool_call_id": tc_id_clone.clone(),
+                                    "tool_name": t_name_clone.clone(),
+                                });
+                                async move {
+                                    while let Some(chunk) = output_rx.recv().await {
+                                        let Some(tx) = chat_state
+                                            .channels
+                                            .get_sender(&output_window_label)
+                                            .await
+                                        else {
+                                            continue;
+                                        };
+                                        let output_response = ChatResponse::new_with_arc(
+                                            chat_id.clone(),
+                                            chunk.text,
+                                            MessageType::ToolOutput,
+                                            Some(metadata.clone()),
+                                            None,
+                                        );
+                                        if let Err(e) = tx.try_send(output_response) {
+                                            log::debug!(
+                                                "Dropped tool output for chat_id {}: {}",
+                                                chat_id,
+                                                e
+                                            );
+                                        }
+                                    }
+                                }
+                            });
+
                             let tool_execution_actual_result = match cs_arc_clone
                                 .tool_manager
-                                .tool_call(&t_name_clone, args_value)
+                                .tool_call_with_sink(&t_name_clone, args_value, output_sink)
                                 .await
                             {
                                 Ok(result) => result,
@@ -784,6 +826,9 @@ async fn global_message_processor_loop(
                                     json!({"error": format!("Tool execution failed: {}", e)})
                                 }
                             };
+                            // The sink is dropped with the finished call, so this drains and exits
+                            // before the result is sent and the UI never sees output after it.
+                            let _ = output_forwarder.await;
 
                             // Check if this is a web search result and extract reference information
                             let mut reference_data: Option<Vec<Value>> = None;
@@ -904,6 +949,15 @@ async fn global_message_processor_loop(
                     chat_id
                 );
 
+                // The response is complete once the UI is told so
+                if response_chunk.finish_reason != Some(FinishReason::ToolCalls) {
+                    if let Some((_, mut message)) =
+                        chat_state_arc.streaming_messages.remove(&chat_id)
+                    {
+                        message.flush(&chat_state_arc.main_store, true);
+                    }
+                }
+
                 // IMPORTANT: Do not send end-of-round flag to frontend during tool calls, otherwise frontend may end the session
                 // IMPORTANT: Do not send Finished with ToolCalls reason to UI,
                 // otherwise UI will end the session.
@@ -957,6 +1011,22 @@ async fn global_message_processor_loop(
                 // Do not remove AiChatEnum instance from ChatState.chats as it's shared per protocol.
             }
             _ => {
+                if response_chunk.r#type == MessageType::Error {
+                    // An error ends the stream, the message keeps its incomplete flag
+                    if let Some((_, mut message)) =
+                        chat_state_arc.streaming_messages.remove(&chat_id)
+                    {
+                        message.flush(&chat_state_arc.main_store, false);
+                    }
+                } else if let Some(mut message) =
+                    chat_state_arc.streaming_messages.get_mut(&chat_id)
+                {
+                    message.push(
+                        &chat_state_arc.main_store,
+                        &response_chunk.r#type,
+                        &response_chunk.chunk,
+                    );
+                }
                 if let Some(tx) = chat_state_arc.channels.get_sender(&window_label).await {
                     if let Err(e_send) = tx.try_send(response_chunk.clone()) {
                         log::error!("Failed to send message type {:?} to window '{}' (chat_id {}) channel: {}", response_chunk.r#type, window_label, chat_id, e_send);
@@ -0,0 +1,134 @@
+//! Incremental persistence of the assistant message of a streaming chat.
+//!
+//! The message row is added before the stream starts and flagged incomplete. Its content is
+//! written again at most every `FLUSH_INTERVAL` while chunks arrive, so a crash or an
+//! interrupted stream leaves the response received so far in the database, still flagged.
+
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+
+use crate::ai::traits::chat::MessageType;
+use crate::db::MainStore;
+
+/// Shortest time between two writes of a streaming message.
+const FLUSH_INTERVAL: Duration = Duration::from_millis(500);
+
+/// The assistant message a chat streams into.
+pub struct StreamingMessage {
+    pub message_id: i64,
+    content: String,
+    reasoning: String,
+    dirty: bool,
+    flushed_at: Instant,
+}
+
+impl StreamingMessage {
+    pub fn new(message_id: i64) -> Self {
+        Self {
+            message_id,
+            content: String::new(),
+            reasoning: String::new(),
+            dirty: false,
+            flushed_at: Instant::now(),
+        }
+    }
+
+    /// Adds a text or reasoning chunk, writing the message once `FLUSH_INTERVAL` has passed
+    /// since the last write. Other chunk types are ignored.
+    pub fn push(&mut self, store: &RwLock<MainStore>, chunk_type: &MessageType, chunk: &str) {
+        match chunk_type {
+            MessageType::Text => self.content.push_str(chunk),
+            MessageType::Reasoning | MessageType::Think => self.reasoning.push_str(chunk),
+            _ => return,
+        }
+        self.dirty = true;
+        if self.flushed_at.elapsed() >= FLUSH_INTERVAL {
+            self.flush(store, false);
+        }
+    }
+
+    /// Writes what was received so far; `complete` clears the incomplete flag.
+    pub fn flush(&mut self, store: &RwLock<MainStore>, complete: bool) {
+        if !self.dirty && !complete {
+            return;
+        }
+        let result = store.read().map_err(|e| e.to_string()).and_then(|store| {
+            store
+                .save_streaming_message(self.message_id, &self.content, &self.reasoning, complete)
+                .map_err(|e| e.to_string())
+        });
+        match result {
+            Ok(()) => {
+                self.dirty = false;
+                self.flushed_at = Instant::now();
+            }
+            Err(e) => log::error!(
+                "Failed to persist streaming message {}: {}",
+                self.message_id,
+                e
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{StreamingMessage, FLUSH_INTERVAL};
+    use crate::ai::traits::chat::MessageType;
+    use crate::db::{chat::MESSAGE_INCOMPLETE_KEY, MainStore};
+    use std::sync::RwLock;
+    use std::time::Instant;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_interrupted_stream_leaves_partial_message_flagged_incomplete() {
+        let dir = tempdir().expect("failed to create temp dir");
+        let store = RwLock::new(
+            MainStore::new(dir.path().join("stream_test.db")).expect("failed to create store"),
+        );
+        let (conversation, message_id) = {
+            let store = store.read().expect("failed to lock store");
+            let conversation = store
+                .add_conversation("stream".to_string())
+                .expect("failed to add conversation");
+            let message_id = store
+                .add_streaming_message(conversation, "assistant".to_string(), None)
+                .expect("failed to add streaming message");
+            (conversation, message_id)
+        };
+        let persisted = |store: &RwLock<MainStore>| {
+            store
+                .read()
+                .expect("failed to lock store")
+                .get_messages_for_conversation(conversation)
+                .expect("failed to list messages")
+                .remove(0)
+        };
+
+        let mut message = StreamingMessage::new(message_id);
+        message.push(&store, &MessageType::Reasoning, "Thinking it over");
+        message.push(&store, &MessageType::Text, "The answer is");
+        message.flushed_at = Instant::now()
+            .checked_sub(FLUSH_INTERVAL)
+            .expect("instant in the past");
+        message.push(&store, &MessageType::Text, " forty");
+        message.push(&store, &MessageType::Text, "-two");
+        // The stream stops here, the last chunk was never written
+        drop(message);
+
+        let partial = persisted(&store);
+        assert_eq!(partial.content, "The answer is forty");
+        let metadata = partial.metadata.expect("metadata");
+        assert_eq!(metadata["reasoning"], "Thinking it over");
+        assert_eq!(metadata[MESSAGE_INCOMPLETE_KEY], true);
+
+        let mut message = StreamingMessage::new(message_id);
+        message.push(&store, &MessageType::Text, "The answer is forty-two");
+        message.flush(&store, true);
+        let complete = persisted(&store);
+        assert!(complete
+            .metadata
+            .as_ref()
+            .is_some_and(|m| m.get(MESSAGE_INCOMPLETE_KEY).is_none()));
+    }
+}
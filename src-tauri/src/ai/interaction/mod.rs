@@ -1,2 +1,4 @@
+pub mod attachments;
 pub mod chat_completion;
 pub mod constants;
+pub mod message_persistence;
@@ -28,6 +28,8 @@ pub enum MessageType {
     Think,
     ToolCalls, // Assistant tool selection
     ToolResults,
+    /// Incremental output of a running tool, for display only
+    ToolOutput,
     Step,
 }
 
@@ -60,13 +62,32 @@ impl Display for MessageType {
             MessageType::Text => "text",
             MessageType::ToolCalls => "tool_calls",
             MessageType::ToolResults => "tool_results",
+            MessageType::ToolOutput => "tool_output",
             MessageType::Step => "step",
         };
         write!(f, "{}", s)
     }
 }
 
+/// Which part of an answer a streamed delta belongs to, so the UI can render reasoning apart
+/// from the answer without splitting the text itself.
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum StreamChannel {
+    Reasoning,
+    Answer,
+}
+
 impl MessageType {
+    /// The channel of a streamed delta, `None` for control messages like steps or errors.
+    pub fn stream_channel(&self) -> Option<StreamChannel> {
+        match self {
+            MessageType::Reasoning | MessageType::Think => Some(StreamChannel::Reasoning),
+            MessageType::Text => Some(StreamChannel::Answer),
+            _ => None,
+        }
+    }
+
     pub fn from_str(value: &str) -> Option<Self> {
         match value {
             "error" => Some(MessageType::Error),
@@ -76,6 +97,7 @@ impl MessageType {
             "text" => Some(MessageType::Text),
             "tool_calls" => Some(MessageType::ToolCalls),
             "tool_results" => Some(MessageType::ToolResults),
+            "tool_output" => Some(MessageType::ToolOutput),
             "step" => Some(MessageType::Step),
             _ => {
                 warn!(
@@ -5,10 +5,11 @@ use std::sync::{Arc, RwLock};
 
 use super::{BackendAdapter, BackendResponse};
 use crate::ccproxy::claude::{
-    ClaudeNativeContentBlock, ClaudeNativeMessage, ClaudeNativeRequest, ClaudeNativeResponse,
-    ClaudeNativeTool, ClaudeStreamEvent, ClaudeToolChoice,
+    ClaudeDocumentSource, ClaudeNativeContentBlock, ClaudeNativeMessage, ClaudeNativeRequest,
+    ClaudeNativeResponse, ClaudeNativeTool, ClaudeStreamEvent, ClaudeToolChoice,
 };
 use crate::ccproxy::get_tool_id;
+use crate::ccproxy::helper::pdf_text::PLAIN_TEXT_MEDIA_TYPE;
 use crate::ccproxy::types::{TOOL_PARSE_ERROR_REMINDER, TOOL_TAG_END, TOOL_TAG_START};
 use crate::ccproxy::{
     adapter::{
@@ -212,6 +213,9 @@ impl BackendAdapter for ClaudeBackendAdapter {
         headers: &mut reqwest::header::HeaderMap,
     ) -> Result<RequestBuilder, anyhow::Error> {
         crate::ccproxy::adapter::backend::common::preprocess_unified_request(unified_request);
+        // No developer role in this protocol
+        unified_request.fold_developer_prompt();
+        crate::ccproxy::adapter::backend::common::reject_audio_blocks(unified_request, "Claude")?;
 
         // Validate tool call sequence before processing
         self.validate_tool_call_sequence(&unified_request.messages)?;
@@ -356,6 +360,30 @@ impl BackendAdapter for ClaudeBackendAdapter {
                                 },
                             });
                         }
+                        UnifiedContentBlock::Audio { .. } => {
+                            // Rejected by `reject_audio_blocks`
+                        }
+                        UnifiedContentBlock::Document {
+                            media_type,
+                            data,
+                            title,
+                        } => {
+                            content_blocks.push(ClaudeNativeContentBlock::Document {
+                                source: ClaudeDocumentSource {
+                                    source_type: if media_type == PLAIN_TEXT_MEDIA_TYPE {
+                                        "text".to_string()
+                                    } else {
+                                        "base64".to_string()
+                                    },
+                                    media_type: Some(media_type.clone()),
+                                    data: Some(data.clone()),
+                                },
+                                title: title.clone(),
+                                context: None,
+                                citations: None,
+                                cache_control: None,
+                            });
+                        }
                         UnifiedContentBlock::ToolUse { id, name, input } => {
                             content_blocks.push(ClaudeNativeContentBlock::ToolUse {
                                 id: id.clone(),
@@ -371,7 +399,7 @@ impl BackendAdapter for ClaudeBackendAdapter {
                         } => {
                             content_blocks.push(ClaudeNativeContentBlock::ToolResult {
                                 tool_use_id: tool_use_id.clone(),
-                                content: content.clone(),
+                                content: content.clone().into(),
                                 is_error: Some(*is_error),
                                 cache_control: None,
                             });
@@ -383,10 +411,14 @@ impl BackendAdapter for ClaudeBackendAdapter {
                         }
                     }
                 }
-                claude_messages.push(ClaudeNativeMessage {
-                    role: role.to_string(),
-                    content: content_blocks,
-                });
+                // Tool results and a user message after them are one user turn in Claude
+                match claude_messages.last_mut() {
+                    Some(last) if last.role == role => last.content.extend(content_blocks),
+                    _ => claude_messages.push(ClaudeNativeMessage {
+                        role: role.to_string(),
+                        content: content_blocks,
+                    }),
+                }
             }
         }
 
@@ -520,6 +552,11 @@ impl BackendAdapter for ClaudeBackendAdapter {
 
         let mut request_json = serde_json::to_value(&claude_request)?;
 
+        crate::ccproxy::adapter::backend::common::merge_extra_body(
+            &mut request_json,
+            &unified_request.extra_body,
+        );
+
         // Merge custom params from model config
         crate::ai::util::merge_custom_params_value(
             &mut request_json,
@@ -567,7 +604,9 @@ impl BackendAdapter for ClaudeBackendAdapter {
 
                         match crate::ccproxy::helper::tool_use_xml::ToolUse::try_from(tool_xml) {
                             Ok(parsed_tool) => {
-                                content_blocks.push(parsed_tool.into());
+                                content_blocks.push(
+                                    parsed_tool.into_content_block(&backend_response.code_reflow),
+                                );
                             }
                             _ => {
                                 log::warn!("parse tool xml failed, xml: {}", tool_xml);
@@ -638,11 +677,20 @@ impl BackendAdapter for ClaudeBackendAdapter {
                         content,
                         is_error,
                         cache_control: _,
-                    } => content_blocks.push(UnifiedContentBlock::ToolResult {
-                        tool_use_id,
-                        content,
-                        is_error: is_error.unwrap_or(false),
-                    }),
+                    } => {
+                        let (content, images) = content.into_text_and_images();
+                        content_blocks.push(UnifiedContentBlock::ToolResult {
+                            tool_use_id,
+                            content,
+                            is_error: is_error.unwrap_or(false),
+                        });
+                        content_blocks.extend(images.into_iter().map(|source| {
+                            UnifiedContentBlock::Image {
+                                media_type: source.media_type,
+                                data: source.data,
+                            }
+                        }));
+                    }
                     ClaudeNativeContentBlock::Image { source } => {
                         content_blocks.push(UnifiedContentBlock::Image {
                             media_type: source.media_type,
@@ -652,6 +700,8 @@ impl BackendAdapter for ClaudeBackendAdapter {
                     ClaudeNativeContentBlock::Thinking { thinking } => {
                         content_blocks.push(UnifiedContentBlock::Thinking { thinking })
                     }
+                    // Documents are only sent by the client, never returned
+                    ClaudeNativeContentBlock::Document { .. } => {}
                 }
             }
         }
@@ -672,6 +722,7 @@ impl BackendAdapter for ClaudeBackendAdapter {
             model: claude_response.model.unwrap_or_default(),
             content: content_blocks,
             stop_reason: claude_response.stop_reason,
+            stop_sequence: claude_response.stop_sequence,
             usage,
         })
     }
@@ -737,6 +788,7 @@ impl BackendAdapter for ClaudeBackendAdapter {
                                     if let Some(delta) = claude_event.delta {
                                         if let Some(stop_reason) = delta.stop_reason {
                                             if let Ok(mut status) = sse_status.write() {
+                                                status.stop_sequence = delta.stop_sequence;
                                                 // On stream finish, try to auto-complete any dangling tool tag.
                                                 crate::ccproxy::adapter::backend::common::auto_complete_and_process_tool_tag(&mut status, &mut unified_chunks);
 
@@ -996,6 +1048,9 @@ impl BackendAdapter for ClaudeBackendAdapter {
                             "message_delta" => {
                                 if let Some(delta) = claude_event.delta {
                                     if let Some(stop_reason) = delta.stop_reason {
+                                        if let Ok(mut status) = sse_status.write() {
+                                            status.stop_sequence = delta.stop_sequence;
+                                        }
                                         let usage = claude_event
                                             .usage
                                             .map(|u| UnifiedUsage {
@@ -1058,3 +1113,153 @@ impl BackendAdapter for ClaudeBackendAdapter {
         ))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::ClaudeBackendAdapter;
+    use crate::ccproxy::adapter::backend::traits::BackendAdapter;
+    use crate::ccproxy::adapter::input::from_claude;
+    use crate::ccproxy::adapter::unified::{
+        UnifiedContentBlock, UnifiedMessage, UnifiedRequest, UnifiedRole,
+    };
+    use reqwest::Client;
+    use serde_json::{json, Value};
+
+    #[tokio::test]
+    async fn test_pdf_document_block_is_forwarded() {
+        let claude_request = serde_json::from_value(json!({
+            "model": "claude-sonnet-4",
+            "max_tokens": 1024,
+            "messages": [{
+                "role": "user",
+                "content": [
+                    {
+                        "type": "document",
+                        "title": "report.pdf",
+                        "source": {
+                            "type": "base64",
+                            "media_type": "application/pdf",
+                            "data": "JVBERi0xLjQK"
+                        }
+                    },
+                    { "type": "text", "text": "Summarize the report" }
+                ]
+            }]
+        }))
+        .expect("Claude request should parse");
+        let mut unified_request =
+            from_claude(claude_request, false, None).expect("Claude request should convert");
+
+        let request = ClaudeBackendAdapter
+            .adapt_request(
+                &Client::new(),
+                &mut unified_request,
+                "test-api-key",
+                "https://api.anthropic.com/v1/messages",
+                "claude-sonnet-4",
+                false,
+                &mut reqwest::header::HeaderMap::new(),
+            )
+            .await
+            .expect("Claude request should adapt")
+            .build()
+            .expect("request should build");
+        let payload: Value = serde_json::from_slice(
+            request
+                .body()
+                .and_then(|body| body.as_bytes())
+                .expect("request body should be available as bytes"),
+        )
+        .expect("request body should be valid json");
+
+        let document = &payload["messages"][0]["content"][0];
+        assert_eq!(document["type"], "document");
+        assert_eq!(document["title"], "report.pdf");
+        assert_eq!(document["source"]["type"], "base64");
+        assert_eq!(document["source"]["media_type"], "application/pdf");
+        assert_eq!(document["source"]["data"], "JVBERi0xLjQK");
+        assert_eq!(
+            payload["messages"][0]["content"][1]["text"],
+            "Summarize the report"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_audio_input_is_rejected() {
+        let mut unified_request = UnifiedRequest {
+            model: "claude-sonnet-4".to_string(),
+            messages: vec![UnifiedMessage {
+                role: UnifiedRole::User,
+                content: vec![UnifiedContentBlock::Audio {
+                    media_type: Some("audio/wav".to_string()),
+                    data: "UklGRg==".to_string(),
+                    transcript: None,
+                }],
+                reasoning_content: None,
+            }],
+            ..Default::default()
+        };
+
+        let error = ClaudeBackendAdapter
+            .adapt_request(
+                &Client::new(),
+                &mut unified_request,
+                "test-api-key",
+                "https://api.anthropic.com/v1/messages",
+                "claude-sonnet-4",
+                false,
+                &mut reqwest::header::HeaderMap::new(),
+            )
+            .await
+            .expect_err("audio input must be rejected");
+        assert!(error.to_string().contains("does not support audio input"));
+    }
+
+    #[tokio::test]
+    async fn test_developer_prompt_is_folded_into_system() {
+        let mut unified_request = UnifiedRequest {
+            model: "claude-sonnet-4".to_string(),
+            system_prompt: Some("You are a coding assistant.".to_string()),
+            developer_prompt: Some("Prefer Rust.".to_string()),
+            messages: vec![UnifiedMessage {
+                role: UnifiedRole::User,
+                content: vec![UnifiedContentBlock::Text {
+                    text: "Hello".to_string(),
+                }],
+                reasoning_content: None,
+            }],
+            ..Default::default()
+        };
+
+        let request = ClaudeBackendAdapter
+            .adapt_request(
+                &Client::new(),
+                &mut unified_request,
+                "test-api-key",
+                "https://api.anthropic.com/v1/messages",
+                "claude-sonnet-4",
+                false,
+                &mut reqwest::header::HeaderMap::new(),
+            )
+            .await
+            .expect("Claude request should adapt")
+            .build()
+            .expect("request should build");
+        let payload: Value = serde_json::from_slice(
+            request
+                .body()
+                .and_then(|body| body.as_bytes())
+                .expect("request body should be available as bytes"),
+        )
+        .expect("request body should be valid json");
+
+        let system = payload["system"].to_string();
+        assert!(system.contains("You are a coding assistant."));
+        assert!(system.contains("Prefer Rust."));
+        assert!(payload["messages"]
+            .as_array()
+            .expect("messages")
+            .iter()
+            .all(|message| message["role"] != "developer"));
+    }
+}
@@ -4,11 +4,15 @@ use std::sync::RwLockWriteGuard;
 use crate::ccproxy::{
     adapter::unified::{SseStatus, UnifiedStreamChunk},
     get_tool_id,
-    helper::tool_use_xml::ToolUse,
+    helper::tool_use_xml::{
+        generate_compact_tools_xml, generate_tools_xml, CompatToolPromptConfig,
+        CompatToolPromptMode, ToolUse,
+    },
     types::{
         TODO_TAG_END, TODO_TAG_START, TOOL_COMPAT_MODE_PROMPT, TOOL_PARSE_ERROR_REMINDER,
         TOOL_TAG_END, TOOL_TAG_START,
     },
+    utils::token_estimator::estimate_tokens,
 };
 
 pub fn update_message_block(status: &mut RwLockWriteGuard<'_, SseStatus>, block: String) {
@@ -18,12 +22,109 @@ pub fn update_message_block(status: &mut RwLockWriteGuard<'_, SseStatus>, block:
     status.current_content_block = block;
 }
 
-pub fn generate_tool_prompt(tools: &Vec<crate::ccproxy::adapter::unified::UnifiedTool>) -> String {
-    let tools_xml = crate::ccproxy::helper::tool_use_xml::generate_tools_xml(tools);
+/// Builds the compat mode system prompt that defines the tools.
+///
+/// With many tools the full schemas crowd out the context of small models, so they can be
+/// listed compactly instead. Tools in `called_tools` were already used in the conversation
+/// and keep their full definition in compact mode.
+pub fn generate_tool_prompt(
+    tools: &Vec<crate::ccproxy::adapter::unified::UnifiedTool>,
+    config: &CompatToolPromptConfig,
+    called_tools: &std::collections::HashSet<String>,
+) -> String {
+    let tools_xml = match config.mode {
+        CompatToolPromptMode::Full => generate_tools_xml(tools),
+        CompatToolPromptMode::Compact => generate_compact_tools_xml(tools, called_tools),
+        CompatToolPromptMode::Auto => {
+            let tools_xml = generate_tools_xml(tools);
+            let tokens = estimate_tokens(&tools_xml);
+            if tokens <= config.token_budget as f64 {
+                tools_xml
+            } else {
+                log::debug!(
+                    "Full tool definitions take about {:.0} tokens, over the budget of {}, using compact ones",
+                    tokens,
+                    config.token_budget
+                );
+                generate_compact_tools_xml(tools, called_tools)
+            }
+        }
+    };
 
     TOOL_COMPAT_MODE_PROMPT.replace("{TOOLS_LIST}", &tools_xml)
 }
 
+/// Replaces `document` blocks with their text for backends without native file input.
+pub fn inline_documents_as_text(
+    unified_request: &mut crate::ccproxy::adapter::unified::UnifiedRequest,
+) {
+    use crate::ccproxy::adapter::unified::UnifiedContentBlock;
+
+    for message in &mut unified_request.messages {
+        for block in &mut message.content {
+            if let UnifiedContentBlock::Document {
+                media_type,
+                data,
+                title,
+            } = block
+            {
+                *block = UnifiedContentBlock::Text {
+                    text: crate::ccproxy::helper::pdf_text::document_to_text(
+                        media_type,
+                        data,
+                        title.as_deref(),
+                    ),
+                };
+            }
+        }
+    }
+}
+
+/// Adds the extra client body fields allowed by the proxy group to the backend body.
+///
+/// The fields are copied verbatim and replace a field of the same name.
+pub fn merge_extra_body(
+    request_json: &mut serde_json::Value,
+    extra_body: &serde_json::Map<String, serde_json::Value>,
+) {
+    if let Some(body) = request_json.as_object_mut() {
+        for (key, value) in extra_body {
+            body.insert(key.clone(), value.clone());
+        }
+    }
+}
+
+/// Fails the request if it carries audio, for backends that cannot take audio input.
+pub fn reject_audio_blocks(
+    unified_request: &crate::ccproxy::adapter::unified::UnifiedRequest,
+    backend: &str,
+) -> Result<(), anyhow::Error> {
+    let has_audio = unified_request.messages.iter().any(|message| {
+        message.content.iter().any(|block| {
+            matches!(
+                block,
+                crate::ccproxy::adapter::unified::UnifiedContentBlock::Audio { .. }
+            )
+        })
+    });
+    if has_audio {
+        anyhow::bail!(
+            "The {} backend does not support audio input, use an OpenAI or Gemini model with audio support",
+            backend
+        );
+    }
+    Ok(())
+}
+
+/// The short audio format name (`wav`, `mp3`, ...) of an audio media type.
+pub fn audio_format_from_media_type(media_type: &str) -> &str {
+    match media_type.strip_prefix("audio/").unwrap_or(media_type) {
+        "mpeg" => "mp3",
+        "x-wav" | "wave" => "wav",
+        format => format,
+    }
+}
+
 pub fn preprocess_unified_request(
     unified_request: &mut crate::ccproxy::adapter::unified::UnifiedRequest,
 ) {
@@ -31,25 +132,7 @@ pub fn preprocess_unified_request(
         "preprocess_unified_request: Starting with {} messages",
         unified_request.messages.len()
     );
-    // 1. Merge consecutive messages with the same role.
-    if !unified_request.messages.is_empty() {
-        let mut merged_messages: Vec<crate::ccproxy::adapter::unified::UnifiedMessage> = Vec::new();
-        let mut message_iter = unified_request.messages.iter().cloned();
-        if let Some(mut last_message) = message_iter.next() {
-            for message in message_iter {
-                if message.role == last_message.role {
-                    last_message.content.extend(message.content);
-                } else {
-                    merged_messages.push(last_message);
-                    last_message = message;
-                }
-            }
-            merged_messages.push(last_message);
-        }
-        unified_request.messages = merged_messages;
-    }
-
-    // 2. Inject dummy responses for any unmatched tool calls and ensure pairing.
+    // Inject dummy responses for any unmatched tool calls and ensure pairing.
     let mut corrected_messages: Vec<crate::ccproxy::adapter::unified::UnifiedMessage> = Vec::new();
     let mut pending_tool_calls: Vec<String> = Vec::new(); // Stores tool_use_ids from assistant's ToolUse
 
@@ -132,7 +215,178 @@ pub fn preprocess_unified_request(
     );
 }
 
+/// Puts the tool results answering an assistant turn in the order of its tool calls.
+///
+/// Results of parallel tool calls may arrive in any order, but providers such as OpenAI expect
+/// the `tool` messages to follow the order of `tool_calls`. Only the result blocks swap places,
+/// other blocks of the answering messages keep their position and results for unknown call ids
+/// go last in their original order.
+pub fn order_tool_results_by_calls(
+    unified_request: &mut crate::ccproxy::adapter::unified::UnifiedRequest,
+) {
+    use crate::ccproxy::adapter::unified::{UnifiedContentBlock, UnifiedRole};
+
+    let messages = &mut unified_request.messages;
+    let mut index = 0;
+    while index < messages.len() {
+        let call_ids: Vec<String> = if messages[index].role == UnifiedRole::Assistant {
+            messages[index]
+                .content
+                .iter()
+                .filter_map(|block| match block {
+                    UnifiedContentBlock::ToolUse { id, .. } => Some(id.clone()),
+                    _ => None,
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+        index += 1;
+        if call_ids.len() < 2 {
+            continue;
+        }
+
+        // Result slots of the tool response messages directly following the assistant turn
+        let mut slots: Vec<(usize, usize)> = Vec::new();
+        while index < messages.len()
+            && messages[index].role != UnifiedRole::Assistant
+            && messages[index]
+                .content
+                .iter()
+                .any(|block| matches!(block, UnifiedContentBlock::ToolResult { .. }))
+        {
+            for (block_index, block) in messages[index].content.iter().enumerate() {
+                if matches!(block, UnifiedContentBlock::ToolResult { .. }) {
+                    slots.push((index, block_index));
+                }
+            }
+            index += 1;
+        }
+
+        let mut results: Vec<UnifiedContentBlock> = slots
+            .iter()
+            .map(|&(message_index, block_index)| {
+                messages[message_index].content[block_index].clone()
+            })
+            .collect();
+        let call_position = |block: &UnifiedContentBlock| match block {
+            UnifiedContentBlock::ToolResult { tool_use_id, .. } => call_ids
+                .iter()
+                .position(|id| id == tool_use_id)
+                .unwrap_or(call_ids.len()),
+            _ => call_ids.len(),
+        };
+        if results
+            .windows(2)
+            .all(|pair| call_position(&pair[0]) <= call_position(&pair[1]))
+        {
+            continue;
+        }
+
+        results.sort_by_key(|block| call_position(block));
+        log::debug!(
+            "order_tool_results_by_calls: reordered {} tool results to follow calls {:?}",
+            results.len(),
+            call_ids
+        );
+        for (&(message_index, block_index), result) in slots.iter().zip(results) {
+            messages[message_index].content[block_index] = result;
+        }
+    }
+}
+
+/// Gives unanswered tool calls a result telling the model not to repeat them.
+///
+/// The generic cancellation placeholder leaves the model free to retry, which some models do
+/// over and over with the same arguments.
+pub fn explain_unanswered_tool_calls(
+    unified_request: &mut crate::ccproxy::adapter::unified::UnifiedRequest,
+) {
+    use crate::ccproxy::adapter::unified::UnifiedContentBlock;
+    use crate::ccproxy::types::{TOOL_CALL_EMPTY_REMAIN, TOOL_CALL_NO_RESULT_REMAIN};
+
+    let mut replaced = 0;
+    for block in unified_request
+        .messages
+        .iter_mut()
+        .flat_map(|message| message.content.iter_mut())
+    {
+        if let UnifiedContentBlock::ToolResult { content, .. } = block {
+            if content == TOOL_CALL_EMPTY_REMAIN {
+                *content = TOOL_CALL_NO_RESULT_REMAIN.to_string();
+                replaced += 1;
+            }
+        }
+    }
+    if replaced > 0 {
+        log::debug!(
+            "explain_unanswered_tool_calls: replaced the placeholder of {} tool results",
+            replaced
+        );
+    }
+}
+
+/// The tool the conversation ends by calling over and over with the same arguments, and how
+/// many times in a row, once that reaches `REPEATED_TOOL_CALL_THRESHOLD`.
+///
+/// Only the latest calls count: a call with another name or other arguments starts a new run.
+pub fn repeated_tool_call(
+    messages: &[crate::ccproxy::adapter::unified::UnifiedMessage],
+) -> Option<(String, usize)> {
+    use crate::ccproxy::adapter::unified::{UnifiedContentBlock, UnifiedRole};
+    use crate::ccproxy::types::REPEATED_TOOL_CALL_THRESHOLD;
+
+    let mut last: Option<(&str, &serde_json::Value)> = None;
+    let mut count = 0;
+    for block in messages
+        .iter()
+        .filter(|message| message.role == UnifiedRole::Assistant)
+        .flat_map(|message| &message.content)
+    {
+        if let UnifiedContentBlock::ToolUse { name, input, .. } = block {
+            if last == Some((name.as_str(), input)) {
+                count += 1;
+            } else {
+                last = Some((name.as_str(), input));
+                count = 1;
+            }
+        }
+    }
+    let (name, _) = last?;
+    (count >= REPEATED_TOOL_CALL_THRESHOLD).then(|| (name.to_string(), count))
+}
+
+/// End of the tool call block at the start of `buffer`, just after its closing tag.
+///
+/// Tool tags inside a call, e.g. in a file documenting the tool format, nest, so the block
+/// ends at the closing tag that balances its opening one.
+fn tool_block_end(buffer: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut position = 0;
+    loop {
+        let rest = &buffer[position..];
+        let next_end = rest.find(TOOL_TAG_END)?;
+        match rest.find(TOOL_TAG_START).filter(|start| *start < next_end) {
+            Some(start) => {
+                depth += 1;
+                position += start + TOOL_TAG_START.len();
+            }
+            None => {
+                depth = depth.saturating_sub(1);
+                position += next_end + TOOL_TAG_END.len();
+                if depth == 0 {
+                    return Some(position);
+                }
+            }
+        }
+    }
+}
+
 /// Process tool calls found in the buffer
+///
+/// Text is only forwarded once it is known not to belong to a tool call, so a tag split
+/// across chunks is completed by the following ones. A call still open after
+/// `tool_compat_max_buffer_bytes` is given up and forwarded as text.
 pub fn process_tool_calls_in_buffer(
     status: &mut std::sync::RwLockWriteGuard<SseStatus>,
     unified_chunks: &mut Vec<UnifiedStreamChunk>,
@@ -140,14 +394,22 @@ pub fn process_tool_calls_in_buffer(
     loop {
         if status.in_tool_call_block {
             // We are inside a tool call, looking for the end tag
-            if let Some(end_pos) = status.tool_compat_buffer.find(TOOL_TAG_END) {
-                let end_of_block = end_pos + TOOL_TAG_END.len();
+            if let Some(end_of_block) = tool_block_end(&status.tool_compat_buffer) {
                 let tool_xml = &status.tool_compat_buffer[..end_of_block].to_string();
 
                 parse_and_emit_tool_call(status, tool_xml, unified_chunks);
 
                 status.tool_compat_buffer = status.tool_compat_buffer[end_of_block..].to_string();
                 status.in_tool_call_block = false; // STATE CHANGE
+            } else if status.tool_compat_buffer.len() > status.tool_compat_max_buffer_bytes {
+                log::warn!(
+                    "Tool call still open after {} bytes, forwarding it as text",
+                    status.tool_compat_buffer.len()
+                );
+                let unfinished = std::mem::take(&mut status.tool_compat_buffer);
+                unified_chunks.push(UnifiedStreamChunk::Text { delta: unfinished });
+                status.in_tool_call_block = false; // STATE CHANGE
+                break;
             } else {
                 // Incomplete block, wait for more data
                 break;
@@ -291,10 +553,7 @@ fn parse_and_emit_tool_call(
         status.tool_id = tool_id.clone();
         update_message_block(status, tool_id.clone());
 
-        let mut arguments = serde_json::Map::new();
-        for param in parsed_tool.args {
-            arguments.insert(param.name.clone(), param.get_value());
-        }
+        let arguments = parsed_tool.input(&status.code_reflow);
 
         // Send tool call start for claude only
         unified_chunks.push(UnifiedStreamChunk::ContentBlockStart {
@@ -367,6 +626,87 @@ mod tests {
         }
     }
 
+    /// Feeds `chunks` one after another, then finishes the stream like the backends do.
+    fn run_chunks<'a>(chunks: impl IntoIterator<Item = &'a str>) -> Vec<UnifiedStreamChunk> {
+        let status_arc = Arc::new(RwLock::new(SseStatus::default()));
+        let mut status_lock = status_arc.write().unwrap();
+        let mut chunks_out = Vec::new();
+        for chunk in chunks {
+            status_lock.tool_compat_buffer.push_str(chunk);
+            process_tool_calls_in_buffer(&mut status_lock, &mut chunks_out);
+        }
+        auto_complete_and_process_tool_tag(&mut status_lock, &mut chunks_out);
+        if !status_lock.tool_compat_buffer.is_empty() {
+            let remaining = std::mem::take(&mut status_lock.tool_compat_buffer);
+            chunks_out.push(UnifiedStreamChunk::Text { delta: remaining });
+        }
+        chunks_out
+    }
+
+    /// The forwarded text and the names and arguments of the emitted tool calls.
+    fn summarize(chunks: &[UnifiedStreamChunk]) -> (String, Vec<(String, String)>) {
+        let mut text = String::new();
+        let mut tools: Vec<(String, String)> = Vec::new();
+        for chunk in chunks {
+            match chunk {
+                UnifiedStreamChunk::Text { delta } => text.push_str(delta),
+                UnifiedStreamChunk::ToolUseStart { name, .. } => {
+                    tools.push((name.clone(), String::new()))
+                }
+                UnifiedStreamChunk::ToolUseDelta { delta, .. } => {
+                    if let Some((_, args)) = tools.last_mut() {
+                        args.push_str(delta);
+                    }
+                }
+                _ => {}
+            }
+        }
+        (text, tools)
+    }
+
+    #[test]
+    fn test_tool_calls_split_at_any_byte_are_reassembled() {
+        let input = "Reading both.<cs:tool_use><name>read_file</name><args><arg name=\"path\">a.rs</arg></args></cs:tool_use><cs:tool_use><name>write_file</name><args><arg name=\"path\">docs.md</arg><arg name=\"content\">Call <cs:tool_use><name>x</name></cs:tool_use> here</arg></args></cs:tool_use>Done.";
+        let expected = summarize(&run_chunks([input]));
+        assert_eq!(expected.0, "Reading both.Done.");
+        assert_eq!(
+            expected
+                .1
+                .iter()
+                .map(|(name, _)| name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["read_file", "write_file"]
+        );
+
+        for split in 1..input.len() {
+            let (head, tail) = input.split_at(split);
+            assert_eq!(summarize(&run_chunks([head, tail])), expected, "{}", split);
+        }
+        let bytes = (0..input.len()).map(|i| &input[i..i + 1]);
+        assert_eq!(summarize(&run_chunks(bytes)), expected);
+    }
+
+    #[test]
+    fn test_unclosed_tool_call_is_given_up_past_the_limit() {
+        let status_arc = Arc::new(RwLock::new(SseStatus::default()));
+        let mut status = status_arc.write().unwrap();
+        status.tool_compat_max_buffer_bytes = 64;
+        let mut chunks = Vec::new();
+
+        status.tool_compat_buffer = "<cs:tool_use><name>read_file</name>".to_string();
+        process_tool_calls_in_buffer(&mut status, &mut chunks);
+        assert!(chunks.is_empty() && status.in_tool_call_block);
+
+        status.tool_compat_buffer.push_str(&"x".repeat(64));
+        process_tool_calls_in_buffer(&mut status, &mut chunks);
+        assert!(!status.in_tool_call_block);
+        assert!(status.tool_compat_buffer.is_empty());
+        assert!(matches!(
+            chunks.as_slice(),
+            [UnifiedStreamChunk::Text { delta }] if delta.starts_with("<cs:tool_use><name>read_file")
+        ));
+    }
+
     #[test]
     fn test_strips_todo_block() {
         let input = "start <cs:todo>\"thought\"</cs:todo> end";
@@ -440,4 +780,84 @@ mod tests {
 
         assert_eq!(result.final_buffer, "text3");
     }
+
+    fn twenty_tools() -> Vec<crate::ccproxy::adapter::unified::UnifiedTool> {
+        (0..20)
+            .map(|i| crate::ccproxy::adapter::unified::UnifiedTool {
+                name: format!("tool_{i}"),
+                description: Some(format!(
+                    "Runs operation {i} on the workspace.\nThe result is returned as text and \
+                     the operation can be repeated as often as needed."
+                )),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "path": { "type": "string", "description": "Absolute path of the target file" },
+                        "limit": { "type": "number", "description": "Maximum number of lines to read" },
+                        "offset": { "type": "number", "description": "Line number to start reading from" },
+                        "pattern": { "type": "string", "description": "Regular expression to filter lines" },
+                        "recursive": { "type": "boolean", "description": "Whether to descend into directories" }
+                    },
+                    "required": ["path"]
+                }),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn compact_tool_prompt_is_much_shorter_for_many_tools() {
+        let tools = twenty_tools();
+        let called = std::collections::HashSet::new();
+        let full_config = CompatToolPromptConfig {
+            mode: CompatToolPromptMode::Full,
+            ..Default::default()
+        };
+        let compact_config = CompatToolPromptConfig {
+            mode: CompatToolPromptMode::Compact,
+            ..Default::default()
+        };
+
+        let full = generate_tool_prompt(&tools, &full_config, &called);
+        let compact = generate_tool_prompt(&tools, &compact_config, &called);
+        let guide = TOOL_COMPAT_MODE_PROMPT.len() - "{TOOLS_LIST}".len();
+        let (full_tools, compact_tools) = (full.len() - guide, compact.len() - guide);
+        assert!(
+            compact_tools * 3 < full_tools,
+            "compact tool list of {compact_tools} bytes, full of {full_tools}"
+        );
+        assert!(compact.contains("<name>tool_19</name>"));
+        assert!(compact.contains("<required>path:string</required>"));
+        assert!(!compact.contains("offset"));
+        assert!(!compact.contains("repeated as often"));
+
+        // A tool the model already called is described in full
+        let called = std::collections::HashSet::from(["tool_3".to_string()]);
+        let compact = generate_tool_prompt(&tools, &compact_config, &called);
+        assert_eq!(compact.matches("name=\"offset\"").count(), 1);
+    }
+
+    #[test]
+    fn auto_tool_prompt_switches_on_token_budget() {
+        let tools = twenty_tools();
+        let called = std::collections::HashSet::new();
+        let full_config = CompatToolPromptConfig {
+            mode: CompatToolPromptMode::Full,
+            ..Default::default()
+        };
+        let full = generate_tool_prompt(&tools, &full_config, &called);
+
+        let roomy = CompatToolPromptConfig {
+            mode: CompatToolPromptMode::Auto,
+            token_budget: 1_000_000,
+        };
+        assert_eq!(generate_tool_prompt(&tools, &roomy, &called), full);
+
+        let tight = CompatToolPromptConfig {
+            mode: CompatToolPromptMode::Auto,
+            token_budget: 1000,
+        };
+        let prompt = generate_tool_prompt(&tools, &tight, &called);
+        assert!(prompt.len() < full.len());
+        assert!(prompt.contains("<required>"));
+    }
 }
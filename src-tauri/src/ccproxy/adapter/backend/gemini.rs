@@ -1,5 +1,6 @@
 use crate::ccproxy::get_tool_id;
 use crate::ccproxy::helper::get_msg_id;
+use crate::ccproxy::helper::pdf_text::{document_to_text, PLAIN_TEXT_MEDIA_TYPE};
 use crate::ccproxy::types::{
     TOOL_PARSE_ERROR_REMINDER, TOOL_RESULT_SUFFIX_REMINDER, TOOL_TAG_END, TOOL_TAG_START,
 };
@@ -11,6 +12,7 @@ use std::sync::{Arc, RwLock};
 
 use super::{BackendAdapter, BackendResponse};
 use crate::ccproxy::adapter::{
+    input::helper::thinking_adapter::reasoning_as_text,
     range_adapter::adapt_temperature,
     unified::{
         SseStatus, UnifiedContentBlock, UnifiedEmbeddingData, UnifiedEmbeddingInput,
@@ -24,9 +26,12 @@ use crate::ccproxy::gemini::{
     GeminiResponse as GeminiNetworkResponse, GeminiTool as GeminiApiTool, GeminiToolConfig,
 };
 use crate::ccproxy::utils::token_estimator::estimate_tokens;
+use crate::constants::CFG_CCPROXY_REASONING_TEXT_PREFIX_DEFAULT;
 
 pub struct GeminiBackendAdapter;
 const GEMINI_DUMMY_THOUGHT_SIGNATURE: &str = "skip_thought_signature_validator";
+/// Text of the user turn put before a history that does not start with one
+const GEMINI_LEADING_USER_TURN: &str = "Continue.";
 
 impl GeminiBackendAdapter {
     /// Extract only Gemini-supported JSON Schema fields
@@ -124,12 +129,34 @@ impl GeminiBackendAdapter {
         }
     }
 
+    /// Earlier reasoning of the assistant becomes text behind `reasoning_prefix`, as Gemini
+    /// takes no thoughts in the history.
     fn build_native_message_parts(
         msg: &crate::ccproxy::adapter::unified::UnifiedMessage,
+        reasoning_prefix: &str,
     ) -> Vec<GeminiPart> {
         let mut parts: Vec<GeminiPart> = Vec::new();
         let mut attach_dummy_thought_signature = msg.role == UnifiedRole::Assistant;
 
+        if msg.role == UnifiedRole::Assistant {
+            let reasoning = msg
+                .reasoning_content
+                .iter()
+                .map(String::as_str)
+                .chain(msg.content.iter().filter_map(|block| match block {
+                    UnifiedContentBlock::Thinking { thinking } => Some(thinking.as_str()),
+                    _ => None,
+                }))
+                .collect::<Vec<_>>()
+                .join("\n");
+            if let Some(text) = reasoning_as_text(&reasoning, reasoning_prefix) {
+                parts.push(GeminiPart {
+                    text: Some(text),
+                    ..Default::default()
+                });
+            }
+        }
+
         for block in &msg.content {
             match block {
                 UnifiedContentBlock::Text { text } => {
@@ -147,6 +174,54 @@ impl GeminiBackendAdapter {
                         ..Default::default()
                     });
                 }
+                UnifiedContentBlock::Document {
+                    media_type,
+                    data,
+                    title,
+                } => {
+                    // Gemini reads PDFs natively, plain text documents are sent as text.
+                    if media_type == PLAIN_TEXT_MEDIA_TYPE {
+                        parts.push(GeminiPart {
+                            text: Some(document_to_text(media_type, data, title.as_deref())),
+                            ..Default::default()
+                        });
+                    } else {
+                        parts.push(GeminiPart {
+                            inline_data: Some(GeminiInlineData {
+                                mime_type: media_type.clone(),
+                                data: data.clone(),
+                            }),
+                            ..Default::default()
+                        });
+                    }
+                }
+                UnifiedContentBlock::Audio {
+                    media_type,
+                    data,
+                    transcript,
+                } => match (msg.role == UnifiedRole::Assistant, media_type) {
+                    // Earlier generated speech is replayed as its transcript.
+                    (true, _) => {
+                        if let Some(transcript) = transcript {
+                            parts.push(GeminiPart {
+                                text: Some(transcript.clone()),
+                                ..Default::default()
+                            });
+                        }
+                    }
+                    (false, Some(media_type)) => {
+                        parts.push(GeminiPart {
+                            inline_data: Some(GeminiInlineData {
+                                mime_type: media_type.clone(),
+                                data: data.clone(),
+                            }),
+                            ..Default::default()
+                        });
+                    }
+                    (false, None) => {
+                        log::warn!("Dropping audio input without a media type for Gemini");
+                    }
+                },
                 UnifiedContentBlock::ToolUse { id: _, name, input } => {
                     let thought_signature = if attach_dummy_thought_signature {
                         attach_dummy_thought_signature = false;
@@ -183,6 +258,31 @@ impl GeminiBackendAdapter {
         parts
     }
 
+    /// Gemini rejects contents that do not start with a user turn, as a trimmed history or a
+    /// conversation opened by the assistant does, so a minimal user turn is put first.
+    fn ensure_leading_user_turn(contents: &mut Vec<GeminiContent>) {
+        let Some(first_role) = contents.first().map(|first| first.role.clone()) else {
+            return;
+        };
+        if first_role.as_deref() == Some("user") {
+            return;
+        }
+        log::debug!(
+            "Gemini contents start with a {:?} turn, prepending a user turn",
+            first_role
+        );
+        contents.insert(
+            0,
+            GeminiContent {
+                role: Some("user".to_string()),
+                parts: vec![GeminiPart {
+                    text: Some(GEMINI_LEADING_USER_TURN.to_string()),
+                    ..Default::default()
+                }],
+            },
+        );
+    }
+
     /// Process tool compatibility mode chunk
     async fn process_tool_compat_chunk(
         &self,
@@ -337,6 +437,8 @@ impl BackendAdapter for GeminiBackendAdapter {
         headers: &mut reqwest::header::HeaderMap,
     ) -> Result<RequestBuilder, anyhow::Error> {
         crate::ccproxy::adapter::backend::common::preprocess_unified_request(unified_request);
+        // No developer role in this protocol
+        unified_request.fold_developer_prompt();
 
         // --- Tool Compatibility Mode Handling ---
         // If tool_compat_mode is enabled, we inject a system prompt with tool definitions
@@ -497,6 +599,10 @@ impl BackendAdapter for GeminiBackendAdapter {
             gemini_contents = processed_messages;
         } else {
             // Standard processing for non-tool-compatibility mode.
+            let reasoning_prefix = unified_request
+                .reasoning_text_prefix
+                .as_deref()
+                .unwrap_or(CFG_CCPROXY_REASONING_TEXT_PREFIX_DEFAULT);
             for msg in &unified_request.messages {
                 let gemini_role = match msg.role {
                     UnifiedRole::User => "user",
@@ -505,17 +611,25 @@ impl BackendAdapter for GeminiBackendAdapter {
                     UnifiedRole::System => continue, // System prompt is handled at the top level
                 };
 
-                let parts = Self::build_native_message_parts(msg);
+                let parts = Self::build_native_message_parts(msg, reasoning_prefix);
 
                 if !parts.is_empty() {
-                    gemini_contents.push(GeminiContent {
-                        role: Some(gemini_role.to_string()),
-                        parts,
-                    });
+                    // Tool responses and a user message after them are one user turn in Gemini
+                    match gemini_contents.last_mut() {
+                        Some(last) if last.role.as_deref() == Some(gemini_role) => {
+                            last.parts.extend(parts)
+                        }
+                        _ => gemini_contents.push(GeminiContent {
+                            role: Some(gemini_role.to_string()),
+                            parts,
+                        }),
+                    }
                 }
             }
         }
 
+        Self::ensure_leading_user_turn(&mut gemini_contents);
+
         // --- Prompt Injection Logic ---
         let injection_pos = unified_request
             .prompt_injection_position
@@ -658,6 +772,12 @@ impl BackendAdapter for GeminiBackendAdapter {
                 stop_sequences: unified_request.stop_sequences.clone(),
                 response_mime_type: unified_request.response_mime_type.clone(),
                 response_schema: unified_request.response_schema.clone(),
+                response_modalities: unified_request.modalities.as_ref().map(|modalities| {
+                    modalities
+                        .iter()
+                        .map(|modality| modality.to_uppercase())
+                        .collect()
+                }),
                 thinking_config: unified_request.thinking.as_ref().map(|t| {
                     crate::ccproxy::types::gemini::GeminiThinkingConfig {
                         thinking_budget: t.budget_tokens,
@@ -679,6 +799,11 @@ impl BackendAdapter for GeminiBackendAdapter {
 
         let mut request_json = serde_json::to_value(&gemini_request)?;
 
+        crate::ccproxy::adapter::backend::common::merge_extra_body(
+            &mut request_json,
+            &unified_request.extra_body,
+        );
+
         // Merge custom params from model config
         crate::ai::util::merge_custom_params_value(
             &mut request_json,
@@ -750,7 +875,10 @@ impl BackendAdapter for GeminiBackendAdapter {
                                             &parsed_tool.name,
                                             &parsed_tool.args
                                         );
-                                        content_blocks.push(parsed_tool.into());
+                                        content_blocks.push(
+                                            parsed_tool
+                                                .into_content_block(&backend_response.code_reflow),
+                                        );
                                     }
                                     Err(e) => {
                                         let tool_xml =
@@ -813,6 +941,15 @@ impl BackendAdapter for GeminiBackendAdapter {
                                 name: function_call.name,
                                 input: function_call.args,
                             });
+                        } else if let Some(inline_data) = part
+                            .inline_data
+                            .filter(|data| data.mime_type.starts_with("audio/"))
+                        {
+                            content_blocks.push(UnifiedContentBlock::Audio {
+                                media_type: Some(inline_data.mime_type),
+                                data: inline_data.data,
+                                transcript: None,
+                            });
                         }
                     }
                     stop_reason = candidate.finish_reason.map(|r| r.to_string());
@@ -833,6 +970,7 @@ impl BackendAdapter for GeminiBackendAdapter {
             model: "gemini".to_string(), // Model name might need to be passed through
             content: content_blocks,
             stop_reason,
+            stop_sequence: None,
             usage,
         })
     }
@@ -1164,7 +1302,7 @@ impl BackendAdapter for GeminiBackendAdapter {
 
 #[cfg(test)]
 mod tests {
-    use super::{GeminiBackendAdapter, GEMINI_DUMMY_THOUGHT_SIGNATURE};
+    use super::{GeminiBackendAdapter, GEMINI_DUMMY_THOUGHT_SIGNATURE, GEMINI_LEADING_USER_TURN};
     use crate::ccproxy::adapter::backend::traits::BackendAdapter;
     use crate::ccproxy::adapter::unified::{
         UnifiedContentBlock, UnifiedMessage, UnifiedRequest, UnifiedRole, UnifiedToolChoice,
@@ -1191,7 +1329,7 @@ mod tests {
             reasoning_content: None,
         };
 
-        let parts = GeminiBackendAdapter::build_native_message_parts(&assistant_msg);
+        let parts = GeminiBackendAdapter::build_native_message_parts(&assistant_msg, "");
         assert_eq!(
             parts[0].thought_signature.as_deref(),
             Some(GEMINI_DUMMY_THOUGHT_SIGNATURE)
@@ -1211,11 +1349,90 @@ mod tests {
             reasoning_content: None,
         };
 
-        let parts = GeminiBackendAdapter::build_native_message_parts(&tool_msg);
+        let parts = GeminiBackendAdapter::build_native_message_parts(&tool_msg, "");
         assert!(parts[0].thought_signature.is_none());
         assert!(parts[0].function_response.is_some());
     }
 
+    #[test]
+    fn earlier_reasoning_is_sent_as_marked_text() {
+        let assistant_msg = UnifiedMessage {
+            role: UnifiedRole::Assistant,
+            content: vec![
+                UnifiedContentBlock::Thinking {
+                    thinking: "check the docs".to_string(),
+                },
+                UnifiedContentBlock::Text {
+                    text: "Zed is an editor.".to_string(),
+                },
+            ],
+            reasoning_content: Some("search first".to_string()),
+        };
+
+        let parts =
+            GeminiBackendAdapter::build_native_message_parts(&assistant_msg, "[Reasoning]\n");
+        let texts: Vec<Option<&str>> = parts.iter().map(|part| part.text.as_deref()).collect();
+        assert_eq!(
+            texts,
+            vec![
+                Some("[Reasoning]\nsearch first\ncheck the docs"),
+                Some("Zed is an editor.")
+            ]
+        );
+        assert!(parts.iter().all(|part| part.thought.is_none()));
+    }
+
+    #[tokio::test]
+    async fn history_opened_by_the_assistant_starts_with_a_user_turn() {
+        let text_message = |role, text: &str| UnifiedMessage {
+            role,
+            content: vec![UnifiedContentBlock::Text {
+                text: text.to_string(),
+            }],
+            reasoning_content: None,
+        };
+        let mut unified_request = UnifiedRequest {
+            model: "gemini-2.5-flash".to_string(),
+            messages: vec![
+                text_message(UnifiedRole::Assistant, "Hello, how can I help?"),
+                text_message(UnifiedRole::User, "What is Rust?"),
+            ],
+            ..Default::default()
+        };
+
+        let request = GeminiBackendAdapter
+            .adapt_request(
+                &reqwest::Client::new(),
+                &mut unified_request,
+                "test-key",
+                "https://example.com",
+                "gemini-2.5-flash",
+                false,
+                &mut reqwest::header::HeaderMap::new(),
+            )
+            .await
+            .expect("request should build")
+            .build()
+            .expect("request should finalize");
+
+        let body = request.body().and_then(|body| body.as_bytes()).unwrap();
+        let payload: GeminiRequest =
+            serde_json::from_slice(body).expect("payload should deserialize");
+        let turns: Vec<(Option<&str>, Option<&str>)> = payload
+            .contents
+            .iter()
+            .map(|content| (content.role.as_deref(), content.parts[0].text.as_deref()))
+            .collect();
+        assert_eq!(
+            turns,
+            vec![
+                (Some("user"), Some(GEMINI_LEADING_USER_TURN)),
+                (Some("model"), Some("Hello, how can I help?")),
+                (Some("user"), Some("What is Rust?")),
+            ]
+        );
+    }
+
     #[tokio::test]
     async fn specific_tool_choice_emits_allowed_function_names() {
         let client = reqwest::Client::new();
@@ -1262,4 +1479,71 @@ mod tests {
 
         assert_eq!(allowed, vec!["get_weather".to_string()]);
     }
+
+    #[test]
+    fn audio_input_is_sent_as_inline_data() {
+        let user_msg = UnifiedMessage {
+            role: UnifiedRole::User,
+            content: vec![UnifiedContentBlock::Audio {
+                media_type: Some("audio/mp3".to_string()),
+                data: "SUQz".to_string(),
+                transcript: None,
+            }],
+            reasoning_content: None,
+        };
+
+        let parts = GeminiBackendAdapter::build_native_message_parts(&user_msg, "");
+        let inline_data = parts[0]
+            .inline_data
+            .as_ref()
+            .expect("audio should be inline data");
+        assert_eq!(inline_data.mime_type, "audio/mp3");
+        assert_eq!(inline_data.data, "SUQz");
+    }
+
+    #[tokio::test]
+    async fn developer_prompt_is_folded_into_system_instruction() {
+        let mut unified_request = UnifiedRequest {
+            model: "gemini-2.5-flash".to_string(),
+            system_prompt: Some("You are a coding assistant.".to_string()),
+            developer_prompt: Some("Prefer Rust.".to_string()),
+            messages: vec![UnifiedMessage {
+                role: UnifiedRole::User,
+                content: vec![UnifiedContentBlock::Text {
+                    text: "hi".to_string(),
+                }],
+                reasoning_content: None,
+            }],
+            ..Default::default()
+        };
+
+        let request = GeminiBackendAdapter
+            .adapt_request(
+                &reqwest::Client::new(),
+                &mut unified_request,
+                "test-key",
+                "https://example.com",
+                "gemini-2.5-flash",
+                false,
+                &mut reqwest::header::HeaderMap::new(),
+            )
+            .await
+            .expect("request should build")
+            .build()
+            .expect("request should finalize");
+
+        let body = request.body().and_then(|body| body.as_bytes()).unwrap();
+        let payload: GeminiRequest =
+            serde_json::from_slice(body).expect("payload should deserialize");
+        let system_text: String = payload
+            .system_instruction
+            .expect("system instruction should exist")
+            .parts
+            .into_iter()
+            .filter_map(|part| part.text)
+            .collect();
+
+        assert!(system_text.contains("You are a coding assistant."));
+        assert!(system_text.contains("Prefer Rust."));
+    }
 }
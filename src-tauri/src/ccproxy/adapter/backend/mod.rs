@@ -3,6 +3,7 @@ mod common;
 mod gemini;
 mod ollama;
 mod openai;
+mod prompt_template;
 mod traits;
 
 pub use claude::ClaudeBackendAdapter;
@@ -10,7 +11,10 @@ pub use common::{generate_tool_prompt, update_message_block};
 pub use gemini::GeminiBackendAdapter;
 pub use ollama::OllamaBackendAdapter;
 pub use openai::OpenAIBackendAdapter;
+pub use prompt_template::PromptTemplate;
 pub use traits::{BackendAdapter, BackendResponse};
 
 #[cfg(test)]
 mod openai_test;
+#[cfg(test)]
+mod stream_fixture_test;
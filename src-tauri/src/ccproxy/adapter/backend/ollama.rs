@@ -23,11 +23,122 @@ use crate::ccproxy::{
     utils::token_estimator::estimate_tokens,
 };
 
-use super::{BackendAdapter, BackendResponse};
+use super::{BackendAdapter, BackendResponse, PromptTemplate};
 use crate::ccproxy::adapter::backend::common;
 
 pub struct OllamaBackendAdapter;
 
+/// Keys of the Ollama `options` object. Ollama ignores them at the top level of a request.
+const OLLAMA_OPTION_KEYS: &[&str] = &[
+    "num_keep",
+    "seed",
+    "num_predict",
+    "top_k",
+    "top_p",
+    "min_p",
+    "typical_p",
+    "repeat_last_n",
+    "temperature",
+    "repeat_penalty",
+    "presence_penalty",
+    "frequency_penalty",
+    "penalize_newline",
+    "stop",
+    "numa",
+    "num_ctx",
+    "num_batch",
+    "num_gpu",
+    "main_gpu",
+    "use_mmap",
+    "num_thread",
+];
+
+fn options_object(
+    request_json: &mut serde_json::Value,
+) -> Option<&mut serde_json::Map<String, serde_json::Value>> {
+    let request = request_json.as_object_mut()?;
+    request
+        .entry("options")
+        .or_insert_with(|| json!({}))
+        .as_object_mut()
+}
+
+/// Adds the options of an Ollama client request that the request does not set otherwise,
+/// such as `num_ctx`. Options derived from the request, like the adapted temperature, win.
+fn fill_client_options(
+    request_json: &mut serde_json::Value,
+    client_options: Option<&serde_json::Map<String, serde_json::Value>>,
+) {
+    let Some(client_options) = client_options else {
+        return;
+    };
+    if let Some(options) = options_object(request_json) {
+        for (key, value) in client_options {
+            if !value.is_null() {
+                options.entry(key.clone()).or_insert_with(|| value.clone());
+            }
+        }
+    }
+}
+
+/// Moves option keys set at the top level, by the model's custom params or the extra body,
+/// into `options`, overriding the options derived from the request.
+fn move_options_from_top_level(request_json: &mut serde_json::Value) {
+    let Some(request) = request_json.as_object_mut() else {
+        return;
+    };
+    let moved: Vec<(String, serde_json::Value)> = OLLAMA_OPTION_KEYS
+        .iter()
+        .filter_map(|key| request.remove(*key).map(|value| (key.to_string(), value)))
+        .filter(|(_, value)| !value.is_null())
+        .collect();
+    if moved.is_empty() {
+        return;
+    }
+    if let Some(options) = options_object(request_json) {
+        options.extend(moved);
+    }
+}
+
+/// The raw generate endpoint next to the chat endpoint of an Ollama server.
+fn generate_url(chat_url: &str) -> String {
+    match chat_url.trim_end_matches('/').strip_suffix("/api/chat") {
+        Some(base) => format!("{}/api/generate", base),
+        None => chat_url.to_string(),
+    }
+}
+
+/// Turns a chat request into a raw `/api/generate` request carrying the templated prompt.
+///
+/// The stop sequences already in `options`, from the client or the model's custom params,
+/// are kept; those of the model config and the template are added after them.
+fn into_raw_generate_request(
+    request_json: &mut serde_json::Value,
+    template: PromptTemplate,
+    prompt: String,
+    images: Vec<String>,
+    model_stop: &[String],
+) {
+    let client_stop: Option<Vec<String>> = request_json
+        .pointer("/options/stop")
+        .and_then(|stop| serde_json::from_value(stop.clone()).ok());
+    let stop = template.merge_stop_sequences(client_stop.as_deref(), model_stop);
+
+    if let Some(request) = request_json.as_object_mut() {
+        request.remove("messages");
+        // Tools are only described in the prompt in tool compat mode
+        request.remove("tools");
+        request.insert("prompt".to_string(), json!(prompt));
+        request.insert("raw".to_string(), json!(true));
+        if !images.is_empty() {
+            request.insert("images".to_string(), json!(images));
+        }
+    }
+    if let Some(options) = options_object(request_json) {
+        options.insert("stop".to_string(), json!(stop));
+    }
+}
+
 #[async_trait]
 impl BackendAdapter for OllamaBackendAdapter {
     async fn adapt_request(
@@ -41,6 +152,10 @@ impl BackendAdapter for OllamaBackendAdapter {
         headers: &mut reqwest::header::HeaderMap,
     ) -> Result<RequestBuilder, anyhow::Error> {
         crate::ccproxy::adapter::backend::common::preprocess_unified_request(unified_request);
+        // No developer role in this protocol
+        unified_request.fold_developer_prompt();
+        crate::ccproxy::adapter::backend::common::inline_documents_as_text(unified_request);
+        crate::ccproxy::adapter::backend::common::reject_audio_blocks(unified_request, "Ollama")?;
 
         // --- Tool Compatibility Mode Handling ---
         // If tool_compat_mode is enabled, we inject a system prompt with tool definitions
@@ -341,6 +456,32 @@ impl BackendAdapter for OllamaBackendAdapter {
             }
         });
 
+        // Models with a prompt template go to the raw generate endpoint with the rendered prompt
+        let prompt_template = unified_request
+            .prompt_template
+            .as_deref()
+            .filter(|name| !name.trim().is_empty())
+            .and_then(|name| match name.parse::<PromptTemplate>() {
+                Ok(template) => Some(template),
+                Err(e) => {
+                    log::warn!("Ignoring prompt template of model '{}': {}", model, e);
+                    None
+                }
+            });
+        let raw_prompt = prompt_template.map(|template| {
+            let prompt = template.render(
+                ollama_messages
+                    .iter()
+                    .map(|m| (m.role.as_str(), m.content.as_str())),
+            );
+            let images: Vec<String> = ollama_messages
+                .iter()
+                .filter_map(|m| m.images.clone())
+                .flatten()
+                .collect();
+            (template, prompt, images)
+        });
+
         let ollama_request = OllamaChatCompletionRequest {
             model: model.to_string(),
             messages: ollama_messages,
@@ -391,10 +532,7 @@ impl BackendAdapter for OllamaBackendAdapter {
                 .thinking
                 .as_ref()
                 .and_then(|thinking| thinking.include_thoughts.map(serde_json::Value::Bool)),
-            keep_alive: unified_request
-                .keep_alive
-                .as_deref()
-                .map(|ka| serde_json::Value::from(ka)),
+            keep_alive: unified_request.keep_alive.clone(),
             tools: ollama_tools,
         };
 
@@ -404,19 +542,38 @@ impl BackendAdapter for OllamaBackendAdapter {
         );
 
         let mut request_json = serde_json::to_value(&ollama_request)?;
+        fill_client_options(&mut request_json, unified_request.ollama_options.as_ref());
+
+        crate::ccproxy::adapter::backend::common::merge_extra_body(
+            &mut request_json,
+            &unified_request.extra_body,
+        );
 
         // Merge custom params from model config
         crate::ai::util::merge_custom_params_value(
             &mut request_json,
             &unified_request.custom_params,
         );
+        move_options_from_top_level(&mut request_json);
+
+        let mut request_url = provider_full_url.to_string();
+        if let Some((template, prompt, images)) = raw_prompt {
+            into_raw_generate_request(
+                &mut request_json,
+                template,
+                prompt,
+                images,
+                &unified_request.default_stop_sequences,
+            );
+            request_url = generate_url(provider_full_url);
+        }
 
         if log_proxy_to_file {
             // Log the request to a file
             log::info!(target: "ccproxy_logger","Ollama Request Body: \n{}\n----------------\n", serde_json::to_string_pretty(&request_json).unwrap_or_default());
         }
 
-        Ok(client.post(provider_full_url).json(&request_json))
+        Ok(client.post(request_url).json(&request_json))
     }
 
     async fn adapt_response(
@@ -431,7 +588,7 @@ impl BackendAdapter for OllamaBackendAdapter {
         let ollama_response: Result<OllamaChatCompletionResponse, serde_json::Error> =
             serde_json::from_slice(&backend_response.body);
 
-        let ollama_response = match ollama_response {
+        let mut ollama_response = match ollama_response {
             Ok(response) => response,
             Err(e) => {
                 log::error!("Failed to parse Ollama response: {}", e);
@@ -443,6 +600,11 @@ impl BackendAdapter for OllamaBackendAdapter {
             }
         };
 
+        // The raw generate endpoint answers with `response` instead of a message
+        if let Some(text) = ollama_response.response.take() {
+            ollama_response.message.content = text;
+        }
+
         let mut content_blocks = Vec::new();
 
         // Handle tool compatibility mode parsing
@@ -480,7 +642,9 @@ impl BackendAdapter for OllamaBackendAdapter {
                                 &parsed_tool.name,
                                 &parsed_tool.args
                             );
-                            content_blocks.push(parsed_tool.into());
+                            content_blocks.push(
+                                parsed_tool.into_content_block(&backend_response.code_reflow),
+                            );
                         }
                         Err(e) => {
                             let tool_xml = &processed_text[start_pos..end_pos + end_tag_len];
@@ -561,6 +725,7 @@ impl BackendAdapter for OllamaBackendAdapter {
             model: ollama_response.model,
             content: content_blocks,
             stop_reason: Some("stop".to_string()),
+            stop_sequence: None,
             usage,
         })
     }
@@ -580,7 +745,7 @@ impl BackendAdapter for OllamaBackendAdapter {
                 continue;
             }
 
-            let ollama_chunk: OllamaStreamResponse = match serde_json::from_str(line) {
+            let mut ollama_chunk: OllamaStreamResponse = match serde_json::from_str(line) {
                 Ok(c) => c,
                 Err(e) => {
                     log::warn!("Failed to parse Ollama stream chunk: {}, line: {}", e, line);
@@ -588,6 +753,10 @@ impl BackendAdapter for OllamaBackendAdapter {
                 }
             };
 
+            if let Some(text) = ollama_chunk.response.take() {
+                ollama_chunk.message.content = text;
+            }
+
             // Handle message start
             self.handle_message_start(&ollama_chunk, &sse_status, &mut unified_chunks);
 
@@ -1074,3 +1243,178 @@ impl OllamaBackendAdapter {
     }
     */
 }
+
+#[cfg(test)]
+mod tests {
+    use super::OllamaBackendAdapter;
+    use crate::ccproxy::adapter::backend::traits::BackendAdapter;
+    use crate::ccproxy::adapter::unified::{
+        UnifiedContentBlock, UnifiedMessage, UnifiedRequest, UnifiedRole,
+    };
+    use reqwest::Client;
+    use serde_json::{json, Value};
+
+    async fn adapted_payload(unified_request: &mut UnifiedRequest) -> Value {
+        let request = OllamaBackendAdapter
+            .adapt_request(
+                &Client::new(),
+                unified_request,
+                "",
+                "http://localhost:11434/api/chat",
+                "llama3",
+                false,
+                &mut reqwest::header::HeaderMap::new(),
+            )
+            .await
+            .expect("Ollama request should adapt")
+            .build()
+            .expect("request should build");
+        serde_json::from_slice(
+            request
+                .body()
+                .and_then(|body| body.as_bytes())
+                .expect("request body should be available as bytes"),
+        )
+        .expect("request body should be valid json")
+    }
+
+    fn user_message(text: &str) -> UnifiedMessage {
+        UnifiedMessage {
+            role: UnifiedRole::User,
+            content: vec![UnifiedContentBlock::Text {
+                text: text.to_string(),
+            }],
+            reasoning_content: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_developer_prompt_is_folded_into_system_message() {
+        let mut unified_request = UnifiedRequest {
+            model: "llama3".to_string(),
+            system_prompt: Some("You are a coding assistant.".to_string()),
+            developer_prompt: Some("Prefer Rust.".to_string()),
+            messages: vec![user_message("Hello")],
+            ..Default::default()
+        };
+
+        let payload = adapted_payload(&mut unified_request).await;
+
+        let messages = payload["messages"].as_array().expect("messages");
+        assert_eq!(messages[0]["role"], "system");
+        assert_eq!(
+            messages[0]["content"],
+            "You are a coding assistant.\n\nPrefer Rust."
+        );
+        assert!(messages
+            .iter()
+            .all(|message| message["role"] != "developer"));
+    }
+
+    #[tokio::test]
+    async fn test_keep_alive_and_options_are_passed_through() {
+        let mut client_options = serde_json::Map::new();
+        client_options.insert("num_ctx".to_string(), json!(16384));
+        client_options.insert("temperature".to_string(), json!(1.5));
+        let mut unified_request = UnifiedRequest {
+            model: "llama3".to_string(),
+            messages: vec![user_message("Hello")],
+            temperature: Some(0.5),
+            max_tokens: Some(256),
+            keep_alive: Some(json!("30m")),
+            ollama_options: Some(client_options),
+            custom_params: Some(json!([{"key": "num_gpu", "value": "20"}])),
+            ..Default::default()
+        };
+
+        let payload = adapted_payload(&mut unified_request).await;
+        assert_eq!(payload["keep_alive"], "30m");
+        let options = &payload["options"];
+        assert_eq!(options["num_ctx"], 16384);
+        assert_eq!(options["num_predict"], 256);
+        assert_eq!(options["num_gpu"], 20);
+        // The adapted request temperature wins over the client option
+        assert_ne!(options["temperature"], json!(1.5));
+        assert!(payload.get("num_gpu").is_none());
+
+        // Unset options keep Ollama's defaults
+        let mut unified_request = UnifiedRequest {
+            model: "llama3".to_string(),
+            messages: vec![user_message("Hello")],
+            ..Default::default()
+        };
+        let payload = adapted_payload(&mut unified_request).await;
+        assert!(payload.get("keep_alive").is_none());
+        assert_eq!(payload["options"], json!({}));
+    }
+
+    #[tokio::test]
+    async fn test_prompt_template_wraps_messages_for_generate_endpoint() {
+        let mut unified_request = UnifiedRequest {
+            model: "qwen-local".to_string(),
+            system_prompt: Some("Be brief.".to_string()),
+            messages: vec![user_message("Hello")],
+            stop_sequences: Some(vec!["###".to_string()]),
+            prompt_template: Some("chatml".to_string()),
+            default_stop_sequences: vec!["<|endoftext|>".to_string(), "###".to_string()],
+            ..Default::default()
+        };
+
+        let request = OllamaBackendAdapter
+            .adapt_request(
+                &Client::new(),
+                &mut unified_request,
+                "",
+                "http://localhost:11434/api/chat",
+                "qwen-local",
+                false,
+                &mut reqwest::header::HeaderMap::new(),
+            )
+            .await
+            .expect("Ollama request should adapt")
+            .build()
+            .expect("request should build");
+        assert_eq!(request.url().path(), "/api/generate");
+
+        let payload: Value =
+            serde_json::from_slice(request.body().and_then(|body| body.as_bytes()).unwrap())
+                .unwrap();
+        assert!(payload.get("messages").is_none());
+        assert_eq!(payload["raw"], true);
+        assert_eq!(
+            payload["prompt"],
+            "<|im_start|>system\nBe brief.<|im_end|>\n\
+             <|im_start|>user\nHello<|im_end|>\n\
+             <|im_start|>assistant\n"
+        );
+        // Client stops first, then the model config's, then the template's
+        assert_eq!(
+            payload["options"]["stop"],
+            json!(["###", "<|endoftext|>", "<|im_end|>", "<|im_start|>"])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_generate_response_text_is_read() {
+        let body = json!({
+            "model": "qwen-local",
+            "created_at": "2024-01-01T00:00:00Z",
+            "response": "Hi there",
+            "done": true
+        });
+        let response = OllamaBackendAdapter
+            .adapt_response(super::BackendResponse {
+                body: serde_json::to_vec(&body).unwrap().into(),
+                tool_compat_mode: false,
+                code_reflow: Default::default(),
+            })
+            .await
+            .expect("generate response should parse");
+        assert_eq!(
+            response.content,
+            vec![UnifiedContentBlock::Text {
+                text: "Hi there".to_string()
+            }]
+        );
+    }
+}
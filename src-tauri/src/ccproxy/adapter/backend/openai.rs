@@ -1,18 +1,21 @@
 use async_trait::async_trait;
 use reqwest::{Client, RequestBuilder};
-use serde_json::json;
+use serde_json::{json, Value};
 use std::sync::{Arc, RwLock};
 
 use super::{BackendAdapter, BackendResponse};
 use crate::ccproxy::get_tool_id;
 use crate::ccproxy::openai::{
-    OpenAIChatCompletionRequest, OpenAIChatCompletionResponse, OpenAIChatCompletionStreamResponse,
-    OpenAIEmbeddingInput, OpenAIEmbeddingRequest, OpenAIEmbeddingResponse, OpenAIFunctionCall,
-    OpenAIFunctionDefinition, OpenAIImageUrl, OpenAIMessageContent, OpenAIMessageContentPart,
-    OpenAIResponseFormat, OpenAITool, OpenAIToolChoice, OpenAIToolChoiceFunction,
-    OpenAIToolChoiceObject, UnifiedChatMessage, UnifiedToolCall,
+    OpenAIAudioParams, OpenAIChatCompletionRequest, OpenAIChatCompletionResponse,
+    OpenAIChatCompletionStreamResponse, OpenAIEmbeddingInput, OpenAIEmbeddingRequest,
+    OpenAIEmbeddingResponse, OpenAIFunctionCall, OpenAIFunctionDefinition, OpenAIImageUrl,
+    OpenAIInputAudio, OpenAIMessageContent, OpenAIMessageContentPart, OpenAIResponseFormat,
+    OpenAITool, OpenAIToolChoice, OpenAIToolChoiceFunction, OpenAIToolChoiceObject,
+    UnifiedChatMessage, UnifiedToolCall,
+};
+use crate::ccproxy::types::{
+    REPEATED_TOOL_CALL_REMINDER, TOOL_PARSE_ERROR_REMINDER, TOOL_TAG_END, TOOL_TAG_START,
 };
-use crate::ccproxy::types::{TOOL_PARSE_ERROR_REMINDER, TOOL_TAG_END, TOOL_TAG_START};
 use crate::ccproxy::{
     adapter::{
         backend::{common, update_message_block},
@@ -23,9 +26,10 @@ use crate::ccproxy::{
         },
         range_adapter::adapt_temperature,
         unified::{
-            SseStatus, UnifiedContentBlock, UnifiedEmbeddingData, UnifiedEmbeddingInput,
-            UnifiedEmbeddingRequest, UnifiedEmbeddingResponse, UnifiedRequest, UnifiedResponse,
-            UnifiedRole, UnifiedStreamChunk, UnifiedToolChoice, UnifiedUsage,
+            OpenAICompatQuirks, SseStatus, UnifiedContentBlock, UnifiedEmbeddingData,
+            UnifiedEmbeddingInput, UnifiedEmbeddingRequest, UnifiedEmbeddingResponse,
+            UnifiedRequest, UnifiedResponse, UnifiedRole, UnifiedStreamChunk, UnifiedToolChoice,
+            UnifiedUsage,
         },
     },
     types::ChatProtocol,
@@ -47,6 +51,18 @@ impl BackendAdapter for OpenAIBackendAdapter {
         headers: &mut reqwest::header::HeaderMap,
     ) -> Result<RequestBuilder, anyhow::Error> {
         crate::ccproxy::adapter::backend::common::preprocess_unified_request(unified_request);
+        // OpenAI expects the tool messages in the order of the assistant's tool calls
+        crate::ccproxy::adapter::backend::common::order_tool_results_by_calls(unified_request);
+        crate::ccproxy::adapter::backend::common::inline_documents_as_text(unified_request);
+        if !unified_request.skip_tool_loop_guard {
+            common::explain_unanswered_tool_calls(unified_request);
+        }
+        let developer_prompt = if supports_developer_role(full_provider_url) {
+            unified_request.developer_prompt.take()
+        } else {
+            unified_request.fold_developer_prompt();
+            None
+        };
 
         // --- Tool Compatibility Mode Handling ---
         // If tool_compat_mode is enabled, we inject a system prompt with tool definitions
@@ -212,6 +228,31 @@ impl BackendAdapter for OpenAIBackendAdapter {
                                 },
                             });
                         }
+                        UnifiedContentBlock::Audio {
+                            media_type,
+                            data,
+                            transcript,
+                        } => {
+                            if msg.role == UnifiedRole::Assistant {
+                                // Earlier generated speech is replayed as its transcript.
+                                if let Some(transcript) = transcript {
+                                    primary_message_parts.push(OpenAIMessageContentPart::Text {
+                                        text: transcript.clone(),
+                                    });
+                                }
+                            } else {
+                                let media_type = media_type.as_deref().ok_or_else(|| {
+                                    anyhow::anyhow!("Audio input without a media type")
+                                })?;
+                                primary_message_parts.push(OpenAIMessageContentPart::InputAudio {
+                                    input_audio: OpenAIInputAudio {
+                                        data: data.clone(),
+                                        format: common::audio_format_from_media_type(media_type)
+                                            .to_string(),
+                                    },
+                                });
+                            }
+                        }
                         UnifiedContentBlock::ToolUse { id, name, input } => {
                             primary_tool_calls.push(UnifiedToolCall {
                                 id: Some(id.clone()),
@@ -256,6 +297,9 @@ impl BackendAdapter for OpenAIBackendAdapter {
                         UnifiedContentBlock::Thinking { .. } => {
                             // Thinking blocks will be handled after the loop
                         }
+                        UnifiedContentBlock::Document { .. } => {
+                            // Documents were replaced by their text in `inline_documents_as_text`
+                        }
                     }
                 }
 
@@ -497,6 +541,43 @@ impl BackendAdapter for OpenAIBackendAdapter {
             }
         }
 
+        if let Some(developer_prompt) = developer_prompt {
+            let position = usize::from(
+                openai_messages
+                    .first()
+                    .is_some_and(|message| message.role.as_deref() == Some("system")),
+            );
+            openai_messages.insert(
+                position,
+                UnifiedChatMessage {
+                    role: Some("developer".to_string()),
+                    content: Some(OpenAIMessageContent::Text(developer_prompt)),
+                    ..Default::default()
+                },
+            );
+        }
+
+        // A model stuck calling the same tool gets told so right before its next turn
+        if !unified_request.skip_tool_loop_guard {
+            if let Some((tool_name, count)) = common::repeated_tool_call(&unified_request.messages)
+            {
+                log::warn!(
+                    "Tool '{}' was called {} times in a row with the same arguments, adding a reminder",
+                    tool_name,
+                    count
+                );
+                openai_messages.push(UnifiedChatMessage {
+                    role: Some("system".to_string()),
+                    content: Some(OpenAIMessageContent::Text(
+                        REPEATED_TOOL_CALL_REMINDER
+                            .replace("{TOOL_NAME}", &tool_name)
+                            .replace("{COUNT}", &count.to_string()),
+                    )),
+                    ..Default::default()
+                });
+            }
+        }
+
         let openai_request = OpenAIChatCompletionRequest {
             model: model.to_string(),
             messages: openai_messages,
@@ -540,6 +621,15 @@ impl BackendAdapter for OpenAIBackendAdapter {
             enable_thinking,
             thinking_budget,
             store: None,
+            modalities: unified_request.modalities.clone(),
+            audio: unified_request
+                .audio
+                .as_ref()
+                .map(|audio| OpenAIAudioParams {
+                    voice: audio.voice.clone(),
+                    format: audio.format.clone(),
+                }),
+            extra: serde_json::Map::new(),
         };
 
         headers.insert(
@@ -555,12 +645,21 @@ impl BackendAdapter for OpenAIBackendAdapter {
 
         let mut request_json = serde_json::to_value(&openai_request)?;
 
+        crate::ccproxy::adapter::backend::common::merge_extra_body(
+            &mut request_json,
+            &unified_request.extra_body,
+        );
+
         // Merge custom params from model config
         crate::ai::util::merge_custom_params_value(
             &mut request_json,
             &unified_request.custom_params,
         );
 
+        if let Some(quirks) = unified_request.openai_quirks {
+            apply_compat_quirks(&mut request_json, quirks);
+        }
+
         if log_proxy_to_file {
             // Log the request to a file
             log::info!(target: "ccproxy_logger","Openai Request Body: \n{}\n----------------\n", serde_json::to_string_pretty(&request_json).unwrap_or_default());
@@ -649,7 +748,9 @@ impl BackendAdapter for OpenAIBackendAdapter {
                                     &parsed_tool.name,
                                     &parsed_tool.args
                                 );
-                                content_blocks.push(parsed_tool.into());
+                                content_blocks.push(
+                                    parsed_tool.into_content_block(&backend_response.code_reflow),
+                                );
                             }
                             Err(e) => {
                                 let tool_xml = &processed_text[start_pos..end_pos + end_tag_len];
@@ -705,6 +806,9 @@ impl BackendAdapter for OpenAIBackendAdapter {
                                 OpenAIMessageContentPart::ImageUrl { image_url: _ } => {
                                     anyhow::bail!("Image URL in assistant response not supported for UnifiedResponse");
                                 }
+                                OpenAIMessageContentPart::InputAudio { .. } => {
+                                    anyhow::bail!("Input audio in assistant response not supported for UnifiedResponse");
+                                }
                             }
                         }
                     }
@@ -712,6 +816,22 @@ impl BackendAdapter for OpenAIBackendAdapter {
             }
         }
 
+        // Speech of audio-capable models, the encoding is the one requested in `audio.format`.
+        if let Some(audio) = first_choice.message.audio {
+            match audio.data {
+                Some(data) => content_blocks.push(UnifiedContentBlock::Audio {
+                    media_type: None,
+                    data,
+                    transcript: audio.transcript,
+                }),
+                None => {
+                    if let Some(text) = audio.transcript {
+                        content_blocks.push(UnifiedContentBlock::Text { text });
+                    }
+                }
+            }
+        }
+
         if let Some(reasoning_content) = first_choice.message.reasoning_content {
             if !reasoning_content.is_empty() {
                 content_blocks.push(UnifiedContentBlock::Thinking {
@@ -776,6 +896,7 @@ impl BackendAdapter for OpenAIBackendAdapter {
             model: openai_response.model,
             content: content_blocks,
             stop_reason: first_choice.finish_reason,
+            stop_sequence: matched_stop_sequence(first_choice.stop_reason.as_ref()),
             usage,
         })
     }
@@ -895,6 +1016,10 @@ impl BackendAdapter for OpenAIBackendAdapter {
 
                     // Process finish reason
                     if let Some(finish_reason) = choice.finish_reason.as_ref() {
+                        if let Ok(mut status) = sse_status.write() {
+                            status.stop_sequence =
+                                matched_stop_sequence(choice.stop_reason.as_ref());
+                        }
                         self.process_finish_reason(
                             finish_reason.clone(),
                             &openai_chunk,
@@ -985,6 +1110,68 @@ impl BackendAdapter for OpenAIBackendAdapter {
     }
 }
 
+/// Rewrites the request body for vLLM and SGLang servers.
+///
+/// vLLM takes structured output as `guided_json`, and both servers read `enable_thinking`
+/// from the chat template arguments instead of the top level.
+fn apply_compat_quirks(request_json: &mut Value, quirks: OpenAICompatQuirks) {
+    let Some(body) = request_json.as_object_mut() else {
+        return;
+    };
+
+    if quirks == OpenAICompatQuirks::Vllm {
+        let schema = body
+            .get("response_format")
+            .filter(|format| format.get("type").and_then(Value::as_str) == Some("json_schema"))
+            .and_then(|format| format.get("json_schema"))
+            .map(|json_schema| {
+                json_schema
+                    .get("schema")
+                    .cloned()
+                    .unwrap_or_else(|| json_schema.clone())
+            });
+        if let Some(schema) = schema {
+            body.remove("response_format");
+            body.insert("guided_json".to_string(), schema);
+        }
+    }
+
+    if let Some(enable_thinking) = body.remove("enable_thinking") {
+        match body
+            .entry("chat_template_kwargs")
+            .or_insert_with(|| json!({}))
+            .as_object_mut()
+        {
+            Some(kwargs) => {
+                // Template arguments sent by the client win
+                kwargs.entry("enable_thinking").or_insert(enable_thinking);
+            }
+            None => log::warn!(
+                "chat_template_kwargs is not an object, dropped enable_thinking for {:?}",
+                quirks
+            ),
+        }
+    }
+}
+
+/// Whether the provider accepts `developer` messages. Most OpenAI-compatible servers reject
+/// the role, so it is only sent to OpenAI itself and Azure OpenAI.
+fn supports_developer_role(provider_url: &str) -> bool {
+    reqwest::Url::parse(provider_url)
+        .ok()
+        .and_then(|url| url.host_str().map(|host| host.to_lowercase()))
+        .is_some_and(|host| host == "api.openai.com" || host.ends_with(".openai.azure.com"))
+}
+
+/// The stop string a vLLM style server reports in `choices[].stop_reason`. A number there is
+/// the id of a stop token, which is not a configured sequence.
+fn matched_stop_sequence(stop_reason: Option<&Value>) -> Option<String> {
+    stop_reason
+        .and_then(Value::as_str)
+        .filter(|sequence| !sequence.is_empty())
+        .map(str::to_string)
+}
+
 /// Clean up internal markers like <!--[ToolCalls]--> from content
 fn cleanup_content(text: &str) -> String {
     // regex would be cleaner but let's use simple replacement for common markers first
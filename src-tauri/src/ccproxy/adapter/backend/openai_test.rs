@@ -5,10 +5,14 @@ mod tests {
     use super::super::openai::OpenAIBackendAdapter;
     use super::super::{BackendAdapter, BackendResponse};
     use crate::ccproxy::adapter::{
-        input::{from_claude, from_ollama, from_openai_responses},
-        unified::{UnifiedContentBlock, UnifiedMessage, UnifiedRequest, UnifiedRole, UnifiedTool},
+        input::{from_claude, from_gemini, from_ollama, from_openai, from_openai_responses},
+        unified::{
+            OpenAICompatQuirks, UnifiedContentBlock, UnifiedMessage, UnifiedRequest, UnifiedRole,
+            UnifiedTool,
+        },
     };
     use crate::ccproxy::types::openai_responses::OpenAIResponsesRequest;
+    use base64::{engine::general_purpose::STANDARD, Engine as _};
     use reqwest::Client;
     use serde_json::{json, Value};
 
@@ -46,6 +50,7 @@ mod tests {
             .expect("response should serialize")
             .into(),
             tool_compat_mode: false,
+            code_reflow: Default::default(),
         };
 
         let unified = adapter
@@ -69,6 +74,141 @@ mod tests {
         serde_json::from_slice(body).expect("request body should be valid json")
     }
 
+    #[tokio::test]
+    async fn test_audio_input_and_output_are_forwarded() {
+        let openai_request = serde_json::from_value(json!({
+            "model": "gpt-4o-audio-preview",
+            "modalities": ["text", "audio"],
+            "audio": { "voice": "alloy", "format": "wav" },
+            "messages": [{
+                "role": "user",
+                "content": [
+                    { "type": "text", "text": "What is said in this recording?" },
+                    { "type": "input_audio", "input_audio": { "data": "UklGRg==", "format": "mp3" } }
+                ]
+            }]
+        }))
+        .expect("OpenAI request should parse");
+        let mut unified_request =
+            from_openai(openai_request, false).expect("OpenAI request should convert");
+        assert!(matches!(
+            &unified_request.messages[0].content[1],
+            UnifiedContentBlock::Audio { media_type: Some(media_type), .. } if media_type == "audio/mp3"
+        ));
+
+        let request = OpenAIBackendAdapter
+            .adapt_request(
+                &Client::new(),
+                &mut unified_request,
+                "test-api-key",
+                "https://example.com/v1/chat/completions",
+                "gpt-4o-audio-preview",
+                false,
+                &mut reqwest::header::HeaderMap::new(),
+            )
+            .await
+            .expect("OpenAI-compatible request should adapt");
+        let payload = request_json(request);
+
+        assert_eq!(payload["modalities"], json!(["text", "audio"]));
+        assert_eq!(
+            payload["audio"],
+            json!({ "voice": "alloy", "format": "wav" })
+        );
+        assert_eq!(
+            payload["messages"][0]["content"][1],
+            json!({ "type": "input_audio", "input_audio": { "data": "UklGRg==", "format": "mp3" } })
+        );
+
+        let response = BackendResponse {
+            body: serde_json::to_vec(&json!({
+                "id": "chatcmpl_audio",
+                "object": "chat.completion",
+                "created": 1,
+                "model": "gpt-4o-audio-preview",
+                "choices": [{
+                    "index": 0,
+                    "message": {
+                        "role": "assistant",
+                        "content": null,
+                        "audio": {
+                            "id": "audio_1",
+                            "data": "UklGRgAA",
+                            "transcript": "Hello there",
+                            "expires_at": 1
+                        }
+                    },
+                    "finish_reason": "stop"
+                }],
+                "usage": { "prompt_tokens": 1, "completion_tokens": 1, "total_tokens": 2 }
+            }))
+            .expect("response should serialize")
+            .into(),
+            tool_compat_mode: false,
+            code_reflow: Default::default(),
+        };
+        let unified = OpenAIBackendAdapter
+            .adapt_response(response)
+            .await
+            .expect("audio response should adapt");
+        assert!(matches!(
+            &unified.content[0],
+            UnifiedContentBlock::Audio { media_type: None, data, transcript: Some(transcript) }
+                if data == "UklGRgAA" && transcript == "Hello there"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_claude_pdf_document_degrades_to_text() {
+        let pdf = b"%PDF-1.4\n4 0 obj\n<< /Length 33 >>\nstream\nBT /F1 12 Tf (Hello PDF) Tj ET\nendstream\nendobj\n%%EOF";
+        let claude_request = serde_json::from_value(json!({
+            "model": "claude-sonnet-4",
+            "max_tokens": 1024,
+            "messages": [{
+                "role": "user",
+                "content": [
+                    {
+                        "type": "document",
+                        "title": "greeting.pdf",
+                        "source": {
+                            "type": "base64",
+                            "media_type": "application/pdf",
+                            "data": STANDARD.encode(pdf)
+                        }
+                    },
+                    { "type": "text", "text": "Summarize the document" }
+                ]
+            }]
+        }))
+        .expect("Claude request should parse");
+        let mut unified_request =
+            from_claude(claude_request, false, None).expect("Claude request should convert");
+        assert!(matches!(
+            unified_request.messages[0].content[0],
+            UnifiedContentBlock::Document { .. }
+        ));
+
+        let request = OpenAIBackendAdapter
+            .adapt_request(
+                &Client::new(),
+                &mut unified_request,
+                "test-api-key",
+                "https://example.com/v1/chat/completions",
+                "gpt-4o",
+                false,
+                &mut reqwest::header::HeaderMap::new(),
+            )
+            .await
+            .expect("OpenAI-compatible request should adapt");
+        let payload = request_json(request);
+
+        let content = payload["messages"][0]["content"].to_string();
+        assert!(content.contains("greeting.pdf"), "{}", content);
+        assert!(content.contains("Hello PDF"), "{}", content);
+        assert!(content.contains("Summarize the document"), "{}", content);
+        assert!(!content.contains("\"document\""), "{}", content);
+    }
+
     #[tokio::test]
     async fn responses_custom_tool_history_uses_object_arguments() {
         let responses_request: OpenAIResponsesRequest = serde_json::from_value(json!({
@@ -516,6 +656,160 @@ mod tests {
         println!("Orphaned tool call handled successfully");
     }
 
+    #[tokio::test]
+    async fn test_gemini_thoughts_reach_openai_backend_as_reasoning() {
+        let gemini_request = serde_json::from_value(json!({
+            "contents": [
+                {"role": "user", "parts": [{"text": "What is Zed?"}]},
+                {"role": "model", "parts": [
+                    {"text": "The user asks about an editor.", "thought": true},
+                    {"text": "Zed is a code editor."}
+                ]},
+                {"role": "user", "parts": [{"text": "Who makes it?"}]}
+            ]
+        }))
+        .expect("gemini request should deserialize");
+        let mut unified_request = from_gemini(gemini_request, false, "generateContent".into())
+            .expect("gemini request should convert");
+
+        let builder = OpenAIBackendAdapter
+            .adapt_request(
+                &Client::new(),
+                &mut unified_request,
+                "test-api-key",
+                "https://api.openai.com/v1",
+                "gpt-4",
+                false,
+                &mut reqwest::header::HeaderMap::new(),
+            )
+            .await
+            .expect("request should adapt");
+        let body = request_json(builder);
+
+        // gpt-4 keeps no reasoning history, the thoughts go before the answer as a think block
+        assert_eq!(
+            body["messages"][1]["content"],
+            "<think>\nThe user asks about an editor.\n</think>\n\nZed is a code editor."
+        );
+    }
+
+    fn search_round(id: &str, query: &str) -> Vec<UnifiedMessage> {
+        vec![
+            UnifiedMessage {
+                role: UnifiedRole::Assistant,
+                content: vec![UnifiedContentBlock::ToolUse {
+                    id: id.to_string(),
+                    name: "WebSearch".to_string(),
+                    input: json!({"query": query}),
+                }],
+                reasoning_content: None,
+            },
+            UnifiedMessage {
+                role: UnifiedRole::Tool,
+                content: vec![UnifiedContentBlock::ToolResult {
+                    tool_use_id: id.to_string(),
+                    content: "No results".to_string(),
+                    is_error: false,
+                }],
+                reasoning_content: None,
+            },
+        ]
+    }
+
+    #[test]
+    fn test_repeated_tool_calls_are_detected() {
+        use super::super::common::repeated_tool_call;
+
+        let mut messages = vec![UnifiedMessage {
+            role: UnifiedRole::User,
+            content: vec![UnifiedContentBlock::Text {
+                text: "What is Zed?".to_string(),
+            }],
+            reasoning_content: None,
+        }];
+        messages.extend(search_round("call_1", "zed editor"));
+        messages.extend(search_round("call_2", "zed editor"));
+        assert_eq!(repeated_tool_call(&messages), None);
+
+        messages.extend(search_round("call_3", "zed editor"));
+        assert_eq!(
+            repeated_tool_call(&messages),
+            Some(("WebSearch".to_string(), 3))
+        );
+
+        // Other arguments start a new run
+        messages.extend(search_round("call_4", "zed industries"));
+        assert_eq!(repeated_tool_call(&messages), None);
+        messages.extend(search_round("call_5", "zed editor"));
+        messages.extend(search_round("call_6", "zed editor"));
+        assert_eq!(repeated_tool_call(&messages), None);
+    }
+
+    #[tokio::test]
+    async fn test_tool_loop_guard_reminds_the_model() {
+        let mut messages = Vec::new();
+        for id in ["call_1", "call_2", "call_3"] {
+            messages.extend(search_round(id, "zed editor"));
+        }
+        // The last call was never answered
+        messages.push(UnifiedMessage {
+            role: UnifiedRole::Assistant,
+            content: vec![UnifiedContentBlock::ToolUse {
+                id: "call_4".to_string(),
+                name: "WebSearch".to_string(),
+                input: json!({"query": "zed editor"}),
+            }],
+            reasoning_content: None,
+        });
+        let request = UnifiedRequest {
+            model: "test-model".to_string(),
+            messages,
+            ..Default::default()
+        };
+
+        let adapt = |mut unified_request: UnifiedRequest| async move {
+            let builder = OpenAIBackendAdapter
+                .adapt_request(
+                    &Client::new(),
+                    &mut unified_request,
+                    "test-api-key",
+                    "https://api.openai.com/v1",
+                    "gpt-4",
+                    false,
+                    &mut reqwest::header::HeaderMap::new(),
+                )
+                .await
+                .expect("request should adapt");
+            request_json(builder)
+        };
+
+        let body = adapt(request.clone()).await;
+        let messages = body["messages"].as_array().expect("messages");
+        let placeholder = &messages[messages.len() - 2];
+        assert_eq!(placeholder["role"], "tool");
+        assert_eq!(
+            placeholder["content"],
+            crate::ccproxy::types::TOOL_CALL_NO_RESULT_REMAIN
+        );
+        let reminder = &messages[messages.len() - 1];
+        assert_eq!(reminder["role"], "system");
+        let reminder = reminder["content"].as_str().expect("reminder text");
+        assert!(reminder.contains("`WebSearch` tool 4 times"));
+
+        let body = adapt(UnifiedRequest {
+            skip_tool_loop_guard: true,
+            ..request
+        })
+        .await;
+        let messages = body["messages"].as_array().expect("messages");
+        let last = &messages[messages.len() - 1];
+        assert_eq!(last["role"], "tool");
+        assert_eq!(
+            last["content"],
+            crate::ccproxy::types::TOOL_CALL_EMPTY_REMAIN
+        );
+    }
+
     #[tokio::test]
     async fn test_claude_request() {
         let test_request_data = r#"
@@ -1290,7 +1584,7 @@ mod tests {
 }
 "#;
         let claude_request = serde_json::from_str(test_request_data).unwrap();
-        let mut unified_request = from_claude(claude_request, false).unwrap();
+        let mut unified_request = from_claude(claude_request, false, None).unwrap();
         let be = OpenAIBackendAdapter {};
         let client = Client::new();
         let _ = be
@@ -1341,4 +1635,328 @@ mod tests {
             .await
             .unwrap();
     }
+
+    async fn adapt_developer_request(provider_url: &str) -> Value {
+        let openai_request = serde_json::from_value(json!({
+            "model": "gpt-4.1",
+            "messages": [
+                { "role": "system", "content": "You are a coding assistant." },
+                { "role": "developer", "content": "Prefer Rust." },
+                { "role": "user", "content": "Hello" }
+            ]
+        }))
+        .expect("OpenAI request should parse");
+        let mut unified_request =
+            from_openai(openai_request, false).expect("OpenAI request should convert");
+
+        let request = OpenAIBackendAdapter
+            .adapt_request(
+                &Client::new(),
+                &mut unified_request,
+                "test-api-key",
+                provider_url,
+                "gpt-4.1",
+                false,
+                &mut reqwest::header::HeaderMap::new(),
+            )
+            .await
+            .expect("OpenAI request should adapt");
+        request_json(request)
+    }
+
+    #[tokio::test]
+    async fn test_developer_role_is_emitted_for_openai() {
+        let payload = adapt_developer_request("https://api.openai.com/v1/chat/completions").await;
+        let messages = payload["messages"].as_array().expect("messages");
+
+        assert_eq!(messages[0]["role"], "system");
+        assert_eq!(messages[0]["content"], "You are a coding assistant.");
+        assert_eq!(messages[1]["role"], "developer");
+        assert_eq!(messages[1]["content"], "Prefer Rust.");
+        assert_eq!(messages[2]["role"], "user");
+    }
+
+    #[tokio::test]
+    async fn test_developer_role_is_folded_for_compatible_providers() {
+        let payload = adapt_developer_request("https://api.deepseek.com/v1/chat/completions").await;
+        let messages = payload["messages"].as_array().expect("messages");
+
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0]["role"], "system");
+        assert_eq!(
+            messages[0]["content"],
+            "You are a coding assistant.\n\nPrefer Rust."
+        );
+        assert!(messages
+            .iter()
+            .all(|message| message["role"] != "developer"));
+    }
+
+    #[tokio::test]
+    async fn test_tool_results_follow_the_order_of_tool_calls() {
+        let tool_use = |id: &str| UnifiedContentBlock::ToolUse {
+            id: id.to_string(),
+            name: "lookup".to_string(),
+            input: json!({ "key": id }),
+        };
+        let tool_result = |id: &str| UnifiedContentBlock::ToolResult {
+            tool_use_id: id.to_string(),
+            content: format!("result of {}", id),
+            is_error: false,
+        };
+        let mut unified_request = UnifiedRequest {
+            model: "gpt-4.1".to_string(),
+            messages: vec![
+                UnifiedMessage {
+                    role: UnifiedRole::User,
+                    content: vec![UnifiedContentBlock::Text {
+                        text: "Look up a, b and c".to_string(),
+                    }],
+                    reasoning_content: None,
+                },
+                UnifiedMessage {
+                    role: UnifiedRole::Assistant,
+                    content: vec![tool_use("call_a"), tool_use("call_b"), tool_use("call_c")],
+                    reasoning_content: None,
+                },
+                UnifiedMessage {
+                    role: UnifiedRole::Tool,
+                    content: vec![tool_result("call_c")],
+                    reasoning_content: None,
+                },
+                UnifiedMessage {
+                    role: UnifiedRole::Tool,
+                    content: vec![tool_result("call_b"), tool_result("call_a")],
+                    reasoning_content: None,
+                },
+            ],
+            ..Default::default()
+        };
+
+        let request = OpenAIBackendAdapter
+            .adapt_request(
+                &Client::new(),
+                &mut unified_request,
+                "test-api-key",
+                "https://api.openai.com/v1/chat/completions",
+                "gpt-4.1",
+                false,
+                &mut reqwest::header::HeaderMap::new(),
+            )
+            .await
+            .expect("OpenAI request should adapt");
+        let payload = request_json(request);
+        let messages = payload["messages"].as_array().expect("messages");
+
+        let call_ids: Vec<&Value> = messages[1]["tool_calls"]
+            .as_array()
+            .expect("tool calls")
+            .iter()
+            .map(|call| &call["id"])
+            .collect();
+        assert_eq!(
+            call_ids,
+            [&json!("call_a"), &json!("call_b"), &json!("call_c")]
+        );
+        let results: Vec<(&Value, &Value)> = messages[2..]
+            .iter()
+            .map(|message| (&message["tool_call_id"], &message["content"]))
+            .collect();
+        assert_eq!(
+            results,
+            [
+                (&json!("call_a"), &json!("result of call_a")),
+                (&json!("call_b"), &json!("result of call_b")),
+                (&json!("call_c"), &json!("result of call_c")),
+            ]
+        );
+        assert!(messages[2..]
+            .iter()
+            .all(|message| message["role"] == "tool"));
+    }
+
+    async fn adapt_vllm_request(body: Value, quirks: Option<OpenAICompatQuirks>) -> Value {
+        let openai_request = serde_json::from_value(body).expect("OpenAI request should parse");
+        let mut unified_request =
+            from_openai(openai_request, false).expect("OpenAI request should convert");
+        unified_request.openai_quirks = quirks;
+
+        let request = OpenAIBackendAdapter
+            .adapt_request(
+                &Client::new(),
+                &mut unified_request,
+                "",
+                "http://localhost:8000/v1/chat/completions",
+                "Qwen/Qwen3-8B",
+                false,
+                &mut reqwest::header::HeaderMap::new(),
+            )
+            .await
+            .expect("OpenAI request should adapt");
+        request_json(request)
+    }
+
+    #[tokio::test]
+    async fn test_vllm_quirks_map_json_schema_to_guided_json() {
+        let schema = json!({
+            "type": "object",
+            "properties": { "city": { "type": "string" } },
+            "required": ["city"]
+        });
+        let body = json!({
+            "model": "Qwen/Qwen3-8B",
+            "messages": [{ "role": "user", "content": "Where is the Eiffel tower?" }],
+            "response_format": {
+                "type": "json_schema",
+                "json_schema": { "name": "place", "schema": schema }
+            }
+        });
+
+        let payload = adapt_vllm_request(body.clone(), Some(OpenAICompatQuirks::Vllm)).await;
+        assert_eq!(payload["guided_json"], schema);
+        assert!(payload.get("response_format").is_none());
+
+        // SGLang and plain OpenAI-compatible servers keep `response_format`
+        for quirks in [Some(OpenAICompatQuirks::Sglang), None] {
+            let payload = adapt_vllm_request(body.clone(), quirks).await;
+            assert_eq!(payload["response_format"]["json_schema"]["schema"], schema);
+            assert!(payload.get("guided_json").is_none());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_quirks_pass_template_kwargs_and_move_enable_thinking() {
+        let body = json!({
+            "model": "Qwen/Qwen3-8B",
+            "stream": true,
+            "messages": [{ "role": "user", "content": "Hi" }],
+            "thinking": { "type": "enabled" },
+            "chat_template_kwargs": { "custom_flag": 1 }
+        });
+
+        // Without quirks Qwen gets the DashScope style top-level flag
+        let payload = adapt_vllm_request(body.clone(), None).await;
+        assert_eq!(payload["enable_thinking"], true);
+
+        for quirks in [OpenAICompatQuirks::Vllm, OpenAICompatQuirks::Sglang] {
+            let payload = adapt_vllm_request(body.clone(), Some(quirks)).await;
+            assert!(payload.get("enable_thinking").is_none());
+            assert_eq!(
+                payload["chat_template_kwargs"],
+                json!({ "custom_flag": 1, "enable_thinking": true })
+            );
+        }
+    }
+
+    /// A compat mode `write_file` call from the OpenAI backend writing `content` to `path`.
+    async fn compat_write_file_content(path: &str, content: &str) -> Value {
+        let tool_xml = format!(
+            "<cs:tool_use><name>write_file</name><args><arg name=\"file_path\" type=\"string\">{}</arg><arg name=\"content\" type=\"string\">{}</arg></args></cs:tool_use>",
+            path, content
+        );
+        let response = BackendResponse {
+            body: serde_json::to_vec(&json!({
+                "id": "chatcmpl_compat",
+                "object": "chat.completion",
+                "created": 1,
+                "model": "test-model",
+                "choices": [{
+                    "index": 0,
+                    "message": {"role": "assistant", "content": tool_xml},
+                    "finish_reason": "stop"
+                }],
+                "usage": {"prompt_tokens": 1, "completion_tokens": 1, "total_tokens": 2}
+            }))
+            .expect("response should serialize")
+            .into(),
+            tool_compat_mode: true,
+            code_reflow: Default::default(),
+        };
+
+        let unified = OpenAIBackendAdapter
+            .adapt_response(response)
+            .await
+            .expect("compat response should adapt");
+        unified
+            .content
+            .iter()
+            .find_map(|block| match block {
+                UnifiedContentBlock::ToolUse { input, .. } => Some(input["content"].clone()),
+                _ => None,
+            })
+            .expect("response should contain the tool call")
+    }
+
+    #[tokio::test]
+    async fn test_compat_single_line_rust_file_is_reflowed() {
+        let content = compat_write_file_content(
+            "src/main.rs",
+            "// Greeting helpers    pub fn greet(name: String) -> String { let greeting = format!(\"Hello; {}!\", name); if name.is_empty() { return String::from(\"Hello; stranger!\"); } greeting }    pub fn main() { println!(\"{}\", greet(\"world\".to_string())); }",
+        )
+        .await;
+        assert_eq!(
+            content,
+            "// Greeting helpers\n\
+             pub fn greet(name: String) -> String {\n    \
+                 let greeting = format!(\"Hello; {}!\", name);\n    \
+                 if name.is_empty() {\n        \
+                     return String::from(\"Hello; stranger!\");\n    \
+                 }\n    \
+                 greeting\n\
+             }\n\
+             pub fn main() {\n    \
+                 println!(\"{}\", greet(\"world\".to_string()));\n\
+             }"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_compat_single_line_js_file_is_reflowed() {
+        let content = compat_write_file_content(
+            "web/list.js",
+            "// Build the list    const items = ['a;b', 'c'];    function render(list) { const out = []; for (let i = 0; i !== list.length; i++) { out.push(`item ${list[i]};`); } return out.join(', '); }    module.exports = { render, items };",
+        )
+        .await;
+        assert_eq!(
+            content,
+            "// Build the list\n\
+             const items = ['a;b', 'c'];\n\
+             function render(list) {\n    \
+                 const out = [];\n    \
+                 for (let i = 0; i !== list.length; i++) {\n        \
+                     out.push(`item ${list[i]};`);\n    \
+                 }\n    \
+                 return out.join(', ');\n\
+             }\n\
+             module.exports = {\n    \
+                 render, items\n\
+             };"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_compat_single_line_python_file_is_reflowed() {
+        let content = compat_write_file_content(
+            "tools/scan.py",
+            "import os; import sys; ROOTS = ['src; lib', 'tests']; VERBOSE = '-v' in sys.argv; def scan(roots):  # Print every root and, when verbose, its entries    for root in roots: print(root); print(os.listdir(root)) if VERBOSE else None",
+        )
+        .await;
+        // A loop body on the header line keeps its `;`, splitting it would leave the loop
+        assert_eq!(
+            content,
+            "import os\n\
+             import sys\n\
+             ROOTS = ['src; lib', 'tests']\n\
+             VERBOSE = '-v' in sys.argv\n\
+             def scan(roots):  # Print every root and, when verbose, its entries\n    \
+                 for root in roots: print(root); print(os.listdir(root)) if VERBOSE else None"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_compat_single_line_markdown_file_is_untouched() {
+        let text = "# Notes; see below { not code } ".repeat(10);
+        let content = compat_write_file_content("docs/notes.md", &text).await;
+        assert_eq!(content, text.as_str());
+    }
 }
@@ -0,0 +1,106 @@
+//! Chat templates for models served through raw completion endpoints.
+//!
+//! A raw endpoint takes one prompt string, so the turn markers a chat endpoint would add
+//! from the model's own template have to be rendered by the proxy instead.
+
+use std::str::FromStr;
+
+/// A chat template known to the proxy, selected by the `promptTemplate` of a model config.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PromptTemplate {
+    /// `<|im_start|>role ... <|im_end|>`, used by Qwen, Yi and many fine-tunes
+    ChatMl,
+    /// `<|start_header_id|>role<|end_header_id|> ... <|eot_id|>`
+    Llama3,
+    /// `[INST] ... [/INST]`, the system prompt is folded into the first instruction
+    Mistral,
+}
+
+impl FromStr for PromptTemplate {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "chatml" => Ok(Self::ChatMl),
+            "llama3" | "llama-3" => Ok(Self::Llama3),
+            "mistral" => Ok(Self::Mistral),
+            other => Err(format!("unknown prompt template '{}'", other)),
+        }
+    }
+}
+
+impl PromptTemplate {
+    /// Tokens ending a turn, the model stops generating when it emits one.
+    pub fn stop_sequences(&self) -> &'static [&'static str] {
+        match self {
+            Self::ChatMl => &["<|im_end|>", "<|im_start|>"],
+            Self::Llama3 => &["<|eot_id|>", "<|start_header_id|>"],
+            Self::Mistral => &["</s>", "[INST]"],
+        }
+    }
+
+    /// Renders `(role, content)` messages into a prompt that ends with the opening of the
+    /// assistant's turn. Tool results are rendered as user turns.
+    pub fn render<'a>(&self, messages: impl IntoIterator<Item = (&'a str, &'a str)>) -> String {
+        let mut prompt = String::new();
+        match self {
+            Self::ChatMl => {
+                for (role, content) in messages {
+                    let role = if role == "tool" { "user" } else { role };
+                    prompt.push_str(&format!("<|im_start|>{}\n{}<|im_end|>\n", role, content));
+                }
+                prompt.push_str("<|im_start|>assistant\n");
+            }
+            Self::Llama3 => {
+                prompt.push_str("<|begin_of_text|>");
+                for (role, content) in messages {
+                    let role = if role == "tool" { "ipython" } else { role };
+                    prompt.push_str(&format!(
+                        "<|start_header_id|>{}<|end_header_id|>\n\n{}<|eot_id|>",
+                        role, content
+                    ));
+                }
+                prompt.push_str("<|start_header_id|>assistant<|end_header_id|>\n\n");
+            }
+            Self::Mistral => {
+                let mut system = String::new();
+                for (role, content) in messages {
+                    match role {
+                        "system" => system.push_str(content),
+                        "assistant" => prompt.push_str(&format!(" {}</s>", content)),
+                        _ if !system.is_empty() => {
+                            prompt.push_str(&format!("[INST] {}\n\n{} [/INST]", system, content));
+                            system.clear();
+                        }
+                        _ => prompt.push_str(&format!("[INST] {} [/INST]", content)),
+                    }
+                }
+                prompt.insert_str(0, "<s>");
+            }
+        }
+        prompt
+    }
+
+    /// The stop sequences of a request: the client's, then the model config's, then the
+    /// template's own, without duplicates.
+    pub fn merge_stop_sequences(
+        &self,
+        client_stop: Option<&[String]>,
+        model_stop: &[String],
+    ) -> Vec<String> {
+        let mut stop: Vec<String> = Vec::new();
+        let template_stop = self.stop_sequences().iter().map(|s| s.to_string());
+        for sequence in client_stop
+            .unwrap_or_default()
+            .iter()
+            .cloned()
+            .chain(model_stop.iter().cloned())
+            .chain(template_stop)
+        {
+            if !sequence.is_empty() && !stop.contains(&sequence) {
+                stop.push(sequence);
+            }
+        }
+        stop
+    }
+}
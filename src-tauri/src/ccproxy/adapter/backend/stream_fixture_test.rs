@@ -0,0 +1,191 @@
+//! Golden tests for the streaming parsers of the backend adapters.
+//!
+//! Each fixture under `fixtures/` is a recorded provider stream that is replayed through
+//! `StreamProcessor` and the provider's `adapt_stream_chunk`, exactly like
+//! `handle_streamed_response` does. The produced unified chunks are compared with the
+//! `.json` golden file of the same name. Tool ids are generated per call, so they are
+//! replaced with `$tool_<n>` in order of appearance before comparing.
+//!
+//! Set `CCPROXY_UPDATE_GOLDEN=1` to rewrite the golden files after an intended change.
+
+#[cfg(test)]
+mod tests {
+    use super::super::{
+        BackendAdapter, ClaudeBackendAdapter, GeminiBackendAdapter, OllamaBackendAdapter,
+        OpenAIBackendAdapter,
+    };
+    use crate::ccproxy::{
+        adapter::unified::{SseStatus, UnifiedStreamChunk},
+        StreamFormat, StreamProcessor,
+    };
+    use serde_json::Value;
+    use std::path::PathBuf;
+    use std::sync::{Arc, RwLock};
+
+    /// Network chunk sizes the recorded bytes are split into, `usize::MAX` sends them at once.
+    const CHUNK_SIZES: [usize; 4] = [1, 7, 64, usize::MAX];
+
+    fn fixture_path(name: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("src/ccproxy/adapter/backend/fixtures")
+            .join(name)
+    }
+
+    /// Builds a backend response that delivers `raw` in chunks of `chunk_size` bytes.
+    fn recorded_response(raw: &[u8], chunk_size: usize) -> reqwest::Response {
+        let chunks: Vec<Result<Vec<u8>, std::io::Error>> = raw
+            .chunks(chunk_size.min(raw.len().max(1)))
+            .map(|chunk| Ok(chunk.to_vec()))
+            .collect();
+        let body = reqwest::Body::wrap_stream(futures::stream::iter(chunks));
+        http::Response::builder()
+            .status(200)
+            .body(body)
+            .expect("valid response")
+            .into()
+    }
+
+    /// Replays a recorded stream the way `handle_streamed_response` consumes it.
+    async fn replay(
+        adapter: &dyn BackendAdapter,
+        format: &StreamFormat,
+        raw: &[u8],
+        chunk_size: usize,
+    ) -> Vec<UnifiedStreamChunk> {
+        let sse_status = Arc::new(RwLock::new(SseStatus::new(
+            "msg_fixture".to_string(),
+            "model_fixture".to_string(),
+            false,
+            0.0,
+        )));
+        let mut receiver = StreamProcessor::new()
+            .process_stream(recorded_response(raw, chunk_size), format)
+            .await;
+
+        let mut unified_chunks = Vec::new();
+        while let Some(event) = receiver.recv().await {
+            match event {
+                Ok(event) => match adapter.adapt_stream_chunk(event, sse_status.clone()).await {
+                    Ok(chunks) => unified_chunks.extend(chunks),
+                    Err(e) => unified_chunks.push(UnifiedStreamChunk::Error {
+                        message: e.to_string(),
+                    }),
+                },
+                Err(message) => unified_chunks.push(UnifiedStreamChunk::Error { message }),
+            }
+        }
+        unified_chunks
+    }
+
+    /// Replaces every generated tool id with a stable placeholder.
+    fn normalize_tool_ids(chunks: &[UnifiedStreamChunk]) -> Value {
+        let tool_ids: Vec<&String> = chunks
+            .iter()
+            .filter_map(|chunk| match chunk {
+                UnifiedStreamChunk::ToolUseStart { id, .. } => Some(id),
+                _ => None,
+            })
+            .collect();
+
+        fn replace(value: &mut Value, tool_ids: &[&String]) {
+            match value {
+                Value::String(s) => {
+                    if let Some(n) = tool_ids.iter().position(|id| id.as_str() == s.as_str()) {
+                        *s = format!("$tool_{}", n);
+                    }
+                }
+                Value::Array(items) => items.iter_mut().for_each(|v| replace(v, tool_ids)),
+                Value::Object(map) => map.values_mut().for_each(|v| replace(v, tool_ids)),
+                _ => {}
+            }
+        }
+
+        let mut value = serde_json::to_value(chunks).expect("unified chunks serialize");
+        replace(&mut value, &tool_ids);
+        value
+    }
+
+    async fn assert_golden(
+        adapter: &dyn BackendAdapter,
+        format: StreamFormat,
+        stream_file: &str,
+        golden_file: &str,
+    ) {
+        let raw = std::fs::read(fixture_path(stream_file)).expect("stream fixture");
+        let golden_path = fixture_path(golden_file);
+
+        if std::env::var_os("CCPROXY_UPDATE_GOLDEN").is_some() {
+            let actual = normalize_tool_ids(&replay(adapter, &format, &raw, usize::MAX).await);
+            let pretty = serde_json::to_string_pretty(&actual).expect("golden serializes");
+            std::fs::write(&golden_path, pretty + "\n").expect("write golden");
+        }
+
+        let expected: Value =
+            serde_json::from_slice(&std::fs::read(&golden_path).expect("golden fixture"))
+                .expect("golden fixture is JSON");
+
+        for chunk_size in CHUNK_SIZES {
+            let actual = normalize_tool_ids(&replay(adapter, &format, &raw, chunk_size).await);
+            assert_eq!(
+                actual, expected,
+                "{} replayed in {}-byte chunks differs from {}",
+                stream_file, chunk_size, golden_file
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_openai_reasoning_and_split_tool_call() {
+        assert_golden(
+            &OpenAIBackendAdapter,
+            StreamFormat::OpenAI,
+            "openai_reasoning_tool_call.sse",
+            "openai_reasoning_tool_call.json",
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_claude_thinking_and_tool_use() {
+        assert_golden(
+            &ClaudeBackendAdapter,
+            StreamFormat::Claude,
+            "claude_thinking_tool_use.sse",
+            "claude_thinking_tool_use.json",
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_claude_error_event() {
+        assert_golden(
+            &ClaudeBackendAdapter,
+            StreamFormat::Claude,
+            "claude_overloaded_error.sse",
+            "claude_overloaded_error.json",
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_gemini_thinking_and_function_call() {
+        assert_golden(
+            &GeminiBackendAdapter,
+            StreamFormat::Gemini,
+            "gemini_thinking_function_call.sse",
+            "gemini_thinking_function_call.json",
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_ollama_thinking_and_tool_call() {
+        assert_golden(
+            &OllamaBackendAdapter,
+            StreamFormat::OpenAI,
+            "ollama_thinking_tool_call.ndjson",
+            "ollama_thinking_tool_call.json",
+        )
+        .await;
+    }
+}
@@ -2,6 +2,7 @@ use crate::ccproxy::adapter::unified::{
     SseStatus, UnifiedEmbeddingRequest, UnifiedEmbeddingResponse, UnifiedRequest, UnifiedResponse,
     UnifiedStreamChunk,
 };
+use crate::ccproxy::helper::tool_use_xml::CompatCodeReflowConfig;
 use async_trait::async_trait;
 use reqwest::{Client, RequestBuilder};
 use std::sync::{Arc, RwLock};
@@ -10,6 +11,8 @@ use std::sync::{Arc, RwLock};
 pub struct BackendResponse {
     pub body: bytes::Bytes,
     pub tool_compat_mode: bool,
+    /// Reflow of single-line file content in compat mode tool calls
+    pub code_reflow: CompatCodeReflowConfig,
 }
 
 /// A trait for adapting between the `UnifiedRequest`/`UnifiedResponse` and a specific backend protocol.
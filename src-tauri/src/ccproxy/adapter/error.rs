@@ -1,5 +1,6 @@
 use http::StatusCode;
 use reqwest::header::HeaderMap;
+use rust_i18n::t;
 use serde_json::Value;
 
 use crate::ccproxy::{adapter::unified::UnifiedErrorResponse, types::ChatProtocol};
@@ -54,6 +55,25 @@ pub fn normalize_backend_error(
         .map(ToString::to_string)
         .or_else(|| request_id_from_headers(headers));
 
+    // A wrong protocol in the provider settings surfaces as a cryptic 404/405, name the likely fix
+    let message = match detect_protocol_mismatch(backend_protocol, status_code, body) {
+        Some(likely_protocol) => {
+            log::warn!(
+                "Backend configured as '{}' answered with a '{}' error, the provider protocol is likely misconfigured",
+                backend_protocol,
+                likely_protocol
+            );
+            t!(
+                "proxy.error.protocol_mismatch",
+                configured = backend_protocol.to_string(),
+                likely = likely_protocol.to_string(),
+                error = message
+            )
+            .to_string()
+        }
+        None => message,
+    };
+
     UnifiedErrorResponse {
         status_code: status_code.as_u16(),
         message,
@@ -63,6 +83,66 @@ pub fn normalize_backend_error(
     }
 }
 
+/// Guesses the protocol a backend actually speaks from the shape of its error body.
+///
+/// Only request-shape and routing errors (400, 404, 405) are considered, and only body
+/// shapes that are specific to one protocol. Returns `None` when the shape matches the
+/// configured protocol or is not distinctive enough.
+pub fn detect_protocol_mismatch(
+    configured: &ChatProtocol,
+    status_code: StatusCode,
+    body: &[u8],
+) -> Option<ChatProtocol> {
+    if !matches!(
+        status_code,
+        StatusCode::BAD_REQUEST | StatusCode::NOT_FOUND | StatusCode::METHOD_NOT_ALLOWED
+    ) {
+        return None;
+    }
+
+    let likely = match serde_json::from_slice::<Value>(body).ok() {
+        Some(value) => {
+            let error = value.get("error");
+            if value.get("type").and_then(Value::as_str) == Some("error")
+                && error.and_then(|e| e.get("type")).is_some()
+            {
+                // {"type":"error","error":{"type":"not_found_error","message":"..."}}
+                ChatProtocol::Claude
+            } else if error
+                .and_then(|e| e.get("status"))
+                .and_then(Value::as_str)
+                .is_some_and(|status| {
+                    !status.is_empty() && status.chars().all(|c| c.is_ascii_uppercase() || c == '_')
+                })
+            {
+                // {"error":{"code":404,"message":"...","status":"NOT_FOUND"}}
+                ChatProtocol::Gemini
+            } else if error.is_some_and(|e| {
+                e.get("message").is_some() && e.get("type").is_some() && e.get("param").is_some()
+            }) {
+                // {"error":{"message":"...","type":"invalid_request_error","param":null,"code":null}}
+                ChatProtocol::OpenAI
+            } else {
+                return None;
+            }
+        }
+        // Ollama's router answers unknown paths with a plain text body
+        None if String::from_utf8_lossy(body).trim() == "404 page not found" => {
+            ChatProtocol::Ollama
+        }
+        None => return None,
+    };
+
+    let same_family = matches!(
+        (configured, &likely),
+        (
+            ChatProtocol::OpenAI | ChatProtocol::HuggingFace,
+            ChatProtocol::OpenAI
+        )
+    );
+    (*configured != likely && !same_family).then_some(likely)
+}
+
 fn error_message(error: &Value) -> Option<String> {
     match error {
         Value::String(message) => Some(message.clone()),
@@ -87,7 +167,7 @@ fn request_id_from_headers(headers: &HeaderMap) -> Option<String> {
 
 #[cfg(test)]
 mod tests {
-    use super::normalize_backend_error;
+    use super::{detect_protocol_mismatch, normalize_backend_error};
     use crate::ccproxy::{
         adapter::output::{ClaudeOutputAdapter, OutputAdapterEnum},
         types::ChatProtocol,
@@ -160,6 +240,74 @@ mod tests {
         assert_eq!(error.request_id.as_deref(), Some("req_header"));
     }
 
+    #[test]
+    fn names_claude_when_openai_config_points_at_claude_endpoint() {
+        let body = br#"{"type":"error","error":{"type":"not_found_error","message":"Not Found"},"request_id":"req_1"}"#;
+        let error = normalize_backend_error(
+            &ChatProtocol::OpenAI,
+            StatusCode::NOT_FOUND,
+            &HeaderMap::new(),
+            body,
+        );
+
+        assert_eq!(error.status_code, 404);
+        assert!(error.message.contains("claude"), "{}", error.message);
+        assert!(error.message.contains("openai"), "{}", error.message);
+        assert!(error.message.contains("Not Found"), "{}", error.message);
+        assert_eq!(error.error_type.as_deref(), Some("not_found_error"));
+
+        assert_eq!(
+            detect_protocol_mismatch(&ChatProtocol::OpenAI, StatusCode::METHOD_NOT_ALLOWED, body),
+            Some(ChatProtocol::Claude)
+        );
+    }
+
+    #[test]
+    fn detects_other_protocol_shapes_only_on_routing_errors() {
+        let openai_body = br#"{"error":{"message":"Invalid URL (POST /v1/messages)","type":"invalid_request_error","param":null,"code":null}}"#;
+        assert_eq!(
+            detect_protocol_mismatch(&ChatProtocol::Claude, StatusCode::NOT_FOUND, openai_body),
+            Some(ChatProtocol::OpenAI)
+        );
+        assert_eq!(
+            detect_protocol_mismatch(
+                &ChatProtocol::HuggingFace,
+                StatusCode::NOT_FOUND,
+                openai_body
+            ),
+            None
+        );
+
+        let gemini_body = br#"{"error":{"code":404,"message":"Not found","status":"NOT_FOUND"}}"#;
+        assert_eq!(
+            detect_protocol_mismatch(&ChatProtocol::OpenAI, StatusCode::NOT_FOUND, gemini_body),
+            Some(ChatProtocol::Gemini)
+        );
+        assert_eq!(
+            detect_protocol_mismatch(
+                &ChatProtocol::Claude,
+                StatusCode::NOT_FOUND,
+                b"404 page not found"
+            ),
+            Some(ChatProtocol::Ollama)
+        );
+
+        // Rate limits and same-protocol errors are passed through untouched.
+        let claude_body = br#"{"type":"error","error":{"type":"rate_limit_error","message":"Too many requests"}}"#;
+        assert_eq!(
+            detect_protocol_mismatch(
+                &ChatProtocol::OpenAI,
+                StatusCode::TOO_MANY_REQUESTS,
+                claude_body
+            ),
+            None
+        );
+        assert_eq!(
+            detect_protocol_mismatch(&ChatProtocol::Claude, StatusCode::BAD_REQUEST, claude_body),
+            None
+        );
+    }
+
     #[tokio::test]
     async fn converts_openai_503_to_claude_overload_response() {
         let body = br#"{"error":{"message":"ResourceExhausted: Worker local total request limit reached (65/48)","type":"Service Unavailable","code":503}}"#;
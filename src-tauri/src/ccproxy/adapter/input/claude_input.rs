@@ -2,22 +2,51 @@ use anyhow::Result;
 
 use crate::ccproxy::{
     adapter::{
+        input::helper::{
+            duplicate_messages::collapse_duplicate_messages,
+            tool_pairing::pair_tool_calls_with_results,
+        },
         range_adapter::{clamp_to_protocol_range, Parameter},
         unified::{
             UnifiedCacheControl, UnifiedContentBlock, UnifiedMessage, UnifiedMetadata,
             UnifiedRequest, UnifiedRole, UnifiedThinking, UnifiedTool, UnifiedToolChoice,
         },
     },
+    helper::pdf_text::PLAIN_TEXT_MEDIA_TYPE,
     types::{
         claude::{ClaudeNativeContentBlock, ClaudeNativeRequest, ClaudeToolChoice},
         ChatProtocol, TOOL_ARG_ERROR_REMINDER,
     },
 };
 
+/// Shortens a tool result to `max_chars` characters, noting how much was left out.
+fn truncate_tool_result(content: String, max_chars: usize) -> String {
+    let total_chars = content.chars().count();
+    if total_chars <= max_chars {
+        return content;
+    }
+    log::debug!(
+        "Truncating a Claude tool_result from {} to {} characters",
+        total_chars,
+        max_chars
+    );
+    let mut truncated: String = content.chars().take(max_chars).collect();
+    truncated.push_str(&format!(
+        "\n... [{} of {} characters truncated]",
+        total_chars - max_chars,
+        total_chars
+    ));
+    truncated
+}
+
 /// Converts a single Claude native content block into a unified content block.
 /// This function handles the direct mapping between the source and unified formats.
+///
+/// The text blocks of a `tool_result` are joined in order and its images follow the result
+/// as image blocks. With `tool_result_max_chars`, longer result text is truncated.
 fn convert_claude_content_block(
     block: ClaudeNativeContentBlock,
+    tool_result_max_chars: Option<usize>,
 ) -> Result<Vec<UnifiedContentBlock>> {
     match block {
         ClaudeNativeContentBlock::Text { text } => Ok(vec![UnifiedContentBlock::Text { text }]),
@@ -68,11 +97,42 @@ fn convert_claude_content_block(
             content,
             is_error,
             cache_control: _,
-        } => Ok(vec![UnifiedContentBlock::ToolResult {
-            tool_use_id,
-            content, // The source is already a String, which is correct for the unified format.
-            is_error: is_error.unwrap_or(false),
-        }]),
+        } => {
+            let (mut content, images) = content.into_text_and_images();
+            if let Some(max_chars) = tool_result_max_chars {
+                content = truncate_tool_result(content, max_chars);
+            }
+            let mut blocks = vec![UnifiedContentBlock::ToolResult {
+                tool_use_id,
+                content,
+                is_error: is_error.unwrap_or(false),
+            }];
+            blocks.extend(images.into_iter().map(|source| UnifiedContentBlock::Image {
+                media_type: source.media_type,
+                data: source.data,
+            }));
+            Ok(blocks)
+        }
+        ClaudeNativeContentBlock::Document { source, title, .. } => {
+            match (source.source_type.as_str(), source.data) {
+                ("base64", Some(data)) => Ok(vec![UnifiedContentBlock::Document {
+                    media_type: source
+                        .media_type
+                        .unwrap_or_else(|| "application/pdf".to_string()),
+                    data,
+                    title,
+                }]),
+                ("text", Some(data)) => Ok(vec![UnifiedContentBlock::Document {
+                    media_type: PLAIN_TEXT_MEDIA_TYPE.to_string(),
+                    data,
+                    title,
+                }]),
+                (source_type, _) => Err(anyhow::anyhow!(
+                    "Unsupported Claude document source type: {}",
+                    source_type
+                )),
+            }
+        }
         ClaudeNativeContentBlock::Thinking { thinking } => {
             Ok(vec![UnifiedContentBlock::Thinking { thinking }])
         }
@@ -84,9 +144,13 @@ fn convert_claude_content_block(
 /// This function is crucial for translating Claude-specific structures, especially
 /// the tool-calling message sequence, into a standardized format that backend
 /// adapters can understand.
+///
+/// `tool_result_max_chars` truncates longer tool result text, so that huge search results
+/// are not sent upstream in full.
 pub fn from_claude(
     native_req: ClaudeNativeRequest,
     tool_compat_mode: bool,
+    tool_result_max_chars: Option<usize>,
 ) -> Result<UnifiedRequest> {
     let mut req = native_req;
     // Validate Claude request parameters before proceeding.
@@ -97,7 +161,7 @@ pub fn from_claude(
     // Use `flat_map` to handle the one-to-many transformation required for tool results.
     // A single Claude `user` message containing `tool_result` blocks needs to be
     // transformed into one or more `UnifiedMessage`s, each with the `Tool` role.
-    let messages = req
+    let mut messages = req
         .messages
         .into_iter()
         .flat_map(|msg| -> Vec<anyhow::Result<UnifiedMessage>> {
@@ -107,7 +171,7 @@ pub fn from_claude(
                     let result = msg
                         .content
                         .into_iter()
-                        .map(convert_claude_content_block)
+                        .map(|block| convert_claude_content_block(block, tool_result_max_chars))
                         .collect::<Result<Vec<Vec<_>>>>()
                         .map(|vecs| vecs.into_iter().flatten().collect())
                         .map(|content| UnifiedMessage {
@@ -142,7 +206,7 @@ pub fn from_claude(
                         // Create Tool messages for each tool result block
                         for tool_block in tool_result_blocks {
                             messages.push(
-                                convert_claude_content_block(tool_block).map(|unified_blocks| {
+                                convert_claude_content_block(tool_block, tool_result_max_chars).map(|unified_blocks| {
                                     UnifiedMessage {
                                         role: UnifiedRole::Tool,
                                         content: unified_blocks,
@@ -156,7 +220,7 @@ pub fn from_claude(
                         if !other_blocks.is_empty() {
                             let user_message = other_blocks
                                 .into_iter()
-                                .map(convert_claude_content_block)
+                                .map(|block| convert_claude_content_block(block, tool_result_max_chars))
                                 .collect::<Result<Vec<Vec<_>>>>()
                                 .map(|vecs| vecs.into_iter().flatten().collect())
                                 .map(|content| UnifiedMessage {
@@ -173,7 +237,7 @@ pub fn from_claude(
                         let result = msg
                             .content
                             .into_iter()
-                            .map(convert_claude_content_block)
+                            .map(|block| convert_claude_content_block(block, tool_result_max_chars))
                             .collect::<Result<Vec<Vec<_>>>>()
                             .map(|vecs| vecs.into_iter().flatten().collect())
                             .map(|content| UnifiedMessage {
@@ -210,6 +274,9 @@ pub fn from_claude(
         ClaudeToolChoice::Tool { name } => UnifiedToolChoice::Tool { name },
     });
 
+    collapse_duplicate_messages(&mut messages);
+    pair_tool_calls_with_results(&mut messages);
+
     Ok(UnifiedRequest {
         model: req.model,
         messages,
@@ -266,3 +333,93 @@ pub fn from_claude(
         ..Default::default()
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::from_claude;
+    use crate::ccproxy::{
+        adapter::unified::{UnifiedContentBlock, UnifiedRole},
+        types::claude::ClaudeNativeRequest,
+    };
+    use serde_json::json;
+
+    /// The WebSearch turn of a Zed plugin session, its result sent as an array of blocks.
+    fn web_search_request() -> ClaudeNativeRequest {
+        serde_json::from_value(json!({
+            "model": "claude-sonnet-4-20250514",
+            "max_tokens": 21333,
+            "messages": [
+                {"role": "user", "content": "分析下 @plan.md 的完成情况，继续未完成的部分"},
+                {
+                    "role": "assistant",
+                    "content": [{
+                        "id": "call_c4870341178545488d891098",
+                        "input": {"query": "Zed editor plugin development tutorial"},
+                        "name": "WebSearch",
+                        "type": "tool_use"
+                    }]
+                },
+                {
+                    "role": "user",
+                    "content": [
+                        {
+                            "tool_use_id": "call_c4870341178545488d891098",
+                            "type": "tool_result",
+                            "content": [
+                                {"type": "text", "text": "Web search results for query: \"Zed editor plugin development tutorial\""},
+                                {"type": "image", "source": {"type": "base64", "media_type": "image/png", "data": "iVBORw0KGgo="}},
+                                {"type": "text", "text": "1. Developing Extensions - Zed"},
+                                {"type": "text", "text": "2. Zed extension API docs"}
+                            ]
+                        },
+                        {"type": "text", "text": "刚工具出错了，让我们继续"}
+                    ]
+                }
+            ]
+        }))
+        .expect("Claude request should deserialize")
+    }
+
+    #[test]
+    fn test_tool_result_blocks_are_flattened() {
+        let request = from_claude(web_search_request(), false, None).unwrap();
+
+        let tool_message = &request.messages[2];
+        assert_eq!(tool_message.role, UnifiedRole::Tool);
+        assert_eq!(
+            tool_message.content,
+            vec![
+                UnifiedContentBlock::ToolResult {
+                    tool_use_id: "call_c4870341178545488d891098".to_string(),
+                    content: "Web search results for query: \"Zed editor plugin development tutorial\"\n1. Developing Extensions - Zed\n2. Zed extension API docs".to_string(),
+                    is_error: false,
+                },
+                UnifiedContentBlock::Image {
+                    media_type: "image/png".to_string(),
+                    data: "iVBORw0KGgo=".to_string(),
+                },
+            ]
+        );
+        assert_eq!(request.messages[3].role, UnifiedRole::User);
+    }
+
+    #[test]
+    fn test_long_tool_result_is_truncated() {
+        let request = from_claude(web_search_request(), false, Some(10)).unwrap();
+
+        match &request.messages[2].content[0] {
+            UnifiedContentBlock::ToolResult { content, .. } => {
+                let total = "Web search results for query: \"Zed editor plugin development tutorial\"\n1. Developing Extensions - Zed\n2. Zed extension API docs".chars().count();
+                assert_eq!(
+                    content,
+                    &format!(
+                        "Web search\n... [{} of {} characters truncated]",
+                        total - 10,
+                        total
+                    )
+                );
+            }
+            other => panic!("expected a tool result, got {:?}", other),
+        }
+    }
+}
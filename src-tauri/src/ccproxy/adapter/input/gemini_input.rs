@@ -2,6 +2,10 @@ use serde_json::{json, Value};
 
 use crate::ccproxy::{
     adapter::{
+        input::helper::{
+            duplicate_messages::collapse_duplicate_messages,
+            tool_pairing::pair_tool_calls_with_results,
+        },
         range_adapter::{clamp_to_protocol_range, Parameter},
         unified::{
             UnifiedContentBlock, UnifiedEmbeddingInput, UnifiedEmbeddingRequest, UnifiedMessage,
@@ -38,6 +42,9 @@ pub fn from_gemini(
         }
     }
 
+    // Gemini has no call ids, the function responses answer the calls of the last model turn in order
+    let mut unanswered_calls: std::collections::VecDeque<String> = Default::default();
+
     for content in req.contents {
         let role = match content.role.as_deref() {
             Some("user") => UnifiedRole::User,
@@ -47,8 +54,18 @@ pub fn from_gemini(
             _ => anyhow::bail!("Invalid or missing role in Gemini message"),
         };
 
-        let content_blocks: Vec<UnifiedContentBlock> = content
+        // Thoughts of a model turn are its reasoning, not part of the answer
+        let (thoughts, parts): (Vec<GeminiPart>, Vec<GeminiPart>) = content
             .parts
+            .into_iter()
+            .partition(|part| role == UnifiedRole::Assistant && part.thought == Some(true));
+        let reasoning: Vec<String> = thoughts
+            .into_iter()
+            .filter_map(|part| part.text)
+            .filter(|text| !text.trim().is_empty())
+            .collect();
+
+        let mut content_blocks: Vec<UnifiedContentBlock> = parts
             .into_iter()
             .map(convert_gemini_part)
             .collect::<Result<Vec<Vec<_>>, _>>()?
@@ -56,10 +73,28 @@ pub fn from_gemini(
             .flatten()
             .collect();
 
+        if role == UnifiedRole::Assistant {
+            unanswered_calls = content_blocks
+                .iter()
+                .filter_map(|block| match block {
+                    UnifiedContentBlock::ToolUse { id, .. } => Some(id.clone()),
+                    _ => None,
+                })
+                .collect();
+        } else {
+            for block in content_blocks.iter_mut() {
+                if let UnifiedContentBlock::ToolResult { tool_use_id, .. } = block {
+                    if let Some(call_id) = unanswered_calls.pop_front() {
+                        *tool_use_id = call_id;
+                    }
+                }
+            }
+        }
+
         messages.push(UnifiedMessage {
             role,
             content: content_blocks,
-            reasoning_content: None,
+            reasoning_content: (!reasoning.is_empty()).then(|| reasoning.join("\n")),
         });
     }
 
@@ -160,6 +195,9 @@ pub fn from_gemini(
         }
     });
 
+    collapse_duplicate_messages(&mut messages);
+    pair_tool_calls_with_results(&mut messages);
+
     Ok(UnifiedRequest {
         model: "gemini".to_string(), // Model name is often in the URL for Gemini
         messages,
@@ -193,6 +231,11 @@ pub fn from_gemini(
                 },
             ),
         cache_control: None, // Gemini uses cached_content instead
+        modalities: req
+            .generation_config
+            .as_ref()
+            .and_then(|config| config.response_modalities.as_ref())
+            .map(|modalities| modalities.iter().map(|m| m.to_lowercase()).collect()),
         // Gemini-specific parameters
         safety_settings: req.safety_settings.clone(),
         response_mime_type,
@@ -261,6 +304,13 @@ fn convert_gemini_part(part: GeminiPart) -> Result<Vec<UnifiedContentBlock>, any
             is_error: false, // Gemini response doesn't directly indicate error here
         }])
     } else if let Some(inline_data) = part.inline_data {
+        if inline_data.mime_type.starts_with("audio/") {
+            return Ok(vec![UnifiedContentBlock::Audio {
+                media_type: Some(inline_data.mime_type),
+                data: inline_data.data,
+                transcript: None,
+            }]);
+        }
         Ok(vec![UnifiedContentBlock::Image {
             media_type: inline_data.mime_type,
             data: inline_data.data,
@@ -309,7 +359,7 @@ pub fn from_gemini_embedding(
 #[cfg(test)]
 mod tests {
     use super::from_gemini;
-    use crate::ccproxy::adapter::unified::UnifiedToolChoice;
+    use crate::ccproxy::adapter::unified::{UnifiedContentBlock, UnifiedToolChoice};
     use crate::ccproxy::types::gemini::GeminiRequest;
     use serde_json::json;
 
@@ -336,4 +386,34 @@ mod tests {
             })
         );
     }
+
+    #[test]
+    fn thoughts_of_model_turns_become_reasoning_content() {
+        let req: GeminiRequest = serde_json::from_value(json!({
+            "contents": [
+                {"role": "user", "parts": [{"text": "What is Zed?"}]},
+                {"role": "model", "parts": [
+                    {"text": "The user asks about an editor.", "thought": true},
+                    {"text": "Zed is a code editor.", "thoughtSignature": "sig"}
+                ]},
+                {"role": "user", "parts": [{"text": "Who makes it?"}]}
+            ]
+        }))
+        .expect("request should deserialize");
+
+        let unified = from_gemini(req, false, "generateContent".to_string())
+            .expect("conversion should succeed");
+
+        let model_turn = &unified.messages[1];
+        assert_eq!(
+            model_turn.reasoning_content.as_deref(),
+            Some("The user asks about an editor.")
+        );
+        assert_eq!(
+            model_turn.content,
+            vec![UnifiedContentBlock::Text {
+                text: "Zed is a code editor.".to_string()
+            }]
+        );
+    }
 }
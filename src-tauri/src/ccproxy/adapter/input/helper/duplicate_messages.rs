@@ -0,0 +1,91 @@
+use crate::ccproxy::adapter::unified::{UnifiedContentBlock, UnifiedMessage};
+
+/// Whether the message may be dropped as a resent copy of the one before it.
+///
+/// Messages carrying tool calls or results are never collapsed, their ids pair them with
+/// other messages.
+fn is_collapsible(message: &UnifiedMessage) -> bool {
+    !message.content.is_empty()
+        && message.content.iter().all(|block| {
+            !matches!(
+                block,
+                UnifiedContentBlock::ToolUse { .. } | UnifiedContentBlock::ToolResult { .. }
+            )
+        })
+}
+
+/// Collapses exact consecutive duplicates (same role, content and reasoning) into one message.
+///
+/// Clients that retry a request sometimes resend the last message. Repeated content with
+/// another message in between is a real conversation and is left alone.
+pub fn collapse_duplicate_messages(messages: &mut Vec<UnifiedMessage>) {
+    let before = messages.len();
+    messages.dedup_by(|current, previous| {
+        current.role == previous.role
+            && current.content == previous.content
+            && current.reasoning_content == previous.reasoning_content
+            && is_collapsible(current)
+    });
+    if messages.len() < before {
+        log::info!(
+            "collapse_duplicate_messages: collapsed {} duplicate consecutive messages",
+            before - messages.len()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::collapse_duplicate_messages;
+    use crate::ccproxy::adapter::unified::{UnifiedContentBlock, UnifiedMessage, UnifiedRole};
+
+    fn text_message(role: UnifiedRole, text: &str) -> UnifiedMessage {
+        UnifiedMessage {
+            role,
+            content: vec![UnifiedContentBlock::Text {
+                text: text.to_string(),
+            }],
+            reasoning_content: None,
+        }
+    }
+
+    #[test]
+    fn test_identical_consecutive_user_messages_are_collapsed() {
+        let mut messages = vec![
+            text_message(UnifiedRole::User, "Fix the build"),
+            text_message(UnifiedRole::User, "Fix the build"),
+        ];
+        collapse_duplicate_messages(&mut messages);
+        assert_eq!(
+            messages,
+            vec![text_message(UnifiedRole::User, "Fix the build")]
+        );
+    }
+
+    #[test]
+    fn test_repeated_content_that_is_not_a_resend_is_kept() {
+        let mut messages = vec![
+            text_message(UnifiedRole::User, "yes"),
+            text_message(UnifiedRole::Assistant, "Done."),
+            text_message(UnifiedRole::User, "yes"),
+            text_message(UnifiedRole::Assistant, "Done."),
+            text_message(UnifiedRole::User, "Continue"),
+            text_message(UnifiedRole::Assistant, "Continue"),
+        ];
+        collapse_duplicate_messages(&mut messages);
+        assert_eq!(messages.len(), 6);
+
+        let tool_result = UnifiedMessage {
+            role: UnifiedRole::Tool,
+            content: vec![UnifiedContentBlock::ToolResult {
+                tool_use_id: "call_1".to_string(),
+                content: "ok".to_string(),
+                is_error: false,
+            }],
+            reasoning_content: None,
+        };
+        let mut messages = vec![tool_result.clone(), tool_result];
+        collapse_duplicate_messages(&mut messages);
+        assert_eq!(messages.len(), 2);
+    }
+}
@@ -0,0 +1,140 @@
+use serde::{Deserialize, Serialize};
+
+use crate::ccproxy::adapter::unified::{UnifiedContentBlock, UnifiedMessage};
+
+/// Text given to an empty message under `EmptyMessagePolicy::Fill`.
+pub const EMPTY_MESSAGE_PLACEHOLDER: &str = "(empty)";
+
+/// What happens to messages without content, from the group's `emptyMessages` metadata.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EmptyMessagePolicy {
+    /// Removes the message
+    #[default]
+    Drop,
+    /// Gives the message a placeholder text
+    Fill,
+    /// Forwards the message as it is
+    Keep,
+}
+
+fn is_blank_text(block: &UnifiedContentBlock) -> bool {
+    matches!(block, UnifiedContentBlock::Text { text } if text.trim().is_empty())
+}
+
+/// Removes blank text blocks and handles the messages left without content by `policy`.
+///
+/// Providers reject messages whose content is empty or only whitespace. Tool calls, tool
+/// results and other non-text blocks are never removed, so a message carrying one is never
+/// empty.
+pub fn filter_empty_messages(messages: &mut Vec<UnifiedMessage>, policy: EmptyMessagePolicy) {
+    if policy == EmptyMessagePolicy::Keep {
+        return;
+    }
+
+    let before = messages.len();
+    let mut filled = 0;
+    messages.retain_mut(|message| {
+        message.content.retain(|block| !is_blank_text(block));
+        if !message.content.is_empty() {
+            return true;
+        }
+        match policy {
+            EmptyMessagePolicy::Fill => {
+                message.content.push(UnifiedContentBlock::Text {
+                    text: EMPTY_MESSAGE_PLACEHOLDER.to_string(),
+                });
+                filled += 1;
+                true
+            }
+            _ => false,
+        }
+    });
+
+    if messages.len() < before || filled > 0 {
+        log::debug!(
+            "filter_empty_messages: dropped {} and filled {} empty messages",
+            before - messages.len(),
+            filled
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{filter_empty_messages, EmptyMessagePolicy, EMPTY_MESSAGE_PLACEHOLDER};
+    use crate::ccproxy::adapter::unified::{UnifiedContentBlock, UnifiedMessage, UnifiedRole};
+    use serde_json::json;
+
+    fn text_message(role: UnifiedRole, text: &str) -> UnifiedMessage {
+        UnifiedMessage {
+            role,
+            content: vec![UnifiedContentBlock::Text {
+                text: text.to_string(),
+            }],
+            reasoning_content: None,
+        }
+    }
+
+    #[test]
+    fn test_empty_assistant_text_message_is_dropped() {
+        let mut messages = vec![
+            text_message(UnifiedRole::User, "hello"),
+            text_message(UnifiedRole::Assistant, "  \n"),
+            text_message(UnifiedRole::User, "are you there?"),
+        ];
+        filter_empty_messages(&mut messages, EmptyMessagePolicy::Drop);
+        assert_eq!(
+            messages,
+            vec![
+                text_message(UnifiedRole::User, "hello"),
+                text_message(UnifiedRole::User, "are you there?"),
+            ]
+        );
+
+        let mut messages = vec![text_message(UnifiedRole::Assistant, "")];
+        filter_empty_messages(&mut messages, EmptyMessagePolicy::Fill);
+        assert_eq!(
+            messages,
+            vec![text_message(
+                UnifiedRole::Assistant,
+                EMPTY_MESSAGE_PLACEHOLDER
+            )]
+        );
+    }
+
+    #[test]
+    fn test_tool_only_message_is_preserved() {
+        let tool_use = UnifiedContentBlock::ToolUse {
+            id: "call_1".to_string(),
+            name: "read_file".to_string(),
+            input: json!({"path": "a.rs"}),
+        };
+        let tool_result = UnifiedContentBlock::ToolResult {
+            tool_use_id: "call_1".to_string(),
+            content: String::new(),
+            is_error: false,
+        };
+        let mut messages = vec![
+            UnifiedMessage {
+                role: UnifiedRole::Assistant,
+                content: vec![
+                    UnifiedContentBlock::Text {
+                        text: " ".to_string(),
+                    },
+                    tool_use.clone(),
+                ],
+                reasoning_content: None,
+            },
+            UnifiedMessage {
+                role: UnifiedRole::Tool,
+                content: vec![tool_result.clone()],
+                reasoning_content: None,
+            },
+        ];
+        filter_empty_messages(&mut messages, EmptyMessagePolicy::Drop);
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].content, vec![tool_use]);
+        assert_eq!(messages[1].content, vec![tool_result]);
+    }
+}
@@ -1 +1,5 @@
+pub mod duplicate_messages;
+pub mod empty_messages;
+pub mod role_merge;
 pub mod thinking_adapter;
+pub mod tool_pairing;
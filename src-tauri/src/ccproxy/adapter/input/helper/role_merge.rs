@@ -0,0 +1,104 @@
+use crate::ccproxy::adapter::unified::{UnifiedContentBlock, UnifiedMessage, UnifiedRole};
+
+/// Merges consecutive messages of the same role into one, concatenating their content blocks.
+///
+/// Backends such as Claude and Gemini reject a conversation whose roles do not alternate.
+/// Tool calls and results keep their ids, and the results of a merged user message go
+/// first, as Claude expects them at the start of the content. Reasoning of merged assistant
+/// messages is joined with a line break.
+pub fn merge_same_role_messages(messages: &mut Vec<UnifiedMessage>) {
+    let before = messages.len();
+    let mut merged: Vec<UnifiedMessage> = Vec::with_capacity(before);
+
+    for message in messages.drain(..) {
+        let Some(last) = merged.last_mut().filter(|last| last.role == message.role) else {
+            merged.push(message);
+            continue;
+        };
+        last.content.extend(message.content);
+        last.reasoning_content = match (last.reasoning_content.take(), message.reasoning_content) {
+            (Some(first), Some(second)) => Some(format!("{}\n{}", first, second)),
+            (first, second) => first.or(second),
+        };
+    }
+
+    for message in merged.iter_mut() {
+        if message.role == UnifiedRole::User {
+            // Stable, so the results and the other blocks each keep their order
+            message
+                .content
+                .sort_by_key(|block| !matches!(block, UnifiedContentBlock::ToolResult { .. }));
+        }
+    }
+    *messages = merged;
+
+    if messages.len() < before {
+        log::debug!(
+            "merge_same_role_messages: merged {} consecutive same-role messages",
+            before - messages.len()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::merge_same_role_messages;
+    use crate::ccproxy::adapter::unified::{UnifiedContentBlock, UnifiedMessage, UnifiedRole};
+    use serde_json::json;
+
+    fn message(
+        role: UnifiedRole,
+        content: Vec<UnifiedContentBlock>,
+        reasoning: Option<&str>,
+    ) -> UnifiedMessage {
+        UnifiedMessage {
+            role,
+            content,
+            reasoning_content: reasoning.map(str::to_string),
+        }
+    }
+
+    fn text(text: &str) -> UnifiedContentBlock {
+        UnifiedContentBlock::Text {
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_consecutive_messages_are_merged_with_tool_blocks() {
+        let call = UnifiedContentBlock::ToolUse {
+            id: "call_a".to_string(),
+            name: "read_file".to_string(),
+            input: json!({"path": "a.rs"}),
+        };
+        let result = UnifiedContentBlock::ToolResult {
+            tool_use_id: "call_a".to_string(),
+            content: "fn a() {}".to_string(),
+            is_error: false,
+        };
+        let mut messages = vec![
+            message(UnifiedRole::User, vec![text("read a.rs")], None),
+            message(UnifiedRole::Assistant, vec![text("Reading")], Some("plan")),
+            message(UnifiedRole::Assistant, vec![call.clone()], Some("call")),
+            message(UnifiedRole::User, vec![text("and explain it")], None),
+            message(UnifiedRole::User, vec![result.clone()], None),
+        ];
+        merge_same_role_messages(&mut messages);
+        assert_eq!(
+            messages,
+            vec![
+                message(UnifiedRole::User, vec![text("read a.rs")], None),
+                message(
+                    UnifiedRole::Assistant,
+                    vec![text("Reading"), call],
+                    Some("plan\ncall")
+                ),
+                message(
+                    UnifiedRole::User,
+                    vec![result, text("and explain it")],
+                    None
+                ),
+            ]
+        );
+    }
+}
@@ -107,6 +107,16 @@ pub fn merge_reasoning_into_openai_message_content(
     }
 }
 
+/// Earlier reasoning as plain text behind `prefix`, for protocols without a reasoning field in
+/// their history. `None` when there is no reasoning.
+pub fn reasoning_as_text(reasoning: &str, prefix: &str) -> Option<String> {
+    let trimmed_reasoning = reasoning.trim();
+    if trimmed_reasoning.is_empty() {
+        return None;
+    }
+    Some(format!("{}{}", prefix, trimmed_reasoning))
+}
+
 fn normalize_effort(raw: &str) -> Option<String> {
     match raw.trim().to_lowercase().as_str() {
         "none" | "minimal" | "low" | "medium" | "high" | "xhigh" | "max" => {
@@ -0,0 +1,214 @@
+use std::collections::HashSet;
+
+use crate::ccproxy::{
+    adapter::unified::{UnifiedContentBlock, UnifiedMessage, UnifiedRole},
+    types::TOOL_CALL_EMPTY_REMAIN,
+};
+
+/// A message answering tool calls: a `tool` message, or a user message carrying results.
+fn is_tool_response(message: &UnifiedMessage) -> bool {
+    message.role == UnifiedRole::Tool
+        || (message.role == UnifiedRole::User
+            && message
+                .content
+                .iter()
+                .any(|block| matches!(block, UnifiedContentBlock::ToolResult { .. })))
+}
+
+fn missing_result(tool_use_id: String) -> UnifiedContentBlock {
+    UnifiedContentBlock::ToolResult {
+        tool_use_id,
+        content: TOOL_CALL_EMPTY_REMAIN.to_string(),
+        is_error: true,
+    }
+}
+
+/// Makes every tool call answered by a result, and every result answer a known call.
+///
+/// A call without a result in the messages following it gets an error result, placed in
+/// the user message holding the other results or in a `tool` message after them. A result
+/// whose id matches no earlier tool call is dropped, as is a message left empty by that.
+pub fn pair_tool_calls_with_results(messages: &mut Vec<UnifiedMessage>) {
+    let mut known_calls: HashSet<String> = HashSet::new();
+    let mut pending: Vec<String> = Vec::new();
+    let mut synthesized: Vec<String> = Vec::new();
+    let mut orphans: Vec<String> = Vec::new();
+    let mut paired = Vec::with_capacity(messages.len());
+
+    for mut message in messages.drain(..) {
+        let answers_calls = is_tool_response(&message);
+        if !pending.is_empty() && !answers_calls {
+            synthesized.extend(pending.iter().cloned());
+            paired.push(UnifiedMessage {
+                role: UnifiedRole::Tool,
+                content: pending.drain(..).map(missing_result).collect(),
+                reasoning_content: None,
+            });
+        }
+
+        if answers_calls {
+            let had_content = !message.content.is_empty();
+            message.content.retain(|block| match block {
+                UnifiedContentBlock::ToolResult { tool_use_id, .. } => {
+                    if known_calls.contains(tool_use_id) {
+                        pending.retain(|id| id != tool_use_id);
+                        true
+                    } else {
+                        orphans.push(tool_use_id.clone());
+                        false
+                    }
+                }
+                _ => true,
+            });
+            if had_content && message.content.is_empty() {
+                continue;
+            }
+            // Results of a user message travel together, the missing ones join them
+            if message.role == UnifiedRole::User && !pending.is_empty() {
+                let position = message
+                    .content
+                    .iter()
+                    .rposition(|block| matches!(block, UnifiedContentBlock::ToolResult { .. }))
+                    .map_or(0, |last| last + 1);
+                synthesized.extend(pending.iter().cloned());
+                message
+                    .content
+                    .splice(position..position, pending.drain(..).map(missing_result));
+            }
+        }
+
+        if message.role == UnifiedRole::Assistant {
+            for block in &message.content {
+                if let UnifiedContentBlock::ToolUse { id, .. } = block {
+                    known_calls.insert(id.clone());
+                    pending.push(id.clone());
+                }
+            }
+        }
+        paired.push(message);
+    }
+
+    if !pending.is_empty() {
+        synthesized.extend(pending.iter().cloned());
+        paired.push(UnifiedMessage {
+            role: UnifiedRole::Tool,
+            content: pending.drain(..).map(missing_result).collect(),
+            reasoning_content: None,
+        });
+    }
+    *messages = paired;
+
+    if !synthesized.is_empty() {
+        log::warn!(
+            "pair_tool_calls_with_results: added error results for unanswered tool calls {:?}",
+            synthesized
+        );
+    }
+    if !orphans.is_empty() {
+        log::warn!(
+            "pair_tool_calls_with_results: dropped results without a matching tool call {:?}",
+            orphans
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::pair_tool_calls_with_results;
+    use crate::ccproxy::{
+        adapter::unified::{UnifiedContentBlock, UnifiedMessage, UnifiedRole},
+        types::TOOL_CALL_EMPTY_REMAIN,
+    };
+    use serde_json::json;
+
+    fn message(role: UnifiedRole, content: Vec<UnifiedContentBlock>) -> UnifiedMessage {
+        UnifiedMessage {
+            role,
+            content,
+            reasoning_content: None,
+        }
+    }
+
+    fn text(text: &str) -> UnifiedContentBlock {
+        UnifiedContentBlock::Text {
+            text: text.to_string(),
+        }
+    }
+
+    fn call(id: &str) -> UnifiedContentBlock {
+        UnifiedContentBlock::ToolUse {
+            id: id.to_string(),
+            name: "read_file".to_string(),
+            input: json!({}),
+        }
+    }
+
+    fn result(id: &str) -> UnifiedContentBlock {
+        UnifiedContentBlock::ToolResult {
+            tool_use_id: id.to_string(),
+            content: "done".to_string(),
+            is_error: false,
+        }
+    }
+
+    fn missing(id: &str) -> UnifiedContentBlock {
+        UnifiedContentBlock::ToolResult {
+            tool_use_id: id.to_string(),
+            content: TOOL_CALL_EMPTY_REMAIN.to_string(),
+            is_error: true,
+        }
+    }
+
+    #[test]
+    fn test_missing_results_are_synthesized() {
+        // Claude style: the results of a turn share one user message
+        let mut messages = vec![
+            message(UnifiedRole::User, vec![text("read both")]),
+            message(UnifiedRole::Assistant, vec![call("call_a"), call("call_b")]),
+            message(UnifiedRole::User, vec![result("call_a"), text("go on")]),
+        ];
+        pair_tool_calls_with_results(&mut messages);
+        assert_eq!(
+            messages[2].content,
+            vec![result("call_a"), missing("call_b"), text("go on")]
+        );
+
+        // OpenAI style: a turn without any result before the next question
+        let mut messages = vec![
+            message(UnifiedRole::User, vec![text("read it")]),
+            message(UnifiedRole::Assistant, vec![call("call_c")]),
+            message(UnifiedRole::User, vec![text("never mind")]),
+        ];
+        pair_tool_calls_with_results(&mut messages);
+        assert_eq!(messages.len(), 4);
+        assert_eq!(
+            messages[2],
+            message(UnifiedRole::Tool, vec![missing("call_c")])
+        );
+        assert_eq!(messages[3].content, vec![text("never mind")]);
+    }
+
+    #[test]
+    fn test_orphan_results_are_dropped() {
+        let mut messages = vec![
+            message(UnifiedRole::User, vec![text("read it")]),
+            message(UnifiedRole::Assistant, vec![call("call_a")]),
+            message(UnifiedRole::Tool, vec![result("call_a")]),
+            message(UnifiedRole::Tool, vec![result("call_unknown")]),
+            message(
+                UnifiedRole::User,
+                vec![result("call_stale"), text("thanks")],
+            ),
+        ];
+        pair_tool_calls_with_results(&mut messages);
+        assert_eq!(
+            messages,
+            vec![
+                message(UnifiedRole::User, vec![text("read it")]),
+                message(UnifiedRole::Assistant, vec![call("call_a")]),
+                message(UnifiedRole::Tool, vec![result("call_a")]),
+                message(UnifiedRole::User, vec![text("thanks")]),
+            ]
+        );
+    }
+}
@@ -2,11 +2,13 @@ mod claude_input;
 mod gemini_input;
 pub mod helper;
 mod ollama_input;
+mod openai_completions_input;
 mod openai_input;
 mod openai_responses_input;
 
 pub use claude_input::from_claude;
 pub use gemini_input::{from_gemini, from_gemini_embedding};
 pub use ollama_input::{from_ollama, from_ollama_embed, from_ollama_embedding};
+pub use openai_completions_input::from_openai_completions;
 pub use openai_input::{from_openai, from_openai_embedding};
 pub use openai_responses_input::from_openai_responses;
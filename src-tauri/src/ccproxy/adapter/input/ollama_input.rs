@@ -1,5 +1,9 @@
 use crate::ccproxy::{
     adapter::{
+        input::helper::{
+            duplicate_messages::collapse_duplicate_messages,
+            tool_pairing::pair_tool_calls_with_results,
+        },
         range_adapter::clamp_to_protocol_range,
         unified::{
             UnifiedContentBlock, UnifiedEmbeddingInput, UnifiedEmbeddingRequest, UnifiedMessage,
@@ -24,6 +28,8 @@ pub fn from_ollama(
 ) -> Result<UnifiedRequest> {
     let mut messages = Vec::new();
     let mut system_prompt: Option<String> = None;
+    // Ids of the tool calls of the last assistant message, answered in order by `tool` messages
+    let mut unanswered_calls: std::collections::VecDeque<String> = Default::default();
 
     for msg in req.messages {
         let role = match msg.role.as_str() {
@@ -40,16 +46,25 @@ pub fn from_ollama(
 
         let content = if role == UnifiedRole::Tool {
             // Correctly handle the tool role by creating a ToolResult block.
-            // Ollama does not provide a tool_call_id, so we generate a placeholder.
+            // Ollama does not provide a tool_call_id, so the results answer the calls of the
+            // preceding assistant message in order; a result beyond them gets a placeholder.
             // This is crucial for the backend adapter to identify this as a tool result.
             vec![UnifiedContentBlock::ToolResult {
-                tool_use_id: get_tool_id(),
+                tool_use_id: unanswered_calls.pop_front().unwrap_or_else(get_tool_id),
                 content: msg.content.clone(),
                 is_error: false,
             }]
         } else {
             // For user and assistant roles, use the helper function.
-            convert_ollama_message_to_content_blocks(&msg)?
+            let blocks = convert_ollama_message_to_content_blocks(&msg)?;
+            unanswered_calls = blocks
+                .iter()
+                .filter_map(|block| match block {
+                    UnifiedContentBlock::ToolUse { id, .. } => Some(id.clone()),
+                    _ => None,
+                })
+                .collect();
+            blocks
         };
 
         messages.push(UnifiedMessage {
@@ -70,7 +85,10 @@ pub fn from_ollama(
             .collect()
     });
 
-    let options = req.options.unwrap_or_default();
+    let options = req.options.clone().unwrap_or_default();
+
+    collapse_duplicate_messages(&mut messages);
+    pair_tool_calls_with_results(&mut messages);
 
     Ok(UnifiedRequest {
         model: req.model,
@@ -140,14 +158,15 @@ pub fn from_ollama(
         tool_compat_mode,
         keep_alive: req
             .keep_alive
+            .clone()
+            .filter(|ka| !ka.is_null() && ka.as_str() != Some("")),
+        ollama_options: req
+            .options
             .as_ref()
-            .and_then(|ka| ka.as_str())
-            .and_then(|ka| {
-                if ka.is_empty() || ka == "-1" {
-                    None
-                } else {
-                    Some(ka.to_string())
-                }
+            .and_then(|options| serde_json::to_value(options).ok())
+            .and_then(|options| match options {
+                serde_json::Value::Object(options) if !options.is_empty() => Some(options),
+                _ => None,
             }),
         ..Default::default()
     })
@@ -271,7 +290,9 @@ pub fn from_ollama_embed(req: OllamaEmbedRequest) -> Result<UnifiedEmbeddingRequ
 #[cfg(test)]
 mod tests {
     use super::from_ollama;
-    use crate::ccproxy::types::ollama::OllamaChatCompletionRequest;
+    use crate::ccproxy::{
+        adapter::unified::UnifiedContentBlock, types::ollama::OllamaChatCompletionRequest,
+    };
     use serde_json::json;
 
     #[test]
@@ -304,4 +325,44 @@ mod tests {
             Some(true)
         );
     }
+
+    #[test]
+    fn ollama_tool_results_answer_the_preceding_calls_in_order() {
+        let req: OllamaChatCompletionRequest = serde_json::from_value(json!({
+            "model": "qwen3",
+            "messages": [
+                {"role": "user", "content": "weather in Paris and Rome?"},
+                {"role": "assistant", "content": "", "tool_calls": [
+                    {"function": {"name": "weather", "arguments": {"city": "Paris"}}},
+                    {"function": {"name": "weather", "arguments": {"city": "Rome"}}}
+                ]},
+                {"role": "tool", "content": "sunny"}
+            ]
+        }))
+        .expect("request should deserialize");
+
+        let unified = from_ollama(req, false).expect("conversion should succeed");
+        let call_ids: Vec<&str> = unified.messages[1]
+            .content
+            .iter()
+            .filter_map(|block| match block {
+                UnifiedContentBlock::ToolUse { id, .. } => Some(id.as_str()),
+                _ => None,
+            })
+            .collect();
+        let results: Vec<(&str, bool)> = unified.messages[2..]
+            .iter()
+            .flat_map(|message| message.content.iter())
+            .filter_map(|block| match block {
+                UnifiedContentBlock::ToolResult {
+                    tool_use_id,
+                    is_error,
+                    ..
+                } => Some((tool_use_id.as_str(), *is_error)),
+                _ => None,
+            })
+            .collect();
+        // The answered call keeps its result, the unanswered one gets an error result
+        assert_eq!(results, vec![(call_ids[0], false), (call_ids[1], true)]);
+    }
 }
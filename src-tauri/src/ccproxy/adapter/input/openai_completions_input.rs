@@ -0,0 +1,112 @@
+use crate::ccproxy::{
+    adapter::{
+        range_adapter::{clamp_to_protocol_range, Parameter},
+        unified::{UnifiedContentBlock, UnifiedMessage, UnifiedRequest, UnifiedRole},
+    },
+    types::{openai_completions::OpenAICompletionsRequest, ChatProtocol},
+};
+
+/// Converts a legacy OpenAI completion request into a chat request with a single user message.
+pub fn from_openai_completions(
+    req: OpenAICompletionsRequest,
+    tool_compat_mode: bool,
+) -> Result<UnifiedRequest, anyhow::Error> {
+    let prompt = req.prompt_text().map_err(|e| anyhow::anyhow!(e))?;
+
+    // A chat model only continues after the prompt, it cannot fill in text before a suffix.
+    if req.suffix.as_deref().is_some_and(|s| !s.is_empty()) {
+        anyhow::bail!("`suffix` (insertion) is not supported by the completions proxy");
+    }
+    if req.n.unwrap_or(1) > 1 || req.best_of.unwrap_or(1) > 1 {
+        anyhow::bail!(
+            "`n` and `best_of` greater than 1 are not supported by the completions proxy"
+        );
+    }
+    if req.logprobs.is_some_and(|l| l > 0) {
+        anyhow::bail!("`logprobs` is not supported by the completions proxy");
+    }
+
+    Ok(UnifiedRequest {
+        model: req.model,
+        messages: vec![UnifiedMessage {
+            role: UnifiedRole::User,
+            content: vec![UnifiedContentBlock::Text { text: prompt }],
+            reasoning_content: None,
+        }],
+        stream: req.stream.unwrap_or(false),
+        temperature: req.temperature.and_then(|t| {
+            if t < 0.0 {
+                None
+            } else {
+                Some(clamp_to_protocol_range(
+                    t,
+                    ChatProtocol::OpenAI,
+                    Parameter::Temperature,
+                ))
+            }
+        }),
+        max_tokens: req.max_tokens.filter(|t| *t > 0),
+        top_p: req
+            .top_p
+            .map(|p| clamp_to_protocol_range(p, ChatProtocol::OpenAI, Parameter::TopP)),
+        stop_sequences: req
+            .stop
+            .map(|stop| stop.into_vec())
+            .filter(|stop| !stop.is_empty()),
+        presence_penalty: req.presence_penalty,
+        frequency_penalty: req.frequency_penalty,
+        seed: req.seed,
+        user: req.user,
+        tool_compat_mode,
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::from_openai_completions;
+    use crate::ccproxy::adapter::unified::{UnifiedContentBlock, UnifiedRole};
+    use crate::ccproxy::types::openai_completions::OpenAICompletionsRequest;
+    use serde_json::json;
+
+    fn request(value: serde_json::Value) -> OpenAICompletionsRequest {
+        serde_json::from_value(value).expect("request should deserialize")
+    }
+
+    #[test]
+    fn prompt_becomes_single_user_message() {
+        let unified = from_openai_completions(
+            request(json!({
+                "model": "gpt-3.5-turbo-instruct",
+                "prompt": "Say this is a test",
+                "max_tokens": 7,
+                "stop": "\n",
+                "stream": true
+            })),
+            false,
+        )
+        .expect("conversion should succeed");
+
+        assert!(unified.stream);
+        assert_eq!(unified.max_tokens, Some(7));
+        assert_eq!(unified.stop_sequences, Some(vec!["\n".to_string()]));
+        assert_eq!(unified.messages.len(), 1);
+        assert_eq!(unified.messages[0].role, UnifiedRole::User);
+        assert!(matches!(
+            &unified.messages[0].content[0],
+            UnifiedContentBlock::Text { text } if text == "Say this is a test"
+        ));
+    }
+
+    #[test]
+    fn unsupported_parameters_are_rejected() {
+        for body in [
+            json!({ "model": "m", "prompt": "def f(", "suffix": "return x" }),
+            json!({ "model": "m", "prompt": ["a", "b"] }),
+            json!({ "model": "m", "prompt": [1, 2, 3] }),
+            json!({ "model": "m", "prompt": "a", "n": 2 }),
+        ] {
+            assert!(from_openai_completions(request(body), false).is_err());
+        }
+    }
+}
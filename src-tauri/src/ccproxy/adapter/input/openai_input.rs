@@ -1,10 +1,15 @@
 use crate::ccproxy::{
     adapter::{
-        input::helper::thinking_adapter::build_unified_thinking_from_openai_request,
+        input::helper::{
+            duplicate_messages::collapse_duplicate_messages,
+            thinking_adapter::build_unified_thinking_from_openai_request,
+            tool_pairing::pair_tool_calls_with_results,
+        },
         range_adapter::{clamp_to_protocol_range, Parameter},
         unified::{
-            UnifiedContentBlock, UnifiedEmbeddingInput, UnifiedEmbeddingRequest, UnifiedMessage,
-            UnifiedRequest, UnifiedRole, UnifiedTool, UnifiedToolChoice,
+            UnifiedAudioOutput, UnifiedContentBlock, UnifiedEmbeddingInput,
+            UnifiedEmbeddingRequest, UnifiedMessage, UnifiedRequest, UnifiedRole, UnifiedTool,
+            UnifiedToolChoice,
         },
     },
     types::{
@@ -16,6 +21,29 @@ use crate::ccproxy::{
     },
 };
 
+/// Returns the trimmed text of a system or developer message, `None` when it is empty.
+fn instruction_text(
+    role: &str,
+    content: Option<OpenAIMessageContent>,
+) -> Result<Option<String>, anyhow::Error> {
+    let text = match content {
+        None => return Ok(None),
+        Some(OpenAIMessageContent::Text(text)) => text,
+        Some(OpenAIMessageContent::Parts(parts)) => {
+            let mut texts = Vec::with_capacity(parts.len());
+            for part in parts {
+                match part {
+                    OpenAIMessageContentPart::Text { text } => texts.push(text),
+                    _ => anyhow::bail!("Only text content is supported in {} messages", role),
+                }
+            }
+            texts.join("\n")
+        }
+    };
+    let text = text.trim();
+    Ok((!text.is_empty()).then(|| text.to_string()))
+}
+
 /// Converts an OpenAI-compatible chat completion request into the `UnifiedRequest`.
 pub fn from_openai(
     req: OpenAIChatCompletionRequest,
@@ -26,28 +54,22 @@ pub fn from_openai(
         anyhow::bail!("OpenAI request validation failed: {}", e);
     }
     let mut messages = Vec::new();
-    let mut system_prompt = None;
+    // System and developer messages may be split or interleaved with the conversation; they
+    // form the system and developer prompts, in the order they were sent.
+    let mut system_parts = Vec::new();
+    let mut developer_parts = Vec::new();
 
     for msg in req.messages {
         let role = match msg.role.as_deref() {
             Some("system") => {
-                // Extract system prompt text and continue, as it's a top-level field in UnifiedRequest
-                if let Some(OpenAIMessageContent::Text(text)) = msg.content {
-                    system_prompt = Some(format!(
-                        "{}{}\n",
-                        system_prompt.unwrap_or_default(),
-                        text.trim()
-                    ));
+                if let Some(text) = instruction_text("system", msg.content)? {
+                    system_parts.push(text);
                 }
                 continue;
             }
             Some("developer") => {
-                if let Some(OpenAIMessageContent::Text(text)) = msg.content {
-                    system_prompt = Some(format!(
-                        "{}{}\n",
-                        system_prompt.unwrap_or_default(),
-                        text.trim()
-                    ));
+                if let Some(text) = instruction_text("developer", msg.content)? {
+                    developer_parts.push(text);
                 }
                 continue;
             }
@@ -117,10 +139,14 @@ pub fn from_openai(
 
     let tool_choice = req.tool_choice.map(convert_openai_tool_choice);
 
+    collapse_duplicate_messages(&mut messages);
+    pair_tool_calls_with_results(&mut messages);
+
     Ok(UnifiedRequest {
         model: req.model,
         messages,
-        system_prompt: system_prompt.map(|s: String| s.trim_end().to_string()),
+        system_prompt: (!system_parts.is_empty()).then(|| system_parts.join("\n\n")),
+        developer_prompt: (!developer_parts.is_empty()).then(|| developer_parts.join("\n\n")),
         tools,
         tool_choice,
         stream: req.stream.unwrap_or(false),
@@ -160,6 +186,11 @@ pub fn from_openai(
         top_logprobs: req.top_logprobs,
         logit_bias: req.logit_bias.clone(),
         reasoning_effort: req.reasoning_effort.clone(),
+        modalities: req.modalities.clone(),
+        audio: req.audio.as_ref().map(|audio| UnifiedAudioOutput {
+            voice: audio.voice.clone(),
+            format: audio.format.clone(),
+        }),
         // Claude-specific parameters - map OpenAI user to Claude metadata.user_id
         metadata: req.user.clone().map(|user_id| {
             crate::ccproxy::adapter::unified::UnifiedMetadata {
@@ -188,6 +219,7 @@ pub fn from_openai(
             .and_then(|rf| rf.json_schema.clone()),
         cached_content: None,
         tool_compat_mode,
+        extra_body: req.extra,
         ..Default::default()
     })
 }
@@ -227,6 +259,13 @@ fn convert_openai_content(
                                 }
                             }
                         }
+                        OpenAIMessageContentPart::InputAudio { input_audio } => {
+                            blocks.push(UnifiedContentBlock::Audio {
+                                media_type: Some(format!("audio/{}", input_audio.format)),
+                                data: input_audio.data,
+                                transcript: None,
+                            });
+                        }
                     }
                 }
             }
@@ -336,7 +375,7 @@ mod tests {
     use serde_json::json;
 
     #[test]
-    fn developer_messages_are_folded_into_system_prompt() {
+    fn developer_messages_map_to_developer_prompt() {
         let req: OpenAIChatCompletionRequest = serde_json::from_value(json!({
             "model": "gpt-5.2",
             "messages": [
@@ -347,8 +386,79 @@ mod tests {
         .expect("request should deserialize");
 
         let unified = from_openai(req, false).expect("conversion should succeed");
-        assert_eq!(unified.system_prompt.as_deref(), Some("Follow policy A."));
+        assert_eq!(unified.system_prompt, None);
+        assert_eq!(
+            unified.developer_prompt.as_deref(),
+            Some("Follow policy A.")
+        );
         assert_eq!(unified.messages.len(), 1);
         assert_eq!(unified.messages[0].role, UnifiedRole::User);
     }
+
+    #[test]
+    fn multiple_system_messages_are_concatenated_in_order() {
+        let req: OpenAIChatCompletionRequest = serde_json::from_value(json!({
+            "model": "gpt-5.2",
+            "messages": [
+                {"role": "system", "content": "You are a coding assistant."},
+                {"role": "user", "content": "Hello"},
+                {"role": "developer", "content": [
+                    {"type": "text", "text": "Prefer Rust."},
+                    {"type": "text", "text": "Keep answers short."}
+                ]},
+                {"role": "assistant", "content": "Hi"},
+                {"role": "system", "content": "  The user is on Linux.  "},
+                {"role": "system", "content": ""},
+                {"role": "user", "content": "Write a CLI"}
+            ]
+        }))
+        .expect("request should deserialize");
+
+        let unified = from_openai(req, false).expect("conversion should succeed");
+        assert_eq!(
+            unified.system_prompt.as_deref(),
+            Some("You are a coding assistant.\n\nThe user is on Linux.")
+        );
+        assert_eq!(
+            unified.developer_prompt.as_deref(),
+            Some("Prefer Rust.\nKeep answers short.")
+        );
+        let roles: Vec<_> = unified.messages.iter().map(|m| m.role.clone()).collect();
+        assert_eq!(
+            roles,
+            vec![UnifiedRole::User, UnifiedRole::Assistant, UnifiedRole::User]
+        );
+    }
+
+    #[test]
+    fn non_text_system_content_is_rejected() {
+        let req: OpenAIChatCompletionRequest = serde_json::from_value(json!({
+            "model": "gpt-5.2",
+            "messages": [
+                {"role": "system", "content": [
+                    {"type": "image_url", "image_url": {"url": "https://example.com/a.png"}}
+                ]},
+                {"role": "user", "content": "Hello"}
+            ]
+        }))
+        .expect("request should deserialize");
+
+        assert!(from_openai(req, false).is_err());
+    }
+
+    #[test]
+    fn resent_user_message_is_collapsed() {
+        let req: OpenAIChatCompletionRequest = serde_json::from_value(json!({
+            "model": "gpt-5.2",
+            "messages": [
+                {"role": "user", "content": "Summarize the log"},
+                {"role": "user", "content": "Summarize the log"}
+            ]
+        }))
+        .expect("request should deserialize");
+
+        let unified = from_openai(req, false).expect("request should convert");
+
+        assert_eq!(unified.messages.len(), 1);
+    }
 }
@@ -1,5 +1,9 @@
 use crate::ccproxy::{
     adapter::{
+        input::helper::{
+            duplicate_messages::collapse_duplicate_messages,
+            tool_pairing::pair_tool_calls_with_results,
+        },
         range_adapter::{clamp_to_protocol_range, Parameter},
         unified::{
             UnifiedContentBlock, UnifiedMessage, UnifiedRequest, UnifiedRole, UnifiedTool,
@@ -64,6 +68,9 @@ pub fn from_openai_responses(
         (None, None) => None,
     };
 
+    collapse_duplicate_messages(&mut messages);
+    pair_tool_calls_with_results(&mut messages);
+
     if messages.is_empty() {
         messages.push(UnifiedMessage {
             role: UnifiedRole::User,
@@ -5,8 +5,8 @@ use std::sync::{Arc, RwLock};
 use super::traits::OutputAdapter;
 use super::{
     claude_output::ClaudeOutputAdapter, gemini_output::GeminiOutputAdapter,
-    ollama_output::OllamaOutputAdapter, openai_output::OpenAIOutputAdapter,
-    openai_responses_output::OpenAIResponsesOutputAdapter,
+    ollama_output::OllamaOutputAdapter, openai_completions_output::OpenAICompletionsOutputAdapter,
+    openai_output::OpenAIOutputAdapter, openai_responses_output::OpenAIResponsesOutputAdapter,
 };
 use crate::ccproxy::adapter::unified::{
     SseStatus, UnifiedEmbeddingResponse, UnifiedErrorResponse, UnifiedResponse, UnifiedStreamChunk,
@@ -16,6 +16,7 @@ use crate::ccproxy::helper::sse::Event;
 pub enum OutputAdapterEnum {
     OpenAI(OpenAIOutputAdapter),
     OpenAIResponses(OpenAIResponsesOutputAdapter),
+    OpenAICompletions(OpenAICompletionsOutputAdapter),
     Claude(ClaudeOutputAdapter),
     Gemini(GeminiOutputAdapter),
     Ollama(OllamaOutputAdapter),
@@ -24,7 +25,7 @@ pub enum OutputAdapterEnum {
 impl OutputAdapterEnum {
     pub fn adapt_error_response(&self, error: UnifiedErrorResponse) -> Response {
         match self {
-            Self::OpenAI(_) | Self::OpenAIResponses(_) => {
+            Self::OpenAI(_) | Self::OpenAIResponses(_) | Self::OpenAICompletions(_) => {
                 super::error_response::openai_error_response(error)
             }
             Self::Claude(_) => super::error_response::claude_error_response(error),
@@ -48,6 +49,9 @@ impl OutputAdapter for OutputAdapterEnum {
             Self::OpenAIResponses(adapter) => adapter
                 .adapt_response(response, sse_status)?
                 .into_response(),
+            Self::OpenAICompletions(adapter) => adapter
+                .adapt_response(response, sse_status)?
+                .into_response(),
             Self::Claude(adapter) => adapter
                 .adapt_response(response, sse_status)?
                 .into_response(),
@@ -69,6 +73,7 @@ impl OutputAdapter for OutputAdapterEnum {
         match self {
             Self::OpenAI(adapter) => adapter.adapt_stream_chunk(chunk, sse_status),
             Self::OpenAIResponses(adapter) => adapter.adapt_stream_chunk(chunk, sse_status),
+            Self::OpenAICompletions(adapter) => adapter.adapt_stream_chunk(chunk, sse_status),
             Self::Claude(adapter) => adapter.adapt_stream_chunk(chunk, sse_status),
             Self::Gemini(adapter) => adapter.adapt_stream_chunk(chunk, sse_status),
             Self::Ollama(adapter) => adapter.adapt_stream_chunk(chunk, sse_status),
@@ -84,6 +89,9 @@ impl OutputAdapter for OutputAdapterEnum {
             Self::OpenAIResponses(adapter) => {
                 adapter.adapt_embedding_response(response)?.into_response()
             }
+            Self::OpenAICompletions(adapter) => {
+                adapter.adapt_embedding_response(response)?.into_response()
+            }
             Self::Claude(adapter) => adapter.adapt_embedding_response(response)?.into_response(),
             Self::Gemini(adapter) => adapter.adapt_embedding_response(response)?.into_response(),
             Self::Ollama(adapter) => adapter.adapt_embedding_response(response)?.into_response(),
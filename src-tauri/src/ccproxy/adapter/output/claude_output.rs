@@ -1,6 +1,6 @@
 use axum::response::{IntoResponse, Response};
 use axum::Json;
-use serde_json::json;
+use serde_json::{json, Value};
 use std::convert::Infallible;
 use std::sync::{Arc, RwLock};
 
@@ -8,7 +8,7 @@ use super::OutputAdapter;
 use crate::ccproxy::utils::token_estimator::resolve_usage_with_estimate;
 use crate::ccproxy::{
     adapter::unified::{SseStatus, UnifiedEmbeddingResponse, UnifiedResponse, UnifiedStreamChunk},
-    helper::sse::Event,
+    helper::{pdf_text::PLAIN_TEXT_MEDIA_TYPE, sse::Event},
     types::claude::{ClaudeNativeContentBlock, ClaudeNativeResponse, ClaudeNativeUsage},
 };
 
@@ -20,48 +20,81 @@ impl OutputAdapter for ClaudeOutputAdapter {
         response: UnifiedResponse,
         sse_status: Arc<RwLock<SseStatus>>,
     ) -> Result<Response, anyhow::Error> {
-        let content = response
+        let content: Vec<ClaudeNativeContentBlock> = response
             .content
             .into_iter()
-            .map(|c| match c {
+            .filter_map(|c| match c {
                 crate::ccproxy::adapter::unified::UnifiedContentBlock::Text { text } => {
-                    ClaudeNativeContentBlock::Text { text }
+                    Some(ClaudeNativeContentBlock::Text { text })
                 }
                 crate::ccproxy::adapter::unified::UnifiedContentBlock::Thinking { thinking } => {
-                    ClaudeNativeContentBlock::Thinking { thinking }
+                    Some(ClaudeNativeContentBlock::Thinking { thinking })
                 }
                 crate::ccproxy::adapter::unified::UnifiedContentBlock::ToolUse {
                     id,
                     name,
                     input,
-                } => ClaudeNativeContentBlock::ToolUse {
+                } => Some(ClaudeNativeContentBlock::ToolUse {
                     id,
                     name,
                     input,
                     cache_control: None,
-                },
+                }),
                 crate::ccproxy::adapter::unified::UnifiedContentBlock::ToolResult {
                     tool_use_id,
                     content,
                     is_error,
-                } => ClaudeNativeContentBlock::ToolResult {
+                } => Some(ClaudeNativeContentBlock::ToolResult {
                     tool_use_id,
-                    content,
+                    content: content.into(),
                     is_error: Some(is_error),
                     cache_control: None,
-                },
+                }),
                 crate::ccproxy::adapter::unified::UnifiedContentBlock::Image {
                     media_type,
                     data,
-                } => ClaudeNativeContentBlock::Image {
+                } => Some(ClaudeNativeContentBlock::Image {
                     source: crate::ccproxy::types::claude::ClaudeImageSource {
                         source_type: "base64".to_string(),
                         media_type,
                         data,
                     },
-                },
+                }),
+                crate::ccproxy::adapter::unified::UnifiedContentBlock::Document {
+                    media_type,
+                    data,
+                    title,
+                } => Some(ClaudeNativeContentBlock::Document {
+                    source: crate::ccproxy::types::claude::ClaudeDocumentSource {
+                        source_type: if media_type == PLAIN_TEXT_MEDIA_TYPE {
+                            "text".to_string()
+                        } else {
+                            "base64".to_string()
+                        },
+                        media_type: Some(media_type),
+                        data: Some(data),
+                    },
+                    title,
+                    context: None,
+                    citations: None,
+                    cache_control: None,
+                }),
+                // Claude has no audio output, only the transcript is returned
+                crate::ccproxy::adapter::unified::UnifiedContentBlock::Audio {
+                    transcript, ..
+                } => transcript.map(|text| ClaudeNativeContentBlock::Text { text }),
             })
             .collect();
+        let used_tools = content
+            .iter()
+            .any(|block| matches!(block, ClaudeNativeContentBlock::ToolUse { .. }));
+        let (stop_reason, stop_sequence) = resolve_stop(
+            response.stop_reason.as_deref(),
+            response.stop_sequence,
+            used_tools,
+            false,
+        );
+
         let model = if let Ok(status) = sse_status.read() {
             status.model_id.clone()
         } else {
@@ -98,8 +131,8 @@ impl OutputAdapter for ClaudeOutputAdapter {
             role: Some("assistant".to_string()),
             content,
             model: Some(model),
-            stop_reason: response.stop_reason,
-            stop_sequence: None, // Add missing field
+            stop_reason: Some(stop_reason.to_string()),
+            stop_sequence,
             usage: Some(ClaudeNativeUsage {
                 input_tokens,
                 output_tokens,
@@ -171,25 +204,52 @@ impl OutputAdapter for ClaudeOutputAdapter {
                 )])
             }
             UnifiedStreamChunk::ToolUseDelta { id, delta, .. } => {
-                let message_index = if let Ok(status) = sse_status.read() {
-                    status
+                // Clients parse the concatenated `partial_json` of a block when it stops, so
+                // the input is held back until it is a complete JSON object. A stream that
+                // ends before that never sends broken JSON.
+                let (message_index, complete_input) = if let Ok(mut status) = sse_status.write() {
+                    let message_index = status
                         .tool_id_to_index
                         .get(&id)
                         .copied()
-                        .unwrap_or(status.message_index)
+                        .unwrap_or(status.message_index);
+                    let buffer = status
+                        .claude_tool_input_buffers
+                        .entry(message_index)
+                        .or_default();
+                    buffer.push_str(&delta);
+                    let complete =
+                        serde_json::from_str::<Value>(buffer).is_ok_and(|input| input.is_object());
+                    let complete_input = if complete {
+                        status.claude_tool_input_buffers.remove(&message_index)
+                    } else {
+                        None
+                    };
+                    (message_index, complete_input)
                 } else {
-                    0
+                    (0, Some(delta))
                 };
-                Ok(vec![Event::default().event("content_block_delta").data(
-                    json!({
-                        "type": "content_block_delta",
-                        "index": message_index,
-                        "delta": { "type": "input_json_delta", "partial_json": delta }
+
+                Ok(complete_input
+                    .map(|input| {
+                        Event::default().event("content_block_delta").data(
+                            json!({
+                                "type": "content_block_delta",
+                                "index": message_index,
+                                "delta": { "type": "input_json_delta", "partial_json": input }
+                            })
+                            .to_string(),
+                        )
                     })
-                    .to_string(),
-                )])
+                    .into_iter()
+                    .collect())
             }
             UnifiedStreamChunk::ContentBlockStart { index, block } => {
+                if block["type"] == "tool_use" {
+                    if let Ok(mut status) = sse_status.write() {
+                        status.claude_tool_use_started = true;
+                    }
+                }
                 Ok(vec![Event::default().event("content_block_start").data(
                     json!({
                         "type": "content_block_start",
@@ -201,6 +261,9 @@ impl OutputAdapter for ClaudeOutputAdapter {
             }
 
             UnifiedStreamChunk::ContentBlockStop { index } => {
+                if let Ok(mut status) = sse_status.write() {
+                    take_truncated_tool_input(&mut status, Some(index));
+                }
                 Ok(vec![Event::default().event("content_block_stop").data(
                     json!({
                         "type": "content_block_stop",
@@ -211,11 +274,19 @@ impl OutputAdapter for ClaudeOutputAdapter {
             }
 
             UnifiedStreamChunk::MessageStop { stop_reason, usage } => {
-                let message_index = if let Ok(status) = sse_status.read() {
-                    status.message_index
-                } else {
-                    0
-                };
+                let (message_index, (reason, stop_sequence)) =
+                    if let Ok(mut status) = sse_status.write() {
+                        take_truncated_tool_input(&mut status, None);
+                        let stop = resolve_stop(
+                            Some(&stop_reason),
+                            status.stop_sequence.take(),
+                            status.claude_tool_use_started,
+                            status.claude_tool_input_truncated,
+                        );
+                        (status.message_index, stop)
+                    } else {
+                        (0, (claude_stop_reason(&stop_reason), None))
+                    };
 
                 let (estimated_input_tokens_f64, estimated_output_tokens_f64) =
                     if let Ok(status) = sse_status.read() {
@@ -259,11 +330,6 @@ impl OutputAdapter for ClaudeOutputAdapter {
                     }
                 }
 
-                let reason = if stop_reason == "tool_use" || stop_reason == "max_tokens" {
-                    stop_reason
-                } else {
-                    "end_turn".to_string()
-                };
                 Ok(vec![
                     Event::default().event("content_block_stop").data(
                         json!({
@@ -276,7 +342,8 @@ impl OutputAdapter for ClaudeOutputAdapter {
                         json!({
                             "type": "message_delta",
                             "delta": {
-                                "stop_reason": reason
+                                "stop_reason": reason,
+                                "stop_sequence": stop_sequence
                             },
                             "usage": usage_data
                         })
@@ -352,6 +419,72 @@ impl OutputAdapter for ClaudeOutputAdapter {
     }
 }
 
+/// Maps the finish reason of any backend to a Claude `stop_reason`.
+///
+/// Clients decide from it whether to run tools or to continue a cut-off answer, so reasons
+/// without a Claude counterpart end the turn.
+fn claude_stop_reason(finish_reason: &str) -> &'static str {
+    match finish_reason.to_ascii_lowercase().as_str() {
+        // OpenAI `length`, Gemini `MAX_TOKENS`
+        "max_tokens" | "length" => "max_tokens",
+        // OpenAI `tool_calls` and the legacy `function_call`
+        "tool_use" | "tool_calls" | "function_call" => "tool_use",
+        "stop_sequence" => "stop_sequence",
+        "pause_turn" => "pause_turn",
+        // OpenAI `content_filter` and the Gemini safety finishes
+        "refusal" | "content_filter" | "safety" | "recitation" | "blocklist"
+        | "prohibited_content" | "spii" | "image_safety" => "refusal",
+        // `end_turn`, OpenAI and Ollama `stop`, Gemini `STOP` and anything unknown
+        _ => "end_turn",
+    }
+}
+
+/// Resolves the `stop_reason` and `stop_sequence` of a finished Claude message.
+///
+/// Gemini finishes a turn with function calls as `STOP`, so a turn that started a tool block
+/// is reported as `tool_use`. A tool call with cut-off input cannot be run, so that turn is
+/// reported as truncated instead.
+fn resolve_stop(
+    finish_reason: Option<&str>,
+    stop_sequence: Option<String>,
+    used_tools: bool,
+    tool_input_truncated: bool,
+) -> (&'static str, Option<String>) {
+    if tool_input_truncated {
+        return ("max_tokens", None);
+    }
+    match finish_reason.map_or("end_turn", claude_stop_reason) {
+        "end_turn" if used_tools => ("tool_use", None),
+        "end_turn" | "stop_sequence" if stop_sequence.is_some() => ("stop_sequence", stop_sequence),
+        reason => (reason, None),
+    }
+}
+
+/// Drops the held back input of tool blocks that stopped before it was complete JSON,
+/// of the block at `index` or of all blocks, and marks the message as truncated.
+fn take_truncated_tool_input(status: &mut SseStatus, index: Option<u32>) {
+    let partial_inputs: Vec<(u32, String)> = match index {
+        Some(index) => status
+            .claude_tool_input_buffers
+            .remove(&index)
+            .map(|input| (index, input))
+            .into_iter()
+            .collect(),
+        None => status.claude_tool_input_buffers.drain().collect(),
+    };
+    for (index, input) in partial_inputs {
+        if input.trim().is_empty() {
+            continue;
+        }
+        log::warn!(
+            "Tool input of content block {} ended as incomplete JSON, dropping it: {}",
+            index,
+            input
+        );
+        status.claude_tool_input_truncated = true;
+    }
+}
+
 fn gen_message_start_event(id: String, model: String, input_token: f64) -> Event {
     Event::default().event("message_start").data(
         json!({
@@ -373,3 +506,225 @@ fn gen_message_start_event(id: String, model: String, input_token: f64) -> Event
         .to_string(),
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ccproxy::adapter::unified::UnifiedUsage;
+
+    fn status() -> Arc<RwLock<SseStatus>> {
+        let status = SseStatus::new(
+            "msg_test".to_string(),
+            "claude-sonnet-4-5".to_string(),
+            false,
+            10.0,
+        );
+        Arc::new(RwLock::new(status))
+    }
+
+    fn stream(chunks: Vec<UnifiedStreamChunk>, sse_status: &Arc<RwLock<SseStatus>>) -> Vec<Value> {
+        chunks
+            .into_iter()
+            .flat_map(|chunk| {
+                ClaudeOutputAdapter
+                    .adapt_stream_chunk(chunk, sse_status.clone())
+                    .expect("chunk should adapt")
+            })
+            .map(|event| {
+                let text = event.to_string();
+                let data = text
+                    .lines()
+                    .find_map(|line| line.strip_prefix("data: "))
+                    .expect("event should carry data");
+                serde_json::from_str(data).expect("event data should be json")
+            })
+            .collect()
+    }
+
+    fn tool_delta(delta: &str) -> UnifiedStreamChunk {
+        UnifiedStreamChunk::ToolUseDelta {
+            id: "toolu_1".to_string(),
+            delta: delta.to_string(),
+            index: 1,
+        }
+    }
+
+    fn message_stop(stop_reason: &str) -> UnifiedStreamChunk {
+        UnifiedStreamChunk::MessageStop {
+            stop_reason: stop_reason.to_string(),
+            usage: UnifiedUsage::default(),
+        }
+    }
+
+    #[test]
+    fn fragmented_tool_input_is_sent_once_complete() {
+        let sse_status = status();
+        sse_status.write().unwrap().message_index = 1;
+
+        let events = stream(
+            vec![
+                tool_delta(""),
+                tool_delta("{\"loca"),
+                tool_delta("tion\": \"Par"),
+                tool_delta("is\", \"unit\": \"c\"}"),
+                UnifiedStreamChunk::ContentBlockStop { index: 1 },
+                message_stop("tool_use"),
+            ],
+            &sse_status,
+        );
+
+        let deltas: Vec<&Value> = events
+            .iter()
+            .filter(|e| e["type"] == "content_block_delta")
+            .collect();
+        assert_eq!(deltas.len(), 1);
+        assert_eq!(deltas[0]["index"], 1);
+        let input: Value = serde_json::from_str(
+            deltas[0]["delta"]["partial_json"]
+                .as_str()
+                .expect("partial_json should be a string"),
+        )
+        .expect("tool input should be valid json");
+        assert_eq!(input["location"], "Paris");
+
+        let message_delta = events
+            .iter()
+            .find(|e| e["type"] == "message_delta")
+            .expect("message_delta should be sent");
+        assert_eq!(message_delta["delta"]["stop_reason"], "tool_use");
+    }
+
+    #[test]
+    fn truncated_tool_input_is_dropped_and_reported() {
+        let sse_status = status();
+        sse_status.write().unwrap().message_index = 1;
+
+        // The stream ends in the middle of the arguments, without a block stop.
+        let events = stream(
+            vec![
+                tool_delta("{\"path\": \"/tmp/"),
+                tool_delta("notes.t"),
+                message_stop("tool_use"),
+            ],
+            &sse_status,
+        );
+
+        assert!(events.iter().all(|e| e["type"] != "content_block_delta"));
+        let message_delta = events
+            .iter()
+            .find(|e| e["type"] == "message_delta")
+            .expect("message_delta should be sent");
+        assert_eq!(message_delta["delta"]["stop_reason"], "max_tokens");
+        assert!(sse_status
+            .read()
+            .unwrap()
+            .claude_tool_input_buffers
+            .is_empty());
+    }
+
+    #[test]
+    fn backend_finish_reasons_map_to_claude_stop_reasons() {
+        let cases = [
+            // OpenAI, raw and as normalized by the stream adapter
+            ("stop", "end_turn"),
+            ("length", "max_tokens"),
+            ("tool_calls", "tool_use"),
+            ("function_call", "tool_use"),
+            ("content_filter", "refusal"),
+            ("unknown", "end_turn"),
+            // Gemini
+            ("STOP", "end_turn"),
+            ("MAX_TOKENS", "max_tokens"),
+            ("SAFETY", "refusal"),
+            ("RECITATION", "refusal"),
+            ("PROHIBITED_CONTENT", "refusal"),
+            ("FINISH_REASON_UNSPECIFIED", "end_turn"),
+            // Claude
+            ("end_turn", "end_turn"),
+            ("max_tokens", "max_tokens"),
+            ("tool_use", "tool_use"),
+            ("stop_sequence", "stop_sequence"),
+            ("pause_turn", "pause_turn"),
+        ];
+        for (finish_reason, expected) in cases {
+            assert_eq!(
+                claude_stop_reason(finish_reason),
+                expected,
+                "finish reason {finish_reason}"
+            );
+        }
+    }
+
+    #[test]
+    fn stop_resolution_accounts_for_tools_and_stop_sequences() {
+        // Gemini reports STOP after function calls
+        assert_eq!(
+            resolve_stop(Some("STOP"), None, true, false),
+            ("tool_use", None)
+        );
+        assert_eq!(resolve_stop(None, None, false, false), ("end_turn", None));
+        assert_eq!(
+            resolve_stop(Some("stop"), Some("###".to_string()), false, false),
+            ("stop_sequence", Some("###".to_string()))
+        );
+        // The matched sequence only explains a natural stop
+        assert_eq!(
+            resolve_stop(Some("length"), Some("###".to_string()), false, false),
+            ("max_tokens", None)
+        );
+        assert_eq!(
+            resolve_stop(Some("tool_calls"), None, true, true),
+            ("max_tokens", None)
+        );
+    }
+
+    #[tokio::test]
+    async fn response_reports_mapped_stop_reason_and_sequence() {
+        let response = UnifiedResponse {
+            id: "chatcmpl_test".to_string(),
+            model: "gpt-4o".to_string(),
+            content: vec![
+                crate::ccproxy::adapter::unified::UnifiedContentBlock::Text {
+                    text: "1, 2, 3".to_string(),
+                },
+            ],
+            stop_reason: Some("stop".to_string()),
+            stop_sequence: Some("4".to_string()),
+            usage: UnifiedUsage::default(),
+        };
+
+        let response = ClaudeOutputAdapter
+            .adapt_response(response, status())
+            .expect("response should adapt");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .expect("body should be readable");
+        let body: Value = serde_json::from_slice(&body).expect("body should be json");
+        assert_eq!(body["stop_reason"], "stop_sequence");
+        assert_eq!(body["stop_sequence"], "4");
+    }
+
+    #[test]
+    fn gemini_stream_stop_after_tool_call_is_tool_use() {
+        let sse_status = status();
+        let events = stream(
+            vec![
+                UnifiedStreamChunk::ContentBlockStart {
+                    index: 1,
+                    block: json!({ "type": "tool_use", "id": "toolu_1", "name": "ls", "input": {} }),
+                },
+                tool_delta("{}"),
+                UnifiedStreamChunk::ContentBlockStop { index: 1 },
+                message_stop("STOP"),
+            ],
+            &sse_status,
+        );
+
+        let message_delta = events
+            .iter()
+            .find(|e| e["type"] == "message_delta")
+            .expect("message_delta should be sent");
+        assert_eq!(message_delta["delta"]["stop_reason"], "tool_use");
+        assert!(message_delta["delta"]["stop_sequence"].is_null());
+    }
+}
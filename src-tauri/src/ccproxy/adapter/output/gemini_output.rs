@@ -6,8 +6,8 @@ use crate::ccproxy::{
         UnifiedStreamChunk,
     },
     gemini::{
-        GeminiCandidate, GeminiContent, GeminiPart, GeminiResponse as GeminiNetworkResponse,
-        GeminiUsageMetadata,
+        GeminiCandidate, GeminiContent, GeminiInlineData, GeminiPart,
+        GeminiResponse as GeminiNetworkResponse, GeminiUsageMetadata,
     },
     helper::sse::Event,
 };
@@ -37,6 +37,13 @@ impl OutputAdapter for GeminiOutputAdapter {
                         ..Default::default()
                     });
                 }
+                crate::ccproxy::adapter::unified::UnifiedContentBlock::Thinking { thinking } => {
+                    gemini_parts.push(GeminiPart {
+                        text: Some(thinking),
+                        thought: Some(true),
+                        ..Default::default()
+                    });
+                }
                 crate::ccproxy::adapter::unified::UnifiedContentBlock::ToolUse {
                     id: _,
                     name,
@@ -50,6 +57,25 @@ impl OutputAdapter for GeminiOutputAdapter {
                         ..Default::default()
                     });
                 }
+                crate::ccproxy::adapter::unified::UnifiedContentBlock::Audio {
+                    media_type,
+                    data,
+                    transcript,
+                } => match media_type {
+                    Some(mime_type) => gemini_parts.push(GeminiPart {
+                        inline_data: Some(GeminiInlineData { mime_type, data }),
+                        ..Default::default()
+                    }),
+                    // The encoding of the audio is unknown, only the transcript can be returned
+                    None => {
+                        if let Some(text) = transcript {
+                            gemini_parts.push(GeminiPart {
+                                text: Some(text),
+                                ..Default::default()
+                            });
+                        }
+                    }
+                },
                 _ => {
                     // Gemini doesn't have direct equivalents for Image/ToolResult in assistant responses
                     // For simplicity, we might convert them to text or ignore.
@@ -166,7 +192,7 @@ impl OutputAdapter for GeminiOutputAdapter {
                             "role": "model",
                             "parts": [{
                                 "text": delta,
-                                "thought": serde_json::Value::Object(Default::default())
+                                "thought": true
                             }]
                         }
                     }]
@@ -295,3 +321,63 @@ impl OutputAdapter for GeminiOutputAdapter {
         Ok(Json(gemini_response).into_response())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::GeminiOutputAdapter;
+    use crate::ccproxy::adapter::output::OutputAdapter;
+    use crate::ccproxy::adapter::unified::{
+        SseStatus, UnifiedContentBlock, UnifiedResponse, UnifiedStreamChunk, UnifiedUsage,
+    };
+    use axum::body::to_bytes;
+    use serde_json::{json, Value};
+    use std::sync::{Arc, RwLock};
+
+    #[tokio::test]
+    async fn reasoning_is_returned_as_thought_parts() {
+        let status = Arc::new(RwLock::new(SseStatus::default()));
+        let response = UnifiedResponse {
+            id: "msg_1".to_string(),
+            model: "gemini-2.5-pro".to_string(),
+            content: vec![
+                UnifiedContentBlock::Thinking {
+                    thinking: "The user asks about an editor.".to_string(),
+                },
+                UnifiedContentBlock::Text {
+                    text: "Zed is a code editor.".to_string(),
+                },
+            ],
+            stop_reason: None,
+            stop_sequence: None,
+            usage: UnifiedUsage::default(),
+        };
+
+        let response = GeminiOutputAdapter
+            .adapt_response(response, status.clone())
+            .expect("response should adapt");
+        let body = to_bytes(response.into_body(), usize::MAX)
+            .await
+            .expect("body should be readable");
+        let body: Value = serde_json::from_slice(&body).expect("body should be json");
+        assert_eq!(
+            body["candidates"][0]["content"]["parts"],
+            json!([
+                {"text": "The user asks about an editor.", "thought": true},
+                {"text": "Zed is a code editor."}
+            ])
+        );
+
+        let events = GeminiOutputAdapter
+            .adapt_stream_chunk(
+                UnifiedStreamChunk::Thinking {
+                    delta: "Checking".to_string(),
+                },
+                status,
+            )
+            .expect("chunk should adapt");
+        assert_eq!(events.len(), 1);
+        assert!(events[0]
+            .to_string()
+            .contains(r#""parts":[{"text":"Checking","thought":true}]"#));
+    }
+}
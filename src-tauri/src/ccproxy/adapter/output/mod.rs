@@ -3,6 +3,7 @@ mod claude_output;
 mod error_response;
 mod gemini_output;
 mod ollama_output;
+mod openai_completions_output;
 mod openai_output;
 mod openai_responses_output;
 pub mod traits;
@@ -11,6 +12,7 @@ pub use adapter_enum::OutputAdapterEnum;
 pub use claude_output::ClaudeOutputAdapter;
 pub use gemini_output::GeminiOutputAdapter;
 pub use ollama_output::OllamaOutputAdapter;
+pub use openai_completions_output::OpenAICompletionsOutputAdapter;
 pub use openai_output::OpenAIOutputAdapter;
 pub use openai_responses_output::OpenAIResponsesOutputAdapter;
 pub use traits::OutputAdapter;
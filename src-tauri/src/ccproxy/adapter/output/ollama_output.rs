@@ -94,6 +94,7 @@ impl OutputAdapter for OllamaOutputAdapter {
             model: response.model,
             created_at: chrono::Utc::now().to_rfc3339(),
             message,
+            response: None,
             done: true,
             total_duration: response.usage.total_duration,
             load_duration: response.usage.load_duration,
@@ -253,6 +254,7 @@ impl OutputAdapter for OllamaOutputAdapter {
                         content: "".to_string(),
                         ..Default::default()
                     },
+                    response: None,
                     done: true,
                     total_duration: Some(usage.total_duration.unwrap_or(0)),
                     load_duration: Some(usage.load_duration.unwrap_or(0)),
@@ -0,0 +1,340 @@
+use super::OutputAdapter;
+use crate::ccproxy::adapter::unified::{
+    SseStatus, UnifiedContentBlock, UnifiedEmbeddingResponse, UnifiedResponse, UnifiedStreamChunk,
+};
+use crate::ccproxy::helper::get_msg_id;
+use crate::ccproxy::helper::sse::Event;
+use crate::ccproxy::utils::token_estimator::resolve_usage_with_estimate;
+
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde_json::{json, Value};
+use std::convert::Infallible;
+use std::sync::{Arc, RwLock};
+
+/// Renders chat output in the legacy `text_completion` shape of `/v1/completions`.
+pub struct OpenAICompletionsOutputAdapter {
+    /// The prompt to put in front of the completion when the client asked for `echo`
+    pub echo_prompt: Option<String>,
+}
+
+/// Legacy clients only know `stop` and `length`.
+fn completion_finish_reason(stop_reason: &str) -> &str {
+    match stop_reason {
+        "max_tokens" | "length" | "MAX_TOKENS" => "length",
+        "content_filter" => "content_filter",
+        _ => "stop",
+    }
+}
+
+fn stream_ids(sse_status: &Arc<RwLock<SseStatus>>) -> (String, String) {
+    if let Ok(status) = sse_status.read() {
+        (status.message_id.clone(), status.model_id.clone())
+    } else {
+        (get_msg_id(), String::new())
+    }
+}
+
+fn completion_chunk(
+    sse_status: &Arc<RwLock<SseStatus>>,
+    text: &str,
+    finish_reason: Option<&str>,
+) -> Value {
+    let (message_id, model) = stream_ids(sse_status);
+    json!({
+        "id": message_id,
+        "object": "text_completion",
+        "created": chrono::Utc::now().timestamp(),
+        "model": model,
+        "choices": [{
+            "text": text,
+            "index": 0,
+            "logprobs": null,
+            "finish_reason": finish_reason
+        }]
+    })
+}
+
+impl OutputAdapter for OpenAICompletionsOutputAdapter {
+    fn adapt_response(
+        &self,
+        response: UnifiedResponse,
+        sse_status: Arc<RwLock<SseStatus>>,
+    ) -> Result<Response, anyhow::Error> {
+        let mut text = self.echo_prompt.clone().unwrap_or_default();
+        for block in response.content {
+            if let UnifiedContentBlock::Text { text: delta } = block {
+                text.push_str(&delta);
+            }
+        }
+
+        let (response_id, model, estimated_input_tokens, estimated_output_tokens) =
+            if let Ok(status) = sse_status.read() {
+                (
+                    status.message_id.clone(),
+                    status.model_id.clone(),
+                    status.estimated_input_tokens,
+                    status.estimated_output_tokens,
+                )
+            } else {
+                (response.id.clone(), response.model.clone(), 0.0, 0.0)
+            };
+
+        let (input_tokens, output_tokens) = resolve_usage_with_estimate(
+            "openai",
+            response.usage.input_tokens,
+            response.usage.output_tokens,
+            estimated_input_tokens,
+            estimated_output_tokens,
+            "response",
+        );
+
+        let created = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_err(|e| anyhow::anyhow!("Failed to get system time: {}", e))?
+            .as_secs();
+
+        let body = json!({
+            "id": response_id,
+            "object": "text_completion",
+            "created": created,
+            "model": model,
+            "choices": [{
+                "text": text,
+                "index": 0,
+                "logprobs": null,
+                "finish_reason": completion_finish_reason(
+                    response.stop_reason.as_deref().unwrap_or("stop")
+                )
+            }],
+            "usage": {
+                "prompt_tokens": input_tokens,
+                "completion_tokens": output_tokens,
+                "total_tokens": input_tokens + output_tokens
+            }
+        });
+
+        Ok(Json(body).into_response())
+    }
+
+    fn adapt_stream_chunk(
+        &self,
+        chunk: UnifiedStreamChunk,
+        sse_status: Arc<RwLock<SseStatus>>,
+    ) -> Result<Vec<Event>, Infallible> {
+        match chunk {
+            UnifiedStreamChunk::MessageStart { .. } => {
+                match &self.echo_prompt {
+                    Some(prompt) if !prompt.is_empty() => Ok(vec![Event::default()
+                        .data(completion_chunk(&sse_status, prompt, None).to_string())]),
+                    _ => Ok(vec![]),
+                }
+            }
+            UnifiedStreamChunk::Text { delta } => {
+                Ok(vec![Event::default().data(
+                    completion_chunk(&sse_status, &delta, None).to_string(),
+                )])
+            }
+            UnifiedStreamChunk::MessageStop { stop_reason, usage } => {
+                let (estimated_input_tokens, estimated_output_tokens) =
+                    if let Ok(status) = sse_status.read() {
+                        (
+                            status.estimated_input_tokens,
+                            status.estimated_output_tokens,
+                        )
+                    } else {
+                        (0.0, 0.0)
+                    };
+                let (input_tokens, output_tokens) = resolve_usage_with_estimate(
+                    "openai",
+                    usage.input_tokens,
+                    usage.output_tokens,
+                    estimated_input_tokens,
+                    estimated_output_tokens,
+                    "stream_stop",
+                );
+
+                let mut data = completion_chunk(
+                    &sse_status,
+                    "",
+                    Some(completion_finish_reason(&stop_reason)),
+                );
+                data["usage"] = json!({
+                    "prompt_tokens": input_tokens,
+                    "completion_tokens": output_tokens,
+                    "total_tokens": input_tokens + output_tokens
+                });
+                Ok(vec![
+                    Event::default().data(data.to_string()),
+                    Event::default().data("[DONE]"),
+                ])
+            }
+            UnifiedStreamChunk::Error { message } => {
+                let data = json!({ "error": { "message": message } });
+                Ok(vec![Event::default().data(data.to_string())])
+            }
+            // Reasoning and tool calls have no place in a plain text completion.
+            _ => Ok(vec![]),
+        }
+    }
+
+    fn adapt_embedding_response(
+        &self,
+        _response: UnifiedEmbeddingResponse,
+    ) -> Result<Response, anyhow::Error> {
+        Err(anyhow::anyhow!(
+            "OpenAI completions output adapter does not support embeddings"
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ccproxy::adapter::unified::UnifiedUsage;
+    use axum::body;
+
+    fn status() -> Arc<RwLock<SseStatus>> {
+        Arc::new(RwLock::new(SseStatus::new(
+            "cmpl_test".to_string(),
+            "gpt-3.5-turbo-instruct".to_string(),
+            false,
+            5.0,
+        )))
+    }
+
+    fn event_json(event: &Event) -> Value {
+        let line = event.to_string();
+        let data = line
+            .trim()
+            .strip_prefix("data: ")
+            .expect("event should carry data");
+        serde_json::from_str(data).expect("event data should be json")
+    }
+
+    #[tokio::test]
+    async fn response_is_a_text_completion_with_echoed_prompt() {
+        let adapter = OpenAICompletionsOutputAdapter {
+            echo_prompt: Some("Say this is a test".to_string()),
+        };
+        let response = UnifiedResponse {
+            id: "chatcmpl_test".to_string(),
+            model: "gpt-4o-mini".to_string(),
+            content: vec![
+                UnifiedContentBlock::Thinking {
+                    thinking: "hidden".to_string(),
+                },
+                UnifiedContentBlock::Text {
+                    text: "\n\nThis is a test.".to_string(),
+                },
+            ],
+            stop_reason: Some("max_tokens".to_string()),
+            stop_sequence: None,
+            usage: UnifiedUsage {
+                input_tokens: 5,
+                output_tokens: 7,
+                ..Default::default()
+            },
+        };
+
+        let http_response = adapter
+            .adapt_response(response, status())
+            .expect("response should adapt");
+        let body = body::to_bytes(http_response.into_body(), usize::MAX)
+            .await
+            .expect("body should read");
+        let payload: Value = serde_json::from_slice(&body).expect("body should be json");
+
+        assert_eq!(payload["object"], "text_completion");
+        assert_eq!(payload["id"], "cmpl_test");
+        assert_eq!(payload["model"], "gpt-3.5-turbo-instruct");
+        assert_eq!(
+            payload["choices"][0]["text"],
+            "Say this is a test\n\nThis is a test."
+        );
+        assert_eq!(payload["choices"][0]["finish_reason"], "length");
+        assert!(payload["choices"][0]["logprobs"].is_null());
+        assert_eq!(payload["usage"]["total_tokens"], 12);
+    }
+
+    #[test]
+    fn stream_emits_text_completion_chunks_and_done() {
+        let adapter = OpenAICompletionsOutputAdapter { echo_prompt: None };
+        let status = status();
+
+        let start = adapter
+            .adapt_stream_chunk(
+                UnifiedStreamChunk::MessageStart {
+                    id: "chatcmpl_test".to_string(),
+                    model: "gpt-4o-mini".to_string(),
+                    usage: UnifiedUsage::default(),
+                },
+                status.clone(),
+            )
+            .expect("start should adapt");
+        assert!(start.is_empty());
+
+        let thinking = adapter
+            .adapt_stream_chunk(
+                UnifiedStreamChunk::Thinking {
+                    delta: "hidden".to_string(),
+                },
+                status.clone(),
+            )
+            .expect("thinking should adapt");
+        assert!(thinking.is_empty());
+
+        let text = adapter
+            .adapt_stream_chunk(
+                UnifiedStreamChunk::Text {
+                    delta: "This is".to_string(),
+                },
+                status.clone(),
+            )
+            .expect("text should adapt");
+        let chunk = event_json(&text[0]);
+        assert_eq!(chunk["object"], "text_completion");
+        assert_eq!(chunk["choices"][0]["text"], "This is");
+        assert!(chunk["choices"][0]["finish_reason"].is_null());
+
+        let stop = adapter
+            .adapt_stream_chunk(
+                UnifiedStreamChunk::MessageStop {
+                    stop_reason: "end_turn".to_string(),
+                    usage: UnifiedUsage {
+                        input_tokens: 5,
+                        output_tokens: 2,
+                        ..Default::default()
+                    },
+                },
+                status,
+            )
+            .expect("stop should adapt");
+        assert_eq!(stop.len(), 2);
+        let last = event_json(&stop[0]);
+        assert_eq!(last["choices"][0]["text"], "");
+        assert_eq!(last["choices"][0]["finish_reason"], "stop");
+        assert_eq!(last["usage"]["completion_tokens"], 2);
+        assert_eq!(stop[1].to_string(), "data: [DONE]\n\n");
+    }
+
+    #[test]
+    fn stream_echoes_prompt_before_completion() {
+        let adapter = OpenAICompletionsOutputAdapter {
+            echo_prompt: Some("Once upon".to_string()),
+        };
+        let start = adapter
+            .adapt_stream_chunk(
+                UnifiedStreamChunk::MessageStart {
+                    id: "chatcmpl_test".to_string(),
+                    model: "gpt-4o-mini".to_string(),
+                    usage: UnifiedUsage::default(),
+                },
+                status(),
+            )
+            .expect("start should adapt");
+
+        assert_eq!(start.len(), 1);
+        assert_eq!(event_json(&start[0])["choices"][0]["text"], "Once upon");
+    }
+}
@@ -6,8 +6,8 @@ use crate::ccproxy::helper::get_msg_id;
 use crate::ccproxy::helper::sse::Event;
 use crate::ccproxy::types::openai::{
     CompletionTokensDetails, OpenAIChatCompletionChoice, OpenAIChatCompletionResponse,
-    OpenAIEmbeddingData, OpenAIEmbeddingResponse, OpenAIMessageContent, OpenAIUsage,
-    PromptTokensDetails, UnifiedChatMessage,
+    OpenAIEmbeddingData, OpenAIEmbeddingResponse, OpenAIMessageAudio, OpenAIMessageContent,
+    OpenAIUsage, PromptTokensDetails, UnifiedChatMessage,
 };
 use crate::ccproxy::utils::token_estimator::resolve_usage_with_estimate;
 
@@ -19,6 +19,24 @@ use std::sync::{Arc, RwLock};
 
 pub struct OpenAIOutputAdapter;
 
+/// Maps the finish reason of any backend to an OpenAI `finish_reason`.
+///
+/// Claude and Gemini report their own vocabulary, and OpenAI clients only continue their
+/// tool loop on `tool_calls`, so reasons without an OpenAI counterpart become `stop`.
+fn openai_finish_reason(stop_reason: &str) -> &'static str {
+    match stop_reason.to_ascii_lowercase().as_str() {
+        // Claude `max_tokens`, Gemini `MAX_TOKENS`
+        "max_tokens" | "length" => "length",
+        // Claude `tool_use`
+        "tool_use" | "tool_calls" | "function_call" => "tool_calls",
+        // Claude `refusal` and the Gemini safety finishes
+        "content_filter" | "refusal" | "safety" | "recitation" | "blocklist"
+        | "prohibited_content" | "spii" | "image_safety" => "content_filter",
+        // Claude `end_turn` and `stop_sequence`, Gemini `STOP` and anything unknown
+        _ => "stop",
+    }
+}
+
 impl OutputAdapter for OpenAIOutputAdapter {
     fn adapt_response(
         &self,
@@ -28,6 +46,7 @@ impl OutputAdapter for OpenAIOutputAdapter {
         let mut text_content = String::new();
         let mut reasoning_content: Option<String> = None;
         let mut tool_calls: Vec<crate::ccproxy::types::openai::UnifiedToolCall> = Vec::new();
+        let mut audio: Option<OpenAIMessageAudio> = None;
 
         for c in response.content {
             match c {
@@ -59,6 +78,17 @@ impl OutputAdapter for OpenAIOutputAdapter {
                         index: None,
                     });
                 }
+                crate::ccproxy::adapter::unified::UnifiedContentBlock::Audio {
+                    data,
+                    transcript,
+                    ..
+                } => {
+                    audio = Some(OpenAIMessageAudio {
+                        data: Some(data),
+                        transcript,
+                        ..Default::default()
+                    });
+                }
                 _ => {}
             }
         }
@@ -98,6 +128,9 @@ impl OutputAdapter for OpenAIOutputAdapter {
             .map_err(|e| anyhow::anyhow!("Failed to get system time: {}", e))?
             .as_secs();
 
+        // Gemini finishes a turn with function calls as `STOP`, clients only run the tools
+        // of a `tool_calls` finish.
+        let has_tool_calls = !tool_calls.is_empty();
         let choice = OpenAIChatCompletionChoice {
             index: 0,
             message: UnifiedChatMessage {
@@ -109,9 +142,18 @@ impl OutputAdapter for OpenAIOutputAdapter {
                     Some(tool_calls)
                 },
                 reasoning_content,
+                audio,
                 ..Default::default()
             },
-            finish_reason: response.stop_reason,
+            finish_reason: Some(
+                if has_tool_calls {
+                    "tool_calls"
+                } else {
+                    openai_finish_reason(response.stop_reason.as_deref().unwrap_or("stop"))
+                }
+                .to_string(),
+            ),
+            stop_reason: None,
             logprobs: None, // Add missing logprobs field
         };
 
@@ -407,7 +449,7 @@ impl OutputAdapter for OpenAIOutputAdapter {
                         "finish_reason": if has_tool {
                             "tool_calls"
                         } else {
-                            &stop_reason
+                            openai_finish_reason(&stop_reason)
                         }
                     }],
                     "usage": usage_json
@@ -453,3 +495,99 @@ impl OutputAdapter for OpenAIOutputAdapter {
         Ok(Json(openai_response).into_response())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ccproxy::adapter::unified::{UnifiedContentBlock, UnifiedUsage};
+    use serde_json::Value;
+
+    fn status() -> Arc<RwLock<SseStatus>> {
+        Arc::new(RwLock::new(SseStatus::new(
+            "chatcmpl_test".to_string(),
+            "gpt-4o".to_string(),
+            false,
+            10.0,
+        )))
+    }
+
+    #[test]
+    fn backend_stop_reasons_map_to_openai_finish_reasons() {
+        let cases = [
+            // Claude
+            ("end_turn", "stop"),
+            ("stop_sequence", "stop"),
+            ("max_tokens", "length"),
+            ("tool_use", "tool_calls"),
+            ("refusal", "content_filter"),
+            ("pause_turn", "stop"),
+            // Gemini
+            ("STOP", "stop"),
+            ("MAX_TOKENS", "length"),
+            ("SAFETY", "content_filter"),
+            ("RECITATION", "content_filter"),
+            ("BLOCKLIST", "content_filter"),
+            ("FINISH_REASON_UNSPECIFIED", "stop"),
+            // OpenAI, raw and as normalized by the stream adapters
+            ("stop", "stop"),
+            ("length", "length"),
+            ("tool_calls", "tool_calls"),
+            ("content_filter", "content_filter"),
+            ("unknown", "stop"),
+        ];
+        for (stop_reason, expected) in cases {
+            assert_eq!(
+                openai_finish_reason(stop_reason),
+                expected,
+                "stop reason {stop_reason}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn gemini_stop_with_function_call_finishes_as_tool_calls() {
+        let response = UnifiedResponse {
+            id: "resp_test".to_string(),
+            model: "gemini".to_string(),
+            content: vec![UnifiedContentBlock::ToolUse {
+                id: "call_1".to_string(),
+                name: "get_weather".to_string(),
+                input: json!({ "location": "Paris" }),
+            }],
+            stop_reason: Some("STOP".to_string()),
+            stop_sequence: None,
+            usage: UnifiedUsage::default(),
+        };
+
+        let response = OpenAIOutputAdapter
+            .adapt_response(response, status())
+            .expect("response should adapt");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .expect("body should be readable");
+        let body: Value = serde_json::from_slice(&body).expect("body should be json");
+        assert_eq!(body["choices"][0]["finish_reason"], "tool_calls");
+    }
+
+    #[test]
+    fn claude_stream_stop_reasons_are_mapped() {
+        for (stop_reason, expected) in [("end_turn", "stop"), ("max_tokens", "length")] {
+            let events = OpenAIOutputAdapter
+                .adapt_stream_chunk(
+                    UnifiedStreamChunk::MessageStop {
+                        stop_reason: stop_reason.to_string(),
+                        usage: UnifiedUsage::default(),
+                    },
+                    status(),
+                )
+                .expect("chunk should adapt");
+            let text = events[0].to_string();
+            let data = text
+                .lines()
+                .find_map(|line| line.strip_prefix("data: "))
+                .expect("event should carry data");
+            let chunk: Value = serde_json::from_str(data).expect("event data should be json");
+            assert_eq!(chunk["choices"][0]["finish_reason"], expected);
+        }
+    }
+}
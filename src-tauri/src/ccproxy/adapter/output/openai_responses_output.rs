@@ -920,6 +920,7 @@ mod tests {
                 },
             ],
             stop_reason: Some("stop".to_string()),
+            stop_sequence: None,
             usage: UnifiedUsage {
                 input_tokens: 10,
                 output_tokens: 3,
@@ -980,6 +981,7 @@ mod tests {
                 input: json!({ "input": "const value = await run();" }),
             }],
             stop_reason: Some("tool_calls".to_string()),
+            stop_sequence: None,
             usage: UnifiedUsage::default(),
         };
         let mut status = SseStatus::default();
@@ -3,7 +3,10 @@ use std::collections::{BTreeMap, HashMap, HashSet};
 use serde::{Deserialize, Serialize, Serializer};
 use serde_json::Value;
 
-use crate::ccproxy::{adapter::backend::generate_tool_prompt, gemini::SafetySetting};
+use crate::ccproxy::{
+    adapter::backend::generate_tool_prompt, gemini::SafetySetting,
+    helper::tool_use_xml::CompatToolPromptConfig,
+};
 
 // ===================================
 // Unified Request Structures
@@ -17,6 +20,9 @@ pub struct UnifiedRequest {
     pub messages: Vec<UnifiedMessage>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub system_prompt: Option<String>,
+    /// Instructions of OpenAI `developer` messages, which take priority over the system prompt
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub developer_prompt: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tools: Option<Vec<UnifiedTool>>,
     #[serde(default, skip_serializing_if = "HashSet::is_empty")]
@@ -59,6 +65,10 @@ pub struct UnifiedRequest {
     pub logit_bias: Option<HashMap<String, f32>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub reasoning_effort: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub modalities: Option<Vec<String>>, // Output modalities, e.g. ["text", "audio"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audio: Option<UnifiedAudioOutput>, // Voice and encoding of generated audio
 
     // Claude-specific parameters
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -79,7 +89,17 @@ pub struct UnifiedRequest {
     pub cached_content: Option<String>, // Context cache content name
 
     // Ollama
-    pub keep_alive: Option<String>,
+    /// How long the model stays loaded: a duration such as `"10m"` or seconds, `-1` forever
+    pub keep_alive: Option<Value>,
+    /// `options` of an Ollama client request, sent for the keys the request leaves unset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ollama_options: Option<serde_json::Map<String, Value>>,
+    /// Chat template rendering the messages into one prompt for the raw generate endpoint
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prompt_template: Option<String>,
+    /// Stop sequences of the backend model's config, sent with the prompt template
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub default_stop_sequences: Vec<String>,
 
     // For tool compatibility mode
     pub tool_compat_mode: bool,
@@ -88,6 +108,47 @@ pub struct UnifiedRequest {
     pub combined_prompt: Option<String>,
     pub prompt_injection_position: Option<String>,
     pub custom_params: Option<Value>,
+    /// Client body fields without a unified counterpart, forwarded verbatim when the
+    /// proxy group allows their keys
+    #[serde(default, skip_serializing_if = "serde_json::Map::is_empty")]
+    pub extra_body: serde_json::Map<String, Value>,
+    /// Server specific adjustments of the OpenAI backend, from the proxy group
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub openai_quirks: Option<OpenAICompatQuirks>,
+    /// Turns off the explicit placeholders for unanswered tool calls and the hint against
+    /// repeated identical calls of the OpenAI backend
+    #[serde(default)]
+    pub skip_tool_loop_guard: bool,
+    /// Marker before earlier reasoning a backend without reasoning history gets as text
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reasoning_text_prefix: Option<String>,
+    /// How the compat mode system prompt describes the tools
+    #[serde(default)]
+    pub compat_tool_prompt: CompatToolPromptConfig,
+}
+
+/// OpenAI-compatible inference servers whose request quirks the OpenAI backend handles.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum OpenAICompatQuirks {
+    Vllm,
+    Sglang,
+}
+
+impl OpenAICompatQuirks {
+    /// Client body keys the server understands, forwarded without a group allowlist entry.
+    pub fn passthrough_keys(self) -> &'static [&'static str] {
+        match self {
+            Self::Vllm => &[
+                "chat_template_kwargs",
+                "guided_json",
+                "guided_regex",
+                "guided_choice",
+                "guided_grammar",
+            ],
+            Self::Sglang => &["chat_template_kwargs", "regex", "ebnf"],
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -130,6 +191,20 @@ pub struct UnifiedEmbeddingData {
 }
 
 impl UnifiedRequest {
+    /// Appends the developer instructions to the system prompt, for backends without a
+    /// `developer` role. Placed last, they read as the most specific instructions.
+    pub fn fold_developer_prompt(&mut self) {
+        let Some(developer_prompt) = self.developer_prompt.take() else {
+            return;
+        };
+        self.system_prompt = Some(match self.system_prompt.take() {
+            Some(system_prompt) if !system_prompt.is_empty() => {
+                format!("{}\n\n{}", system_prompt, developer_prompt)
+            }
+            _ => developer_prompt,
+        });
+    }
+
     pub fn enhance_prompt(&mut self) {
         // We only inject prompts when tools are present.
         if self.tools.as_deref().unwrap_or_default().is_empty() {
@@ -139,7 +214,16 @@ impl UnifiedRequest {
         // Generate tool prompt if in compatibility mode.
         let tool_prompt = if self.tool_compat_mode {
             if let Some(tools) = &self.tools {
-                let prompt = generate_tool_prompt(tools);
+                let called_tools: HashSet<String> = self
+                    .messages
+                    .iter()
+                    .flat_map(|message| &message.content)
+                    .filter_map(|block| match block {
+                        UnifiedContentBlock::ToolUse { name, .. } => Some(name.clone()),
+                        _ => None,
+                    })
+                    .collect();
+                let prompt = generate_tool_prompt(tools, &self.compat_tool_prompt, &called_tools);
                 self.tools = None; // Clear tools as they are now in the prompt.
                 self.tool_choice = None; // Clear tool_choice as tools are now in the prompt.
                 prompt
@@ -173,7 +257,7 @@ impl UnifiedRequest {
 }
 
 /// A single message in the chat history.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct UnifiedMessage {
     pub role: UnifiedRole,
     pub content: Vec<UnifiedContentBlock>,
@@ -192,7 +276,7 @@ pub enum UnifiedRole {
 }
 
 /// A block of content within a message, allowing for multimodal inputs.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum UnifiedContentBlock {
     Text {
@@ -202,6 +286,22 @@ pub enum UnifiedContentBlock {
         media_type: String,
         data: String,
     },
+    /// A file such as a PDF, `data` is base64 encoded unless `media_type` is `text/plain`
+    Document {
+        media_type: String,
+        data: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        title: Option<String>,
+    },
+    /// Audio input or generated speech, `data` is base64 encoded. `media_type` is `None`
+    /// for generated audio when the backend does not report the encoding.
+    Audio {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        media_type: Option<String>,
+        data: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        transcript: Option<String>,
+    },
     ToolUse {
         id: String,
         name: String,
@@ -238,6 +338,13 @@ pub enum UnifiedToolChoice {
     Tool { name: String },
 }
 
+/// How generated audio is spoken and encoded, required when `modalities` includes `audio`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct UnifiedAudioOutput {
+    pub voice: String,
+    pub format: String, // e.g. "wav", "mp3", "pcm16"
+}
+
 /// Unified metadata for requests (primarily for Claude)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UnifiedMetadata {
@@ -271,6 +378,9 @@ pub struct UnifiedResponse {
     pub model: String,
     pub content: Vec<UnifiedContentBlock>,
     pub stop_reason: Option<String>,
+    /// The configured stop sequence that ended the generation, if the backend reports it
+    #[serde(default)]
+    pub stop_sequence: Option<String>,
     pub usage: UnifiedUsage,
 }
 
@@ -454,6 +564,9 @@ pub struct SseStatus {
     pub tool_compat_fragment_buffer: String,
     pub tool_compat_fragment_count: u32,
     pub tool_compat_last_flush_time: std::time::Instant,
+    pub code_reflow: crate::ccproxy::helper::tool_use_xml::CompatCodeReflowConfig,
+    /// Bytes an unclosed tool call may buffer before it is forwarded as text
+    pub tool_compat_max_buffer_bytes: usize,
     // For gemini tools: tool_id -> tool define
     pub gemini_tools: HashMap<String, UnifiedFunctionCallPart>,
     // For tracking tool_id to index mapping
@@ -474,6 +587,13 @@ pub struct SseStatus {
     pub responses_tool_indexes: HashMap<String, u32>,
     pub responses_custom_tool_names: HashSet<String>,
     pub responses_completed_tool_items: BTreeMap<u32, Value>,
+    // For Claude output: tool input held back per block index until it is complete JSON
+    pub claude_tool_input_buffers: HashMap<u32, String>,
+    pub claude_tool_input_truncated: bool,
+    // For Claude output: whether a tool_use block was started, the stop reason depends on it
+    pub claude_tool_use_started: bool,
+    // The configured stop sequence that ended the stream, if the backend reports it
+    pub stop_sequence: Option<String>,
 }
 
 impl Default for SseStatus {
@@ -497,6 +617,9 @@ impl Default for SseStatus {
             tool_compat_fragment_buffer: String::new(),
             tool_compat_fragment_count: 0,
             tool_compat_last_flush_time: std::time::Instant::now(),
+            code_reflow: Default::default(),
+            tool_compat_max_buffer_bytes:
+                crate::constants::CFG_CCPROXY_COMPAT_TOOL_BUFFER_LIMIT_DEFAULT,
             gemini_tools: HashMap::new(),
             tool_id_to_index: HashMap::new(),
             tool_name: None,
@@ -513,6 +636,10 @@ impl Default for SseStatus {
             responses_tool_indexes: HashMap::new(),
             responses_custom_tool_names: HashSet::new(),
             responses_completed_tool_items: BTreeMap::new(),
+            claude_tool_input_buffers: HashMap::new(),
+            claude_tool_input_truncated: false,
+            claude_tool_use_started: false,
+            stop_sequence: None,
         }
     }
 }
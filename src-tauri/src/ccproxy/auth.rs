@@ -11,6 +11,13 @@ use crate::{
 
 use http::HeaderMap;
 use std::sync::Arc;
+use subtle::ConstantTimeEq;
+
+/// Compares two tokens in constant time, so that timing does not reveal how much of a
+/// guessed token is right.
+fn tokens_match(token: &str, expected: &str) -> bool {
+    token.as_bytes().ct_eq(expected.as_bytes()).into()
+}
 
 /// Authenticates the request based on the Authorization Bearer token or x-api-key.
 /// Reads `chat_completion_proxy_keys` from `MainStore`.
@@ -33,7 +40,7 @@ pub async fn authenticate_request(
                 if let Ok(auth_str) = auth_header.to_str() {
                     if let Some(token) = auth_str.strip_prefix("Bearer ") {
                         let internal_key = INTERNAL_CCPROXY_API_KEY.read().clone();
-                        if token.trim() == internal_key {
+                        if tokens_match(token.trim(), &internal_key) {
                             log::debug!("Internal request authenticated successfully.");
                             return Ok(());
                         }
@@ -93,7 +100,7 @@ pub async fn authenticate_request(
         .and_then(|v| v.to_str().ok())
     {
         if let Some(stored_key) = chat_state.workflow_keys.get(workflow_id) {
-            if token_to_check == *stored_key {
+            if tokens_match(&token_to_check, &stored_key) {
                 log::debug!("Workflow session authenticated successfully.");
                 return Ok(());
             }
@@ -110,7 +117,10 @@ pub async fn authenticate_request(
         return Err(CCProxyError::NoKeysConfigured);
     }
 
-    if proxy_keys.iter().any(|k| k.token == token_to_check) {
+    if proxy_keys
+        .iter()
+        .any(|k| tokens_match(&token_to_check, &k.token))
+    {
         #[cfg(debug_assertions)]
         log::debug!("Proxy authentication: Token is valid.");
 
@@ -42,61 +42,88 @@ pub enum CCProxyError {
     /// Failed to acquire lock on the MainStore.
     #[error("{}", t!("proxy.error.store_lock_failed", error = _0))]
     StoreLockError(String),
+    /// A reached spend limit blocks further requests, holds the localized message.
+    #[error("{0}")]
+    BudgetExceeded(String),
+    /// No slot of the group's concurrency limit freed up in time, holds the localized message.
+    #[error("{0}")]
+    ConcurrencyLimitTimeout(String),
+    /// The moderation policy rejected the prompt or response, holds the localized message.
+    #[error("{0}")]
+    ContentModerated(String),
+    /// A stream produced no output within the first-token timeout, holds the localized message.
+    #[error("{0}")]
+    FirstTokenTimeout(String),
+}
+
+impl CCProxyError {
+    /// The HTTP status the error is answered with.
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            CCProxyError::InvalidToken
+            | CCProxyError::MissingToken
+            | CCProxyError::NoKeysConfigured => StatusCode::UNAUTHORIZED,
+            CCProxyError::NoBackendTargets(_) | CCProxyError::ContentModerated(_) => {
+                StatusCode::BAD_REQUEST
+            }
+            CCProxyError::ModelAliasNotFound(_) => StatusCode::NOT_FOUND,
+            CCProxyError::BudgetExceeded(_) => StatusCode::PAYMENT_REQUIRED,
+            CCProxyError::ConcurrencyLimitTimeout(_) => StatusCode::TOO_MANY_REQUESTS,
+            CCProxyError::BackendRequestError(_) => StatusCode::BAD_GATEWAY,
+            CCProxyError::FirstTokenTimeout(_) => StatusCode::GATEWAY_TIMEOUT,
+            CCProxyError::InvalidProtocolError(_)
+            | CCProxyError::InternalError(_)
+            | CCProxyError::ModelDetailsFetchError(_)
+            | CCProxyError::StoreLockError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
 }
 
 impl IntoResponse for CCProxyError {
     fn into_response(self) -> Response {
-        let (status, error_type, message) = match self {
+        let status = self.status_code();
+        let (error_type, message) = match self {
             CCProxyError::InvalidToken => (
-                StatusCode::UNAUTHORIZED,
                 "Authentication Error",
                 t!("proxy.error.invalid_api_key").to_string(),
             ),
             CCProxyError::MissingToken => (
-                StatusCode::UNAUTHORIZED,
                 "Authentication Error",
                 t!("proxy.error.missing_auth_header").to_string(),
             ),
             CCProxyError::NoBackendTargets(alias) => (
-                StatusCode::BAD_REQUEST,
                 "Configuration Error",
                 t!("proxy.error.no_backend_targets", alias = alias).to_string(),
             ),
             CCProxyError::NoKeysConfigured => (
-                StatusCode::UNAUTHORIZED,
                 "Configuration Error",
                 t!("proxy.error.no_keys_configured").to_string(),
             ),
             CCProxyError::InvalidProtocolError(protocol) => (
-                StatusCode::INTERNAL_SERVER_ERROR,
                 "Configuration Error",
                 t!("proxy.error.invalid_protocol", protocol = protocol).to_string(),
             ),
             CCProxyError::InternalError(message) => (
-                StatusCode::INTERNAL_SERVER_ERROR,
                 "Internal Server Error",
                 t!("proxy.error.internal_server_error", error = message).to_string(),
             ),
-            CCProxyError::BackendRequestError(message) => (
-                StatusCode::BAD_GATEWAY,
-                "Upstream Connection Error",
-                message,
-            ),
+            CCProxyError::BackendRequestError(message) => ("Upstream Connection Error", message),
             CCProxyError::ModelAliasNotFound(alias) => (
-                StatusCode::NOT_FOUND,
                 "Model Not Found",
                 t!("proxy.error.model_alias_not_found", alias = alias).to_string(),
             ),
             CCProxyError::ModelDetailsFetchError(id) => (
-                StatusCode::INTERNAL_SERVER_ERROR,
                 "Database Error",
                 t!("proxy.error.model_details_fetch_failed", id = id).to_string(),
             ),
             CCProxyError::StoreLockError(message) => (
-                StatusCode::INTERNAL_SERVER_ERROR,
                 "Store Error",
                 t!("proxy.error.store_lock_failed", error = message).to_string(),
             ),
+            CCProxyError::BudgetExceeded(message) => ("Budget Exceeded", message),
+            CCProxyError::ConcurrencyLimitTimeout(message) => ("Rate Limit Error", message),
+            CCProxyError::ContentModerated(message) => ("Content Policy Error", message),
+            CCProxyError::FirstTokenTimeout(message) => ("Upstream Timeout", message),
         };
 
         log::error!("CCProxyError: type={}, message={}", error_type, &message);
@@ -0,0 +1,381 @@
+//! Benchmarks configured models through the proxy's streaming path.
+//!
+//! Each model answers the same prompt as a streaming request sent straight to its provider,
+//! like an internal request with `x-cs-provider-id`. Time to first token is taken when the
+//! first content delta arrives, throughput from the tokens generated after it.
+
+use axum::body::to_bytes;
+use axum::response::IntoResponse;
+use futures_util::StreamExt;
+use reqwest::header::{HeaderMap, HeaderValue};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use crate::ccproxy::{
+    helper::{sse::SseLineBuffer, CcproxyQuery},
+    utils::token_estimator::estimate_tokens,
+    ChatProtocol,
+};
+use crate::db::MainStore;
+
+use super::handle_chat_completion;
+
+/// Prompt used when the caller does not pass one.
+pub const BENCHMARK_DEFAULT_PROMPT: &str =
+    "Explain in about 150 words how a hash map works and when to use one.";
+/// Output limit of a benchmark answer, keeps runs short and comparable.
+const BENCHMARK_MAX_TOKENS: i32 = 512;
+/// Largest error body kept in a result.
+const MAX_ERROR_BODY_BYTES: usize = 64 * 1024;
+
+/// A configured model to benchmark.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BenchmarkTarget {
+    pub provider_id: i64,
+    pub model: String,
+}
+
+/// Measurements of one model.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BenchmarkResult {
+    pub provider_id: i64,
+    pub model: String,
+    pub status_code: u16,
+    /// Time to first token, `None` when no content arrived
+    pub ttft_ms: Option<u64>,
+    pub total_ms: u64,
+    /// From the usage of the final chunk, estimated by the proxy when the provider sends none
+    pub output_tokens: u64,
+    /// Output tokens per second after the first token
+    pub tokens_per_second: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Results of all benchmarked models, with the best of each metric.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BenchmarkReport {
+    pub prompt: String,
+    pub results: Vec<BenchmarkResult>,
+    /// Index into `results` of the lowest time to first token
+    pub fastest_first_token: Option<usize>,
+    /// Index into `results` of the highest throughput
+    pub highest_throughput: Option<usize>,
+}
+
+/// What the streamed answer told us so far.
+#[derive(Default)]
+struct StreamMeasure {
+    first_token_at: Option<Duration>,
+    content: String,
+    completion_tokens: Option<u64>,
+    error: Option<String>,
+}
+
+impl StreamMeasure {
+    /// Reads complete SSE lines of an OpenAI chat completion stream.
+    fn read_event(&mut self, event: &str, elapsed: Duration) {
+        for data in event
+            .lines()
+            .filter_map(|line| line.strip_prefix("data:"))
+            .map(str::trim)
+            .filter(|data| !data.is_empty() && *data != "[DONE]")
+        {
+            let Ok(chunk) = serde_json::from_str::<Value>(data) else {
+                continue;
+            };
+            if let Some(error) = chunk.get("error") {
+                self.error = Some(
+                    error
+                        .get("message")
+                        .and_then(Value::as_str)
+                        .map_or_else(|| error.to_string(), str::to_string),
+                );
+            }
+            let delta = &chunk["choices"][0]["delta"];
+            for key in ["content", "reasoning_content"] {
+                if let Some(text) = delta.get(key).and_then(Value::as_str) {
+                    if !text.is_empty() {
+                        self.first_token_at.get_or_insert(elapsed);
+                        self.content.push_str(text);
+                    }
+                }
+            }
+            if let Some(tokens) = chunk["usage"]["completion_tokens"].as_u64() {
+                self.completion_tokens = Some(tokens);
+            }
+        }
+    }
+}
+
+/// Benchmarks `targets` one after another, so the runs do not slow each other down.
+pub async fn benchmark_models(
+    main_store: Arc<RwLock<MainStore>>,
+    targets: Vec<BenchmarkTarget>,
+    prompt: Option<String>,
+) -> BenchmarkReport {
+    let prompt = prompt
+        .map(|prompt| prompt.trim().to_string())
+        .filter(|prompt| !prompt.is_empty())
+        .unwrap_or_else(|| BENCHMARK_DEFAULT_PROMPT.to_string());
+
+    let mut results = Vec::with_capacity(targets.len());
+    for target in targets {
+        results.push(benchmark_model(main_store.clone(), target, &prompt).await);
+    }
+
+    let fastest_first_token = results
+        .iter()
+        .enumerate()
+        .filter(|(_, result)| result.error.is_none())
+        .filter_map(|(index, result)| result.ttft_ms.map(|ttft| (index, ttft)))
+        .min_by_key(|(_, ttft)| *ttft)
+        .map(|(index, _)| index);
+    let highest_throughput = results
+        .iter()
+        .enumerate()
+        .filter(|(_, result)| result.error.is_none())
+        .filter_map(|(index, result)| result.tokens_per_second.map(|tps| (index, tps)))
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(index, _)| index);
+
+    BenchmarkReport {
+        prompt,
+        results,
+        fastest_first_token,
+        highest_throughput,
+    }
+}
+
+/// Streams one answer of `target` and measures it.
+pub async fn benchmark_model(
+    main_store: Arc<RwLock<MainStore>>,
+    target: BenchmarkTarget,
+    prompt: &str,
+) -> BenchmarkResult {
+    let mut result = BenchmarkResult {
+        provider_id: target.provider_id,
+        model: target.model.clone(),
+        ..Default::default()
+    };
+
+    let mut headers = HeaderMap::new();
+    headers.insert("x-cs-provider-id", HeaderValue::from(target.provider_id));
+    match HeaderValue::from_str(&target.model) {
+        Ok(model_id) => {
+            headers.insert("x-cs-model-id", model_id);
+        }
+        Err(e) => {
+            result.error = Some(e.to_string());
+            return result;
+        }
+    }
+    let body = json!({
+        "model": &target.model,
+        "stream": true,
+        "max_tokens": BENCHMARK_MAX_TOKENS,
+        "messages": [{ "role": "user", "content": prompt }]
+    });
+
+    log::info!(
+        "Benchmarking model '{}' of provider {}",
+        &target.model,
+        target.provider_id
+    );
+    let started_at = Instant::now();
+    let response = handle_chat_completion(
+        ChatProtocol::OpenAI,
+        headers,
+        CcproxyQuery {
+            key: None,
+            debug: None,
+        },
+        bytes::Bytes::from(body.to_string()),
+        None,
+        false,
+        target.model.clone(),
+        "generateContent".to_string(),
+        main_store,
+    )
+    .await
+    .into_response();
+    result.status_code = response.status().as_u16();
+
+    if !response.status().is_success() {
+        let body = to_bytes(response.into_body(), MAX_ERROR_BODY_BYTES)
+            .await
+            .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+            .unwrap_or_else(|e| e.to_string());
+        result.total_ms = started_at.elapsed().as_millis() as u64;
+        result.error = Some(body);
+        return result;
+    }
+
+    let mut measure = StreamMeasure::default();
+    let mut lines = SseLineBuffer::default();
+    let mut stream = response.into_body().into_data_stream();
+    while let Some(chunk) = stream.next().await {
+        match chunk {
+            Ok(bytes) => {
+                for line in lines.push(&bytes) {
+                    measure.read_event(&line, started_at.elapsed());
+                }
+            }
+            Err(e) => {
+                measure.error = Some(e.to_string());
+                break;
+            }
+        }
+    }
+    if let Some(line) = lines.finish() {
+        measure.read_event(&line, started_at.elapsed());
+    }
+    let total = started_at.elapsed();
+
+    result.total_ms = total.as_millis() as u64;
+    result.ttft_ms = measure
+        .first_token_at
+        .map(|first_token_at| first_token_at.as_millis() as u64);
+    result.output_tokens = measure
+        .completion_tokens
+        .unwrap_or_else(|| estimate_tokens(&measure.content).ceil() as u64);
+    result.tokens_per_second = measure.first_token_at.and_then(|first_token_at| {
+        let generating = total.saturating_sub(first_token_at).as_secs_f64();
+        (generating > 0.0 && result.output_tokens > 0)
+            .then(|| result.output_tokens as f64 / generating)
+    });
+    result.error = measure.error;
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ccproxy::test_util::{store_with_providers, MockProvider};
+    use crate::test::spawn_mock_backend;
+    use axum::{body::Body, routing::post, Router};
+    use futures_util::stream;
+    use std::convert::Infallible;
+
+    /// Starts an OpenAI-compatible backend streaming `deltas` with `gap` between events.
+    async fn mock_streaming_backend(deltas: Vec<&'static str>, gap: Duration) -> String {
+        let app = Router::new().route(
+            "/v1/chat/completions",
+            post(move || {
+                let deltas = deltas.clone();
+                async move {
+                    let mut events: Vec<String> = deltas
+                        .iter()
+                        .map(|delta| {
+                            format!(
+                                "data: {}\n\n",
+                                json!({
+                                    "id": "chatcmpl-1",
+                                    "object": "chat.completion.chunk",
+                                    "created": 1,
+                                    "model": "bench-model",
+                                    "choices": [{"index": 0, "delta": {"content": delta}}]
+                                })
+                            )
+                        })
+                        .collect();
+                    events.push(format!(
+                        "data: {}\n\n",
+                        json!({
+                            "id": "chatcmpl-1",
+                            "object": "chat.completion.chunk",
+                            "created": 1,
+                            "model": "bench-model",
+                            "choices": [{"index": 0, "delta": {}, "finish_reason": "stop"}],
+                            "usage": {"prompt_tokens": 12, "completion_tokens": 40, "total_tokens": 52}
+                        })
+                    ));
+                    events.push("data: [DONE]\n\n".to_string());
+                    let body = stream::iter(events).then(move |event| async move {
+                        tokio::time::sleep(gap).await;
+                        Ok::<_, Infallible>(event)
+                    });
+                    axum::response::Response::builder()
+                        .header("Content-Type", "text/event-stream")
+                        .body(Body::from_stream(body))
+                        .expect("response")
+                }
+            }),
+        );
+        format!("{}/v1", spawn_mock_backend(app).await)
+    }
+
+    #[tokio::test]
+    async fn test_benchmark_measures_ttft_and_throughput() {
+        let gap = Duration::from_millis(60);
+        let base_url =
+            mock_streaming_backend(vec!["Hash ", "maps ", "store ", "pairs."], gap).await;
+
+        let (main_store, provider_ids) = store_with_providers(
+            vec![MockProvider::new("Mock", "bench-model", base_url)],
+            "",
+            &[],
+        );
+
+        let report = benchmark_models(
+            main_store,
+            vec![BenchmarkTarget {
+                provider_id: provider_ids[0],
+                model: "bench-model".to_string(),
+            }],
+            None,
+        )
+        .await;
+
+        assert_eq!(report.prompt, BENCHMARK_DEFAULT_PROMPT);
+        let result = &report.results[0];
+        assert_eq!(result.status_code, 200, "{:?}", result.error);
+        assert_eq!(result.error, None);
+        assert_eq!(result.output_tokens, 40);
+
+        // The first delta comes after one gap, the rest of the stream after five more
+        let ttft = result.ttft_ms.expect("ttft");
+        assert!(ttft >= gap.as_millis() as u64, "ttft {}ms", ttft);
+        assert!(result.total_ms >= ttft + 5 * gap.as_millis() as u64);
+        let tps = result.tokens_per_second.expect("throughput");
+        let expected = 40.0 / ((result.total_ms - ttft) as f64 / 1000.0);
+        assert!(
+            (tps - expected).abs() / expected < 0.05,
+            "{} vs {}",
+            tps,
+            expected
+        );
+
+        assert_eq!(report.fastest_first_token, Some(0));
+        assert_eq!(report.highest_throughput, Some(0));
+    }
+
+    #[test]
+    fn test_stream_measure_falls_back_to_reasoning_and_reads_errors() {
+        let mut measure = StreamMeasure::default();
+        measure.read_event(": keep-alive\n\n", Duration::from_millis(5));
+        assert_eq!(measure.first_token_at, None);
+
+        measure.read_event(
+            "data: {\"choices\":[{\"delta\":{\"reasoning_content\":\"Hmm\"}}]}\n\n",
+            Duration::from_millis(20),
+        );
+        measure.read_event(
+            "data: {\"choices\":[{\"delta\":{\"content\":\"Hi\"}}]}\n\n",
+            Duration::from_millis(30),
+        );
+        assert_eq!(measure.first_token_at, Some(Duration::from_millis(20)));
+        assert_eq!(measure.content, "HmmHi");
+
+        measure.read_event(
+            "data: {\"error\":{\"message\":\"overloaded\"}}\n\n",
+            Duration::from_millis(40),
+        );
+        assert_eq!(measure.error.as_deref(), Some("overloaded"));
+    }
+}
@@ -4,9 +4,19 @@ use rust_i18n::t;
 use std::sync::{Arc, RwLock};
 
 use crate::ccproxy::handler::request_preprocessor::{
-    preprocess_client_request_body, preprocess_unified_request,
+    clamp_max_tokens, inject_compat_format_rules, preprocess_client_request_body,
+    preprocess_unified_request, CompatFormatRulesConfig, MaxTokensLimitsConfig,
+};
+use crate::ccproxy::helper::{
+    audit_log, budget, concurrency,
+    dead_letter::DeadLetterContext,
+    fallback, first_token, get_msg_id,
+    moderation::{self, Moderation},
+    redaction::Redactor,
+    send_with_retry,
+    tool_use_xml::{CompatCodeReflowConfig, CompatToolPromptConfig},
+    RetryConfig, CC_PROXY_ROTATOR,
 };
-use crate::ccproxy::helper::{get_msg_id, send_with_retry, RetryConfig};
 use crate::ccproxy::ChatProtocol;
 use crate::ccproxy::{
     adapter::{
@@ -29,8 +39,12 @@ use crate::ccproxy::{
     types::{ollama::OllamaChatCompletionRequest, ProxyModel},
 };
 use crate::constants::{
-    CFG_CCPROXY_LOG_PROXY_TO_FILE, CFG_CCPROXY_LOG_TO_FILE, CFG_CCPROXY_RETRY_ON_429,
-    CFG_CCPROXY_RETRY_ON_429_DEFAULT,
+    CFG_CCPROXY_COMPAT_CODE_REFLOW, CFG_CCPROXY_COMPAT_FORMAT_RULES,
+    CFG_CCPROXY_COMPAT_TOOL_BUFFER_LIMIT, CFG_CCPROXY_COMPAT_TOOL_BUFFER_LIMIT_DEFAULT,
+    CFG_CCPROXY_COMPAT_TOOL_PROMPT, CFG_CCPROXY_LOG_PROXY_TO_FILE, CFG_CCPROXY_LOG_TO_FILE,
+    CFG_CCPROXY_MAX_TOKENS_LIMITS, CFG_CCPROXY_REASONING_TEXT_PREFIX,
+    CFG_CCPROXY_REASONING_TEXT_PREFIX_DEFAULT, CFG_CCPROXY_RETRY_ON_429,
+    CFG_CCPROXY_RETRY_ON_429_DEFAULT, CFG_CCPROXY_TOOL_LOOP_GUARD,
 };
 use crate::db::{CcproxyStat, MainStore};
 
@@ -86,6 +100,7 @@ fn build_unified_request(
     tool_compat_mode: bool,
     route_model_alias: String,
     generate_action: String,
+    tool_result_max_chars: Option<usize>,
 ) -> Result<(UnifiedRequest, String, bool), CCProxyError> {
     match chat_protocol {
         ChatProtocol::OpenAI | ChatProtocol::HuggingFace => {
@@ -167,12 +182,16 @@ fn build_unified_request(
             let proxy_alias = client_request_payload.model.clone();
 
             // 1. Convert to UnifiedRequest
-            let unified_request =
-                from_claude(client_request_payload, tool_compat_mode).map_err(|e| {
-                    CCProxyError::InternalError(
-                        t!("proxy.error.invalid_request", error = e.to_string()).to_string(),
-                    )
-                })?;
+            let unified_request = from_claude(
+                client_request_payload,
+                tool_compat_mode,
+                tool_result_max_chars,
+            )
+            .map_err(|e| {
+                CCProxyError::InternalError(
+                    t!("proxy.error.invalid_request", error = e.to_string()).to_string(),
+                )
+            })?;
             let is_streaming_request = unified_request.stream;
             Ok((unified_request, proxy_alias, is_streaming_request))
         }
@@ -218,12 +237,42 @@ pub(crate) fn prepare_unified_request_for_proxy_model(
     proxy_model: &ProxyModel,
 ) {
     unified_request.custom_params = proxy_model.custom_params.clone();
+    unified_request.prompt_template = proxy_model.prompt_template.clone();
+    unified_request.default_stop_sequences = proxy_model.model_stop.clone();
+
+    // Extra body fields reach the backend only when the group allows their keys, or the
+    // quirks mode knows them
+    unified_request.openai_quirks = proxy_model.openai_quirks;
+    let quirks_keys = proxy_model
+        .openai_quirks
+        .map_or(&[][..], |quirks| quirks.passthrough_keys());
+    let extra_body_len = unified_request.extra_body.len();
+    unified_request.extra_body.retain(|key, _| {
+        proxy_model.extra_body_keys.contains(key) || quirks_keys.contains(&key.as_str())
+    });
+    if unified_request.extra_body.len() < extra_body_len {
+        log::debug!(
+            "Dropped {} extra body fields not allowed by the group, kept: {:?}",
+            extra_body_len - unified_request.extra_body.len(),
+            unified_request.extra_body.keys().collect::<Vec<_>>()
+        );
+    }
 
     // --- Inject Engine Defaults only if missing from client AND configured with valid non-default values ---
     ModelResolver::merge_parameters_unified(unified_request, proxy_model);
 
     preprocess_unified_request(unified_request, proxy_model);
 
+    // Forced tools are merged before filtering so the never-include list still wins
+    if !proxy_model.tool_include.is_empty() {
+        let tools = unified_request.tools.get_or_insert_with(Vec::new);
+        for forced in &proxy_model.tool_include {
+            if !tools.iter().any(|tool| tool.name == forced.name) {
+                tools.push(forced.clone());
+            }
+        }
+    }
+
     if proxy_model.tool_filter.len() > 0 {
         unified_request.tools = unified_request.tools.take().map(|tools| {
             tools
@@ -245,16 +294,34 @@ pub(crate) fn prepare_unified_request_for_proxy_model(
     }
 
     if !proxy_model.prompt_replace.is_empty() {
-        if let Some(system_prompt) = &mut unified_request.system_prompt {
+        for prompt in [
+            &mut unified_request.system_prompt,
+            &mut unified_request.developer_prompt,
+        ]
+        .into_iter()
+        .flatten()
+        {
             for (key, value) in &proxy_model.prompt_replace {
                 if !key.is_empty() {
-                    *system_prompt = system_prompt.replace(key, value);
+                    *prompt = prompt.replace(key, value);
                 }
             }
         }
     }
 }
 
+/// The adapter that speaks the protocol of a backend.
+pub(crate) fn backend_adapter_for(chat_protocol: &ChatProtocol) -> Arc<dyn BackendAdapter> {
+    match chat_protocol {
+        ChatProtocol::OpenAI | ChatProtocol::HuggingFace => {
+            Arc::new(crate::ccproxy::adapter::backend::OpenAIBackendAdapter)
+        }
+        ChatProtocol::Ollama => Arc::new(backend::OllamaBackendAdapter),
+        ChatProtocol::Claude => Arc::new(backend::ClaudeBackendAdapter),
+        ChatProtocol::Gemini => Arc::new(backend::GeminiBackendAdapter),
+    }
+}
+
 pub(crate) async fn execute_unified_chat_request(
     client_protocol: ChatProtocol,
     client_headers: HeaderMap,
@@ -270,6 +337,59 @@ pub(crate) async fn execute_unified_chat_request(
     main_store_arc: Arc<std::sync::RwLock<MainStore>>,
     output_adapter: OutputAdapterEnum,
 ) -> ProxyResult<Response> {
+    let (
+        format_rules,
+        code_reflow,
+        tool_buffer_limit,
+        tool_prompt,
+        tool_loop_guard,
+        reasoning_text_prefix,
+        max_tokens_limits,
+    ) = if let Ok(store) = main_store_arc.read() {
+        (
+            store.get_config(
+                CFG_CCPROXY_COMPAT_FORMAT_RULES,
+                CompatFormatRulesConfig::default(),
+            ),
+            store.get_config(
+                CFG_CCPROXY_COMPAT_CODE_REFLOW,
+                CompatCodeReflowConfig::default(),
+            ),
+            store.get_config(
+                CFG_CCPROXY_COMPAT_TOOL_BUFFER_LIMIT,
+                CFG_CCPROXY_COMPAT_TOOL_BUFFER_LIMIT_DEFAULT,
+            ),
+            store.get_config(
+                CFG_CCPROXY_COMPAT_TOOL_PROMPT,
+                CompatToolPromptConfig::default(),
+            ),
+            store.get_config(CFG_CCPROXY_TOOL_LOOP_GUARD, true),
+            store.get_config(
+                CFG_CCPROXY_REASONING_TEXT_PREFIX,
+                CFG_CCPROXY_REASONING_TEXT_PREFIX_DEFAULT.to_string(),
+            ),
+            store.get_config(
+                CFG_CCPROXY_MAX_TOKENS_LIMITS,
+                MaxTokensLimitsConfig::default(),
+            ),
+        )
+    } else {
+        (
+            CompatFormatRulesConfig::default(),
+            CompatCodeReflowConfig::default(),
+            CFG_CCPROXY_COMPAT_TOOL_BUFFER_LIMIT_DEFAULT,
+            CompatToolPromptConfig::default(),
+            true,
+            CFG_CCPROXY_REASONING_TEXT_PREFIX_DEFAULT.to_string(),
+            MaxTokensLimitsConfig::default(),
+        )
+    };
+    unified_request.skip_tool_loop_guard = !tool_loop_guard;
+    unified_request.reasoning_text_prefix = Some(reasoning_text_prefix);
+    unified_request.compat_tool_prompt = tool_prompt;
+    inject_compat_format_rules(&mut unified_request, &format_rules);
+    clamp_max_tokens(&mut unified_request, &proxy_model.model, &max_tokens_limits);
+
     let full_url = get_provider_chat_full_url(
         proxy_model.chat_protocol.clone(),
         &proxy_model.base_url,
@@ -278,14 +398,7 @@ pub(crate) async fn execute_unified_chat_request(
         is_streaming_request,
     );
 
-    let backend_adapter: Arc<dyn BackendAdapter> = match proxy_model.chat_protocol {
-        ChatProtocol::OpenAI | ChatProtocol::HuggingFace => {
-            Arc::new(crate::ccproxy::adapter::backend::OpenAIBackendAdapter)
-        }
-        ChatProtocol::Ollama => Arc::new(backend::OllamaBackendAdapter),
-        ChatProtocol::Claude => Arc::new(backend::ClaudeBackendAdapter),
-        ChatProtocol::Gemini => Arc::new(backend::GeminiBackendAdapter),
-    };
+    let backend_adapter = backend_adapter_for(&proxy_model.chat_protocol);
 
     let http_client = ModelResolver::build_http_client(
         main_store_arc.clone(),
@@ -340,8 +453,22 @@ pub(crate) async fn execute_unified_chat_request(
     let retry_config = RetryConfig::from_settings(max_retries);
 
     let target_response = match send_with_retry(onward_request_builder, &retry_config).await {
-        Ok(response) => response,
+        Ok(response) => {
+            CC_PROXY_ROTATOR.record_key_status(
+                proxy_model.provider_id,
+                &proxy_model.model,
+                &proxy_model.api_key,
+                response.status().as_u16(),
+            );
+            response
+        }
         Err(CCProxyError::BackendRequestError(message)) => {
+            CC_PROXY_ROTATOR.record_key_status(
+                proxy_model.provider_id,
+                &proxy_model.model,
+                &proxy_model.api_key,
+                http::StatusCode::BAD_GATEWAY.as_u16(),
+            );
             log::warn!(
                 "Backend request failed before receiving a response (alias: '{}', model: '{}', provider: '{}'): error={}",
                 proxy_alias,
@@ -355,21 +482,24 @@ pub(crate) async fn execute_unified_chat_request(
             }
 
             if let Ok(store) = main_store_arc.read() {
-                let _ = store.record_ccproxy_stat(CcproxyStat {
-                    id: None,
-                    client_model: proxy_model.client_alias.clone(),
-                    backend_model: proxy_model.model.clone(),
-                    provider_id: Some(proxy_model.provider_id),
-                    provider: proxy_model.provider.clone(),
-                    protocol: client_protocol.to_string(),
-                    tool_compat_mode: if final_tool_compat_mode { 1 } else { 0 },
-                    status_code: http::StatusCode::BAD_GATEWAY.as_u16() as i32,
-                    error_message: Some(message.clone()),
-                    input_tokens: 0,
-                    output_tokens: 0,
-                    cache_tokens: 0,
-                    request_at: None,
-                });
+                budget::record_stat(
+                    &store,
+                    CcproxyStat {
+                        id: None,
+                        client_model: proxy_model.client_alias.clone(),
+                        backend_model: proxy_model.model.clone(),
+                        provider_id: Some(proxy_model.provider_id),
+                        provider: proxy_model.provider.clone(),
+                        protocol: client_protocol.to_string(),
+                        tool_compat_mode: if final_tool_compat_mode { 1 } else { 0 },
+                        status_code: http::StatusCode::BAD_GATEWAY.as_u16() as i32,
+                        error_message: Some(message.clone()),
+                        input_tokens: 0,
+                        output_tokens: 0,
+                        cache_tokens: 0,
+                        request_at: None,
+                    },
+                );
             }
 
             return Ok(output_adapter.adapt_error_response(UnifiedErrorResponse {
@@ -422,21 +552,24 @@ pub(crate) async fn execute_unified_chat_request(
         let message_content = unified_error.message.clone();
 
         if let Ok(store) = main_store_arc.read() {
-            let _ = store.record_ccproxy_stat(CcproxyStat {
-                id: None,
-                client_model: proxy_model.client_alias.clone(),
-                backend_model: proxy_model.model.clone(),
-                provider_id: Some(proxy_model.provider_id),
-                provider: proxy_model.provider.clone(),
-                protocol: client_protocol.to_string(),
-                tool_compat_mode: if final_tool_compat_mode { 1 } else { 0 },
-                status_code: status_code.as_u16() as i32,
-                error_message: Some(message_content),
-                input_tokens: 0,
-                output_tokens: 0,
-                cache_tokens: 0,
-                request_at: None,
-            });
+            budget::record_stat(
+                &store,
+                CcproxyStat {
+                    id: None,
+                    client_model: proxy_model.client_alias.clone(),
+                    backend_model: proxy_model.model.clone(),
+                    provider_id: Some(proxy_model.provider_id),
+                    provider: proxy_model.provider.clone(),
+                    protocol: client_protocol.to_string(),
+                    tool_compat_mode: if final_tool_compat_mode { 1 } else { 0 },
+                    status_code: status_code.as_u16() as i32,
+                    error_message: Some(message_content),
+                    input_tokens: 0,
+                    output_tokens: 0,
+                    cache_tokens: 0,
+                    request_at: None,
+                },
+            );
         }
 
         let mut response = output_adapter.adapt_error_response(unified_error);
@@ -460,6 +593,45 @@ pub(crate) async fn execute_unified_chat_request(
         return Ok(response);
     }
 
+    let target_response = match first_token::first_token_timeout(&main_store_arc) {
+        Some(timeout) if is_streaming_request => {
+            match first_token::await_first_token(target_response, timeout).await {
+                Ok(response) => response,
+                Err(error) => {
+                    log::warn!(
+                        "Backend stream produced no output in time (alias: '{}', model: '{}', provider: '{}'): {}",
+                        proxy_alias,
+                        proxy_model.model,
+                        proxy_model.provider,
+                        error
+                    );
+                    if let Ok(store) = main_store_arc.read() {
+                        budget::record_stat(
+                            &store,
+                            CcproxyStat {
+                                id: None,
+                                client_model: proxy_model.client_alias.clone(),
+                                backend_model: proxy_model.model.clone(),
+                                provider_id: Some(proxy_model.provider_id),
+                                provider: proxy_model.provider.clone(),
+                                protocol: client_protocol.to_string(),
+                                tool_compat_mode: if final_tool_compat_mode { 1 } else { 0 },
+                                status_code: error.status_code().as_u16() as i32,
+                                error_message: Some(error.to_string()),
+                                input_tokens: 0,
+                                output_tokens: 0,
+                                cache_tokens: 0,
+                                request_at: None,
+                            },
+                        );
+                    }
+                    return Err(error);
+                }
+            }
+        }
+        _ => target_response,
+    };
+
     let estimated_input_tokens =
         crate::ccproxy::utils::token_estimator::estimate_unified_request_tokens(&unified_request);
 
@@ -471,6 +643,8 @@ pub(crate) async fn execute_unified_chat_request(
         estimated_input_tokens,
     );
     status.responses_custom_tool_names = responses_custom_tool_names;
+    status.code_reflow = code_reflow.clone();
+    status.tool_compat_max_buffer_bytes = tool_buffer_limit;
     let sse_status = Arc::new(RwLock::new(status));
 
     if is_streaming_request {
@@ -505,6 +679,7 @@ pub(crate) async fn execute_unified_chat_request(
         let backend_response = crate::ccproxy::adapter::backend::BackendResponse {
             body: body_bytes,
             tool_compat_mode,
+            code_reflow,
         };
         let unified_response = backend_adapter
             .adapt_response(backend_response)
@@ -533,21 +708,24 @@ pub(crate) async fn execute_unified_chat_request(
                 .or(unified_response.usage.cached_content_tokens)
                 .unwrap_or(0);
 
-            let _ = store.record_ccproxy_stat(CcproxyStat {
-                id: None,
-                client_model: proxy_model.client_alias.clone(),
-                backend_model: proxy_model.model.clone(),
-                provider_id: Some(proxy_model.provider_id),
-                provider: proxy_model.provider.clone(),
-                protocol: client_protocol.to_string(),
-                tool_compat_mode: if final_tool_compat_mode { 1 } else { 0 },
-                status_code: 200,
-                error_message: None,
-                input_tokens: unified_response.usage.input_tokens as i64,
-                output_tokens: unified_response.usage.output_tokens as i64,
-                cache_tokens: cache_tokens as i64,
-                request_at: None,
-            });
+            budget::record_stat(
+                &store,
+                CcproxyStat {
+                    id: None,
+                    client_model: proxy_model.client_alias.clone(),
+                    backend_model: proxy_model.model.clone(),
+                    provider_id: Some(proxy_model.provider_id),
+                    provider: proxy_model.provider.clone(),
+                    protocol: client_protocol.to_string(),
+                    tool_compat_mode: if final_tool_compat_mode { 1 } else { 0 },
+                    status_code: 200,
+                    error_message: None,
+                    input_tokens: unified_response.usage.input_tokens as i64,
+                    output_tokens: unified_response.usage.output_tokens as i64,
+                    cache_tokens: cache_tokens as i64,
+                    request_at: None,
+                },
+            );
         }
 
         let mut response = output_adapter
@@ -569,6 +747,114 @@ pub(crate) async fn execute_unified_chat_request(
     }
 }
 
+/// Whether the client asked for a streamed response.
+fn client_wants_stream(chat_protocol: &ChatProtocol, body: &[u8], generate_action: &str) -> bool {
+    match chat_protocol {
+        ChatProtocol::OpenAI | ChatProtocol::HuggingFace => {
+            let req: OpenAIChatCompletionRequest = serde_json::from_slice(body).unwrap_or_default();
+            req.stream.unwrap_or(false)
+        }
+        ChatProtocol::Claude => {
+            let req: Result<ClaudeNativeRequest, _> = serde_json::from_slice(body);
+            req.map(|r| r.stream.unwrap_or(false)).unwrap_or(false)
+        }
+        ChatProtocol::Ollama => {
+            let req: OllamaChatCompletionRequest = serde_json::from_slice(body).unwrap_or_default();
+            req.stream.unwrap_or(false)
+        }
+        ChatProtocol::Gemini => generate_action == "streamGenerateContent",
+    }
+}
+
+/// Sends the client request to `proxy_model`, forwarded as is when the protocols match or
+/// through the unified adapters otherwise.
+#[allow(clippy::too_many_arguments)]
+async fn forward_chat_request(
+    chat_protocol: ChatProtocol,
+    client_headers: HeaderMap,
+    client_request_body: bytes::Bytes,
+    proxy_model: ProxyModel,
+    tool_compat_mode: bool,
+    route_model_alias: String,
+    generate_action: String,
+    message_id: String,
+    log_org_to_file: bool,
+    log_proxy_to_file: bool,
+    main_store_arc: Arc<std::sync::RwLock<MainStore>>,
+) -> ProxyResult<Response> {
+    let protocol_string = chat_protocol.to_string();
+    //======================================================
+    // Direct send request to ai server
+    //======================================================
+    // Determine final tool_compat_mode based on metadata override
+    let final_tool_compat_mode = match proxy_model.tool_compat_mode.as_deref() {
+        Some("compat") => true,
+        Some("native") => false,
+        Some("auto") | None => tool_compat_mode, // Use route parameter for auto mode
+        _ => tool_compat_mode,                   // Fallback to route parameter
+    };
+
+    let preprocessed_request_body =
+        preprocess_client_request_body(client_request_body, &chat_protocol, &proxy_model)?;
+
+    if chat_protocol == proxy_model.chat_protocol && !final_tool_compat_mode {
+        let is_streaming =
+            client_wants_stream(&chat_protocol, &preprocessed_request_body, &generate_action);
+
+        let result = super::handle_direct_forward(
+            client_headers,
+            preprocessed_request_body,
+            proxy_model,
+            is_streaming,
+            main_store_arc,
+            log_proxy_to_file,
+        )
+        .await?;
+        return Ok(result.into_response());
+    }
+
+    if log_org_to_file {
+        log::info!(target: "ccproxy_logger", "message id:{}\n{} Origin Request Body: \n{}\n----------------\n", &message_id, &protocol_string, String::from_utf8_lossy(&preprocessed_request_body));
+    }
+
+    let (mut unified_request, proxy_alias, is_streaming_request) = build_unified_request(
+        chat_protocol.clone(),
+        preprocessed_request_body,
+        final_tool_compat_mode,
+        route_model_alias,
+        generate_action,
+        proxy_model.tool_result_max_chars,
+    )?;
+
+    prepare_unified_request_for_proxy_model(&mut unified_request, &proxy_model);
+
+    let output_adapter: OutputAdapterEnum = match chat_protocol {
+        ChatProtocol::OpenAI | ChatProtocol::HuggingFace => {
+            OutputAdapterEnum::OpenAI(OpenAIOutputAdapter)
+        }
+        ChatProtocol::Claude => OutputAdapterEnum::Claude(ClaudeOutputAdapter),
+        ChatProtocol::Gemini => OutputAdapterEnum::Gemini(GeminiOutputAdapter),
+        ChatProtocol::Ollama => OutputAdapterEnum::Ollama(OllamaOutputAdapter),
+    };
+
+    execute_unified_chat_request(
+        chat_protocol,
+        client_headers,
+        unified_request,
+        proxy_alias,
+        proxy_model,
+        is_streaming_request,
+        tool_compat_mode,
+        final_tool_compat_mode,
+        message_id,
+        log_org_to_file,
+        log_proxy_to_file,
+        main_store_arc,
+        output_adapter,
+    )
+    .await
+}
+
 pub async fn handle_chat_completion(
     chat_protocol: ChatProtocol,
     client_headers: HeaderMap,
@@ -580,7 +866,6 @@ pub async fn handle_chat_completion(
     generate_action: String,
     main_store_arc: Arc<std::sync::RwLock<MainStore>>,
 ) -> ProxyResult<Response> {
-    let protocol_string = chat_protocol.to_string();
     let message_id = get_msg_id();
 
     let log_org_to_file = if let Ok(store) = main_store_arc.read() {
@@ -594,6 +879,26 @@ pub async fn handle_chat_completion(
         false
     };
 
+    budget::check_budget(&main_store_arc)?;
+
+    // Optional redaction of personal data, before anything leaves the machine
+    let redactor = Redactor::from_store(&main_store_arc);
+    let (client_request_body, placeholders) = match &redactor {
+        Some(redactor) => redactor.redact_request(client_request_body),
+        None => (client_request_body, Default::default()),
+    };
+
+    // Optional moderation of the latest prompt, disabled unless configured
+    let moderation = Moderation::from_store(&main_store_arc);
+    let prompt_flags = match &moderation {
+        Some(moderation) => {
+            moderation
+                .check_prompt(&chat_protocol, &client_request_body)
+                .await?
+        }
+        None => None,
+    };
+
     let proxy_model = if let Some(provider_id) = client_headers
         .get("x-cs-provider-id")
         .and_then(|v| v.to_str().ok())
@@ -634,89 +939,541 @@ pub async fn handle_chat_completion(
             .await?
     };
 
-    //======================================================
-    // Direct send request to ai server
-    //======================================================
-    // Determine final tool_compat_mode based on metadata override
-    let final_tool_compat_mode = match proxy_model.tool_compat_mode.as_deref() {
-        Some("compat") => true,
-        Some("native") => false,
-        Some("auto") | None => tool_compat_mode, // Use route parameter for auto mode
-        _ => tool_compat_mode,                   // Fallback to route parameter
+    let slot = concurrency::acquire_slot(proxy_model.concurrency_limit.as_ref()).await?;
+
+    audit_log::record(
+        &main_store_arc,
+        &message_id,
+        &chat_protocol.to_string(),
+        (&proxy_model).into(),
+        &client_request_body,
+    );
+
+    let dead_letter = DeadLetterContext::new(
+        &message_id,
+        chat_protocol.to_string(),
+        &proxy_model,
+        &client_request_body,
+    );
+    let dead_letter_store = main_store_arc.clone();
+    let is_streaming = client_wants_stream(&chat_protocol, &client_request_body, &generate_action);
+    let result =
+        fallback::run_with_fallback(&main_store_arc, proxy_model, is_streaming, |proxy_model| {
+            forward_chat_request(
+                chat_protocol.clone(),
+                client_headers.clone(),
+                client_request_body.clone(),
+                proxy_model,
+                tool_compat_mode,
+                route_model_alias.clone(),
+                generate_action.clone(),
+                message_id.clone(),
+                log_org_to_file,
+                log_proxy_to_file,
+                main_store_arc.clone(),
+            )
+        })
+        .await;
+    let response = dead_letter.capture(&dead_letter_store, result).await?;
+    let response = match &moderation {
+        Some(moderation) => moderation.check_response(&chat_protocol, response).await?,
+        None => response,
     };
+    let response = match &redactor {
+        Some(redactor) => redactor.restore_response(response, placeholders),
+        None => response,
+    };
+    Ok(concurrency::hold_slot_until_body_end(
+        moderation::mark_flagged(response, prompt_flags),
+        slot,
+    ))
+}
 
-    let preprocessed_request_body =
-        preprocess_client_request_body(client_request_body, &chat_protocol, &proxy_model)?;
+#[cfg(test)]
+mod tests {
+    use super::prepare_unified_request_for_proxy_model;
+    use crate::ccproxy::{
+        adapter::{
+            backend::{BackendAdapter, OpenAIBackendAdapter},
+            input::from_openai,
+            unified::{OpenAICompatQuirks, UnifiedRequest, UnifiedTool},
+        },
+        test_util::proxy_model_with_tools,
+        ChatProtocol,
+    };
+    use crate::test::spawn_mock_backend;
+    use serde_json::{json, Value};
+
+    fn tool_names(request: &UnifiedRequest) -> Vec<&str> {
+        request
+            .tools
+            .iter()
+            .flatten()
+            .map(|tool| tool.name.as_str())
+            .collect()
+    }
 
-    if chat_protocol == proxy_model.chat_protocol && !final_tool_compat_mode {
-        let is_streaming = match chat_protocol {
-            ChatProtocol::OpenAI | ChatProtocol::HuggingFace => {
-                let req: OpenAIChatCompletionRequest =
-                    serde_json::from_slice(&preprocessed_request_body).unwrap_or_default();
-                req.stream.unwrap_or(false)
-            }
-            ChatProtocol::Claude => {
-                let req: Result<ClaudeNativeRequest, _> =
-                    serde_json::from_slice(&preprocessed_request_body);
-                req.map(|r| r.stream.unwrap_or(false)).unwrap_or(false)
-            }
-            ChatProtocol::Ollama => {
-                let req: OllamaChatCompletionRequest =
-                    serde_json::from_slice(&preprocessed_request_body).unwrap_or_default();
-                req.stream.unwrap_or(false)
-            }
-            ChatProtocol::Gemini => generate_action == "streamGenerateContent",
+    #[test]
+    fn forced_tools_are_added_when_client_sent_none() {
+        let proxy_model = proxy_model_with_tools(ChatProtocol::OpenAI);
+        let mut request = UnifiedRequest::default();
+
+        prepare_unified_request_for_proxy_model(&mut request, &proxy_model);
+
+        // `bash` is forced but also filtered, the never-include list wins
+        assert_eq!(tool_names(&request), vec!["web_search"]);
+    }
+
+    #[test]
+    fn forced_tools_do_not_duplicate_client_tools() {
+        let proxy_model = proxy_model_with_tools(ChatProtocol::OpenAI);
+        let mut request = UnifiedRequest {
+            tools: Some(vec![
+                UnifiedTool {
+                    name: "web_search".to_string(),
+                    description: Some("Client definition".to_string()),
+                    input_schema: json!({"type": "object"}),
+                },
+                UnifiedTool {
+                    name: "read_file".to_string(),
+                    description: None,
+                    input_schema: json!({"type": "object"}),
+                },
+            ]),
+            ..Default::default()
         };
 
-        let result = super::handle_direct_forward(
-            client_headers,
-            preprocessed_request_body,
-            proxy_model,
-            is_streaming,
-            main_store_arc,
-            log_proxy_to_file,
+        prepare_unified_request_for_proxy_model(&mut request, &proxy_model);
+
+        assert_eq!(tool_names(&request), vec!["web_search", "read_file"]);
+        let tools = request.tools.unwrap_or_default();
+        assert_eq!(tools[0].description.as_deref(), Some("Client definition"));
+    }
+
+    #[tokio::test]
+    async fn allowlisted_extra_body_fields_reach_the_backend() {
+        let mut proxy_model = proxy_model_with_tools(ChatProtocol::OpenAI);
+        proxy_model.extra_body_keys = vec!["chat_template_kwargs".to_string()];
+        let openai_request = serde_json::from_value(json!({
+            "model": "agent",
+            "messages": [{ "role": "user", "content": "Hello" }],
+            "chat_template_kwargs": { "enable_thinking": false },
+            "guided_choice": ["yes", "no"]
+        }))
+        .expect("OpenAI request should parse");
+        let mut request = from_openai(openai_request, false).expect("request should convert");
+
+        prepare_unified_request_for_proxy_model(&mut request, &proxy_model);
+        let builder = OpenAIBackendAdapter
+            .adapt_request(
+                &reqwest::Client::new(),
+                &mut request,
+                "",
+                "http://localhost:8000/v1/chat/completions",
+                "qwen3",
+                false,
+                &mut reqwest::header::HeaderMap::new(),
+            )
+            .await
+            .expect("request should adapt");
+        let backend_request = builder.build().expect("request should build");
+        let body: Value = serde_json::from_slice(
+            backend_request
+                .body()
+                .and_then(|body| body.as_bytes())
+                .expect("request body should be available as bytes"),
         )
-        .await?;
-        return Ok(result.into_response());
+        .expect("request body should be valid json");
+
+        assert_eq!(
+            body["chat_template_kwargs"],
+            json!({ "enable_thinking": false })
+        );
+        assert!(body.get("guided_choice").is_none());
     }
 
-    if log_org_to_file {
-        log::info!(target: "ccproxy_logger", "message id:{}\n{} Origin Request Body: \n{}\n----------------\n", &message_id, &protocol_string, String::from_utf8_lossy(&preprocessed_request_body));
+    #[test]
+    fn quirks_mode_keeps_its_extra_body_keys() {
+        let mut proxy_model = proxy_model_with_tools(ChatProtocol::OpenAI);
+        proxy_model.openai_quirks = Some(OpenAICompatQuirks::Vllm);
+        let mut request = UnifiedRequest::default();
+        request
+            .extra_body
+            .insert("chat_template_kwargs".to_string(), json!({"x": 1}));
+        request
+            .extra_body
+            .insert("guided_regex".to_string(), json!("[0-9]+"));
+        request
+            .extra_body
+            .insert("unknown".to_string(), json!(true));
+
+        prepare_unified_request_for_proxy_model(&mut request, &proxy_model);
+
+        assert_eq!(request.openai_quirks, Some(OpenAICompatQuirks::Vllm));
+        let mut keys: Vec<&String> = request.extra_body.keys().collect();
+        keys.sort();
+        assert_eq!(keys, ["chat_template_kwargs", "guided_regex"]);
     }
 
-    let (mut unified_request, proxy_alias, is_streaming_request) = build_unified_request(
-        chat_protocol.clone(),
-        preprocessed_request_body,
-        final_tool_compat_mode,
-        route_model_alias,
-        generate_action,
-    )?;
+    #[test]
+    fn group_defaults_fill_only_missing_parameters() {
+        let mut proxy_model = proxy_model_with_tools(ChatProtocol::OpenAI);
+        proxy_model.temperature = Some(0.2);
+        proxy_model.max_tokens = Some(2048);
+        proxy_model.top_p = Some(0.9);
 
-    prepare_unified_request_for_proxy_model(&mut unified_request, &proxy_model);
+        let mut request = UnifiedRequest {
+            temperature: Some(1.1),
+            ..Default::default()
+        };
+        prepare_unified_request_for_proxy_model(&mut request, &proxy_model);
+        assert_eq!(request.temperature, Some(1.1));
+        assert_eq!(request.max_tokens, Some(2048));
+        assert_eq!(request.top_p, Some(0.9));
+
+        let mut request = UnifiedRequest {
+            max_tokens: Some(64),
+            top_p: Some(0.5),
+            ..Default::default()
+        };
+        prepare_unified_request_for_proxy_model(&mut request, &proxy_model);
+        assert_eq!(request.temperature, Some(0.2));
+        assert_eq!(request.max_tokens, Some(64));
+        assert_eq!(request.top_p, Some(0.5));
+    }
 
-    let output_adapter: OutputAdapterEnum = match chat_protocol {
-        ChatProtocol::OpenAI | ChatProtocol::HuggingFace => {
-            OutputAdapterEnum::OpenAI(OpenAIOutputAdapter)
+    /// Starts an OpenAI-compatible backend answering every chat request with `body`
+    /// gzip-compressed, sent in several chunks.
+    async fn gzip_backend(content_type: &'static str, body: String) -> String {
+        use axum::{body::Body, routing::post, Router};
+        use flate2::{write::GzEncoder, Compression};
+        use std::io::Write;
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder
+            .write_all(body.as_bytes())
+            .expect("compress response");
+        let compressed = encoder.finish().expect("finish compression");
+
+        let app = Router::new().route(
+            "/v1/chat/completions",
+            post(move || {
+                let chunks: Vec<Result<Vec<u8>, std::convert::Infallible>> = compressed
+                    .chunks(16)
+                    .map(|chunk| Ok(chunk.to_vec()))
+                    .collect();
+                async move {
+                    axum::response::Response::builder()
+                        .header("Content-Type", content_type)
+                        .header("Content-Encoding", "gzip")
+                        .body(Body::from_stream(futures_util::stream::iter(chunks)))
+                        .expect("response")
+                }
+            }),
+        );
+        format!("{}/v1", spawn_mock_backend(app).await)
+    }
+
+    /// Sends an OpenAI chat request straight to a provider at `base_url` through the proxy.
+    async fn proxy_chat(base_url: String, stream: bool) -> (u16, String) {
+        use crate::ccproxy::helper::CcproxyQuery;
+        use crate::ccproxy::test_util::{store_with_providers, MockProvider};
+        use axum::response::IntoResponse;
+
+        let (main_store, provider_ids) = store_with_providers(
+            vec![MockProvider::new("Gateway", "gpt-4o", base_url)],
+            "",
+            &[],
+        );
+
+        let mut headers = http::HeaderMap::new();
+        headers.insert("x-cs-provider-id", http::HeaderValue::from(provider_ids[0]));
+        headers.insert("x-cs-model-id", http::HeaderValue::from_static("gpt-4o"));
+        let body = json!({
+            "model": "gpt-4o",
+            "stream": stream,
+            "messages": [{ "role": "user", "content": "Hello" }]
+        });
+        let response = super::handle_chat_completion(
+            ChatProtocol::OpenAI,
+            headers,
+            CcproxyQuery {
+                key: None,
+                debug: None,
+            },
+            bytes::Bytes::from(body.to_string()),
+            None,
+            false,
+            "gpt-4o".to_string(),
+            "generateContent".to_string(),
+            main_store,
+        )
+        .await
+        .into_response();
+        let status = response.status().as_u16();
+        let bytes = axum::body::to_bytes(response.into_body(), 1024 * 1024)
+            .await
+            .expect("read proxy response");
+        (status, String::from_utf8_lossy(&bytes).into_owned())
+    }
+
+    #[tokio::test]
+    async fn gzip_encoded_response_is_decoded() {
+        let base_url = gzip_backend(
+            "application/json",
+            json!({
+                "id": "chatcmpl-1",
+                "object": "chat.completion",
+                "created": 1,
+                "model": "gpt-4o",
+                "choices": [{
+                    "index": 0,
+                    "message": {"role": "assistant", "content": "Hi from a compressing gateway"},
+                    "finish_reason": "stop"
+                }],
+                "usage": {"prompt_tokens": 3, "completion_tokens": 6, "total_tokens": 9}
+            })
+            .to_string(),
+        )
+        .await;
+
+        let (status, body) = proxy_chat(base_url, false).await;
+
+        assert_eq!(status, 200, "{}", body);
+        let body: Value = serde_json::from_str(&body).expect("decoded JSON response");
+        assert_eq!(
+            body["choices"][0]["message"]["content"],
+            "Hi from a compressing gateway"
+        );
+    }
+
+    #[tokio::test]
+    async fn gzip_encoded_stream_is_decoded_chunk_by_chunk() {
+        let events = ["Hi ", "from ", "a ", "compressing ", "gateway"]
+            .iter()
+            .map(|delta| {
+                format!(
+                    "data: {}\n\n",
+                    json!({
+                        "id": "chatcmpl-1",
+                        "object": "chat.completion.chunk",
+                        "created": 1,
+                        "model": "gpt-4o",
+                        "choices": [{"index": 0, "delta": {"content": delta}}]
+                    })
+                )
+            })
+            .collect::<String>()
+            + "data: [DONE]\n\n";
+        let base_url = gzip_backend("text/event-stream", events).await;
+
+        let (status, body) = proxy_chat(base_url, true).await;
+
+        assert_eq!(status, 200, "{}", body);
+        let content: String = body
+            .lines()
+            .filter_map(|line| line.strip_prefix("data: "))
+            .filter_map(|data| serde_json::from_str::<Value>(data).ok())
+            .filter_map(|chunk| {
+                chunk["choices"][0]["delta"]["content"]
+                    .as_str()
+                    .map(str::to_string)
+            })
+            .collect();
+        assert_eq!(content, "Hi from a compressing gateway");
+    }
+
+    /// Starts an OpenAI-compatible backend answering every chat request with `status` and
+    /// `body`, and returns its base URL.
+    async fn fixed_backend(status: u16, body: Value) -> String {
+        use axum::{http::StatusCode, routing::post, Json, Router};
+
+        let status = StatusCode::from_u16(status).expect("status");
+        let app = Router::new().route(
+            "/v1/chat/completions",
+            post(move || {
+                let body = body.clone();
+                async move { (status, Json(body)) }
+            }),
+        );
+        format!("{}/v1", spawn_mock_backend(app).await)
+    }
+
+    /// Serves an OpenAI stream that sends its headers right away and its first chunk only after
+    /// `delay`.
+    async fn streaming_backend(delay: std::time::Duration, content: &'static str) -> String {
+        use axum::{body::Body, http::header, routing::post, Router};
+        use futures_util::StreamExt;
+
+        let app = Router::new().route(
+            "/v1/chat/completions",
+            post(move || async move {
+                let chunk = json!({
+                    "id": "chatcmpl-1",
+                    "object": "chat.completion.chunk",
+                    "created": 1,
+                    "model": "backup-model",
+                    "choices": [{"index": 0, "delta": {"content": content}, "finish_reason": "stop"}]
+                });
+                let events = format!("data: {}\n\ndata: [DONE]\n\n", chunk);
+                let body = futures_util::stream::once(async move {
+                    tokio::time::sleep(delay).await;
+                    Ok::<_, std::io::Error>(bytes::Bytes::from(events))
+                })
+                .boxed();
+                (
+                    [(header::CONTENT_TYPE, "text/event-stream")],
+                    Body::from_stream(body),
+                )
+            }),
+        );
+        format!("{}/v1", spawn_mock_backend(app).await)
+    }
+
+    /// Sends an OpenAI chat request for `primary` of a group falling back to `backup`, where
+    /// `primary` is served by a failing provider and `backup` by a working one.
+    async fn proxy_chat_with_fallback(group: &str, stream: bool) -> axum::response::Response {
+        let failing_url = fixed_backend(
+            503,
+            json!({"error": {"message": "The model is overloaded", "type": "server_error"}}),
+        )
+        .await;
+        let working_url = fixed_backend(
+            200,
+            json!({
+                "id": "chatcmpl-1",
+                "object": "chat.completion",
+                "created": 1,
+                "model": "backup-model",
+                "choices": [{
+                    "index": 0,
+                    "message": {"role": "assistant", "content": "Hi from the backup"},
+                    "finish_reason": "stop"
+                }],
+                "usage": {"prompt_tokens": 3, "completion_tokens": 4, "total_tokens": 7}
+            }),
+        )
+        .await;
+        proxy_chat_between(group, stream, failing_url, working_url, 0).await
+    }
+
+    /// Sends an OpenAI chat request for `primary` of a group falling back to `backup`, served
+    /// at `primary_url` and `backup_url`, with the given first-token timeout.
+    async fn proxy_chat_between(
+        group: &str,
+        stream: bool,
+        primary_url: String,
+        backup_url: String,
+        first_token_timeout: u64,
+    ) -> axum::response::Response {
+        use crate::ccproxy::helper::CcproxyQuery;
+        use crate::ccproxy::test_util::{store_with_providers, MockProvider};
+        use crate::constants::CFG_CCPROXY_FIRST_TOKEN_TIMEOUT;
+        use crate::db::ProxyGroup;
+        use axum::response::IntoResponse;
+
+        let (main_store, _) = store_with_providers(
+            vec![
+                MockProvider::new("Failing", "primary-model", primary_url),
+                MockProvider::new("Working", "backup-model", backup_url),
+            ],
+            group,
+            &[("primary", 0), ("backup", 1)],
+        );
+        {
+            let mut store = main_store.write().expect("store lock");
+            store
+                .proxy_group_add(&ProxyGroup {
+                    name: group.to_string(),
+                    metadata: Some(json!({"fallbackModels": ["backup"]})),
+                    ..Default::default()
+                })
+                .expect("add group");
+            store
+                .set_config(CFG_CCPROXY_FIRST_TOKEN_TIMEOUT, &json!(first_token_timeout))
+                .expect("save first-token timeout");
         }
-        ChatProtocol::Claude => OutputAdapterEnum::Claude(ClaudeOutputAdapter),
-        ChatProtocol::Gemini => OutputAdapterEnum::Gemini(GeminiOutputAdapter),
-        ChatProtocol::Ollama => OutputAdapterEnum::Ollama(OllamaOutputAdapter),
-    };
 
-    execute_unified_chat_request(
-        chat_protocol,
-        client_headers,
-        unified_request,
-        proxy_alias,
-        proxy_model,
-        is_streaming_request,
-        tool_compat_mode,
-        final_tool_compat_mode,
-        message_id,
-        log_org_to_file,
-        log_proxy_to_file,
-        main_store_arc,
-        output_adapter,
-    )
-    .await
+        let body = json!({
+            "model": "primary",
+            "stream": stream,
+            "messages": [{ "role": "user", "content": "Hello" }]
+        });
+        super::handle_chat_completion(
+            ChatProtocol::OpenAI,
+            http::HeaderMap::new(),
+            CcproxyQuery {
+                key: None,
+                debug: None,
+            },
+            bytes::Bytes::from(body.to_string()),
+            Some(group.to_string()),
+            false,
+            String::new(),
+            "generateContent".to_string(),
+            main_store,
+        )
+        .await
+        .into_response()
+    }
+
+    #[tokio::test]
+    async fn failed_primary_model_falls_back_to_the_next_model() {
+        use crate::ccproxy::helper::fallback::FALLBACK_MODEL_HEADER;
+
+        let response = proxy_chat_with_fallback("fallback-non-streaming", false).await;
+
+        assert_eq!(response.status().as_u16(), 200);
+        assert_eq!(
+            response
+                .headers()
+                .get(FALLBACK_MODEL_HEADER)
+                .and_then(|v| v.to_str().ok()),
+            Some("backup")
+        );
+        let bytes = axum::body::to_bytes(response.into_body(), 1024 * 1024)
+            .await
+            .expect("read proxy response");
+        let body: Value = serde_json::from_slice(&bytes).expect("JSON response");
+        assert_eq!(
+            body["choices"][0]["message"]["content"],
+            "Hi from the backup"
+        );
+    }
+
+    #[tokio::test]
+    async fn streaming_requests_do_not_fall_back_by_default() {
+        let response = proxy_chat_with_fallback("fallback-streaming", true).await;
+        assert_eq!(response.status().as_u16(), 503);
+    }
+
+    #[tokio::test]
+    async fn stream_without_first_token_in_time_falls_back() {
+        use crate::ccproxy::helper::fallback::FALLBACK_MODEL_HEADER;
+        use std::time::Duration;
+
+        let slow_url = streaming_backend(Duration::from_secs(30), "Hi from the primary").await;
+        let fast_url = streaming_backend(Duration::ZERO, "Hi from the backup").await;
+        let response =
+            proxy_chat_between("fallback-first-token", true, slow_url, fast_url, 1).await;
+
+        assert_eq!(response.status().as_u16(), 200);
+        assert_eq!(
+            response
+                .headers()
+                .get(FALLBACK_MODEL_HEADER)
+                .and_then(|v| v.to_str().ok()),
+            Some("backup")
+        );
+        let bytes = tokio::time::timeout(
+            Duration::from_secs(10),
+            axum::body::to_bytes(response.into_body(), 1024 * 1024),
+        )
+        .await
+        .expect("stream finished")
+        .expect("read proxy response");
+        let text = String::from_utf8_lossy(&bytes);
+        assert!(text.contains("Hi from the backup"), "{}", text);
+        assert!(!text.contains("Hi from the primary"));
+    }
 }
@@ -0,0 +1,217 @@
+use axum::response::Response;
+use reqwest::header::HeaderMap;
+use rust_i18n::t;
+use std::sync::{Arc, RwLock};
+
+use crate::ccproxy::{
+    adapter::{
+        input::from_openai_completions,
+        output::{OpenAICompletionsOutputAdapter, OutputAdapterEnum},
+    },
+    errors::{CCProxyError, ProxyResult},
+    handler::chat_handler::{
+        execute_unified_chat_request, prepare_unified_request_for_proxy_model,
+    },
+    helper::{
+        audit_log, budget, concurrency,
+        dead_letter::{DeadLetterContext, COMPLETIONS_PROTOCOL},
+        get_msg_id,
+        redaction::Redactor,
+        CcproxyQuery, ModelResolver,
+    },
+    types::openai_completions::OpenAICompletionsRequest,
+    ChatProtocol,
+};
+use crate::constants::{CFG_CCPROXY_LOG_PROXY_TO_FILE, CFG_CCPROXY_LOG_TO_FILE};
+use crate::db::MainStore;
+
+/// Serves the legacy `/v1/completions` API on top of the chat pipeline.
+///
+/// The prompt is sent as a single user message to the resolved model, and its answer is
+/// returned as a `text_completion`, streamed or not.
+pub async fn handle_completions(
+    client_headers: HeaderMap,
+    client_query: CcproxyQuery,
+    client_request_body: bytes::Bytes,
+    group_name: Option<String>,
+    tool_compat_mode: bool,
+    main_store_arc: Arc<RwLock<MainStore>>,
+) -> ProxyResult<Response> {
+    let message_id = get_msg_id();
+    let log_org_to_file = if let Ok(store) = main_store_arc.read() {
+        store.get_config(CFG_CCPROXY_LOG_TO_FILE, false)
+    } else {
+        client_query.debug.unwrap_or(false)
+    };
+    let log_proxy_to_file = if let Ok(store) = main_store_arc.read() {
+        store.get_config(CFG_CCPROXY_LOG_PROXY_TO_FILE, false)
+    } else {
+        false
+    };
+
+    // Optional redaction of personal data, before anything leaves the machine
+    let redactor = Redactor::from_store(&main_store_arc);
+    let (client_request_body, placeholders) = match &redactor {
+        Some(redactor) => redactor.redact_request(client_request_body),
+        None => (client_request_body, Default::default()),
+    };
+
+    let client_request_payload: OpenAICompletionsRequest =
+        serde_json::from_slice(&client_request_body).map_err(|e| {
+            CCProxyError::InternalError(
+                t!("proxy.error.invalid_request_format", error = e.to_string()).to_string(),
+            )
+        })?;
+
+    if log_org_to_file {
+        log::info!(target: "ccproxy_logger", "message id:{}\nOpenAI Completions Origin Request Body: \n{}\n----------------\n", &message_id, String::from_utf8_lossy(&client_request_body));
+    }
+
+    let proxy_alias_raw = client_request_payload.model.clone();
+    let (proxy_alias, group_name) = if let Some((g, a)) = proxy_alias_raw.split_once('@') {
+        (a.to_string(), Some(g.to_string()))
+    } else {
+        (proxy_alias_raw, group_name)
+    };
+
+    budget::check_budget(&main_store_arc)?;
+
+    let proxy_model = if let Some(provider_id) = client_headers
+        .get("x-cs-provider-id")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|s| s.parse::<i64>().ok())
+    {
+        let model_id = client_headers
+            .get("x-cs-model-id")
+            .and_then(|v| v.to_str().ok())
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| proxy_alias.clone());
+
+        ModelResolver::get_ai_model_by_provider_and_model(
+            main_store_arc.clone(),
+            provider_id,
+            model_id,
+        )
+        .await?
+    } else {
+        ModelResolver::get_ai_model_by_alias(
+            main_store_arc.clone(),
+            proxy_alias.clone(),
+            group_name.as_deref(),
+        )
+        .await?
+    };
+
+    let slot = concurrency::acquire_slot(proxy_model.concurrency_limit.as_ref()).await?;
+
+    audit_log::record(
+        &main_store_arc,
+        &message_id,
+        COMPLETIONS_PROTOCOL,
+        (&proxy_model).into(),
+        &client_request_body,
+    );
+
+    let dead_letter = DeadLetterContext::new(
+        &message_id,
+        COMPLETIONS_PROTOCOL.to_string(),
+        &proxy_model,
+        &client_request_body,
+    );
+    let dead_letter_store = main_store_arc.clone();
+    let result = async {
+        let final_tool_compat_mode = match proxy_model.tool_compat_mode.as_deref() {
+            Some("compat") => true,
+            Some("native") => false,
+            Some("auto") | None => tool_compat_mode,
+            _ => tool_compat_mode,
+        };
+
+        let is_streaming_request = client_request_payload.stream.unwrap_or(false);
+        let echo_prompt = if client_request_payload.echo.unwrap_or(false) {
+            client_request_payload.prompt_text().ok()
+        } else {
+            None
+        };
+        let mut unified_request =
+            from_openai_completions(client_request_payload, final_tool_compat_mode).map_err(
+                |e| {
+                    CCProxyError::InternalError(
+                        t!("proxy.error.invalid_request", error = e.to_string()).to_string(),
+                    )
+                },
+            )?;
+        prepare_unified_request_for_proxy_model(&mut unified_request, &proxy_model);
+
+        execute_unified_chat_request(
+            ChatProtocol::OpenAI,
+            client_headers,
+            unified_request,
+            proxy_alias,
+            proxy_model,
+            is_streaming_request,
+            tool_compat_mode,
+            final_tool_compat_mode,
+            message_id,
+            log_org_to_file,
+            log_proxy_to_file,
+            main_store_arc,
+            OutputAdapterEnum::OpenAICompletions(OpenAICompletionsOutputAdapter { echo_prompt }),
+        )
+        .await
+    }
+    .await;
+    let response = dead_letter.capture(&dead_letter_store, result).await?;
+    let response = match &redactor {
+        Some(redactor) => redactor.restore_response(response, placeholders),
+        None => response,
+    };
+    Ok(concurrency::hold_slot_until_body_end(response, slot))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ccproxy::test_util::{echo_backend, store_with_alias};
+    use crate::constants::CFG_CCPROXY_REDACTION;
+    use axum::body::to_bytes;
+    use serde_json::{json, Value};
+
+    #[tokio::test]
+    async fn test_prompt_is_redacted_upstream_and_restored_in_the_answer() {
+        let (base_url, mut received) = echo_backend().await;
+        let main_store = store_with_alias(base_url, "redact-completions");
+        main_store
+            .write()
+            .expect("store lock")
+            .set_config(CFG_CCPROXY_REDACTION, &json!({"enabled": true}))
+            .expect("save redaction config");
+
+        let body = json!({"model": "echo", "prompt": "Reply to jane@example.com"});
+        let response = handle_completions(
+            HeaderMap::new(),
+            CcproxyQuery {
+                key: None,
+                debug: None,
+            },
+            bytes::Bytes::from(body.to_string()),
+            Some("redact-completions".to_string()),
+            false,
+            main_store,
+        )
+        .await
+        .expect("completion");
+
+        let upstream = received.recv().await.expect("upstream request").to_string();
+        assert!(upstream.contains("[EMAIL_1]"), "{}", upstream);
+        assert!(!upstream.contains("jane@example.com"));
+
+        let answer: Value = serde_json::from_slice(
+            &to_bytes(response.into_body(), usize::MAX)
+                .await
+                .expect("body"),
+        )
+        .expect("JSON answer");
+        assert_eq!(answer["choices"][0]["text"], "Reply to jane@example.com");
+    }
+}
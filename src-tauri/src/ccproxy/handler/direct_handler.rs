@@ -1,5 +1,15 @@
-use crate::ccproxy::adapter::unified::{SseStatus, StreamLogRecorder, UnifiedFunctionCallPart};
-use crate::ccproxy::helper::{get_tool_id, send_with_retry, RetryConfig};
+use crate::ccproxy::adapter::error::{detect_protocol_mismatch, normalize_backend_error};
+use crate::ccproxy::adapter::output::{
+    ClaudeOutputAdapter, GeminiOutputAdapter, OllamaOutputAdapter, OpenAIOutputAdapter,
+    OutputAdapterEnum,
+};
+use crate::ccproxy::adapter::unified::{
+    SseStatus, StreamLogRecorder, UnifiedFunctionCallPart, UnifiedTool,
+};
+use crate::ccproxy::helper::{
+    budget, first_token, get_tool_id, send_with_retry, sse::SseLineBuffer,
+    stream_handler::stream_stall_timeout, stream_with_stall_timeout, RetryConfig,
+};
 use crate::ccproxy::openai::OpenAIUsage;
 use crate::ccproxy::utils::token_estimator::estimate_tokens;
 use crate::ccproxy::{
@@ -14,7 +24,7 @@ use crate::db::{CcproxyStat, MainStore};
 use axum::body::Body;
 use axum::response::Response;
 use futures_util::stream::StreamExt;
-use http::HeaderMap;
+use http::{HeaderMap, StatusCode};
 use reqwest::header::{HeaderName as ReqwestHeaderName, HeaderValue as ReqwestHeaderValue};
 use rust_i18n::t;
 use serde_json::{json, Value};
@@ -168,34 +178,33 @@ pub async fn handle_direct_forward(
                 t!("network.response_read_error", error = e.to_string()).to_string(),
             )
         })?;
-        let filtered_headers = crate::ccproxy::utils::http::filter_proxy_headers(&response_headers);
-        let mut response = Response::builder()
-            .status(status_code)
-            .body(Body::from(error_body_bytes.clone()))
-            .map_err(|e| {
-                CCProxyError::InternalError(format!("Failed to build error response: {}", e))
-            })?;
-
-        *response.headers_mut() = filtered_headers;
+        let (response, error_msg) = upstream_error_response(
+            &proxy_model.chat_protocol,
+            status_code,
+            &response_headers,
+            error_body_bytes,
+        )?;
 
         // Record error for non-streaming direct forward
-        let error_msg = String::from_utf8_lossy(&error_body_bytes).to_string();
         if let Ok(store) = main_store_arc.read() {
-            let _ = store.record_ccproxy_stat(CcproxyStat {
-                id: None,
-                client_model: proxy_model.client_alias.clone(),
-                backend_model: model_name.clone(),
-                provider_id: Some(proxy_model.provider_id),
-                provider: provider_name.clone(),
-                protocol: chat_protocol_for_stat.to_string(),
-                tool_compat_mode: 0,
-                status_code: status_code.as_u16() as i32,
-                error_message: Some(error_msg.clone()),
-                input_tokens: 0,
-                output_tokens: 0,
-                cache_tokens: 0,
-                request_at: None,
-            });
+            budget::record_stat(
+                &store,
+                CcproxyStat {
+                    id: None,
+                    client_model: proxy_model.client_alias.clone(),
+                    backend_model: model_name.clone(),
+                    provider_id: Some(proxy_model.provider_id),
+                    provider: provider_name.clone(),
+                    protocol: chat_protocol_for_stat.to_string(),
+                    tool_compat_mode: 0,
+                    status_code: status_code.as_u16() as i32,
+                    error_message: Some(error_msg.clone()),
+                    input_tokens: 0,
+                    output_tokens: 0,
+                    cache_tokens: 0,
+                    request_at: None,
+                },
+            );
         }
 
         log_direct_backend_error(&format!(
@@ -216,6 +225,39 @@ pub async fn handle_direct_forward(
         return Ok(response);
     }
 
+    let target_response = match first_token::first_token_timeout(&main_store_arc) {
+        Some(timeout) if is_streaming_request => {
+            match first_token::await_first_token(target_response, timeout).await {
+                Ok(response) => response,
+                Err(error) => {
+                    if let Ok(store) = main_store_arc.read() {
+                        budget::record_stat(
+                            &store,
+                            CcproxyStat {
+                                id: None,
+                                client_model: proxy_model.client_alias.clone(),
+                                backend_model: model_name.clone(),
+                                provider_id: Some(proxy_model.provider_id),
+                                provider: provider_name.clone(),
+                                protocol: chat_protocol_for_stat.to_string(),
+                                tool_compat_mode: 0,
+                                status_code: error.status_code().as_u16() as i32,
+                                error_message: Some(error.to_string()),
+                                input_tokens: 0,
+                                output_tokens: 0,
+                                cache_tokens: 0,
+                                request_at: None,
+                            },
+                        );
+                    }
+                    log_direct_backend_error(&error.to_string());
+                    return Err(error);
+                }
+            }
+        }
+        _ => target_response,
+    };
+
     let mut response_builder = Response::builder().status(status_code);
     let filtered_headers = crate::ccproxy::utils::http::filter_proxy_headers(&response_headers);
 
@@ -230,6 +272,7 @@ pub async fn handle_direct_forward(
         )));
         let chat_protocol = proxy_model.chat_protocol.clone();
         let log_recorder_clone = log_recorder.clone();
+        let line_buffer = Arc::new(Mutex::new(SseLineBuffer::default()));
         let sse_status = Arc::new(RwLock::new(SseStatus::new(
             "".to_string(),
             "".to_string(),
@@ -250,17 +293,24 @@ pub async fn handle_direct_forward(
             tool_compat_mode: false,
         });
 
-        let stream = target_response.bytes_stream().map(move |chunk| {
+        let stall_timeout = stream_stall_timeout(&main_store_arc);
+        let stream = stream_with_stall_timeout(target_response, stall_timeout).map(move |chunk| {
             let sse_status = sse_status.clone();
             let log_recorder = log_recorder_clone.clone();
             let chat_protocol = chat_protocol.clone();
+            let line_buffer = line_buffer.clone();
             // Move the guard into the closure so it lives as long as the stream
             let _guard = stat_guard.clone();
 
             chunk.map(move |bytes| {
+                // Lines split across chunks are parsed once they are complete
+                let lines = line_buffer
+                    .lock()
+                    .map(|mut buffer| buffer.push(&bytes))
+                    .unwrap_or_default();
                 // Always parse for statistics, but only log to file if enabled
                 chunk_parser_and_log(
-                    &bytes,
+                    &lines,
                     log_recorder.clone(),
                     &chat_protocol,
                     sse_status.clone(),
@@ -311,21 +361,24 @@ pub async fn handle_direct_forward(
                     output
                 );
 
-                let _ = store.record_ccproxy_stat(CcproxyStat {
-                    id: None,
-                    client_model: proxy_model.client_alias.clone(),
-                    backend_model: model_name.clone(),
-                    provider_id: Some(proxy_model.provider_id),
-                    provider: provider_name.clone(),
-                    protocol: chat_protocol_for_stat.to_string(),
-                    tool_compat_mode: 0,
-                    status_code: status_code.as_u16() as i32,
-                    error_message: None,
-                    input_tokens: input,
-                    output_tokens: output,
-                    cache_tokens: cache,
-                    request_at: None,
-                });
+                budget::record_stat(
+                    &store,
+                    CcproxyStat {
+                        id: None,
+                        client_model: proxy_model.client_alias.clone(),
+                        backend_model: model_name.clone(),
+                        provider_id: Some(proxy_model.provider_id),
+                        provider: provider_name.clone(),
+                        protocol: chat_protocol_for_stat.to_string(),
+                        tool_compat_mode: 0,
+                        status_code: status_code.as_u16() as i32,
+                        error_message: None,
+                        input_tokens: input,
+                        output_tokens: output,
+                        cache_tokens: cache,
+                        request_at: None,
+                    },
+                );
             }
         }
 
@@ -339,29 +392,147 @@ pub async fn handle_direct_forward(
     }
 }
 
+/// Returns the names declared by a tool entry in the protocol's native format.
+/// Builds the client response for a non-2xx backend answer, along with the message to log.
+///
+/// The status, the headers and the body are passed through as the backend sent them, so the
+/// client sees the backend's own error, streaming requests included. Two bodies are replaced
+/// by a protocol-safe error: one pointing to a misconfigured provider protocol, which gets
+/// the likely fix, and an empty one, which would reach the client without any detail.
+fn upstream_error_response(
+    chat_protocol: &ChatProtocol,
+    status_code: StatusCode,
+    headers: &HeaderMap,
+    body: bytes::Bytes,
+) -> ProxyResult<(Response, String)> {
+    let mut filtered_headers = crate::ccproxy::utils::http::filter_proxy_headers(headers);
+
+    if body.iter().all(u8::is_ascii_whitespace)
+        || detect_protocol_mismatch(chat_protocol, status_code, &body).is_some()
+    {
+        let unified_error = normalize_backend_error(chat_protocol, status_code, headers, &body);
+        let error_msg = unified_error.message.clone();
+        let output_adapter = match chat_protocol {
+            ChatProtocol::OpenAI | ChatProtocol::HuggingFace => {
+                OutputAdapterEnum::OpenAI(OpenAIOutputAdapter)
+            }
+            ChatProtocol::Claude => OutputAdapterEnum::Claude(ClaudeOutputAdapter),
+            ChatProtocol::Gemini => OutputAdapterEnum::Gemini(GeminiOutputAdapter),
+            ChatProtocol::Ollama => OutputAdapterEnum::Ollama(OllamaOutputAdapter),
+        };
+        let mut response = output_adapter.adapt_error_response(unified_error);
+        let final_headers = response.headers_mut();
+        for (name, value) in filtered_headers.iter() {
+            if name != http::header::CONTENT_TYPE {
+                final_headers.insert(name.clone(), value.clone());
+            }
+        }
+        return Ok((response, error_msg));
+    }
+
+    if !filtered_headers.contains_key(http::header::CONTENT_TYPE) {
+        let content_type = if serde_json::from_slice::<Value>(&body).is_ok() {
+            "application/json"
+        } else {
+            "text/plain; charset=utf-8"
+        };
+        filtered_headers.insert(
+            http::header::CONTENT_TYPE,
+            http::HeaderValue::from_static(content_type),
+        );
+    }
+    let error_msg = String::from_utf8_lossy(&body).to_string();
+    let mut response = Response::builder()
+        .status(status_code)
+        .body(Body::from(body))
+        .map_err(|e| {
+            CCProxyError::InternalError(format!("Failed to build error response: {}", e))
+        })?;
+    *response.headers_mut() = filtered_headers;
+    Ok((response, error_msg))
+}
+
+fn direct_tool_names<'a>(tool: &'a Value, chat_protocol: &ChatProtocol) -> Vec<&'a str> {
+    match chat_protocol {
+        ChatProtocol::Claude => tool
+            .get("name")
+            .and_then(|n| n.as_str())
+            .into_iter()
+            .collect(),
+        ChatProtocol::Gemini => tool
+            .get("function_declarations")
+            .and_then(|f| f.as_array())
+            .map(|decls| {
+                decls
+                    .iter()
+                    .filter_map(|f| f.get("name").and_then(|n| n.as_str()))
+                    .collect()
+            })
+            .unwrap_or_default(),
+        _ => tool
+            .get("function")
+            .and_then(|f| f.get("name"))
+            .and_then(|n| n.as_str())
+            .into_iter()
+            .collect(),
+    }
+}
+
+/// Builds a tool entry in the protocol's native format from a unified tool.
+fn direct_tool_definition(tool: &UnifiedTool, chat_protocol: &ChatProtocol) -> Value {
+    let description = tool.description.clone().unwrap_or_default();
+    match chat_protocol {
+        ChatProtocol::Claude => json!({
+            "name": tool.name,
+            "description": description,
+            "input_schema": tool.input_schema,
+        }),
+        ChatProtocol::Gemini => json!({
+            "function_declarations": [{
+                "name": tool.name,
+                "description": description,
+                "parameters": tool.input_schema,
+            }]
+        }),
+        _ => json!({
+            "type": "function",
+            "function": {
+                "name": tool.name,
+                "description": description,
+                "parameters": tool.input_schema,
+            }
+        }),
+    }
+}
+
 /// Enhances the request body sent directly to the AI provider.
 fn enhance_direct_request_body(
     mut body: Value,
     proxy_model: &ProxyModel,
     chat_protocol: &ChatProtocol,
 ) -> Value {
-    // 1. Tool filtering
+    // 1. Forced tools (merged before filtering so the never-include list still wins)
+    if !proxy_model.tool_include.is_empty() {
+        if let Some(body_map) = body.as_object_mut() {
+            let tools = body_map.entry("tools").or_insert_with(|| json!([]));
+            if let Some(tools) = tools.as_array_mut() {
+                for forced in &proxy_model.tool_include {
+                    let present = tools.iter().any(|tool| {
+                        direct_tool_names(tool, chat_protocol).contains(&forced.name.as_str())
+                    });
+                    if !present {
+                        tools.push(direct_tool_definition(forced, chat_protocol));
+                    }
+                }
+            }
+        }
+    }
+
+    // 2. Tool filtering
     if !proxy_model.tool_filter.is_empty() {
         if let Some(tools) = body.get_mut("tools").and_then(|t| t.as_array_mut()) {
             tools.retain(|tool| {
-                let tool_name = match chat_protocol {
-                    ChatProtocol::Claude => tool.get("name").and_then(|n| n.as_str()),
-                    ChatProtocol::Gemini => tool
-                        .get("function_declarations")
-                        .and_then(|f| f.as_array())
-                        .and_then(|f| f.first())
-                        .and_then(|f| f.get("name"))
-                        .and_then(|n| n.as_str()),
-                    _ => tool
-                        .get("function")
-                        .and_then(|f| f.get("name"))
-                        .and_then(|n| n.as_str()),
-                };
+                let tool_name = direct_tool_names(tool, chat_protocol).into_iter().next();
 
                 if let Some(name) = tool_name {
                     return !proxy_model.tool_filter.contains_key(name);
@@ -589,15 +760,13 @@ fn log_direct_stream_response_if_complete(
 }
 
 fn chunk_parser_and_log(
-    bytes: &bytes::Bytes,
+    lines: &[String],
     log_recorder: Arc<Mutex<StreamLogRecorder>>,
     chat_protocol: &ChatProtocol,
     sse_status: Arc<RwLock<SseStatus>>,
     log_to_file: bool,
 ) {
-    let chunk_str = String::from_utf8_lossy(bytes);
-
-    for line in chunk_str.lines() {
+    for line in lines {
         if line.starts_with("data:") {
             let data = &line["data:".len()..].trim();
             if data.is_empty() || *data == "[DONE]" {
@@ -1055,7 +1224,7 @@ fn chunk_parser_and_log(
     }
 
     log_direct_stream_response_if_complete(
-        &chunk_str,
+        &lines.join("\n"),
         log_recorder,
         chat_protocol,
         sse_status,
@@ -1155,3 +1324,126 @@ fn extract_usage_from_value(value: &Value, protocol: &ChatProtocol) -> (i64, i64
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::enhance_direct_request_body;
+    use crate::ccproxy::{test_util::proxy_model_with_tools, ChatProtocol};
+    use crate::test::spawn_mock_backend;
+    use serde_json::json;
+
+    /// Forwards a chat request directly through the proxy to a backend that answers with
+    /// `status` and `body`.
+    async fn direct_forward_error(
+        status: u16,
+        body: &'static str,
+        stream: bool,
+    ) -> (u16, Option<String>, String) {
+        use crate::db::MainStore;
+        use axum::{routing::post, Router};
+        use std::sync::{Arc, RwLock};
+
+        let app = Router::new().route(
+            "/v1/chat/completions",
+            post(move || async move {
+                let mut response = axum::response::Response::builder().status(status);
+                if !body.is_empty() {
+                    response = response.header("Content-Type", "application/json");
+                }
+                response
+                    .body(axum::body::Body::from(body))
+                    .expect("response")
+            }),
+        );
+        let mut proxy_model = proxy_model_with_tools(ChatProtocol::OpenAI);
+        proxy_model.base_url = format!("{}/v1", spawn_mock_backend(app).await);
+        let request = json!({
+            "model": "agent",
+            "stream": stream,
+            "messages": [{ "role": "user", "content": "Hello" }]
+        });
+        let response = super::handle_direct_forward(
+            http::HeaderMap::new(),
+            bytes::Bytes::from(request.to_string()),
+            proxy_model,
+            stream,
+            Arc::new(RwLock::new(
+                MainStore::new(":memory:").expect("in-memory store"),
+            )),
+            false,
+        )
+        .await
+        .expect("direct forward");
+        let status = response.status().as_u16();
+        let content_type = response
+            .headers()
+            .get(http::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        let bytes = axum::body::to_bytes(response.into_body(), 1024 * 1024)
+            .await
+            .expect("read proxy response");
+        (
+            status,
+            content_type,
+            String::from_utf8_lossy(&bytes).into_owned(),
+        )
+    }
+
+    #[tokio::test]
+    async fn upstream_error_body_is_passed_through() {
+        let upstream = r#"{"error":{"message":"Method POST is not allowed for this model","type":"invalid_request_error","param":null,"code":"method_not_allowed"}}"#;
+        for stream in [false, true] {
+            let (status, content_type, body) = direct_forward_error(405, upstream, stream).await;
+            assert_eq!(status, 405);
+            assert_eq!(content_type.as_deref(), Some("application/json"));
+            assert!(!body.is_empty());
+            assert_eq!(body, upstream);
+        }
+    }
+
+    #[tokio::test]
+    async fn empty_upstream_error_body_is_replaced_by_a_protocol_error() {
+        let (status, _, body) = direct_forward_error(405, "", true).await;
+        assert_eq!(status, 405);
+        let body: serde_json::Value = serde_json::from_str(&body).expect("JSON error body");
+        assert_eq!(body["error"]["message"], "Method Not Allowed");
+    }
+
+    #[test]
+    fn forced_tools_are_added_to_openai_body_without_tools() {
+        let proxy_model = proxy_model_with_tools(ChatProtocol::OpenAI);
+        let body = json!({"model": "agent", "messages": []});
+
+        let body = enhance_direct_request_body(body, &proxy_model, &ChatProtocol::OpenAI);
+
+        let tools = body["tools"].as_array().cloned().unwrap_or_default();
+        assert_eq!(tools.len(), 1);
+        assert_eq!(tools[0]["type"], "function");
+        assert_eq!(tools[0]["function"]["name"], "web_search");
+        assert_eq!(tools[0]["function"]["parameters"]["type"], "object");
+    }
+
+    #[test]
+    fn forced_tools_use_native_claude_and_gemini_formats() {
+        let claude = enhance_direct_request_body(
+            json!({"tools": [{"name": "read_file", "input_schema": {"type": "object"}}]}),
+            &proxy_model_with_tools(ChatProtocol::Claude),
+            &ChatProtocol::Claude,
+        );
+        assert_eq!(claude["tools"][0]["name"], "read_file");
+        assert_eq!(claude["tools"][1]["name"], "web_search");
+        assert_eq!(claude["tools"][1]["input_schema"]["type"], "object");
+        assert_eq!(claude["tools"].as_array().map(|t| t.len()), Some(2));
+
+        let gemini = enhance_direct_request_body(
+            json!({"tools": [{"function_declarations": [
+                {"name": "read_file"},
+                {"name": "web_search"}
+            ]}]}),
+            &proxy_model_with_tools(ChatProtocol::Gemini),
+            &ChatProtocol::Gemini,
+        );
+        assert_eq!(gemini["tools"].as_array().map(|t| t.len()), Some(1));
+    }
+}
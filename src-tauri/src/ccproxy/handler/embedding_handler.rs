@@ -21,7 +21,8 @@ use crate::ccproxy::{
     errors::CCProxyError,
     gemini::GeminiEmbedRequest,
     helper::{
-        get_provider_embedding_full_url, send_with_retry, CcproxyQuery, ModelResolver, RetryConfig,
+        budget, concurrency, get_provider_embedding_full_url, send_with_retry, CcproxyQuery,
+        ModelResolver, RetryConfig,
     },
     openai::OpenAIEmbeddingRequest,
     types::ollama::{OllamaEmbedRequest, OllamaEmbeddingsRequest},
@@ -126,12 +127,16 @@ pub async fn handle_embedding(
         route_model_alias.clone(),
     )?;
 
+    budget::check_budget(&store_arc)?;
+
     let proxy_model = ModelResolver::get_ai_model_by_alias(
         store_arc.clone(),
         proxy_alias.clone(),
         group_name.as_deref(),
     )
     .await?;
+    // Embedding responses are read in full, so the slot is held until the handler returns
+    let _slot = concurrency::acquire_slot(proxy_model.concurrency_limit.as_ref()).await?;
 
     let backend_adapter: Arc<dyn BackendAdapter> = match proxy_model.chat_protocol {
         ChatProtocol::OpenAI | ChatProtocol::HuggingFace => Arc::new(OpenAIBackendAdapter),
@@ -195,6 +200,7 @@ pub async fn handle_embedding(
     let backend_response = crate::ccproxy::adapter::backend::BackendResponse {
         body: body_bytes,
         tool_compat_mode: false,
+        code_reflow: Default::default(),
     };
 
     let unified_response = backend_adapter
@@ -217,21 +223,24 @@ pub async fn handle_embedding(
 
     // Record stats
     if let Ok(store) = store_arc.read() {
-        let _ = store.record_ccproxy_stat(CcproxyStat {
-            id: None,
-            client_model: proxy_alias,
-            backend_model: proxy_model.model.clone(),
-            provider_id: Some(proxy_model.provider_id),
-            provider: proxy_model.provider.clone(),
-            protocol: chat_protocol.to_string(),
-            tool_compat_mode: 0,
-            status_code: status_code.as_u16() as i32,
-            error_message: None,
-            input_tokens: 0,
-            output_tokens: 0,
-            cache_tokens: 0,
-            request_at: Some(chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string()),
-        });
+        budget::record_stat(
+            &store,
+            CcproxyStat {
+                id: None,
+                client_model: proxy_alias,
+                backend_model: proxy_model.model.clone(),
+                provider_id: Some(proxy_model.provider_id),
+                provider: proxy_model.provider.clone(),
+                protocol: chat_protocol.to_string(),
+                tool_compat_mode: 0,
+                status_code: status_code.as_u16() as i32,
+                error_message: None,
+                input_tokens: 0,
+                output_tokens: 0,
+                cache_tokens: 0,
+                request_at: Some(chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string()),
+            },
+        );
     } else {
         log::error!("Failed to acquire store lock for recording ccproxy stats");
     }
@@ -1,18 +1,28 @@
 use axum::Json;
+use futures::future::join_all;
 use lazy_static::*;
 use regex::Regex;
 use serde_json::{json, Value};
 use sha2::{Digest, Sha256};
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    future::Future,
+    sync::Arc,
+    time::Duration,
+};
 
 use crate::{
+    ai::{interaction::chat_completion::list_models_async, traits::chat::ModelDetails},
     ccproxy::{
         errors::{CCProxyError, ProxyResult},
         types::BackendModelTarget,
     },
-    db::MainStore,
+    db::{AiModel, MainStore},
 };
 
+/// How long a backend may take to list its models before it is left out of the aggregate.
+const BACKEND_LIST_MODELS_TIMEOUT_SECS: u64 = 10;
+
 // =================================================
 //  chat completion proxy
 // =================================================
@@ -40,34 +50,70 @@ pub async fn handle_list_models(
 
     let keys = config.keys().cloned().collect::<Vec<String>>();
     let mut models_info: HashMap<String, (i32, String)> = HashMap::new();
-    let store = main_store
-        .read()
-        .map_err(|e| CCProxyError::StoreLockError(e.to_string()))?;
-
-    for (alias, target) in config.into_iter() {
-        // If the alias has already been processed and added to `models_info`, skip it.
-        // This prevents re-processing the same alias.
-        if models_info.contains_key(&alias) {
-            continue;
+    let mut backends: Vec<AiModel> = Vec::new();
+    let mut backend_ids: HashSet<i64> = HashSet::new();
+    // The store guard is not `Send`, so it must be gone before the backends are queried
+    let backend_timeout = {
+        let store = main_store
+            .read()
+            .map_err(|e| CCProxyError::StoreLockError(e.to_string()))?;
+
+        // Groups with `aggregateBackendModels` also list the models reported by their backends.
+        let group_metadata = store
+            .config
+            .get_proxy_group_by_name(group)
+            .ok()
+            .and_then(|g| g.metadata);
+        let aggregate_backends = group_metadata
+            .as_ref()
+            .and_then(|m| m.get("aggregateBackendModels"))
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        let backend_timeout = Duration::from_secs(
+            group_metadata
+                .as_ref()
+                .and_then(|m| m.get("listModelsTimeoutSecs"))
+                .and_then(|v| v.as_u64())
+                .filter(|secs| *secs > 0)
+                .unwrap_or(BACKEND_LIST_MODELS_TIMEOUT_SECS),
+        );
+        if aggregate_backends {
+            for t in config.values().flatten() {
+                if t.id > 0 && backend_ids.insert(t.id) {
+                    match store.config.get_ai_model_by_id(t.id) {
+                        Ok(backend) if !backend.disabled => backends.push(backend),
+                        _ => {}
+                    }
+                }
+            }
         }
 
-        // Iterate through the `target` items associated with the current `alias`.
-        for t in target.into_iter() {
-            // Only consider target items with a positive ID.
-            if t.id > 0 {
-                // Attempt to retrieve the AI model from the store using the target item's ID.
-                if let Ok(model) = store.config.get_ai_model_by_id(t.id) {
-                    // If a model is successfully retrieved, insert its details into `models_info`.
-                    // We clone `alias` because the `for` loop consumes it by value,
-                    // and we need to move it into the HashMap.
-                    models_info.insert(alias.clone(), (model.max_tokens, model.name.clone()));
-                    // Since we found a model for this alias, we can stop searching
-                    // further in `target` for this specific alias.
-                    break;
+        for (alias, target) in config.into_iter() {
+            // If the alias has already been processed and added to `models_info`, skip it.
+            // This prevents re-processing the same alias.
+            if models_info.contains_key(&alias) {
+                continue;
+            }
+
+            // Iterate through the `target` items associated with the current `alias`.
+            for t in target.into_iter() {
+                // Only consider target items with a positive ID.
+                if t.id > 0 {
+                    // Attempt to retrieve the AI model from the store using the target item's ID.
+                    if let Ok(model) = store.config.get_ai_model_by_id(t.id) {
+                        // If a model is successfully retrieved, insert its details into `models_info`.
+                        // We clone `alias` because the `for` loop consumes it by value,
+                        // and we need to move it into the HashMap.
+                        models_info.insert(alias.clone(), (model.max_tokens, model.name.clone()));
+                        // Since we found a model for this alias, we can stop searching
+                        // further in `target` for this specific alias.
+                        break;
+                    }
                 }
             }
         }
-    }
+        backend_timeout
+    };
 
     let mut sorted_keys = keys.clone();
     sorted_keys.sort();
@@ -76,7 +122,7 @@ pub async fn handle_list_models(
         log::info!("'chat_completion_proxy' configuration is empty or not found. Returning empty model list.");
     };
 
-    let models_list: Vec<Value> = sorted_keys
+    let mut models_list: Vec<Value> = sorted_keys
         .into_iter()
         .map(|alias| {
             let (max_token, provider) = if let Some(m) = models_info.get(&alias) {
@@ -104,6 +150,25 @@ pub async fn handle_list_models(
         })
         .collect();
 
+    if !backends.is_empty() {
+        let aggregated = aggregate_backend_models(backends, backend_timeout, |backend| {
+            let main_store = main_store.clone();
+            async move {
+                list_models_async(
+                    main_store,
+                    backend.api_protocol.clone(),
+                    Some(backend.base_url.as_str()),
+                    Some(backend.api_key.as_str()),
+                    backend.metadata.clone(),
+                )
+                .await
+                .map_err(|e| e.to_string())
+            }
+        })
+        .await;
+        models_list.extend(aggregated);
+    }
+
     let first_id = models_list
         .first()
         .and_then(|m| m["id"].as_str())
@@ -124,6 +189,71 @@ pub async fn handle_list_models(
     Ok(Json(response))
 }
 
+/// Lists the models of every backend concurrently and tags each with the provider it came from.
+///
+/// A backend that fails or does not answer within `timeout` is skipped with a warning, so one
+/// unreachable provider does not empty the whole list. Models are returned in backend order.
+async fn aggregate_backend_models<F, Fut>(
+    backends: Vec<AiModel>,
+    timeout: Duration,
+    fetch: F,
+) -> Vec<Value>
+where
+    F: Fn(AiModel) -> Fut,
+    Fut: Future<Output = Result<Vec<ModelDetails>, String>>,
+{
+    let requests = backends.into_iter().map(|backend| {
+        let name = backend.name.clone();
+        let provider_id = backend.id.unwrap_or_default();
+        let request = tokio::time::timeout(timeout, fetch(backend));
+        async move { (name, provider_id, request.await) }
+    });
+
+    let created_unix = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let mut models = Vec::new();
+    for (provider, provider_id, result) in join_all(requests).await {
+        let details = match result {
+            Ok(Ok(details)) => details,
+            Ok(Err(e)) => {
+                log::warn!("Skipping models of backend '{}': {}", provider, e);
+                continue;
+            }
+            Err(_) => {
+                log::warn!(
+                    "Skipping models of backend '{}': no answer within {}s",
+                    provider,
+                    timeout.as_secs()
+                );
+                continue;
+            }
+        };
+
+        let mut seen = HashSet::new();
+        for model in details {
+            if !seen.insert(model.id.clone()) {
+                continue;
+            }
+            models.push(json!({
+                "id": model.id,
+                "object": "model",
+                "type": "model",
+                "created": created_unix,
+                "created_at": chrono::Utc::now().to_rfc3339(),
+                "display_name": model.name,
+                "owned_by": provider,
+                "provider": provider,
+                "provider_id": provider_id,
+                "max_tokens": model.max_output_tokens.map(i64::from).unwrap_or(-1),
+                "powered_by": "chatspeed ccproxy",
+            }));
+        }
+    }
+    models
+}
+
 /// List proxied models handler for ollama
 /// GET `/api/tags`
 pub async fn handle_ollama_tags(
@@ -362,3 +492,72 @@ pub async fn handle_gemini_list_models(
 
     Ok(Json(response))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ccproxy::ChatProtocol;
+
+    fn model(id: &str) -> ModelDetails {
+        ModelDetails {
+            id: id.to_string(),
+            name: id.to_string(),
+            protocol: ChatProtocol::OpenAI,
+            max_input_tokens: None,
+            max_output_tokens: Some(8192),
+            description: None,
+            last_updated: None,
+            family: None,
+            reasoning: None,
+            function_call: None,
+            image_input: None,
+            metadata: None,
+        }
+    }
+
+    fn backend(id: i64, name: &str) -> AiModel {
+        AiModel {
+            id: Some(id),
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_aggregate_skips_backend_that_times_out() {
+        let backends = vec![backend(1, "OpenAI"), backend(2, "Slow Ollama")];
+
+        let models =
+            aggregate_backend_models(backends, Duration::from_millis(50), |b| async move {
+                if b.name == "Slow Ollama" {
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                    return Ok(vec![model("llama3")]);
+                }
+                Ok(vec![model("gpt-4o"), model("gpt-4o-mini"), model("gpt-4o")])
+            })
+            .await;
+
+        let ids: Vec<&str> = models.iter().filter_map(|m| m["id"].as_str()).collect();
+        assert_eq!(ids, vec!["gpt-4o", "gpt-4o-mini"]);
+        assert_eq!(models[0]["provider"], "OpenAI");
+        assert_eq!(models[0]["provider_id"], 1);
+        assert_eq!(models[0]["max_tokens"], 8192);
+    }
+
+    #[tokio::test]
+    async fn test_aggregate_skips_failing_backend() {
+        let backends = vec![backend(1, "Broken"), backend(2, "Gemini")];
+
+        let models = aggregate_backend_models(backends, Duration::from_secs(1), |b| async move {
+            match b.name.as_str() {
+                "Broken" => Err("401 unauthorized".to_string()),
+                _ => Ok(vec![model("gemini-2.5-pro")]),
+            }
+        })
+        .await;
+
+        assert_eq!(models.len(), 1);
+        assert_eq!(models[0]["provider"], "Gemini");
+        assert_eq!(models[0]["owned_by"], "Gemini");
+    }
+}
@@ -1,14 +1,28 @@
+mod benchmark_handler;
 mod chat_handler;
+mod completions_handler;
+mod count_tokens_handler;
 mod direct_handler;
 mod embedding_handler;
 mod list_models_handler;
 pub mod ollama_extra_handler;
+mod replay_handler;
 mod request_preprocessor;
 mod responses_handler;
+mod setup_wizard_handler;
+mod warmup_handler;
 
+pub use benchmark_handler::{benchmark_models, BenchmarkReport, BenchmarkTarget};
 pub use chat_handler::handle_chat_completion;
+pub use completions_handler::handle_completions;
+pub use count_tokens_handler::handle_count_tokens;
 pub use direct_handler::handle_direct_forward;
 pub use embedding_handler::handle_embedding;
 pub use list_models_handler::{handle_gemini_list_models, handle_list_models, handle_ollama_tags};
 pub use ollama_extra_handler::handle_ollama_show;
+pub use replay_handler::{replay_dead_letter, ReplayResult, ReplayTarget};
 pub use responses_handler::handle_responses;
+pub use setup_wizard_handler::{apply_setup_preset, setup_presets, SetupPreset, SetupWizardResult};
+pub use warmup_handler::{
+    get_group_warmup, warm_up_enabled_group, warm_up_group, GroupWarmup, CCPROXY_WARMUP_EVENT,
+};
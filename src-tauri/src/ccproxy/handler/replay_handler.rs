@@ -0,0 +1,287 @@
+//! Replays a request from the dead-letter log against another model or proxy group.
+//!
+//! The stored client request is sent through the same handler that served it, without the
+//! original client, so a failure can be narrowed down to the model or the configuration.
+//! Replays always run non-streaming and use the provider keys of the current configuration.
+
+use axum::body::to_bytes;
+use axum::response::IntoResponse;
+use reqwest::header::{HeaderMap, HeaderValue};
+use rust_i18n::t;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+use std::time::Instant;
+
+use crate::ccproxy::{
+    errors::{CCProxyError, ProxyResult},
+    helper::{
+        dead_letter::{COMPLETIONS_PROTOCOL, RESPONSES_PROTOCOL},
+        CcproxyQuery,
+    },
+    ChatProtocol,
+};
+use crate::db::MainStore;
+
+use super::{handle_chat_completion, handle_completions, handle_responses};
+
+/// Largest response body returned to the caller.
+const MAX_REPLAY_RESPONSE_BYTES: usize = 16 * 1024 * 1024;
+
+/// Where a stored request is replayed to.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplayTarget {
+    /// Proxy alias, or the backend model id when `provider_id` is set
+    pub model: String,
+    /// Proxy group the alias is resolved in
+    #[serde(default)]
+    pub group: Option<String>,
+    /// Sends the request straight to this provider, bypassing alias resolution
+    #[serde(default)]
+    pub provider_id: Option<i64>,
+    #[serde(default)]
+    pub tool_compat_mode: bool,
+}
+
+/// The answer of a replayed request.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplayResult {
+    pub status_code: u16,
+    /// The response body, parsed as JSON when possible
+    pub body: Value,
+    pub duration_ms: u64,
+}
+
+/// Points the stored body at the target model and turns streaming off.
+fn prepare_replay_body(protocol: &str, body: &mut Value, target: &ReplayTarget) {
+    let Some(object) = body.as_object_mut() else {
+        return;
+    };
+    if protocol != ChatProtocol::Gemini.to_string() {
+        object.insert("model".to_string(), Value::String(target.model.clone()));
+        object.insert("stream".to_string(), Value::Bool(false));
+    }
+}
+
+/// Replays dead letter `dead_letter_id` against `target`.
+pub async fn replay_dead_letter(
+    main_store: Arc<RwLock<MainStore>>,
+    dead_letter_id: i64,
+    target: ReplayTarget,
+) -> ProxyResult<ReplayResult> {
+    let letter = main_store
+        .read()
+        .map_err(|e| CCProxyError::StoreLockError(e.to_string()))?
+        .get_ccproxy_dead_letter(dead_letter_id)
+        .map_err(|e| CCProxyError::InternalError(e.to_string()))?;
+    if letter.request_truncated {
+        return Err(CCProxyError::InternalError(
+            t!("proxy.error.replay_truncated_request").to_string(),
+        ));
+    }
+
+    let mut body: Value = serde_json::from_str(&letter.request_body).map_err(|e| {
+        CCProxyError::InternalError(
+            t!("proxy.error.invalid_request_format", error = e.to_string()).to_string(),
+        )
+    })?;
+    prepare_replay_body(&letter.protocol, &mut body, &target);
+    let body = bytes::Bytes::from(body.to_string());
+
+    let mut headers = HeaderMap::new();
+    if let Some(provider_id) = target.provider_id {
+        headers.insert("x-cs-provider-id", HeaderValue::from(provider_id));
+        let model_id = HeaderValue::from_str(&target.model)
+            .map_err(|e| CCProxyError::InternalError(e.to_string()))?;
+        headers.insert("x-cs-model-id", model_id);
+    }
+    let query = CcproxyQuery {
+        key: None,
+        debug: None,
+    };
+
+    log::info!(
+        "Replaying dead letter {} (request {}, protocol {}) against model '{}', group: {:?}, provider: {:?}",
+        dead_letter_id,
+        &letter.request_id,
+        &letter.protocol,
+        &target.model,
+        &target.group,
+        &target.provider_id
+    );
+
+    let started_at = Instant::now();
+    let result = if letter.protocol == RESPONSES_PROTOCOL {
+        handle_responses(
+            headers,
+            query,
+            body,
+            target.group,
+            target.tool_compat_mode,
+            main_store,
+        )
+        .await
+    } else if letter.protocol == COMPLETIONS_PROTOCOL {
+        handle_completions(
+            headers,
+            query,
+            body,
+            target.group,
+            target.tool_compat_mode,
+            main_store,
+        )
+        .await
+    } else {
+        let protocol = ChatProtocol::from_str(&letter.protocol)?;
+        handle_chat_completion(
+            protocol,
+            headers,
+            query,
+            body,
+            target.group,
+            target.tool_compat_mode,
+            target.model,
+            "generateContent".to_string(),
+            main_store,
+        )
+        .await
+    };
+    let response = result.into_response();
+
+    let status_code = response.status().as_u16();
+    let bytes = to_bytes(response.into_body(), MAX_REPLAY_RESPONSE_BYTES)
+        .await
+        .map_err(|e| {
+            CCProxyError::InternalError(
+                t!("network.response_read_error", error = e.to_string()).to_string(),
+            )
+        })?;
+    let body = serde_json::from_slice(&bytes)
+        .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(&bytes).into_owned()));
+
+    Ok(ReplayResult {
+        status_code,
+        body,
+        duration_ms: started_at.elapsed().as_millis() as u64,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ccproxy::test_util::{store_with_providers, MockProvider};
+    use crate::db::CcproxyDeadLetter;
+    use crate::test::spawn_mock_backend;
+    use axum::{routing::post, Json, Router};
+    use serde_json::json;
+    use tokio::sync::mpsc;
+
+    /// Starts an OpenAI-compatible backend that answers every chat request with `reply`
+    /// and reports the request bodies it received.
+    async fn mock_backend(reply: Value) -> (String, mpsc::UnboundedReceiver<Value>) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let app = Router::new().route(
+            "/v1/chat/completions",
+            post(move |Json(body): Json<Value>| {
+                let sender = sender.clone();
+                let reply = reply.clone();
+                async move {
+                    let _ = sender.send(body);
+                    Json(reply)
+                }
+            }),
+        );
+        (format!("{}/v1", spawn_mock_backend(app).await), receiver)
+    }
+
+    #[tokio::test]
+    async fn test_replays_stored_request_against_another_model() {
+        let (base_url, mut received) = mock_backend(json!({
+            "id": "chatcmpl-1",
+            "object": "chat.completion",
+            "created": 1,
+            "model": "qwen-plus",
+            "choices": [{
+                "index": 0,
+                "message": {"role": "assistant", "content": "pong"},
+                "finish_reason": "stop"
+            }],
+            "usage": {"prompt_tokens": 3, "completion_tokens": 1, "total_tokens": 4}
+        }))
+        .await;
+
+        let (main_store, provider_ids) = store_with_providers(
+            vec![MockProvider::new("Mock", "qwen-plus", base_url)],
+            "",
+            &[],
+        );
+        let provider_id = provider_ids[0];
+        let dead_letter_id = main_store
+            .write()
+            .expect("store lock")
+            .record_ccproxy_dead_letter(
+                &CcproxyDeadLetter {
+                    id: None,
+                    request_id: "msg_failed".to_string(),
+                    client_model: "code-large".to_string(),
+                    backend_model: "glm-4.6".to_string(),
+                    provider_id: Some(99),
+                    provider: "Zhipu".to_string(),
+                    protocol: "openai".to_string(),
+                    backend_protocol: "openai".to_string(),
+                    status_code: 500,
+                    error_message: "internal error".to_string(),
+                    request_body: json!({
+                        "model": "code-large",
+                        "stream": true,
+                        "messages": [{"role": "user", "content": "ping"}]
+                    })
+                    .to_string(),
+                    request_truncated: false,
+                    duration_ms: 12,
+                    created_at: None,
+                },
+                10,
+            )
+            .expect("record dead letter");
+
+        let result = replay_dead_letter(
+            main_store,
+            dead_letter_id,
+            ReplayTarget {
+                model: "qwen-plus".to_string(),
+                provider_id: Some(provider_id),
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("replay");
+
+        assert_eq!(result.status_code, 200);
+        assert_eq!(result.body["choices"][0]["message"]["content"], "pong");
+
+        let sent = received.recv().await.expect("backend received the replay");
+        assert_eq!(sent["model"], "qwen-plus");
+        assert_eq!(sent["stream"], false);
+        assert_eq!(sent["messages"][0]["content"], "ping");
+    }
+
+    #[test]
+    fn test_gemini_body_keeps_model_out_of_the_body() {
+        let target = ReplayTarget {
+            model: "gemini-2.5-flash".to_string(),
+            ..Default::default()
+        };
+        let mut body = json!({"contents": []});
+        prepare_replay_body("gemini", &mut body, &target);
+        assert_eq!(body, json!({"contents": []}));
+
+        let mut body = json!({"model": "old", "messages": []});
+        prepare_replay_body("claude", &mut body, &target);
+        assert_eq!(body["model"], "gemini-2.5-flash");
+        assert_eq!(body["stream"], false);
+    }
+}
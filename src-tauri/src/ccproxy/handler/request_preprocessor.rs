@@ -1,10 +1,132 @@
 use bytes::Bytes;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
-use crate::ccproxy::adapter::unified::{UnifiedRequest, UnifiedToolChoice};
+use crate::ccproxy::adapter::input::helper::empty_messages::filter_empty_messages;
+use crate::ccproxy::adapter::input::helper::role_merge::merge_same_role_messages;
+use crate::ccproxy::adapter::unified::{
+    UnifiedContentBlock, UnifiedMessage, UnifiedRequest, UnifiedRole, UnifiedToolChoice,
+};
 use crate::ccproxy::errors::CCProxyError;
-use crate::ccproxy::types::ProxyModel;
+use crate::ccproxy::types::{ProxyModel, COMPAT_FORMAT_RULES_PROMPT};
+use crate::ccproxy::utils::token_estimator::estimate_unified_request_tokens;
 use crate::ccproxy::ChatProtocol;
+use crate::tools::helper::is_file_tool;
+
+fn default_true() -> bool {
+    true
+}
+
+/// Formatting rules added in compat mode, stored under
+/// `chat_completion_proxy_compat_format_rules`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompatFormatRulesConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Replaces the built-in rules when set
+    #[serde(default)]
+    pub prompt: Option<String>,
+}
+
+impl Default for CompatFormatRulesConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            prompt: None,
+        }
+    }
+}
+
+impl CompatFormatRulesConfig {
+    fn prompt(&self) -> &str {
+        self.prompt
+            .as_deref()
+            .map(str::trim)
+            .filter(|prompt| !prompt.is_empty())
+            .unwrap_or(COMPAT_FORMAT_RULES_PROMPT)
+    }
+}
+
+/// Output and context limits of the backend models whose ids start with `model`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelTokenLimit {
+    /// Case-insensitive prefix of the backend model id, without any `vendor/` part
+    pub model: String,
+    /// Largest `max_tokens` the backend accepts
+    #[serde(default)]
+    pub max_output_tokens: Option<i32>,
+    /// Tokens the prompt and the answer may use together
+    #[serde(default)]
+    pub context_window: Option<i32>,
+}
+
+/// Limits of models that reject a larger `max_tokens` with a 400, used when no entry of
+/// `MaxTokensLimitsConfig::limits` matches: (model prefix, max output tokens, context window).
+const KNOWN_MODEL_TOKEN_LIMITS: &[(&str, Option<i32>, Option<i32>)] = &[
+    ("deepseek-chat", Some(8192), Some(131072)),
+    ("deepseek-reasoner", Some(65536), Some(131072)),
+    ("qwen-max", Some(8192), Some(32768)),
+    ("qwen-plus", Some(8192), Some(131072)),
+    ("qwen-turbo", Some(8192), Some(1000000)),
+    ("moonshot-v1-8k", None, Some(8192)),
+    ("moonshot-v1-32k", None, Some(32768)),
+    ("moonshot-v1-128k", None, Some(131072)),
+    ("ernie-4.0-8k", Some(2048), Some(8192)),
+];
+
+/// Share of the context window kept free when `max_tokens` is derived from the room left,
+/// as the prompt size is only estimated.
+const CONTEXT_WINDOW_SAFETY_RATIO: f64 = 0.05;
+
+/// Clamping of `max_tokens` to the limits of the backend model, stored under
+/// `chat_completion_proxy_max_tokens_limits`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MaxTokensLimitsConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Checked before the built-in limits
+    #[serde(default)]
+    pub limits: Vec<ModelTokenLimit>,
+}
+
+impl Default for MaxTokensLimitsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            limits: Vec::new(),
+        }
+    }
+}
+
+impl MaxTokensLimitsConfig {
+    /// The limit with the longest prefix matching `model`, configured entries first.
+    fn limit_for(&self, model: &str) -> Option<ModelTokenLimit> {
+        let model = model.rsplit('/').next().unwrap_or(model).to_lowercase();
+        let longest = |limits: Vec<ModelTokenLimit>| {
+            limits
+                .into_iter()
+                .filter(|limit| model.starts_with(&limit.model.to_lowercase()))
+                .max_by_key(|limit| limit.model.len())
+        };
+        longest(self.limits.clone()).or_else(|| {
+            longest(
+                KNOWN_MODEL_TOKEN_LIMITS
+                    .iter()
+                    .map(
+                        |(model, max_output_tokens, context_window)| ModelTokenLimit {
+                            model: model.to_string(),
+                            max_output_tokens: *max_output_tokens,
+                            context_window: *context_window,
+                        },
+                    )
+                    .collect(),
+            )
+        })
+    }
+}
 
 fn should_relax_required_tool_choice(base_url: &str) -> bool {
     reqwest::Url::parse(base_url)
@@ -149,12 +271,203 @@ pub fn preprocess_unified_request(unified_request: &mut UnifiedRequest, proxy_mo
     {
         unified_request.tool_choice = Some(UnifiedToolChoice::Auto);
     }
+
+    if let Some(max_turns) = proxy_model.max_history_turns {
+        limit_history_turns(unified_request, max_turns);
+    }
+    if proxy_model.strip_history_reasoning {
+        strip_history_reasoning(unified_request);
+    }
+    filter_empty_messages(&mut unified_request.messages, proxy_model.empty_messages);
+    // Claude and Gemini reject consecutive messages of the same role
+    if proxy_model.merge_same_role_messages
+        || matches!(
+            proxy_model.chat_protocol,
+            ChatProtocol::Claude | ChatProtocol::Gemini
+        )
+    {
+        merge_same_role_messages(&mut unified_request.messages);
+    }
+}
+
+/// A turn starts at a user message that is more than a reply with tool results.
+fn starts_turn(message: &UnifiedMessage) -> bool {
+    message.role == UnifiedRole::User
+        && message
+            .content
+            .iter()
+            .any(|block| !matches!(block, UnifiedContentBlock::ToolResult { .. }))
+}
+
+/// Keeps only the latest `max_turns` turns of the history.
+///
+/// Whole turns are dropped, so tool calls stay with their results. System messages of the
+/// dropped part are kept.
+pub fn limit_history_turns(unified_request: &mut UnifiedRequest, max_turns: usize) {
+    let turn_starts: Vec<usize> = unified_request
+        .messages
+        .iter()
+        .enumerate()
+        .filter(|(_, message)| starts_turn(message))
+        .map(|(index, _)| index)
+        .collect();
+    if max_turns == 0 || turn_starts.len() <= max_turns {
+        return;
+    }
+
+    let cut = turn_starts[turn_starts.len() - max_turns];
+    let mut messages: Vec<UnifiedMessage> = unified_request
+        .messages
+        .drain(..cut)
+        .filter(|message| message.role == UnifiedRole::System)
+        .collect();
+    let dropped = cut - messages.len();
+    messages.append(&mut unified_request.messages);
+    unified_request.messages = messages;
+    log::info!(
+        "limit_history_turns: kept the latest {} of {} turns, dropped {} messages",
+        max_turns,
+        turn_starts.len(),
+        dropped
+    );
+}
+
+/// Removes the reasoning of assistant messages before the current turn.
+///
+/// The current turn, from the last user message that is more than tool results, keeps its
+/// reasoning, providers expect it next to the tool calls still in progress. Assistant
+/// messages left without content are dropped.
+pub fn strip_history_reasoning(unified_request: &mut UnifiedRequest) {
+    let Some(current_turn) = unified_request.messages.iter().rposition(starts_turn) else {
+        return;
+    };
+
+    let mut stripped = 0;
+    let mut messages = Vec::with_capacity(unified_request.messages.len());
+    for (index, mut message) in unified_request.messages.drain(..).enumerate() {
+        if index < current_turn && message.role == UnifiedRole::Assistant {
+            let blocks = message.content.len();
+            message
+                .content
+                .retain(|block| !matches!(block, UnifiedContentBlock::Thinking { .. }));
+            let had_reasoning_content = message.reasoning_content.take().is_some();
+            if blocks == message.content.len() && !had_reasoning_content {
+                messages.push(message);
+                continue;
+            }
+            stripped += 1;
+            if message.content.is_empty() {
+                continue;
+            }
+        }
+        messages.push(message);
+    }
+    unified_request.messages = messages;
+
+    if stripped > 0 {
+        log::debug!(
+            "strip_history_reasoning: removed the reasoning of {} earlier assistant messages",
+            stripped
+        );
+    }
+}
+
+/// Adds the formatting rules to the injected prompt when a compat mode request offers
+/// file editing tools.
+///
+/// Must run before `enhance_prompt`, which moves the tools into the prompt.
+pub fn inject_compat_format_rules(
+    unified_request: &mut UnifiedRequest,
+    config: &CompatFormatRulesConfig,
+) {
+    if !config.enabled || !unified_request.tool_compat_mode {
+        return;
+    }
+    let has_file_tools = unified_request
+        .tools
+        .as_deref()
+        .is_some_and(|tools| tools.iter().any(|tool| is_file_tool(&tool.name)));
+    if !has_file_tools {
+        return;
+    }
+
+    let rules = config.prompt();
+    unified_request.prompt_enhance_text = Some(match unified_request.prompt_enhance_text.take() {
+        Some(text) if !text.is_empty() => format!("{}\n\n{}", text, rules),
+        _ => rules.to_string(),
+    });
+}
+
+/// Lowers `max_tokens` to what the backend model accepts.
+///
+/// The ceiling is the model's output limit, and the room the estimated prompt leaves in its
+/// context window, minus a safety margin. A smaller `max_tokens`, a request without one and
+/// a model without a known limit are left alone. When the prompt alone fills the window,
+/// `max_tokens` is left too and the backend reports the overflow.
+pub fn clamp_max_tokens(
+    unified_request: &mut UnifiedRequest,
+    model: &str,
+    config: &MaxTokensLimitsConfig,
+) {
+    if !config.enabled {
+        return;
+    }
+    let Some(requested) = unified_request.max_tokens else {
+        return;
+    };
+    let Some(limit) = config.limit_for(model) else {
+        return;
+    };
+
+    let room = limit.context_window.map(|window| {
+        let prompt_tokens = estimate_unified_request_tokens(unified_request).ceil() as i64;
+        let margin = (window as f64 * CONTEXT_WINDOW_SAFETY_RATIO).ceil() as i64;
+        window as i64 - prompt_tokens - margin
+    });
+    if let Some(room) = room.filter(|room| *room <= 0) {
+        log::warn!(
+            "clamp_max_tokens: the prompt of model '{}' leaves no room in its {}-token context window ({} tokens short), max_tokens {} is kept",
+            model,
+            limit.context_window.unwrap_or_default(),
+            -room,
+            requested
+        );
+        return;
+    }
+
+    let ceiling = [limit.max_output_tokens.map(i64::from), room]
+        .into_iter()
+        .flatten()
+        .min();
+    if let Some(ceiling) = ceiling.filter(|ceiling| (requested as i64) > *ceiling) {
+        unified_request.max_tokens = Some(ceiling as i32);
+        log::info!(
+            "clamp_max_tokens: max_tokens of model '{}' lowered from {} to {} (limit '{}': max output {:?}, context window {:?})",
+            model,
+            requested,
+            ceiling,
+            limit.model,
+            limit.max_output_tokens,
+            limit.context_window
+        );
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::preprocess_client_request_body;
-    use crate::ccproxy::{types::ProxyModel, ChatProtocol};
+    use super::{
+        clamp_max_tokens, inject_compat_format_rules, limit_history_turns,
+        preprocess_client_request_body, preprocess_unified_request, CompatFormatRulesConfig,
+        MaxTokensLimitsConfig, ModelTokenLimit,
+    };
+    use crate::ccproxy::{
+        adapter::unified::{
+            UnifiedContentBlock, UnifiedMessage, UnifiedRequest, UnifiedRole, UnifiedTool,
+        },
+        types::{ProxyModel, COMPAT_FORMAT_RULES_PROMPT},
+        utils::token_estimator::estimate_unified_request_tokens,
+        ChatProtocol,
+    };
     use bytes::Bytes;
     use serde_json::json;
 
@@ -173,7 +486,17 @@ mod tests {
             prompt_injection_position: None,
             prompt_text: String::new(),
             tool_filter: Default::default(),
+            tool_include: Vec::new(),
             prompt_replace: Vec::new(),
+            max_history_turns: None,
+            tool_result_max_chars: None,
+            strip_history_reasoning: false,
+            merge_same_role_messages: false,
+            empty_messages: Default::default(),
+            extra_body_keys: Vec::new(),
+            openai_quirks: None,
+            concurrency_limit: None,
+            fallback: None,
             temp_ratio: 1.0,
             max_tokens: None,
             temperature: None,
@@ -182,6 +505,8 @@ mod tests {
             top_p: None,
             top_k: None,
             stop: Vec::new(),
+            prompt_template: None,
+            model_stop: Vec::new(),
             tool_compat_mode: None,
         }
     }
@@ -336,4 +661,311 @@ mod tests {
             .get("reasoning_content")
             .is_none());
     }
+
+    fn request_with_tools(tool_compat_mode: bool, tool_names: &[&str]) -> UnifiedRequest {
+        UnifiedRequest {
+            tool_compat_mode,
+            tools: Some(
+                tool_names
+                    .iter()
+                    .map(|name| UnifiedTool {
+                        name: name.to_string(),
+                        description: None,
+                        input_schema: json!({"type": "object"}),
+                    })
+                    .collect(),
+            ),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_format_rules_injected_only_in_compat_mode_with_file_tools() {
+        let config = CompatFormatRulesConfig::default();
+
+        let mut request = request_with_tools(true, &["Read", "Edit"]);
+        request.prompt_enhance_text = Some("Answer in English.".to_string());
+        inject_compat_format_rules(&mut request, &config);
+        assert_eq!(
+            request.prompt_enhance_text.as_deref(),
+            Some(format!("Answer in English.\n\n{}", COMPAT_FORMAT_RULES_PROMPT).as_str())
+        );
+
+        let mut native = request_with_tools(false, &["Edit"]);
+        inject_compat_format_rules(&mut native, &config);
+        assert!(native.prompt_enhance_text.is_none());
+
+        let mut without_file_tools = request_with_tools(true, &["Read", "Grep"]);
+        inject_compat_format_rules(&mut without_file_tools, &config);
+        assert!(without_file_tools.prompt_enhance_text.is_none());
+
+        let mut disabled = request_with_tools(true, &["write_file"]);
+        inject_compat_format_rules(
+            &mut disabled,
+            &CompatFormatRulesConfig {
+                enabled: false,
+                prompt: None,
+            },
+        );
+        assert!(disabled.prompt_enhance_text.is_none());
+    }
+
+    #[test]
+    fn test_format_rules_use_custom_text() {
+        let mut request = request_with_tools(true, &["write_file"]);
+        inject_compat_format_rules(
+            &mut request,
+            &CompatFormatRulesConfig {
+                enabled: true,
+                prompt: Some("Keep every line break.".to_string()),
+            },
+        );
+        assert_eq!(
+            request.prompt_enhance_text.as_deref(),
+            Some("Keep every line break.")
+        );
+    }
+
+    fn message(role: UnifiedRole, block: UnifiedContentBlock) -> UnifiedMessage {
+        UnifiedMessage {
+            role,
+            content: vec![block],
+            reasoning_content: None,
+        }
+    }
+
+    fn text(text: &str) -> UnifiedContentBlock {
+        UnifiedContentBlock::Text {
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_history_is_limited_to_latest_turns_with_tool_pairs() {
+        let mut messages = vec![message(UnifiedRole::System, text("Be brief."))];
+        for turn in 1..=6 {
+            messages.push(message(
+                UnifiedRole::User,
+                text(&format!("question {}", turn)),
+            ));
+            if turn % 2 == 1 {
+                let id = format!("call_{}", turn);
+                messages.push(message(
+                    UnifiedRole::Assistant,
+                    UnifiedContentBlock::ToolUse {
+                        id: id.clone(),
+                        name: "lookup".to_string(),
+                        input: json!({}),
+                    },
+                ));
+                messages.push(message(
+                    UnifiedRole::User,
+                    UnifiedContentBlock::ToolResult {
+                        tool_use_id: id,
+                        content: "found".to_string(),
+                        is_error: false,
+                    },
+                ));
+            }
+            messages.push(message(
+                UnifiedRole::Assistant,
+                text(&format!("answer {}", turn)),
+            ));
+        }
+        let mut request = UnifiedRequest {
+            messages,
+            ..Default::default()
+        };
+
+        limit_history_turns(&mut request, 4);
+
+        let questions: Vec<&UnifiedMessage> = request
+            .messages
+            .iter()
+            .filter(|message| super::starts_turn(message))
+            .collect();
+        assert_eq!(questions.len(), 4);
+        assert!(matches!(
+            &request.messages[1].content[0],
+            UnifiedContentBlock::Text { text } if text == "question 3"
+        ));
+        assert_eq!(request.messages[0].role, UnifiedRole::System);
+        // Turn 3 and 5 keep their tool call together with its result
+        for id in ["call_3", "call_5"] {
+            let call = request.messages.iter().position(|message| {
+                matches!(&message.content[0], UnifiedContentBlock::ToolUse { id: call_id, .. } if call_id == id)
+            });
+            let result = request.messages.iter().position(|message| {
+                matches!(&message.content[0], UnifiedContentBlock::ToolResult { tool_use_id, .. } if tool_use_id == id)
+            });
+            assert!(matches!((call, result), (Some(call), Some(result)) if result == call + 1));
+        }
+        assert!(!request.messages.iter().any(|message| {
+            matches!(&message.content[0], UnifiedContentBlock::ToolUse { id, .. } if id == "call_1")
+        }));
+        assert_eq!(request.messages.len(), 1 + 4 * 2 + 2 * 2);
+    }
+
+    #[test]
+    fn test_history_within_limit_is_untouched() {
+        let mut request = UnifiedRequest {
+            messages: vec![
+                message(UnifiedRole::User, text("hi")),
+                message(UnifiedRole::Assistant, text("hello")),
+                message(UnifiedRole::User, text("bye")),
+            ],
+            ..Default::default()
+        };
+        limit_history_turns(&mut request, 4);
+        assert_eq!(request.messages.len(), 3);
+    }
+
+    #[test]
+    fn test_history_reasoning_is_stripped_but_current_turn_kept() {
+        let thinking = |text: &str| UnifiedContentBlock::Thinking {
+            thinking: text.to_string(),
+        };
+        let mut reasoned_answer = message(UnifiedRole::Assistant, text("answer 1"));
+        reasoned_answer.reasoning_content = Some("old reasoning".to_string());
+        let mut tool_call = message(UnifiedRole::Assistant, thinking("current thinking"));
+        tool_call.content.push(UnifiedContentBlock::ToolUse {
+            id: "call_1".to_string(),
+            name: "lookup".to_string(),
+            input: json!({}),
+        });
+        let mut request = UnifiedRequest {
+            messages: vec![
+                message(UnifiedRole::User, text("question 1")),
+                reasoned_answer,
+                message(UnifiedRole::User, text("question 2")),
+                message(UnifiedRole::Assistant, thinking("only thinking")),
+                message(UnifiedRole::User, text("question 3")),
+                tool_call,
+                message(
+                    UnifiedRole::User,
+                    UnifiedContentBlock::ToolResult {
+                        tool_use_id: "call_1".to_string(),
+                        content: "found".to_string(),
+                        is_error: false,
+                    },
+                ),
+            ],
+            ..Default::default()
+        };
+        let mut proxy_model = deepseek_proxy_model();
+        proxy_model.strip_history_reasoning = true;
+
+        preprocess_unified_request(&mut request, &proxy_model);
+
+        // The thinking-only message of turn 2 is gone, turn 1 keeps its answer text
+        assert_eq!(request.messages.len(), 6);
+        assert_eq!(request.messages[1].reasoning_content, None);
+        assert_eq!(request.messages[1].content, vec![text("answer 1")]);
+        assert_eq!(
+            request.messages[3],
+            message(UnifiedRole::User, text("question 3"))
+        );
+        // The current turn is unaffected
+        assert_eq!(request.messages[4].content[0], thinking("current thinking"));
+        assert_eq!(request.messages[4].content.len(), 2);
+    }
+
+    #[test]
+    fn test_consecutive_user_messages_are_merged_for_claude() {
+        let request = || UnifiedRequest {
+            messages: vec![
+                message(UnifiedRole::User, text("Here is the log")),
+                message(UnifiedRole::User, text("Why does it fail?")),
+            ],
+            ..Default::default()
+        };
+
+        // Turned off for the group, an OpenAI backend gets the messages as they are
+        let mut openai_request = request();
+        preprocess_unified_request(&mut openai_request, &deepseek_proxy_model());
+        assert_eq!(openai_request.messages.len(), 2);
+
+        // A Claude backend needs alternating roles, so they are merged anyway
+        let mut claude_request = request();
+        let mut proxy_model = deepseek_proxy_model();
+        proxy_model.chat_protocol = ChatProtocol::Claude;
+        preprocess_unified_request(&mut claude_request, &proxy_model);
+        assert_eq!(
+            claude_request.messages,
+            vec![UnifiedMessage {
+                role: UnifiedRole::User,
+                content: vec![text("Here is the log"), text("Why does it fail?")],
+                reasoning_content: None,
+            }]
+        );
+    }
+
+    fn request_with_max_tokens(prompt: &str, max_tokens: Option<i32>) -> UnifiedRequest {
+        UnifiedRequest {
+            messages: vec![message(UnifiedRole::User, text(prompt))],
+            max_tokens,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_max_tokens_is_clamped_to_known_model_limits() {
+        let config = MaxTokensLimitsConfig::default();
+        let clamped = |model: &str, max_tokens: Option<i32>, config: &MaxTokensLimitsConfig| {
+            let mut request = request_with_max_tokens("hello", max_tokens);
+            clamp_max_tokens(&mut request, model, config);
+            request.max_tokens
+        };
+
+        assert_eq!(clamped("deepseek-chat", Some(32000), &config), Some(8192));
+        assert_eq!(
+            clamped("deepseek/DeepSeek-Chat", Some(32000), &config),
+            Some(8192)
+        );
+        // Smaller values, missing values and unknown models are untouched
+        assert_eq!(clamped("deepseek-chat", Some(4096), &config), Some(4096));
+        assert_eq!(clamped("deepseek-chat", None, &config), None);
+        assert_eq!(clamped("gpt-4o", Some(100000), &config), Some(100000));
+
+        // A configured entry wins over the built-in one
+        let configured = MaxTokensLimitsConfig {
+            enabled: true,
+            limits: vec![ModelTokenLimit {
+                model: "deepseek-chat".to_string(),
+                max_output_tokens: Some(4000),
+                context_window: None,
+            }],
+        };
+        assert_eq!(
+            clamped("deepseek-chat", Some(8000), &configured),
+            Some(4000)
+        );
+        let disabled = MaxTokensLimitsConfig {
+            enabled: false,
+            ..configured
+        };
+        assert_eq!(clamped("deepseek-chat", Some(8000), &disabled), Some(8000));
+    }
+
+    #[test]
+    fn test_max_tokens_fits_the_room_left_by_a_long_prompt() {
+        let config = MaxTokensLimitsConfig {
+            enabled: true,
+            limits: vec![ModelTokenLimit {
+                model: "small-model".to_string(),
+                max_output_tokens: Some(900),
+                context_window: Some(1000),
+            }],
+        };
+
+        let mut request = request_with_max_tokens(&"word ".repeat(400), Some(900));
+        let prompt_tokens = estimate_unified_request_tokens(&request).ceil() as i32;
+        clamp_max_tokens(&mut request, "small-model", &config);
+        assert_eq!(request.max_tokens, Some(1000 - prompt_tokens - 50));
+
+        // A prompt filling the whole window is left for the backend to reject
+        let mut request = request_with_max_tokens(&"word ".repeat(1000), Some(900));
+        clamp_max_tokens(&mut request, "small-model", &config);
+        assert_eq!(request.max_tokens, Some(900));
+    }
 }
@@ -15,7 +15,13 @@ use crate::ccproxy::{
     handler::chat_handler::{
         execute_unified_chat_request, prepare_unified_request_for_proxy_model,
     },
-    helper::{get_msg_id, send_with_retry, CcproxyQuery, ModelResolver, RetryConfig},
+    helper::{
+        audit_log, budget, concurrency,
+        dead_letter::{DeadLetterContext, RESPONSES_PROTOCOL},
+        get_msg_id,
+        redaction::Redactor,
+        send_with_retry, CcproxyQuery, ModelResolver, RetryConfig, CC_PROXY_ROTATOR,
+    },
     types::{openai_responses::OpenAIResponsesRequest, ProxyModel},
     ChatProtocol,
 };
@@ -187,6 +193,12 @@ async fn direct_forward_responses(
     let target_response = send_with_retry(onward_request_builder, &retry_config).await?;
 
     let status_code = target_response.status();
+    CC_PROXY_ROTATOR.record_key_status(
+        proxy_model.provider_id,
+        &proxy_model.model,
+        &proxy_model.api_key,
+        status_code.as_u16(),
+    );
     let response_headers = target_response.headers().clone();
     let body_bytes = target_response.bytes().await.map_err(|e| {
         CCProxyError::InternalError(
@@ -221,21 +233,24 @@ async fn direct_forward_responses(
     };
 
     if let Ok(store) = main_store_arc.read() {
-        let _ = store.record_ccproxy_stat(CcproxyStat {
-            id: None,
-            client_model: proxy_model.client_alias.clone(),
-            backend_model: model_name,
-            provider_id: Some(proxy_model.provider_id),
-            provider: provider_name,
-            protocol: ChatProtocol::OpenAI.to_string(),
-            tool_compat_mode: 0,
-            status_code: status_code.as_u16() as i32,
-            error_message,
-            input_tokens,
-            output_tokens,
-            cache_tokens,
-            request_at: None,
-        });
+        budget::record_stat(
+            &store,
+            CcproxyStat {
+                id: None,
+                client_model: proxy_model.client_alias.clone(),
+                backend_model: model_name,
+                provider_id: Some(proxy_model.provider_id),
+                provider: provider_name,
+                protocol: ChatProtocol::OpenAI.to_string(),
+                tool_compat_mode: 0,
+                status_code: status_code.as_u16() as i32,
+                error_message,
+                input_tokens,
+                output_tokens,
+                cache_tokens,
+                request_at: None,
+            },
+        );
     }
 
     Ok(response)
@@ -261,6 +276,13 @@ pub async fn handle_responses(
         false
     };
 
+    // Optional redaction of personal data, before anything leaves the machine
+    let redactor = Redactor::from_store(&main_store_arc);
+    let (client_request_body, placeholders) = match &redactor {
+        Some(redactor) => redactor.redact_request(client_request_body),
+        None => (client_request_body, Default::default()),
+    };
+
     let routing_request: OpenAIResponsesRoutingRequest =
         serde_json::from_slice(&client_request_body).map_err(|e| {
             CCProxyError::InternalError(
@@ -275,6 +297,8 @@ pub async fn handle_responses(
         (proxy_alias_raw, group_name)
     };
 
+    budget::check_budget(&main_store_arc)?;
+
     let proxy_model = if let Some(provider_id) = client_headers
         .get("x-cs-provider-id")
         .and_then(|v| v.to_str().ok())
@@ -301,64 +325,90 @@ pub async fn handle_responses(
         .await?
     };
 
-    let final_tool_compat_mode = match proxy_model.tool_compat_mode.as_deref() {
-        Some("compat") => true,
-        Some("native") => false,
-        Some("auto") | None => tool_compat_mode,
-        _ => tool_compat_mode,
-    };
+    let slot = concurrency::acquire_slot(proxy_model.concurrency_limit.as_ref()).await?;
+
+    audit_log::record(
+        &main_store_arc,
+        &message_id,
+        RESPONSES_PROTOCOL,
+        (&proxy_model).into(),
+        &client_request_body,
+    );
+
+    let dead_letter = DeadLetterContext::new(
+        &message_id,
+        RESPONSES_PROTOCOL.to_string(),
+        &proxy_model,
+        &client_request_body,
+    );
+    let dead_letter_store = main_store_arc.clone();
+    let result = async {
+        let final_tool_compat_mode = match proxy_model.tool_compat_mode.as_deref() {
+            Some("compat") => true,
+            Some("native") => false,
+            Some("auto") | None => tool_compat_mode,
+            _ => tool_compat_mode,
+        };
+
+        if supports_responses_api(&proxy_model) {
+            let response = direct_forward_responses(
+                client_headers,
+                client_request_body,
+                proxy_model,
+                main_store_arc,
+                log_proxy_to_file,
+            )
+            .await?;
+            return Ok(response.into_response());
+        }
+
+        let client_request_payload: OpenAIResponsesRequest =
+            serde_json::from_slice(&client_request_body).map_err(|e| {
+                CCProxyError::InternalError(
+                    t!("proxy.error.invalid_request_format", error = e.to_string()).to_string(),
+                )
+            })?;
+
+        // TODO: The Responses-to-chat-completion fallback still needs detailed testing and
+        // debugging, especially for streaming reasoning output and client-side completion
+        // behavior. Prefer the direct Responses path when a provider supports it.
+        if log_org_to_file {
+            log::info!(target: "ccproxy_logger", "message id:{}\nOpenAI Responses Origin Request Body: \n{}\n----------------\n", &message_id, String::from_utf8_lossy(&client_request_body));
+        }
 
-    if supports_responses_api(&proxy_model) {
-        let response = direct_forward_responses(
+        let is_streaming_request = client_request_payload.stream.unwrap_or(false);
+        let mut unified_request = from_openai_responses(client_request_payload, final_tool_compat_mode)
+            .map_err(|e| {
+                CCProxyError::InternalError(
+                    t!("proxy.error.invalid_request", error = e.to_string()).to_string(),
+                )
+            })?;
+        prepare_unified_request_for_proxy_model(&mut unified_request, &proxy_model);
+
+        execute_unified_chat_request(
+            ChatProtocol::OpenAI,
             client_headers,
-            client_request_body,
+            unified_request,
+            proxy_alias,
             proxy_model,
-            main_store_arc,
+            is_streaming_request,
+            tool_compat_mode,
+            final_tool_compat_mode,
+            message_id,
+            log_org_to_file,
             log_proxy_to_file,
+            main_store_arc,
+            OutputAdapterEnum::OpenAIResponses(OpenAIResponsesOutputAdapter),
         )
-        .await?;
-        return Ok(response.into_response());
-    }
-
-    let client_request_payload: OpenAIResponsesRequest =
-        serde_json::from_slice(&client_request_body).map_err(|e| {
-            CCProxyError::InternalError(
-                t!("proxy.error.invalid_request_format", error = e.to_string()).to_string(),
-            )
-        })?;
-
-    // TODO: The Responses-to-chat-completion fallback still needs detailed testing and
-    // debugging, especially for streaming reasoning output and client-side completion
-    // behavior. Prefer the direct Responses path when a provider supports it.
-    if log_org_to_file {
-        log::info!(target: "ccproxy_logger", "message id:{}\nOpenAI Responses Origin Request Body: \n{}\n----------------\n", &message_id, String::from_utf8_lossy(&client_request_body));
+        .await
     }
-
-    let is_streaming_request = client_request_payload.stream.unwrap_or(false);
-    let mut unified_request = from_openai_responses(client_request_payload, final_tool_compat_mode)
-        .map_err(|e| {
-            CCProxyError::InternalError(
-                t!("proxy.error.invalid_request", error = e.to_string()).to_string(),
-            )
-        })?;
-    prepare_unified_request_for_proxy_model(&mut unified_request, &proxy_model);
-
-    execute_unified_chat_request(
-        ChatProtocol::OpenAI,
-        client_headers,
-        unified_request,
-        proxy_alias,
-        proxy_model,
-        is_streaming_request,
-        tool_compat_mode,
-        final_tool_compat_mode,
-        message_id,
-        log_org_to_file,
-        log_proxy_to_file,
-        main_store_arc,
-        OutputAdapterEnum::OpenAIResponses(OpenAIResponsesOutputAdapter),
-    )
-    .await
+    .await;
+    let response = dead_letter.capture(&dead_letter_store, result).await?;
+    let response = match &redactor {
+        Some(redactor) => redactor.restore_response(response, placeholders),
+        None => response,
+    };
+    Ok(concurrency::hold_slot_until_body_end(response, slot))
 }
 
 #[cfg(test)]
@@ -367,7 +417,13 @@ mod tests {
         prepare_direct_responses_body, response_usage_tokens, response_usage_tokens_from_body,
         supports_responses_api, OpenAIResponsesRoutingRequest,
     };
-    use crate::ccproxy::types::{ChatProtocol, ProxyModel};
+    use crate::ccproxy::{
+        helper::CcproxyQuery,
+        test_util::{echo_backend, store_with_alias},
+        types::{ChatProtocol, ProxyModel},
+    };
+    use crate::constants::CFG_CCPROXY_REDACTION;
+    use axum::body::to_bytes;
     use serde_json::json;
 
     fn proxy_model_with_metadata(metadata: Option<serde_json::Value>) -> ProxyModel {
@@ -385,7 +441,17 @@ mod tests {
             prompt_injection_position: None,
             prompt_text: String::new(),
             tool_filter: Default::default(),
+            tool_include: Vec::new(),
             prompt_replace: Vec::new(),
+            max_history_turns: None,
+            tool_result_max_chars: None,
+            strip_history_reasoning: false,
+            merge_same_role_messages: true,
+            empty_messages: Default::default(),
+            extra_body_keys: Vec::new(),
+            openai_quirks: None,
+            concurrency_limit: None,
+            fallback: None,
             temp_ratio: 1.0,
             max_tokens: None,
             temperature: None,
@@ -394,6 +460,8 @@ mod tests {
             top_p: None,
             top_k: None,
             stop: Vec::new(),
+            prompt_template: None,
+            model_stop: Vec::new(),
             tool_compat_mode: None,
         }
     }
@@ -539,4 +607,41 @@ data: {"type":"response.completed","response":{"id":"resp_123","status":"complet
         assert_eq!(output, 397);
         assert_eq!(cache, 76672);
     }
+
+    #[tokio::test]
+    async fn responses_prompt_is_redacted_upstream_and_restored_in_the_answer() {
+        let (base_url, mut received) = echo_backend().await;
+        let main_store = store_with_alias(base_url, "redact-responses");
+        main_store
+            .write()
+            .expect("store lock")
+            .set_config(CFG_CCPROXY_REDACTION, &json!({"enabled": true}))
+            .expect("save redaction config");
+
+        let body = json!({"model": "echo", "input": "Reply to jane@example.com"});
+        let response = super::handle_responses(
+            reqwest::header::HeaderMap::new(),
+            CcproxyQuery {
+                key: None,
+                debug: None,
+            },
+            bytes::Bytes::from(body.to_string()),
+            Some("redact-responses".to_string()),
+            false,
+            main_store,
+        )
+        .await
+        .expect("response");
+
+        let upstream = received.recv().await.expect("upstream request").to_string();
+        assert!(upstream.contains("[EMAIL_1]"), "{}", upstream);
+        assert!(!upstream.contains("jane@example.com"));
+
+        let answer = to_bytes(response.into_body(), usize::MAX)
+            .await
+            .expect("body");
+        let answer = String::from_utf8_lossy(&answer);
+        assert!(answer.contains("Reply to jane@example.com"), "{}", answer);
+        assert!(!answer.contains("[EMAIL_1]"));
+    }
 }
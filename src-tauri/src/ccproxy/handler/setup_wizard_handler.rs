@@ -0,0 +1,417 @@
+//! First-run setup of a working proxy group from a provider preset.
+//!
+//! A preset names a provider, its base URL, API protocol and a few models, and maps the
+//! aliases of the group to those models. The presets ship as data in
+//! `assets/setup/presets.json`. Applying one creates the provider and the proxy group,
+//! routes the aliases in `chat_completion_proxy`, then checks the group with a dry run that
+//! resolves every route without sending a request.
+
+use indexmap::IndexMap;
+use lazy_static::lazy_static;
+use rust_i18n::t;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use crate::ccproxy::{types::BackendModelTarget, ChatCompletionProxyConfig, ChatProtocol};
+use crate::constants::CFG_CHAT_COMPLETION_PROXY;
+use crate::db::{MainStore, ModelConfig, ProxyGroup, StoreError};
+
+lazy_static! {
+    static ref SETUP_PRESETS: Vec<SetupPreset> =
+        serde_json::from_str(include_str!("../../../assets/setup/presets.json"))
+            .expect("assets/setup/presets.json should be valid");
+}
+
+/// A model offered by a preset.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetupPresetModel {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub context_size: Option<i32>,
+    #[serde(default)]
+    pub function_call: Option<bool>,
+    #[serde(default)]
+    pub reasoning: Option<bool>,
+}
+
+/// A provider the setup wizard can configure in one step.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetupPreset {
+    pub id: String,
+    pub name: String,
+    pub api_protocol: String,
+    pub base_url: String,
+    pub requires_api_key: bool,
+    pub models: Vec<SetupPresetModel>,
+    /// Proxy alias to the id of one of `models`
+    pub aliases: IndexMap<String, String>,
+}
+
+/// Outcome of applying a preset.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetupWizardResult {
+    pub provider_id: i64,
+    pub group_id: i64,
+    pub group: String,
+    pub aliases: Vec<String>,
+    /// Problems the dry run found, empty when every alias resolves
+    pub issues: Vec<String>,
+}
+
+impl SetupWizardResult {
+    #[cfg(test)]
+    pub fn is_valid(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// Returns the shipped presets.
+pub fn setup_presets() -> Vec<SetupPreset> {
+    SETUP_PRESETS.clone()
+}
+
+fn find_preset(preset: &str) -> Option<&'static SetupPreset> {
+    let preset = preset.trim();
+    SETUP_PRESETS
+        .iter()
+        .find(|p| p.id.eq_ignore_ascii_case(preset) || p.name.eq_ignore_ascii_case(preset))
+}
+
+/// Whether a provider can run without an API key, as local servers usually do.
+fn is_keyless(protocol: &ChatProtocol, url: &url::Url) -> bool {
+    *protocol == ChatProtocol::Ollama
+        || matches!(
+            url.host_str(),
+            Some("localhost") | Some("127.0.0.1") | Some("[::1]")
+        )
+}
+
+/// Applies `preset`: creates its provider unless one with the same name and base URL
+/// exists, creates the proxy group `group_name` (the preset id by default) unless it
+/// exists, and routes the preset aliases of the group to the provider. Other aliases of an
+/// existing group are kept.
+pub fn apply_setup_preset(
+    store: &mut MainStore,
+    preset: &str,
+    api_key: Option<String>,
+    group_name: Option<String>,
+) -> Result<SetupWizardResult, StoreError> {
+    let preset = find_preset(preset).ok_or_else(|| {
+        StoreError::NotFound(t!("proxy.setup.unknown_preset", preset = preset).to_string())
+    })?;
+    let api_key = api_key
+        .map(|key| key.trim().to_string())
+        .unwrap_or_default();
+    let group_name = group_name
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| preset.id.clone());
+
+    // 1. Provider, a key is only needed when the existing one has none
+    let existing = store
+        .config
+        .get_ai_models()?
+        .into_iter()
+        .find(|m| m.name == preset.name && m.base_url == preset.base_url);
+    let has_key = !api_key.is_empty() || existing.as_ref().is_some_and(|m| !m.api_key.is_empty());
+    if preset.requires_api_key && !has_key {
+        return Err(StoreError::InvalidData(
+            t!("proxy.setup.api_key_required", preset = preset.name).to_string(),
+        ));
+    }
+    let provider_id = match existing {
+        Some(model) => {
+            let id = model.id.unwrap_or_default();
+            if !api_key.is_empty() && api_key != model.api_key {
+                store.update_ai_model(
+                    id,
+                    model.name,
+                    model.models,
+                    model.default_model,
+                    model.api_protocol,
+                    model.base_url,
+                    api_key,
+                    model.max_tokens,
+                    model.temperature,
+                    model.top_p,
+                    model.top_k,
+                    model.disabled,
+                    model.metadata,
+                )?;
+            }
+            id
+        }
+        None => {
+            let models = preset
+                .models
+                .iter()
+                .map(|m| ModelConfig {
+                    id: m.id.clone(),
+                    name: m.name.clone(),
+                    context_size: m.context_size.or(ModelConfig::default().context_size),
+                    function_call: m.function_call,
+                    reasoning: m.reasoning.or(Some(false)),
+                    ..Default::default()
+                })
+                .collect();
+            let default_model = preset
+                .aliases
+                .get("default")
+                .or_else(|| preset.models.first().map(|m| &m.id))
+                .cloned()
+                .unwrap_or_default();
+            // Same defaults as a provider added in the settings
+            store.add_ai_model(
+                preset.name.clone(),
+                models,
+                default_model,
+                preset.api_protocol.clone(),
+                preset.base_url.clone(),
+                api_key,
+                4096,
+                -0.1,
+                0.0,
+                0,
+                false,
+                None,
+            )?
+        }
+    };
+
+    // 2. Proxy group
+    let group_id = match store
+        .config
+        .get_proxy_groups()
+        .into_iter()
+        .find(|g| g.name == group_name)
+    {
+        Some(group) => group.id,
+        None => store.proxy_group_add(&ProxyGroup {
+            name: group_name.clone(),
+            description: t!("proxy.setup.group_description", preset = preset.name).to_string(),
+            prompt_injection: "off".to_string(),
+            temperature: Some(1.0),
+            ..Default::default()
+        })?,
+    };
+
+    // 3. Aliases
+    let mut proxy_config: ChatCompletionProxyConfig =
+        store.get_config(CFG_CHAT_COMPLETION_PROXY, HashMap::new());
+    let aliases = proxy_config.entry(group_name.clone()).or_default();
+    for (alias, model) in &preset.aliases {
+        aliases.insert(
+            alias.clone(),
+            vec![BackendModelTarget {
+                id: provider_id,
+                model: model.clone(),
+            }],
+        );
+    }
+    let value =
+        serde_json::to_value(&proxy_config).map_err(|e| StoreError::JsonError(e.to_string()))?;
+    store.set_config(CFG_CHAT_COMPLETION_PROXY, &value)?;
+
+    let issues = dry_run_proxy_group(store, &group_name);
+    if !issues.is_empty() {
+        log::warn!(
+            "Setup preset '{}' left group '{}' with issues: {:?}",
+            preset.id,
+            group_name,
+            issues
+        );
+    }
+    Ok(SetupWizardResult {
+        provider_id,
+        group_id,
+        group: group_name,
+        aliases: preset.aliases.keys().cloned().collect(),
+        issues,
+    })
+}
+
+/// Resolves every alias of `group` to its providers and models without sending a request,
+/// and returns the problems that would make a request fail.
+pub fn dry_run_proxy_group(store: &MainStore, group: &str) -> Vec<String> {
+    let mut issues = Vec::new();
+    match store.config.get_proxy_group_by_name(group) {
+        Ok(g) if g.disabled => {
+            issues.push(t!("proxy.setup.group_disabled", group = group).to_string())
+        }
+        Ok(_) => {}
+        Err(e) => issues.push(e.to_string()),
+    }
+
+    let proxy_config: ChatCompletionProxyConfig =
+        store.get_config(CFG_CHAT_COMPLETION_PROXY, HashMap::new());
+    let aliases = proxy_config.get(group).cloned().unwrap_or_default();
+    if aliases.is_empty() {
+        issues.push(t!("proxy.setup.no_aliases", group = group).to_string());
+    }
+
+    for (alias, targets) in &aliases {
+        if targets.is_empty() {
+            issues.push(t!("proxy.error.no_backend_targets", alias = alias).to_string());
+        }
+        for target in targets {
+            let provider = match store.config.get_ai_model_by_id(target.id) {
+                Ok(provider) => provider,
+                Err(e) => {
+                    issues.push(e.to_string());
+                    continue;
+                }
+            };
+            if provider.disabled {
+                issues.push(
+                    t!(
+                        "proxy.setup.provider_disabled",
+                        alias = alias,
+                        provider = provider.name
+                    )
+                    .to_string(),
+                );
+            }
+            if !provider.models.iter().any(|m| m.id == target.model) {
+                issues.push(
+                    t!(
+                        "proxy.setup.model_missing",
+                        alias = alias,
+                        provider = provider.name,
+                        model = target.model
+                    )
+                    .to_string(),
+                );
+            }
+            let protocol = match ChatProtocol::from_str(&provider.api_protocol) {
+                Ok(protocol) => protocol,
+                Err(e) => {
+                    issues.push(e.to_string());
+                    continue;
+                }
+            };
+            match url::Url::parse(&provider.base_url)
+                .ok()
+                .filter(|url| matches!(url.scheme(), "http" | "https"))
+            {
+                Some(url) => {
+                    if provider.api_key.trim().is_empty() && !is_keyless(&protocol, &url) {
+                        issues.push(
+                            t!("proxy.setup.missing_api_key", provider = provider.name).to_string(),
+                        );
+                    }
+                }
+                None => issues.push(
+                    t!(
+                        "proxy.setup.invalid_base_url",
+                        provider = provider.name,
+                        url = provider.base_url
+                    )
+                    .to_string(),
+                ),
+            }
+        }
+    }
+
+    issues.dedup();
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ccproxy::test_util::memory_store;
+
+    #[test]
+    fn shipped_presets_are_consistent() {
+        let presets = setup_presets();
+        for id in ["openrouter", "deepseek", "ollama"] {
+            assert!(presets.iter().any(|p| p.id == id), "missing preset {id}");
+        }
+        for preset in &presets {
+            assert!(ChatProtocol::from_str(&preset.api_protocol).is_ok());
+            assert!(preset.aliases.contains_key("default"));
+            for model in preset.aliases.values() {
+                assert!(
+                    preset.models.iter().any(|m| &m.id == model),
+                    "{} routes to unknown model {model}",
+                    preset.id
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn applying_a_preset_creates_a_valid_group() {
+        let mut store = memory_store();
+        let result = apply_setup_preset(&mut store, "DeepSeek", Some("sk-test".to_string()), None)
+            .expect("preset should apply");
+
+        assert!(result.is_valid(), "issues: {:?}", result.issues);
+        assert_eq!(result.group, "deepseek");
+        assert_eq!(result.aliases, vec!["default", "reasoner"]);
+
+        let provider = store.config.get_ai_model_by_id(result.provider_id).unwrap();
+        assert_eq!(provider.base_url, "https://api.deepseek.com/v1");
+        assert_eq!(provider.api_protocol, "openai");
+        assert_eq!(provider.api_key, "sk-test");
+        let group = store.config.get_proxy_group_by_name("deepseek").unwrap();
+        assert_eq!(group.id, result.group_id);
+        assert_eq!(group.prompt_injection, "off");
+
+        let config: ChatCompletionProxyConfig =
+            store.get_config(CFG_CHAT_COMPLETION_PROXY, HashMap::new());
+        let target = &config["deepseek"]["default"][0];
+        assert_eq!(target.id, result.provider_id);
+        assert_eq!(target.model, "deepseek-chat");
+
+        // Applying it again reuses the provider and the group
+        let again = apply_setup_preset(&mut store, "deepseek", None, None).unwrap();
+        assert_eq!(again.provider_id, result.provider_id);
+        assert_eq!(again.group_id, result.group_id);
+        assert_eq!(store.config.get_ai_models().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn local_preset_needs_no_key_and_hosted_ones_do() {
+        let mut store = memory_store();
+        let result = apply_setup_preset(&mut store, "ollama", None, Some("local".to_string()))
+            .expect("ollama preset should apply");
+        assert!(result.is_valid(), "issues: {:?}", result.issues);
+        assert_eq!(result.group, "local");
+
+        assert!(apply_setup_preset(&mut store, "openrouter", None, None).is_err());
+        assert!(apply_setup_preset(&mut store, "nope", None, None).is_err());
+    }
+
+    #[test]
+    fn dry_run_reports_broken_routes() {
+        let mut store = memory_store();
+        let result = apply_setup_preset(&mut store, "deepseek", Some("sk".into()), None).unwrap();
+
+        let mut config: ChatCompletionProxyConfig =
+            store.get_config(CFG_CHAT_COMPLETION_PROXY, HashMap::new());
+        config.get_mut("deepseek").unwrap().insert(
+            "broken".to_string(),
+            vec![BackendModelTarget {
+                id: result.provider_id,
+                model: "no-such-model".to_string(),
+            }],
+        );
+        store
+            .set_config(
+                CFG_CHAT_COMPLETION_PROXY,
+                &serde_json::to_value(&config).unwrap(),
+            )
+            .unwrap();
+
+        let issues = dry_run_proxy_group(&store, "deepseek");
+        assert_eq!(issues.len(), 1);
+        assert!(issues[0].contains("no-such-model"));
+        assert!(!dry_run_proxy_group(&store, "missing").is_empty());
+    }
+}
@@ -0,0 +1,414 @@
+//! Warm-up of a proxy group when it is enabled.
+//!
+//! Every backend model of the group gets a one-token request in the background, so a wrong
+//! key or base URL shows up when the group is switched on instead of on the first real
+//! request. The ping goes straight to the backend, it is not a client request: budgets,
+//! moderation, the audit log and the dead-letter log do not see it. The outcome of the last warm-up of each group is kept in memory. Warm-ups can be
+//! turned off with `chat_completion_proxy_warmup`.
+
+use axum::body::to_bytes;
+use axum::response::IntoResponse;
+use lazy_static::lazy_static;
+use reqwest::header::{HeaderMap, HeaderValue, ACCEPT, CONTENT_TYPE};
+use rust_i18n::t;
+use serde::Serialize;
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::Instant;
+
+use crate::ccproxy::{
+    adapter::input::from_openai,
+    errors::{CCProxyError, ProxyResult},
+    helper::{get_msg_id, get_provider_chat_full_url, ModelResolver},
+    openai::OpenAIChatCompletionRequest,
+    types::BackendModelTarget,
+    ChatCompletionProxyConfig,
+};
+use crate::constants::{CFG_CCPROXY_WARMUP, CFG_CCPROXY_WARMUP_DEFAULT, CFG_CHAT_COMPLETION_PROXY};
+use crate::db::{MainStore, ProxyGroup};
+
+use super::chat_handler::backend_adapter_for;
+
+/// Event emitted with the `GroupWarmup` of a finished warm-up.
+pub const CCPROXY_WARMUP_EVENT: &str = "cs://ccproxy-warmup";
+/// Largest error body kept in a warm-up result.
+const MAX_ERROR_BODY_BYTES: usize = 16 * 1024;
+
+lazy_static! {
+    /// Last warm-up of each proxy group, by group name.
+    static ref WARMUP_RESULTS: RwLock<HashMap<String, GroupWarmup>> = RwLock::new(HashMap::new());
+}
+
+/// Outcome of warming up one backend model.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WarmupTargetResult {
+    pub provider_id: i64,
+    pub model: String,
+    pub ok: bool,
+    pub status_code: u16,
+    pub latency_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Outcome of warming up a proxy group.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GroupWarmup {
+    pub group: String,
+    pub checked_at: String,
+    pub targets: Vec<WarmupTargetResult>,
+}
+
+/// Returns the last warm-up of `group`, if one ran.
+pub fn get_group_warmup(group: &str) -> Option<GroupWarmup> {
+    WARMUP_RESULTS
+        .read()
+        .ok()
+        .and_then(|results| results.get(group).cloned())
+}
+
+/// Warms up `current` when the change from `previous` enabled it, `previous` is `None` for a
+/// new group.
+///
+/// Returns `None` when the group was not enabled by the change or warm-ups are turned off.
+pub async fn warm_up_enabled_group(
+    main_store: Arc<RwLock<MainStore>>,
+    previous: Option<&ProxyGroup>,
+    current: &ProxyGroup,
+) -> Option<GroupWarmup> {
+    let enabled = !current.disabled && previous.map_or(true, |previous| previous.disabled);
+    if !enabled {
+        return None;
+    }
+    warm_up_group(main_store, &current.name).await
+}
+
+/// Sends a one-token request to every backend model of `group` and keeps the outcome.
+///
+/// Returns `None` when warm-ups are turned off.
+pub async fn warm_up_group(main_store: Arc<RwLock<MainStore>>, group: &str) -> Option<GroupWarmup> {
+    let targets = {
+        let store = main_store.read().ok()?;
+        if !store.get_config(CFG_CCPROXY_WARMUP, CFG_CCPROXY_WARMUP_DEFAULT) {
+            log::debug!(
+                "Proxy group warm-up is turned off, skipped group '{}'",
+                group
+            );
+            return None;
+        }
+        let proxy_config: ChatCompletionProxyConfig =
+            store.get_config(CFG_CHAT_COMPLETION_PROXY, HashMap::new());
+        let mut targets: Vec<BackendModelTarget> = Vec::new();
+        for target in proxy_config
+            .get(group)
+            .into_iter()
+            .flat_map(|aliases| aliases.values().flatten())
+        {
+            if !targets
+                .iter()
+                .any(|seen| seen.id == target.id && seen.model == target.model)
+            {
+                targets.push(target.clone());
+            }
+        }
+        targets
+    };
+
+    log::info!(
+        "Warming up proxy group '{}' with {} backend models",
+        group,
+        targets.len()
+    );
+    let results = futures::future::join_all(
+        targets
+            .into_iter()
+            .map(|target| warm_up_target(main_store.clone(), target)),
+    )
+    .await;
+    for failed in results.iter().filter(|result| !result.ok) {
+        log::warn!(
+            "Warm-up of model '{}' (provider {}) in proxy group '{}' failed with status {}: {}",
+            &failed.model,
+            failed.provider_id,
+            group,
+            failed.status_code,
+            failed.error.as_deref().unwrap_or_default()
+        );
+    }
+
+    let warmup = GroupWarmup {
+        group: group.to_string(),
+        checked_at: chrono::Utc::now().to_rfc3339(),
+        targets: results,
+    };
+    if let Ok(mut results) = WARMUP_RESULTS.write() {
+        results.insert(group.to_string(), warmup.clone());
+    }
+    Some(warmup)
+}
+
+/// Sends the one-token request to a backend model through its backend adapter.
+async fn ping_target(
+    main_store: Arc<RwLock<MainStore>>,
+    target: &BackendModelTarget,
+) -> ProxyResult<reqwest::Response> {
+    let proxy_model = ModelResolver::get_ai_model_by_provider_and_model(
+        main_store.clone(),
+        target.id,
+        target.model.clone(),
+    )
+    .await?;
+    let request: OpenAIChatCompletionRequest = serde_json::from_value(json!({
+        "model": &proxy_model.model,
+        "stream": false,
+        "max_tokens": 1,
+        "messages": [{ "role": "user", "content": "ping" }]
+    }))
+    .map_err(|e| CCProxyError::InternalError(e.to_string()))?;
+    let mut unified_request = from_openai(request, false).map_err(|e| {
+        CCProxyError::InternalError(
+            t!("proxy.error.invalid_request", error = e.to_string()).to_string(),
+        )
+    })?;
+
+    let full_url = get_provider_chat_full_url(
+        proxy_model.chat_protocol.clone(),
+        &proxy_model.base_url,
+        &proxy_model.model,
+        &proxy_model.api_key,
+        false,
+    );
+    let http_client =
+        ModelResolver::build_http_client(main_store, proxy_model.model_metadata.clone())?;
+    let mut headers = HeaderMap::new();
+    ModelResolver::inject_proxy_headers(
+        &mut headers,
+        &HeaderMap::new(),
+        &proxy_model,
+        &get_msg_id(),
+    );
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    headers.insert(ACCEPT, HeaderValue::from_static("application/json"));
+
+    let request_builder = backend_adapter_for(&proxy_model.chat_protocol)
+        .adapt_request(
+            &http_client,
+            &mut unified_request,
+            &proxy_model.api_key,
+            &full_url,
+            &proxy_model.model,
+            false,
+            &mut headers,
+        )
+        .await
+        .map_err(|e| CCProxyError::InternalError(e.to_string()))?;
+    request_builder
+        .headers(headers)
+        .send()
+        .await
+        .map_err(|e| CCProxyError::BackendRequestError(e.to_string()))
+}
+
+async fn warm_up_target(
+    main_store: Arc<RwLock<MainStore>>,
+    target: BackendModelTarget,
+) -> WarmupTargetResult {
+    let mut result = WarmupTargetResult {
+        provider_id: target.id,
+        model: target.model.clone(),
+        ok: false,
+        status_code: 0,
+        latency_ms: 0,
+        error: None,
+    };
+
+    let started_at = Instant::now();
+    let outcome = ping_target(main_store, &target).await;
+    result.latency_ms = started_at.elapsed().as_millis() as u64;
+
+    match outcome {
+        Ok(response) => {
+            result.status_code = response.status().as_u16();
+            result.ok = response.status().is_success();
+            if !result.ok {
+                result.error = Some(match response.bytes().await {
+                    Ok(bytes) => {
+                        String::from_utf8_lossy(&bytes[..bytes.len().min(MAX_ERROR_BODY_BYTES)])
+                            .into_owned()
+                    }
+                    Err(e) => e.to_string(),
+                });
+            }
+        }
+        Err(e) => {
+            let response = e.into_response();
+            result.status_code = response.status().as_u16();
+            result.error = Some(
+                to_bytes(response.into_body(), MAX_ERROR_BODY_BYTES)
+                    .await
+                    .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+                    .unwrap_or_else(|e| e.to_string()),
+            );
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ccproxy::test_util::{store_with_providers, MockProvider};
+    use crate::test::spawn_mock_backend;
+    use axum::{http::StatusCode, routing::post, Json, Router};
+    use serde_json::Value;
+    use tokio::sync::mpsc;
+
+    /// Starts an OpenAI-compatible backend that accepts `valid_key` and reports the request
+    /// bodies it received.
+    async fn mock_backend(valid_key: &'static str) -> (String, mpsc::UnboundedReceiver<Value>) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let app = Router::new().route(
+            "/v1/chat/completions",
+            post(move |headers: axum::http::HeaderMap, Json(body): Json<Value>| {
+                let sender = sender.clone();
+                async move {
+                    let _ = sender.send(body);
+                    let authorized = headers
+                        .get("authorization")
+                        .and_then(|value| value.to_str().ok())
+                        == Some(format!("Bearer {}", valid_key).as_str());
+                    if !authorized {
+                        return (
+                            StatusCode::UNAUTHORIZED,
+                            Json(json!({"error": {"message": "invalid api key"}})),
+                        );
+                    }
+                    (
+                        StatusCode::OK,
+                        Json(json!({
+                            "id": "chatcmpl-1",
+                            "object": "chat.completion",
+                            "created": 1,
+                            "model": "warm-model",
+                            "choices": [{
+                                "index": 0,
+                                "message": {"role": "assistant", "content": "p"},
+                                "finish_reason": "length"
+                            }],
+                            "usage": {"prompt_tokens": 1, "completion_tokens": 1, "total_tokens": 2}
+                        })),
+                    )
+                }
+            }),
+        );
+        (format!("{}/v1", spawn_mock_backend(app).await), receiver)
+    }
+
+    /// Creates a store whose group `group` routes an alias to one provider of the mock.
+    fn store_with_group(base_url: String, api_key: &str, group: &str) -> Arc<RwLock<MainStore>> {
+        let mut provider = MockProvider::new("Mock", "warm-model", base_url);
+        provider.api_key = api_key.to_string();
+        store_with_providers(vec![provider], group, &[("fast", 0), ("fast-*", 0)]).0
+    }
+
+    fn group(name: &str, disabled: bool) -> ProxyGroup {
+        ProxyGroup {
+            name: name.to_string(),
+            disabled,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_enabling_a_group_warms_up_its_models() {
+        let (base_url, mut received) = mock_backend("sk-valid").await;
+        let main_store = store_with_group(base_url, "sk-valid", "warm-enable");
+
+        let warmup = warm_up_enabled_group(
+            main_store.clone(),
+            Some(&group("warm-enable", true)),
+            &group("warm-enable", false),
+        )
+        .await
+        .expect("warm-up ran");
+
+        // Both aliases point at the same model, it is warmed up once
+        assert_eq!(warmup.targets.len(), 1);
+        assert!(warmup.targets[0].ok, "{:?}", warmup.targets[0].error);
+        let request = received.recv().await.expect("warm-up request");
+        assert_eq!(request["max_tokens"], 1);
+        assert!(received.try_recv().is_err());
+        assert_eq!(
+            get_group_warmup("warm-enable").map(|cached| cached.targets.len()),
+            Some(1)
+        );
+
+        // Saving a group that already was enabled does not warm it up again
+        let again = warm_up_enabled_group(
+            main_store,
+            Some(&group("warm-enable", false)),
+            &group("warm-enable", false),
+        )
+        .await;
+        assert!(again.is_none());
+        assert!(received.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_warmup_reports_bad_key_and_can_be_turned_off() {
+        let (base_url, mut received) = mock_backend("sk-valid").await;
+        let main_store = store_with_group(base_url, "sk-wrong", "warm-bad-key");
+
+        let warmup = warm_up_enabled_group(main_store.clone(), None, &group("warm-bad-key", false))
+            .await
+            .expect("warm-up ran");
+        assert!(!warmup.targets[0].ok);
+        assert_eq!(warmup.targets[0].status_code, 401);
+        assert!(warmup.targets[0]
+            .error
+            .as_deref()
+            .is_some_and(|error| error.contains("invalid api key")));
+        while received.try_recv().is_ok() {}
+
+        main_store
+            .write()
+            .expect("store lock")
+            .set_config(CFG_CCPROXY_WARMUP, &json!(false))
+            .expect("turn off warm-up");
+        let skipped = warm_up_enabled_group(main_store, None, &group("warm-bad-key", false)).await;
+        assert!(skipped.is_none());
+        assert!(received.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_warmup_is_not_logged_as_a_client_request() {
+        use crate::constants::CFG_CCPROXY_AUDIT_LOG;
+        use crate::db::CcproxyAuditQuery;
+
+        let (base_url, _received) = mock_backend("sk-valid").await;
+        let main_store = store_with_group(base_url, "sk-wrong", "warm-unlogged");
+        main_store
+            .write()
+            .expect("store lock")
+            .set_config(CFG_CCPROXY_AUDIT_LOG, &json!({"enabled": true}))
+            .expect("enable audit log");
+
+        let warmup = warm_up_group(main_store.clone(), "warm-unlogged")
+            .await
+            .expect("warm-up ran");
+        assert_eq!(warmup.targets[0].status_code, 401);
+
+        let store = main_store.read().expect("store lock");
+        assert!(store
+            .get_ccproxy_audit_log(&CcproxyAuditQuery::default())
+            .expect("audit log")
+            .is_empty());
+        assert!(store
+            .get_ccproxy_dead_letters(10, 0)
+            .expect("dead letters")
+            .is_empty());
+    }
+}
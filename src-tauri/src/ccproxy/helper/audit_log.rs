@@ -0,0 +1,267 @@
+//! Local audit log of proxied requests.
+//!
+//! When enabled under `chat_completion_proxy_audit_log`, every request forwarded by the proxy
+//! appends an entry to `ccproxy_audit_log`: when it was made, the client and backend model, a
+//! fingerprint of the API key, the estimated prompt tokens and a SHA-256 of the prompt. The
+//! prompt itself is only stored with `includeContent`, with credentials scrubbed. Entries are
+//! never rotated out or updated, unlike the dead-letter log, and the log never leaves the machine.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use std::sync::{Arc, RwLock};
+
+use super::redaction::PROMPT_FIELDS;
+use super::secrets::{scrub_text, scrub_value};
+use crate::ccproxy::types::ProxyModel;
+use crate::ccproxy::utils::token_estimator::estimate_tokens;
+use crate::constants::CFG_CCPROXY_AUDIT_LOG;
+use crate::db::{CcproxyAuditEntry, MainStore};
+
+/// Hex characters of the key hash kept as its fingerprint.
+const KEY_ID_LEN: usize = 12;
+
+/// Audit log settings of the proxy, stored under `chat_completion_proxy_audit_log`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AuditLogConfig {
+    pub enabled: bool,
+    /// Whether the prompt itself is stored next to its hash
+    pub include_content: bool,
+}
+
+/// The model and key a request was sent with.
+pub struct AuditTarget<'a> {
+    pub client_model: &'a str,
+    pub backend_model: &'a str,
+    pub provider_id: i64,
+    pub provider: &'a str,
+    pub api_key: &'a str,
+}
+
+impl<'a> From<&'a ProxyModel> for AuditTarget<'a> {
+    fn from(proxy_model: &'a ProxyModel) -> Self {
+        Self {
+            client_model: &proxy_model.client_alias,
+            backend_model: &proxy_model.model,
+            provider_id: proxy_model.provider_id,
+            provider: &proxy_model.provider,
+            api_key: &proxy_model.api_key,
+        }
+    }
+}
+
+/// A short fingerprint that tells keys apart without revealing them.
+fn key_id(api_key: &str) -> String {
+    if api_key.is_empty() {
+        return String::new();
+    }
+    let mut digest = hex::encode(Sha256::digest(api_key.as_bytes()));
+    digest.truncate(KEY_ID_LEN);
+    digest
+}
+
+fn collect_text(value: &Value, out: &mut String) {
+    match value {
+        Value::String(text) => {
+            out.push_str(text);
+            out.push('\n');
+        }
+        Value::Array(items) => items.iter().for_each(|item| collect_text(item, out)),
+        Value::Object(map) => map.values().for_each(|item| collect_text(item, out)),
+        _ => {}
+    }
+}
+
+/// Returns the prompt fields of a client request as JSON with secrets scrubbed, and their
+/// estimated tokens.
+///
+/// Bodies that are not JSON are taken as the prompt text as a whole.
+fn prompt_of(body: &[u8]) -> (String, i64) {
+    let Ok(Value::Object(body)) = serde_json::from_slice::<Value>(body) else {
+        let text = String::from_utf8_lossy(body);
+        let tokens = estimate_tokens(&text).ceil() as i64;
+        return (scrub_text(&text).into_owned(), tokens);
+    };
+    let prompt: Map<String, Value> = body
+        .into_iter()
+        .filter(|(key, _)| PROMPT_FIELDS.contains(&key.as_str()))
+        .collect();
+    let mut prompt = Value::Object(prompt);
+    let mut text = String::new();
+    collect_text(&prompt, &mut text);
+    scrub_value(&mut prompt);
+    (prompt.to_string(), estimate_tokens(&text).ceil() as i64)
+}
+
+/// Builds the audit entry of a request.
+pub fn build_entry(
+    request_id: &str,
+    protocol: &str,
+    target: AuditTarget,
+    body: &[u8],
+    include_content: bool,
+) -> CcproxyAuditEntry {
+    let (prompt, prompt_tokens) = prompt_of(body);
+    CcproxyAuditEntry {
+        id: None,
+        request_id: request_id.to_string(),
+        protocol: protocol.to_string(),
+        client_model: target.client_model.to_string(),
+        backend_model: target.backend_model.to_string(),
+        provider_id: Some(target.provider_id),
+        provider: target.provider.to_string(),
+        key_id: key_id(target.api_key),
+        prompt_tokens,
+        prompt_hash: hex::encode(Sha256::digest(prompt.as_bytes())),
+        prompt_content: include_content.then_some(prompt),
+        created_at: None,
+    }
+}
+
+/// Appends the request to the audit log when it is enabled.
+///
+/// A failure to write the log is logged and does not fail the request.
+pub fn record(
+    main_store: &Arc<RwLock<MainStore>>,
+    request_id: &str,
+    protocol: &str,
+    target: AuditTarget,
+    body: &[u8],
+) {
+    let Ok(store) = main_store.read() else {
+        log::error!("Failed to write audit entry of request {}", request_id);
+        return;
+    };
+    let config: AuditLogConfig = store.get_config(CFG_CCPROXY_AUDIT_LOG, AuditLogConfig::default());
+    if !config.enabled {
+        return;
+    }
+    let entry = build_entry(request_id, protocol, target, body, config.include_content);
+    if let Err(e) = store.record_ccproxy_audit_entry(&entry) {
+        log::error!(
+            "Failed to write audit entry of request {}: {}",
+            request_id,
+            e
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::CcproxyAuditQuery;
+    use serde_json::json;
+
+    fn store(config: AuditLogConfig) -> Arc<RwLock<MainStore>> {
+        let mut store = MainStore::new(":memory:").expect("in-memory store");
+        store
+            .set_config(CFG_CCPROXY_AUDIT_LOG, &json!(config))
+            .expect("audit config");
+        Arc::new(RwLock::new(store))
+    }
+
+    fn target() -> AuditTarget<'static> {
+        AuditTarget {
+            client_model: "code-large",
+            backend_model: "glm-4.6",
+            provider_id: 7,
+            provider: "Zhipu",
+            api_key: "sk-secret-key",
+        }
+    }
+
+    fn entries(main_store: &Arc<RwLock<MainStore>>) -> Vec<CcproxyAuditEntry> {
+        main_store
+            .read()
+            .expect("store lock")
+            .get_ccproxy_audit_log(&CcproxyAuditQuery::default())
+            .expect("audit log")
+    }
+
+    fn body() -> Vec<u8> {
+        json!({
+            "model": "code-large",
+            "stream": true,
+            "messages": [{"role": "user", "content": "Summarize the quarterly report"}]
+        })
+        .to_string()
+        .into_bytes()
+    }
+
+    #[test]
+    fn test_proxied_request_produces_audit_entry() {
+        let main_store = store(AuditLogConfig {
+            enabled: true,
+            include_content: false,
+        });
+        record(&main_store, "msg_1", "openai", target(), &body());
+
+        let entries = entries(&main_store);
+        assert_eq!(entries.len(), 1);
+        let entry = &entries[0];
+        assert_eq!(entry.request_id, "msg_1");
+        assert_eq!(entry.protocol, "openai");
+        assert_eq!(entry.client_model, "code-large");
+        assert_eq!(entry.backend_model, "glm-4.6");
+        assert_eq!(entry.provider_id, Some(7));
+        assert_eq!(entry.key_id, key_id("sk-secret-key"));
+        assert_eq!(entry.key_id.len(), KEY_ID_LEN);
+        assert!(!entry.key_id.contains("secret"));
+        assert!(entry.prompt_tokens > 0);
+        assert_eq!(entry.prompt_hash.len(), 64);
+        assert_eq!(entry.prompt_content, None);
+        assert!(entry.created_at.is_some());
+    }
+
+    #[test]
+    fn test_prompt_hash_ignores_request_options() {
+        let other_options = json!({
+            "model": "code-small",
+            "stream": false,
+            "messages": [{"role": "user", "content": "Summarize the quarterly report"}]
+        })
+        .to_string();
+        let a = build_entry("a", "openai", target(), &body(), false);
+        let b = build_entry("b", "openai", target(), other_options.as_bytes(), false);
+        assert_eq!(a.prompt_hash, b.prompt_hash);
+
+        let c = build_entry("c", "openai", target(), br#"{"messages": []}"#, false);
+        assert_ne!(a.prompt_hash, c.prompt_hash);
+    }
+
+    #[test]
+    fn test_content_is_stored_only_when_opted_in() {
+        let main_store = store(AuditLogConfig {
+            enabled: true,
+            include_content: true,
+        });
+        record(&main_store, "msg_1", "claude", target(), &body());
+        let entries = entries(&main_store);
+        let content = entries[0]
+            .prompt_content
+            .as_deref()
+            .expect("prompt content");
+        assert!(content.contains("Summarize the quarterly report"));
+        assert!(!content.contains("stream"));
+    }
+
+    #[test]
+    fn test_stored_content_has_secrets_scrubbed() {
+        let body = json!({
+            "messages": [{"role": "user", "content": "Why is sk-abcdefghijklmnopqrstuv rejected?"}]
+        })
+        .to_string();
+        let entry = build_entry("msg_1", "openai", target(), body.as_bytes(), true);
+        let content = entry.prompt_content.expect("prompt content");
+        assert!(content.contains("Why is [REDACTED] rejected?"));
+        assert!(!content.contains("sk-abcdefghijklmnopqrstuv"));
+    }
+
+    #[test]
+    fn test_disabled_audit_log_records_nothing() {
+        let main_store = store(AuditLogConfig::default());
+        record(&main_store, "msg_1", "openai", target(), &body());
+        assert!(entries(&main_store).is_empty());
+    }
+}
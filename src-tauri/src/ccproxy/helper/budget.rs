@@ -0,0 +1,461 @@
+//! Estimated cost of proxy requests and spend budgets.
+//!
+//! Every recorded request is priced with the `pricing` of its backend model, using the same
+//! formula as the statistics page. The spend of the current day and month is kept in memory,
+//! seeded from `ccproxy_stats` on first use. When a configured limit is reached the
+//! `cs://ccproxy-budget-alert` event is emitted once per period, and with `blockWhenExceeded`
+//! further requests are rejected until the period ends.
+
+use chrono::{Datelike, Local, NaiveDate};
+use lazy_static::lazy_static;
+use rust_i18n::t;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex, RwLock};
+use tauri::{AppHandle, Emitter};
+
+use crate::ccproxy::errors::{CCProxyError, ProxyResult};
+use crate::constants::CFG_CCPROXY_BUDGET;
+use crate::db::{CcproxyStat, MainStore, PricingConfig};
+
+/// Event emitted to the frontend when a spend limit is reached.
+pub const CCPROXY_BUDGET_ALERT_EVENT: &str = "cs://ccproxy-budget-alert";
+
+/// Budget settings of the proxy, stored under `chat_completion_proxy_budget`.
+///
+/// Limits are in the currency of the model price tables.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BudgetConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub daily_limit: Option<f64>,
+    #[serde(default)]
+    pub monthly_limit: Option<f64>,
+    /// Whether requests are rejected once a limit is reached
+    #[serde(default)]
+    pub block_when_exceeded: bool,
+}
+
+impl BudgetConfig {
+    fn limit(&self, period: BudgetPeriod) -> Option<f64> {
+        let limit = match period {
+            BudgetPeriod::Daily => self.daily_limit,
+            BudgetPeriod::Monthly => self.monthly_limit,
+        };
+        limit.filter(|limit| *limit > 0.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BudgetPeriod {
+    Daily,
+    Monthly,
+}
+
+/// A reached spend limit, sent to the frontend as the event payload.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BudgetAlert {
+    pub period: BudgetPeriod,
+    pub limit: f64,
+    pub spent: f64,
+    /// Whether further requests are rejected until the period ends
+    pub blocking: bool,
+}
+
+impl BudgetAlert {
+    fn message(&self) -> String {
+        let spent = format!("{:.4}", self.spent);
+        let limit = format!("{:.4}", self.limit);
+        match self.period {
+            BudgetPeriod::Daily => t!(
+                "proxy.error.daily_budget_exceeded",
+                spent = spent,
+                limit = limit
+            )
+            .to_string(),
+            BudgetPeriod::Monthly => t!(
+                "proxy.error.monthly_budget_exceeded",
+                spent = spent,
+                limit = limit
+            )
+            .to_string(),
+        }
+    }
+}
+
+/// Estimates the cost of a request from its token usage.
+///
+/// Cached tokens are part of the input tokens and billed with `cache_per_million` instead.
+pub fn estimate_cost(
+    pricing: &PricingConfig,
+    input_tokens: u64,
+    output_tokens: u64,
+    cache_tokens: u64,
+) -> f64 {
+    let uncached_input = input_tokens.saturating_sub(cache_tokens) as f64;
+    let cost = uncached_input * pricing.input_per_million
+        + output_tokens as f64 * pricing.output_per_million
+        + cache_tokens as f64 * pricing.cache_per_million;
+    cost / 1_000_000.0 * pricing.multiplier
+}
+
+/// Looks up the price table of a backend model, `None` if the model has none.
+fn model_pricing(
+    store: &MainStore,
+    provider_id: Option<i64>,
+    backend_model: &str,
+) -> Option<PricingConfig> {
+    let provider = store.config.get_ai_model_by_id(provider_id?).ok()?;
+    provider
+        .models
+        .into_iter()
+        .find(|model| model.id == backend_model)
+        .and_then(|model| model.pricing)
+}
+
+/// Spend of the current day and month.
+#[derive(Debug, Default)]
+struct Spend {
+    day: Option<NaiveDate>,
+    daily: f64,
+    monthly: f64,
+    daily_alerted: bool,
+    monthly_alerted: bool,
+}
+
+impl Spend {
+    /// Resets the totals of the periods that ended before `today`.
+    fn roll_over(&mut self, today: NaiveDate) {
+        if let Some(day) = self.day {
+            if (day.year(), day.month()) != (today.year(), today.month()) {
+                self.monthly = 0.0;
+                self.monthly_alerted = false;
+            }
+            if day != today {
+                self.daily = 0.0;
+                self.daily_alerted = false;
+            }
+        }
+        self.day = Some(today);
+    }
+
+    fn spent(&self, period: BudgetPeriod) -> f64 {
+        match period {
+            BudgetPeriod::Daily => self.daily,
+            BudgetPeriod::Monthly => self.monthly,
+        }
+    }
+
+    fn alerted(&mut self, period: BudgetPeriod) -> &mut bool {
+        match period {
+            BudgetPeriod::Daily => &mut self.daily_alerted,
+            BudgetPeriod::Monthly => &mut self.monthly_alerted,
+        }
+    }
+
+    fn alert(&self, config: &BudgetConfig, period: BudgetPeriod) -> Option<BudgetAlert> {
+        let limit = config.limit(period)?;
+        let spent = self.spent(period);
+        (spent >= limit).then_some(BudgetAlert {
+            period,
+            limit,
+            spent,
+            blocking: config.block_when_exceeded,
+        })
+    }
+
+    /// Adds the cost of a request and returns the limits it reached.
+    ///
+    /// Every limit is reported once per period.
+    fn add(&mut self, config: &BudgetConfig, cost: f64, today: NaiveDate) -> Vec<BudgetAlert> {
+        self.roll_over(today);
+        self.daily += cost;
+        self.monthly += cost;
+        if !config.enabled {
+            return Vec::new();
+        }
+
+        let mut alerts = Vec::new();
+        for period in [BudgetPeriod::Daily, BudgetPeriod::Monthly] {
+            if let Some(alert) = self.alert(config, period) {
+                let alerted = self.alerted(period);
+                if !*alerted {
+                    *alerted = true;
+                    alerts.push(alert);
+                }
+            }
+        }
+        alerts
+    }
+
+    /// The first reached limit that blocks further requests.
+    fn blocking(&mut self, config: &BudgetConfig, today: NaiveDate) -> Option<BudgetAlert> {
+        self.roll_over(today);
+        if !config.enabled || !config.block_when_exceeded {
+            return None;
+        }
+        [BudgetPeriod::Daily, BudgetPeriod::Monthly]
+            .into_iter()
+            .find_map(|period| self.alert(config, period))
+    }
+}
+
+/// Running spend of the proxy.
+pub struct BudgetTracker {
+    /// `None` until seeded from the recorded statistics
+    spend: Mutex<Option<Spend>>,
+    app_handle: RwLock<Option<AppHandle>>,
+}
+
+lazy_static! {
+    pub static ref CCPROXY_BUDGET: Arc<BudgetTracker> = Arc::new(BudgetTracker::new());
+}
+
+impl BudgetTracker {
+    fn new() -> Self {
+        Self {
+            spend: Mutex::new(None),
+            app_handle: RwLock::new(None),
+        }
+    }
+
+    /// Sets the handle used to emit budget alerts to the frontend.
+    pub fn set_app_handle(&self, app_handle: AppHandle) {
+        if let Ok(mut handle) = self.app_handle.write() {
+            *handle = Some(app_handle);
+        }
+    }
+
+    /// Prices the requests of the current month recorded before the proxy started.
+    fn seed(store: &MainStore, config: &BudgetConfig, today: NaiveDate) -> Spend {
+        let mut spend = Spend {
+            day: Some(today),
+            ..Default::default()
+        };
+        let month_start = today.with_day(1).unwrap_or(today);
+        let usage = match store.get_ccproxy_token_usage_since(&month_start.to_string()) {
+            Ok(usage) => usage,
+            Err(e) => {
+                log::error!("Failed to load the proxy spend of the current month: {}", e);
+                return spend;
+            }
+        };
+
+        let today_str = today.to_string();
+        for row in usage {
+            let Some(pricing) = model_pricing(store, row.provider_id, &row.backend_model) else {
+                continue;
+            };
+            let cost = estimate_cost(
+                &pricing,
+                row.input_tokens.max(0) as u64,
+                row.output_tokens.max(0) as u64,
+                row.cache_tokens.max(0) as u64,
+            );
+            spend.monthly += cost;
+            if row.date == today_str {
+                spend.daily += cost;
+            }
+        }
+        // Limits reached before the start were already reported
+        spend.daily_alerted = spend.alert(config, BudgetPeriod::Daily).is_some();
+        spend.monthly_alerted = spend.alert(config, BudgetPeriod::Monthly).is_some();
+        spend
+    }
+
+    fn with_spend<T>(
+        &self,
+        store: &MainStore,
+        config: &BudgetConfig,
+        f: impl FnOnce(&mut Spend, NaiveDate) -> T,
+    ) -> Option<T> {
+        let today = Local::now().date_naive();
+        let mut spend = self.spend.lock().ok()?;
+        let spend = spend.get_or_insert_with(|| Self::seed(store, config, today));
+        Some(f(spend, today))
+    }
+
+    fn emit(&self, alert: &BudgetAlert) {
+        log::warn!(
+            "Proxy {:?} budget reached: spent={:.4}, limit={:.4}, blocking={}",
+            alert.period,
+            alert.spent,
+            alert.limit,
+            alert.blocking
+        );
+        if let Ok(handle) = self.app_handle.read() {
+            if let Some(app_handle) = handle.as_ref() {
+                if let Err(e) = app_handle.emit(CCPROXY_BUDGET_ALERT_EVENT, alert) {
+                    log::error!("Failed to emit the proxy budget alert: {}", e);
+                }
+            }
+        }
+    }
+}
+
+/// Records a proxy statistic and adds its estimated cost to the running spend.
+pub fn record_stat(store: &MainStore, stat: CcproxyStat) {
+    let config: BudgetConfig = store.get_config(CFG_CCPROXY_BUDGET, BudgetConfig::default());
+    let cost = model_pricing(store, stat.provider_id, &stat.backend_model).map(|pricing| {
+        estimate_cost(
+            &pricing,
+            stat.input_tokens.max(0) as u64,
+            stat.output_tokens.max(0) as u64,
+            stat.cache_tokens.max(0) as u64,
+        )
+    });
+    // Seed before recording, otherwise this request would be counted twice
+    let _ = CCPROXY_BUDGET.with_spend(store, &config, |_, _| ());
+    let _ = store.record_ccproxy_stat(stat.clone());
+
+    let Some(cost) = cost.filter(|cost| *cost > 0.0) else {
+        return;
+    };
+    log::info!(
+        "Estimated cost of the request: model={}, provider={}, cost={:.6}",
+        &stat.backend_model,
+        &stat.provider,
+        cost
+    );
+    let alerts = CCPROXY_BUDGET
+        .with_spend(store, &config, |spend, today| {
+            spend.add(&config, cost, today)
+        })
+        .unwrap_or_default();
+    for alert in alerts {
+        CCPROXY_BUDGET.emit(&alert);
+    }
+}
+
+/// Rejects the request if a reached spend limit blocks further requests.
+pub fn check_budget(main_store: &Arc<RwLock<MainStore>>) -> ProxyResult<()> {
+    let store = main_store
+        .read()
+        .map_err(|e| CCProxyError::StoreLockError(e.to_string()))?;
+    let config: BudgetConfig = store.get_config(CFG_CCPROXY_BUDGET, BudgetConfig::default());
+    if !config.enabled || !config.block_when_exceeded {
+        return Ok(());
+    }
+
+    match CCPROXY_BUDGET
+        .with_spend(&store, &config, |spend, today| {
+            spend.blocking(&config, today)
+        })
+        .flatten()
+    {
+        Some(alert) => Err(CCProxyError::BudgetExceeded(alert.message())),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).expect("valid date")
+    }
+
+    fn budget(daily_limit: Option<f64>, monthly_limit: Option<f64>) -> BudgetConfig {
+        BudgetConfig {
+            enabled: true,
+            daily_limit,
+            monthly_limit,
+            block_when_exceeded: true,
+        }
+    }
+
+    #[test]
+    fn test_cost_from_usage() {
+        let pricing = PricingConfig {
+            input_per_million: 3.0,
+            output_per_million: 15.0,
+            cache_per_million: 0.3,
+            multiplier: 1.0,
+        };
+        // 800k uncached input, 200k cached input and 100k output
+        let cost = estimate_cost(&pricing, 1_000_000, 100_000, 200_000);
+        assert!((cost - (2.4 + 1.5 + 0.06)).abs() < 1e-9);
+
+        let discounted = PricingConfig {
+            multiplier: 0.5,
+            ..pricing.clone()
+        };
+        assert!(
+            (estimate_cost(&discounted, 1_000_000, 100_000, 200_000) - cost / 2.0).abs() < 1e-9
+        );
+
+        // More cached than input tokens never yields a negative input cost
+        assert!((estimate_cost(&pricing, 10, 0, 20) - 20.0 * 0.3 / 1e6).abs() < 1e-12);
+
+        let pricing: PricingConfig =
+            serde_json::from_str(r#"{"inputPerMillion": 1.0}"#).expect("pricing");
+        assert_eq!(pricing.multiplier, 1.0);
+        assert_eq!(estimate_cost(&pricing, 2_000_000, 0, 0), 2.0);
+    }
+
+    #[test]
+    fn test_crossing_threshold_alerts_once() {
+        let config = budget(Some(1.0), Some(5.0));
+        let today = date(2026, 3, 10);
+        let mut spend = Spend::default();
+
+        assert!(spend.add(&config, 0.6, today).is_empty());
+        assert!(spend.blocking(&config, today).is_none());
+
+        let alerts = spend.add(&config, 0.5, today);
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].period, BudgetPeriod::Daily);
+        assert_eq!(alerts[0].limit, 1.0);
+        assert!((alerts[0].spent - 1.1).abs() < 1e-9);
+        assert!(alerts[0].blocking);
+        assert_eq!(
+            spend.blocking(&config, today).map(|alert| alert.period),
+            Some(BudgetPeriod::Daily)
+        );
+
+        // Already reported for today
+        assert!(spend.add(&config, 0.1, today).is_empty());
+    }
+
+    #[test]
+    fn test_periods_roll_over() {
+        let config = budget(Some(1.0), Some(2.5));
+        let mut spend = Spend::default();
+
+        assert_eq!(spend.add(&config, 1.2, date(2026, 3, 30)).len(), 1);
+        let alerts = spend.add(&config, 1.4, date(2026, 3, 31));
+        assert_eq!(
+            alerts.iter().map(|alert| alert.period).collect::<Vec<_>>(),
+            vec![BudgetPeriod::Daily, BudgetPeriod::Monthly]
+        );
+
+        // A new month resets both periods
+        assert!(spend.blocking(&config, date(2026, 4, 1)).is_none());
+        assert!((spend.monthly).abs() < 1e-9);
+        assert_eq!(spend.add(&config, 1.0, date(2026, 4, 1)).len(), 1);
+    }
+
+    #[test]
+    fn test_disabled_budget_tracks_without_alerting() {
+        let today = date(2026, 3, 10);
+        let config = BudgetConfig {
+            enabled: false,
+            ..budget(Some(1.0), None)
+        };
+        let mut spend = Spend::default();
+        assert!(spend.add(&config, 2.0, today).is_empty());
+        assert!(spend.blocking(&config, today).is_none());
+        assert_eq!(spend.daily, 2.0);
+
+        let alert_only = BudgetConfig {
+            block_when_exceeded: false,
+            ..budget(Some(1.0), None)
+        };
+        assert!(spend.blocking(&alert_only, today).is_none());
+        assert!(!spend.add(&alert_only, 0.1, today)[0].blocking);
+    }
+}
@@ -0,0 +1,354 @@
+//! Reflows source code a model wrote on a single line.
+//!
+//! After a few failed edits in tool compat mode, some models write a whole file as one line, so
+//! the first line comment swallows all the code after it. The reflow breaks such content into
+//! lines again: after `;`, `{` and `}` for brace languages and after `;` for Python. A line
+//! comment ends where the indentation of the next line shows up as a run of whitespace. String
+//! literals and block comments are never split.
+
+/// How statements of a language are grouped.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Layout {
+    Braces,
+    Indentation,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Syntax {
+    layout: Layout,
+    line_comment: Option<&'static str>,
+    /// `'...'` is a string, otherwise only a character literal such as `'a'` or `'\n'`
+    single_quote_strings: bool,
+    backtick_strings: bool,
+}
+
+fn syntax_for(extension: &str) -> Option<Syntax> {
+    let syntax = |layout, line_comment, single_quote_strings, backtick_strings| Syntax {
+        layout,
+        line_comment,
+        single_quote_strings,
+        backtick_strings,
+    };
+    match extension.to_ascii_lowercase().as_str() {
+        "rs" | "c" | "h" | "cc" | "cpp" | "cxx" | "hpp" | "java" | "cs" | "kt" | "kts"
+        | "swift" | "scala" | "dart" => Some(syntax(Layout::Braces, Some("//"), false, false)),
+        "go" => Some(syntax(Layout::Braces, Some("//"), false, true)),
+        "js" | "jsx" | "mjs" | "cjs" | "ts" | "tsx" => {
+            Some(syntax(Layout::Braces, Some("//"), true, true))
+        }
+        "php" | "scss" | "less" => Some(syntax(Layout::Braces, Some("//"), true, false)),
+        "css" => Some(syntax(Layout::Braces, None, true, false)),
+        "py" | "pyi" => Some(syntax(Layout::Indentation, Some("#"), true, false)),
+        _ => None,
+    }
+}
+
+/// Returns `text` broken into lines when it is code on a single line longer than `min_chars`
+/// characters, `None` when it is left as it is.
+pub fn reflow_single_line_code(text: &str, extension: &str, min_chars: usize) -> Option<String> {
+    if text.trim_end().contains('\n') || text.chars().count() <= min_chars {
+        return None;
+    }
+    let syntax = syntax_for(extension)?;
+    let chars: Vec<char> = text.trim().chars().collect();
+    let lines = match syntax.layout {
+        Layout::Braces => reflow_braces(&chars, &syntax)?,
+        Layout::Indentation => reflow_indented(&chars, &syntax),
+    };
+    if lines.len() < 2 {
+        return None;
+    }
+    let mut reflowed = lines.join("\n");
+    if text.ends_with('\n') {
+        reflowed.push('\n');
+    }
+    Some(reflowed)
+}
+
+fn starts_with_at(chars: &[char], index: usize, pattern: &str) -> bool {
+    pattern
+        .chars()
+        .enumerate()
+        .all(|(offset, expected)| chars.get(index + offset) == Some(&expected))
+}
+
+/// Index after the closing `quote` of a literal opened before `from`, honouring escapes.
+fn closing_quote(chars: &[char], from: usize, quote: &str) -> usize {
+    let mut index = from;
+    while index < chars.len() {
+        if chars[index] == '\\' {
+            index += 2;
+        } else if starts_with_at(chars, index, quote) {
+            return index + quote.chars().count();
+        } else {
+            index += 1;
+        }
+    }
+    chars.len()
+}
+
+/// End of the string or character literal starting at `index`, if one does.
+fn literal_end(chars: &[char], index: usize, syntax: &Syntax) -> Option<usize> {
+    if syntax.layout == Layout::Indentation {
+        for quote in ["\"\"\"", "'''"] {
+            if starts_with_at(chars, index, quote) {
+                return Some(closing_quote(chars, index + 3, quote));
+            }
+        }
+    }
+    match chars[index] {
+        '"' => Some(closing_quote(chars, index + 1, "\"")),
+        '`' if syntax.backtick_strings => Some(closing_quote(chars, index + 1, "`")),
+        '\'' if syntax.single_quote_strings => Some(closing_quote(chars, index + 1, "'")),
+        // A character literal, a lifetime such as `'a` is not one
+        '\'' if chars.get(index + 1) == Some(&'\\') => Some(closing_quote(chars, index + 2, "'")),
+        '\'' if chars.get(index + 2) == Some(&'\'') => Some(index + 3),
+        _ => None,
+    }
+}
+
+/// End of the line comment starting at `index`: the first run of two whitespace characters or
+/// a tab, where the indentation of the next line begins, or the end of the text.
+fn line_comment_end(chars: &[char], index: usize) -> usize {
+    (index..chars.len())
+        .find(|&position| {
+            chars[position] == '\t'
+                || (chars[position] == ' '
+                    && chars.get(position + 1).is_some_and(|c| c.is_whitespace()))
+        })
+        .unwrap_or(chars.len())
+}
+
+fn skip_whitespace(chars: &[char], mut index: usize) -> usize {
+    while chars.get(index).is_some_and(|c| c.is_whitespace()) {
+        index += 1;
+    }
+    index
+}
+
+fn next_word(chars: &[char], index: usize) -> String {
+    chars[skip_whitespace(chars, index)..]
+        .iter()
+        .take_while(|c| c.is_alphanumeric())
+        .collect()
+}
+
+/// Lines of brace-delimited code, `None` when the braces do not balance.
+fn reflow_braces(chars: &[char], syntax: &Syntax) -> Option<Vec<String>> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut line = String::new();
+    let mut depth = 0usize;
+    let mut brackets: Vec<char> = Vec::new();
+    let flush = |lines: &mut Vec<String>, line: &mut String, depth: usize| {
+        let trimmed = line.trim();
+        if !trimmed.is_empty() {
+            lines.push(format!("{}{}", "    ".repeat(depth), trimmed));
+        }
+        line.clear();
+    };
+
+    let mut index = 0;
+    while index < chars.len() {
+        if let Some(end) = literal_end(chars, index, syntax) {
+            line.extend(&chars[index..end]);
+            index = end;
+            continue;
+        }
+        if starts_with_at(chars, index, "/*") {
+            let end = (index + 2..chars.len())
+                .find(|&position| starts_with_at(chars, position, "*/"))
+                .map_or(chars.len(), |position| position + 2);
+            line.extend(&chars[index..end]);
+            index = end;
+            continue;
+        }
+        if syntax
+            .line_comment
+            .is_some_and(|comment| starts_with_at(chars, index, comment))
+        {
+            let end = line_comment_end(chars, index);
+            line.extend(&chars[index..end]);
+            flush(&mut lines, &mut line, depth);
+            index = skip_whitespace(chars, end);
+            continue;
+        }
+
+        let ch = chars[index];
+        match ch {
+            '(' | '[' => {
+                brackets.push(ch);
+                line.push(ch);
+            }
+            ')' | ']' => {
+                brackets.pop();
+                line.push(ch);
+            }
+            '{' => {
+                brackets.push(ch);
+                line.push(ch);
+                flush(&mut lines, &mut line, depth);
+                depth += 1;
+            }
+            '}' => {
+                if brackets.pop() != Some('{') {
+                    return None;
+                }
+                flush(&mut lines, &mut line, depth);
+                depth = depth.checked_sub(1)?;
+                line.push(ch);
+                let next = chars[skip_whitespace(chars, index + 1)..].first();
+                let continues = next.is_some_and(|&c| matches!(c, ';' | ',' | ')' | ']'))
+                    || matches!(
+                        next_word(chars, index + 1).as_str(),
+                        "else" | "catch" | "finally" | "while"
+                    );
+                if !continues {
+                    flush(&mut lines, &mut line, depth);
+                }
+            }
+            ';' => {
+                line.push(ch);
+                // The `;` of a `for (..;..;..)` header or of `[0; 4]` stays on the line
+                if brackets.last().is_none_or(|open| *open == '{') {
+                    flush(&mut lines, &mut line, depth);
+                }
+            }
+            _ if line.is_empty() && ch.is_whitespace() => {}
+            _ => line.push(ch),
+        }
+        index += 1;
+    }
+    if depth != 0 {
+        return None;
+    }
+    flush(&mut lines, &mut line, depth);
+    Some(lines)
+}
+
+fn starts_compound_statement(line: &str) -> bool {
+    let keyword: String = line
+        .trim_start()
+        .chars()
+        .take_while(|c| c.is_alphanumeric())
+        .collect();
+    matches!(
+        keyword.as_str(),
+        "if" | "elif"
+            | "else"
+            | "for"
+            | "while"
+            | "with"
+            | "def"
+            | "class"
+            | "try"
+            | "except"
+            | "finally"
+            | "async"
+    )
+}
+
+/// Lines of indentation-based code; the whitespace after a comment is the next indentation.
+fn reflow_indented(chars: &[char], syntax: &Syntax) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut line = String::new();
+    let mut indent = String::new();
+    let mut brackets = 0usize;
+    let flush = |lines: &mut Vec<String>, line: &mut String, indent: &str| {
+        let trimmed = line.trim();
+        if !trimmed.is_empty() {
+            lines.push(format!("{}{}", indent, trimmed));
+        }
+        line.clear();
+    };
+
+    let mut index = 0;
+    while index < chars.len() {
+        if let Some(end) = literal_end(chars, index, syntax) {
+            line.extend(&chars[index..end]);
+            index = end;
+            continue;
+        }
+        if syntax
+            .line_comment
+            .is_some_and(|comment| starts_with_at(chars, index, comment))
+        {
+            let end = line_comment_end(chars, index);
+            line.extend(&chars[index..end]);
+            flush(&mut lines, &mut line, &indent);
+            let next = skip_whitespace(chars, end);
+            indent = chars[end..next].iter().collect();
+            index = next;
+            continue;
+        }
+
+        let ch = chars[index];
+        match ch {
+            '(' | '[' | '{' => {
+                brackets += 1;
+                line.push(ch);
+            }
+            ')' | ']' | '}' => {
+                brackets = brackets.saturating_sub(1);
+                line.push(ch);
+            }
+            // The `;` of `for x in y: a; b` separates statements of the loop body
+            ';' if brackets == 0 && !starts_compound_statement(&line) => {
+                flush(&mut lines, &mut line, &indent)
+            }
+            _ if line.is_empty() && ch.is_whitespace() => {}
+            _ => line.push(ch),
+        }
+        index += 1;
+    }
+    flush(&mut lines, &mut line, &indent);
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::reflow_single_line_code;
+
+    #[test]
+    fn test_comment_no_longer_swallows_the_code() {
+        let code = "// Entry point    fn main() { let s = \"a; b { c }\"; for c in s.chars() { if c == ';' { continue; } else { print!(\"{}\", c); } } }";
+        assert_eq!(
+            reflow_single_line_code(code, "rs", 40).as_deref(),
+            Some(
+                "// Entry point\n\
+                 fn main() {\n    \
+                     let s = \"a; b { c }\";\n    \
+                     for c in s.chars() {\n        \
+                         if c == ';' {\n            \
+                             continue;\n        \
+                         } else {\n            \
+                             print!(\"{}\", c);\n        \
+                         }\n    \
+                     }\n\
+                 }"
+            )
+        );
+    }
+
+    #[test]
+    fn test_content_that_is_not_collapsed_code_is_left_alone() {
+        let long_line = format!("let x = [0; 4]; {}", "let y = 1; ".repeat(30));
+        // Multi-line content, short content and non-code files
+        assert_eq!(
+            reflow_single_line_code(&format!("{}\nfn a() {{}}", long_line), "rs", 200),
+            None
+        );
+        assert_eq!(
+            reflow_single_line_code("let a = 1; let b = 2;", "rs", 200),
+            None
+        );
+        assert_eq!(reflow_single_line_code(&long_line, "md", 200), None);
+        // Unbalanced braces are not guessed at
+        assert_eq!(
+            reflow_single_line_code(&format!("{} }}", long_line), "rs", 200),
+            None
+        );
+        // Bracketed `;` stays put
+        let reflowed = reflow_single_line_code(&long_line, "rs", 200).expect("reflowed");
+        assert!(reflowed.starts_with("let x = [0; 4];\nlet y = 1;\n"));
+    }
+}
@@ -1,8 +1,15 @@
 use crate::{
     ai::{network::ProxyType, util::get_proxy_type},
     ccproxy::{
+        adapter::{
+            input::helper::empty_messages::EmptyMessagePolicy,
+            unified::{OpenAICompatQuirks, UnifiedTool},
+        },
         errors::{CCProxyError, ProxyResult},
-        helper::{proxy_rotator::GlobalApiKey, CC_PROXY_ROTATOR},
+        helper::{
+            concurrency::ConcurrencyLimit, fallback::ModelFallback, proxy_rotator::GlobalApiKey,
+            CC_PROXY_ROTATOR,
+        },
         types::{BackendModelTarget, ChatCompletionProxyConfig, ProxyModel},
         ChatProtocol,
     },
@@ -13,6 +20,7 @@ use crate::{
 use reqwest::Client;
 use rust_i18n::t;
 use serde::Deserialize;
+use serde_json::{json, Value};
 use std::{collections::HashMap, str::FromStr, sync::Arc, vec};
 
 #[derive(Deserialize)]
@@ -271,6 +279,10 @@ impl ModelResolver {
             }
             map
         });
+        let tool_include = group_config
+            .as_ref()
+            .map(|g| parse_tool_include(g.metadata.as_ref()))
+            .unwrap_or_default();
         let temp_ratio = group_config
             .as_ref()
             .map_or(1.0, |g| g.temperature.unwrap_or(1.0));
@@ -296,6 +308,54 @@ impl ModelResolver {
                 .unwrap_or_default()
         });
 
+        let max_history_turns = group_config
+            .as_ref()
+            .ok()
+            .and_then(|g| parse_max_history_turns(g.metadata.as_ref()));
+        let tool_result_max_chars = group_config
+            .as_ref()
+            .ok()
+            .and_then(|g| parse_tool_result_max_chars(g.metadata.as_ref()));
+        let strip_history_reasoning = group_config
+            .as_ref()
+            .ok()
+            .and_then(|g| g.metadata.as_ref())
+            .and_then(|m| m.get("stripHistoryReasoning"))
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        let merge_same_role_messages = group_config
+            .as_ref()
+            .ok()
+            .and_then(|g| g.metadata.as_ref())
+            .and_then(|m| m.get("mergeSameRoleMessages"))
+            .and_then(|v| v.as_bool())
+            .unwrap_or(true);
+        let empty_messages = group_config
+            .as_ref()
+            .map(|g| parse_empty_messages(g.metadata.as_ref()))
+            .unwrap_or_default();
+        let extra_body_keys = group_config
+            .as_ref()
+            .map(|g| parse_extra_body_keys(g.metadata.as_ref()))
+            .unwrap_or_default();
+        let openai_quirks = group_config
+            .as_ref()
+            .ok()
+            .and_then(|g| parse_openai_quirks(g.metadata.as_ref()));
+        let concurrency_limit = group_config
+            .as_ref()
+            .ok()
+            .and_then(|g| ConcurrencyLimit::from_group_metadata(group_name, g.metadata.as_ref()));
+        let fallback = group_config
+            .as_ref()
+            .ok()
+            .and_then(|g| ModelFallback::from_group_metadata(group_name, g.metadata.as_ref()));
+        // Group defaults take precedence over the model's own defaults, the client over both
+        let group_defaults = group_config
+            .as_ref()
+            .map(|g| parse_default_params(g.metadata.as_ref()))
+            .unwrap_or_default();
+
         // Read tool_compat_mode from metadata
         let tool_compat_mode_override = group_config
             .as_ref()
@@ -309,11 +369,15 @@ impl ModelResolver {
 
         // Ollama hasn't api key
         if ai_model_detail.api_protocol == ChatProtocol::Ollama.to_string() {
-            let custom_params = ai_model_detail
+            let model_config = ai_model_detail
                 .models
                 .iter()
-                .find(|m| m.id == backend_target.model)
-                .and_then(|m| m.custom_params.clone());
+                .find(|m| m.id == backend_target.model);
+            let custom_params = model_config.and_then(|m| m.custom_params.clone());
+            let prompt_template = model_config.and_then(|m| m.prompt_template.clone());
+            let model_stop = model_config
+                .and_then(|m| m.stop.clone())
+                .unwrap_or_default();
 
             let metadata = ai_model_detail.metadata.as_ref();
 
@@ -331,16 +395,19 @@ impl ModelResolver {
                 prompt_injection: prompt_injection,
                 prompt_text: prompt_text,
                 tool_filter: tool_filter,
+                tool_include: tool_include,
                 temp_ratio,
-                max_tokens: if ai_model_detail.max_tokens > 0 {
-                    Some(ai_model_detail.max_tokens)
-                } else {
-                    None
-                },
-                temperature: metadata
+                max_tokens: group_defaults
+                    .max_tokens
+                    .or(if ai_model_detail.max_tokens > 0 {
+                        Some(ai_model_detail.max_tokens)
+                    } else {
+                        None
+                    }),
+                temperature: group_defaults.temperature.or(metadata
                     .and_then(|m| m.get("temperature"))
                     .and_then(|v| v.as_f64())
-                    .map(|v| v as f32),
+                    .map(|v| v as f32)),
                 presence_penalty: metadata
                     .and_then(|m| m.get("presencePenalty"))
                     .and_then(|v| v.as_f64())
@@ -349,17 +416,28 @@ impl ModelResolver {
                     .and_then(|m| m.get("frequencyPenalty"))
                     .and_then(|v| v.as_f64())
                     .map(|v| v as f32),
-                top_p: if ai_model_detail.top_p > 0.0 && ai_model_detail.top_p < 1.0 {
-                    Some(ai_model_detail.top_p)
-                } else {
-                    None
-                },
+                top_p: group_defaults.top_p.or(
+                    if ai_model_detail.top_p > 0.0 && ai_model_detail.top_p < 1.0 {
+                        Some(ai_model_detail.top_p)
+                    } else {
+                        None
+                    },
+                ),
                 top_k: if ai_model_detail.top_k > 0 {
                     Some(ai_model_detail.top_k)
                 } else {
                     None
                 },
                 prompt_replace,
+                max_history_turns,
+                tool_result_max_chars,
+                strip_history_reasoning,
+                merge_same_role_messages,
+                empty_messages,
+                extra_body_keys,
+                openai_quirks,
+                concurrency_limit,
+                fallback,
                 stop: metadata
                     .and_then(|m| m.get("stop"))
                     .and_then(|v| v.as_str())
@@ -370,6 +448,8 @@ impl ModelResolver {
                             .collect()
                     })
                     .unwrap_or_default(),
+                prompt_template,
+                model_stop,
                 tool_compat_mode: tool_compat_mode_override.map(|s| s.to_string()),
             });
         }
@@ -414,11 +494,15 @@ impl ModelResolver {
         let backend_chat_protocol = ChatProtocol::from_str(&ai_model_details.api_protocol)
             .map_err(|e| CCProxyError::InvalidProtocolError(e.to_string()))?;
 
-        let custom_params = ai_model_details
+        let model_config = ai_model_details
             .models
             .iter()
-            .find(|m| m.id == global_key.model_name)
-            .and_then(|m| m.custom_params.clone());
+            .find(|m| m.id == global_key.model_name);
+        let custom_params = model_config.and_then(|m| m.custom_params.clone());
+        let prompt_template = model_config.and_then(|m| m.prompt_template.clone());
+        let model_stop = model_config
+            .and_then(|m| m.stop.clone())
+            .unwrap_or_default();
 
         let metadata = ai_model_details.metadata.as_ref();
 
@@ -436,16 +520,19 @@ impl ModelResolver {
             prompt_injection_position: Some(prompt_injection_position),
             prompt_text,
             tool_filter,
+            tool_include,
             temp_ratio,
-            max_tokens: if ai_model_details.max_tokens > 0 {
-                Some(ai_model_details.max_tokens)
-            } else {
-                None
-            },
-            temperature: metadata
+            max_tokens: group_defaults
+                .max_tokens
+                .or(if ai_model_details.max_tokens > 0 {
+                    Some(ai_model_details.max_tokens)
+                } else {
+                    None
+                }),
+            temperature: group_defaults.temperature.or(metadata
                 .and_then(|m| m.get("temperature"))
                 .and_then(|v| v.as_f64())
-                .map(|v| v as f32),
+                .map(|v| v as f32)),
             presence_penalty: metadata
                 .and_then(|m| m.get("presencePenalty"))
                 .and_then(|v| v.as_f64())
@@ -454,17 +541,28 @@ impl ModelResolver {
                 .and_then(|m| m.get("frequencyPenalty"))
                 .and_then(|v| v.as_f64())
                 .map(|v| v as f32),
-            top_p: if ai_model_details.top_p > 0.0 && ai_model_details.top_p < 1.0 {
-                Some(ai_model_details.top_p)
-            } else {
-                None
-            },
+            top_p: group_defaults.top_p.or(
+                if ai_model_details.top_p > 0.0 && ai_model_details.top_p < 1.0 {
+                    Some(ai_model_details.top_p)
+                } else {
+                    None
+                },
+            ),
             top_k: if ai_model_details.top_k > 0 {
                 Some(ai_model_details.top_k)
             } else {
                 None
             },
             prompt_replace,
+            max_history_turns,
+            tool_result_max_chars,
+            strip_history_reasoning,
+            merge_same_role_messages,
+            empty_messages,
+            extra_body_keys,
+            openai_quirks,
+            concurrency_limit,
+            fallback,
             stop: metadata
                 .and_then(|m| m.get("stop"))
                 .and_then(|v| v.as_str())
@@ -475,6 +573,8 @@ impl ModelResolver {
                         .collect()
                 })
                 .unwrap_or_default(),
+            prompt_template,
+            model_stop,
             tool_compat_mode: tool_compat_mode_override.map(|s| s.to_string()),
         })
     }
@@ -686,11 +786,12 @@ impl ModelResolver {
             &selected_api_key[std::cmp::max(0, selected_api_key.len() - 8)..] // Log last 8 chars for debugging
         );
 
-        let custom_params = ai_model_detail
-            .models
-            .iter()
-            .find(|m| m.id == model_id)
-            .and_then(|m| m.custom_params.clone());
+        let model_config = ai_model_detail.models.iter().find(|m| m.id == model_id);
+        let custom_params = model_config.and_then(|m| m.custom_params.clone());
+        let prompt_template = model_config.and_then(|m| m.prompt_template.clone());
+        let model_stop = model_config
+            .and_then(|m| m.stop.clone())
+            .unwrap_or_default();
 
         let metadata = ai_model_detail.metadata.as_ref();
 
@@ -708,7 +809,17 @@ impl ModelResolver {
             prompt_injection_position: Some("system".to_string()),
             prompt_text: "".to_string(),
             tool_filter: HashMap::new(),
+            tool_include: Vec::new(),
             prompt_replace: Vec::new(),
+            max_history_turns: None,
+            tool_result_max_chars: None,
+            strip_history_reasoning: false,
+            merge_same_role_messages: true,
+            empty_messages: EmptyMessagePolicy::default(),
+            extra_body_keys: Vec::new(),
+            openai_quirks: None,
+            concurrency_limit: None,
+            fallback: None,
             temp_ratio: 1.0,
             max_tokens: if ai_model_detail.max_tokens > 0 {
                 Some(ai_model_detail.max_tokens)
@@ -747,6 +858,8 @@ impl ModelResolver {
                         .collect()
                 })
                 .unwrap_or_default(),
+            prompt_template,
+            model_stop,
             tool_compat_mode: None,
         })
     }
@@ -927,6 +1040,8 @@ pub fn should_forward_header(name_str: &str) -> bool {
         "host",
         "connection",
         "content-length",
+        // Negotiated by the HTTP client, which only decodes the encodings it asked for
+        "accept-encoding",
         "transfer-encoding",
         "keep-alive",
         "proxy-authenticate",
@@ -1054,9 +1169,181 @@ pub fn get_msg_id() -> String {
     format!("msg_{}", &uuid::Uuid::new_v4().to_string()[..8])
 }
 
+/// Parses the `toolInclude` group metadata into tool definitions.
+///
+/// Each entry needs a `name`; the schema is read from `inputSchema`, `input_schema`
+/// or `parameters` so definitions can be pasted from any protocol.
+/// Reads the group's `maxHistoryTurns`, a positive turn count.
+fn parse_max_history_turns(metadata: Option<&Value>) -> Option<usize> {
+    metadata
+        .and_then(|m| m.get("maxHistoryTurns"))
+        .and_then(|v| v.as_u64())
+        .filter(|turns| *turns > 0)
+        .and_then(|turns| usize::try_from(turns).ok())
+}
+
+/// Reads the group's `toolResultMaxChars`, a positive character count.
+fn parse_tool_result_max_chars(metadata: Option<&Value>) -> Option<usize> {
+    metadata
+        .and_then(|m| m.get("toolResultMaxChars"))
+        .and_then(|v| v.as_u64())
+        .filter(|chars| *chars > 0)
+        .and_then(|chars| usize::try_from(chars).ok())
+}
+
+/// Reads the group's `extraBodyKeys`, the client body keys forwarded verbatim.
+fn parse_extra_body_keys(metadata: Option<&Value>) -> Vec<String> {
+    metadata
+        .and_then(|m| m.get("extraBodyKeys"))
+        .and_then(|v| v.as_array())
+        .map(|keys| {
+            keys.iter()
+                .filter_map(|key| key.as_str())
+                .map(str::trim)
+                .filter(|key| !key.is_empty())
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Sampling defaults of a proxy group, used when the client does not send the parameter.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+struct GroupDefaultParams {
+    temperature: Option<f32>,
+    max_tokens: Option<i32>,
+    top_p: Option<f32>,
+}
+
+/// Reads the group's `defaultParams` (`temperature`, `maxTokens`, `topP`), values outside
+/// their valid range are ignored.
+fn parse_default_params(metadata: Option<&Value>) -> GroupDefaultParams {
+    let Some(params) = metadata.and_then(|m| m.get("defaultParams")) else {
+        return GroupDefaultParams::default();
+    };
+    let read = |key: &str, is_valid: &dyn Fn(f64) -> bool| -> Option<f64> {
+        let value = params.get(key).filter(|v| !v.is_null())?;
+        let number = value.as_f64().filter(|n| is_valid(*n));
+        if number.is_none() {
+            log::warn!("Ignoring defaultParams.{} out of range: {}", key, value);
+        }
+        number
+    };
+
+    GroupDefaultParams {
+        temperature: read("temperature", &|t| (0.0..=2.0).contains(&t)).map(|t| t as f32),
+        max_tokens: read("maxTokens", &|t| {
+            t >= 1.0 && t.fract() == 0.0 && t <= f64::from(i32::MAX)
+        })
+        .map(|t| t as i32),
+        top_p: read("topP", &|p| p > 0.0 && p <= 1.0).map(|p| p as f32),
+    }
+}
+
+/// Reads the group's `emptyMessages`, `drop` (the default), `fill` or `keep`.
+fn parse_empty_messages(metadata: Option<&Value>) -> EmptyMessagePolicy {
+    let Some(policy) = metadata
+        .and_then(|m| m.get("emptyMessages"))
+        .filter(|v| !v.is_null())
+    else {
+        return EmptyMessagePolicy::default();
+    };
+    serde_json::from_value(policy.clone())
+        .map_err(|e| log::warn!("Ignoring unknown emptyMessages {}: {}", policy, e))
+        .unwrap_or_default()
+}
+
+/// Reads the group's `openaiQuirks`, `vllm` or `sglang`.
+fn parse_openai_quirks(metadata: Option<&Value>) -> Option<OpenAICompatQuirks> {
+    let quirks = metadata
+        .and_then(|m| m.get("openaiQuirks"))
+        .filter(|v| !v.is_null() && v.as_str().map_or(true, |s| !s.is_empty() && s != "none"))?;
+    serde_json::from_value(quirks.clone())
+        .map_err(|e| log::warn!("Ignoring unknown openaiQuirks {}: {}", quirks, e))
+        .ok()
+}
+
+fn parse_tool_include(metadata: Option<&Value>) -> Vec<UnifiedTool> {
+    metadata
+        .and_then(|m| m.get("toolInclude"))
+        .and_then(|v| v.as_array())
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|item| {
+                    let name = item.get("name").and_then(|v| v.as_str())?.trim();
+                    if name.is_empty() {
+                        return None;
+                    }
+                    let input_schema = item
+                        .get("inputSchema")
+                        .or_else(|| item.get("input_schema"))
+                        .or_else(|| item.get("parameters"))
+                        .cloned()
+                        .unwrap_or_else(|| json!({"type": "object", "properties": {}}));
+                    Some(UnifiedTool {
+                        name: name.to_string(),
+                        description: item
+                            .get("description")
+                            .and_then(|v| v.as_str())
+                            .map(|d| d.to_string()),
+                        input_schema,
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
 #[cfg(test)]
 mod tests {
     use indexmap::IndexMap;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_tool_include() {
+        let metadata = json!({
+            "toolInclude": [
+                {"name": "web_search", "description": "Search", "parameters": {"type": "object"}},
+                {"name": "get_current_time"},
+                {"description": "missing name"}
+            ]
+        });
+        let tools = super::parse_tool_include(Some(&metadata));
+        assert_eq!(tools.len(), 2);
+        assert_eq!(tools[0].name, "web_search");
+        assert_eq!(tools[0].description.as_deref(), Some("Search"));
+        assert_eq!(tools[0].input_schema, json!({"type": "object"}));
+        assert_eq!(tools[1].input_schema["type"], "object");
+        assert!(super::parse_tool_include(None).is_empty());
+    }
+
+    #[test]
+    fn test_parse_default_params() {
+        let metadata = json!({
+            "defaultParams": {"temperature": 0.3, "maxTokens": 4096, "topP": null}
+        });
+        assert_eq!(
+            super::parse_default_params(Some(&metadata)),
+            super::GroupDefaultParams {
+                temperature: Some(0.3),
+                max_tokens: Some(4096),
+                top_p: None,
+            }
+        );
+
+        let out_of_range = json!({
+            "defaultParams": {"temperature": 3.5, "maxTokens": -1, "topP": 0.0}
+        });
+        assert_eq!(
+            super::parse_default_params(Some(&out_of_range)),
+            super::GroupDefaultParams::default()
+        );
+        assert_eq!(
+            super::parse_default_params(None),
+            super::GroupDefaultParams::default()
+        );
+    }
 
     #[test]
     fn test_wildmatch_logic() {
@@ -0,0 +1,235 @@
+//! Concurrent request limit per proxy group.
+//!
+//! A group with `maxConcurrency` in its metadata gets a semaphore with that many slots.
+//! Requests beyond the limit wait in line for a free slot instead of failing, for at most
+//! `concurrencyQueueTimeoutSecs` (60 by default). A slot is held until the response body
+//! has been fully sent or the client went away, so streams count for their whole length.
+
+use axum::{body::Body, response::Response};
+use futures_util::StreamExt;
+use lazy_static::lazy_static;
+use rust_i18n::t;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::ccproxy::errors::{CCProxyError, ProxyResult};
+
+/// How long a request waits for a free slot when the group sets no timeout.
+const DEFAULT_QUEUE_TIMEOUT: Duration = Duration::from_secs(60);
+
+lazy_static! {
+    pub static ref UPSTREAM_LIMITER: UpstreamLimiter = UpstreamLimiter::default();
+}
+
+/// Concurrency settings of a proxy group.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConcurrencyLimit {
+    pub group: String,
+    pub max_concurrent: usize,
+    /// How long a request waits for a free slot before it is rejected
+    pub queue_timeout: Duration,
+}
+
+impl ConcurrencyLimit {
+    /// Reads the group's `maxConcurrency` and `concurrencyQueueTimeoutSecs`,
+    /// `None` when the group has no positive limit.
+    pub fn from_group_metadata(group: &str, metadata: Option<&Value>) -> Option<Self> {
+        let metadata = metadata?;
+        let max_concurrent = metadata
+            .get("maxConcurrency")
+            .and_then(|v| v.as_u64())
+            .filter(|max| *max > 0)
+            .and_then(|max| usize::try_from(max).ok())?;
+        let queue_timeout = metadata
+            .get("concurrencyQueueTimeoutSecs")
+            .and_then(|v| v.as_f64())
+            .filter(|secs| *secs > 0.0)
+            .map(Duration::from_secs_f64)
+            .unwrap_or(DEFAULT_QUEUE_TIMEOUT);
+        Some(Self {
+            group: group.to_string(),
+            max_concurrent,
+            queue_timeout,
+        })
+    }
+}
+
+/// The semaphores of every limited group, keyed by group name.
+#[derive(Default)]
+pub struct UpstreamLimiter {
+    semaphores: Mutex<HashMap<String, (usize, Arc<Semaphore>)>>,
+}
+
+impl UpstreamLimiter {
+    /// Returns the semaphore of the group, replacing it when the limit was changed.
+    ///
+    /// Requests running under the old limit keep their slots on the old semaphore.
+    fn semaphore(&self, limit: &ConcurrencyLimit) -> Arc<Semaphore> {
+        let mut semaphores = self
+            .semaphores
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let entry = semaphores.entry(limit.group.clone()).or_insert_with(|| {
+            (
+                limit.max_concurrent,
+                Arc::new(Semaphore::new(limit.max_concurrent)),
+            )
+        });
+        if entry.0 != limit.max_concurrent {
+            *entry = (
+                limit.max_concurrent,
+                Arc::new(Semaphore::new(limit.max_concurrent)),
+            );
+        }
+        entry.1.clone()
+    }
+
+    /// Waits for a free slot of the group.
+    ///
+    /// # Errors
+    /// `CCProxyError::ConcurrencyLimitTimeout` when no slot frees up within the queue timeout.
+    pub async fn acquire(&self, limit: &ConcurrencyLimit) -> ProxyResult<OwnedSemaphorePermit> {
+        let semaphore = self.semaphore(limit);
+        match tokio::time::timeout(limit.queue_timeout, semaphore.acquire_owned()).await {
+            Ok(Ok(permit)) => Ok(permit),
+            Ok(Err(e)) => Err(CCProxyError::InternalError(e.to_string())),
+            Err(_) => {
+                log::warn!(
+                    "ccproxy: group '{}' has {} requests in flight, gave up waiting after {:?}",
+                    limit.group,
+                    limit.max_concurrent,
+                    limit.queue_timeout
+                );
+                Err(CCProxyError::ConcurrencyLimitTimeout(
+                    t!(
+                        "proxy.error.concurrency_limit_timeout",
+                        group = limit.group,
+                        limit = limit.max_concurrent.to_string(),
+                        secs = limit.queue_timeout.as_secs_f64().to_string()
+                    )
+                    .to_string(),
+                ))
+            }
+        }
+    }
+}
+
+/// Waits for a free slot of a limited group, so that nothing is sent upstream before the
+/// request gets its turn. Returns `None` when the group is not limited.
+pub async fn acquire_slot(
+    limit: Option<&ConcurrencyLimit>,
+) -> ProxyResult<Option<OwnedSemaphorePermit>> {
+    match limit {
+        Some(limit) => UPSTREAM_LIMITER.acquire(limit).await.map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Keeps the slot until the response body has been fully sent or dropped.
+pub fn hold_slot_until_body_end(
+    response: Response,
+    slot: Option<OwnedSemaphorePermit>,
+) -> Response {
+    let Some(slot) = slot else {
+        return response;
+    };
+    let (parts, body) = response.into_parts();
+    let stream = body.into_data_stream().map(move |chunk| {
+        let _slot = &slot;
+        chunk
+    });
+    Response::from_parts(parts, Body::from_stream(stream))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn limit(group: &str, max_concurrent: usize, queue_timeout_ms: u64) -> ConcurrencyLimit {
+        ConcurrencyLimit {
+            group: group.to_string(),
+            max_concurrent,
+            queue_timeout: Duration::from_millis(queue_timeout_ms),
+        }
+    }
+
+    #[test]
+    fn test_limit_from_group_metadata() {
+        let metadata = json!({"maxConcurrency": 2, "concurrencyQueueTimeoutSecs": 5});
+        let parsed = ConcurrencyLimit::from_group_metadata("default", Some(&metadata)).unwrap();
+        assert_eq!(parsed.max_concurrent, 2);
+        assert_eq!(parsed.queue_timeout, Duration::from_secs(5));
+
+        let metadata = json!({"maxConcurrency": 1});
+        let parsed = ConcurrencyLimit::from_group_metadata("default", Some(&metadata)).unwrap();
+        assert_eq!(parsed.queue_timeout, DEFAULT_QUEUE_TIMEOUT);
+
+        for metadata in [json!({}), json!({"maxConcurrency": 0})] {
+            assert!(ConcurrencyLimit::from_group_metadata("default", Some(&metadata)).is_none());
+        }
+        assert!(ConcurrencyLimit::from_group_metadata("default", None).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_request_over_limit_waits_for_free_slot() {
+        let limiter = UpstreamLimiter::default();
+        let limit = limit("waits", 2, 2_000);
+        let first = limiter.acquire(&limit).await.unwrap();
+        let _second = limiter.acquire(&limit).await.unwrap();
+
+        let queued = limiter.acquire(&limit);
+        tokio::pin!(queued);
+        assert!(
+            tokio::time::timeout(Duration::from_millis(50), &mut queued)
+                .await
+                .is_err(),
+            "the third request must wait while both slots are taken"
+        );
+
+        drop(first);
+        let third = tokio::time::timeout(Duration::from_millis(500), queued)
+            .await
+            .expect("the queued request should get the freed slot");
+        assert!(third.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_request_over_limit_times_out() {
+        let limiter = UpstreamLimiter::default();
+        let limit = limit("times-out", 1, 50);
+        let _held = limiter.acquire(&limit).await.unwrap();
+
+        let result = limiter.acquire(&limit).await;
+        assert!(matches!(
+            result,
+            Err(CCProxyError::ConcurrencyLimitTimeout(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_groups_are_limited_separately() {
+        let limiter = UpstreamLimiter::default();
+        let _a = limiter.acquire(&limit("a", 1, 50)).await.unwrap();
+        assert!(limiter.acquire(&limit("b", 1, 50)).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_slot_is_released_with_the_body() {
+        let limiter = UpstreamLimiter::default();
+        let limit = limit("body", 1, 50);
+        let slot = limiter.acquire(&limit).await.unwrap();
+
+        let response = hold_slot_until_body_end(Response::new(Body::from("done")), Some(slot));
+        assert!(limiter.acquire(&limit).await.is_err());
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], b"done");
+        assert!(limiter.acquire(&limit).await.is_ok());
+    }
+}
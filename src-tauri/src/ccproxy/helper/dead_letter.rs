@@ -0,0 +1,309 @@
+//! Dead-letter log of failed proxy requests.
+//!
+//! A request whose final answer is an error, after all retries, is stored in
+//! `ccproxy_dead_letters` together with the client request, the upstream status and the time
+//! it took, so recurring failures can be analyzed later. Credentials in the request body are
+//! redacted before storing, and only the newest `DEAD_LETTER_MAX_ENTRIES` entries are kept.
+
+use axum::{
+    body::{to_bytes, Body},
+    response::Response,
+};
+use bytes::Bytes;
+use serde_json::Value;
+use std::sync::{Arc, RwLock};
+use std::time::Instant;
+
+use super::secrets::scrub_body;
+use crate::ccproxy::{errors::ProxyResult, types::ProxyModel};
+use crate::db::{CcproxyDeadLetter, MainStore};
+
+/// Protocol recorded for requests of the OpenAI Responses endpoint.
+pub const RESPONSES_PROTOCOL: &str = "openai_responses";
+/// Protocol recorded for requests of the legacy OpenAI completions endpoint.
+pub const COMPLETIONS_PROTOCOL: &str = "openai_completions";
+/// Number of failed requests kept in the log, older entries are rotated out.
+pub const DEAD_LETTER_MAX_ENTRIES: usize = 200;
+/// Client request bodies above this size are stored truncated.
+const MAX_REQUEST_BODY_BYTES: usize = 1024 * 1024;
+/// Error responses above this size are not read into the log.
+const MAX_ERROR_BODY_BYTES: usize = 256 * 1024;
+
+/// Cuts `text` to at most `max_bytes` on a character boundary, returns whether it was cut.
+fn truncate_to(text: &mut String, max_bytes: usize) -> bool {
+    if text.len() <= max_bytes {
+        return false;
+    }
+    let mut end = max_bytes;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    text.truncate(end);
+    true
+}
+
+/// Extracts the error message from an error response body.
+fn error_message_from_body(body: &[u8]) -> String {
+    serde_json::from_slice::<Value>(body)
+        .ok()
+        .and_then(|value| {
+            ["/error/message", "/message", "/error"]
+                .iter()
+                .find_map(|pointer| value.pointer(pointer).and_then(Value::as_str))
+                .map(str::to_string)
+        })
+        .unwrap_or_else(|| String::from_utf8_lossy(body).into_owned())
+}
+
+/// What is known about a request when it is forwarded, recorded if it fails.
+pub struct DeadLetterContext {
+    request_id: String,
+    client_model: String,
+    backend_model: String,
+    provider_id: i64,
+    provider: String,
+    protocol: String,
+    backend_protocol: String,
+    request_body: Bytes,
+    started_at: Instant,
+}
+
+impl DeadLetterContext {
+    pub fn new(
+        request_id: &str,
+        protocol: String,
+        proxy_model: &ProxyModel,
+        request_body: &Bytes,
+    ) -> Self {
+        Self {
+            request_id: request_id.to_string(),
+            client_model: proxy_model.client_alias.clone(),
+            backend_model: proxy_model.model.clone(),
+            provider_id: proxy_model.provider_id,
+            provider: proxy_model.provider.clone(),
+            protocol,
+            backend_protocol: proxy_model.chat_protocol.to_string(),
+            request_body: request_body.clone(),
+            started_at: Instant::now(),
+        }
+    }
+
+    fn record(self, main_store: &Arc<RwLock<MainStore>>, status_code: u16, error_message: String) {
+        let mut request_body = scrub_body(&self.request_body);
+        let request_truncated = truncate_to(&mut request_body, MAX_REQUEST_BODY_BYTES);
+        let letter = CcproxyDeadLetter {
+            id: None,
+            request_id: self.request_id,
+            client_model: self.client_model,
+            backend_model: self.backend_model,
+            provider_id: Some(self.provider_id),
+            provider: self.provider,
+            protocol: self.protocol,
+            backend_protocol: self.backend_protocol,
+            status_code: status_code as i32,
+            error_message,
+            request_body,
+            request_truncated,
+            duration_ms: self.started_at.elapsed().as_millis() as i64,
+            created_at: None,
+        };
+
+        match main_store.read() {
+            Ok(store) => {
+                if let Err(e) = store.record_ccproxy_dead_letter(&letter, DEAD_LETTER_MAX_ENTRIES) {
+                    log::error!(
+                        "Failed to store dead letter of request {}: {}",
+                        &letter.request_id,
+                        e
+                    );
+                }
+            }
+            Err(e) => log::error!(
+                "Failed to store dead letter of request {}: {}",
+                &letter.request_id,
+                e
+            ),
+        }
+    }
+
+    /// Records the request if `result` is a failure, then hands `result` back unchanged.
+    pub async fn capture(
+        self,
+        main_store: &Arc<RwLock<MainStore>>,
+        result: ProxyResult<Response>,
+    ) -> ProxyResult<Response> {
+        let response = match result {
+            Ok(response) => response,
+            Err(error) => {
+                self.record(main_store, error.status_code().as_u16(), error.to_string());
+                return Err(error);
+            }
+        };
+        let status = response.status();
+        if !status.is_client_error() && !status.is_server_error() {
+            return Ok(response);
+        }
+
+        let (parts, body) = response.into_parts();
+        match to_bytes(body, MAX_ERROR_BODY_BYTES).await {
+            Ok(bytes) => {
+                self.record(main_store, status.as_u16(), error_message_from_body(&bytes));
+                Ok(Response::from_parts(parts, Body::from(bytes)))
+            }
+            Err(e) => {
+                let message = e.to_string();
+                self.record(main_store, status.as_u16(), message.clone());
+                Ok(Response::from_parts(parts, Body::from(message)))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ccproxy::{errors::CCProxyError, helper::secrets::REDACTED};
+    use axum::response::IntoResponse;
+    use http::StatusCode;
+    use serde_json::json;
+
+    fn context(request_body: Value) -> DeadLetterContext {
+        DeadLetterContext {
+            request_id: "msg_1".to_string(),
+            client_model: "code-large".to_string(),
+            backend_model: "glm-4.6".to_string(),
+            provider_id: 7,
+            provider: "Zhipu".to_string(),
+            protocol: "claude".to_string(),
+            backend_protocol: "openai".to_string(),
+            request_body: Bytes::from(request_body.to_string()),
+            started_at: Instant::now(),
+        }
+    }
+
+    fn store() -> Arc<RwLock<MainStore>> {
+        Arc::new(RwLock::new(
+            MainStore::new(":memory:").expect("in-memory store"),
+        ))
+    }
+
+    fn letters(main_store: &Arc<RwLock<MainStore>>) -> Vec<CcproxyDeadLetter> {
+        main_store
+            .read()
+            .expect("store lock")
+            .get_ccproxy_dead_letters(100, 0)
+            .expect("dead letters")
+    }
+
+    #[tokio::test]
+    async fn test_failed_request_lands_in_dead_letter_log() {
+        let main_store = store();
+        let body = json!({
+            "model": "code-large",
+            "api_key": "plain-secret",
+            "messages": [{"role": "user", "content": "my key is sk-abcdefghijklmnopqrstuvwx"}],
+            "max_tokens": 1024
+        });
+        let backend_error = (
+            StatusCode::METHOD_NOT_ALLOWED,
+            axum::Json(json!({"error": {"message": "tool_choice is not supported"}})),
+        )
+            .into_response();
+
+        let response = context(body)
+            .capture(&main_store, Ok(backend_error))
+            .await
+            .expect("response passes through");
+        assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+        let passed_body = to_bytes(response.into_body(), usize::MAX)
+            .await
+            .expect("body");
+        assert!(String::from_utf8_lossy(&passed_body).contains("tool_choice"));
+
+        let letters = letters(&main_store);
+        assert_eq!(letters.len(), 1);
+        let letter = &letters[0];
+        assert_eq!(letter.status_code, 405);
+        assert_eq!(letter.error_message, "tool_choice is not supported");
+        assert_eq!(letter.backend_model, "glm-4.6");
+        assert_eq!(letter.provider_id, Some(7));
+        assert!(!letter.request_truncated);
+        assert!(letter.created_at.is_some());
+
+        let stored: Value = serde_json::from_str(&letter.request_body).expect("stored JSON");
+        assert_eq!(stored["api_key"], REDACTED);
+        assert_eq!(stored["max_tokens"], 1024);
+        assert_eq!(
+            stored["messages"][0]["content"],
+            format!("my key is {}", REDACTED)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_proxy_errors_are_recorded_and_success_is_not() {
+        let main_store = store();
+
+        let result = context(json!({"model": "code-large"}))
+            .capture(
+                &main_store,
+                Err(CCProxyError::BackendRequestError(
+                    "connection reset".to_string(),
+                )),
+            )
+            .await;
+        assert!(matches!(result, Err(CCProxyError::BackendRequestError(_))));
+
+        let ok = context(json!({"model": "code-large"}))
+            .capture(&main_store, Ok(StatusCode::OK.into_response()))
+            .await;
+        assert!(ok.is_ok());
+
+        let letters = letters(&main_store);
+        assert_eq!(letters.len(), 1);
+        assert_eq!(letters[0].status_code, 502);
+        assert_eq!(letters[0].error_message, "connection reset");
+    }
+
+    #[test]
+    fn test_log_rotates_oldest_entries() {
+        let main_store = store();
+        let store = main_store.read().expect("store lock");
+        let letter = |n: usize| CcproxyDeadLetter {
+            id: None,
+            request_id: format!("msg_{}", n),
+            client_model: "alias".to_string(),
+            backend_model: "model".to_string(),
+            provider_id: None,
+            provider: "provider".to_string(),
+            protocol: "openai".to_string(),
+            backend_protocol: "openai".to_string(),
+            status_code: 500,
+            error_message: "boom".to_string(),
+            request_body: "{}".to_string(),
+            request_truncated: false,
+            duration_ms: 1,
+            created_at: None,
+        };
+        for n in 0..5 {
+            store
+                .record_ccproxy_dead_letter(&letter(n), 3)
+                .expect("record");
+        }
+
+        let ids: Vec<String> = store
+            .get_ccproxy_dead_letters(10, 0)
+            .expect("dead letters")
+            .into_iter()
+            .map(|letter| letter.request_id)
+            .collect();
+        assert_eq!(ids, vec!["msg_4", "msg_3", "msg_2"]);
+    }
+
+    #[test]
+    fn test_truncate_keeps_char_boundary() {
+        let mut text = "日本語".to_string();
+        assert!(truncate_to(&mut text, 4));
+        assert_eq!(text, "日");
+        assert!(!truncate_to(&mut text, 10));
+    }
+}
@@ -0,0 +1,210 @@
+//! Model fallback per proxy group.
+//!
+//! A group with `fallbackModels` in its metadata retries a request whose model failed upstream,
+//! with a 5xx answer such as an overloaded backend or no answer at all, on each listed alias of
+//! the group in turn. The aliases may be served by other providers. Only non-streaming requests
+//! fall back unless the group sets `fallbackOnStreaming`, as they are replayed whole with nothing
+//! sent to the client yet. A stream that missed its first-token timeout has not sent anything
+//! either and always falls back.
+
+use axum::{http::HeaderValue, response::Response};
+use serde_json::Value;
+use std::future::Future;
+use std::sync::{Arc, RwLock};
+
+use super::ModelResolver;
+use crate::ccproxy::errors::{CCProxyError, ProxyResult};
+use crate::ccproxy::types::ProxyModel;
+use crate::db::MainStore;
+
+/// Response header naming the fallback alias that answered the request.
+pub const FALLBACK_MODEL_HEADER: &str = "x-cs-fallback-model";
+
+/// Fallback settings of a proxy group.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelFallback {
+    pub group: String,
+    /// Aliases of the group tried in order after the requested model failed
+    pub aliases: Vec<String>,
+    /// Whether streaming requests fall back as well
+    pub streaming: bool,
+}
+
+impl ModelFallback {
+    /// Reads the group's `fallbackModels` and `fallbackOnStreaming`,
+    /// `None` when the group lists no fallback alias.
+    pub fn from_group_metadata(group: &str, metadata: Option<&Value>) -> Option<Self> {
+        let metadata = metadata?;
+        let aliases: Vec<String> = metadata
+            .get("fallbackModels")
+            .and_then(|v| v.as_array())?
+            .iter()
+            .filter_map(|alias| alias.as_str())
+            .map(str::trim)
+            .filter(|alias| !alias.is_empty())
+            .map(str::to_string)
+            .collect();
+        if aliases.is_empty() {
+            return None;
+        }
+        let streaming = metadata
+            .get("fallbackOnStreaming")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        Some(Self {
+            group: group.to_string(),
+            aliases,
+            streaming,
+        })
+    }
+
+    pub fn applies_to(&self, is_streaming: bool) -> bool {
+        !is_streaming || self.streaming
+    }
+
+    /// Whether the failed attempt is retried on the next fallback model.
+    fn retries(&self, result: &ProxyResult<Response>, is_streaming: bool) -> bool {
+        should_fall_back(result)
+            && (self.applies_to(is_streaming)
+                || matches!(result, Err(CCProxyError::FirstTokenTimeout(_))))
+    }
+}
+
+/// Whether an attempt failed in a way another model may not, an upstream 5xx or no answer.
+pub fn should_fall_back(result: &ProxyResult<Response>) -> bool {
+    match result {
+        Ok(response) => response.status().is_server_error(),
+        Err(CCProxyError::BackendRequestError(_)) | Err(CCProxyError::FirstTokenTimeout(_)) => true,
+        Err(_) => false,
+    }
+}
+
+/// Sends the request to `proxy_model` through `attempt`, then to each fallback alias of its
+/// group for as long as the previous model failed.
+///
+/// Fallback models are resolved like the alias of a client request, so they rotate keys and
+/// providers as usual. The response of the last model tried is returned, and a response of a
+/// fallback model is marked with `x-cs-fallback-model`.
+pub async fn run_with_fallback<F, Fut>(
+    main_store_arc: &Arc<RwLock<MainStore>>,
+    proxy_model: ProxyModel,
+    is_streaming: bool,
+    mut attempt: F,
+) -> ProxyResult<Response>
+where
+    F: FnMut(ProxyModel) -> Fut,
+    Fut: Future<Output = ProxyResult<Response>>,
+{
+    let fallback = proxy_model.fallback.clone();
+    let mut failed_model = proxy_model.client_alias.clone();
+    let mut result = attempt(proxy_model).await;
+    let Some(fallback) = fallback else {
+        return result;
+    };
+
+    for alias in &fallback.aliases {
+        if !fallback.retries(&result, is_streaming) {
+            break;
+        }
+        let fallback_model = match ModelResolver::get_ai_model_by_alias(
+            main_store_arc.clone(),
+            alias.clone(),
+            Some(fallback.group.as_str()),
+        )
+        .await
+        {
+            Ok(fallback_model) => fallback_model,
+            Err(e) => {
+                log::warn!(
+                    "ccproxy: fallback model '{}' of group '{}' is unavailable: {}",
+                    alias,
+                    fallback.group,
+                    e
+                );
+                continue;
+            }
+        };
+        log::warn!(
+            "ccproxy: '{}' failed, falling back to '{}' ({} / {}) in group '{}'",
+            failed_model,
+            alias,
+            fallback_model.provider,
+            fallback_model.model,
+            fallback.group
+        );
+        failed_model = alias.clone();
+        result = attempt(fallback_model).await.map(|mut response| {
+            if let Ok(value) = HeaderValue::from_str(alias) {
+                response.headers_mut().insert(FALLBACK_MODEL_HEADER, value);
+            }
+            response
+        });
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::StatusCode;
+    use axum::response::IntoResponse;
+    use serde_json::json;
+
+    #[test]
+    fn test_fallback_is_read_from_group_metadata() {
+        let metadata = json!({"fallbackModels": ["backup", " ", "last-resort"]});
+        let fallback = ModelFallback::from_group_metadata("coding", Some(&metadata))
+            .expect("fallback settings");
+        assert_eq!(fallback.group, "coding");
+        assert_eq!(fallback.aliases, vec!["backup", "last-resort"]);
+        assert!(fallback.applies_to(false));
+        assert!(!fallback.applies_to(true));
+
+        let metadata = json!({"fallbackModels": ["backup"], "fallbackOnStreaming": true});
+        let fallback = ModelFallback::from_group_metadata("coding", Some(&metadata))
+            .expect("fallback settings");
+        assert!(fallback.applies_to(true));
+
+        assert!(ModelFallback::from_group_metadata("coding", Some(&json!({}))).is_none());
+        assert!(
+            ModelFallback::from_group_metadata("coding", Some(&json!({"fallbackModels": []})))
+                .is_none()
+        );
+        assert!(ModelFallback::from_group_metadata("coding", None).is_none());
+    }
+
+    #[test]
+    fn test_only_upstream_failures_fall_back() {
+        let response = |status: StatusCode| Ok(status.into_response());
+        assert!(should_fall_back(&response(
+            StatusCode::INTERNAL_SERVER_ERROR
+        )));
+        assert!(should_fall_back(&response(StatusCode::SERVICE_UNAVAILABLE)));
+        assert!(should_fall_back(&response(
+            StatusCode::from_u16(529).expect("status")
+        )));
+        assert!(!should_fall_back(&response(StatusCode::OK)));
+        assert!(!should_fall_back(&response(StatusCode::BAD_REQUEST)));
+        assert!(!should_fall_back(&response(StatusCode::TOO_MANY_REQUESTS)));
+        assert!(should_fall_back(&Err(CCProxyError::BackendRequestError(
+            "connection refused".to_string()
+        ))));
+        assert!(!should_fall_back(&Err(CCProxyError::BudgetExceeded(
+            "over budget".to_string()
+        ))));
+    }
+
+    #[test]
+    fn test_streams_fall_back_only_before_first_token() {
+        let fallback = ModelFallback::from_group_metadata(
+            "coding",
+            Some(&json!({"fallbackModels": ["backup"]})),
+        )
+        .expect("fallback settings");
+        let timed_out = Err(CCProxyError::FirstTokenTimeout("no output".to_string()));
+        let unavailable = Ok(StatusCode::SERVICE_UNAVAILABLE.into_response());
+        assert!(fallback.retries(&timed_out, true));
+        assert!(!fallback.retries(&unavailable, true));
+        assert!(fallback.retries(&unavailable, false));
+    }
+}
@@ -0,0 +1,144 @@
+//! First-token timeout of streamed responses.
+//!
+//! An overloaded provider may accept a streaming request and then hold the connection without
+//! producing anything. With `chat_completion_proxy_first_token_timeout` set, the proxy waits that
+//! long for the first chunk with output, then drops the upstream request and fails with
+//! `FirstTokenTimeout`, which moves on to the fallback models of the group as nothing was sent to
+//! the client yet. Keep-alive comments and ping events are not output. Once the first token
+//! arrived, the stall timeout applies between chunks instead.
+
+use futures_util::{stream, StreamExt};
+use rust_i18n::t;
+use serde_json::Value;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use crate::ccproxy::errors::{CCProxyError, ProxyResult};
+use crate::constants::{CFG_CCPROXY_FIRST_TOKEN_TIMEOUT, CFG_CCPROXY_FIRST_TOKEN_TIMEOUT_DEFAULT};
+use crate::db::MainStore;
+
+/// The configured first-token timeout, `None` when it is disabled.
+pub fn first_token_timeout(main_store: &Arc<RwLock<MainStore>>) -> Option<Duration> {
+    let secs = main_store
+        .read()
+        .map(|store| {
+            store.get_config(
+                CFG_CCPROXY_FIRST_TOKEN_TIMEOUT,
+                CFG_CCPROXY_FIRST_TOKEN_TIMEOUT_DEFAULT,
+            )
+        })
+        .unwrap_or(CFG_CCPROXY_FIRST_TOKEN_TIMEOUT_DEFAULT);
+    (secs > 0).then(|| Duration::from_secs(secs))
+}
+
+/// Whether a chunk holds nothing but blank lines, SSE comments and ping events.
+fn is_keep_alive(chunk: &[u8]) -> bool {
+    String::from_utf8_lossy(chunk)
+        .lines()
+        .map(str::trim)
+        .all(|line| {
+            if line.is_empty() || line.starts_with(':') || line == "event: ping" {
+                return true;
+            }
+            line.strip_prefix("data:")
+                .and_then(|data| serde_json::from_str::<Value>(data.trim()).ok())
+                .is_some_and(|data| data.get("type").and_then(Value::as_str) == Some("ping"))
+        })
+}
+
+/// Waits up to `timeout` for the first chunk of a streamed response that is not a keep-alive.
+///
+/// Returns the response with the chunks read so far put back in front of its body, or
+/// `FirstTokenTimeout` after dropping the response, which aborts the upstream request. A read
+/// error counts as the first chunk so that it reaches the stream handling as usual.
+pub async fn await_first_token(
+    response: reqwest::Response,
+    timeout: Duration,
+) -> ProxyResult<reqwest::Response> {
+    let status = response.status();
+    let mut headers = response.headers().clone();
+    let mut upstream = response.bytes_stream();
+    let mut received = Vec::new();
+
+    let waited = tokio::time::timeout(timeout, async {
+        while let Some(chunk) = upstream.next().await {
+            let is_output = !matches!(&chunk, Ok(bytes) if is_keep_alive(bytes));
+            received.push(chunk);
+            if is_output {
+                break;
+            }
+        }
+    })
+    .await;
+    if waited.is_err() {
+        return Err(CCProxyError::FirstTokenTimeout(
+            t!(
+                "proxy.error.first_token_timeout",
+                seconds = timeout.as_secs()
+            )
+            .to_string(),
+        ));
+    }
+
+    headers.remove(reqwest::header::CONTENT_LENGTH);
+    let body = reqwest::Body::wrap_stream(stream::iter(received).chain(upstream));
+    let mut rebuilt = http::Response::new(body);
+    *rebuilt.status_mut() = status;
+    *rebuilt.headers_mut() = headers;
+    Ok(reqwest::Response::from(rebuilt))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+
+    fn response(chunks: Vec<&'static str>) -> reqwest::Response {
+        let chunks = chunks
+            .into_iter()
+            .map(|chunk| Ok::<_, std::io::Error>(Bytes::from_static(chunk.as_bytes())));
+        let body = stream::iter(chunks).chain(stream::pending());
+        reqwest::Response::from(http::Response::new(reqwest::Body::wrap_stream(body)))
+    }
+
+    #[test]
+    fn test_keep_alive_chunks() {
+        assert!(is_keep_alive(b": keep-alive\n\n"));
+        assert!(is_keep_alive(
+            b"event: ping\ndata: {\"type\": \"ping\"}\n\n"
+        ));
+        assert!(!is_keep_alive(
+            b"data: {\"choices\":[{\"delta\":{\"content\":\"ping\"}}]}\n\n"
+        ));
+        assert!(!is_keep_alive(
+            b"event: message_start\ndata: {\"type\":\"message_start\"}\n\n"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_keep_alive_does_not_count_as_first_token() {
+        let result = await_first_token(
+            response(vec![": keep-alive\n\n", "event: ping\n\n"]),
+            Duration::from_millis(50),
+        )
+        .await;
+        assert!(matches!(result, Err(CCProxyError::FirstTokenTimeout(_))));
+    }
+
+    #[tokio::test]
+    async fn test_chunks_read_while_waiting_are_kept() {
+        let response = await_first_token(
+            response(vec![": keep-alive\n\n", "data: {\"id\":\"1\"}\n\n"]),
+            Duration::from_secs(5),
+        )
+        .await
+        .expect("first token");
+        let mut body = response.bytes_stream();
+        let mut text = String::new();
+        for _ in 0..2 {
+            let chunk = body.next().await.expect("chunk").expect("bytes");
+            text.push_str(&String::from_utf8_lossy(&chunk));
+        }
+        assert_eq!(text, ": keep-alive\n\ndata: {\"id\":\"1\"}\n\n");
+    }
+}
@@ -1,6 +1,17 @@
+pub mod audit_log;
+pub mod budget;
+pub mod code_reflow;
 mod common;
+pub mod concurrency;
+pub mod dead_letter;
+pub mod fallback;
+pub mod first_token;
+pub mod moderation;
+pub mod pdf_text;
 mod proxy_rotator;
+pub mod redaction;
 pub mod retry;
+pub mod secrets;
 pub mod sse;
 pub mod stat_guard;
 pub mod stream_handler;
@@ -8,10 +19,10 @@ mod stream_processor;
 pub mod tool_use_xml;
 
 pub use common::*;
-pub use proxy_rotator::CC_PROXY_ROTATOR;
+pub use proxy_rotator::{KeyHealth, CC_PROXY_ROTATOR};
 pub use retry::{send_with_retry, RetryConfig};
 pub use sse::Event;
-pub use stream_processor::StreamProcessor;
+pub use stream_processor::{stream_with_stall_timeout, StreamProcessor};
 
 #[cfg(test)]
 mod proxy_rotator_test;
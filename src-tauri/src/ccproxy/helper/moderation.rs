@@ -0,0 +1,500 @@
+//! Optional content moderation of proxied chats.
+//!
+//! When enabled under `chat_completion_proxy_moderation`, the latest user prompt and/or the
+//! response are sent to an OpenAI compatible `/moderations` endpoint before they pass the
+//! proxy. Depending on the `action`, flagged content is either rejected with a policy error
+//! or let through with the `x-cs-moderation-flagged` response header.
+//!
+//! Only complete responses can be checked, streamed responses are passed through. If the
+//! moderation endpoint itself fails, the request is let through and the failure is logged.
+
+use async_trait::async_trait;
+use axum::{
+    body::{to_bytes, Body},
+    http::{header, HeaderValue},
+    response::Response,
+};
+use rust_i18n::t;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use crate::ccproxy::errors::{CCProxyError, ProxyResult};
+use crate::ccproxy::ChatProtocol;
+use crate::constants::CFG_CCPROXY_MODERATION;
+use crate::db::MainStore;
+
+/// Response header listing the flagged categories when the action is `flag`.
+pub const MODERATION_FLAGGED_HEADER: &str = "x-cs-moderation-flagged";
+/// Largest complete response body read for moderation.
+const MAX_RESPONSE_BYTES: usize = 16 * 1024 * 1024;
+const MODERATION_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// What happens to flagged content.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ModerationAction {
+    /// Reject the request with a policy error
+    #[default]
+    Block,
+    /// Let it through and mark the response with `x-cs-moderation-flagged`
+    Flag,
+}
+
+/// Moderation settings of the proxy, stored under `chat_completion_proxy_moderation`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ModerationConfig {
+    pub enabled: bool,
+    /// Full URL of an OpenAI compatible moderation endpoint
+    pub endpoint: String,
+    pub api_key: String,
+    pub model: String,
+    pub check_prompts: bool,
+    pub check_responses: bool,
+    pub action: ModerationAction,
+    /// Categories that count as a violation, empty means every flagged category
+    pub categories: Vec<String>,
+}
+
+impl Default for ModerationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            endpoint: "https://api.openai.com/v1/moderations".to_string(),
+            api_key: String::new(),
+            model: "omni-moderation-latest".to_string(),
+            check_prompts: true,
+            check_responses: false,
+            action: ModerationAction::Block,
+            categories: Vec::new(),
+        }
+    }
+}
+
+/// The result of moderating a piece of text.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ModerationVerdict {
+    pub flagged: bool,
+    pub categories: Vec<String>,
+}
+
+/// A moderation backend.
+#[async_trait]
+pub trait Moderator: Send + Sync {
+    async fn moderate(&self, text: &str) -> Result<ModerationVerdict, String>;
+}
+
+/// Calls an OpenAI compatible `/moderations` endpoint.
+pub struct OpenAIModerator {
+    client: reqwest::Client,
+    endpoint: String,
+    api_key: String,
+    model: String,
+}
+
+impl OpenAIModerator {
+    pub fn new(config: &ModerationConfig) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(MODERATION_TIMEOUT)
+            .build()
+            .unwrap_or_default();
+        Self {
+            client,
+            endpoint: config.endpoint.clone(),
+            api_key: config.api_key.clone(),
+            model: config.model.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl Moderator for OpenAIModerator {
+    async fn moderate(&self, text: &str) -> Result<ModerationVerdict, String> {
+        let mut request = self
+            .client
+            .post(&self.endpoint)
+            .json(&json!({ "model": self.model, "input": text }));
+        if !self.api_key.is_empty() {
+            request = request.bearer_auth(&self.api_key);
+        }
+        let response = request.send().await.map_err(|e| e.to_string())?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(format!("status {}: {}", status, body));
+        }
+        let body: Value = response.json().await.map_err(|e| e.to_string())?;
+        Ok(parse_moderation_response(&body))
+    }
+}
+
+/// Merges every entry of the `results` array of a moderation response.
+fn parse_moderation_response(body: &Value) -> ModerationVerdict {
+    let mut verdict = ModerationVerdict::default();
+    for result in body["results"].as_array().into_iter().flatten() {
+        verdict.flagged |= result["flagged"].as_bool().unwrap_or(false);
+        for (category, hit) in result["categories"].as_object().into_iter().flatten() {
+            if hit.as_bool() == Some(true) && !verdict.categories.contains(category) {
+                verdict.categories.push(category.clone());
+            }
+        }
+    }
+    verdict
+}
+
+/// Joins the text of a message `content`, either a string or a list of text parts.
+fn content_text(content: &Value) -> String {
+    match content {
+        Value::String(text) => text.clone(),
+        Value::Array(parts) => parts
+            .iter()
+            .filter_map(|part| part.as_str().or_else(|| part["text"].as_str()))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => String::new(),
+    }
+}
+
+/// Returns the text of the latest user turn of a client request.
+///
+/// Earlier turns were already checked when they were sent.
+fn prompt_text(protocol: &ChatProtocol, body: &Value) -> String {
+    match protocol {
+        ChatProtocol::Gemini => body["contents"]
+            .as_array()
+            .and_then(|contents| {
+                contents
+                    .iter()
+                    .rev()
+                    .find(|c| c["role"].as_str().map_or(true, |role| role == "user"))
+            })
+            .map(|c| content_text(&c["parts"]))
+            .unwrap_or_default(),
+        _ => body["messages"]
+            .as_array()
+            .and_then(|messages| messages.iter().rev().find(|m| m["role"] == "user"))
+            .map(|m| content_text(&m["content"]))
+            .unwrap_or_default(),
+    }
+}
+
+/// Returns the generated text of a complete response in the client protocol.
+fn response_text(protocol: &ChatProtocol, body: &Value) -> String {
+    let texts: Vec<String> = match protocol {
+        ChatProtocol::OpenAI | ChatProtocol::HuggingFace => body["choices"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|choice| content_text(&choice["message"]["content"]))
+            .collect(),
+        ChatProtocol::Claude => vec![content_text(&body["content"])],
+        ChatProtocol::Gemini => body["candidates"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|candidate| content_text(&candidate["content"]["parts"]))
+            .collect(),
+        ChatProtocol::Ollama => vec![content_text(&body["message"]["content"])],
+    };
+    texts
+        .into_iter()
+        .filter(|text| !text.trim().is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The moderation step of a proxied chat.
+pub struct Moderation {
+    config: ModerationConfig,
+    moderator: Arc<dyn Moderator>,
+}
+
+impl Moderation {
+    pub fn new(config: ModerationConfig, moderator: Arc<dyn Moderator>) -> Self {
+        Self { config, moderator }
+    }
+
+    /// Loads the configured moderation, `None` when it is disabled.
+    pub fn from_store(main_store: &Arc<RwLock<MainStore>>) -> Option<Self> {
+        let config: ModerationConfig = main_store
+            .read()
+            .ok()?
+            .get_config(CFG_CCPROXY_MODERATION, ModerationConfig::default());
+        if !config.enabled || (!config.check_prompts && !config.check_responses) {
+            return None;
+        }
+        let moderator = Arc::new(OpenAIModerator::new(&config));
+        Some(Self::new(config, moderator))
+    }
+
+    /// Moderates a text, returning the violated categories.
+    async fn violations(&self, text: &str) -> Option<Vec<String>> {
+        if text.trim().is_empty() {
+            return None;
+        }
+        let verdict = match self.moderator.moderate(text).await {
+            Ok(verdict) => verdict,
+            Err(e) => {
+                log::warn!(
+                    "Content moderation failed, letting the request through: {}",
+                    e
+                );
+                return None;
+            }
+        };
+        if !verdict.flagged {
+            return None;
+        }
+        if self.config.categories.is_empty() {
+            return Some(verdict.categories);
+        }
+        let hits: Vec<String> = verdict
+            .categories
+            .into_iter()
+            .filter(|c| self.config.categories.contains(c))
+            .collect();
+        (!hits.is_empty()).then_some(hits)
+    }
+
+    /// Checks the latest user prompt of a client request.
+    ///
+    /// # Returns
+    /// The flagged categories when the action is `flag`, `None` for clean prompts.
+    ///
+    /// # Errors
+    /// `CCProxyError::ContentModerated` when the prompt is flagged and the action is `block`.
+    pub async fn check_prompt(
+        &self,
+        protocol: &ChatProtocol,
+        body: &[u8],
+    ) -> ProxyResult<Option<Vec<String>>> {
+        if !self.config.check_prompts {
+            return Ok(None);
+        }
+        let Ok(body) = serde_json::from_slice::<Value>(body) else {
+            return Ok(None);
+        };
+        let Some(categories) = self.violations(&prompt_text(protocol, &body)).await else {
+            return Ok(None);
+        };
+        log::warn!("Proxy prompt flagged by moderation: {:?}", categories);
+        match self.config.action {
+            ModerationAction::Block => Err(CCProxyError::ContentModerated(
+                t!(
+                    "proxy.error.prompt_moderated",
+                    categories = categories.join(", ")
+                )
+                .to_string(),
+            )),
+            ModerationAction::Flag => Ok(Some(categories)),
+        }
+    }
+
+    /// Checks a complete response, streamed responses are returned untouched.
+    ///
+    /// # Errors
+    /// `CCProxyError::ContentModerated` when the response is flagged and the action is `block`.
+    pub async fn check_response(
+        &self,
+        protocol: &ChatProtocol,
+        response: Response,
+    ) -> ProxyResult<Response> {
+        let is_json = response
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.starts_with("application/json"));
+        if !self.config.check_responses || !response.status().is_success() || !is_json {
+            return Ok(response);
+        }
+
+        let (parts, body) = response.into_parts();
+        let bytes = to_bytes(body, MAX_RESPONSE_BYTES)
+            .await
+            .map_err(|e| CCProxyError::InternalError(e.to_string()))?;
+        let text = serde_json::from_slice::<Value>(&bytes)
+            .map(|body| response_text(protocol, &body))
+            .unwrap_or_default();
+        let mut response = Response::from_parts(parts, Body::from(bytes));
+
+        if let Some(categories) = self.violations(&text).await {
+            log::warn!("Proxy response flagged by moderation: {:?}", categories);
+            match self.config.action {
+                ModerationAction::Block => {
+                    return Err(CCProxyError::ContentModerated(
+                        t!(
+                            "proxy.error.response_moderated",
+                            categories = categories.join(", ")
+                        )
+                        .to_string(),
+                    ))
+                }
+                ModerationAction::Flag => response = mark_flagged(response, Some(categories)),
+            }
+        }
+        Ok(response)
+    }
+}
+
+/// Lists the flagged categories in the `x-cs-moderation-flagged` header.
+pub fn mark_flagged(mut response: Response, categories: Option<Vec<String>>) -> Response {
+    let Some(categories) = categories else {
+        return response;
+    };
+    let existing = response
+        .headers()
+        .get(MODERATION_FLAGGED_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| {
+            v.split(',')
+                .map(|c| c.trim().to_string())
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    let mut all = existing;
+    for category in categories {
+        if !all.contains(&category) {
+            all.push(category);
+        }
+    }
+    let value = if all.is_empty() {
+        "flagged".to_string()
+    } else {
+        all.join(", ")
+    };
+    if let Ok(value) = HeaderValue::from_str(&value) {
+        response
+            .headers_mut()
+            .insert(MODERATION_FLAGGED_HEADER, value);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::StatusCode;
+
+    /// Flags every text containing "forbidden".
+    struct KeywordModerator;
+
+    #[async_trait]
+    impl Moderator for KeywordModerator {
+        async fn moderate(&self, text: &str) -> Result<ModerationVerdict, String> {
+            let flagged = text.contains("forbidden");
+            Ok(ModerationVerdict {
+                flagged,
+                categories: if flagged {
+                    vec!["violence".to_string()]
+                } else {
+                    Vec::new()
+                },
+            })
+        }
+    }
+
+    fn moderation(action: ModerationAction) -> Moderation {
+        let config = ModerationConfig {
+            enabled: true,
+            check_responses: true,
+            action,
+            ..Default::default()
+        };
+        Moderation::new(config, Arc::new(KeywordModerator))
+    }
+
+    fn openai_request(prompt: &str) -> Vec<u8> {
+        serde_json::to_vec(&json!({
+            "model": "gpt-4o",
+            "messages": [
+                {"role": "system", "content": "You are helpful."},
+                {"role": "user", "content": [{"type": "text", "text": prompt}]}
+            ]
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_flagged_prompt_is_blocked() {
+        let result = moderation(ModerationAction::Block)
+            .check_prompt(&ChatProtocol::OpenAI, &openai_request("a forbidden plan"))
+            .await;
+
+        let err = result.unwrap_err();
+        assert!(matches!(err, CCProxyError::ContentModerated(_)));
+        assert_eq!(err.status_code(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_clean_prompt_passes() {
+        let result = moderation(ModerationAction::Block)
+            .check_prompt(&ChatProtocol::OpenAI, &openai_request("a birthday poem"))
+            .await;
+
+        assert_eq!(result.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_flag_action_lets_prompt_through() {
+        let body = serde_json::to_vec(&json!({
+            "contents": [{"role": "user", "parts": [{"text": "a forbidden plan"}]}]
+        }))
+        .unwrap();
+        let result = moderation(ModerationAction::Flag)
+            .check_prompt(&ChatProtocol::Gemini, &body)
+            .await;
+
+        assert_eq!(result.unwrap(), Some(vec!["violence".to_string()]));
+    }
+
+    #[tokio::test]
+    async fn test_flagged_response_is_blocked_but_streams_pass() {
+        let json_response = |text: &str| {
+            Response::builder()
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(
+                    json!({"content": [{"type": "text", "text": text}]}).to_string(),
+                ))
+                .unwrap()
+        };
+        let moderation = moderation(ModerationAction::Block);
+
+        let blocked = moderation
+            .check_response(&ChatProtocol::Claude, json_response("forbidden answer"))
+            .await;
+        assert!(matches!(blocked, Err(CCProxyError::ContentModerated(_))));
+
+        let clean = moderation
+            .check_response(&ChatProtocol::Claude, json_response("fine answer"))
+            .await
+            .unwrap();
+        let body = to_bytes(clean.into_body(), usize::MAX).await.unwrap();
+        assert!(String::from_utf8_lossy(&body).contains("fine answer"));
+
+        let stream = Response::builder()
+            .header(header::CONTENT_TYPE, "text/event-stream")
+            .body(Body::from("data: forbidden\n\n"))
+            .unwrap();
+        assert!(moderation
+            .check_response(&ChatProtocol::Claude, stream)
+            .await
+            .is_ok());
+    }
+
+    #[test]
+    fn test_parse_moderation_response() {
+        let verdict = parse_moderation_response(&json!({
+            "id": "modr-1",
+            "model": "omni-moderation-latest",
+            "results": [{
+                "flagged": true,
+                "categories": {"harassment": false, "violence": true},
+                "category_scores": {"harassment": 0.01, "violence": 0.93}
+            }]
+        }));
+        assert!(verdict.flagged);
+        assert_eq!(verdict.categories, vec!["violence".to_string()]);
+    }
+}
@@ -0,0 +1,423 @@
+//! Best-effort text extraction from PDF documents.
+//!
+//! Backends without native PDF input receive the text of `document` blocks instead of the
+//! file. Only the text-showing operators of uncompressed or `FlateDecode` content streams
+//! are read. Layout, images and text in fonts with custom encodings (e.g. CID fonts) are
+//! lost.
+
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use flate2::read::ZlibDecoder;
+use std::io::Read;
+
+/// Media type of documents whose `data` is plain text instead of base64.
+pub const PLAIN_TEXT_MEDIA_TYPE: &str = "text/plain";
+
+/// Renders a document block as text for backends that cannot read the file itself.
+pub fn document_to_text(media_type: &str, data: &str, title: Option<&str>) -> String {
+    let name = title.filter(|t| !t.trim().is_empty()).unwrap_or("untitled");
+    if media_type == PLAIN_TEXT_MEDIA_TYPE {
+        return format!("<document title=\"{}\">\n{}\n</document>", name, data);
+    }
+
+    let text = STANDARD
+        .decode(data.trim())
+        .ok()
+        .filter(|_| media_type == "application/pdf")
+        .and_then(|pdf| extract_pdf_text(&pdf));
+    match text {
+        Some(text) => format!(
+            "<document title=\"{}\">\n[Text extracted from the attached {} file, layout, images and tables are not preserved.]\n{}\n</document>",
+            name, media_type, text
+        ),
+        None => {
+            log::warn!(
+                "Could not extract text from document '{}' ({}), the model only receives a note",
+                name,
+                media_type
+            );
+            format!(
+                "<document title=\"{}\">\n[A {} file was attached, but its text could not be extracted for this model.]\n</document>",
+                name, media_type
+            )
+        }
+    }
+}
+
+/// Extracts the text of all page content streams, returns `None` if no text was found.
+pub fn extract_pdf_text(pdf: &[u8]) -> Option<String> {
+    let mut text = String::new();
+    let mut pos = 0;
+    while let Some(start) = find(pdf, b"stream", pos) {
+        pos = start + b"stream".len();
+        // Skip the `stream` inside `endstream`.
+        if pdf[..start].ends_with(b"end") {
+            continue;
+        }
+        let Some(end) = find(pdf, b"endstream", pos) else {
+            break;
+        };
+        let dictionary = stream_dictionary(pdf, start);
+        let body = stream_body(&pdf[pos..end]);
+        pos = end + b"endstream".len();
+
+        if !is_content_stream(dictionary) {
+            continue;
+        }
+        let decoded = if contains(dictionary, b"/FlateDecode") {
+            let mut inflated = Vec::new();
+            if ZlibDecoder::new(body).read_to_end(&mut inflated).is_err() {
+                continue;
+            }
+            inflated
+        } else {
+            body.to_vec()
+        };
+        let page_text = content_stream_text(&decoded);
+        if !page_text.trim().is_empty() {
+            if !text.is_empty() {
+                text.push('\n');
+            }
+            text.push_str(page_text.trim());
+        }
+    }
+    (!text.is_empty()).then_some(text)
+}
+
+fn find(haystack: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    memchr::memmem::find(haystack.get(from..)?, needle).map(|i| i + from)
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    memchr::memmem::find(haystack, needle).is_some()
+}
+
+/// The object dictionary in front of the `stream` keyword at `stream_start`.
+fn stream_dictionary(pdf: &[u8], stream_start: usize) -> &[u8] {
+    let head = &pdf[..stream_start];
+    let from = memchr::memmem::rfind(head, b"obj").map_or(0, |i| i + 3);
+    &head[from..]
+}
+
+/// Strips the end-of-line markers around the stream data.
+fn stream_body(raw: &[u8]) -> &[u8] {
+    let raw = raw
+        .strip_prefix(b"\r\n")
+        .or_else(|| raw.strip_prefix(b"\n"))
+        .unwrap_or(raw);
+    raw.strip_suffix(b"\r\n")
+        .or_else(|| raw.strip_suffix(b"\n"))
+        .unwrap_or(raw)
+}
+
+/// Whether a stream may hold page content, fonts, images and cross-reference data don't.
+fn is_content_stream(dictionary: &[u8]) -> bool {
+    let has_unsupported_filter =
+        contains(dictionary, b"/Filter") && !contains(dictionary, b"/FlateDecode");
+    let is_binary = [
+        b"/Image".as_slice(),
+        b"/XRef",
+        b"/ObjStm",
+        b"/Length1",
+        b"/Type1C",
+        b"/CIDFontType0C",
+        b"/OpenType",
+        b"/DecodeParms",
+    ]
+    .iter()
+    .any(|marker| contains(dictionary, marker));
+    !has_unsupported_filter && !is_binary
+}
+
+/// Decodes a PDF string, UTF-16 when it starts with a byte order mark, PDFDocEncoding otherwise.
+fn decode_pdf_string(bytes: &[u8]) -> String {
+    if let Some(utf16) = bytes.strip_prefix(&[0xFE, 0xFF]) {
+        let units: Vec<u16> = utf16
+            .chunks_exact(2)
+            .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+            .collect();
+        return String::from_utf16_lossy(&units);
+    }
+    bytes
+        .iter()
+        .filter(|b| **b >= 0x20 || **b == b'\t')
+        .map(|b| *b as char)
+        .collect()
+}
+
+/// Reads a literal string starting after its opening parenthesis, returns it with the
+/// position after the closing parenthesis.
+fn read_literal_string(content: &[u8], mut i: usize) -> (Vec<u8>, usize) {
+    let mut bytes = Vec::new();
+    let mut depth = 1;
+    while i < content.len() {
+        let c = content[i];
+        i += 1;
+        match c {
+            b'\\' if i < content.len() => {
+                let escaped = content[i];
+                i += 1;
+                match escaped {
+                    b'n' => bytes.push(b'\n'),
+                    b'r' => bytes.push(b'\r'),
+                    b't' => bytes.push(b'\t'),
+                    b'b' => bytes.push(0x08),
+                    b'f' => bytes.push(0x0C),
+                    b'0'..=b'7' => {
+                        let mut value = (escaped - b'0') as u32;
+                        for _ in 0..2 {
+                            match content.get(i) {
+                                Some(d @ b'0'..=b'7') => {
+                                    value = value * 8 + (d - b'0') as u32;
+                                    i += 1;
+                                }
+                                _ => break,
+                            }
+                        }
+                        bytes.push(value as u8);
+                    }
+                    // A backslash at the end of a line continues the string.
+                    b'\r' => {
+                        if content.get(i) == Some(&b'\n') {
+                            i += 1;
+                        }
+                    }
+                    b'\n' => {}
+                    other => bytes.push(other),
+                }
+            }
+            b'(' => {
+                depth += 1;
+                bytes.push(c);
+            }
+            b')' => {
+                depth -= 1;
+                if depth == 0 {
+                    break;
+                }
+                bytes.push(c);
+            }
+            _ => bytes.push(c),
+        }
+    }
+    (bytes, i)
+}
+
+/// Reads a hex string starting after its `<`, returns it with the position after the `>`.
+fn read_hex_string(content: &[u8], i: usize) -> (Vec<u8>, usize) {
+    let end = memchr::memchr(b'>', &content[i..]).map_or(content.len(), |e| i + e);
+    let digits: Vec<u8> = content[i..end]
+        .iter()
+        .filter(|c| c.is_ascii_hexdigit())
+        .copied()
+        .collect();
+    let bytes = digits
+        .chunks(2)
+        .filter_map(|pair| {
+            let hex = if pair.len() == 2 {
+                [pair[0], pair[1]]
+            } else {
+                [pair[0], b'0']
+            };
+            u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()
+        })
+        .collect();
+    (bytes, (end + 1).min(content.len()))
+}
+
+enum Operand {
+    Text(String),
+    Number(f64),
+    Array(Vec<Operand>),
+    Other,
+}
+
+/// Collects the text shown by the operators of a content stream.
+fn content_stream_text(content: &[u8]) -> String {
+    let mut text = String::new();
+    let mut operands: Vec<Operand> = Vec::new();
+    let mut arrays: Vec<Vec<Operand>> = Vec::new();
+    let mut in_text_object = false;
+    let mut i = 0;
+
+    let newline = |text: &mut String| {
+        if !text.is_empty() && !text.ends_with('\n') {
+            text.push('\n');
+        }
+    };
+
+    while i < content.len() {
+        let c = content[i];
+        let operand = match c {
+            b'%' => {
+                i = memchr::memchr(b'\n', &content[i..]).map_or(content.len(), |e| i + e);
+                continue;
+            }
+            b'(' => {
+                let (bytes, next) = read_literal_string(content, i + 1);
+                i = next;
+                Operand::Text(decode_pdf_string(&bytes))
+            }
+            b'<' if content.get(i + 1) == Some(&b'<') => {
+                i += 2;
+                continue;
+            }
+            b'<' => {
+                let (bytes, next) = read_hex_string(content, i + 1);
+                i = next;
+                // Hex strings usually hold glyph ids, keep only what reads as text.
+                let decoded = decode_pdf_string(&bytes);
+                if bytes.starts_with(&[0xFE, 0xFF])
+                    || (!bytes.is_empty() && decoded.len() == bytes.len())
+                {
+                    Operand::Text(decoded)
+                } else {
+                    Operand::Other
+                }
+            }
+            b'[' => {
+                arrays.push(std::mem::take(&mut operands));
+                i += 1;
+                continue;
+            }
+            b']' => {
+                let items = std::mem::replace(&mut operands, arrays.pop().unwrap_or_default());
+                i += 1;
+                Operand::Array(items)
+            }
+            b'0'..=b'9' | b'-' | b'+' | b'.' => {
+                let start = i;
+                while i < content.len() && matches!(content[i], b'0'..=b'9' | b'-' | b'+' | b'.') {
+                    i += 1;
+                }
+                std::str::from_utf8(&content[start..i])
+                    .ok()
+                    .and_then(|n| n.parse().ok())
+                    .map_or(Operand::Other, Operand::Number)
+            }
+            b'/' => {
+                i += 1;
+                while i < content.len() && is_regular(content[i]) {
+                    i += 1;
+                }
+                Operand::Other
+            }
+            c if c.is_ascii_whitespace() || c == b'>' || c == b'{' || c == b'}' => {
+                i += 1;
+                continue;
+            }
+            _ => {
+                let start = i;
+                while i < content.len() && is_regular(content[i]) {
+                    i += 1;
+                }
+                if start == i {
+                    i += 1;
+                }
+                let operator = &content[start..i];
+                match operator {
+                    b"BT" => in_text_object = true,
+                    b"ET" => {
+                        in_text_object = false;
+                        newline(&mut text);
+                    }
+                    b"T*" if in_text_object => newline(&mut text),
+                    b"Td" | b"TD" if in_text_object => {
+                        // A vertical move starts a new line.
+                        if matches!(operands.last(), Some(Operand::Number(ty)) if *ty != 0.0) {
+                            newline(&mut text);
+                        }
+                    }
+                    b"Tj" | b"'" | b"\"" if in_text_object => {
+                        if operator != b"Tj" {
+                            newline(&mut text);
+                        }
+                        if let Some(Operand::Text(shown)) = operands.last() {
+                            text.push_str(shown);
+                        }
+                    }
+                    b"TJ" if in_text_object => {
+                        if let Some(Operand::Array(items)) = operands.last() {
+                            for item in items {
+                                match item {
+                                    Operand::Text(shown) => text.push_str(shown),
+                                    // Large negative kerning separates words.
+                                    Operand::Number(n) if *n < -200.0 && !text.ends_with(' ') => {
+                                        text.push(' ');
+                                    }
+                                    _ => {}
+                                }
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+                operands.clear();
+                continue;
+            }
+        };
+        operands.push(operand);
+    }
+    text
+}
+
+/// Whether `c` may be part of a name or operator.
+fn is_regular(c: u8) -> bool {
+    !c.is_ascii_whitespace() && !b"()<>[]{}/%".contains(&c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::{write::ZlibEncoder, Compression};
+    use std::io::Write;
+
+    /// Builds a one-page PDF whose page content stream is `content`.
+    fn single_page_pdf(content: &[u8], compress: bool) -> Vec<u8> {
+        let (filter, data) = if compress {
+            let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(content).expect("compress");
+            (" /Filter /FlateDecode", encoder.finish().expect("compress"))
+        } else {
+            ("", content.to_vec())
+        };
+        let mut pdf = b"%PDF-1.4\n1 0 obj\n<< /Type /Catalog /Pages 2 0 R >>\nendobj\n\
+            2 0 obj\n<< /Type /Pages /Kids [3 0 R] /Count 1 >>\nendobj\n\
+            3 0 obj\n<< /Type /Page /Parent 2 0 R /Contents 4 0 R >>\nendobj\n"
+            .to_vec();
+        pdf.extend_from_slice(
+            format!("4 0 obj\n<< /Length {}{} >>\nstream\n", data.len(), filter).as_bytes(),
+        );
+        pdf.extend_from_slice(&data);
+        pdf.extend_from_slice(b"\nendstream\nendobj\ntrailer\n<< /Root 1 0 R >>\n%%EOF\n");
+        pdf
+    }
+
+    #[test]
+    fn test_extracts_text_from_plain_and_compressed_streams() {
+        let content = b"BT /F1 12 Tf 72 712 Td (Quarterly \\(Q3\\) report) Tj 0 -14 Td \
+            [(Rev) -20 (enue) -300 (grew)] TJ ET";
+        for compress in [false, true] {
+            let pdf = single_page_pdf(content, compress);
+            assert_eq!(
+                extract_pdf_text(&pdf).as_deref(),
+                Some("Quarterly (Q3) report\nRevenue grew")
+            );
+        }
+        assert!(extract_pdf_text(b"not a pdf").is_none());
+    }
+
+    #[test]
+    fn test_document_to_text_notes_unreadable_files() {
+        let pdf = STANDARD.encode(single_page_pdf(b"BT (Hello) Tj ET", true));
+        let text = document_to_text("application/pdf", &pdf, Some("greeting.pdf"));
+        assert!(text.starts_with("<document title=\"greeting.pdf\">"));
+        assert!(text.contains("\nHello\n"));
+
+        let unreadable = document_to_text("application/pdf", "AAAA", None);
+        assert!(unreadable.contains("could not be extracted"));
+        assert_eq!(
+            document_to_text(PLAIN_TEXT_MEDIA_TYPE, "notes", Some("a.txt")),
+            "<document title=\"a.txt\">\nnotes\n</document>"
+        );
+    }
+}
@@ -4,11 +4,60 @@
 //! 1. Model target rotation for proxy aliases within a specific group.
 //! 2. Global API key rotation across ALL providers for a proxy alias within a specific group.
 //! 3. Ensures even distribution of key usage across all providers.
+//! 4. Circuit-breaks keys of a (provider, model) that keep answering with a retryable status.
 
 use dashmap::DashMap;
 use lazy_static::lazy_static;
+use serde::Serialize;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::libs::retry::is_retryable_status;
+
+/// Consecutive retryable responses of a key, within `KEY_FAILURE_WINDOW`, that open its circuit.
+pub const KEY_FAILURE_THRESHOLD: u32 = 3;
+/// Failures further apart from the first of a run than this start a new run.
+pub const KEY_FAILURE_WINDOW: Duration = Duration::from_secs(60);
+/// How long a key with an open circuit is skipped by the rotation.
+pub const KEY_CIRCUIT_COOLDOWN: Duration = Duration::from_secs(60);
+
+/// Identifies a key for health tracking: (provider_id, model_name, key).
+type KeyHealthId = (i64, String, String);
+
+#[derive(Debug, Clone, Default)]
+struct KeyHealthState {
+    consecutive_failures: u32,
+    first_failure_at: Option<Instant>,
+    last_status: Option<u16>,
+    open_until: Option<Instant>,
+}
+
+impl KeyHealthState {
+    fn is_open(&self, now: Instant) -> bool {
+        self.open_until.is_some_and(|until| until > now)
+    }
+}
+
+/// Health of a pooled key as reported to the command layer.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyHealth {
+    pub provider_id: i64,
+    pub model_name: String,
+    /// Last 8 characters of the key
+    pub key_suffix: String,
+    pub consecutive_failures: u32,
+    pub last_status: Option<u16>,
+    pub circuit_open: bool,
+    /// Seconds until an open circuit closes again, 0 when it is closed
+    pub retry_after_secs: u64,
+}
+
+fn key_suffix(key: &str) -> String {
+    let chars: Vec<char> = key.chars().collect();
+    chars[chars.len().saturating_sub(8)..].iter().collect()
+}
 
 /// Represents a single API key with its associated provider information
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -52,6 +101,10 @@ pub struct ProxyRotator {
     /// Key: format!("{}:{}", composite_key, provider_id)
     /// Value: (Vec<String>, base_url, model_name) - keys and metadata for this provider
     provider_keys_mapping: Arc<DashMap<String, (Vec<String>, String, String)>>,
+
+    /// Failure tracking and circuit state per key.
+    /// Key: (provider_id, model_name, key)
+    key_health: Arc<DashMap<KeyHealthId, KeyHealthState>>,
 }
 
 impl ProxyRotator {
@@ -83,6 +136,7 @@ impl ProxyRotator {
 
     /// Get the next API key from the global pool for a composite key.
     /// This ensures even distribution across ALL providers and ALL keys for the given group/alias.
+    /// Keys with an open circuit are skipped.
     ///
     /// # Arguments
     /// * `composite_key` - The composite key ("group_name/proxy_alias") to get a key for.
@@ -90,6 +144,18 @@ impl ProxyRotator {
     /// # Returns
     /// The next GlobalApiKey to use, or None if no keys are available.
     pub async fn get_next_global_key(&self, composite_key: &str) -> Option<GlobalApiKey> {
+        self.next_global_key_at(composite_key, Instant::now())
+    }
+
+    /// `get_next_global_key` as of `now`.
+    ///
+    /// When every key of the pool has an open circuit, the one closing first is returned so
+    /// that the alias keeps probing its backends instead of failing until a cooldown ends.
+    pub(crate) fn next_global_key_at(
+        &self,
+        composite_key: &str,
+        now: Instant,
+    ) -> Option<GlobalApiKey> {
         let keys = self.global_key_pools.get(composite_key)?;
 
         if keys.is_empty() {
@@ -103,11 +169,150 @@ impl ProxyRotator {
             .or_insert_with(|| AtomicUsize::new(0));
 
         let current_index = counter.fetch_add(1, Ordering::SeqCst);
-        let selected_key = &keys[current_index % keys.len()];
+        let len = keys.len();
+        let rotation = (0..len).map(|offset| &keys[(current_index + offset) % len]);
+        let mut soonest: Option<(Instant, &GlobalApiKey)> = None;
+        for candidate in rotation {
+            match self.open_until(candidate, now) {
+                None => return Some(candidate.clone()),
+                Some(until) => {
+                    if soonest.is_none_or(|(earliest, _)| until < earliest) {
+                        soonest = Some((until, candidate));
+                    }
+                }
+            }
+        }
 
+        let (until, selected_key) = soonest?;
+        log::warn!(
+            "All {} keys of '{}' are circuit-broken, using the one recovering first (provider_id={}, model={}, key=...{}, recovers in {}s)",
+            len,
+            composite_key,
+            selected_key.provider_id,
+            selected_key.model_name,
+            key_suffix(&selected_key.key),
+            until.saturating_duration_since(now).as_secs()
+        );
         Some(selected_key.clone())
     }
 
+    /// End of the open circuit of `key`, `None` when its circuit is closed.
+    fn open_until(&self, key: &GlobalApiKey, now: Instant) -> Option<Instant> {
+        let id = (key.provider_id, key.model_name.clone(), key.key.clone());
+        self.key_health
+            .get(&id)
+            .filter(|state| state.is_open(now))
+            .and_then(|state| state.open_until)
+    }
+
+    /// Records the HTTP status a backend answered a request made with `key` for `model_name`.
+    ///
+    /// A success clears the failures of the key. Retryable statuses (408, 429 and 5xx) count
+    /// as failures, and `KEY_FAILURE_THRESHOLD` of them in a row within `KEY_FAILURE_WINDOW`
+    /// open the circuit of the key for `KEY_CIRCUIT_COOLDOWN`. Other statuses are client
+    /// errors and say nothing about the key's health.
+    pub fn record_key_status(&self, provider_id: i64, model_name: &str, key: &str, status: u16) {
+        self.record_key_status_at(provider_id, model_name, key, status, Instant::now());
+    }
+
+    /// `record_key_status` as of `now`.
+    pub(crate) fn record_key_status_at(
+        &self,
+        provider_id: i64,
+        model_name: &str,
+        key: &str,
+        status: u16,
+        now: Instant,
+    ) {
+        let id = (provider_id, model_name.to_string(), key.to_string());
+        if (200..300).contains(&status) {
+            if let Some((_, state)) = self.key_health.remove(&id) {
+                if state.consecutive_failures > 0 || state.open_until.is_some() {
+                    log::info!(
+                        "Key recovered (provider_id={}, model={}, key=...{}, status={})",
+                        provider_id,
+                        model_name,
+                        key_suffix(key),
+                        status
+                    );
+                }
+            }
+            return;
+        }
+        if !is_retryable_status(status) {
+            return;
+        }
+
+        let mut state = self.key_health.entry(id).or_default();
+        let run_expired = state
+            .first_failure_at
+            .is_none_or(|first| now.saturating_duration_since(first) > KEY_FAILURE_WINDOW);
+        if run_expired {
+            state.consecutive_failures = 0;
+            state.first_failure_at = Some(now);
+        }
+        state.consecutive_failures += 1;
+        state.last_status = Some(status);
+
+        if state.consecutive_failures >= KEY_FAILURE_THRESHOLD {
+            state.open_until = Some(now + KEY_CIRCUIT_COOLDOWN);
+            log::warn!(
+                "Key circuit opened for {}s after {} consecutive failures (provider_id={}, model={}, key=...{}, last_status={})",
+                KEY_CIRCUIT_COOLDOWN.as_secs(),
+                state.consecutive_failures,
+                provider_id,
+                model_name,
+                key_suffix(key),
+                status
+            );
+            state.consecutive_failures = 0;
+            state.first_failure_at = None;
+        }
+    }
+
+    /// Health of every key in the pools, ordered by provider, model and key.
+    pub fn key_health(&self) -> Vec<KeyHealth> {
+        self.key_health_at(Instant::now())
+    }
+
+    /// `key_health` as of `now`.
+    pub(crate) fn key_health_at(&self, now: Instant) -> Vec<KeyHealth> {
+        let mut ids: Vec<KeyHealthId> = self
+            .global_key_pools
+            .iter()
+            .flat_map(|pool| {
+                pool.value()
+                    .iter()
+                    .map(|key| (key.provider_id, key.model_name.clone(), key.key.clone()))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        ids.sort();
+        ids.dedup();
+
+        ids.into_iter()
+            .map(|id| {
+                let state = self
+                    .key_health
+                    .get(&id)
+                    .map(|state| state.clone())
+                    .unwrap_or_default();
+                let (provider_id, model_name, key) = id;
+                KeyHealth {
+                    provider_id,
+                    model_name,
+                    key_suffix: key_suffix(&key),
+                    consecutive_failures: state.consecutive_failures,
+                    last_status: state.last_status,
+                    circuit_open: state.is_open(now),
+                    retry_after_secs: state
+                        .open_until
+                        .map_or(0, |until| until.saturating_duration_since(now).as_secs()),
+                }
+            })
+            .collect()
+    }
+
     // [Gemini] New method for atomic replacement of the key pool.
     /// Atomically replaces the entire key pool for a given composite key.
     pub async fn replace_pool_for_composite_key(
@@ -2,8 +2,11 @@
 
 #[cfg(test)]
 mod tests {
-    use super::super::proxy_rotator::{GlobalApiKey, ProxyRotator};
+    use super::super::proxy_rotator::{
+        GlobalApiKey, ProxyRotator, KEY_CIRCUIT_COOLDOWN, KEY_FAILURE_THRESHOLD, KEY_FAILURE_WINDOW,
+    };
     use std::collections::HashMap;
+    use std::time::{Duration, Instant};
 
     // [Gemini] The old `update_global_key_pool` helper is removed as tests now use the new public API directly.
 
@@ -219,4 +222,129 @@ mod tests {
 
         println!("Balanced global rotation test passed!");
     }
+
+    fn breaker_pool() -> Vec<GlobalApiKey> {
+        vec![
+            GlobalApiKey::new(
+                "key-a".to_string(),
+                1,
+                "https://api.openai.com".to_string(),
+                "gpt-4".to_string(),
+            ),
+            GlobalApiKey::new(
+                "key-b".to_string(),
+                1,
+                "https://api.openai.com".to_string(),
+                "gpt-4".to_string(),
+            ),
+        ]
+    }
+
+    fn fail(rotator: &ProxyRotator, key: &str, status: u16, times: u32, now: Instant) {
+        for _ in 0..times {
+            rotator.record_key_status_at(1, "gpt-4", key, status, now);
+        }
+    }
+
+    /// A key failing with 429/5xx is skipped while its circuit is open and used again after
+    #[tokio::test]
+    async fn test_failing_key_is_circuit_broken_and_recovers() {
+        let rotator = ProxyRotator::new();
+        let alias = "test-breaker";
+        rotator
+            .replace_pool_for_composite_key(alias, breaker_pool())
+            .await;
+        let now = Instant::now();
+
+        fail(&rotator, "key-a", 429, KEY_FAILURE_THRESHOLD - 1, now);
+        fail(&rotator, "key-a", 400, 1, now);
+        let health = rotator.key_health_at(now);
+        assert_eq!(health[0].key_suffix, "key-a");
+        assert_eq!(health[0].consecutive_failures, KEY_FAILURE_THRESHOLD - 1);
+        assert!(!health[0].circuit_open);
+
+        fail(&rotator, "key-a", 503, 1, now);
+        let health = rotator.key_health_at(now);
+        assert!(health[0].circuit_open);
+        assert_eq!(health[0].last_status, Some(503));
+        assert_eq!(health[0].retry_after_secs, KEY_CIRCUIT_COOLDOWN.as_secs());
+        assert!(!health[1].circuit_open);
+
+        for _ in 0..4 {
+            let key = rotator.next_global_key_at(alias, now).expect("key");
+            assert_eq!(key.key, "key-b");
+        }
+
+        let after_cooldown = now + KEY_CIRCUIT_COOLDOWN + Duration::from_secs(1);
+        let used: Vec<String> = (0..2)
+            .map(|_| {
+                rotator
+                    .next_global_key_at(alias, after_cooldown)
+                    .expect("key")
+                    .key
+            })
+            .collect();
+        assert!(used.contains(&"key-a".to_string()));
+        assert!(!rotator.key_health_at(after_cooldown)[0].circuit_open);
+
+        // A success clears the failures counted after the recovery
+        fail(
+            &rotator,
+            "key-a",
+            500,
+            KEY_FAILURE_THRESHOLD - 1,
+            after_cooldown,
+        );
+        fail(&rotator, "key-a", 200, 1, after_cooldown);
+        fail(&rotator, "key-a", 500, 1, after_cooldown);
+        let health = rotator.key_health_at(after_cooldown);
+        assert_eq!(health[0].consecutive_failures, 1);
+        assert!(!health[0].circuit_open);
+    }
+
+    /// Failures spread over more than the window never open the circuit
+    #[tokio::test]
+    async fn test_failures_outside_the_window_do_not_open_the_circuit() {
+        let rotator = ProxyRotator::new();
+        let alias = "test-breaker-window";
+        rotator
+            .replace_pool_for_composite_key(alias, breaker_pool())
+            .await;
+        let mut now = Instant::now();
+
+        for _ in 0..KEY_FAILURE_THRESHOLD * 2 {
+            fail(&rotator, "key-a", 502, 1, now);
+            now += KEY_FAILURE_WINDOW / 2 + Duration::from_secs(1);
+        }
+        assert!(rotator
+            .key_health_at(now)
+            .iter()
+            .all(|health| !health.circuit_open));
+    }
+
+    /// With every key broken, the one recovering first is still handed out
+    #[tokio::test]
+    async fn test_all_keys_broken_uses_the_first_to_recover() {
+        let rotator = ProxyRotator::new();
+        let alias = "test-breaker-all";
+        rotator
+            .replace_pool_for_composite_key(alias, breaker_pool())
+            .await;
+        let now = Instant::now();
+
+        fail(&rotator, "key-b", 429, KEY_FAILURE_THRESHOLD, now);
+        fail(
+            &rotator,
+            "key-a",
+            429,
+            KEY_FAILURE_THRESHOLD,
+            now + Duration::from_secs(5),
+        );
+
+        let later = now + Duration::from_secs(10);
+        for _ in 0..3 {
+            let key = rotator.next_global_key_at(alias, later).expect("key");
+            assert_eq!(key.key, "key-b");
+        }
+    }
 }
@@ -0,0 +1,883 @@
+//! Optional redaction of personal data in proxied prompts.
+//!
+//! When enabled under `chat_completion_proxy_redaction`, emails, phone numbers, API keys,
+//! card numbers and any configured custom pattern found in the prompt are replaced by
+//! placeholders such as `[EMAIL_1]` before the request is sent upstream. The same value
+//! always gets the same placeholder within a request, so the model can still refer to it.
+//!
+//! With `restoreResponse`, placeholders the model echoes back are replaced by the original
+//! values before the response reaches the client. Streams are restored after parsing each
+//! event, joining the text deltas of each choice or content block, so a placeholder split
+//! across two events is restored too.
+
+use axum::{body::Body, response::Response};
+use bytes::Bytes;
+use futures_util::StreamExt;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use super::secrets::API_KEY_PATTERN;
+use crate::constants::CFG_CCPROXY_REDACTION;
+use crate::db::MainStore;
+
+/// Request fields that carry prompt text, across all client protocols.
+pub(crate) const PROMPT_FIELDS: &[&str] = &[
+    "messages",
+    "system",
+    "contents",
+    "systemInstruction",
+    "system_instruction",
+    "input",
+    "instructions",
+    "prompt",
+];
+/// Fields inside the prompt that are identifiers or binary data rather than text.
+const SKIPPED_FIELDS: &[&str] = &[
+    "role",
+    "type",
+    "id",
+    "tool_call_id",
+    "tool_use_id",
+    "call_id",
+    "name",
+    "model",
+    "mime_type",
+    "mimeType",
+    "data",
+    "url",
+    "image_url",
+    "signature",
+    "cache_control",
+];
+
+/// Built-in kinds of personal data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PiiKind {
+    Email,
+    Phone,
+    ApiKey,
+    CreditCard,
+}
+
+impl PiiKind {
+    fn label(&self) -> &'static str {
+        match self {
+            PiiKind::Email => "EMAIL",
+            PiiKind::Phone => "PHONE",
+            PiiKind::ApiKey => "API_KEY",
+            PiiKind::CreditCard => "CREDIT_CARD",
+        }
+    }
+
+    fn pattern(&self) -> &'static str {
+        match self {
+            PiiKind::Email => r"[A-Za-z0-9._%+-]+@[A-Za-z0-9-]+(?:\.[A-Za-z0-9-]+)*\.[A-Za-z]{2,}",
+            PiiKind::Phone => {
+                r"(?:\+\d{1,3}[ .-]?)?(?:\(\d{1,4}\)[ .-]?)?\d{2,4}(?:[ .-]?\d{2,4}){1,4}"
+            }
+            PiiKind::ApiKey => API_KEY_PATTERN,
+            PiiKind::CreditCard => r"\b(?:\d[ -]?){12,18}\d\b",
+        }
+    }
+
+    /// Heuristic check of a regex match, weeding out lookalikes.
+    fn accepts(&self, text: &str, start: usize, end: usize) -> bool {
+        let found = &text[start..end];
+        match self {
+            PiiKind::Phone => {
+                let digits = found.chars().filter(char::is_ascii_digit).count();
+                let before = text[..start].chars().next_back();
+                let after = text[end..].chars().next();
+                (7..=15).contains(&digits)
+                    && !before.is_some_and(|c| c.is_alphanumeric() || c == '.')
+                    && !after.is_some_and(|c| c.is_alphanumeric())
+                    && !looks_like_date_or_ip(found)
+            }
+            PiiKind::CreditCard => luhn_valid(found),
+            PiiKind::Email | PiiKind::ApiKey => true,
+        }
+    }
+}
+
+/// Dates and IPv4 addresses match the phone pattern but are not phone numbers.
+fn looks_like_date_or_ip(text: &str) -> bool {
+    let groups: Vec<&str> = text.split(['-', '.', '/', ' ']).collect();
+    let lens: Vec<usize> = groups.iter().map(|g| g.len()).collect();
+    match lens.as_slice() {
+        [4, m, d] | [d, m, 4] => *m <= 2 && *d <= 2,
+        [a, b, c, d] => text.contains('.') && [a, b, c, d].iter().all(|l| **l <= 3),
+        _ => false,
+    }
+}
+
+fn luhn_valid(text: &str) -> bool {
+    let digits: Vec<u32> = text.chars().filter_map(|c| c.to_digit(10)).collect();
+    if !(13..=19).contains(&digits.len()) {
+        return false;
+    }
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, d)| match (i % 2 == 1, d * 2) {
+            (true, doubled) if doubled > 9 => doubled - 9,
+            (true, doubled) => doubled,
+            (false, _) => *d,
+        })
+        .sum();
+    sum % 10 == 0
+}
+
+/// A user defined pattern, its matches are replaced by `[LABEL_n]`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CustomPattern {
+    pub label: String,
+    /// A regular expression in the syntax of the `regex` crate
+    pub pattern: String,
+}
+
+/// Redaction settings of the proxy, stored under `chat_completion_proxy_redaction`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RedactionConfig {
+    pub enabled: bool,
+    /// Put the original values back into the response
+    pub restore_response: bool,
+    /// Built-in kinds to redact
+    pub kinds: Vec<PiiKind>,
+    pub custom_patterns: Vec<CustomPattern>,
+}
+
+impl Default for RedactionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            restore_response: true,
+            kinds: vec![
+                PiiKind::Email,
+                PiiKind::Phone,
+                PiiKind::ApiKey,
+                PiiKind::CreditCard,
+            ],
+            custom_patterns: Vec::new(),
+        }
+    }
+}
+
+struct Detector {
+    label: String,
+    regex: Regex,
+    kind: Option<PiiKind>,
+}
+
+/// The placeholders handed out for one request, and the values they stand for.
+#[derive(Debug, Clone, Default)]
+pub struct Placeholders {
+    by_value: HashMap<String, String>,
+    counters: HashMap<String, usize>,
+    /// (placeholder, original value) in the order they were handed out
+    entries: Vec<(String, String)>,
+}
+
+impl Placeholders {
+    fn placeholder_for(&mut self, label: &str, value: &str) -> String {
+        if let Some(placeholder) = self.by_value.get(value) {
+            return placeholder.clone();
+        }
+        let counter = self.counters.entry(label.to_string()).or_default();
+        *counter += 1;
+        let placeholder = format!("[{}_{}]", label, counter);
+        self.by_value.insert(value.to_string(), placeholder.clone());
+        self.entries.push((placeholder.clone(), value.to_string()));
+        placeholder
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn max_len(&self) -> usize {
+        self.entries.iter().map(|(p, _)| p.len()).max().unwrap_or(0)
+    }
+
+    /// Replaces the placeholders in a JSON or SSE body by their JSON escaped values.
+    pub fn restore(&self, text: &str) -> String {
+        self.replace(text, |value| {
+            let escaped = serde_json::to_string(value).unwrap_or_default();
+            escaped
+                .strip_prefix('"')
+                .and_then(|s| s.strip_suffix('"'))
+                .unwrap_or(value)
+                .to_string()
+        })
+    }
+
+    /// Replaces the placeholders in plain text by their values.
+    fn restore_text(&self, text: &str) -> String {
+        self.replace(text, str::to_string)
+    }
+
+    fn replace(&self, text: &str, value_of: impl Fn(&str) -> String) -> String {
+        // `[EMAIL_10]` must not be restored as `[EMAIL_1]` followed by `0]`,
+        // the closing bracket is part of every placeholder so this cannot happen
+        let mut restored = text.to_string();
+        for (placeholder, value) in &self.entries {
+            if restored.contains(placeholder.as_str()) {
+                restored = restored.replace(placeholder.as_str(), &value_of(value));
+            }
+        }
+        restored
+    }
+}
+
+/// The redaction step of a proxied chat.
+pub struct Redactor {
+    detectors: Vec<Detector>,
+    restore_response: bool,
+}
+
+impl Redactor {
+    /// Compiles the configured patterns, skipping invalid custom ones.
+    pub fn new(config: &RedactionConfig) -> Self {
+        let mut detectors: Vec<Detector> = config
+            .kinds
+            .iter()
+            .filter_map(|kind| {
+                Regex::new(kind.pattern()).ok().map(|regex| Detector {
+                    label: kind.label().to_string(),
+                    regex,
+                    kind: Some(*kind),
+                })
+            })
+            .collect();
+        for custom in &config.custom_patterns {
+            let label = custom
+                .label
+                .trim()
+                .to_uppercase()
+                .replace(|c: char| !c.is_ascii_alphanumeric(), "_");
+            if label.is_empty() || custom.pattern.is_empty() {
+                continue;
+            }
+            match Regex::new(&custom.pattern) {
+                Ok(regex) => detectors.push(Detector {
+                    label,
+                    regex,
+                    kind: None,
+                }),
+                Err(e) => log::warn!(
+                    "Ignoring invalid redaction pattern '{}': {}",
+                    custom.pattern,
+                    e
+                ),
+            }
+        }
+        Self {
+            detectors,
+            restore_response: config.restore_response,
+        }
+    }
+
+    /// Loads the configured redaction, `None` when it is disabled.
+    pub fn from_store(main_store: &Arc<RwLock<MainStore>>) -> Option<Self> {
+        let config: RedactionConfig = main_store
+            .read()
+            .ok()?
+            .get_config(CFG_CCPROXY_REDACTION, RedactionConfig::default());
+        if !config.enabled {
+            return None;
+        }
+        let redactor = Self::new(&config);
+        (!redactor.detectors.is_empty()).then_some(redactor)
+    }
+
+    /// Replaces every detected value in the text by its placeholder.
+    ///
+    /// Overlapping matches are resolved in favour of the one starting first, then the longer.
+    pub fn redact_text(&self, text: &str, placeholders: &mut Placeholders) -> String {
+        let mut found: Vec<(usize, usize, &str)> = Vec::new();
+        for detector in &self.detectors {
+            for m in detector.regex.find_iter(text) {
+                if m.is_empty() {
+                    continue;
+                }
+                let accepted = detector
+                    .kind
+                    .map_or(true, |kind| kind.accepts(text, m.start(), m.end()));
+                if accepted {
+                    found.push((m.start(), m.end(), detector.label.as_str()));
+                }
+            }
+        }
+        if found.is_empty() {
+            return text.to_string();
+        }
+        found.sort_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)));
+
+        let mut redacted = String::with_capacity(text.len());
+        let mut cursor = 0;
+        for (start, end, label) in found {
+            if start < cursor {
+                continue;
+            }
+            redacted.push_str(&text[cursor..start]);
+            redacted.push_str(&placeholders.placeholder_for(label, &text[start..end]));
+            cursor = end;
+        }
+        redacted.push_str(&text[cursor..]);
+        redacted
+    }
+
+    fn redact_value(&self, value: &mut Value, placeholders: &mut Placeholders) {
+        match value {
+            Value::String(text) => *text = self.redact_text(text, placeholders),
+            Value::Array(items) => items
+                .iter_mut()
+                .for_each(|item| self.redact_value(item, placeholders)),
+            Value::Object(map) => map
+                .iter_mut()
+                .filter(|(key, _)| !SKIPPED_FIELDS.contains(&key.as_str()))
+                .for_each(|(_, item)| self.redact_value(item, placeholders)),
+            _ => {}
+        }
+    }
+
+    /// Redacts the prompt fields of a client request body.
+    ///
+    /// Bodies that are not JSON are returned unchanged.
+    pub fn redact_request(&self, body: Bytes) -> (Bytes, Placeholders) {
+        let mut placeholders = Placeholders::default();
+        let Ok(mut json) = serde_json::from_slice::<Value>(&body) else {
+            return (body, placeholders);
+        };
+        let Some(object) = json.as_object_mut() else {
+            return (body, placeholders);
+        };
+        for (key, value) in object.iter_mut() {
+            if PROMPT_FIELDS.contains(&key.as_str()) {
+                self.redact_value(value, &mut placeholders);
+            }
+        }
+        if placeholders.is_empty() {
+            return (body, placeholders);
+        }
+        log::debug!(
+            "Redacted {} value(s) from the proxy request",
+            placeholders.entries.len()
+        );
+        match serde_json::to_vec(&json) {
+            Ok(redacted) => (Bytes::from(redacted), placeholders),
+            Err(_) => (body, Placeholders::default()),
+        }
+    }
+
+    /// Puts the original values back into the response body when configured.
+    pub fn restore_response(&self, response: Response, placeholders: Placeholders) -> Response {
+        if !self.restore_response || placeholders.is_empty() {
+            return response;
+        }
+        let (mut parts, body) = response.into_parts();
+        // The restored body has a different length
+        parts.headers.remove(axum::http::header::CONTENT_LENGTH);
+        let streamed = parts
+            .headers
+            .get(axum::http::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.contains("event-stream") || value.contains("ndjson"));
+        let placeholders = Arc::new(placeholders);
+        let body = if streamed {
+            restore_stream(body, placeholders)
+        } else {
+            restore_body(body, placeholders)
+        };
+        Response::from_parts(parts, body)
+    }
+}
+
+/// Where the text can be cut without splitting a placeholder that is still arriving.
+fn safe_cut(pending: &[u8], max_len: usize) -> usize {
+    match pending.iter().rposition(|b| *b == b'[') {
+        Some(pos) if !pending[pos..].contains(&b']') && pending.len() - pos < max_len => pos,
+        _ => pending.len(),
+    }
+}
+
+/// Restores the placeholders in a complete, non-streamed body.
+fn restore_body(body: Body, placeholders: Arc<Placeholders>) -> Body {
+    Body::from_stream(futures_util::stream::once(async move {
+        axum::body::to_bytes(body, usize::MAX)
+            .await
+            .map(|bytes| Bytes::from(placeholders.restore(&String::from_utf8_lossy(&bytes))))
+    }))
+}
+
+/// Restores the placeholders in an SSE or NDJSON stream, event by event.
+fn restore_stream(body: Body, placeholders: Arc<Placeholders>) -> Body {
+    let stream = futures_util::stream::unfold(
+        (
+            body.into_data_stream(),
+            StreamRestorer::new(placeholders),
+            false,
+        ),
+        |(mut stream, mut restorer, done)| async move {
+            if done {
+                return None;
+            }
+            match stream.next().await {
+                Some(Ok(chunk)) => {
+                    let restored = restorer.push(&chunk);
+                    Some((Ok(Bytes::from(restored)), (stream, restorer, false)))
+                }
+                Some(Err(e)) => Some((Err(e), (stream, restorer, true))),
+                None => {
+                    let restored = restorer.finish();
+                    Some((Ok(Bytes::from(restored)), (stream, restorer, true)))
+                }
+            }
+        },
+    )
+    .filter(|chunk| futures_util::future::ready(!matches!(chunk, Ok(bytes) if bytes.is_empty())));
+    Body::from_stream(stream)
+}
+
+/// The streamed text fields of an event, as (stream key, JSON pointer).
+///
+/// The key tells apart the choices, candidates and content blocks a stream interleaves, so
+/// a placeholder is only ever joined with the text that precedes it in the same block.
+fn text_deltas(data: &Value) -> Vec<(String, String)> {
+    let mut deltas = Vec::new();
+    let mut push = |key: String, pointer: String| {
+        if data
+            .pointer(&pointer)
+            .and_then(Value::as_str)
+            .is_some_and(|text| !text.is_empty())
+        {
+            deltas.push((key, pointer));
+        }
+    };
+    let index_of = |value: &Value, field: &str| value.get(field).and_then(Value::as_u64);
+
+    // OpenAI chat and legacy completions chunks
+    if let Some(choices) = data.get("choices").and_then(Value::as_array) {
+        for (i, choice) in choices.iter().enumerate() {
+            let index = index_of(choice, "index").unwrap_or(i as u64);
+            for field in ["/delta/content", "/delta/reasoning_content", "/text"] {
+                push(
+                    format!("choice:{}{}", index, field),
+                    format!("/choices/{}{}", i, field),
+                );
+            }
+        }
+    }
+    // Gemini candidates
+    if let Some(candidates) = data.get("candidates").and_then(Value::as_array) {
+        for (i, candidate) in candidates.iter().enumerate() {
+            let index = index_of(candidate, "index").unwrap_or(i as u64);
+            let parts = candidate
+                .pointer("/content/parts")
+                .and_then(Value::as_array)
+                .map_or(0, Vec::len);
+            for part in 0..parts {
+                push(
+                    format!("candidate:{}:{}", index, part),
+                    format!("/candidates/{}/content/parts/{}/text", i, part),
+                );
+            }
+        }
+    }
+    match data.get("type").and_then(Value::as_str) {
+        // Claude content blocks
+        Some("content_block_delta") => {
+            let index = index_of(data, "index").unwrap_or_default();
+            for field in ["/delta/text", "/delta/thinking"] {
+                push(format!("block:{}{}", index, field), field.to_string());
+            }
+        }
+        // OpenAI Responses text parts
+        Some(kind @ ("response.output_text.delta" | "response.reasoning_summary_text.delta")) => {
+            let output = index_of(data, "output_index").unwrap_or_default();
+            let part = index_of(data, "content_index")
+                .or_else(|| index_of(data, "summary_index"))
+                .unwrap_or_default();
+            push(
+                format!("{}:{}:{}", kind, output, part),
+                "/delta".to_string(),
+            );
+        }
+        _ => {}
+    }
+    // Ollama chunks
+    if data.get("done").and_then(Value::as_bool) == Some(false) {
+        push("message".to_string(), "/message/content".to_string());
+    }
+    deltas
+}
+
+/// Text held back from a streamed text field, with the event it came from.
+struct HeldText {
+    key: String,
+    pointer: String,
+    text: String,
+    /// Lines of the event, reused to send the text on its own
+    lines: Vec<String>,
+    data_line: usize,
+    data: Value,
+}
+
+/// Restores the placeholders of a stream after parsing each event.
+///
+/// Text deltas are restored per choice or content block. The end of a delta that may be the
+/// start of a placeholder is held back until the next delta of the same block, and is sent
+/// in a copy of its event before the first event that carries no text.
+struct StreamRestorer {
+    placeholders: Arc<Placeholders>,
+    max_len: usize,
+    /// Bytes of a line that is not complete yet
+    partial: Vec<u8>,
+    /// Lines of the event being read, with their line endings
+    event: Vec<String>,
+    held: Vec<HeldText>,
+}
+
+impl StreamRestorer {
+    fn new(placeholders: Arc<Placeholders>) -> Self {
+        Self {
+            max_len: placeholders.max_len(),
+            placeholders,
+            partial: Vec::new(),
+            event: Vec::new(),
+            held: Vec::new(),
+        }
+    }
+
+    /// Adds a network chunk and returns the restored events it completed.
+    fn push(&mut self, chunk: &[u8]) -> String {
+        self.partial.extend_from_slice(chunk);
+        let Some(last_newline) = memchr::memrchr(b'\n', &self.partial) else {
+            return String::new();
+        };
+        let rest = self.partial.split_off(last_newline + 1);
+        let complete = std::mem::replace(&mut self.partial, rest);
+
+        let mut restored = String::new();
+        for line in String::from_utf8_lossy(&complete).split_inclusive('\n') {
+            let content = line.trim_end_matches(['\r', '\n']);
+            // A blank line ends an SSE event, a JSON line is an NDJSON event on its own
+            let ends_event =
+                content.is_empty() || (self.event.is_empty() && content.starts_with('{'));
+            self.event.push(line.to_string());
+            if ends_event {
+                restored.push_str(&self.finish_event());
+            }
+        }
+        restored
+    }
+
+    /// Returns the rest of the stream, including all held back text.
+    fn finish(&mut self) -> String {
+        if !self.partial.is_empty() {
+            let rest = std::mem::take(&mut self.partial);
+            self.event.push(String::from_utf8_lossy(&rest).into_owned());
+        }
+        let mut restored = if self.event.is_empty() {
+            String::new()
+        } else {
+            self.finish_event()
+        };
+        restored.push_str(&self.flush());
+        restored
+    }
+
+    fn finish_event(&mut self) -> String {
+        let mut lines = std::mem::take(&mut self.event);
+        let parsed = lines.iter().enumerate().find_map(|(i, line)| {
+            serde_json::from_str::<Value>(event_payload(line))
+                .ok()
+                .filter(Value::is_object)
+                .map(|data| (i, data))
+        });
+        let Some((data_line, mut data)) = parsed else {
+            return self.flush() + &self.placeholders.restore(&lines.concat());
+        };
+        let deltas = text_deltas(&data);
+        if deltas.is_empty() {
+            return self.flush() + &self.placeholders.restore(&lines.concat());
+        }
+
+        let mut held = Vec::new();
+        for (key, pointer) in &deltas {
+            let mut text = self.take_held(key);
+            if let Some(delta) = data.pointer(pointer).and_then(Value::as_str) {
+                text.push_str(delta);
+            }
+            let rest = text.split_off(safe_cut(text.as_bytes(), self.max_len));
+            if let Some(slot) = data.pointer_mut(pointer) {
+                *slot = Value::String(self.placeholders.restore_text(&text));
+            }
+            if !rest.is_empty() {
+                held.push((key.clone(), pointer.clone(), rest));
+            }
+        }
+        for (key, pointer, text) in held {
+            // The copy sent later carries the held back text only
+            let mut template = data.clone();
+            for (_, other) in &deltas {
+                if let Some(slot) = template.pointer_mut(other) {
+                    *slot = Value::String(String::new());
+                }
+            }
+            self.held.push(HeldText {
+                key,
+                pointer,
+                text,
+                lines: lines.clone(),
+                data_line,
+                data: template,
+            });
+        }
+
+        lines[data_line] = with_payload(&lines[data_line], &data);
+        self.placeholders.restore(&lines.concat())
+    }
+
+    fn take_held(&mut self, key: &str) -> String {
+        match self.held.iter().position(|held| held.key == key) {
+            Some(pos) => self.held.remove(pos).text,
+            None => String::new(),
+        }
+    }
+
+    /// Sends all held back text, each in a copy of the event it came from.
+    fn flush(&mut self) -> String {
+        std::mem::take(&mut self.held)
+            .into_iter()
+            .map(|mut held| {
+                if let Some(slot) = held.data.pointer_mut(&held.pointer) {
+                    *slot = Value::String(self.placeholders.restore_text(&held.text));
+                }
+                held.lines[held.data_line] = with_payload(&held.lines[held.data_line], &held.data);
+                held.lines.concat()
+            })
+            .collect()
+    }
+}
+
+/// The JSON of an SSE `data:` line or an NDJSON line.
+fn event_payload(line: &str) -> &str {
+    let content = line.trim_end_matches(['\r', '\n']);
+    content
+        .strip_prefix("data:")
+        .map_or(content, str::trim_start)
+}
+
+/// Replaces the JSON of an event line, keeping its prefix and line ending.
+fn with_payload(line: &str, data: &Value) -> String {
+    let content = line.trim_end_matches(['\r', '\n']);
+    let ending = &line[content.len()..];
+    let prefix = if content.starts_with("data:") {
+        "data: "
+    } else {
+        ""
+    };
+    format!("{}{}{}", prefix, data, ending)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn redactor() -> Redactor {
+        Redactor::new(&RedactionConfig {
+            enabled: true,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_redacts_email_and_phone_in_prompt() {
+        let body = json!({
+            "model": "gpt-4o",
+            "messages": [
+                {"role": "system", "content": "You are helpful."},
+                {"role": "user", "content": [
+                    {"type": "text", "text": "Mail jane.doe@example.com or call +1 (555) 123-4567, again jane.doe@example.com"}
+                ]}
+            ]
+        });
+        let (redacted, placeholders) =
+            redactor().redact_request(Bytes::from(serde_json::to_vec(&body).unwrap()));
+        let redacted: Value = serde_json::from_slice(&redacted).unwrap();
+
+        assert_eq!(
+            redacted["messages"][1]["content"][0]["text"],
+            "Mail [EMAIL_1] or call [PHONE_1], again [EMAIL_1]"
+        );
+        assert_eq!(redacted["model"], "gpt-4o");
+        assert_eq!(redacted["messages"][1]["role"], "user");
+        assert_eq!(placeholders.entries.len(), 2);
+    }
+
+    #[test]
+    fn test_keeps_dates_versions_and_short_numbers() {
+        let mut placeholders = Placeholders::default();
+        let text = "Released 2024-05-17 as v1.2.3, server 192.168.1.20, order 1234.";
+        assert_eq!(redactor().redact_text(text, &mut placeholders), text);
+        assert!(placeholders.is_empty());
+    }
+
+    #[test]
+    fn test_redacts_keys_cards_and_custom_patterns() {
+        let redactor = Redactor::new(&RedactionConfig {
+            enabled: true,
+            custom_patterns: vec![
+                CustomPattern {
+                    label: "employee id".to_string(),
+                    pattern: r"\bEMP-\d{5}\b".to_string(),
+                },
+                CustomPattern {
+                    label: "broken".to_string(),
+                    pattern: "(unclosed".to_string(),
+                },
+            ],
+            ..Default::default()
+        });
+        let mut placeholders = Placeholders::default();
+        let text = "key sk-abcdefghijklmnopqrstuvwx card 4111 1111 1111 1111 for EMP-01234";
+        assert_eq!(
+            redactor.redact_text(text, &mut placeholders),
+            "key [API_KEY_1] card [CREDIT_CARD_1] for [EMPLOYEE_ID_1]"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_restores_placeholders_in_response() {
+        let redactor = redactor();
+        let (_, placeholders) = redactor.redact_request(Bytes::from(
+            json!({"messages": [{"role": "user", "content": "I am jane@example.com, phone 13812345678"}]})
+                .to_string(),
+        ));
+
+        let upstream = json!({"choices": [{"message": {
+            "role": "assistant",
+            "content": "Noted: [EMAIL_1] and [PHONE_1]."
+        }}]});
+        let response = redactor.restore_response(
+            Response::new(Body::from(upstream.to_string())),
+            placeholders,
+        );
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            body["choices"][0]["message"]["content"],
+            "Noted: jane@example.com and 13812345678."
+        );
+    }
+
+    /// Restores a stream sent in `chunks`, with the placeholders of two prompt values.
+    async fn restored_stream(chunks: &[&str]) -> String {
+        let redactor = redactor();
+        let mut placeholders = Placeholders::default();
+        redactor.redact_text("write to jane@example.com", &mut placeholders);
+        redactor.redact_text("or call 13812345678", &mut placeholders);
+
+        let chunks: Vec<Result<Bytes, std::io::Error>> = chunks
+            .iter()
+            .map(|chunk| Ok(Bytes::from(chunk.to_string())))
+            .collect();
+        let response = Response::builder()
+            .header(axum::http::header::CONTENT_TYPE, "text/event-stream")
+            .body(Body::from_stream(futures_util::stream::iter(chunks)))
+            .unwrap();
+        let response = redactor.restore_response(response, placeholders);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        String::from_utf8_lossy(&body).into_owned()
+    }
+
+    /// The streamed content of each choice of an OpenAI stream.
+    fn choice_contents(stream: &str) -> Vec<String> {
+        let mut contents = vec![String::new(); 2];
+        for data in stream
+            .lines()
+            .filter_map(|line| line.strip_prefix("data: "))
+        {
+            let Ok(chunk) = serde_json::from_str::<Value>(data) else {
+                continue;
+            };
+            for choice in chunk["choices"].as_array().into_iter().flatten() {
+                let index = choice["index"].as_u64().unwrap() as usize;
+                contents[index].push_str(choice["delta"]["content"].as_str().unwrap_or_default());
+            }
+        }
+        contents
+    }
+
+    #[tokio::test]
+    async fn test_restores_placeholder_split_across_chunks() {
+        let restored =
+            restored_stream(&["data: {\"delta\":\"Sent to [EMA", "IL_1] today\"}\n\n"]).await;
+        assert_eq!(
+            restored,
+            "data: {\"delta\":\"Sent to jane@example.com today\"}\n\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_restores_placeholder_split_across_events() {
+        let restored = restored_stream(&[
+            "data: {\"choices\":[{\"index\":0,\"delta\":{\"content\":\"Sent to [EMA\"}}]}\n\n",
+            "data: {\"choices\":[{\"index\":1,\"delta\":{\"content\":\"Call [PHO\"}}]}\n\n",
+            "data: {\"choices\":[{\"index\":0,\"delta\":{\"content\":\"IL_1] today\"}}]}\n\n",
+            "data: {\"choices\":[{\"index\":1,\"delta\":{\"content\":\"NE_1] now [\"}}]}\n\n",
+            "data: {\"choices\":[{\"index\":1,\"delta\":{},\"finish_reason\":\"stop\"}]}\n\n",
+            "data: [DONE]\n\n",
+        ])
+        .await;
+
+        assert_eq!(
+            choice_contents(&restored),
+            vec![
+                "Sent to jane@example.com today".to_string(),
+                "Call 13812345678 now [".to_string()
+            ]
+        );
+        assert!(!restored.contains("[EMAIL_1]") && !restored.contains("[PHONE_1]"));
+        // The held back bracket is sent before the finish chunk
+        let bracket = restored.find(r#""content":"[""#).unwrap();
+        assert!(bracket < restored.find("finish_reason").unwrap());
+        assert!(restored.ends_with("data: [DONE]\n\n"));
+    }
+
+    #[tokio::test]
+    async fn test_restores_placeholder_split_across_claude_events() {
+        let restored = restored_stream(&[
+            "event: content_block_delta\ndata: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\"Mail [EM\"}}\n\n",
+            "event: content_block_delta\ndata: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\"AIL_1]\"}}\n\n",
+            "event: content_block_stop\ndata: {\"type\":\"content_block_stop\",\"index\":0}\n\n",
+        ])
+        .await;
+
+        let events: Vec<Value> = restored
+            .lines()
+            .filter_map(|line| line.strip_prefix("data: "))
+            .map(|data| serde_json::from_str(data).unwrap())
+            .collect();
+        let texts: Vec<&str> = events
+            .iter()
+            .filter_map(|event| event["delta"]["text"].as_str())
+            .collect();
+        assert_eq!(texts, vec!["Mail ", "jane@example.com"]);
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[2]["type"], "content_block_stop");
+        assert_eq!(restored.matches("event: content_block_delta\n").count(), 2);
+    }
+}
@@ -1,4 +1,5 @@
 use crate::ccproxy::errors::{CCProxyError, ProxyResult};
+use crate::libs::retry::is_retryable_error;
 use reqwest::{RequestBuilder, Response};
 use rust_i18n::t;
 use std::error::Error;
@@ -48,6 +49,9 @@ impl RetryConfig {
 }
 
 /// Send request with exponential backoff retry support for 429 status code
+///
+/// Transport errors are retried as well when `is_retryable_error` considers them transient.
+/// Other error statuses are returned right away, a 5xx is left to the model fallback.
 pub async fn send_with_retry(
     request_builder: RequestBuilder,
     retry_config: &RetryConfig,
@@ -116,6 +120,9 @@ pub async fn send_with_retry(
             Err(error) => {
                 let error_msg = format_backend_request_error(&error);
                 log_backend_request_error(Some(attempt), &error);
+                if !is_retryable_error(&error) {
+                    return Err(CCProxyError::BackendRequestError(error_msg));
+                }
                 last_error = Some(error_msg);
 
                 // If not the last attempt, wait and retry
@@ -245,4 +252,39 @@ mod tests {
         assert_eq!(message.matches("Request to backend failed:").count(), 1);
         assert!(message.contains("error sending request for url"));
     }
+
+    #[tokio::test]
+    async fn test_only_rate_limits_are_retried() {
+        use crate::test::spawn_mock_backend;
+        use axum::{extract::State, http::StatusCode, routing::get, Router};
+        use std::sync::atomic::{AtomicU32, Ordering};
+        use std::sync::Arc;
+
+        // Fails with `status` on the first request, then succeeds
+        async fn flaky(State((hits, status)): State<(Arc<AtomicU32>, u16)>) -> StatusCode {
+            if hits.fetch_add(1, Ordering::SeqCst) == 0 {
+                StatusCode::from_u16(status).expect("status")
+            } else {
+                StatusCode::OK
+            }
+        }
+
+        let config = RetryConfig {
+            max_retries: 2,
+            initial_backoff_ms: 10,
+            ..Default::default()
+        };
+        for (status, expected, hits_expected) in [(429, 200, 2), (503, 503, 1), (401, 401, 1)] {
+            let hits = Arc::new(AtomicU32::new(0));
+            let app = Router::new()
+                .route("/", get(flaky))
+                .with_state((hits.clone(), status));
+            let base_url = spawn_mock_backend(app).await;
+
+            let request = reqwest::Client::new().get(format!("{}/", base_url));
+            let response = send_with_retry(request, &config).await.expect("response");
+            assert_eq!(response.status().as_u16(), expected);
+            assert_eq!(hits.load(Ordering::SeqCst), hits_expected);
+        }
+    }
 }
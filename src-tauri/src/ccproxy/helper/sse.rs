@@ -93,11 +93,64 @@ impl fmt::Display for Event {
     }
 }
 
+/// Reassembles the lines of an SSE or NDJSON stream from network chunks.
+///
+/// Bytes are held back until their line is complete, so neither a line nor a multi-byte
+/// UTF-8 character split across two chunks is decoded in pieces.
+#[derive(Debug, Default)]
+pub struct SseLineBuffer {
+    pending: Vec<u8>,
+}
+
+impl SseLineBuffer {
+    /// Adds a network chunk and returns the lines it completed, without line endings.
+    ///
+    /// Blank lines are returned too, they end an SSE event.
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<String> {
+        self.pending.extend_from_slice(chunk);
+        let Some(last_newline) = memchr::memrchr(b'\n', &self.pending) else {
+            return Vec::new();
+        };
+        let rest = self.pending.split_off(last_newline + 1);
+        let complete = std::mem::replace(&mut self.pending, rest);
+        String::from_utf8_lossy(&complete)
+            .lines()
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// Returns the last line of a stream that did not end with a line break.
+    pub fn finish(&mut self) -> Option<String> {
+        let rest = std::mem::take(&mut self.pending);
+        let line = String::from_utf8_lossy(&rest)
+            .trim_end_matches('\r')
+            .to_string();
+        (!line.is_empty()).then_some(line)
+    }
+}
+
 // --- Unit Tests ---
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_line_buffer_joins_characters_split_across_chunks() {
+        let event = "data: {\"content\":\"你好\"}\r\n\r\ndata: [DONE]";
+        let bytes = event.as_bytes();
+        // "你" is three bytes, the first chunk ends after its first byte
+        let split = event.find('你').expect("character") + 1;
+
+        let mut buffer = SseLineBuffer::default();
+        assert!(buffer.push(&bytes[..split]).is_empty());
+        assert_eq!(
+            buffer.push(&bytes[split..]),
+            vec!["data: {\"content\":\"你好\"}".to_string(), String::new()]
+        );
+        assert_eq!(buffer.finish().as_deref(), Some("data: [DONE]"));
+        assert_eq!(buffer.finish(), None);
+    }
+
     #[test]
     fn test_simple_data_event_has_space() {
         let event = Event::default().data("test").to_string();
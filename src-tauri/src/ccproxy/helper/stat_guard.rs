@@ -1,4 +1,5 @@
 use crate::ccproxy::adapter::unified::{SseStatus, StreamLogRecorder};
+use crate::ccproxy::helper::budget;
 use crate::db::{CcproxyStat, MainStore};
 use std::sync::{Arc, Mutex, RwLock};
 
@@ -63,21 +64,24 @@ impl Drop for StreamStatGuard {
                     cache
                 );
 
-                let _ = store.record_ccproxy_stat(CcproxyStat {
-                    id: None,
-                    client_model: self.client_model.clone(),
-                    backend_model: self.backend_model.clone(),
-                    provider_id: Some(self.provider_id),
-                    provider: self.provider.clone(),
-                    protocol: self.protocol.clone(),
-                    tool_compat_mode: if self.tool_compat_mode { 1 } else { 0 },
-                    status_code: 200,
-                    error_message: None,
-                    input_tokens: final_input as i64,
-                    output_tokens: final_output as i64,
-                    cache_tokens: cache as i64,
-                    request_at: None,
-                });
+                budget::record_stat(
+                    &store,
+                    CcproxyStat {
+                        id: None,
+                        client_model: self.client_model.clone(),
+                        backend_model: self.backend_model.clone(),
+                        provider_id: Some(self.provider_id),
+                        provider: self.provider.clone(),
+                        protocol: self.protocol.clone(),
+                        tool_compat_mode: if self.tool_compat_mode { 1 } else { 0 },
+                        status_code: 200,
+                        error_message: None,
+                        input_tokens: final_input as i64,
+                        output_tokens: final_output as i64,
+                        cache_tokens: cache as i64,
+                        request_at: None,
+                    },
+                );
             }
         }
     }
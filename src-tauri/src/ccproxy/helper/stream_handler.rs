@@ -9,14 +9,32 @@ use crate::ccproxy::{
     ChatProtocol, StreamFormat, StreamProcessor,
 };
 
+use crate::constants::{
+    CFG_CCPROXY_STREAM_STALL_TIMEOUT, CFG_CCPROXY_STREAM_STALL_TIMEOUT_DEFAULT,
+};
 use crate::db::MainStore;
 use axum::body::Body;
 use axum::response::Response;
 use futures_util::{stream::iter, StreamExt};
 use http::StatusCode;
 use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
 use tokio_stream::wrappers::ReceiverStream;
 
+/// How long a proxied stream may go without upstream data, from
+/// `chat_completion_proxy_stream_stall_timeout`.
+pub fn stream_stall_timeout(main_store_arc: &Arc<RwLock<MainStore>>) -> Duration {
+    let seconds = if let Ok(store) = main_store_arc.read() {
+        store.get_config(
+            CFG_CCPROXY_STREAM_STALL_TIMEOUT,
+            CFG_CCPROXY_STREAM_STALL_TIMEOUT_DEFAULT,
+        )
+    } else {
+        CFG_CCPROXY_STREAM_STALL_TIMEOUT_DEFAULT
+    };
+    Duration::from_secs(seconds)
+}
+
 pub async fn handle_streamed_response(
     backend_protocol: Arc<ChatProtocol>,
     client_protocol: ChatProtocol,
@@ -42,6 +60,7 @@ pub async fn handle_streamed_response(
     let response_headers_from_target = target_response.headers().clone();
 
     let reassembled_receiver = StreamProcessor::new()
+        .with_stall_timeout(stream_stall_timeout(&main_store_arc))
         .process_stream(target_response, &stream_format)
         .await;
 
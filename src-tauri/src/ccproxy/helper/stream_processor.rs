@@ -1,29 +1,46 @@
-use bytes::BytesMut;
+use bytes::{Bytes, BytesMut};
+use futures_util::Stream;
 use reqwest::Response;
+use rust_i18n::t;
 use std::sync::{
     atomic::{AtomicBool, Ordering},
     Arc,
 };
 use tokio::sync::mpsc;
+use tokio::time::{error::Elapsed, timeout, Duration};
 
 use crate::ccproxy::types::StreamFormat;
 
 /// A processor for handling Server-Sent Events (SSE) streams
 ///
 /// The processor maintains an internal buffer to accumulate incoming chunks and
-/// splits them into complete SSE events delimited by double newlines (`\n\n`).
+/// splits them into complete SSE events delimited by double newlines (`\n\n` or `\r\n\r\n`).
+/// Bytes are only split at a delimiter, so an event or a multi-byte UTF-8 character that
+/// arrives in several network chunks is always handed on in one piece.
+///
+/// With a stall timeout set, a stream that delivers no bytes for that long is closed with an
+/// error instead of hanging the client. Keep-alive events of the upstream (SSE comments, `ping`
+/// events) are bytes too, so a slow but alive stream keeps resetting the timer.
 #[derive(Clone)]
 pub struct StreamProcessor {
     stop_flag: Arc<AtomicBool>,
+    stall_timeout: Option<Duration>,
 }
 
 impl StreamProcessor {
     pub fn new() -> Self {
         Self {
             stop_flag: Arc::new(AtomicBool::new(false)),
+            stall_timeout: None,
         }
     }
 
+    /// Closes the stream with an error after `stall_timeout` without upstream data.
+    pub fn with_stall_timeout(mut self, stall_timeout: Duration) -> Self {
+        self.stall_timeout = Some(stall_timeout).filter(|limit| !limit.is_zero());
+        self
+    }
+
     /// stops the stream processor
     pub fn stop(&self) {
         self.stop_flag.store(true, Ordering::Relaxed);
@@ -41,8 +58,10 @@ impl StreamProcessor {
     ///
     /// # Behavior
     /// - Maintains an 8KB internal buffer for accumulating partial events
-    /// - Splits the stream on `\n\n` boundaries per SSE specification
-    /// - Automatically stops when the response ends or receiver is dropped
+    /// - Splits the stream on `\n\n` or `\r\n\r\n` boundaries per SSE specification
+    /// - Automatically stops when the response ends or receiver is dropped; a dropped receiver
+    ///   (the client disconnected) also cancels the upstream request while it is idle
+    /// - Sends an `Err` and stops when the stall timeout passes without data
     ///
     /// # Example
     /// ```no_run
@@ -63,18 +82,41 @@ impl StreamProcessor {
     ) -> mpsc::Receiver<Result<bytes::Bytes, String>> {
         let (tx, rx) = mpsc::channel(32);
         let stop_flag = self.stop_flag.clone();
+        let stall_timeout = self.stall_timeout;
 
-        let deliv = match format {
-            StreamFormat::Gemini => b"\r\n",
-            _ => b"\n\n",
+        let delimiters: &'static [&'static [u8]] = match format {
+            StreamFormat::Gemini => &[b"\r\n"],
+            _ => &[b"\n\n", b"\r\n\r\n"],
         };
-        let deliv_len = deliv.len();
 
         tokio::spawn(async move {
             let mut buffer = BytesMut::with_capacity(8192);
 
             while !stop_flag.load(Ordering::Relaxed) {
-                match response.chunk().await {
+                let read_chunk = read_chunk(&mut response, stall_timeout);
+                // Stop reading as soon as the client is gone, even while the upstream is silent,
+                // e.g. during a long reasoning phase. Returning drops `response`, which aborts the
+                // upstream request instead of letting the model generate tokens nobody reads.
+                let read = tokio::select! {
+                    _ = tx.closed() => None,
+                    read = read_chunk => Some(read),
+                };
+                let Some(read) = read else {
+                    log::info!(
+                        "StreamProcessor: client disconnected, cancelling the upstream stream"
+                    );
+                    return Ok::<(), String>(());
+                };
+                let next_chunk = match read {
+                    Ok(next_chunk) => next_chunk,
+                    Err(_) => {
+                        if let Some(limit) = stall_timeout {
+                            let _ = tx.send(Err(stalled_error(limit, buffer.len()))).await;
+                        }
+                        break;
+                    }
+                };
+                match next_chunk {
                     Ok(Some(chunk)) => {
                         buffer.extend_from_slice(&chunk);
 
@@ -83,8 +125,15 @@ impl StreamProcessor {
                                 return Ok::<(), String>(());
                             }
 
-                            match memchr::memmem::find(&buffer, deliv) {
-                                Some(pos) => {
+                            let next_delimiter = delimiters
+                                .iter()
+                                .filter_map(|deliv| {
+                                    memchr::memmem::find(&buffer, deliv)
+                                        .map(|pos| (pos, deliv.len()))
+                                })
+                                .min_by_key(|(pos, _)| *pos);
+                            match next_delimiter {
+                                Some((pos, deliv_len)) => {
                                     let end = pos + deliv_len;
                                     if end > buffer.len() {
                                         break;
@@ -120,3 +169,167 @@ impl StreamProcessor {
         rx
     }
 }
+
+/// Reads the next chunk of `response`, `Err` when `stall_timeout` passes without data.
+async fn read_chunk(
+    response: &mut Response,
+    stall_timeout: Option<Duration>,
+) -> Result<reqwest::Result<Option<Bytes>>, Elapsed> {
+    match stall_timeout {
+        Some(limit) => timeout(limit, response.chunk()).await,
+        None => Ok(response.chunk().await),
+    }
+}
+
+fn stalled_error(limit: Duration, buffered: usize) -> String {
+    log::warn!(
+        "StreamProcessor: no upstream data for {}s, closing the stalled stream ({} bytes buffered)",
+        limit.as_secs(),
+        buffered
+    );
+    t!("proxy.error.stream_stalled", seconds = limit.as_secs()).to_string()
+}
+
+/// Passes the body of `response` on chunk by chunk as it arrives, without splitting it into
+/// events, and closes it with an error after `stall_timeout` without upstream data, like
+/// `StreamProcessor` does. A zero timeout waits forever.
+pub fn stream_with_stall_timeout(
+    response: Response,
+    stall_timeout: Duration,
+) -> impl Stream<Item = Result<Bytes, String>> {
+    let stall_timeout = Some(stall_timeout).filter(|limit| !limit.is_zero());
+    futures_util::stream::unfold(Some(response), move |response| async move {
+        let mut response = response?;
+        match read_chunk(&mut response, stall_timeout).await {
+            Ok(Ok(Some(chunk))) => Some((Ok(chunk), Some(response))),
+            Ok(Ok(None)) => None,
+            Ok(Err(e)) => Some((Err(e.to_string()), None)),
+            Err(_) => {
+                let limit = stall_timeout.unwrap_or_default();
+                Some((Err(stalled_error(limit, 0)), None))
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::{stream, StreamExt};
+
+    /// A response that sends `events` and then stalls without closing the connection.
+    fn stalling_response(events: &[&'static str]) -> Response {
+        let chunks = events
+            .iter()
+            .map(|event| Ok::<_, std::io::Error>(bytes::Bytes::from_static(event.as_bytes())))
+            .collect::<Vec<_>>();
+        let body = reqwest::Body::wrap_stream(stream::iter(chunks).chain(stream::pending()));
+        Response::from(http::Response::new(body))
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_stalled_stream_ends_with_error() {
+        let processor = StreamProcessor::new().with_stall_timeout(Duration::from_secs(30));
+        let mut receiver = processor
+            .process_stream(
+                stalling_response(&["data: {\"a\":1}\n\n", ": keep-alive\n\n"]),
+                &StreamFormat::OpenAI,
+            )
+            .await;
+
+        assert_eq!(
+            receiver.recv().await,
+            Some(Ok(bytes::Bytes::from_static(b"data: {\"a\":1}\n\n")))
+        );
+        assert_eq!(
+            receiver.recv().await,
+            Some(Ok(bytes::Bytes::from_static(b": keep-alive\n\n")))
+        );
+        let stalled = receiver.recv().await;
+        assert!(matches!(stalled, Some(Err(message)) if message.contains("30")));
+        assert_eq!(receiver.recv().await, None);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_stalled_passthrough_stream_ends_with_error() {
+        let stream = stream_with_stall_timeout(
+            stalling_response(&["data: {\"a\":1}\n", "\n"]),
+            Duration::from_secs(30),
+        );
+        let items: Vec<_> = stream.collect().await;
+
+        assert_eq!(items.len(), 3);
+        assert_eq!(items[0], Ok(Bytes::from_static(b"data: {\"a\":1}\n")));
+        assert_eq!(items[1], Ok(Bytes::from_static(b"\n")));
+        assert!(matches!(&items[2], Err(message) if message.contains("30")));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_stream_without_stall_timeout_keeps_waiting() {
+        let mut receiver = StreamProcessor::new()
+            .process_stream(stalling_response(&["data: 1\n\n"]), &StreamFormat::OpenAI)
+            .await;
+
+        assert!(matches!(receiver.recv().await, Some(Ok(_))));
+        let waited = timeout(Duration::from_secs(3600), receiver.recv()).await;
+        assert!(
+            waited.is_err(),
+            "the stream must stay open without a stall timeout"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_client_drop_cancels_idle_upstream() {
+        // The sender lives inside the upstream body stream, so it is dropped with the request
+        let (cancelled_tx, cancelled_rx) = tokio::sync::oneshot::channel::<()>();
+        let chunks = vec![Ok::<_, std::io::Error>(bytes::Bytes::from_static(
+            b"data: 1\n\n",
+        ))];
+        let body = stream::iter(chunks)
+            .chain(stream::pending())
+            .map(move |chunk| {
+                let _ = &cancelled_tx;
+                chunk
+            });
+        let response = Response::from(http::Response::new(reqwest::Body::wrap_stream(body)));
+
+        let mut receiver = StreamProcessor::new()
+            .process_stream(response, &StreamFormat::OpenAI)
+            .await;
+        assert!(matches!(receiver.recv().await, Some(Ok(_))));
+
+        // The client goes away while the upstream has nothing more to send
+        drop(receiver);
+
+        let cancelled = timeout(Duration::from_secs(5), cancelled_rx).await;
+        assert!(
+            matches!(cancelled, Ok(Err(_))),
+            "the upstream stream must be dropped after the client disconnects"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_character_split_across_chunks_is_decoded_whole() {
+        let event = "data: {\"content\":\"你好\"}\r\n\r\n";
+        // "你" is three bytes, the first chunk ends after its first byte
+        let split = event.find('你').expect("character") + 1;
+        let chunks = vec![
+            Ok::<_, std::io::Error>(bytes::Bytes::copy_from_slice(&event.as_bytes()[..split])),
+            Ok(bytes::Bytes::copy_from_slice(&event.as_bytes()[split..])),
+            Ok(bytes::Bytes::from_static(b"data: [DONE]\r\n\r\n")),
+        ];
+        let response = Response::from(http::Response::new(reqwest::Body::wrap_stream(
+            stream::iter(chunks),
+        )));
+
+        let mut receiver = StreamProcessor::new()
+            .process_stream(response, &StreamFormat::OpenAI)
+            .await;
+
+        let first = receiver.recv().await.expect("event").expect("no error");
+        assert_eq!(std::str::from_utf8(&first), Ok(event));
+        let second = receiver.recv().await.expect("event").expect("no error");
+        assert_eq!(&second[..], b"data: [DONE]\r\n\r\n");
+        assert_eq!(receiver.recv().await, None);
+    }
+}
@@ -56,7 +56,138 @@ use scraper::{ElementRef, Html, Selector};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Map, Value};
 
-use crate::ccproxy::{adapter::unified::UnifiedContentBlock, helper::get_tool_id};
+use crate::ccproxy::{
+    adapter::unified::UnifiedContentBlock,
+    helper::{code_reflow::reflow_single_line_code, get_tool_id},
+};
+use crate::tools::helper::{is_file_tool, normalize_file_tool_arguments};
+
+/// Arguments naming the file a file tool writes, its extension picks the reflow language.
+const FILE_PATH_KEYS: &[&str] = &["file_path", "path", "filePath", "target_file", "file"];
+/// Arguments holding content written to the file; `old_string` must match the file as it is.
+const WRITTEN_CONTENT_KEYS: &[&str] = &[
+    "content",
+    "file_text",
+    "new_content",
+    "new_string",
+    "new_str",
+    "code",
+];
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_reflow_min_chars() -> usize {
+    200
+}
+
+/// Reflow of file content a compat mode tool call wrote on a single line, stored under
+/// `chat_completion_proxy_compat_code_reflow`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompatCodeReflowConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Single-line content is reflowed when it has more characters than this
+    #[serde(default = "default_reflow_min_chars")]
+    pub min_chars: usize,
+}
+
+impl Default for CompatCodeReflowConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            min_chars: default_reflow_min_chars(),
+        }
+    }
+}
+
+fn default_tool_prompt_token_budget() -> usize {
+    4000
+}
+
+/// How the compat mode system prompt describes the tools.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CompatToolPromptMode {
+    /// Every tool with its full argument schema
+    Full,
+    /// Name, one line of description and the required arguments of every tool
+    Compact,
+    /// Full definitions while they fit the token budget, compact ones otherwise
+    #[default]
+    Auto,
+}
+
+/// Tool definitions of the compat mode system prompt, stored under
+/// `chat_completion_proxy_compat_tool_prompt`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct CompatToolPromptConfig {
+    #[serde(default)]
+    pub mode: CompatToolPromptMode,
+    /// Estimated tokens the full definitions may take in `auto` mode
+    #[serde(default = "default_tool_prompt_token_budget")]
+    pub token_budget: usize,
+}
+
+impl Default for CompatToolPromptConfig {
+    fn default() -> Self {
+        Self {
+            mode: CompatToolPromptMode::default(),
+            token_budget: default_tool_prompt_token_budget(),
+        }
+    }
+}
+
+/// Breaks file content a file tool call wrote on a single line back into lines.
+///
+/// Only code files are touched, the language comes from the extension of the file path
+/// argument. Returns whether any argument was changed.
+pub fn reflow_file_tool_arguments(
+    tool_name: &str,
+    args: &mut Value,
+    config: &CompatCodeReflowConfig,
+) -> bool {
+    if !config.enabled || !is_file_tool(tool_name) {
+        return false;
+    }
+    let Some(extension) = FILE_PATH_KEYS
+        .iter()
+        .find_map(|key| args.get(*key)?.as_str())
+        .and_then(|path| std::path::Path::new(path).extension()?.to_str())
+        .map(str::to_string)
+    else {
+        return false;
+    };
+    reflow_written_content(args, &extension, config.min_chars)
+}
+
+fn reflow_written_content(value: &mut Value, extension: &str, min_chars: usize) -> bool {
+    match value {
+        Value::Object(map) => map.iter_mut().fold(false, |changed, (key, value)| {
+            let reflowed = match value {
+                Value::String(text) if WRITTEN_CONTENT_KEYS.contains(&key.as_str()) => {
+                    match reflow_single_line_code(text, extension, min_chars) {
+                        Some(reflowed) => {
+                            *text = reflowed;
+                            true
+                        }
+                        None => false,
+                    }
+                }
+                Value::String(_) => false,
+                other => reflow_written_content(other, extension, min_chars),
+            };
+            changed | reflowed
+        }),
+        Value::Array(items) => items.iter_mut().fold(false, |changed, item| {
+            reflow_written_content(item, extension, min_chars) | changed
+        }),
+        _ => false,
+    }
+}
 
 #[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
 pub enum ParamType {
@@ -109,22 +240,46 @@ impl TryFrom<String> for ToolUse {
     }
 }
 
-/// Convert a ToolUse into a UnifiedContentBlock::ToolUse.
-impl From<ToolUse> for UnifiedContentBlock {
-    fn from(tool_use: ToolUse) -> UnifiedContentBlock {
+impl ToolUse {
+    /// Arguments of the call as a JSON object, with the content of file tools repaired.
+    pub fn input(&self, code_reflow: &CompatCodeReflowConfig) -> Value {
         let mut arguments = serde_json::Map::new();
-        for arg in tool_use.args {
+        for arg in &self.args {
             arguments.insert(arg.name.clone(), arg.get_value());
         }
+        let mut input = serde_json::Value::Object(arguments);
+        if normalize_file_tool_arguments(&self.name, &mut input) {
+            log::debug!(
+                "Restored escaped line breaks in arguments of compat tool call {}",
+                &self.name
+            );
+        }
+        if reflow_file_tool_arguments(&self.name, &mut input, code_reflow) {
+            log::info!(
+                "Reflowed single-line file content in arguments of compat tool call {}",
+                &self.name
+            );
+        }
+        input
+    }
 
+    /// Converts the call into a `UnifiedContentBlock::ToolUse` with a new id.
+    pub fn into_content_block(self, code_reflow: &CompatCodeReflowConfig) -> UnifiedContentBlock {
         UnifiedContentBlock::ToolUse {
             id: get_tool_id(),
-            name: tool_use.name,
-            input: serde_json::Value::Object(arguments),
+            input: self.input(code_reflow),
+            name: self.name,
         }
     }
 }
 
+/// Convert a ToolUse into a UnifiedContentBlock::ToolUse.
+impl From<ToolUse> for UnifiedContentBlock {
+    fn from(tool_use: ToolUse) -> UnifiedContentBlock {
+        tool_use.into_content_block(&CompatCodeReflowConfig::default())
+    }
+}
+
 #[derive(Deserialize, Serialize, Debug)]
 pub struct Arg {
     pub name: String,
@@ -192,81 +347,137 @@ impl Arg {
 pub fn generate_tools_xml(tools: &Vec<crate::ccproxy::adapter::unified::UnifiedTool>) -> String {
     let mut tools_xml = String::new();
     tools_xml.push_str("<cs:tools desc=\"All available tools\">\n");
+    for tool in tools {
+        append_tool_define(&mut tools_xml, tool);
+    }
+    tools_xml.push_str("</cs:tools>");
+    tools_xml
+}
 
+/// Generate compact XML for tool definitions: the name, the first line of the description
+/// and the required arguments. Tools in `detailed` keep their full definition, compat mode
+/// passes the tools the model already called so their optional arguments are known.
+pub fn generate_compact_tools_xml(
+    tools: &[crate::ccproxy::adapter::unified::UnifiedTool],
+    detailed: &std::collections::HashSet<String>,
+) -> String {
+    let mut tools_xml = String::new();
+    tools_xml.push_str(
+        "<cs:tools desc=\"All available tools. Tools with <required> list only their required args, their full definition is given after you call them\">\n",
+    );
     for tool in tools {
+        if detailed.contains(&tool.name) {
+            append_tool_define(&mut tools_xml, tool);
+            continue;
+        }
+        let desc = tool
+            .description
+            .as_deref()
+            .and_then(|desc| desc.lines().map(str::trim).find(|line| !line.is_empty()))
+            .unwrap_or("");
+        let properties = tool.input_schema.get("properties");
+        let required_args: Vec<String> = tool
+            .input_schema
+            .get("required")
+            .and_then(|r| r.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|v| v.as_str())
+            .map(|name| {
+                let param_type = properties
+                    .and_then(|p| p.get(name))
+                    .and_then(|details| details.get("type"))
+                    .and_then(|t| t.as_str())
+                    .unwrap_or("any");
+                format!("{}:{}", name, param_type)
+            })
+            .collect();
         tools_xml.push_str(&format!(
-            "<cs:tool_define>\n<name>{}</name>\n<desc>{}</desc>\n",
+            "<cs:tool_define>\n<name>{}</name>\n<desc>{}</desc>\n<required>{}</required>\n</cs:tool_define>\n",
             tool.name,
-            tool.description.as_deref().unwrap_or("")
+            desc,
+            required_args.join(", ")
         ));
+    }
+    tools_xml.push_str("</cs:tools>");
+    tools_xml
+}
 
-        if let Some(schema) = tool.input_schema.as_object() {
-            // Get the list of required parameters from the JSON schema
-            let required_args: std::collections::HashSet<String> = schema
-                .get("required")
-                .and_then(|r| r.as_array())
-                .map(|arr| {
-                    arr.iter()
-                        .filter_map(|v| v.as_str().map(String::from))
-                        .collect()
-                })
-                .unwrap_or_default();
-
-            if let Some(properties) = schema.get("properties").and_then(|p| p.as_object()) {
-                tools_xml.push_str("<args>\n");
-
-                let mut required_keys: Vec<&String> = Vec::new();
-                let mut optional_keys: Vec<&String> = Vec::new();
-
-                for name in properties.keys() {
-                    if required_args.contains(name) {
-                        required_keys.push(name);
-                    } else {
-                        optional_keys.push(name);
-                    }
+/// Appends the full definition of a tool, with every argument of its schema.
+fn append_tool_define(
+    tools_xml: &mut String,
+    tool: &crate::ccproxy::adapter::unified::UnifiedTool,
+) {
+    tools_xml.push_str(&format!(
+        "<cs:tool_define>\n<name>{}</name>\n<desc>{}</desc>\n",
+        tool.name,
+        tool.description.as_deref().unwrap_or("")
+    ));
+
+    if let Some(schema) = tool.input_schema.as_object() {
+        // Get the list of required parameters from the JSON schema
+        let required_args: std::collections::HashSet<String> = schema
+            .get("required")
+            .and_then(|r| r.as_array())
+            .map(|arr| {
+                arr.iter()
+                    .filter_map(|v| v.as_str().map(String::from))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        if let Some(properties) = schema.get("properties").and_then(|p| p.as_object()) {
+            tools_xml.push_str("<args>\n");
+
+            let mut required_keys: Vec<&String> = Vec::new();
+            let mut optional_keys: Vec<&String> = Vec::new();
+
+            for name in properties.keys() {
+                if required_args.contains(name) {
+                    required_keys.push(name);
+                } else {
+                    optional_keys.push(name);
                 }
+            }
 
-                required_keys.sort();
-                optional_keys.sort();
-
-                let mut append_param = |name: &String| {
-                    let details = &properties[name];
-                    let param_type = details
-                        .get("type")
-                        .and_then(|t| t.as_str())
-                        .unwrap_or("any");
-                    let mut description = details
-                        .get("description")
-                        .and_then(|d| d.as_str())
-                        .unwrap_or("")
-                        .to_string();
-
-                    if required_args.contains(name) {
-                        description.push_str(" (required)");
-                    } else {
-                        description.push_str(" (optional)");
-                    }
-
-                    tools_xml.push_str(&format!(
-                        "<arg name=\"{}\" type=\"{}\">{}</arg>\n",
-                        name, param_type, description
-                    ));
-                };
-
-                for name in required_keys {
-                    append_param(name);
-                }
-                for name in optional_keys {
-                    append_param(name);
+            required_keys.sort();
+            optional_keys.sort();
+
+            let mut append_param = |name: &String| {
+                let details = &properties[name];
+                let param_type = details
+                    .get("type")
+                    .and_then(|t| t.as_str())
+                    .unwrap_or("any");
+                let mut description = details
+                    .get("description")
+                    .and_then(|d| d.as_str())
+                    .unwrap_or("")
+                    .to_string();
+
+                if required_args.contains(name) {
+                    description.push_str(" (required)");
+                } else {
+                    description.push_str(" (optional)");
                 }
 
-                tools_xml.push_str("</args>\n");
+                tools_xml.push_str(&format!(
+                    "<arg name=\"{}\" type=\"{}\">{}</arg>\n",
+                    name, param_type, description
+                ));
+            };
+
+            for name in required_keys {
+                append_param(name);
             }
+            for name in optional_keys {
+                append_param(name);
+            }
+
+            tools_xml.push_str("</args>\n");
         }
-        tools_xml.push_str("</cs:tool_define>\n");
     }
-    tools_xml.push_str("</cs:tools>");
-    tools_xml
+    tools_xml.push_str("</cs:tool_define>\n");
 }
 
 pub fn format_tool_use_xml(id: &str, name: &str, input: &serde_json::Value) -> String {
@@ -1522,4 +1733,24 @@ line</arg>
             "JSON quotes should be preserved"
         );
     }
+
+    #[test]
+    fn test_compat_file_content_escaped_newlines_are_restored() {
+        let xml_input = r#"<cs:tool_use>
+            <name>write_file</name>
+            <args>
+                <arg name="file_path" type="string">src/lib.rs</arg>
+                <arg name="content" type="string">pub fn greet() {\n    println!("hi\n");\n}</arg>
+            </args>
+        </cs:tool_use>"#;
+
+        let block = UnifiedContentBlock::from(parse_tool_use(xml_input).unwrap());
+        let UnifiedContentBlock::ToolUse { input, .. } = block else {
+            panic!("expected a tool use block");
+        };
+        assert_eq!(
+            input["content"],
+            "pub fn greet() {\n    println!(\"hi\\n\");\n}"
+        );
+    }
 }
@@ -8,14 +8,21 @@ mod errors;
 mod handler;
 mod helper;
 mod router;
+#[cfg(test)]
+mod test_util;
 mod types;
 pub mod utils;
 
+pub(crate) use auth::authenticate_request;
 pub use errors::CCProxyError;
 pub use handler::{
-    handle_chat_completion, handle_embedding, handle_list_models, handle_ollama_tags,
-    handle_responses,
+    apply_setup_preset, benchmark_models, get_group_warmup, handle_chat_completion,
+    handle_completions, handle_count_tokens, handle_embedding, handle_list_models,
+    handle_ollama_tags, handle_responses, replay_dead_letter, setup_presets, warm_up_enabled_group,
+    warm_up_group, BenchmarkReport, BenchmarkTarget, GroupWarmup, ReplayResult, ReplayTarget,
+    SetupPreset, SetupWizardResult, CCPROXY_WARMUP_EVENT,
 };
-pub use helper::{get_tool_id, StreamProcessor};
+pub(crate) use helper::CcproxyQuery;
+pub use helper::{get_tool_id, KeyHealth, StreamProcessor, CC_PROXY_ROTATOR};
 pub use router::routes;
 pub use types::{claude, gemini, openai, ChatCompletionProxyConfig, ChatProtocol, StreamFormat};
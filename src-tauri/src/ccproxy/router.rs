@@ -40,10 +40,12 @@
 //! - `GET /v1/models`: Lists available models.
 //! - `POST /v1/chat/completions`: Creates a chat completion.
 //! - `POST /v1/responses`: Creates an OpenAI Responses-compatible non-streaming response.
+//! - `POST /v1/completions`: Creates a legacy text completion, served by the chat pipeline.
 //! - `POST /v1/embeddings`: Creates an embedding vector.
 //!
 //! ### Claude-Compatible Endpoints
 //! - `POST /v1/messages`: Creates a message with the Claude model.
+//! - `POST /v1/messages/count_tokens`: Estimates the input tokens of a message request locally.
 //! - `POST /v1/claude/embeddings`: Creates an embedding vector.
 //!   *(Note: Claude natively does not support embeddings; this endpoint is provided for protocol consistency).*
 //!
@@ -62,6 +64,8 @@
 //! These routes are integrated into this router for unified access but handled by separate modules:
 //! - **MCP (Model Context Protocol)**:
 //!   - `POST /mcp/http`: Streamable HTTP entry (Recommended).
+//!   - `GET /mcp/ws`: WebSocket entry, only mounted when `mcp_ws_enabled` is set.
+//!   - Both require a ccproxy access key when `mcp_server_auth` is enabled.
 //!
 //! # Internal Architecture
 //!
@@ -91,12 +95,12 @@ use crate::ccproxy::errors::CCProxyError;
 use crate::ccproxy::ChatProtocol;
 use crate::ccproxy::{
     auth::authenticate_request,
-    handle_chat_completion, handle_embedding, handle_list_models, handle_ollama_tags,
-    handle_responses,
+    handle_chat_completion, handle_completions, handle_count_tokens, handle_embedding,
+    handle_list_models, handle_ollama_tags, handle_responses,
     handler::{handle_gemini_list_models, handle_ollama_show, ollama_extra_handler::ShowRequest},
-    helper::CcproxyQuery,
+    helper::{budget::CCPROXY_BUDGET, CcproxyQuery},
 };
-use crate::constants::CFG_ACTIVE_PROXY_GROUP;
+use crate::constants::{CFG_ACTIVE_PROXY_GROUP, CFG_MCP_WS_ENABLED};
 use crate::db::MainStore;
 
 use axum::{
@@ -129,6 +133,15 @@ pub struct SharedState {
 // These handlers contain the actual business logic. They are called by the
 // axum handlers below and are agnostic of the routing layer.
 
+/// Whether the optional WebSocket transport of the MCP server is enabled in settings.
+fn mcp_ws_enabled(state: &Arc<SharedState>) -> bool {
+    state
+        .main_store
+        .read()
+        .map(|store| store.get_config(CFG_MCP_WS_ENABLED, false))
+        .unwrap_or(false)
+}
+
 /// Resolves the group name for a request. If the prefix is 'switch', it reads the active group from settings.
 fn resolve_group_name(state: &Arc<SharedState>, group_name: Option<String>) -> Option<String> {
     if let Some(g) = group_name {
@@ -193,6 +206,28 @@ async fn openai_responses_logic(
     .into_response())
 }
 
+async fn openai_completions_logic(
+    state: Arc<SharedState>,
+    query: CcproxyQuery,
+    headers: HeaderMap,
+    body: Bytes,
+    group_name: Option<String>,
+    compat_mode: bool,
+) -> Result<Response, CCProxyError> {
+    let final_group = resolve_group_name(&state, group_name);
+
+    Ok(handle_completions(
+        headers,
+        query,
+        body,
+        final_group,
+        compat_mode,
+        state.main_store.clone(),
+    )
+    .await
+    .into_response())
+}
+
 async fn claude_chat_logic(
     state: Arc<SharedState>,
     query: CcproxyQuery,
@@ -218,6 +253,12 @@ async fn claude_chat_logic(
     .into_response())
 }
 
+async fn claude_count_tokens_logic(body: Bytes) -> Result<Response, CCProxyError> {
+    handle_count_tokens(body)
+        .await
+        .map(|res| res.into_response())
+}
+
 async fn gemini_chat_logic(
     state: Arc<SharedState>,
     query: CcproxyQuery,
@@ -431,6 +472,24 @@ fn openai_routes(compat_mode: bool, mode: GroupMode) -> Router<Arc<SharedState>>
                     .map_err(|e| e.into_response())
                 },
             );
+            let completions_handler = post(
+                move |State(state): State<Arc<SharedState>>,
+                      Path(group_name): Path<String>,
+                      Query(query): Query<CcproxyQuery>,
+                      headers: HeaderMap,
+                      body: Bytes| async move {
+                    openai_completions_logic(
+                        state,
+                        query,
+                        headers,
+                        body,
+                        Some(group_name),
+                        compat_mode,
+                    )
+                    .await
+                    .map_err(|e| e.into_response())
+                },
+            );
             let list_model_handler = get(
                 |State(state): State<Arc<SharedState>>, Path(group_name): Path<String>| async move {
                     openai_list_models_logic(state, Some(group_name))
@@ -452,6 +511,7 @@ fn openai_routes(compat_mode: bool, mode: GroupMode) -> Router<Arc<SharedState>>
             Router::new()
                 .route("/v1/chat/completions", chat_handler)
                 .route("/v1/responses", responses_handler)
+                .route("/v1/completions", completions_handler)
                 .route("/v1/models", list_model_handler)
                 .route("/v1/embeddings", embedding_handler)
         }
@@ -476,6 +536,16 @@ fn openai_routes(compat_mode: bool, mode: GroupMode) -> Router<Arc<SharedState>>
                         .map_err(|e| e.into_response())
                 },
             );
+            let completions_handler = post(
+                move |State(state): State<Arc<SharedState>>,
+                      Query(query): Query<CcproxyQuery>,
+                      headers: HeaderMap,
+                      body: Bytes| async move {
+                    openai_completions_logic(state, query, headers, body, None, compat_mode)
+                        .await
+                        .map_err(|e| e.into_response())
+                },
+            );
             let list_model_handler = get(|State(state): State<Arc<SharedState>>| async move {
                 openai_list_models_logic(state, None)
                     .await
@@ -494,6 +564,7 @@ fn openai_routes(compat_mode: bool, mode: GroupMode) -> Router<Arc<SharedState>>
             Router::new()
                 .route("/v1/chat/completions", chat_handler)
                 .route("/v1/responses", responses_handler)
+                .route("/v1/completions", completions_handler)
                 .route("/v1/models", list_model_handler)
                 .route("/v1/embeddings", embedding_handler)
         }
@@ -526,8 +597,14 @@ fn claude_routes(compat_mode: bool, mode: GroupMode) -> Router<Arc<SharedState>>
                         .map_err(|e| e.into_response())
                 },
             );
+            let count_tokens_handler = post(|body: Bytes| async move {
+                claude_count_tokens_logic(body)
+                    .await
+                    .map_err(|e| e.into_response())
+            });
             Router::new()
                 .route("/v1/messages", chat_handler)
+                .route("/v1/messages/count_tokens", count_tokens_handler)
                 .route("/v1/claude/embeddings", embedding_handler)
         }
         GroupMode::None => {
@@ -551,8 +628,14 @@ fn claude_routes(compat_mode: bool, mode: GroupMode) -> Router<Arc<SharedState>>
                         .map_err(|e| e.into_response())
                 },
             );
+            let count_tokens_handler = post(|body: Bytes| async move {
+                claude_count_tokens_logic(body)
+                    .await
+                    .map_err(|e| e.into_response())
+            });
             Router::new()
                 .route("/v1/messages", chat_handler)
+                .route("/v1/messages/count_tokens", count_tokens_handler)
                 .route("/v1/claude/embeddings", embedding_handler)
         }
     }
@@ -824,6 +907,7 @@ pub async fn routes(
     main_store_arc: Arc<std::sync::RwLock<MainStore>>,
     chat_state: Arc<ChatState>,
 ) -> Router {
+    CCPROXY_BUDGET.set_app_handle(app_handle.clone());
     let shared_state = Arc::new(SharedState {
         app_handle,
         main_store: main_store_arc,
@@ -917,8 +1001,29 @@ pub async fn routes(
         );
 
     let new_mcp_router = {
-        let http_service = crate::mcp::server::create_http_service(shared_state.chat_state.clone());
-        Router::new().nest_service("/http", http_service)
+        let http_service = crate::mcp::server::create_http_service(
+            shared_state.chat_state.clone(),
+            shared_state.app_handle.clone(),
+        );
+        let router = Router::new().nest_service("/http", http_service);
+        let router = if mcp_ws_enabled(&shared_state) {
+            router.nest_service(
+                "/ws",
+                crate::mcp::server::create_ws_router(
+                    shared_state.chat_state.clone(),
+                    shared_state.app_handle.clone(),
+                ),
+            )
+        } else {
+            router
+        };
+        router.layer(middleware::from_fn_with_state(
+            (
+                shared_state.main_store.clone(),
+                shared_state.chat_state.clone(),
+            ),
+            crate::mcp::server::require_token,
+        ))
     };
 
     log_registered_routes();
@@ -1021,9 +1126,11 @@ fn log_registered_routes() {
     log::info!("  - GET /");
     log::info!("  - GET /api/version");
     log::info!("[OpenAI-Compatible]");
-    log::info!("  - /v1/models, /v1/chat/completions, /v1/responses, /v1/embeddings");
+    log::info!(
+        "  - /v1/models, /v1/chat/completions, /v1/completions, /v1/responses, /v1/embeddings"
+    );
     log::info!("[Claude-Compatible]");
-    log::info!("  - /v1/messages, /v1/claude/embeddings");
+    log::info!("  - /v1/messages, /v1/messages/count_tokens, /v1/claude/embeddings");
     log::info!("[Gemini-Compatible]");
     log::info!("  - /v1beta/models, /v1beta/models/{{model_id}}:{{action}}");
     log::info!("[Ollama-Specific]");
@@ -1037,5 +1144,6 @@ fn log_registered_routes() {
     log::info!("-------------------------------------");
     log::info!("[MCP]");
     log::info!("  - MCP Http Streamable Proxy: /mcp/http");
+    log::info!("  - MCP WebSocket Proxy:       /mcp/ws (when enabled)");
     log::info!("-------------------------------------");
 }
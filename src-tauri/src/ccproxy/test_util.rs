@@ -0,0 +1,196 @@
+//! Fixtures shared by the ccproxy handler tests.
+
+use axum::{routing::post, Json, Router};
+use indexmap::IndexMap;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use tokio::sync::mpsc;
+
+use crate::ccproxy::{
+    adapter::unified::UnifiedTool,
+    types::{BackendModelTarget, ProxyModel},
+    ChatCompletionProxyConfig, ChatProtocol,
+};
+use crate::constants::CFG_CHAT_COMPLETION_PROXY;
+use crate::db::{MainStore, ModelConfig};
+use crate::test::spawn_mock_backend;
+
+/// A proxy model that exposes `web_search` and `bash` to the backend, with `bash` filtered out.
+pub fn proxy_model_with_tools(chat_protocol: ChatProtocol) -> ProxyModel {
+    let mut tool_filter = HashMap::new();
+    tool_filter.insert("bash".to_string(), 1_i8);
+    ProxyModel {
+        client_alias: "agent".to_string(),
+        provider_id: 1,
+        provider: "OpenAI".to_string(),
+        chat_protocol,
+        base_url: "https://api.openai.com/v1".to_string(),
+        model: "gpt-4o".to_string(),
+        api_key: String::new(),
+        model_metadata: None,
+        custom_params: None,
+        prompt_injection: "off".to_string(),
+        prompt_injection_position: None,
+        prompt_text: String::new(),
+        tool_filter,
+        tool_include: vec![
+            UnifiedTool {
+                name: "web_search".to_string(),
+                description: Some("Search the web".to_string()),
+                input_schema: json!({"type": "object", "properties": {}}),
+            },
+            UnifiedTool {
+                name: "bash".to_string(),
+                description: None,
+                input_schema: json!({"type": "object", "properties": {}}),
+            },
+        ],
+        prompt_replace: Vec::new(),
+        max_history_turns: None,
+        tool_result_max_chars: None,
+        strip_history_reasoning: false,
+        merge_same_role_messages: true,
+        empty_messages: Default::default(),
+        extra_body_keys: Vec::new(),
+        openai_quirks: None,
+        concurrency_limit: None,
+        fallback: None,
+        temp_ratio: 1.0,
+        max_tokens: None,
+        temperature: None,
+        presence_penalty: None,
+        frequency_penalty: None,
+        top_p: None,
+        top_k: None,
+        stop: Vec::new(),
+        prompt_template: None,
+        model_stop: Vec::new(),
+        tool_compat_mode: None,
+    }
+}
+
+/// Serves an OpenAI-compatible backend that answers each chat request with the text of its
+/// last message, and reports the request bodies it received.
+pub async fn echo_backend() -> (String, mpsc::UnboundedReceiver<Value>) {
+    let (sender, receiver) = mpsc::unbounded_channel();
+    let app = Router::new().route(
+        "/v1/chat/completions",
+        post(move |Json(body): Json<Value>| {
+            let sender = sender.clone();
+            async move {
+                let content = match &body["messages"].as_array().and_then(|m| m.last()) {
+                    Some(message) => match &message["content"] {
+                        Value::String(text) => text.clone(),
+                        parts => parts
+                            .as_array()
+                            .into_iter()
+                            .flatten()
+                            .filter_map(|part| part["text"].as_str())
+                            .collect(),
+                    },
+                    None => String::new(),
+                };
+                let _ = sender.send(body);
+                Json(json!({
+                    "id": "chatcmpl-1",
+                    "object": "chat.completion",
+                    "created": 1,
+                    "model": "echo-model",
+                    "choices": [{
+                        "index": 0,
+                        "message": {"role": "assistant", "content": content},
+                        "finish_reason": "stop"
+                    }],
+                    "usage": {"prompt_tokens": 1, "completion_tokens": 1, "total_tokens": 2}
+                }))
+            }
+        }),
+    );
+    (format!("{}/v1", spawn_mock_backend(app).await), receiver)
+}
+
+/// An OpenAI-compatible provider serving one model, added by [`store_with_providers`].
+pub struct MockProvider {
+    pub name: &'static str,
+    pub model: &'static str,
+    pub base_url: String,
+    pub api_key: String,
+}
+
+impl MockProvider {
+    /// A provider whose API key is `sk-mock-{name}-key`.
+    pub fn new(name: &'static str, model: &'static str, base_url: String) -> Self {
+        Self {
+            name,
+            model,
+            base_url,
+            api_key: format!("sk-mock-{}-key", name.to_lowercase()),
+        }
+    }
+}
+
+/// Creates an empty in-memory store.
+pub fn memory_store() -> MainStore {
+    MainStore::new(":memory:").expect("in-memory store")
+}
+
+/// Creates an in-memory store with `providers` and returns it with their ids.
+///
+/// Each `(alias, index)` of `aliases` routes the alias of the group `group` to the model of
+/// the provider at `index`.
+pub fn store_with_providers(
+    providers: Vec<MockProvider>,
+    group: &str,
+    aliases: &[(&str, usize)],
+) -> (Arc<RwLock<MainStore>>, Vec<i64>) {
+    let mut store = memory_store();
+    let targets: Vec<BackendModelTarget> = providers
+        .into_iter()
+        .map(|provider| BackendModelTarget {
+            id: store
+                .add_ai_model(
+                    provider.name.to_string(),
+                    vec![ModelConfig {
+                        id: provider.model.to_string(),
+                        ..Default::default()
+                    }],
+                    provider.model.to_string(),
+                    "openai".to_string(),
+                    provider.base_url,
+                    provider.api_key,
+                    0,
+                    0.0,
+                    0.0,
+                    0,
+                    false,
+                    None,
+                )
+                .expect("add provider"),
+            model: provider.model.to_string(),
+        })
+        .collect();
+    if !aliases.is_empty() {
+        let routes: IndexMap<String, Vec<BackendModelTarget>> = aliases
+            .iter()
+            .map(|(alias, index)| (alias.to_string(), vec![targets[*index].clone()]))
+            .collect();
+        let mut proxy_config: ChatCompletionProxyConfig = HashMap::new();
+        proxy_config.insert(group.to_string(), routes);
+        store
+            .set_config(CFG_CHAT_COMPLETION_PROXY, &json!(proxy_config))
+            .expect("save proxy config");
+    }
+    let ids = targets.iter().map(|target| target.id).collect();
+    (Arc::new(RwLock::new(store)), ids)
+}
+
+/// Creates a store whose group `group` routes the alias `echo` to the provider at `base_url`.
+pub fn store_with_alias(base_url: String, group: &str) -> Arc<RwLock<MainStore>> {
+    store_with_providers(
+        vec![MockProvider::new("Echo", "echo-model", base_url)],
+        group,
+        &[("echo", 0)],
+    )
+    .0
+}
@@ -32,6 +32,35 @@ pub struct ClaudeNativeRequest {
     pub cache_control: Option<ClaudeCacheControl>, // Cache control breakpoint
 }
 
+/// Request body of `/v1/messages/count_tokens`, a messages request without generation options
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ClaudeCountTokensRequest {
+    pub model: String,
+    pub messages: Vec<ClaudeNativeMessage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default, deserialize_with = "deserialize_system_field")]
+    pub system: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tools: Option<Vec<ClaudeNativeTool>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_choice: Option<ClaudeToolChoice>,
+}
+
+impl From<ClaudeCountTokensRequest> for ClaudeNativeRequest {
+    fn from(req: ClaudeCountTokensRequest) -> Self {
+        Self {
+            model: req.model,
+            messages: req.messages,
+            system: req.system,
+            // Nothing is generated, but validation requires a positive limit.
+            max_tokens: 1,
+            tools: req.tools,
+            tool_choice: req.tool_choice,
+            ..Default::default()
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ClaudeNativeMessage {
     pub role: String, // "user" or "assistant"
@@ -49,8 +78,7 @@ pub enum ClaudeNativeContentBlock {
     #[serde(rename = "tool_result")]
     ToolResult {
         tool_use_id: String,
-        #[serde(deserialize_with = "deserialize_text_or_array_of_strings")]
-        content: String,
+        content: ClaudeToolResultContent,
         #[serde(skip_serializing_if = "Option::is_none")]
         is_error: Option<bool>,
 
@@ -69,13 +97,79 @@ pub enum ClaudeNativeContentBlock {
     Image {
         source: ClaudeImageSource,
     },
+    Document {
+        source: ClaudeDocumentSource,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        title: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        context: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        citations: Option<Value>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        cache_control: Option<Value>,
+    },
     Thinking {
         // Added Thinking variant
         thinking: String,
     },
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+/// Content of a `tool_result` block: a string, or an array of text and image blocks.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum ClaudeToolResultContent {
+    Text(String),
+    Strings(Vec<String>),
+    Blocks(Vec<ClaudeToolResultBlock>),
+}
+
+/// A block of a `tool_result` content array.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClaudeToolResultBlock {
+    Text {
+        text: String,
+    },
+    Image {
+        source: ClaudeImageSource,
+    },
+    /// A block type the proxy does not forward, e.g. `search_result`
+    #[serde(other)]
+    Unsupported,
+}
+
+impl From<String> for ClaudeToolResultContent {
+    fn from(text: String) -> Self {
+        Self::Text(text)
+    }
+}
+
+impl ClaudeToolResultContent {
+    /// Splits the content into its text, the text blocks joined by line breaks in order, and
+    /// its images.
+    pub fn into_text_and_images(self) -> (String, Vec<ClaudeImageSource>) {
+        match self {
+            Self::Text(text) => (text, Vec::new()),
+            Self::Strings(texts) => (texts.join("\n"), Vec::new()),
+            Self::Blocks(blocks) => {
+                let mut texts = Vec::new();
+                let mut images = Vec::new();
+                for block in blocks {
+                    match block {
+                        ClaudeToolResultBlock::Text { text } => texts.push(text),
+                        ClaudeToolResultBlock::Image { source } => images.push(source),
+                        ClaudeToolResultBlock::Unsupported => {
+                            log::debug!("Skipping an unsupported block in a Claude tool_result")
+                        }
+                    }
+                }
+                (texts.join("\n"), images)
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct ClaudeImageSource {
     #[serde(rename = "type")]
     pub source_type: String,
@@ -83,6 +177,17 @@ pub struct ClaudeImageSource {
     pub data: String,
 }
 
+/// Source of a `document` block: a base64 PDF (`base64`) or plain text (`text`).
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ClaudeDocumentSource {
+    #[serde(rename = "type")]
+    pub source_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub media_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<String>,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ClaudeNativeTool {
     pub name: String,
@@ -219,8 +324,12 @@ pub struct ClaudeStreamContentBlock {
 pub struct ClaudeStreamDelta {
     #[serde(rename = "type")]
     pub delta_type: Option<String>,
+    /// Text of a `text_delta`, or the reasoning of a `thinking_delta`
+    #[serde(alias = "thinking")]
     pub text: Option<String>,
     pub stop_reason: Option<String>,
+    /// The custom stop sequence that ended the message, sent with `message_delta`
+    pub stop_sequence: Option<String>,
     pub partial_json: Option<String>,
 }
 
@@ -246,7 +355,7 @@ pub struct ClaudeStreamEvent {
 #[derive(Deserialize, Debug, Clone)]
 pub struct ClaudeStreamMessageStart {
     pub id: String,
-    #[serde(rename = "type")]
+    // #[serde(rename = "type")]
     // pub message_type: String,
     // pub role: String,
     pub model: String,
@@ -441,7 +550,10 @@ mod tests {
         }) = result
         {
             assert_eq!(tool_use_id, "call_481b3a6a05e84442a0b50048");
-            assert_eq!(content, "[{\"id\":1,\"title\":\"Test\"}]");
+            assert_eq!(
+                content,
+                ClaudeToolResultContent::Text("[{\"id\":1,\"title\":\"Test\"}]".to_string())
+            );
             assert_eq!(is_error, None);
             assert!(cache_control.is_some());
         } else {
@@ -471,7 +583,10 @@ mod tests {
         }) = result
         {
             assert_eq!(tool_use_id, "call_123");
-            assert_eq!(content, "test content");
+            assert_eq!(
+                content,
+                ClaudeToolResultContent::Text("test content".to_string())
+            );
             assert_eq!(is_error, None);
             assert!(cache_control.is_none());
         } else {
@@ -521,8 +636,10 @@ mod tests {
         } = result.unwrap()
         {
             assert_eq!(tool_use_id, "call_eb964a55354d4193876d41c9");
-            assert!(content.contains("\"id\":1"));
-            assert!(content.contains("\"title\":\"Test\""));
+            let (text, images) = content.into_text_and_images();
+            assert!(text.contains("\"id\":1"));
+            assert!(text.contains("\"title\":\"Test\""));
+            assert!(images.is_empty());
         } else {
             panic!("Expected ToolResult variant");
         }
@@ -9,7 +9,14 @@ use std::{
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
-use crate::ccproxy::errors::CCProxyError;
+use crate::ccproxy::{
+    adapter::{
+        input::helper::empty_messages::EmptyMessagePolicy,
+        unified::{OpenAICompatQuirks, UnifiedTool},
+    },
+    errors::CCProxyError,
+    helper::{concurrency::ConcurrencyLimit, fallback::ModelFallback},
+};
 
 /// Represents a target backend model for a proxy alias.
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -87,7 +94,31 @@ pub struct ProxyModel {
     pub prompt_injection_position: Option<String>,
     pub prompt_text: String,
     pub tool_filter: HashMap<String, i8>,
+    /// Tools from the group's `toolInclude` metadata, merged into every request
+    pub tool_include: Vec<UnifiedTool>,
     pub prompt_replace: Vec<(String, String)>,
+    /// Most recent conversation turns forwarded upstream, from the group's `maxHistoryTurns`
+    /// metadata; `None` forwards the whole history
+    pub max_history_turns: Option<usize>,
+    /// Characters a Claude client's tool result keeps before it is truncated, from the
+    /// group's `toolResultMaxChars` metadata; `None` forwards results in full
+    pub tool_result_max_chars: Option<usize>,
+    /// Removes the reasoning of earlier turns before forwarding, from the group's
+    /// `stripHistoryReasoning` metadata
+    pub strip_history_reasoning: bool,
+    /// Merges consecutive messages of the same role before forwarding, from the group's
+    /// `mergeSameRoleMessages` metadata; always done for Claude and Gemini backends
+    pub merge_same_role_messages: bool,
+    /// What happens to messages without content, from the group's `emptyMessages` metadata
+    pub empty_messages: EmptyMessagePolicy,
+    /// Extra client body keys forwarded verbatim, from the group's `extraBodyKeys` metadata
+    pub extra_body_keys: Vec<String>,
+    /// Quirks mode of the OpenAI backend, from the group's `openaiQuirks` metadata
+    pub openai_quirks: Option<OpenAICompatQuirks>,
+    /// In-flight request limit of the group, from its `maxConcurrency` metadata
+    pub concurrency_limit: Option<ConcurrencyLimit>,
+    /// Aliases tried when this model fails, from the group's `fallbackModels` metadata
+    pub fallback: Option<ModelFallback>,
     // ratio of the temperature (from proxy group)
     pub temp_ratio: f32,
     // Base parameters from AiModel (Option represents 'not set' in config)
@@ -98,6 +129,10 @@ pub struct ProxyModel {
     pub top_p: Option<f32>,
     pub top_k: Option<i32>,
     pub stop: Vec<String>,
+    /// Chat template of the backend model for raw completion endpoints, from its model config
+    pub prompt_template: Option<String>,
+    /// Stop sequences of the backend model's config, added on raw completion endpoints
+    pub model_stop: Vec<String>,
     // Tool compatibility mode from metadata: "auto", "compat", "native"
     pub tool_compat_mode: Option<String>,
 }
@@ -160,3 +195,96 @@ impl TryFrom<String> for ChatProtocol {
         value.parse().map_err(|e: CCProxyError| e.to_string())
     }
 }
+
+/// Hosts of OpenAI-compatible providers that do not use `api.openai.com`
+const OPENAI_COMPATIBLE_HOSTS: &[&str] = &[
+    "openrouter.ai",
+    "api.deepseek.com",
+    "api.groq.com",
+    "api.mistral.ai",
+    "api.together.xyz",
+];
+
+impl ChatProtocol {
+    /// Guesses the protocol of a provider from its base URL, e.g. `https://api.anthropic.com`
+    /// is `Claude` and `http://localhost:11434` is `Ollama`.
+    ///
+    /// The guess is only a suggestion for pre-filling forms; `None` means the URL is not
+    /// recognized and the user has to choose the protocol.
+    pub fn detect_from_base_url(base_url: &str) -> Option<Self> {
+        let base_url = base_url.trim();
+        if base_url.is_empty() {
+            return None;
+        }
+        let url = url::Url::parse(base_url)
+            .ok()
+            .filter(|u| u.has_host())
+            .or_else(|| url::Url::parse(&format!("http://{}", base_url)).ok())?;
+        let host = url.host_str()?.to_lowercase();
+        let matches_host = |domain: &str| host == domain || host.ends_with(&format!(".{}", domain));
+
+        if matches_host("anthropic.com") || url.path().trim_end_matches('/').ends_with("/anthropic")
+        {
+            Some(ChatProtocol::Claude)
+        } else if matches_host("generativelanguage.googleapis.com") {
+            Some(ChatProtocol::Gemini)
+        } else if matches_host("huggingface.co") {
+            Some(ChatProtocol::HuggingFace)
+        } else if url.port() == Some(11434) {
+            Some(ChatProtocol::Ollama)
+        } else if matches_host("openai.com")
+            || OPENAI_COMPATIBLE_HOSTS.iter().any(|h| matches_host(h))
+        {
+            Some(ChatProtocol::OpenAI)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_protocol_from_known_base_urls() {
+        let cases = [
+            ("https://api.anthropic.com", ChatProtocol::Claude),
+            ("https://api.anthropic.com/v1/", ChatProtocol::Claude),
+            ("https://api.deepseek.com/anthropic", ChatProtocol::Claude),
+            (
+                "https://generativelanguage.googleapis.com/v1beta",
+                ChatProtocol::Gemini,
+            ),
+            ("https://api.openai.com/v1", ChatProtocol::OpenAI),
+            ("https://openrouter.ai/api/v1", ChatProtocol::OpenAI),
+            ("http://localhost:11434", ChatProtocol::Ollama),
+            ("192.168.1.10:11434", ChatProtocol::Ollama),
+            (
+                "https://router.huggingface.co/v1",
+                ChatProtocol::HuggingFace,
+            ),
+        ];
+        for (url, expected) in cases {
+            assert_eq!(
+                ChatProtocol::detect_from_base_url(url),
+                Some(expected),
+                "{}",
+                url
+            );
+        }
+    }
+
+    #[test]
+    fn test_detect_protocol_from_unknown_base_url() {
+        assert_eq!(
+            ChatProtocol::detect_from_base_url("https://llm.example.com/v1"),
+            None
+        );
+        assert_eq!(ChatProtocol::detect_from_base_url(""), None);
+        assert_eq!(
+            ChatProtocol::detect_from_base_url("https://notopenai.com"),
+            None
+        );
+    }
+}
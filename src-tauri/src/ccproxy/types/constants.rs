@@ -5,6 +5,17 @@ pub const TODO_TAG_END: &str = "</cs:todo>";
 
 pub const TOOL_CALL_EMPTY_REMAIN:&str = "<SYSTEM_REMINDER>The tool call was cancelled or failed. The model can try again later if necessary.</SYSTEM_REMINDER>";
 
+/// Replaces `TOOL_CALL_EMPTY_REMAIN` when the tool loop guard is on, so the model does not retry the same call.
+pub const TOOL_CALL_NO_RESULT_REMAIN: &str = "<SYSTEM_REMINDER>This tool call returned no result. Do not repeat the same call with the same arguments; use a different approach or another tool to continue.</SYSTEM_REMINDER>";
+
+/// Consecutive identical tool calls after which `REPEATED_TOOL_CALL_REMINDER` is sent.
+pub const REPEATED_TOOL_CALL_THRESHOLD: usize = 3;
+
+pub const REPEATED_TOOL_CALL_REMINDER: &str = r#"<SYSTEM_REMINDER>
+You have called the `{TOOL_NAME}` tool {COUNT} times in a row with exactly the same arguments. Repeating the call will not give a different result.
+Do not call it again with these arguments. Use the results you already have, change the arguments, try another tool, or tell the user what is blocking you.
+</SYSTEM_REMINDER>"#;
+
 pub const TOOL_PARSE_ERROR_REMINDER: &str = r###"<SYSTEM_REMINDER>
 Your last tool call had an invalid XML format and could not be parsed. Please check carefully and strictly follow the tool usage specifications.
 Common reasons for failure:
@@ -204,3 +215,14 @@ If a tool call fails, check for these common errors before retrying:
 - CRITICAL: The ONLY correct way to call a tool is with a complete `<cs:tool_use></cs:tool_use>` block. Ensure the closing tag `</cs:tool_use>` is always present at the very end. Incomplete tags will cause a failure.
 </cs:Remember>
 </cs:tool-use-guide>"###;
+
+/// Default formatting rules added to the system prompt in compat mode when the client offers
+/// file editing tools. Models following the XML tool guide tend to collapse line breaks in
+/// the file content they write, which these rules counter.
+pub const COMPAT_FORMAT_RULES_PROMPT: &str = r###"<cs:format-rules>
+## FILE CONTENT FORMATTING
+- When a tool argument contains file content or code, write every line on its own line exactly as it must appear in the file. Never join several lines of code into one line.
+- Keep the original indentation, blank lines and line endings of the code you edit.
+- Inside tool arguments, a line break is a real line break in the file. Do not write a literal `\n` unless the file itself must contain the two characters `\` and `n`.
+- After every file edit, compare the number of lines you intended to write with the number of lines in the content you sent. If they differ, fix the file before continuing.
+</cs:format-rules>"###;
@@ -118,6 +118,8 @@ pub struct GeminiGenerationConfig {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub response_schema: Option<Value>, // JSON schema for structured output
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_modalities: Option<Vec<String>>, // "TEXT", "AUDIO"
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub thinking_config: Option<GeminiThinkingConfig>, // Extended thinking configuration
 }
 
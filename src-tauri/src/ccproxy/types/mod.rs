@@ -4,6 +4,7 @@ mod constants;
 pub mod gemini;
 pub mod ollama;
 pub mod openai;
+pub mod openai_completions;
 pub mod openai_responses;
 
 pub use common::*;
@@ -53,7 +53,11 @@ pub struct OllamaFunctionCall {
 pub struct OllamaChatCompletionResponse {
     pub model: String,
     pub created_at: String,
+    #[serde(default)]
     pub message: OllamaMessage,
+    /// Generated text of the raw `/api/generate` endpoint, which sends no `message`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response: Option<String>,
     pub done: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub total_duration: Option<u64>,
@@ -74,7 +78,11 @@ pub struct OllamaChatCompletionResponse {
 pub struct OllamaStreamResponse {
     pub model: String,
     pub created_at: String,
+    #[serde(default)]
     pub message: OllamaMessage,
+    /// Generated text of the raw `/api/generate` endpoint, which sends no `message`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response: Option<String>,
     pub done: bool,
     // The final stream object contains these additional fields.
     #[serde(skip_serializing_if = "Option::is_none")]
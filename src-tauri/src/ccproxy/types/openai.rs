@@ -27,6 +27,10 @@ pub struct UnifiedChatMessage {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub reasoning_details: Option<Vec<Value>>,
 
+    /// Generated speech of audio-capable models
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audio: Option<OpenAIMessageAudio>,
+
     // An extended reference content to store additional information, e.g., citations, sources, etc.
     // It's only for output messages.
     // DO NOT delete this field
@@ -49,6 +53,7 @@ pub enum OpenAIMessageContent {
 pub enum OpenAIMessageContentPart {
     Text { text: String },
     ImageUrl { image_url: OpenAIImageUrl },
+    InputAudio { input_audio: OpenAIInputAudio },
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -58,6 +63,32 @@ pub struct OpenAIImageUrl {
     pub detail: Option<String>, // "auto", "low", "high"
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OpenAIInputAudio {
+    pub data: String,   // Base64 encoded audio
+    pub format: String, // "wav", "mp3"
+}
+
+/// Audio generated by the model, in streams `data` and `transcript` arrive in pieces.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct OpenAIMessageAudio {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<String>, // Base64 encoded audio
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transcript: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<u64>,
+}
+
+/// Voice and encoding of generated audio, required with the `audio` modality.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OpenAIAudioParams {
+    pub voice: String,
+    pub format: String, // "wav", "mp3", "flac", "opus", "pcm16"
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, Default)] // Added Default
 pub struct OpenAIChatCompletionRequest {
     pub model: String, // This will be our proxy alias
@@ -111,6 +142,14 @@ pub struct OpenAIChatCompletionRequest {
     pub thinking_budget: Option<i32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub store: Option<bool>,
+    // Audio output
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub modalities: Option<Vec<String>>, // ["text"] or ["text", "audio"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audio: Option<OpenAIAudioParams>,
+    /// Body fields this struct does not know, e.g. `chat_template_kwargs` of vLLM
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, Value>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
@@ -133,6 +172,9 @@ pub struct OpenAIChatCompletionChoice {
     pub index: i32,
     pub message: UnifiedChatMessage, // For non-streaming, role: "assistant"
     pub finish_reason: Option<String>, // e.g., "stop", "length", "tool_calls", "content_filter"
+    /// vLLM style detail of a "stop" finish: the matched stop string, or the stop token id
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop_reason: Option<Value>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub logprobs: Option<OpenAILogprobs>, // Log probabilities for the choice
 }
@@ -220,6 +262,9 @@ pub struct OpenAIStreamChoice {
     pub delta: UnifiedChatMessage, // Using the unified message structure for delta
     #[serde(skip_serializing_if = "Option::is_none")]
     pub finish_reason: Option<String>,
+    /// vLLM style detail of a "stop" finish: the matched stop string, or the stop token id
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop_reason: Option<Value>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub logprobs: Option<OpenAILogprobs>, // Log probabilities for streaming
 }
@@ -0,0 +1,87 @@
+use serde::{Deserialize, Serialize};
+
+/// Request body of the legacy OpenAI `/v1/completions` API.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct OpenAICompletionsRequest {
+    pub model: String,
+    #[serde(default)]
+    pub prompt: Option<OpenAICompletionsPrompt>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suffix: Option<String>,
+    #[serde(default)]
+    pub echo: Option<bool>,
+    #[serde(default)]
+    pub stream: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub n: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub best_of: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub logprobs: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stop: Option<OpenAICompletionsStop>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub presence_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frequency_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+}
+
+/// The prompt of a legacy completion: a string, a batch of strings or pre-tokenized input.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum OpenAICompletionsPrompt {
+    Text(String),
+    Texts(Vec<String>),
+    Tokens(Vec<i64>),
+    TokenBatches(Vec<Vec<i64>>),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum OpenAICompletionsStop {
+    Single(String),
+    Multiple(Vec<String>),
+}
+
+impl OpenAICompletionsStop {
+    pub fn into_vec(self) -> Vec<String> {
+        match self {
+            Self::Single(s) => vec![s],
+            Self::Multiple(v) => v,
+        }
+    }
+}
+
+impl OpenAICompletionsRequest {
+    /// Returns the single text prompt of the request.
+    ///
+    /// The proxy answers one prompt with one chat turn, so batches and token arrays are
+    /// rejected instead of being silently truncated.
+    pub fn prompt_text(&self) -> Result<String, String> {
+        match &self.prompt {
+            None => Ok(String::new()),
+            Some(OpenAICompletionsPrompt::Text(text)) => Ok(text.clone()),
+            Some(OpenAICompletionsPrompt::Texts(texts)) if texts.len() <= 1 => {
+                Ok(texts.first().cloned().unwrap_or_default())
+            }
+            Some(OpenAICompletionsPrompt::Texts(texts)) => Err(format!(
+                "only one prompt per request is supported, got {}",
+                texts.len()
+            )),
+            Some(OpenAICompletionsPrompt::Tokens(_))
+            | Some(OpenAICompletionsPrompt::TokenBatches(_)) => {
+                Err("token array prompts are not supported, send the prompt as text".to_string())
+            }
+        }
+    }
+}
@@ -1,8 +1,10 @@
 //! A simple token estimator.
 
+use serde::Serialize;
 use serde_json::Value;
 
 use crate::ccproxy::adapter::unified::{UnifiedContentBlock, UnifiedRequest};
+use crate::ccproxy::helper::pdf_text::PLAIN_TEXT_MEDIA_TYPE;
 
 /// A rough heuristic for token estimation.
 ///
@@ -40,6 +42,14 @@ pub fn estimate_tokens(text: &str) -> f64 {
 
 const IMAGE_BLOCK_PLACEHOLDER_TOKENS: f64 = 256.0;
 
+/// PDFs are billed per page for the text and an image of the page, which comes to roughly
+/// one token per 50 characters of the base64 file for typical documents.
+const BASE64_CHARS_PER_DOCUMENT_TOKEN: f64 = 50.0;
+
+/// Audio is billed by duration, about 32 tokens per second, which is one token per
+/// 1000 characters of base64 for common mp3 and 16 kHz wav recordings.
+const BASE64_CHARS_PER_AUDIO_TOKEN: f64 = 1000.0;
+
 fn estimate_json_value_tokens(value: &Value) -> f64 {
     match value {
         Value::Null => 0.0,
@@ -69,6 +79,10 @@ pub fn estimate_unified_request_tokens(request: &UnifiedRequest) -> f64 {
         total += estimate_tokens(system_prompt);
     }
 
+    if let Some(developer_prompt) = request.developer_prompt.as_deref() {
+        total += estimate_tokens(developer_prompt);
+    }
+
     if let Some(combined_prompt) = request.combined_prompt.as_deref() {
         total += estimate_tokens(combined_prompt);
     }
@@ -142,6 +156,26 @@ pub fn estimate_unified_request_tokens(request: &UnifiedRequest) -> f64 {
                 UnifiedContentBlock::Image { media_type, data } => {
                     estimate_tokens(media_type) + estimate_data_url_tokens(data)
                 }
+                UnifiedContentBlock::Document {
+                    media_type,
+                    data,
+                    title,
+                } => {
+                    let data_tokens = if media_type == PLAIN_TEXT_MEDIA_TYPE {
+                        estimate_tokens(data)
+                    } else {
+                        data.len() as f64 / BASE64_CHARS_PER_DOCUMENT_TOKEN
+                    };
+                    estimate_tokens(media_type)
+                        + estimate_tokens(title.as_deref().unwrap_or_default())
+                        + data_tokens
+                }
+                UnifiedContentBlock::Audio {
+                    data, transcript, ..
+                } => {
+                    data.len() as f64 / BASE64_CHARS_PER_AUDIO_TOKEN
+                        + estimate_tokens(transcript.as_deref().unwrap_or_default())
+                }
                 UnifiedContentBlock::ToolUse { id, name, input } => {
                     estimate_tokens(id) + estimate_tokens(name) + estimate_json_value_tokens(input)
                 }
@@ -263,6 +297,274 @@ fn estimate_openai_like_content_tokens(content: &Value) -> f64 {
     }
 }
 
+/// Tokenizer families with distinct token densities, used to estimate prompts before sending.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TokenizerFamily {
+    /// o200k/cl100k based GPT and o-series models
+    OpenAI,
+    Claude,
+    Gemini,
+    /// Qwen, DeepSeek, GLM, Kimi and other models with CJK-heavy vocabularies
+    ChineseOptimized,
+    /// Llama, Mistral and their derivatives
+    Llama,
+    /// Unknown models, estimated conservatively
+    Generic,
+}
+
+impl TokenizerFamily {
+    /// Picks the family from a model id such as `gpt-4o` or `deepseek-chat`.
+    pub fn from_model(model: &str) -> Self {
+        let model = model.to_lowercase();
+        // Strip provider prefixes like `openai/` or `accounts/fireworks/models/`.
+        let name = model.rsplit('/').next().unwrap_or(&model);
+        let starts_with_any = |prefixes: &[&str]| prefixes.iter().any(|p| name.starts_with(p));
+
+        if starts_with_any(&["gpt", "o1", "o3", "o4", "chatgpt", "text-embedding"]) {
+            Self::OpenAI
+        } else if name.contains("claude") {
+            Self::Claude
+        } else if starts_with_any(&["gemini", "gemma"]) {
+            Self::Gemini
+        } else if starts_with_any(&[
+            "qwen", "qwq", "deepseek", "glm", "chatglm", "kimi", "moonshot", "yi-", "baichuan",
+            "minimax", "abab", "doubao", "ernie", "hunyuan",
+        ]) {
+            Self::ChineseOptimized
+        } else if starts_with_any(&["llama", "mistral", "mixtral", "codestral", "ministral"]) {
+            Self::Llama
+        } else {
+            Self::Generic
+        }
+    }
+
+    /// Multiplier for the ASCII estimate, relative to the OpenAI tokenizer.
+    fn ascii_scale(&self) -> f64 {
+        match self {
+            Self::OpenAI | Self::Gemini => 1.0,
+            Self::ChineseOptimized => 1.05,
+            Self::Llama => 1.1,
+            Self::Claude | Self::Generic => 1.15,
+        }
+    }
+
+    /// Tokens per CJK character.
+    fn cjk_tokens_per_char(&self) -> f64 {
+        match self {
+            Self::ChineseOptimized => 0.65,
+            Self::Gemini => 0.7,
+            Self::OpenAI => 0.75,
+            Self::Llama => 1.0,
+            Self::Claude => 1.1,
+            Self::Generic => 1.5,
+        }
+    }
+}
+
+/// Letters of one word that typically fit in a single token.
+const CHARS_PER_WORD_TOKEN: f64 = 8.0;
+/// Digits are split into groups of up to three.
+const DIGITS_PER_TOKEN: f64 = 3.0;
+/// Tokens per non-CJK, non-ASCII character (accented Latin, Cyrillic, ...).
+const OTHER_SCRIPT_TOKENS_PER_CHAR: f64 = 0.5;
+/// Role markers and separators every chat message adds.
+const MESSAGE_OVERHEAD_TOKENS: f64 = 4.0;
+
+fn is_cjk(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x3000..=0x303F // CJK punctuation
+            | 0x3040..=0x30FF // Hiragana, Katakana
+            | 0x3400..=0x4DBF // CJK extension A
+            | 0x4E00..=0x9FFF // CJK unified ideographs
+            | 0xAC00..=0xD7AF // Hangul syllables
+            | 0xF900..=0xFAFF // CJK compatibility ideographs
+            | 0xFF00..=0xFFEF // Full-width forms
+    )
+}
+
+/// Estimates the tokens of `text` for a tokenizer family.
+///
+/// Unlike [`estimate_tokens`], which only looks at single characters, this counts words,
+/// digit groups and punctuation runs the way BPE tokenizers split them, which keeps English
+/// estimates within about 15% of the real count.
+pub fn estimate_tokens_for_family(text: &str, family: TokenizerFamily) -> f64 {
+    #[derive(PartialEq)]
+    enum Run {
+        None,
+        Word,
+        Digits,
+        Punctuation,
+    }
+
+    fn run_tokens(run: &Run, len: usize) -> f64 {
+        let len = len as f64;
+        match run {
+            Run::None => 0.0,
+            Run::Word => (len / CHARS_PER_WORD_TOKEN).ceil(),
+            Run::Digits => (len / DIGITS_PER_TOKEN).ceil(),
+            // Common pairs such as `()`, `",` or `**` are merged into one token.
+            Run::Punctuation => (len / 2.0).ceil(),
+        }
+    }
+
+    let mut ascii_tokens = 0.0;
+    let mut other_tokens = 0.0;
+    let mut run = Run::None;
+    let mut run_len = 0;
+
+    for c in text.chars() {
+        let next = if c.is_ascii_alphabetic() {
+            Run::Word
+        } else if c.is_ascii_digit() {
+            Run::Digits
+        } else if c.is_ascii_punctuation() {
+            Run::Punctuation
+        } else {
+            Run::None
+        };
+        if next != run || next == Run::None {
+            ascii_tokens += run_tokens(&run, run_len);
+            run_len = 0;
+        }
+
+        if next == Run::None {
+            if c == '\n' {
+                ascii_tokens += 0.5;
+            } else if is_cjk(c) {
+                other_tokens += family.cjk_tokens_per_char();
+            } else if !c.is_ascii() {
+                other_tokens += OTHER_SCRIPT_TOKENS_PER_CHAR;
+            }
+            // Other whitespace is merged into the following token.
+        }
+        run = next;
+        run_len += 1;
+    }
+    ascii_tokens += run_tokens(&run, run_len);
+
+    ascii_tokens * family.ascii_scale() + other_tokens
+}
+
+/// Estimates the input tokens of a unified request for a tokenizer family.
+///
+/// Counts what the model actually reads: the system prompt, tool definitions and messages,
+/// including the per-message template overhead. Used to answer token counting requests.
+pub fn estimate_unified_input_tokens(request: &UnifiedRequest, family: TokenizerFamily) -> u64 {
+    let text_tokens = |text: &str| estimate_tokens_for_family(text, family);
+    let json_tokens = |value: &Value| estimate_tokens_for_family(&value.to_string(), family);
+
+    let mut total = request.system_prompt.as_deref().map_or(0.0, text_tokens);
+
+    if let Some(tools) = request.tools.as_ref() {
+        for tool in tools {
+            total += MESSAGE_OVERHEAD_TOKENS
+                + text_tokens(&tool.name)
+                + tool.description.as_deref().map_or(0.0, text_tokens)
+                + json_tokens(&tool.input_schema);
+        }
+    }
+
+    for message in &request.messages {
+        total += MESSAGE_OVERHEAD_TOKENS;
+        for block in &message.content {
+            total += match block {
+                UnifiedContentBlock::Text { text } => text_tokens(text),
+                UnifiedContentBlock::Thinking { thinking } => text_tokens(thinking),
+                UnifiedContentBlock::Image { data, .. } => estimate_data_url_tokens(data),
+                UnifiedContentBlock::Document {
+                    media_type, data, ..
+                } => {
+                    if media_type == PLAIN_TEXT_MEDIA_TYPE {
+                        text_tokens(data)
+                    } else {
+                        data.len() as f64 / BASE64_CHARS_PER_DOCUMENT_TOKEN
+                    }
+                }
+                UnifiedContentBlock::Audio {
+                    data, transcript, ..
+                } => {
+                    data.len() as f64 / BASE64_CHARS_PER_AUDIO_TOKEN
+                        + transcript.as_deref().map_or(0.0, text_tokens)
+                }
+                UnifiedContentBlock::ToolUse { name, input, .. } => {
+                    text_tokens(name) + json_tokens(input)
+                }
+                UnifiedContentBlock::ToolResult { content, .. } => text_tokens(content),
+            };
+        }
+    }
+
+    total.ceil() as u64
+}
+
+/// The estimated size of a prompt and whether it fits the model's context.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptTokenEstimate {
+    pub estimated_tokens: u64,
+    pub tokenizer_family: TokenizerFamily,
+    /// The configured context size of the model, `None` if unknown
+    pub context_size: Option<u64>,
+    /// Tokens kept free for the answer, the model's configured max tokens
+    pub reserved_output_tokens: u64,
+    /// Whether prompt and answer fit the context, `None` if the context size is unknown
+    pub fits_context: Option<bool>,
+}
+
+/// Estimates OpenAI-style chat `messages` (`{role, content}` with string or part array content)
+/// for `model`.
+pub fn estimate_prompt_tokens(
+    messages: &[Value],
+    model: &str,
+    context_size: Option<u64>,
+    reserved_output_tokens: u64,
+) -> PromptTokenEstimate {
+    let family = TokenizerFamily::from_model(model);
+    let text_tokens = |text: &str| estimate_tokens_for_family(text, family);
+
+    let total: f64 = messages
+        .iter()
+        .map(|message| {
+            let content_tokens = match message.get("content") {
+                Some(Value::String(text)) => text_tokens(text),
+                Some(Value::Array(parts)) => parts
+                    .iter()
+                    .map(|part| match part.get("type").and_then(|t| t.as_str()) {
+                        Some("text") => part
+                            .get("text")
+                            .and_then(|t| t.as_str())
+                            .map(text_tokens)
+                            .unwrap_or(0.0),
+                        Some("image_url") | Some("image") => IMAGE_BLOCK_PLACEHOLDER_TOKENS,
+                        _ => estimate_json_value_tokens(part),
+                    })
+                    .sum(),
+                Some(other) => estimate_json_value_tokens(other),
+                None => 0.0,
+            };
+            let reasoning_tokens = message
+                .get("reasoning")
+                .or_else(|| message.get("reasoning_content"))
+                .and_then(|r| r.as_str())
+                .map(text_tokens)
+                .unwrap_or(0.0);
+            MESSAGE_OVERHEAD_TOKENS + content_tokens + reasoning_tokens
+        })
+        .sum();
+
+    let estimated_tokens = total.ceil() as u64;
+    PromptTokenEstimate {
+        estimated_tokens,
+        tokenizer_family: family,
+        context_size,
+        reserved_output_tokens,
+        fits_context: context_size
+            .map(|size| estimated_tokens.saturating_add(reserved_output_tokens) <= size),
+    }
+}
+
 pub fn resolve_usage_with_estimate(
     protocol: &str,
     usage_input_tokens: u64,
@@ -300,7 +602,8 @@ pub fn resolve_usage_with_estimate(
 #[cfg(test)]
 mod tests {
     use super::{
-        estimate_known_request_json_tokens, estimate_tokens, estimate_unified_request_tokens,
+        estimate_known_request_json_tokens, estimate_prompt_tokens, estimate_tokens,
+        estimate_tokens_for_family, estimate_unified_request_tokens, TokenizerFamily,
     };
     use crate::ccproxy::adapter::unified::{
         UnifiedContentBlock, UnifiedMessage, UnifiedRequest, UnifiedRole, UnifiedTool,
@@ -363,4 +666,93 @@ mod tests {
 
         assert!(estimated > baseline);
     }
+
+    fn assert_within(estimated: f64, expected: f64, tolerance: f64, text: &str) {
+        let deviation = (estimated - expected).abs() / expected;
+        assert!(
+            deviation <= tolerance,
+            "estimated {} tokens for {:?}, expected {} within {}%",
+            estimated,
+            text,
+            expected,
+            tolerance * 100.0
+        );
+    }
+
+    #[test]
+    fn family_estimates_are_close_to_reference_counts() {
+        // Reference counts of the OpenAI o200k tokenizer.
+        for (text, expected) in [
+            ("The quick brown fox jumps over the lazy dog.", 10.0),
+            ("Hello, world!", 4.0),
+            ("Order 12345678 ships on 2024-06-01.", 16.0),
+            ("fn main() {\n    println!(\"Hello, world!\");\n}", 15.0),
+        ] {
+            let estimated = estimate_tokens_for_family(text, TokenizerFamily::OpenAI);
+            assert_within(estimated, expected, 0.2, text);
+        }
+
+        // CJK text costs well under one token per character with CJK-heavy vocabularies
+        // and more than one with Claude's.
+        let chinese = "今天天气很好，我们一起去公园散步吧。".repeat(10);
+        let chars = chinese.chars().count() as f64;
+        let qwen = estimate_tokens_for_family(&chinese, TokenizerFamily::ChineseOptimized);
+        let claude = estimate_tokens_for_family(&chinese, TokenizerFamily::Claude);
+        assert_within(qwen, chars * 0.65, 0.1, &chinese);
+        assert_within(claude, chars * 1.1, 0.1, &chinese);
+        assert!(qwen < claude);
+    }
+
+    #[test]
+    fn tokenizer_family_is_derived_from_model_id() {
+        assert_eq!(
+            TokenizerFamily::from_model("gpt-4o-mini"),
+            TokenizerFamily::OpenAI
+        );
+        assert_eq!(
+            TokenizerFamily::from_model("anthropic/claude-sonnet-4"),
+            TokenizerFamily::Claude
+        );
+        assert_eq!(
+            TokenizerFamily::from_model("gemini-2.5-pro"),
+            TokenizerFamily::Gemini
+        );
+        assert_eq!(
+            TokenizerFamily::from_model("Qwen/Qwen3-Coder-480B-A35B-Instruct"),
+            TokenizerFamily::ChineseOptimized
+        );
+        assert_eq!(
+            TokenizerFamily::from_model("llama3.1:8b"),
+            TokenizerFamily::Llama
+        );
+        assert_eq!(
+            TokenizerFamily::from_model("my-finetune"),
+            TokenizerFamily::Generic
+        );
+    }
+
+    #[test]
+    fn prompt_estimate_reports_whether_it_fits_the_context() {
+        let messages = vec![
+            json!({"role": "system", "content": "You are a helpful assistant."}),
+            json!({
+                "role": "user",
+                "content": [
+                    {"type": "text", "text": "The quick brown fox jumps over the lazy dog."},
+                    {"type": "image_url", "image_url": {"url": "data:image/png;base64,AAAA"}}
+                ]
+            }),
+        ];
+
+        let estimate = estimate_prompt_tokens(&messages, "gpt-4o", Some(8192), 1024);
+        assert_eq!(estimate.tokenizer_family, TokenizerFamily::OpenAI);
+        assert!(estimate.estimated_tokens > 256 + 10);
+        assert_eq!(estimate.fits_context, Some(true));
+
+        let estimate = estimate_prompt_tokens(&messages, "gpt-4o", Some(1024), 1000);
+        assert_eq!(estimate.fits_context, Some(false));
+
+        let estimate = estimate_prompt_tokens(&messages, "gpt-4o", None, 0);
+        assert_eq!(estimate.fits_context, None);
+    }
 }
@@ -1,4 +1,9 @@
-use crate::db::MainStore;
+use crate::ccproxy::{
+    apply_setup_preset, benchmark_models, replay_dead_letter, setup_presets, BenchmarkReport,
+    BenchmarkTarget, ChatProtocol, KeyHealth, ReplayResult, ReplayTarget, SetupPreset,
+    SetupWizardResult, CC_PROXY_ROTATOR,
+};
+use crate::db::{CcproxyAuditEntry, CcproxyAuditQuery, CcproxyDeadLetter, MainStore};
 use std::sync::Arc;
 use tauri::State;
 
@@ -100,3 +105,123 @@ pub async fn get_ccproxy_provider_token_usage_stats(
         .get_ccproxy_provider_token_usage_stats(days)
         .map_err(|e| e.to_string())
 }
+
+/// Lists failed proxy requests from the dead-letter log, newest first.
+#[tauri::command]
+pub async fn get_ccproxy_dead_letters(
+    limit: Option<i64>,
+    offset: Option<i64>,
+    main_store: State<'_, Arc<std::sync::RwLock<MainStore>>>,
+) -> Result<Vec<CcproxyDeadLetter>, String> {
+    let store = main_store.read().map_err(|e| e.to_string())?;
+    store
+        .get_ccproxy_dead_letters(limit.unwrap_or(50), offset.unwrap_or(0))
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_ccproxy_dead_letters(
+    main_store: State<'_, Arc<std::sync::RwLock<MainStore>>>,
+) -> Result<(), String> {
+    let store = main_store.read().map_err(|e| e.to_string())?;
+    store
+        .delete_ccproxy_dead_letters()
+        .map_err(|e| e.to_string())
+}
+
+/// Lists the audit log of proxied requests matching the query, newest first.
+#[tauri::command]
+pub async fn get_ccproxy_audit_log(
+    query: Option<CcproxyAuditQuery>,
+    main_store: State<'_, Arc<std::sync::RwLock<MainStore>>>,
+) -> Result<Vec<CcproxyAuditEntry>, String> {
+    let mut query = query.unwrap_or_default();
+    query.limit = Some(query.limit.unwrap_or(50));
+    let store = main_store.read().map_err(|e| e.to_string())?;
+    store
+        .get_ccproxy_audit_log(&query)
+        .map_err(|e| e.to_string())
+}
+
+/// Exports the audit log entries matching the query to a JSON Lines file.
+///
+/// # Returns
+/// The number of exported entries
+#[tauri::command]
+pub async fn export_ccproxy_audit_log(
+    path: String,
+    query: Option<CcproxyAuditQuery>,
+    main_store: State<'_, Arc<std::sync::RwLock<MainStore>>>,
+) -> Result<usize, String> {
+    let entries = main_store
+        .read()
+        .map_err(|e| e.to_string())?
+        .get_ccproxy_audit_log(&query.unwrap_or_default())
+        .map_err(|e| e.to_string())?;
+    let mut lines = String::new();
+    for entry in &entries {
+        lines.push_str(&serde_json::to_string(entry).map_err(|e| e.to_string())?);
+        lines.push('\n');
+    }
+    std::fs::write(&path, lines).map_err(|e| e.to_string())?;
+    Ok(entries.len())
+}
+
+/// Replays a failed request from the dead-letter log against another model or group.
+#[tauri::command]
+pub async fn replay_ccproxy_request(
+    dead_letter_id: i64,
+    target: ReplayTarget,
+    main_store: State<'_, Arc<std::sync::RwLock<MainStore>>>,
+) -> Result<ReplayResult, String> {
+    replay_dead_letter(main_store.inner().clone(), dead_letter_id, target)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Streams a prompt through each target model and compares first-token latency and throughput.
+///
+/// A failed model is reported in its result and does not stop the others.
+#[tauri::command]
+pub async fn benchmark_model(
+    targets: Vec<BenchmarkTarget>,
+    prompt: Option<String>,
+    main_store: State<'_, Arc<std::sync::RwLock<MainStore>>>,
+) -> Result<BenchmarkReport, String> {
+    Ok(benchmark_models(main_store.inner().clone(), targets, prompt).await)
+}
+
+/// Returns the health of every pooled proxy key, including the keys whose circuit is open
+/// after repeated 429 or 5xx responses.
+#[tauri::command]
+pub async fn get_ccproxy_key_health() -> Result<Vec<KeyHealth>, String> {
+    Ok(CC_PROXY_ROTATOR.key_health())
+}
+
+/// Lists the provider presets of the first-run setup wizard.
+#[tauri::command]
+pub async fn setup_wizard_presets() -> Result<Vec<SetupPreset>, String> {
+    Ok(setup_presets())
+}
+
+/// Configures a working proxy group from a provider preset, e.g. `deepseek` or `ollama`.
+///
+/// Creates the provider and the proxy group and routes the preset aliases, then dry-runs the
+/// group. Problems found by the dry run are returned in `issues` rather than as an error.
+#[tauri::command]
+pub async fn setup_wizard_apply(
+    preset: String,
+    api_key: Option<String>,
+    group: Option<String>,
+    main_store: State<'_, Arc<std::sync::RwLock<MainStore>>>,
+) -> Result<SetupWizardResult, String> {
+    let mut store = main_store.write().map_err(|e| e.to_string())?;
+    apply_setup_preset(&mut store, &preset, api_key, group).map_err(|e| e.to_string())
+}
+
+/// Guesses the API protocol of a provider from its base URL, e.g. `claude` for
+/// `https://api.anthropic.com`. Returns `None` when the URL is not recognized.
+#[tauri::command]
+pub async fn detect_api_protocol(base_url: String) -> Result<Option<String>, String> {
+    Ok(ChatProtocol::detect_from_base_url(&base_url).map(|p| p.to_string()))
+}
@@ -45,20 +45,26 @@
 //! - Finally, add the new AI provider to the `init_chats!` macro.
 
 use crate::ai::error::AiError;
+use crate::ai::interaction::attachments::inject_attachments;
 use crate::ai::interaction::chat_completion::{
     list_models_async, start_new_chat_interaction, ChatState,
 };
 use crate::ai::interaction::constants::{SYSTEM_PROMPT, TOOL_USAGE_GUIDANCE};
+use crate::ai::interaction::message_persistence::StreamingMessage;
 use crate::ai::traits::chat::{ChatMetadata, MCPToolDeclaration, ModelDetails};
+use crate::ccproxy::utils::token_estimator::{estimate_prompt_tokens, PromptTokenEstimate};
 use crate::ccproxy::ChatProtocol;
-use crate::constants::{CFG_INTERFACE_LANGUAGE, DEFAULT_WEB_FETCH_TOOL, DEFAULT_WEB_SEARCH_TOOL};
+use crate::constants::{
+    CFG_CHAT_INJECT_CURRENT_TIME, CFG_CHAT_SEPARATE_REASONING_STREAM, CFG_INTERFACE_LANGUAGE,
+    CFG_TIMEZONE, DEFAULT_WEB_FETCH_TOOL, DEFAULT_WEB_SEARCH_TOOL,
+};
 use crate::db::MainStore;
 use crate::error::{AppError, Result};
 use crate::libs::lang::{get_available_lang, lang_to_iso_639_1};
 use crate::sensitive::manager::{FilterManager, SensitiveConfig};
-use crate::tools::MCP_TOOL_NAME_SPLIT;
+use crate::tools::{format_current_time, TimeZoneSpec, MCP_TOOL_NAME_SPLIT};
 
-use chrono::{DateTime, Local};
+use chrono::Utc;
 use rust_i18n::t;
 use serde_json::{json, Value};
 use std::env;
@@ -67,15 +73,13 @@ use tauri::State;
 use whatlang::detect;
 
 /// Generates environment information for the AI assistant
-fn generate_environment_info() -> String {
+///
+/// The current time line is only included when `current_time` is provided.
+fn generate_environment_info(current_time: Option<&str>) -> String {
     let os = env::consts::OS;
     let arch = env::consts::ARCH;
     let family = env::consts::FAMILY;
 
-    // Get current time in both UTC and local timezone
-    // let utc_now: DateTime<Utc> = Utc::now();
-    let local_now: DateTime<Local> = Local::now();
-
     // Get shell information
     let shell = env::var("SHELL")
         .or_else(|_| env::var("COMSPEC"))
@@ -90,28 +94,44 @@ fn generate_environment_info() -> String {
         .or_else(|_| env::var("USERPROFILE"))
         .unwrap_or_else(|_| "unknown".to_string());
 
+    let time_line = current_time
+        .map(|time| format!("Current Local Time: {}\n", time))
+        .unwrap_or_default();
+
     format!(
         r#"<environment>
 Operating System: {} ({})
 Architecture: {}
-Current Local Time: {}
-Shell: {}
+{}Shell: {}
 Home Directory: {}
 </environment>"#,
-        os,
-        family,
-        arch,
-        local_now.format("%Y-%m-%d %H:%M:%S %Z"),
-        shell,
-        home
+        os, family, arch, time_line, shell, home
     )
 }
 
+/// Returns the current time to inject into the chat system prompt,
+/// or `None` when the injection is disabled in settings.
+fn current_time_for_system_prompt(store: &MainStore) -> Option<String> {
+    if !store.get_config(CFG_CHAT_INJECT_CURRENT_TIME, true) {
+        return None;
+    }
+    let timezone = store.get_config(CFG_TIMEZONE, String::new());
+    let spec = TimeZoneSpec::parse(&timezone).unwrap_or_else(|| {
+        log::warn!(
+            "Invalid timezone '{}' in settings, using local time",
+            timezone
+        );
+        TimeZoneSpec::Local
+    });
+    Some(format_current_time(&spec, Utc::now()))
+}
+
 /// Prepares messages with system prompts and environment information
 fn prepare_messages_with_system_context(
     mut messages: Vec<Value>,
     has_tools: bool,
     mcp_summaries: Vec<crate::ai::traits::chat::MCPToolDeclaration>,
+    current_time: Option<&str>,
 ) -> Vec<Value> {
     let mut system_content = SYSTEM_PROMPT.to_string();
 
@@ -158,7 +178,7 @@ fn prepare_messages_with_system_context(
 
     // Add environment information
     system_content.push_str("\n\n");
-    system_content.push_str(&generate_environment_info());
+    system_content.push_str(&generate_environment_info(current_time));
 
     // Create the final system message
     let system_message = json!({
@@ -307,9 +327,11 @@ pub async fn list_models(
 /// - `messages` - The messages to send to the chat.
 /// - `network_enabled` - Whether to enable network search for URLs in the user message
 /// - `metadata` - Optional extra parameters for the chat.
+/// - `conversation_id` - When set, the assistant message is added to this conversation before
+///   the response streams and updated while it does, flagged incomplete until it finishes.
 ///
 /// # Returns
-/// A `Result` containing () or an error message.
+/// The ID of the assistant message when `conversation_id` is set.
 #[tauri::command]
 pub async fn chat_completion(
     window: tauri::Window,
@@ -322,7 +344,8 @@ pub async fn chat_completion(
     network_enabled: Option<bool>,
     mcp_enabled: Option<bool>,
     metadata: Option<Value>, // This comes from frontend, contains model params & UI flags
-) -> Result<()> {
+    conversation_id: Option<i64>,
+) -> Result<Option<i64>> {
     if provider_id < 1 {
         return Err(AppError::Ai(AiError::InitFailed(
             t!("chat.empty_provider_id").to_string(),
@@ -363,7 +386,7 @@ pub async fn chat_completion(
     }
 
     // Sensitive Data Filtering
-    let (sensitive_config, interface_lang): (SensitiveConfig, String) = {
+    let (sensitive_config, interface_lang, current_time, separate_reasoning) = {
         let store = chat_state
             .main_store
             .read()
@@ -371,10 +394,17 @@ pub async fn chat_completion(
         (
             store.get_config("sensitive_config", SensitiveConfig::default()),
             store.get_config(CFG_INTERFACE_LANGUAGE, "en".to_string()),
+            current_time_for_system_prompt(&store),
+            store.get_config(CFG_CHAT_SEPARATE_REASONING_STREAM, false),
         )
     };
+    chat_state
+        .channels
+        .set_separate_reasoning(window.label(), separate_reasoning);
 
     let mut filtered_messages = messages;
+    // Attached files become content blocks first, so their text is filtered as well
+    inject_attachments(&mut filtered_messages)?;
     if sensitive_config.enabled {
         #[cfg(debug_assertions)]
         log::debug!(
@@ -473,7 +503,7 @@ pub async fn chat_completion(
 
     // Get MCP tool summaries for system prompt (descriptions only)
     let mcp_summaries = if mcp_enabled.unwrap_or(false) {
-        chat_state
+        let mut summaries: Vec<MCPToolDeclaration> = chat_state
             .tool_manager
             .get_tool_calling_spec(Some(crate::tools::ToolScope::Chat), None)
             .await?
@@ -484,30 +514,70 @@ pub async fn chat_completion(
                 t.input_schema = serde_json::json!({});
                 t
             })
-            .collect()
+            .collect();
+        chat_state
+            .tool_manager
+            .expose_tool_names(&mut summaries)
+            .await;
+        summaries
     } else {
         vec![]
     };
 
     // Prepare messages with system context
     let has_tools = tools.as_ref().map_or(false, |t| !t.is_empty());
-    let prepared_messages =
-        prepare_messages_with_system_context(filtered_messages, has_tools, mcp_summaries);
+    let prepared_messages = prepare_messages_with_system_context(
+        filtered_messages,
+        has_tools,
+        mcp_summaries,
+        current_time.as_deref(),
+    );
 
     #[cfg(debug_assertions)]
     log::debug!("Processed messages count: {}", prepared_messages.len());
 
-    start_new_chat_interaction(
+    let streaming_message_id = match conversation_id {
+        Some(conversation_id) => {
+            let message_id = chat_state
+                .main_store
+                .read()
+                .map_err(|e| AppError::Db(crate::db::StoreError::IoError(e.to_string())))?
+                .add_streaming_message(
+                    conversation_id,
+                    "assistant".to_string(),
+                    Some(json!({ "chatId": chat_id, "provider": model })),
+                )
+                .map_err(AppError::Db)?;
+            chat_state
+                .streaming_messages
+                .insert(chat_id.clone(), StreamingMessage::new(message_id));
+            Some(message_id)
+        }
+        None => None,
+    };
+
+    let started = start_new_chat_interaction(
         chat_state.inner().clone(),
         provider_id,
         model,
-        chat_id,
+        chat_id.clone(),
         prepared_messages,
         tools,
         Some(final_metadata),
         None,
     )
-    .await
+    .await;
+    if let (Err(_), Some(message_id)) = (&started, streaming_message_id) {
+        // Nothing was streamed, the empty message is removed
+        chat_state.streaming_messages.remove(&chat_id);
+        chat_state
+            .main_store
+            .read()
+            .map_err(|e| AppError::Db(crate::db::StoreError::IoError(e.to_string())))?
+            .delete_message(vec![message_id])
+            .map_err(AppError::Db)?;
+    }
+    started.map(|()| streaming_message_id)
 }
 
 /// Tauri command to stop the ongoing chat for a specific API provider.
@@ -537,6 +607,11 @@ pub async fn stop_chat(
             t!("chat.invalid_api_protocol", protocll = api_protocol.clone()).to_string(),
         )
     })?;
+    // A stopped response stays flagged incomplete
+    if let Some((_, mut message)) = state.streaming_messages.remove(chat_id) {
+        message.flush(&state.main_store, false);
+    }
+
     let mut chats = state.chats.lock().await;
 
     // Find the specific chat instance
@@ -599,9 +674,83 @@ pub fn detect_language(text: &str) -> Result<Value> {
     }
 }
 
+/// Estimates the prompt tokens of `messages` before they are sent to `model`.
+///
+/// # Arguments
+/// - `messages` - The chat messages, `{ role, content }` objects like in `chat_completion`.
+/// - `model` - The model id, which selects the tokenizer approximation.
+/// - `provider_id` - The provider of the model, its model config supplies the context size
+///   and the max tokens reserved for the answer.
+///
+/// # Returns
+/// The estimated prompt tokens and, if the context size is known, whether they fit.
+#[tauri::command]
+pub fn estimate_tokens(
+    main_state: State<'_, Arc<std::sync::RwLock<MainStore>>>,
+    messages: Vec<Value>,
+    model: String,
+    provider_id: Option<i64>,
+) -> Result<PromptTokenEstimate> {
+    if model.is_empty() {
+        return Err(AppError::Ai(AiError::InitFailed(
+            t!("chat.empty_model").to_string(),
+        )));
+    }
+
+    let model_config = match provider_id {
+        Some(provider_id) => main_state
+            .read()?
+            .config
+            .get_ai_model_by_id(provider_id)?
+            .models
+            .into_iter()
+            .find(|config| config.id == model),
+        None => None,
+    };
+    let context_size = model_config
+        .as_ref()
+        .and_then(|config| config.context_size)
+        .filter(|size| *size > 0)
+        .map(|size| size as u64);
+    let reserved_output_tokens = model_config
+        .as_ref()
+        .and_then(|config| config.max_tokens)
+        .filter(|tokens| *tokens > 0)
+        .map_or(0, |tokens| tokens as u64);
+
+    Ok(estimate_prompt_tokens(
+        &messages,
+        &model,
+        context_size,
+        reserved_output_tokens,
+    ))
+}
+
 #[cfg(test)]
 mod tests {
+    use super::prepare_messages_with_system_context;
     use crate::commands::constants::URL_REGEX;
+    use serde_json::json;
+
+    #[test]
+    fn test_current_time_injected_only_when_enabled() {
+        let messages = vec![json!({"role": "user", "content": "What day is it?"})];
+
+        let prepared = prepare_messages_with_system_context(
+            messages.clone(),
+            false,
+            vec![],
+            Some("2024-06-01 20:00:00 +08:00 (Saturday)"),
+        );
+        let system = prepared[0]["content"].as_str().unwrap();
+        assert_eq!(prepared[0]["role"], "system");
+        assert!(system.contains("Current Local Time: 2024-06-01 20:00:00 +08:00 (Saturday)"));
+
+        let prepared = prepare_messages_with_system_context(messages, false, vec![], None);
+        let system = prepared[0]["content"].as_str().unwrap();
+        assert!(!system.contains("Current Local Time"));
+        assert!(system.contains("<environment>"));
+    }
 
     #[test]
     fn test_url_regex() {
@@ -8,6 +8,7 @@ use crate::{
     error::{AppError, Result},
     scraper::{
         engine::run as run_scraper,
+        pool::{ScraperPool, ScraperPoolMetrics},
         types::{ContentOptions, ScrapeRequest},
     },
     tools::{TOOL_WEB_FETCH, TOOL_WEB_SEARCH},
@@ -146,3 +147,11 @@ pub async fn test_scrape(
         }
     }
 }
+
+/// Returns the size and usage counters of the scraper webview pool.
+#[command]
+pub async fn get_scraper_pool_metrics(
+    scraper_pool: State<'_, Arc<ScraperPool>>,
+) -> Result<ScraperPoolMetrics> {
+    Ok(scraper_pool.metrics().await)
+}
@@ -19,10 +19,16 @@
 
 use crate::{
     ai::{interaction::chat_completion::ChatState, traits::chat::MCPToolDeclaration},
+    constants::CFG_MCP_SERVER_AUTH,
     db::{MainStore, Mcp},
     error::{AppError, Result},
-    mcp::client::{McpProtocolType, McpServerConfig},
+    mcp::client::{
+        process::{self, McpProcessInfo},
+        McpProtocolType, McpServerConfig,
+    },
+    mcp::server::{McpServerAuthConfig, McpToolApprovalRequest},
     mcp::McpError,
+    tools::SamplingApprovalRequest,
 };
 use rust_i18n::t;
 use std::{
@@ -822,3 +828,147 @@ pub async fn update_mcp_tool_status(
 
     Ok(mcp)
 }
+
+/// Approves or rejects a sampling request of an MCP server whose sampling policy is `ask`.
+///
+/// The request id comes from the `cs://mcp-sampling-approval` event payload.
+///
+/// # Returns
+/// * `bool` - `false` if the request is unknown, already resolved or timed out.
+///
+/// # Example
+///
+/// ```js
+/// import { invoke } from '@tauri-apps/api/core'
+///
+/// await invoke('respond_mcp_sampling_approval', { requestId, approved: true });
+/// ```
+#[tauri::command]
+pub fn respond_mcp_sampling_approval(
+    chat_state: State<'_, Arc<ChatState>>,
+    request_id: String,
+    approved: bool,
+) -> bool {
+    chat_state
+        .tool_manager
+        .sampling_approvals
+        .respond(&request_id, approved)
+}
+
+/// Lists sampling requests of MCP servers waiting for approval.
+#[tauri::command]
+pub fn list_pending_mcp_sampling_approvals(
+    chat_state: State<'_, Arc<ChatState>>,
+) -> Vec<SamplingApprovalRequest> {
+    chat_state.tool_manager.sampling_approvals.pending()
+}
+
+/// Lists the running stdio MCP server processes with their uptime and memory.
+///
+/// # Example
+///
+/// ```js
+/// import { invoke } from '@tauri-apps/api/core'
+///
+/// const processes = await invoke('list_mcp_processes');
+/// // [{ pid: 4242, server: 'filesystem', uptimeSecs: 3600, memoryBytes: 52428800 }]
+/// ```
+#[tauri::command]
+pub async fn list_mcp_processes() -> Vec<McpProcessInfo> {
+    process::list().await
+}
+
+/// Force-terminates a stuck stdio MCP server process.
+///
+/// Only processes listed by `list_mcp_processes` can be terminated. The server shows up as
+/// disconnected afterwards and can be restarted as usual.
+///
+/// # Example
+///
+/// ```js
+/// import { invoke } from '@tauri-apps/api/core'
+///
+/// await invoke('kill_mcp_process', { pid: 4242 });
+/// ```
+#[tauri::command]
+pub async fn kill_mcp_process(pid: u32) -> Result<()> {
+    process::kill(pid).await?;
+    Ok(())
+}
+
+/// Approves or rejects a gated tool call of an external MCP client.
+///
+/// The request id comes from the `cs://mcp-tool-approval` event payload. In `perSession`
+/// mode an approval also covers later calls of the same tool in that session.
+///
+/// # Returns
+/// * `bool` - `false` if the request is unknown, already resolved or timed out.
+///
+/// # Example
+///
+/// ```js
+/// import { invoke } from '@tauri-apps/api/core'
+///
+/// await invoke('respond_mcp_tool_approval', { requestId, approved: true });
+/// ```
+#[tauri::command]
+pub fn respond_mcp_tool_approval(
+    chat_state: State<'_, Arc<ChatState>>,
+    request_id: String,
+    approved: bool,
+) -> bool {
+    chat_state
+        .tool_manager
+        .mcp_server_approvals
+        .respond(&request_id, approved)
+}
+
+/// Lists tool calls of external MCP clients waiting for approval.
+#[tauri::command]
+pub fn list_pending_mcp_tool_approvals(
+    chat_state: State<'_, Arc<ChatState>>,
+) -> Vec<McpToolApprovalRequest> {
+    chat_state.tool_manager.mcp_server_approvals.pending()
+}
+
+/// Returns the authentication settings of the MCP server.
+#[tauri::command]
+pub fn get_mcp_server_auth(main_store: State<'_, Arc<RwLock<MainStore>>>) -> McpServerAuthConfig {
+    McpServerAuthConfig::load(&main_store)
+}
+
+/// Saves the authentication settings of the MCP server.
+fn save_mcp_server_auth(
+    main_store: &Arc<RwLock<MainStore>>,
+    config: &McpServerAuthConfig,
+) -> Result<()> {
+    let value = serde_json::to_value(config).map_err(|e| AppError::General {
+        message: e.to_string(),
+    })?;
+    main_store
+        .write()?
+        .set_config(CFG_MCP_SERVER_AUTH, &value)?;
+    Ok(())
+}
+
+/// Enables or disables authentication of the MCP server.
+///
+/// Clients then authenticate with one of the ccproxy access keys.
+///
+/// # Example
+///
+/// ```js
+/// import { invoke } from '@tauri-apps/api/core'
+///
+/// await invoke('set_mcp_server_auth_enabled', { enabled: true });
+/// ```
+#[tauri::command]
+pub fn set_mcp_server_auth_enabled(
+    main_store: State<'_, Arc<RwLock<MainStore>>>,
+    enabled: bool,
+) -> Result<McpServerAuthConfig> {
+    let mut config = McpServerAuthConfig::load(&main_store);
+    config.enabled = enabled;
+    save_mcp_server_auth(&main_store, &config)?;
+    Ok(config)
+}
@@ -43,10 +43,11 @@ use whatlang::detect;
 
 /// Get all conversations
 ///
-/// Retrieves a list of all conversations from the chat store.
+/// Retrieves a list of all conversations from the chat store, pinned ones first.
 ///
 /// # Arguments
 /// - `state` - The state of the chat store, automatically injected by Tauri
+/// - `include_archived` - Whether archived conversations are listed too, `false` by default
 ///
 /// # Returns
 /// * `Result<Vec<Conversation>, String>` - A vector of conversations or an error message
@@ -58,12 +59,18 @@ use whatlang::detect;
 /// import { invoke } from '@tauri-apps/api/core';
 ///
 /// const conversations = await invoke('get_all_conversations');
+/// const withArchived = await invoke('get_all_conversations', { includeArchived: true });
 /// console.log(conversations);
 /// ```
 #[command]
-pub fn get_all_conversations(state: State<Arc<RwLock<MainStore>>>) -> Result<Vec<Conversation>> {
+pub fn get_all_conversations(
+    state: State<Arc<RwLock<MainStore>>>,
+    include_archived: Option<bool>,
+) -> Result<Vec<Conversation>> {
     let main_store = state.read()?;
-    main_store.get_all_conversations().map_err(AppError::Db)
+    main_store
+        .get_all_conversations(include_archived.unwrap_or(false))
+        .map_err(AppError::Db)
 }
 
 /// Get a conversation by ID
@@ -155,6 +162,70 @@ pub fn update_conversation(
         .map_err(AppError::Db)
 }
 
+/// Pin or unpin a conversation
+///
+/// Pinned conversations are listed before the others.
+///
+/// # Arguments
+/// - `state` - The state of the chat store, automatically injected by Tauri
+/// - `id` - The ID of the conversation to update
+/// - `pinned` - The new pinned state
+///
+/// # Returns
+/// * `Result<(), String>` - Ok if successful or an error message
+///
+/// # Example
+///
+/// ```js
+/// // Call from frontend:
+/// import { invoke } from '@tauri-apps/api/core';
+///
+/// await invoke('set_conversation_pinned', { id: 1, pinned: true });
+/// ```
+#[command]
+pub fn set_conversation_pinned(
+    state: State<Arc<RwLock<MainStore>>>,
+    id: i64,
+    pinned: bool,
+) -> Result<()> {
+    let main_store = state.write()?;
+    main_store
+        .set_conversation_pinned(id, pinned)
+        .map_err(AppError::Db)
+}
+
+/// Archive or restore a conversation
+///
+/// Archived conversations are left out of `get_all_conversations` unless asked for.
+///
+/// # Arguments
+/// - `state` - The state of the chat store, automatically injected by Tauri
+/// - `id` - The ID of the conversation to update
+/// - `archived` - The new archived state
+///
+/// # Returns
+/// * `Result<(), String>` - Ok if successful or an error message
+///
+/// # Example
+///
+/// ```js
+/// // Call from frontend:
+/// import { invoke } from '@tauri-apps/api/core';
+///
+/// await invoke('set_conversation_archived', { id: 1, archived: true });
+/// ```
+#[command]
+pub fn set_conversation_archived(
+    state: State<Arc<RwLock<MainStore>>>,
+    id: i64,
+    archived: bool,
+) -> Result<()> {
+    let main_store = state.write()?;
+    main_store
+        .set_conversation_archived(id, archived)
+        .map_err(AppError::Db)
+}
+
 /// Delete a conversation
 ///
 /// Removes a conversation from the chat store by its ID.
@@ -314,6 +385,79 @@ pub fn delete_message(state: State<Arc<RwLock<MainStore>>>, id: Vec<i64>) -> Res
     let main_store = state.write()?;
     main_store.delete_message(id).map_err(AppError::Db)
 }
+
+/// Delete messages along with the messages sharing a tool call with them
+///
+/// # Arguments
+/// - `state` - The state of the chat store, automatically injected by Tauri
+/// - `ids` - The IDs of the messages to delete
+///
+/// # Returns
+/// * `Result<Vec<i64>>` - The IDs of all deleted messages
+///
+/// # Example
+///
+/// ```js
+/// import { invoke } from '@tauri-apps/api/core';
+///
+/// const deletedIds = await invoke('delete_messages', { ids: [1, 2] });
+/// ```
+#[command]
+pub fn delete_messages(state: State<Arc<RwLock<MainStore>>>, ids: Vec<i64>) -> Result<Vec<i64>> {
+    let main_store = state.write()?;
+    main_store.delete_messages(&ids).map_err(AppError::Db)
+}
+
+/// Delete every message of a conversation after the given one
+///
+/// Results of tool calls made up to the given message are kept.
+///
+/// # Arguments
+/// - `state` - The state of the chat store, automatically injected by Tauri
+/// - `message_id` - The ID of the last message to keep
+///
+/// # Returns
+/// * `Result<Vec<i64>>` - The IDs of the deleted messages
+///
+/// # Example
+///
+/// ```js
+/// import { invoke } from '@tauri-apps/api/core';
+///
+/// const deletedIds = await invoke('delete_messages_after', { messageId: 5 });
+/// ```
+#[command]
+pub fn delete_messages_after(
+    state: State<Arc<RwLock<MainStore>>>,
+    message_id: i64,
+) -> Result<Vec<i64>> {
+    let main_store = state.write()?;
+    main_store
+        .delete_messages_after(message_id)
+        .map_err(AppError::Db)
+}
+
+/// Delete all messages of a conversation, keeping the conversation
+///
+/// # Arguments
+/// - `state` - The state of the chat store, automatically injected by Tauri
+/// - `id` - The ID of the conversation to clear
+///
+/// # Returns
+/// * `Result<usize>` - The number of deleted messages
+///
+/// # Example
+///
+/// ```js
+/// import { invoke } from '@tauri-apps/api/core';
+///
+/// await invoke('clear_conversation', { id: 1 });
+/// ```
+#[command]
+pub fn clear_conversation(state: State<Arc<RwLock<MainStore>>>, id: i64) -> Result<usize> {
+    let main_store = state.write()?;
+    main_store.clear_conversation(id).map_err(AppError::Db)
+}
 /// Update the metadata of a message
 ///
 /// Updates the metadata of a message in the chat store.
@@ -346,6 +490,36 @@ pub fn update_message_metadata(
         .map_err(AppError::Db)
 }
 
+/// Replace the content and metadata of a message
+///
+/// Used to finish an assistant message that `chat_completion` persisted while it streamed.
+///
+/// # Arguments
+/// - `state` - The state of the chat store, automatically injected by Tauri
+/// - `id` - The ID of the message to update
+/// - `content` - The new content of the message
+/// - `metadata` - The new metadata of the message
+///
+/// # Example
+///
+/// ```js
+/// import { invoke } from '@tauri-apps/api/core';
+///
+/// await invoke('update_message', { id: 1, content: 'Hello', metadata: { tokens: 12 } });
+/// ```
+#[command]
+pub fn update_message(
+    state: State<Arc<RwLock<MainStore>>>,
+    id: i64,
+    content: String,
+    metadata: Option<serde_json::Value>,
+) -> Result<()> {
+    let main_store = state.write()?;
+    main_store
+        .update_message(id, content, metadata)
+        .map_err(AppError::Db)
+}
+
 /// Sends a conversation message to the frontend with the specified label and message content.
 ///
 /// # Arguments
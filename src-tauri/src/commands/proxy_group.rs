@@ -1,7 +1,10 @@
+use crate::ccproxy::{
+    get_group_warmup, warm_up_enabled_group, warm_up_group, GroupWarmup, CCPROXY_WARMUP_EVENT,
+};
 use crate::db::{MainStore, ProxyGroup};
 use serde_json::Value;
 use std::sync::{Arc, RwLock};
-use tauri::{command, State};
+use tauri::{command, AppHandle, Emitter, State};
 
 use crate::error::{AppError, Result};
 
@@ -11,16 +14,63 @@ pub fn proxy_group_list(state: State<Arc<RwLock<MainStore>>>) -> Result<Vec<Prox
     Ok(store.config.get_proxy_groups())
 }
 
+/// Reports a finished warm-up to the frontend.
+fn emit_warmup(app: &AppHandle, warmup: Option<GroupWarmup>) {
+    if let Some(warmup) = warmup {
+        if let Err(e) = app.emit(CCPROXY_WARMUP_EVENT, &warmup) {
+            log::error!("Failed to emit the proxy group warm-up: {}", e);
+        }
+    }
+}
+
 #[command]
-pub fn proxy_group_add(state: State<Arc<RwLock<MainStore>>>, item: ProxyGroup) -> Result<i64> {
-    let mut store = state.write()?;
-    store.proxy_group_add(&item).map_err(AppError::Db)
+pub fn proxy_group_add(
+    app: AppHandle,
+    state: State<Arc<RwLock<MainStore>>>,
+    item: ProxyGroup,
+) -> Result<i64> {
+    let id = {
+        let mut store = state.write()?;
+        store.proxy_group_add(&item).map_err(AppError::Db)?
+    };
+
+    let main_store = state.inner().clone();
+    tauri::async_runtime::spawn(async move {
+        let warmup = warm_up_enabled_group(main_store, None, &item).await;
+        emit_warmup(&app, warmup);
+    });
+    Ok(id)
 }
 
 #[command]
-pub fn proxy_group_update(state: State<Arc<RwLock<MainStore>>>, item: ProxyGroup) -> Result<()> {
-    let mut store = state.write()?;
-    store.proxy_group_update(&item).map_err(AppError::Db)
+pub fn proxy_group_update(
+    app: AppHandle,
+    state: State<Arc<RwLock<MainStore>>>,
+    item: ProxyGroup,
+) -> Result<()> {
+    let previous = {
+        let mut store = state.write()?;
+        let previous = store
+            .config
+            .get_proxy_groups()
+            .into_iter()
+            .find(|group| group.id == item.id);
+        store.proxy_group_update(&item).map_err(AppError::Db)?;
+        previous
+    };
+
+    let main_store = state.inner().clone();
+    tauri::async_runtime::spawn(async move {
+        let warmup = warm_up_enabled_group(main_store, previous.as_ref(), &item).await;
+        emit_warmup(&app, warmup);
+    });
+    Ok(())
+}
+
+/// Returns the last warm-up of a proxy group, `None` when none ran since the app started.
+#[command]
+pub fn get_proxy_group_warmup(name: String) -> Option<GroupWarmup> {
+    get_group_warmup(&name)
 }
 
 #[command]
@@ -55,14 +105,35 @@ pub fn proxy_group_delete(state: State<Arc<RwLock<MainStore>>>, id: i64) -> Resu
 }
 
 #[command]
-pub fn set_active_proxy_group(state: State<Arc<RwLock<MainStore>>>, name: String) -> Result<()> {
-    let mut store = state.write()?;
-    store
-        .set_config(
-            crate::constants::CFG_ACTIVE_PROXY_GROUP,
-            &serde_json::json!(name),
-        )
-        .map_err(AppError::Db)
+pub fn set_active_proxy_group(
+    app: AppHandle,
+    state: State<Arc<RwLock<MainStore>>>,
+    name: String,
+) -> Result<()> {
+    let changed = {
+        let mut store = state.write()?;
+        let changed = store
+            .config
+            .get_setting(crate::constants::CFG_ACTIVE_PROXY_GROUP)
+            .and_then(|v| v.as_str())
+            != Some(name.as_str());
+        store
+            .set_config(
+                crate::constants::CFG_ACTIVE_PROXY_GROUP,
+                &serde_json::json!(name),
+            )
+            .map_err(AppError::Db)?;
+        changed
+    };
+
+    if changed {
+        let main_store = state.inner().clone();
+        tauri::async_runtime::spawn(async move {
+            let warmup = warm_up_group(main_store, &name).await;
+            emit_warmup(&app, warmup);
+        });
+    }
+    Ok(())
 }
 
 #[command]
@@ -31,10 +31,12 @@
 //! ```
 //!
 
+use crate::ai::interaction::chat_completion::ChatState;
 use crate::constants::*;
 use crate::db::api_key_crypto::{ApiKeyEncryptionStatus, API_KEY_FILE_CONFIG_KEY};
 use crate::db::{AiModel, AiSkill, MainStore, ModelConfig, StoreError};
-use crate::db::{BackupConfig, DbBackup};
+use crate::db::{BackupConfig, DatabaseOptimizeReport, DbBackup};
+use crate::db::{ImportPolicy, ImportSummary, PortableSettings};
 use crate::libs::fs::{self, get_file_name};
 use crate::tray::create_tray;
 
@@ -43,8 +45,8 @@ use serde_json::Value;
 use std::collections::HashMap;
 use std::path::Path;
 use std::sync::{Arc, RwLock};
-use tauri::State;
 use tauri::{command, AppHandle};
+use tauri::{Manager, State};
 
 use crate::error::{AppError, Result};
 
@@ -145,6 +147,15 @@ pub fn set_config(
                     #[cfg(debug_assertions)]
                     log::debug!("Language set to: {}", lang);
                 }
+                CFG_MCP_ROOTS => {
+                    let roots = config_store.get_config(CFG_MCP_ROOTS, Vec::<String>::new());
+                    if let Some(chat_state) = app.try_state::<Arc<ChatState>>() {
+                        let tool_manager = chat_state.tool_manager.clone();
+                        tauri::async_runtime::spawn(async move {
+                            tool_manager.set_mcp_roots(roots).await;
+                        });
+                    }
+                }
                 _ => {}
             },
             Err(e) => return Err(e),
@@ -802,6 +813,75 @@ pub async fn backup_setting(
     result.map_err(AppError::Db)
 }
 
+/// Vacuums the database and rebuilds its full-text indexes.
+///
+/// Holds the write lock of the store for the whole run, so no other command reads or
+/// writes while the database file is rewritten.
+///
+/// # Returns
+/// * `Result<DatabaseOptimizeReport>` - The sizes before and after and the rebuilt tables
+#[tauri::command]
+pub async fn optimize_database(
+    state: State<'_, Arc<RwLock<MainStore>>>,
+) -> Result<DatabaseOptimizeReport> {
+    let main_store = state.inner().clone();
+    tokio::task::spawn_blocking(move || {
+        let mut store = main_store
+            .write()
+            .map_err(|e| AppError::Db(StoreError::LockError(e.to_string())))?;
+        store.optimize_database().map_err(AppError::Db)
+    })
+    .await
+    .map_err(|e| AppError::General {
+        message: e.to_string(),
+    })?
+}
+
+/// Exports models, skills, proxy groups, MCP servers and settings to a portable JSON file
+///
+/// # Arguments
+/// * `path` - The file to write
+/// * `include_secrets` - Whether API keys, tokens and other secrets are kept
+#[tauri::command]
+pub fn export_settings(
+    state: State<'_, Arc<RwLock<MainStore>>>,
+    path: String,
+    include_secrets: bool,
+) -> Result<()> {
+    let settings = state
+        .read()
+        .map_err(|e| AppError::Db(StoreError::LockError(e.to_string())))?
+        .export_settings(include_secrets)?;
+    let json = serde_json::to_string_pretty(&settings)
+        .map_err(|e| AppError::Db(StoreError::JsonError(e.to_string())))?;
+    std::fs::write(&path, json).map_err(|e| AppError::Db(StoreError::IoError(e.to_string())))
+}
+
+/// Imports a file written by `export_settings`, matching records by name
+///
+/// # Arguments
+/// * `path` - The file to read
+/// * `policy` - Whether existing records and settings are kept (`merge`) or replaced (`overwrite`)
+///
+/// # Returns
+/// * `Result<ImportSummary>` - How many records and settings were added, updated and skipped
+#[tauri::command]
+pub fn import_settings(
+    state: State<'_, Arc<RwLock<MainStore>>>,
+    path: String,
+    policy: Option<ImportPolicy>,
+) -> Result<ImportSummary> {
+    let content = std::fs::read_to_string(&path)
+        .map_err(|e| AppError::Db(StoreError::IoError(e.to_string())))?;
+    let settings: PortableSettings = serde_json::from_str(&content)
+        .map_err(|e| AppError::Db(StoreError::JsonError(e.to_string())))?;
+    state
+        .write()
+        .map_err(|e| AppError::Db(StoreError::LockError(e.to_string())))?
+        .import_settings(settings, policy.unwrap_or_default())
+        .map_err(AppError::Db)
+}
+
 #[tauri::command]
 pub async fn restore_setting(
     app: AppHandle,
@@ -0,0 +1,131 @@
+//! Tauri commands for inspecting the available tools and answering tools that need
+//! input from the user while they run.
+
+use crate::{
+    ai::interaction::chat_completion::ChatState,
+    db::MainStore,
+    error::{AppError, Result},
+    tools::{AvailableTool, CommandApprovalRequest, ToolMetricsSnapshot, ToolRerunResult},
+};
+use serde_json::Value;
+use std::sync::{Arc, RwLock};
+use tauri::State;
+
+/// Lists every tool registered in the tool manager, native and MCP.
+///
+/// Each entry carries its source, description, input schema and whether it is enabled,
+/// which helps to find out why the model does not call a tool.
+///
+/// # Example
+///
+/// ```js
+/// import { invoke } from '@tauri-apps/api/core'
+///
+/// const tools = await invoke('list_available_tools');
+/// ```
+#[tauri::command]
+pub async fn list_available_tools(
+    chat_state: State<'_, Arc<ChatState>>,
+) -> Result<Vec<AvailableTool>> {
+    Ok(chat_state.tool_manager.list_available_tools().await)
+}
+
+/// Returns per-tool call counts, latencies and error rates since startup
+/// (or since the persisted baseline when `persist_tool_metrics` is enabled).
+#[tauri::command]
+pub fn get_tool_metrics(chat_state: State<'_, Arc<ChatState>>) -> Vec<ToolMetricsSnapshot> {
+    chat_state.tool_manager.metrics.snapshot()
+}
+
+/// Clears all collected tool metrics.
+#[tauri::command]
+pub fn reset_tool_metrics(chat_state: State<'_, Arc<ChatState>>) {
+    chat_state.tool_manager.metrics.reset();
+}
+
+/// Approves or rejects a command held by the `execute_command` tool.
+///
+/// The request id comes from the `cs://command-approval` event payload.
+///
+/// # Returns
+/// * `bool` - `false` if the request is unknown, already resolved or timed out.
+///
+/// # Example
+///
+/// ```js
+/// import { invoke } from '@tauri-apps/api/core'
+///
+/// await invoke('respond_command_approval', { requestId, approved: true });
+/// ```
+#[tauri::command]
+pub fn respond_command_approval(
+    chat_state: State<'_, Arc<ChatState>>,
+    request_id: String,
+    approved: bool,
+) -> bool {
+    chat_state
+        .tool_manager
+        .command_approvals
+        .respond(&request_id, approved)
+}
+
+/// Lists commands waiting for approval, e.g. to restore the prompt after a window reload.
+#[tauri::command]
+pub fn list_pending_command_approvals(
+    chat_state: State<'_, Arc<ChatState>>,
+) -> Vec<CommandApprovalRequest> {
+    chat_state.tool_manager.command_approvals.pending()
+}
+
+/// Re-executes the tool calls recorded in a past assistant message and returns fresh results,
+/// without re-running the whole turn. The stored message is left unchanged.
+///
+/// # Arguments
+/// * `message_id` - The message whose `metadata.toolCall` entries are re-run.
+/// * `tool_call_id` - Only re-run the call with this id; all calls when omitted.
+///
+/// # Returns
+/// * `Vec<ToolRerunResult>` - One entry per re-run call; calls to tools that no longer
+///   exist carry an `error` instead of a result.
+///
+/// # Example
+///
+/// ```js
+/// import { invoke } from '@tauri-apps/api/core'
+///
+/// const results = await invoke('rerun_message_tool_calls', { messageId: 42, toolCallId: 'call_1' });
+/// ```
+#[tauri::command]
+pub async fn rerun_message_tool_calls(
+    main_store: State<'_, Arc<RwLock<MainStore>>>,
+    chat_state: State<'_, Arc<ChatState>>,
+    message_id: i64,
+    tool_call_id: Option<String>,
+) -> Result<Vec<ToolRerunResult>> {
+    let metadata = main_store
+        .read()?
+        .get_message_metadata(message_id)
+        .map_err(AppError::Db)?;
+    let tool_calls: Vec<Value> = metadata
+        .as_ref()
+        .and_then(|m| m.get("toolCall"))
+        .and_then(Value::as_array)
+        .map(|calls| {
+            calls
+                .iter()
+                .filter(|call| {
+                    tool_call_id
+                        .as_deref()
+                        .is_none_or(|wanted| call.get("id").and_then(Value::as_str) == Some(wanted))
+                })
+                .cloned()
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let mut results = Vec::with_capacity(tool_calls.len());
+    for call in &tool_calls {
+        results.push(chat_state.tool_manager.rerun_recorded_tool_call(call).await);
+    }
+    Ok(results)
+}
@@ -17,6 +17,8 @@ pub const TRAY_ID: &str = "Chatspeed";
 // Startup and update config
 pub const CFG_AUTO_START: &str = "auto_start";
 pub const CFG_AUTO_UPDATE: &str = "auto_update";
+/// Mirrors update packages are also downloaded from, whichever source is fastest
+pub const CFG_UPDATE_MIRRORS: &str = "update_mirrors";
 
 // =================================================
 // Core plugin identifiers
@@ -30,6 +32,8 @@ pub const CFG_AUTO_UPDATE: &str = "auto_update";
 
 // interface language
 pub const CFG_INTERFACE_LANGUAGE: &str = "interface_language";
+// emit reasoning deltas on `chat_stream_reasoning` instead of `chat_stream`
+pub const CFG_CHAT_SEPARATE_REASONING_STREAM: &str = "chat_separate_reasoning_stream";
 pub const CFG_CHAT_COMPLETION_PROXY: &str = "chat_completion_proxy";
 pub const CFG_ACTIVE_PROXY_GROUP: &str = "active_proxy_group";
 pub const CFG_CCPROXY_PORT: &str = "chat_completion_proxy_port";
@@ -40,10 +44,65 @@ pub const CFG_CCPROXY_LOG_TO_FILE: &str = "chat_completion_proxy_log_to_file";
 pub const CFG_CCPROXY_LOG_PROXY_TO_FILE: &str = "chat_completion_proxy_log_proxy_to_file";
 pub const CFG_CCPROXY_RETRY_ON_429: &str = "chat_completion_proxy_retry_on_429";
 pub const CFG_CCPROXY_RETRY_ON_429_DEFAULT: u32 = 0;
+pub const CFG_CCPROXY_BUDGET: &str = "chat_completion_proxy_budget";
+/// Content moderation of proxied prompts and responses, disabled by default
+pub const CFG_CCPROXY_MODERATION: &str = "chat_completion_proxy_moderation";
+/// Redaction of personal data in proxied prompts, disabled by default
+pub const CFG_CCPROXY_REDACTION: &str = "chat_completion_proxy_redaction";
+/// Local audit log of proxied requests, disabled by default
+pub const CFG_CCPROXY_AUDIT_LOG: &str = "chat_completion_proxy_audit_log";
+pub const CFG_CCPROXY_COMPAT_FORMAT_RULES: &str = "chat_completion_proxy_compat_format_rules";
+/// Reflow of single-line file content written by compat mode tool calls
+pub const CFG_CCPROXY_COMPAT_CODE_REFLOW: &str = "chat_completion_proxy_compat_code_reflow";
+/// Bytes an unclosed compat mode tool call may buffer before it is forwarded as text
+pub const CFG_CCPROXY_COMPAT_TOOL_BUFFER_LIMIT: &str =
+    "chat_completion_proxy_compat_tool_buffer_limit";
+pub const CFG_CCPROXY_COMPAT_TOOL_BUFFER_LIMIT_DEFAULT: usize = 1024 * 1024;
+/// Full or compact tool definitions in the compat mode system prompt, and the token budget
+pub const CFG_CCPROXY_COMPAT_TOOL_PROMPT: &str = "chat_completion_proxy_compat_tool_prompt";
+/// Explicit placeholders for unanswered tool calls and a hint against repeated identical calls
+pub const CFG_CCPROXY_TOOL_LOOP_GUARD: &str = "chat_completion_proxy_tool_loop_guard";
+/// Marker before earlier reasoning sent as text to backends that take no reasoning history
+pub const CFG_CCPROXY_REASONING_TEXT_PREFIX: &str = "chat_completion_proxy_reasoning_text_prefix";
+pub const CFG_CCPROXY_REASONING_TEXT_PREFIX_DEFAULT: &str = "[Reasoning]\n";
+/// Model token limits `max_tokens` is clamped to before a request is forwarded
+pub const CFG_CCPROXY_MAX_TOKENS_LIMITS: &str = "chat_completion_proxy_max_tokens_limits";
+/// Seconds without any upstream bytes before a stream is closed with an error, 0 disables it
+pub const CFG_CCPROXY_STREAM_STALL_TIMEOUT: &str = "chat_completion_proxy_stream_stall_timeout";
+pub const CFG_CCPROXY_STREAM_STALL_TIMEOUT_DEFAULT: u64 = 120;
+/// Seconds a stream may take to produce its first token before it fails over, 0 disables it
+pub const CFG_CCPROXY_FIRST_TOKEN_TIMEOUT: &str = "chat_completion_proxy_first_token_timeout";
+pub const CFG_CCPROXY_FIRST_TOKEN_TIMEOUT_DEFAULT: u64 = 0;
+/// Whether enabling a proxy group sends a one-token request to each of its models
+pub const CFG_CCPROXY_WARMUP: &str = "chat_completion_proxy_warmup";
+pub const CFG_CCPROXY_WARMUP_DEFAULT: bool = true;
 pub const CFG_BUILTIN_AGENTS_LAST_SYNCED_APP_VERSION: &str =
     "builtin_agents_last_synced_app_version";
 pub const CFG_SEARCH_ENGINE: &str = "search_engine";
+pub const CFG_SEARCH_RECENCY_WEIGHT: &str = "search_recency_weight";
+/// Concurrency and per-domain rate limits for fetching the pages behind search results
+pub const CFG_SEARCH_FETCH_LIMITS: &str = "search_fetch_limits";
+pub const CFG_TIMEZONE: &str = "timezone";
+pub const CFG_CHAT_INJECT_CURRENT_TIME: &str = "chat_inject_current_time";
+pub const CFG_PERSIST_TOOL_METRICS: &str = "persist_tool_metrics";
+/// Folders the `execute_command` tool may run commands in without asking the user
+pub const CFG_SHELL_AUTHORIZED_PATHS: &str = "shell_authorized_paths";
+pub const CFG_WORKFLOW_FORMAT_ON_EDIT: &str = "workflow_format_on_edit";
+/// Consecutive identical tool calls after which a ReAct workflow is warned, and stopped when
+/// it repeats the call once more
+pub const CFG_WORKFLOW_LOOP_REPEAT_THRESHOLD: &str = "workflow_loop_repeat_threshold";
+/// Whether a failed todo fails only its dependents (`continue`) or the whole plan (`fail_fast`)
+pub const CFG_WORKFLOW_TODO_FAILURE_POLICY: &str = "workflow_todo_failure_policy";
+pub const CFG_TOOL_METRICS_SNAPSHOT: &str = "tool_metrics_snapshot";
+pub const CFG_DB_OPTIMIZE_INTERVAL_DAYS: &str = "db_optimize_interval_days";
+pub const CFG_DB_LAST_OPTIMIZED_AT: &str = "db_last_optimized_at";
+pub const CFG_MCP_SAMPLING_MODEL: &str = "mcp_sampling_model";
+pub const CFG_MCP_ROOTS: &str = "mcp_roots";
+pub const CFG_MCP_WS_ENABLED: &str = "mcp_ws_enabled";
+pub const CFG_MCP_SERVER_AUTH: &str = "mcp_server_auth";
+pub const CFG_MCP_SERVER_TOOL_APPROVAL: &str = "mcp_server_tool_approval";
 pub const CFG_SCRAPER_DEBUG_MODE: &str = "scraper_debug_mode";
+pub const CFG_WEB_FETCH_ROTATE_USER_AGENT: &str = "web_fetch_rotate_user_agent";
 pub const DEFAULT_WEB_SEARCH_TOOL: &str = "WebSearch";
 pub const DEFAULT_WEB_FETCH_TOOL: &str = "WebFetch";
 // pub const CFG_SCRAPER_CONCURRENCY_COUNT: &str = "scraper_concurrency_count";
@@ -259,3 +318,4 @@ pub static RESTRICTED_EXTENSIONS: phf::Set<&'static str> = phf::phf_set! {
 // Internal parameter names for tool execution context
 // These are injected by the workflow engine and should be removed before tool processing
 pub const INTERNAL_PARAM_TOOL_CALL_ID: &str = "__inner_tool_call_id";
+pub const INTERNAL_PARAM_SESSION_ID: &str = "__inner_session_id";
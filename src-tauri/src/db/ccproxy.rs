@@ -1,5 +1,52 @@
-use crate::db::{error::StoreError, types::CcproxyStat, MainStore};
+use crate::db::{
+    error::StoreError,
+    types::{
+        CcproxyAuditEntry, CcproxyAuditQuery, CcproxyDeadLetter, CcproxyStat, CcproxyTokenUsage,
+    },
+    MainStore,
+};
 use rusqlite::params;
+use rust_i18n::t;
+
+const DEAD_LETTER_COLUMNS: &str = "id, request_id, client_model, backend_model, provider_id, provider, protocol, backend_protocol, status_code, error_message, request_body, request_truncated, duration_ms, created_at";
+
+fn dead_letter_from_row(row: &rusqlite::Row) -> rusqlite::Result<CcproxyDeadLetter> {
+    Ok(CcproxyDeadLetter {
+        id: row.get(0)?,
+        request_id: row.get(1)?,
+        client_model: row.get(2)?,
+        backend_model: row.get(3)?,
+        provider_id: row.get(4)?,
+        provider: row.get(5)?,
+        protocol: row.get(6)?,
+        backend_protocol: row.get(7)?,
+        status_code: row.get(8)?,
+        error_message: row.get(9)?,
+        request_body: row.get(10)?,
+        request_truncated: row.get(11)?,
+        duration_ms: row.get(12)?,
+        created_at: row.get(13)?,
+    })
+}
+
+const AUDIT_LOG_COLUMNS: &str = "id, request_id, protocol, client_model, backend_model, provider_id, provider, key_id, prompt_tokens, prompt_hash, prompt_content, created_at";
+
+fn audit_entry_from_row(row: &rusqlite::Row) -> rusqlite::Result<CcproxyAuditEntry> {
+    Ok(CcproxyAuditEntry {
+        id: row.get(0)?,
+        request_id: row.get(1)?,
+        protocol: row.get(2)?,
+        client_model: row.get(3)?,
+        backend_model: row.get(4)?,
+        provider_id: row.get(5)?,
+        provider: row.get(6)?,
+        key_id: row.get(7)?,
+        prompt_tokens: row.get(8)?,
+        prompt_hash: row.get(9)?,
+        prompt_content: row.get(10)?,
+        created_at: row.get(11)?,
+    })
+}
 
 impl MainStore {
     /// Records a new proxy statistic entry in the database.
@@ -115,6 +162,223 @@ impl MainStore {
         Ok(stats)
     }
 
+    /// Sums the token usage per provider, backend model and local day since `since`
+    /// (`YYYY-MM-DD`, inclusive).
+    pub fn get_ccproxy_token_usage_since(
+        &self,
+        since: &str,
+    ) -> Result<Vec<CcproxyTokenUsage>, StoreError> {
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| StoreError::LockError(e.to_string()))?;
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT provider_id, backend_model, DATE(request_at, 'localtime') as date,
+                    SUM(input_tokens), SUM(output_tokens), SUM(cache_tokens)
+                 FROM ccproxy_stats
+                 WHERE DATE(request_at, 'localtime') >= ?1
+                 GROUP BY provider_id, backend_model, date",
+            )
+            .map_err(|e| StoreError::Query(e.to_string()))?;
+
+        let rows = stmt
+            .query_map(params![since], |row| {
+                Ok(CcproxyTokenUsage {
+                    provider_id: row.get(0)?,
+                    backend_model: row.get(1)?,
+                    date: row.get(2)?,
+                    input_tokens: row.get::<_, Option<i64>>(3)?.unwrap_or(0),
+                    output_tokens: row.get::<_, Option<i64>>(4)?.unwrap_or(0),
+                    cache_tokens: row.get::<_, Option<i64>>(5)?.unwrap_or(0),
+                })
+            })
+            .map_err(|e| StoreError::Query(e.to_string()))?;
+
+        let mut usage = Vec::new();
+        for row in rows {
+            usage.push(row.map_err(|e| StoreError::Query(e.to_string()))?);
+        }
+        Ok(usage)
+    }
+
+    /// Stores a failed request in the dead-letter log, keeping the newest `max_entries`.
+    pub fn record_ccproxy_dead_letter(
+        &self,
+        letter: &CcproxyDeadLetter,
+        max_entries: usize,
+    ) -> Result<i64, StoreError> {
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| StoreError::LockError(e.to_string()))?;
+
+        conn.execute(
+            "INSERT INTO ccproxy_dead_letters (request_id, client_model, backend_model, provider_id, provider, protocol, backend_protocol, status_code, error_message, request_body, request_truncated, duration_ms)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+            params![
+                letter.request_id,
+                letter.client_model,
+                letter.backend_model,
+                letter.provider_id,
+                letter.provider,
+                letter.protocol,
+                letter.backend_protocol,
+                letter.status_code,
+                letter.error_message,
+                letter.request_body,
+                letter.request_truncated,
+                letter.duration_ms,
+            ],
+        )
+        .map_err(|e| StoreError::Query(e.to_string()))?;
+        let id = conn.last_insert_rowid();
+
+        conn.execute(
+            "DELETE FROM ccproxy_dead_letters WHERE id NOT IN
+                (SELECT id FROM ccproxy_dead_letters ORDER BY id DESC LIMIT ?1)",
+            params![max_entries as i64],
+        )
+        .map_err(|e| StoreError::Query(e.to_string()))?;
+
+        Ok(id)
+    }
+
+    /// Lists the dead-letter log, newest first.
+    pub fn get_ccproxy_dead_letters(
+        &self,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<CcproxyDeadLetter>, StoreError> {
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| StoreError::LockError(e.to_string()))?;
+
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT {} FROM ccproxy_dead_letters ORDER BY id DESC LIMIT ?1 OFFSET ?2",
+                DEAD_LETTER_COLUMNS
+            ))
+            .map_err(|e| StoreError::Query(e.to_string()))?;
+
+        let rows = stmt
+            .query_map(params![limit, offset], dead_letter_from_row)
+            .map_err(|e| StoreError::Query(e.to_string()))?;
+
+        let mut letters = Vec::new();
+        for row in rows {
+            letters.push(row.map_err(|e| StoreError::Query(e.to_string()))?);
+        }
+        Ok(letters)
+    }
+
+    /// Retrieves one entry of the dead-letter log.
+    pub fn get_ccproxy_dead_letter(&self, id: i64) -> Result<CcproxyDeadLetter, StoreError> {
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| StoreError::LockError(e.to_string()))?;
+
+        conn.query_row(
+            &format!(
+                "SELECT {} FROM ccproxy_dead_letters WHERE id = ?1",
+                DEAD_LETTER_COLUMNS
+            ),
+            params![id],
+            dead_letter_from_row,
+        )
+        .map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => {
+                StoreError::NotFound(t!("db.ccproxy_dead_letter_not_found", id = id).to_string())
+            }
+            e => StoreError::Query(e.to_string()),
+        })
+    }
+
+    /// Clears the dead-letter log.
+    pub fn delete_ccproxy_dead_letters(&self) -> Result<(), StoreError> {
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| StoreError::LockError(e.to_string()))?;
+
+        conn.execute("DELETE FROM ccproxy_dead_letters", params![])
+            .map_err(|e| StoreError::Query(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Appends an entry to the audit log, entries are never updated or rotated out.
+    pub fn record_ccproxy_audit_entry(&self, entry: &CcproxyAuditEntry) -> Result<i64, StoreError> {
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| StoreError::LockError(e.to_string()))?;
+
+        conn.execute(
+            "INSERT INTO ccproxy_audit_log (request_id, protocol, client_model, backend_model, provider_id, provider, key_id, prompt_tokens, prompt_hash, prompt_content)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                entry.request_id,
+                entry.protocol,
+                entry.client_model,
+                entry.backend_model,
+                entry.provider_id,
+                entry.provider,
+                entry.key_id,
+                entry.prompt_tokens,
+                entry.prompt_hash,
+                entry.prompt_content,
+            ],
+        )
+        .map_err(|e| StoreError::Query(e.to_string()))?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    /// Lists the audit log entries matching the query, newest first.
+    ///
+    /// Without a `limit`, every matching entry is returned.
+    pub fn get_ccproxy_audit_log(
+        &self,
+        query: &CcproxyAuditQuery,
+    ) -> Result<Vec<CcproxyAuditEntry>, StoreError> {
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| StoreError::LockError(e.to_string()))?;
+
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT {} FROM ccproxy_audit_log
+                 WHERE (?1 IS NULL OR created_at >= ?1)
+                   AND (?2 IS NULL OR created_at < ?2)
+                   AND (?3 IS NULL OR client_model = ?3 OR backend_model = ?3)
+                 ORDER BY id DESC LIMIT ?4 OFFSET ?5",
+                AUDIT_LOG_COLUMNS
+            ))
+            .map_err(|e| StoreError::Query(e.to_string()))?;
+
+        let rows = stmt
+            .query_map(
+                params![
+                    query.since,
+                    query.until,
+                    query.model,
+                    query.limit.unwrap_or(-1),
+                    query.offset.unwrap_or(0),
+                ],
+                audit_entry_from_row,
+            )
+            .map_err(|e| StoreError::Query(e.to_string()))?;
+
+        let mut entries = Vec::new();
+        for row in rows {
+            entries.push(row.map_err(|e| StoreError::Query(e.to_string()))?);
+        }
+        Ok(entries)
+    }
+
     /// Deletes proxy statistics older than a certain number of days.
     /// If days is -1, deletes all statistics.
     pub fn delete_ccproxy_stats(&self, days: i32) -> Result<(), StoreError> {
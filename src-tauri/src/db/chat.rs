@@ -2,9 +2,14 @@ use super::types::{Conversation, Message};
 use crate::db::error::StoreError;
 use crate::db::main_store::MainStore;
 
-use rusqlite::params;
+use std::collections::HashSet;
+
+use rusqlite::{params, Connection};
 use rust_i18n::t;
-use serde_json::Value;
+use serde_json::{Map, Value};
+
+/// Metadata flag of a message whose stream has not finished, so its content may be truncated.
+pub const MESSAGE_INCOMPLETE_KEY: &str = "incomplete";
 
 impl MainStore {
     /// Retrieves a conversation by its ID.
@@ -25,7 +30,8 @@ impl MainStore {
             .map_err(|e| StoreError::LockError(e.to_string()))?;
         let conversation = conn
             .query_row(
-                "SELECT id, title, created_at, is_favorite FROM conversations WHERE id = ?",
+                "SELECT id, title, created_at, is_favorite, is_pinned, is_archived
+                 FROM conversations WHERE id = ?",
                 [id],
                 |row| {
                     Ok(Conversation {
@@ -33,6 +39,8 @@ impl MainStore {
                         title: row.get("title")?,
                         created_at: row.get("created_at")?,
                         is_favorite: row.get("is_favorite")?,
+                        is_pinned: row.get("is_pinned")?,
+                        is_archived: row.get("is_archived")?,
                     })
                 },
             )
@@ -49,6 +57,12 @@ impl MainStore {
     // TODO: add pagination to get_all_conversations
     /// Retrieves all conversation topics from the database.
     ///
+    /// Pinned conversations come first, each group ordered by its latest activity.
+    ///
+    /// # Arguments
+    ///
+    /// * `include_archived` - Whether archived conversations are listed too.
+    ///
     /// # Returns
     ///
     /// A vector of `Conversation` instances.
@@ -56,25 +70,31 @@ impl MainStore {
     /// # Errors
     ///
     /// Returns a `StoreError` if the database operation fails.
-    pub fn get_all_conversations(&self) -> Result<Vec<Conversation>, StoreError> {
+    pub fn get_all_conversations(
+        &self,
+        include_archived: bool,
+    ) -> Result<Vec<Conversation>, StoreError> {
         let conn = self
             .conn
             .lock()
             .map_err(|e| StoreError::LockError(e.to_string()))?;
         let mut stmt = conn.prepare(
-            "SELECT c.id, c.title, c.is_favorite, 
+            "SELECT c.id, c.title, c.is_favorite, c.is_pinned, c.is_archived,
                     COALESCE(MAX(m.timestamp), c.created_at) as active_time 
              FROM conversations c 
              LEFT JOIN messages m ON c.id = m.conversation_id 
+             WHERE ?1 OR NOT c.is_archived
              GROUP BY c.id 
-             ORDER BY active_time DESC",
+             ORDER BY c.is_pinned DESC, active_time DESC",
         )?;
-        let conversations = stmt.query_map([], |row| {
+        let conversations = stmt.query_map([include_archived], |row| {
             Ok(Conversation {
                 id: row.get("id")?,
                 title: row.get("title")?,
                 created_at: row.get("active_time")?,
                 is_favorite: row.get("is_favorite")?,
+                is_pinned: row.get("is_pinned")?,
+                is_archived: row.get("is_archived")?,
             })
         })?;
         conversations
@@ -201,6 +221,50 @@ impl MainStore {
         Ok(())
     }
 
+    /// Pins a conversation to the top of the list, or unpins it.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The ID of the conversation to update.
+    /// * `pinned` - The new pinned state.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `StoreError` if the database operation fails.
+    pub fn set_conversation_pinned(&self, id: i64, pinned: bool) -> Result<(), StoreError> {
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| StoreError::LockError(e.to_string()))?;
+        conn.execute(
+            "UPDATE conversations SET is_pinned = ? WHERE id = ?",
+            params![pinned, id],
+        )?;
+        Ok(())
+    }
+
+    /// Archives a conversation, hiding it from the default list, or restores it.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The ID of the conversation to update.
+    /// * `archived` - The new archived state.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `StoreError` if the database operation fails.
+    pub fn set_conversation_archived(&self, id: i64, archived: bool) -> Result<(), StoreError> {
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| StoreError::LockError(e.to_string()))?;
+        conn.execute(
+            "UPDATE conversations SET is_archived = ? WHERE id = ?",
+            params![archived, id],
+        )?;
+        Ok(())
+    }
+
     /// Deletes a conversation from the database.
     ///
     /// Removes the record with the specified ID from the `conversations` table.
@@ -267,6 +331,135 @@ impl MainStore {
         Ok(conn.last_insert_rowid())
     }
 
+    /// Adds an empty message flagged incomplete, to be filled while its response streams.
+    ///
+    /// # Arguments
+    ///
+    /// * `conversation_id` - The ID of the conversation to which the message belongs.
+    /// * `role` - The role of the message sender.
+    /// * `metadata` - The metadata of the message, the incomplete flag is added to it.
+    ///
+    /// # Returns
+    ///
+    /// The ID of the newly inserted message.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `StoreError` if the database operation fails.
+    pub fn add_streaming_message(
+        &self,
+        conversation_id: i64,
+        role: String,
+        metadata: Option<Value>,
+    ) -> Result<i64, StoreError> {
+        let mut metadata = metadata_object(metadata);
+        metadata.insert(MESSAGE_INCOMPLETE_KEY.to_string(), Value::Bool(true));
+        self.add_message(
+            conversation_id,
+            role,
+            String::new(),
+            Some(Value::Object(metadata)),
+        )
+    }
+
+    /// Writes the content streamed so far to a message added by `add_streaming_message`.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The ID of the message.
+    /// * `content` - The whole content received so far.
+    /// * `reasoning` - The whole reasoning received so far, kept in the `reasoning` metadata.
+    /// * `complete` - Whether the stream has finished, which clears the incomplete flag.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `StoreError` if the database operation fails.
+    pub fn save_streaming_message(
+        &self,
+        id: i64,
+        content: &str,
+        reasoning: &str,
+        complete: bool,
+    ) -> Result<(), StoreError> {
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| StoreError::LockError(e.to_string()))?;
+
+        let metadata_str: Option<String> = conn
+            .query_row("SELECT metadata FROM messages WHERE id = ?", [id], |row| {
+                row.get(0)
+            })
+            .map_err(|e| {
+                if e == rusqlite::Error::QueryReturnedNoRows {
+                    StoreError::NotFound(t!("db.message_not_found").to_string())
+                } else {
+                    StoreError::from(e)
+                }
+            })?;
+        let mut metadata =
+            metadata_object(metadata_str.and_then(|m| serde_json::from_str(&m).ok()));
+        if !reasoning.is_empty() {
+            metadata.insert(
+                "reasoning".to_string(),
+                Value::String(reasoning.to_string()),
+            );
+        }
+        if complete {
+            metadata.remove(MESSAGE_INCOMPLETE_KEY);
+        } else {
+            metadata.insert(MESSAGE_INCOMPLETE_KEY.to_string(), Value::Bool(true));
+        }
+        let metadata_str = serde_json::to_string(&metadata).map_err(|e| {
+            StoreError::JsonError(
+                t!("db.json_serialize_failed_metadata", error = e.to_string()).to_string(),
+            )
+        })?;
+
+        conn.execute(
+            "UPDATE messages SET content = ?, metadata = ? WHERE id = ?",
+            params![content, metadata_str, id],
+        )?;
+        Ok(())
+    }
+
+    /// Replaces the content and metadata of a message.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The ID of the message to update.
+    /// * `content` - The new content of the message.
+    /// * `metadata` - The new metadata of the message.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `StoreError` if the database operation fails.
+    pub fn update_message(
+        &self,
+        id: i64,
+        content: String,
+        metadata: Option<Value>,
+    ) -> Result<(), StoreError> {
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| StoreError::LockError(e.to_string()))?;
+        let metadata_str = metadata
+            .map(|m| serde_json::to_string(&m))
+            .transpose()
+            .map_err(|e| {
+                StoreError::JsonError(
+                    t!("db.json_serialize_failed_metadata", error = e.to_string()).to_string(),
+                )
+            })?;
+
+        conn.execute(
+            "UPDATE messages SET content = ?, metadata = ? WHERE id = ?",
+            params![content, metadata_str, id],
+        )?;
+        Ok(())
+    }
+
     /// Deletes messages from the database.
     ///
     /// Removes the records with the specified IDs from the `messages` table.
@@ -297,6 +490,146 @@ impl MainStore {
         Ok(())
     }
 
+    /// Deletes messages together with the messages sharing a tool call with them.
+    ///
+    /// A tool call and its result stay paired: deleting a message that calls a tool also
+    /// deletes the message holding the result, and the other way round.
+    ///
+    /// # Arguments
+    ///
+    /// * `ids` - The IDs of the messages to be deleted.
+    ///
+    /// # Returns
+    ///
+    /// The IDs of all deleted messages, in ascending order.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `StoreError` if the database operation fails.
+    pub fn delete_messages(&self, ids: &[i64]) -> Result<Vec<i64>, StoreError> {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut conn = self
+            .conn
+            .lock()
+            .map_err(|e| StoreError::LockError(e.to_string()))?;
+
+        let placeholders = vec!["?"; ids.len()].join(",");
+        let mut stmt = conn.prepare(&format!(
+            "SELECT DISTINCT conversation_id FROM messages WHERE id IN ({})",
+            placeholders
+        ))?;
+        let conversation_ids = stmt
+            .query_map(rusqlite::params_from_iter(ids), |row| row.get::<_, i64>(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        drop(stmt);
+
+        let mut deleted: HashSet<i64> = ids.iter().copied().collect();
+        for conversation_id in conversation_ids {
+            let rows = tool_call_rows(&conn, conversation_id)?;
+            extend_tool_call_groups(&rows, &mut deleted);
+        }
+        let mut deleted: Vec<i64> = deleted.into_iter().collect();
+        deleted.sort_unstable();
+
+        delete_message_rows(&mut conn, &deleted)?;
+        Ok(deleted)
+    }
+
+    /// Deletes every message of a conversation that comes after the given one.
+    ///
+    /// Messages holding the results of tool calls made up to the given message are kept, so
+    /// the truncated conversation never ends with an unanswered call.
+    ///
+    /// # Arguments
+    ///
+    /// * `message_id` - The ID of the last message to keep.
+    ///
+    /// # Returns
+    ///
+    /// The IDs of the deleted messages, in ascending order.
+    ///
+    /// # Errors
+    ///
+    /// Returns `StoreError::NotFound` if the message does not exist, or a `StoreError` if the
+    /// database operation fails.
+    pub fn delete_messages_after(&self, message_id: i64) -> Result<Vec<i64>, StoreError> {
+        let mut conn = self
+            .conn
+            .lock()
+            .map_err(|e| StoreError::LockError(e.to_string()))?;
+
+        let conversation_id: i64 = conn
+            .query_row(
+                "SELECT conversation_id FROM messages WHERE id = ?",
+                [message_id],
+                |row| row.get(0),
+            )
+            .map_err(|e| {
+                if e == rusqlite::Error::QueryReturnedNoRows {
+                    StoreError::NotFound(t!("db.message_not_found").to_string())
+                } else {
+                    StoreError::from(e)
+                }
+            })?;
+
+        let rows = tool_call_rows(&conn, conversation_id)?;
+        let mut kept: HashSet<i64> = rows
+            .iter()
+            .map(|(id, _)| *id)
+            .filter(|id| *id <= message_id)
+            .collect();
+        extend_tool_call_groups(&rows, &mut kept);
+        let deleted: Vec<i64> = rows
+            .iter()
+            .map(|(id, _)| *id)
+            .filter(|id| !kept.contains(id))
+            .collect();
+
+        delete_message_rows(&mut conn, &deleted)?;
+        Ok(deleted)
+    }
+
+    /// Deletes all messages of a conversation, keeping the conversation itself.
+    ///
+    /// # Arguments
+    ///
+    /// * `conversation_id` - The ID of the conversation to clear.
+    ///
+    /// # Returns
+    ///
+    /// The number of deleted messages.
+    ///
+    /// # Errors
+    ///
+    /// Returns `StoreError::NotFound` if the conversation does not exist, or a `StoreError`
+    /// if the database operation fails.
+    pub fn clear_conversation(&self, conversation_id: i64) -> Result<usize, StoreError> {
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| StoreError::LockError(e.to_string()))?;
+
+        let exists: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM conversations WHERE id = ?)",
+            [conversation_id],
+            |row| row.get(0),
+        )?;
+        if !exists {
+            return Err(StoreError::NotFound(
+                t!("db.conversation_not_found").to_string(),
+            ));
+        }
+
+        let deleted = conn.execute(
+            "DELETE FROM messages WHERE conversation_id = ?",
+            [conversation_id],
+        )?;
+        Ok(deleted)
+    }
+
     /// Updates the metadata of a message.
     ///
     /// # Arguments
@@ -331,4 +664,260 @@ impl MainStore {
         )?;
         Ok(())
     }
+
+    /// Retrieves the metadata of a message.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The ID of the message.
+    ///
+    /// # Returns
+    ///
+    /// The parsed metadata, `None` if the message has none or it is not valid JSON.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `StoreError::NotFound` if the message does not exist.
+    pub fn get_message_metadata(&self, id: i64) -> Result<Option<Value>, StoreError> {
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| StoreError::LockError(e.to_string()))?;
+
+        let metadata_str: Option<String> = conn
+            .query_row("SELECT metadata FROM messages WHERE id = ?", [id], |row| {
+                row.get(0)
+            })
+            .map_err(|e| {
+                if e == rusqlite::Error::QueryReturnedNoRows {
+                    StoreError::NotFound(t!("db.message_not_found").to_string())
+                } else {
+                    StoreError::from(e)
+                }
+            })?;
+        Ok(metadata_str.and_then(|m| serde_json::from_str(&m).ok()))
+    }
+}
+
+/// The fields of a metadata object, empty for missing or non-object metadata.
+fn metadata_object(metadata: Option<Value>) -> Map<String, Value> {
+    match metadata {
+        Some(Value::Object(map)) => map,
+        _ => Map::new(),
+    }
+}
+
+/// Tool call ids a message takes part in: the ids of its calls in `metadata.toolCall` and
+/// the id answered by a result message in `metadata.tool_call_id`.
+fn tool_call_ids(metadata: Option<&str>) -> Vec<String> {
+    let Some(metadata) = metadata.and_then(|m| serde_json::from_str::<Value>(m).ok()) else {
+        return Vec::new();
+    };
+    let mut ids: Vec<String> = metadata
+        .get("toolCall")
+        .and_then(Value::as_array)
+        .map(|calls| {
+            calls
+                .iter()
+                .filter_map(|call| call.get("id").and_then(Value::as_str))
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default();
+    if let Some(id) = metadata.get("tool_call_id").and_then(Value::as_str) {
+        ids.push(id.to_string());
+    }
+    ids
+}
+
+/// Messages of a conversation in ascending order, with the tool call ids each takes part in.
+fn tool_call_rows(
+    conn: &Connection,
+    conversation_id: i64,
+) -> Result<Vec<(i64, Vec<String>)>, StoreError> {
+    let mut stmt =
+        conn.prepare("SELECT id, metadata FROM messages WHERE conversation_id = ? ORDER BY id")?;
+    let rows = stmt
+        .query_map([conversation_id], |row| {
+            let metadata: Option<String> = row.get(1)?;
+            Ok((row.get::<_, i64>(0)?, tool_call_ids(metadata.as_deref())))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
+
+/// Adds to `selected` every message sharing a tool call id with a selected message, until no
+/// message is added anymore.
+fn extend_tool_call_groups(rows: &[(i64, Vec<String>)], selected: &mut HashSet<i64>) {
+    loop {
+        let call_ids: HashSet<&String> = rows
+            .iter()
+            .filter(|(id, _)| selected.contains(id))
+            .flat_map(|(_, ids)| ids)
+            .collect();
+        let before = selected.len();
+        selected.extend(
+            rows.iter()
+                .filter(|(_, ids)| ids.iter().any(|id| call_ids.contains(id)))
+                .map(|(id, _)| *id),
+        );
+        if selected.len() == before {
+            return;
+        }
+    }
+}
+
+fn delete_message_rows(conn: &mut Connection, ids: &[i64]) -> Result<(), StoreError> {
+    if ids.is_empty() {
+        return Ok(());
+    }
+    let tx = conn.transaction()?;
+    {
+        let mut stmt = tx.prepare("DELETE FROM messages WHERE id = ?")?;
+        for id in ids {
+            stmt.execute([id])?;
+        }
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::db::MainStore;
+    use serde_json::json;
+    use tempfile::tempdir;
+
+    fn titles(store: &MainStore, include_archived: bool) -> Vec<String> {
+        store
+            .get_all_conversations(include_archived)
+            .expect("failed to list conversations")
+            .into_iter()
+            .map(|conversation| conversation.title)
+            .collect()
+    }
+
+    fn add_with_activity(store: &MainStore, title: &str, timestamp: &str) -> i64 {
+        let id = store
+            .add_conversation(title.to_string())
+            .expect("failed to add conversation");
+        let message_id = store
+            .add_message(id, "user".to_string(), title.to_string(), None)
+            .expect("failed to add message");
+        let conn = store.conn.lock().expect("failed to lock db connection");
+        conn.execute(
+            "UPDATE messages SET timestamp = ?1 WHERE id = ?2",
+            rusqlite::params![timestamp, message_id],
+        )
+        .expect("failed to set message timestamp");
+        id
+    }
+
+    #[test]
+    fn test_pinned_conversations_come_first() {
+        let dir = tempdir().expect("failed to create temp dir");
+        let store =
+            MainStore::new(dir.path().join("chat_test.db")).expect("failed to create store");
+        let old = add_with_activity(&store, "old", "2024-01-01 00:00:00");
+        add_with_activity(&store, "recent", "2024-03-01 00:00:00");
+        let older = add_with_activity(&store, "older", "2023-06-01 00:00:00");
+        assert_eq!(titles(&store, false), vec!["recent", "old", "older"]);
+
+        store.set_conversation_pinned(older, true).expect("pin");
+        store.set_conversation_pinned(old, true).expect("pin");
+        assert_eq!(titles(&store, false), vec!["old", "older", "recent"]);
+        assert!(store.get_conversation_by_id(old).expect("get").is_pinned);
+
+        store.set_conversation_pinned(old, false).expect("unpin");
+        assert_eq!(titles(&store, false), vec!["older", "recent", "old"]);
+    }
+
+    #[test]
+    fn test_archived_conversations_are_filtered_out() {
+        let dir = tempdir().expect("failed to create temp dir");
+        let store =
+            MainStore::new(dir.path().join("chat_test.db")).expect("failed to create store");
+        let done = add_with_activity(&store, "done", "2024-01-01 00:00:00");
+        add_with_activity(&store, "active", "2024-02-01 00:00:00");
+
+        store
+            .set_conversation_archived(done, true)
+            .expect("archive");
+        assert_eq!(titles(&store, false), vec!["active"]);
+        assert_eq!(titles(&store, true), vec!["active", "done"]);
+        assert!(store.get_conversation_by_id(done).expect("get").is_archived);
+
+        store
+            .set_conversation_archived(done, false)
+            .expect("restore");
+        assert_eq!(titles(&store, false), vec!["active", "done"]);
+    }
+
+    #[test]
+    fn test_delete_after_keeps_tool_results_of_kept_calls() {
+        let dir = tempdir().expect("failed to create temp dir");
+        let store =
+            MainStore::new(dir.path().join("chat_test.db")).expect("failed to create store");
+        let conversation = store
+            .add_conversation("tools".to_string())
+            .expect("failed to add conversation");
+        let add = |role: &str, metadata: Option<serde_json::Value>| {
+            store
+                .add_message(conversation, role.to_string(), role.to_string(), metadata)
+                .expect("failed to add message")
+        };
+        let question = add("user", None);
+        let call = add(
+            "assistant",
+            Some(json!({"toolCall": [{"id": "call_1", "function": {"name": "read"}}]})),
+        );
+        let result = add("tool", Some(json!({"tool_call_id": "call_1"})));
+        let follow_up = add("user", None);
+        let answer = add("assistant", Some(json!({"toolCall": []})));
+
+        let deleted = store.delete_messages_after(call).expect("truncate");
+        assert_eq!(deleted, vec![follow_up, answer]);
+        let kept: Vec<i64> = store
+            .get_messages_for_conversation(conversation)
+            .expect("failed to list messages")
+            .into_iter()
+            .map(|message| message.id.expect("message id"))
+            .collect();
+        assert_eq!(kept, vec![question, call, result]);
+
+        // Deleting the result takes its call along
+        let deleted = store.delete_messages(&[result]).expect("delete");
+        assert_eq!(deleted, vec![call, result]);
+        assert!(store.delete_messages_after(answer).is_err());
+    }
+
+    #[test]
+    fn test_clear_conversation_keeps_the_conversation() {
+        let dir = tempdir().expect("failed to create temp dir");
+        let store =
+            MainStore::new(dir.path().join("chat_test.db")).expect("failed to create store");
+        let cleared = add_with_activity(&store, "cleared", "2024-01-01 00:00:00");
+        let other = add_with_activity(&store, "other", "2024-02-01 00:00:00");
+        store
+            .add_message(cleared, "assistant".to_string(), "hi".to_string(), None)
+            .expect("failed to add message");
+
+        assert_eq!(store.clear_conversation(cleared).expect("clear"), 2);
+        assert!(store
+            .get_messages_for_conversation(cleared)
+            .expect("failed to list messages")
+            .is_empty());
+        assert_eq!(
+            store.get_conversation_by_id(cleared).expect("get").title,
+            "cleared"
+        );
+        assert_eq!(
+            store
+                .get_messages_for_conversation(other)
+                .expect("failed to list messages")
+                .len(),
+            1
+        );
+        assert!(store.clear_conversation(other + 100).is_err());
+    }
 }
@@ -0,0 +1,191 @@
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+
+use crate::constants::{CFG_DB_LAST_OPTIMIZED_AT, CFG_DB_OPTIMIZE_INTERVAL_DAYS};
+use crate::db::{MainStore, StoreError};
+
+/// How often the scheduler checks whether an optimization is due.
+const SCHEDULE_CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Outcome of an `optimize_database` run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DatabaseOptimizeReport {
+    pub size_before: u64,
+    pub size_after: u64,
+    pub freed_bytes: u64,
+    /// Full-text search tables whose index was rebuilt
+    pub fts_tables: Vec<String>,
+    pub duration_ms: u64,
+}
+
+fn database_size(conn: &Connection) -> Result<u64, StoreError> {
+    let page_count: i64 = conn.query_row("PRAGMA page_count", [], |row| row.get(0))?;
+    let page_size: i64 = conn.query_row("PRAGMA page_size", [], |row| row.get(0))?;
+    Ok((page_count * page_size).max(0) as u64)
+}
+
+fn fts_tables(conn: &Connection) -> Result<Vec<String>, StoreError> {
+    let mut stmt = conn.prepare(
+        "SELECT name FROM sqlite_master
+         WHERE type = 'table' AND upper(sql) LIKE 'CREATE VIRTUAL TABLE%USING FTS%'
+         ORDER BY name",
+    )?;
+    let names = stmt
+        .query_map([], |row| row.get(0))?
+        .collect::<Result<Vec<String>, _>>()?;
+    Ok(names)
+}
+
+impl MainStore {
+    /// Rebuilds the full-text indexes, vacuums the database and reports the space freed.
+    ///
+    /// Takes `&mut self` so that callers must hold the write lock of the shared store: no
+    /// other command can read or write while the database is being rewritten.
+    pub fn optimize_database(&mut self) -> Result<DatabaseOptimizeReport, StoreError> {
+        let started = std::time::Instant::now();
+        let report = {
+            let conn = self
+                .conn
+                .lock()
+                .map_err(|e| StoreError::LockError(e.to_string()))?;
+
+            let size_before = database_size(&conn)?;
+            let fts_tables = fts_tables(&conn)?;
+            for table in &fts_tables {
+                let name = table.replace('"', "\"\"");
+                conn.execute(
+                    &format!("INSERT INTO \"{0}\"(\"{0}\") VALUES('rebuild')", name),
+                    [],
+                )?;
+            }
+            conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))?;
+            conn.execute_batch("VACUUM; PRAGMA optimize;")?;
+            let size_after = database_size(&conn)?;
+
+            DatabaseOptimizeReport {
+                size_before,
+                size_after,
+                freed_bytes: size_before.saturating_sub(size_after),
+                fts_tables,
+                duration_ms: started.elapsed().as_millis() as u64,
+            }
+        };
+
+        self.set_config(
+            CFG_DB_LAST_OPTIMIZED_AT,
+            &serde_json::json!(chrono::Utc::now().timestamp()),
+        )?;
+        log::info!(
+            "Database optimized in {}ms: {} -> {} bytes, {} FTS tables rebuilt",
+            report.duration_ms,
+            report.size_before,
+            report.size_after,
+            report.fts_tables.len()
+        );
+        Ok(report)
+    }
+}
+
+/// Runs `optimize_database` every `db_optimize_interval_days` days, counted from the last
+/// run, manual runs included. An interval of `0` turns the schedule off.
+pub fn spawn_scheduled_optimize(main_store: Arc<RwLock<MainStore>>) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(SCHEDULE_CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            let store = main_store.clone();
+            if let Err(e) = tokio::task::spawn_blocking(move || run_if_due(&store)).await {
+                log::warn!("Scheduled database optimization panicked: {}", e);
+            }
+        }
+    });
+}
+
+fn run_if_due(main_store: &RwLock<MainStore>) {
+    let due = match main_store.read() {
+        Ok(store) => {
+            let days: u64 = store.get_config(CFG_DB_OPTIMIZE_INTERVAL_DAYS, 0);
+            let last_run: i64 = store.get_config(CFG_DB_LAST_OPTIMIZED_AT, 0);
+            days > 0 && chrono::Utc::now().timestamp() - last_run >= (days * 86_400) as i64
+        }
+        Err(e) => {
+            log::warn!("Failed to lock main store for database optimization: {}", e);
+            return;
+        }
+    };
+    if !due {
+        return;
+    }
+
+    match main_store.write() {
+        Ok(mut store) => {
+            if let Err(e) = store.optimize_database() {
+                log::error!("Scheduled database optimization failed: {}", e);
+            }
+        }
+        Err(e) => log::warn!("Failed to lock main store for database optimization: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::db::MainStore;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_optimize_leaves_database_usable() {
+        let dir = tempdir().expect("failed to create temp dir");
+        let mut store =
+            MainStore::new(dir.path().join("maintenance_test.db")).expect("failed to create store");
+
+        {
+            let conn = store.conn.lock().expect("failed to lock db connection");
+            conn.execute_batch(
+                "CREATE VIRTUAL TABLE notes_fts USING fts5(body);
+                 INSERT INTO notes_fts(body) VALUES ('sqlite maintenance');",
+            )
+            .expect("failed to create fts table");
+        }
+        let stale = store
+            .add_conversation("stale".to_string())
+            .expect("failed to add conversation");
+        for _ in 0..50 {
+            store
+                .add_message(stale, "user".to_string(), "x".repeat(4096), None)
+                .expect("failed to add message");
+        }
+        store
+            .delete_conversation(stale)
+            .expect("failed to delete conversation");
+
+        let report = store.optimize_database().expect("optimize failed");
+        assert_eq!(report.fts_tables, vec!["notes_fts".to_string()]);
+        assert!(report.size_after <= report.size_before);
+        assert_eq!(report.freed_bytes, report.size_before - report.size_after);
+
+        let kept = store
+            .add_conversation("kept".to_string())
+            .expect("failed to write after optimize");
+        store
+            .add_message(kept, "user".to_string(), "hello".to_string(), None)
+            .expect("failed to write after optimize");
+        let messages = store
+            .get_messages_for_conversation(kept)
+            .expect("failed to read after optimize");
+        assert_eq!(messages.len(), 1);
+
+        let conn = store.conn.lock().expect("failed to lock db connection");
+        let hits: i64 = conn
+            .query_row(
+                "SELECT count(*) FROM notes_fts WHERE notes_fts MATCH 'maintenance'",
+                [],
+                |row| row.get(0),
+            )
+            .expect("failed to query fts table");
+        assert_eq!(hits, 1);
+    }
+}
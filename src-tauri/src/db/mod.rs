@@ -7,6 +7,8 @@ pub mod chat;
 pub mod config;
 pub mod error;
 pub mod main_store;
+pub mod maintenance;
+pub mod portable;
 // pub mod plugin;
 mod ccproxy;
 mod mcp;
@@ -24,10 +26,15 @@ pub use automation::{
 pub use backup::{BackupConfig, DbBackup};
 pub use error::StoreError;
 pub use main_store::MainStore;
+pub use maintenance::DatabaseOptimizeReport;
 pub use mcp::Mcp;
 pub use note::{Note, NoteTag};
+pub use portable::{ImportPolicy, ImportSummary, PortableSettings};
 pub use proxy_group::ProxyGroup;
-pub use types::{AiModel, AiSkill, CcproxyStat, Conversation, ModelConfig, ThinkingConfig};
+pub use types::{
+    AiModel, AiSkill, CcproxyAuditEntry, CcproxyAuditQuery, CcproxyDeadLetter, CcproxyStat,
+    Conversation, ModelConfig, PricingConfig, ThinkingConfig,
+};
 pub use workflow::{
     Workflow, WorkflowAiContextMessage, WorkflowEfficiencyReport, WorkflowMessage, WorkflowSnapshot,
 };
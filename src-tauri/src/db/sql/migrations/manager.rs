@@ -1,5 +1,5 @@
 use crate::db::sql::migrations::{
-    common::MigrationDefinition, v1, v10, v2, v3, v4, v5, v6, v7, v8, v9,
+    common::MigrationDefinition, v1, v10, v11, v12, v13, v2, v3, v4, v5, v6, v7, v8, v9,
 };
 use crate::db::StoreError;
 use rusqlite::Connection;
@@ -15,6 +15,9 @@ const MIGRATIONS: &[MigrationDefinition] = &[
     v8::MIGRATION,
     v9::MIGRATION,
     v10::MIGRATION,
+    v11::MIGRATION,
+    v12::MIGRATION,
+    v13::MIGRATION,
 ];
 
 fn latest_migration_version() -> i32 {
@@ -202,6 +205,8 @@ mod tests {
         assert!(table_exists(&conn, "memory_candidates"));
         assert!(has_column(&conn, "ccproxy_stats", "provider_id"));
         assert!(has_column(&conn, "agents", "mcp_tool_exposure"));
+        assert!(table_exists(&conn, "ccproxy_dead_letters"));
+        assert!(table_exists(&conn, "ccproxy_audit_log"));
 
         let recorded_versions: i64 = conn
             .query_row("SELECT COUNT(1) FROM db_version", [], |row| row.get(0))
@@ -233,6 +238,8 @@ mod tests {
         assert!(table_exists(&conn, "ccproxy_stats"));
         assert!(table_exists(&conn, "workflows"));
         assert!(table_exists(&conn, "workflow_context_messages"));
+        assert!(table_exists(&conn, "ccproxy_dead_letters"));
+        assert!(table_exists(&conn, "ccproxy_audit_log"));
         assert!(has_column(&conn, "ccproxy_stats", "provider_id"));
 
         let has_v3_marker: i64 = conn
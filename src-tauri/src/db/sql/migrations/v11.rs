@@ -0,0 +1,36 @@
+use super::common::MigrationDefinition;
+
+/// Version 11 migration SQL statements
+/// Adds the dead-letter log of failed proxy requests.
+pub const MIGRATION_SQL: &[(&str, &str)] = &[
+    (
+        "ccproxy_dead_letters",
+        "CREATE TABLE IF NOT EXISTS ccproxy_dead_letters (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            request_id TEXT NOT NULL,
+            client_model TEXT NOT NULL,
+            backend_model TEXT NOT NULL,
+            provider_id INTEGER,
+            provider TEXT NOT NULL,
+            protocol TEXT NOT NULL,
+            backend_protocol TEXT NOT NULL,
+            status_code INTEGER NOT NULL,
+            error_message TEXT NOT NULL,
+            request_body TEXT NOT NULL,
+            request_truncated INTEGER DEFAULT 0,
+            duration_ms INTEGER DEFAULT 0,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        )",
+    ),
+    (
+        "idx_ccproxy_dead_letters_created_at",
+        "CREATE INDEX IF NOT EXISTS idx_ccproxy_dead_letters_created_at ON ccproxy_dead_letters(created_at DESC)",
+    ),
+];
+
+pub const MIGRATION: MigrationDefinition = MigrationDefinition {
+    version: 11,
+    description: "v11 migration: Add ccproxy_dead_letters table",
+    sql: MIGRATION_SQL,
+    ensure: None,
+};
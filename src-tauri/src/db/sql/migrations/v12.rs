@@ -0,0 +1,28 @@
+use super::common::{column_exists, MigrationDefinition};
+use crate::db::StoreError;
+use rusqlite::Connection;
+
+pub const MIGRATION_SQL: &[(&str, &str)] = &[];
+
+fn ensure_conversation_flags(conn: &Connection) -> Result<(), StoreError> {
+    if !column_exists(conn, "conversations", "is_pinned")? {
+        conn.execute(
+            "ALTER TABLE conversations ADD COLUMN is_pinned BOOLEAN DEFAULT FALSE",
+            [],
+        )?;
+    }
+    if !column_exists(conn, "conversations", "is_archived")? {
+        conn.execute(
+            "ALTER TABLE conversations ADD COLUMN is_archived BOOLEAN DEFAULT FALSE",
+            [],
+        )?;
+    }
+    Ok(())
+}
+
+pub const MIGRATION: MigrationDefinition = MigrationDefinition {
+    version: 12,
+    description: "v12 migration: Add pinned and archived flags to conversations",
+    sql: MIGRATION_SQL,
+    ensure: Some(ensure_conversation_flags),
+};
@@ -0,0 +1,34 @@
+use super::common::MigrationDefinition;
+
+/// Version 13 migration SQL statements
+/// Adds the append-only audit log of proxied requests.
+pub const MIGRATION_SQL: &[(&str, &str)] = &[
+    (
+        "ccproxy_audit_log",
+        "CREATE TABLE IF NOT EXISTS ccproxy_audit_log (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            request_id TEXT NOT NULL,
+            protocol TEXT NOT NULL,
+            client_model TEXT NOT NULL,
+            backend_model TEXT NOT NULL,
+            provider_id INTEGER,
+            provider TEXT NOT NULL,
+            key_id TEXT NOT NULL,
+            prompt_tokens INTEGER DEFAULT 0,
+            prompt_hash TEXT NOT NULL,
+            prompt_content TEXT,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        )",
+    ),
+    (
+        "idx_ccproxy_audit_log_created_at",
+        "CREATE INDEX IF NOT EXISTS idx_ccproxy_audit_log_created_at ON ccproxy_audit_log(created_at DESC)",
+    ),
+];
+
+pub const MIGRATION: MigrationDefinition = MigrationDefinition {
+    version: 13,
+    description: "v13 migration: Add ccproxy_audit_log table",
+    sql: MIGRATION_SQL,
+    ensure: None,
+};
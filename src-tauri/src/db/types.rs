@@ -33,6 +33,10 @@ pub struct Conversation {
     pub created_at: String,
     #[serde(rename = "isFavorite")]
     pub is_favorite: bool,
+    #[serde(rename = "isPinned")]
+    pub is_pinned: bool,
+    #[serde(rename = "isArchived")]
+    pub is_archived: bool,
 }
 
 // =================================================
@@ -70,6 +74,81 @@ pub struct CcproxyStat {
     pub request_at: Option<String>,
 }
 
+/// A proxy request that ultimately failed, kept for analyzing recurring failures.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CcproxyDeadLetter {
+    pub id: Option<i64>,
+    /// Message id of the proxied request
+    pub request_id: String,
+    pub client_model: String,
+    pub backend_model: String,
+    pub provider_id: Option<i64>,
+    pub provider: String,
+    /// Protocol of the client request
+    pub protocol: String,
+    /// Protocol of the backend the request was sent to
+    pub backend_protocol: String,
+    pub status_code: i32,
+    pub error_message: String,
+    /// Client request body with credentials redacted
+    pub request_body: String,
+    /// Whether `request_body` was cut to the size limit
+    pub request_truncated: bool,
+    pub duration_ms: i64,
+    pub created_at: Option<String>,
+}
+
+/// One entry of the audit log of proxied requests.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CcproxyAuditEntry {
+    pub id: Option<i64>,
+    /// Message id of the proxied request
+    pub request_id: String,
+    /// Protocol of the client request
+    pub protocol: String,
+    pub client_model: String,
+    pub backend_model: String,
+    pub provider_id: Option<i64>,
+    pub provider: String,
+    /// Fingerprint of the API key the request was sent with, never the key itself
+    pub key_id: String,
+    /// Estimated tokens of the prompt
+    pub prompt_tokens: i64,
+    /// SHA-256 of the prompt
+    pub prompt_hash: String,
+    /// The prompt itself, only kept when storing prompts was opted in
+    pub prompt_content: Option<String>,
+    pub created_at: Option<String>,
+}
+
+/// Filter of an audit log query, every field is optional.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CcproxyAuditQuery {
+    /// Earliest `created_at`, inclusive, e.g. `2025-01-31` or `2025-01-31 08:00:00` in UTC
+    pub since: Option<String>,
+    /// Latest `created_at`, exclusive
+    pub until: Option<String>,
+    /// Matches the client or backend model
+    pub model: Option<String>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// Token usage of one provider model on one local day, used to price past requests.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CcproxyTokenUsage {
+    pub provider_id: Option<i64>,
+    pub backend_model: String,
+    /// Local date in `YYYY-MM-DD` format
+    pub date: String,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    pub cache_tokens: i64,
+}
+
 // =================================================
 // config
 // =================================================
@@ -119,6 +198,13 @@ pub struct ModelConfig {
     pub custom_params: Option<Value>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub pricing: Option<PricingConfig>,
+    /// Chat template, e.g. `chatml`, that renders the messages into a single prompt for
+    /// raw completion endpoints. Set for local models whose clients send no template.
+    #[serde(rename = "promptTemplate", skip_serializing_if = "Option::is_none")]
+    pub prompt_template: Option<String>,
+    /// Stop sequences added to the client's own on raw completion endpoints
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stop: Option<Vec<String>>,
 }
 
 impl Default for ModelConfig {
@@ -135,6 +221,8 @@ impl Default for ModelConfig {
             temperature: Some(-0.1),
             custom_params: None,
             pricing: None,
+            prompt_template: None,
+            stop: None,
         }
     }
 }
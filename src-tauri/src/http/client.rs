@@ -37,6 +37,8 @@ use scraper::{Html, Selector};
 use std::{collections::HashMap, time::Duration};
 use tokio::{fs::File, io::AsyncWriteExt};
 
+use crate::libs::retry::is_retryable_error;
+
 use super::{
     error::{HttpError, HttpResult},
     types::{HttpConfig, HttpRequest, HttpResponse, Progress, ProgressState, RetryPolicy},
@@ -394,7 +396,7 @@ impl HttpClient {
                     }
                     return Ok(response);
                 }
-                Err(e) if Self::should_retry(&e) && attempts < retry_policy.max_retries => {
+                Err(e) if is_retryable_error(&e) && attempts < retry_policy.max_retries => {
                     log::warn!("Request failed, retrying: {:?}", e);
                     delay = (delay * retry_policy.backoff_factor).min(retry_policy.max_delay);
                     tokio::time::sleep(Duration::from_secs_f32(delay)).await;
@@ -413,14 +415,6 @@ impl HttpClient {
             .collect()
     }
 
-    /// Determines if a request should be retried based on the error
-    fn should_retry(error: &reqwest::Error) -> bool {
-        error.is_timeout()
-            || error.is_connect()
-            || error.is_request()
-            || matches!(error.status(), Some(status) if status.is_server_error())
-    }
-
     /// send async request
     ///
     /// # Arguments
@@ -6,8 +6,8 @@ use axum::{
     extract::DefaultBodyLimit,
     http::StatusCode,
     response::{IntoResponse, Response},
-    routing::post,
-    Router,
+    routing::{get, post},
+    Json, Router,
 };
 use rust_i18n::t;
 use std::{
@@ -90,6 +90,13 @@ pub async fn start_http_server(
     // Main HTTP server routes
     let app_routes = Router::new()
         .route("/save/png", post(handle_save_png))
+        .route(
+            "/metrics/tools",
+            get({
+                let chat_state = chat_state.clone();
+                move || async move { Json(chat_state.tool_manager.metrics.snapshot()) }
+            }),
+        )
         .nest_service("/theme", ServeDir::new(theme_dir.clone()))
         .nest_service("/upload", ServeDir::new(upload_dir.clone()))
         .nest_service("/tmp", ServeDir::new(tmp_dir.clone()))
@@ -11,6 +11,7 @@ use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
 
+use crate::libs::retry::is_retryable_error;
 use crate::libs::util;
 
 use super::error::HttpError;
@@ -128,9 +129,7 @@ impl RetryPolicy {
             return false;
         }
 
-        error.is_timeout()
-            || error.is_connect()
-            || error.status().map_or(false, |s| s.is_server_error())
+        is_retryable_error(error)
     }
 
     /// Calculates the delay duration for the next retry attempt
@@ -56,6 +56,7 @@ use commands::note::*;
 use commands::proxy_group::*;
 use commands::sensitive::*;
 use commands::setting::*;
+use commands::tool::*;
 use commands::updater::{check_for_updates, install_and_restart};
 use commands::window::*;
 use commands::workflow::*;
@@ -233,6 +234,9 @@ pub async fn run() -> crate::error::Result<()> {
             delete_ai_skill,
             update_shortcut,
             backup_setting,
+            optimize_database,
+            export_settings,
+            import_settings,
             get_all_backups,
             restore_setting,
             update_tray,
@@ -250,6 +254,7 @@ pub async fn run() -> crate::error::Result<()> {
             stop_chat,
             sync_state,
             detect_language,
+            estimate_tokens,
             // ccproxy stats
             delete_ccproxy_stats,
             get_ccproxy_daily_stats,
@@ -260,6 +265,16 @@ pub async fn run() -> crate::error::Result<()> {
             get_ccproxy_model_token_usage_stats,
             get_ccproxy_error_distribution_stats,
             get_ccproxy_provider_token_usage_stats,
+            get_ccproxy_dead_letters,
+            delete_ccproxy_dead_letters,
+            get_ccproxy_audit_log,
+            export_ccproxy_audit_log,
+            replay_ccproxy_request,
+            benchmark_model,
+            get_ccproxy_key_health,
+            setup_wizard_presets,
+            setup_wizard_apply,
+            detect_api_protocol,
             // mcp
             list_mcp_servers,
             add_mcp_server,
@@ -271,6 +286,21 @@ pub async fn run() -> crate::error::Result<()> {
             refresh_mcp_server,
             get_mcp_server_tools,
             update_mcp_tool_status,
+            respond_mcp_sampling_approval,
+            list_pending_mcp_sampling_approvals,
+            get_mcp_server_auth,
+            set_mcp_server_auth_enabled,
+            respond_mcp_tool_approval,
+            list_pending_mcp_tool_approvals,
+            list_mcp_processes,
+            kill_mcp_process,
+            // tool
+            list_available_tools,
+            get_tool_metrics,
+            reset_tool_metrics,
+            respond_command_approval,
+            list_pending_command_approvals,
+            rerun_message_tool_calls,
             // proxy group
             proxy_group_list,
             proxy_group_add,
@@ -278,6 +308,7 @@ pub async fn run() -> crate::error::Result<()> {
             proxy_group_batch_update,
             proxy_group_delete,
             set_active_proxy_group,
+            get_proxy_group_warmup,
             get_active_proxy_group,
             // message
             get_conversation_by_id,
@@ -285,11 +316,17 @@ pub async fn run() -> crate::error::Result<()> {
             get_messages_for_conversation,
             add_conversation,
             update_conversation,
+            set_conversation_pinned,
+            set_conversation_archived,
             delete_conversation,
             add_message,
             delete_message,
+            delete_messages,
+            delete_messages_after,
+            clear_conversation,
             send_message,
             update_message_metadata,
+            update_message,
             // node
             get_tags,
             add_note,
@@ -371,6 +408,7 @@ pub async fn run() -> crate::error::Result<()> {
 
             // dev tools
             test_scrape,
+            get_scraper_pool_metrics,
             // updater
             check_for_updates,
             install_and_restart,
@@ -792,7 +830,9 @@ pub async fn run() -> crate::error::Result<()> {
             tauri::async_runtime::spawn(async move {
                 // 1. Register native tools first (fast, local-only)
                 let tm = chat_state_clone.tool_manager.clone();
-                let _ = tm.register_available_tools(handle.clone()).await;
+                let _ = tm.clone().register_available_tools(handle.clone()).await;
+                tools::spawn_metrics_persistence(tm.metrics.clone(), main_store_clone.clone());
+                db::maintenance::spawn_scheduled_optimize(main_store_clone.clone());
 
                 // 2. Start the HTTP server without waiting for MCP startup
                 // The HTTP server includes:
@@ -3,6 +3,7 @@
 pub mod ai_temp;
 pub mod fs;
 pub mod lang;
+pub mod retry;
 pub mod tsid;
 pub mod util;
 pub mod window_channels;
@@ -0,0 +1,140 @@
+//! Which failures are worth retrying.
+//!
+//! Search providers, the chat completion proxy, workflows and the HTTP client all retry failed
+//! requests, and they should agree on what a transient failure is: timeouts, dropped or refused
+//! connections, rate limits and server errors. Everything else, such as an authentication or
+//! validation error, fails the same way on the next attempt and is returned right away.
+
+/// Whether a request that got `status` may succeed when sent again.
+///
+/// Request timeouts (408), rate limits (429) and server errors (5xx) are retryable, any other
+/// client error is not.
+pub fn is_retryable_status(status: u16) -> bool {
+    matches!(status, 408 | 429) || (500..600).contains(&status)
+}
+
+/// Whether a request that failed with `error` may succeed when sent again.
+///
+/// Timeouts and errors while connecting or sending are retryable, as are errors from
+/// `error_for_status` with a retryable status. Errors building the request or decoding the
+/// response are not.
+pub fn is_retryable_error(error: &reqwest::Error) -> bool {
+    if let Some(status) = error.status() {
+        return is_retryable_status(status.as_u16());
+    }
+    error.is_timeout() || error.is_connect() || error.is_request()
+}
+
+/// Whether an I/O operation that failed with `error` may succeed on a new connection.
+///
+/// Dropped, refused and timed out connections are retryable, any other I/O error is not.
+pub fn is_retryable_io_error(error: &std::io::Error) -> bool {
+    use std::io::ErrorKind;
+
+    matches!(
+        error.kind(),
+        ErrorKind::ConnectionRefused
+            | ErrorKind::ConnectionReset
+            | ErrorKind::ConnectionAborted
+            | ErrorKind::NotConnected
+            | ErrorKind::BrokenPipe
+            | ErrorKind::TimedOut
+            | ErrorKind::UnexpectedEof
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::spawn_mock_backend;
+    use axum::{http::StatusCode, routing::get, Router};
+    use std::time::Duration;
+
+    #[test]
+    fn test_retryable_statuses() {
+        for status in [408, 429, 500, 502, 503, 504, 529] {
+            assert!(is_retryable_status(status), "{} should be retried", status);
+        }
+        for status in [200, 301, 400, 401, 403, 404, 409, 413, 422] {
+            assert!(!is_retryable_status(status), "{} should fail fast", status);
+        }
+    }
+
+    #[test]
+    fn test_retryable_io_errors() {
+        use std::io::{Error, ErrorKind};
+
+        for kind in [
+            ErrorKind::ConnectionReset,
+            ErrorKind::BrokenPipe,
+            ErrorKind::TimedOut,
+        ] {
+            assert!(is_retryable_io_error(&Error::from(kind)), "{:?}", kind);
+        }
+        for kind in [
+            ErrorKind::NotFound,
+            ErrorKind::PermissionDenied,
+            ErrorKind::InvalidData,
+        ] {
+            assert!(!is_retryable_io_error(&Error::from(kind)), "{:?}", kind);
+        }
+    }
+
+    async fn server() -> String {
+        let app = Router::new()
+            .route(
+                "/unavailable",
+                get(|| async { StatusCode::SERVICE_UNAVAILABLE }),
+            )
+            .route("/unauthorized", get(|| async { StatusCode::UNAUTHORIZED }))
+            .route(
+                "/slow",
+                get(|| async {
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                    "late"
+                }),
+            );
+        spawn_mock_backend(app).await
+    }
+
+    #[tokio::test]
+    async fn test_retryable_errors() {
+        let base = server().await;
+        let client = reqwest::Client::new();
+        let status_error = |path: &'static str| {
+            let request = client.get(format!("{}{}", base, path));
+            async move {
+                request
+                    .send()
+                    .await
+                    .expect("response")
+                    .error_for_status()
+                    .expect_err("error status")
+            }
+        };
+        assert!(is_retryable_error(&status_error("/unavailable").await));
+        assert!(!is_retryable_error(&status_error("/unauthorized").await));
+
+        let timeout = client
+            .get(format!("{}/slow", base))
+            .timeout(Duration::from_millis(50))
+            .send()
+            .await
+            .expect_err("timed out");
+        assert!(is_retryable_error(&timeout));
+
+        let refused = client
+            .get("http://127.0.0.1:0/")
+            .send()
+            .await
+            .expect_err("connection refused");
+        assert!(is_retryable_error(&refused));
+
+        let invalid = client
+            .get("not a url")
+            .send()
+            .await
+            .expect_err("invalid url");
+        assert!(!is_retryable_error(&invalid));
+    }
+}
@@ -1,21 +1,47 @@
-use serde_json::json;
-use std::collections::HashMap;
-use std::sync::Arc;
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock};
 use tauri::Emitter;
 use tokio::sync::mpsc;
 use tokio::sync::Mutex;
 use tokio::time::{sleep, Duration, Instant};
 
 use crate::ai::traits::chat::ChatResponse;
-use crate::ai::traits::chat::MessageType;
+use crate::ai::traits::chat::{MessageType, StreamChannel};
 
 const BATCH_SIZE: usize = 100; // Maximum number of messages to batch
 const THROTTLE_DURATION: Duration = Duration::from_millis(100);
 
+/// Event carrying all stream messages of a window
+pub const CHAT_STREAM_EVENT: &str = "chat_stream";
+/// Event carrying reasoning deltas of windows that route reasoning separately
+pub const CHAT_REASONING_STREAM_EVENT: &str = "chat_stream_reasoning";
+
+/// Serializes a stream message, tagging text deltas with their `channel`.
+fn stream_payload(chunk: &ChatResponse) -> Value {
+    let mut payload = json!(chunk);
+    if let (Some(channel), Some(object)) = (chunk.r#type.stream_channel(), payload.as_object_mut())
+    {
+        object.insert("channel".to_string(), json!(channel));
+    }
+    payload
+}
+
+/// Picks the event a stream message is emitted on.
+fn stream_event(chunk: &ChatResponse, separate_reasoning: bool) -> &'static str {
+    if separate_reasoning && chunk.r#type.stream_channel() == Some(StreamChannel::Reasoning) {
+        CHAT_REASONING_STREAM_EVENT
+    } else {
+        CHAT_STREAM_EVENT
+    }
+}
+
 /// Define a structure to hold the channel sender for each window
 pub struct WindowChannels {
     // The parameter is a tuple of (chat_id, chunk, is_error, is_done,is_reasoning, metadata)
     channels: Arc<Mutex<HashMap<String, mpsc::Sender<Arc<ChatResponse>>>>>,
+    /// Labels of windows receiving reasoning on `CHAT_REASONING_STREAM_EVENT`
+    separate_reasoning: Arc<RwLock<HashSet<String>>>,
 }
 
 impl WindowChannels {
@@ -23,9 +49,36 @@ impl WindowChannels {
     pub fn new() -> Self {
         Self {
             channels: Arc::new(Mutex::new(HashMap::new())),
+            separate_reasoning: Arc::new(RwLock::new(HashSet::new())),
+        }
+    }
+
+    /// Routes the reasoning deltas of a window to `CHAT_REASONING_STREAM_EVENT` instead of
+    /// `CHAT_STREAM_EVENT`.
+    pub fn set_separate_reasoning(&self, window_label: &str, separate: bool) {
+        match self.separate_reasoning.write() {
+            Ok(mut labels) => {
+                if separate {
+                    labels.insert(window_label.to_string());
+                } else {
+                    labels.remove(window_label);
+                }
+            }
+            Err(e) => log::error!(
+                "Failed to set reasoning routing of window {}: {}",
+                window_label,
+                e
+            ),
         }
     }
 
+    fn separates_reasoning(labels: &RwLock<HashSet<String>>, window_label: &str) -> bool {
+        labels
+            .read()
+            .map(|labels| labels.contains(window_label))
+            .unwrap_or(false)
+    }
+
     /// Get the sender for a specific chat_id/window_label if it exists.
     /// This does not create a new channel if one doesn't exist.
     pub async fn get_sender(&self, window_label: &str) -> Option<mpsc::Sender<Arc<ChatResponse>>> {
@@ -38,14 +91,21 @@ impl WindowChannels {
     /// # Arguments
     /// * `window`: The Tauri window to send the message to
     /// * `chunk`: message to send
+    /// * `separate_reasoning`: Whether reasoning deltas go to their own event
     async fn emit_chat_stream(
         window: &tauri::Window,
         chunk: Arc<ChatResponse>,
+        separate_reasoning: bool,
     ) -> Result<(), tauri::Error> {
-        window.emit("chat_stream", json!(&chunk)).map_err(|e| {
-            log::error!("Failed to serialize AI chunk: {}", e);
-            e
-        })
+        window
+            .emit(
+                stream_event(&chunk, separate_reasoning),
+                stream_payload(&chunk),
+            )
+            .map_err(|e| {
+                log::error!("Failed to serialize AI chunk: {}", e);
+                e
+            })
     }
 
     /// Emit a batch of AI chunks as a single message
@@ -53,9 +113,11 @@ impl WindowChannels {
     /// # Arguments
     /// * `window`: The Tauri window to send the message to
     /// * `batch`: The batch of AI chunks to send
+    /// * `separate_reasoning`: Whether reasoning deltas go to their own event
     async fn emit_batch(
         window: &tauri::Window,
         batch: &[ChatResponse],
+        separate_reasoning: bool,
     ) -> Result<(), tauri::Error> {
         if batch.is_empty() {
             return Ok(());
@@ -76,6 +138,7 @@ impl WindowChannels {
                 r#type: first_msg.r#type.clone(),
                 finish_reason: first_msg.finish_reason.clone(),
             }),
+            separate_reasoning,
         )
         .await
     }
@@ -100,12 +163,15 @@ impl WindowChannels {
                 let (tx, mut rx) = mpsc::channel::<Arc<ChatResponse>>(1000);
 
                 let window = window.clone();
+                let separate_reasoning = self.separate_reasoning.clone();
                 tokio::spawn(async move {
                     let mut batch = Vec::<ChatResponse>::with_capacity(BATCH_SIZE as usize);
                     let mut last_emit = Instant::now();
                     // let mut is_first: bool = true;
 
                     while let Some(chunk) = rx.recv().await {
+                        let separate =
+                            Self::separates_reasoning(&separate_reasoning, window.label());
                         // For error or done messages: they should be sent immediately
                         // 1. Send current batch first
                         // 2. Then send the error/done/step/reference message separately
@@ -124,14 +190,14 @@ impl WindowChannels {
 
                             // Send current batch if not empty
                             if !batch.is_empty() {
-                                if let Err(e) = Self::emit_batch(&window, &batch).await {
+                                if let Err(e) = Self::emit_batch(&window, &batch, separate).await {
                                     log::error!("Failed to emit batch: {}", e);
                                 }
                                 batch.clear();
                             }
 
                             // Send the error/done message
-                            if let Err(e) = Self::emit_chat_stream(&window, chunk).await {
+                            if let Err(e) = Self::emit_chat_stream(&window, chunk, separate).await {
                                 log::error!("Failed to emit AI chunk: {}", e);
                             }
                             continue;
@@ -156,7 +222,7 @@ impl WindowChannels {
                             }
 
                             // Send current batch
-                            if let Err(e) = Self::emit_batch(&window, &batch).await {
+                            if let Err(e) = Self::emit_batch(&window, &batch, separate).await {
                                 log::error!("Failed to emit batch: {}", e);
                             }
                             batch.clear();
@@ -176,3 +242,64 @@ impl WindowChannels {
             .clone())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(r#type: MessageType, text: &str) -> ChatResponse {
+        ChatResponse {
+            chat_id: "chat_1".to_string(),
+            chunk: text.to_string(),
+            r#type,
+            metadata: None,
+            finish_reason: None,
+        }
+    }
+
+    #[test]
+    fn test_deltas_are_tagged_by_channel() {
+        let reasoning = stream_payload(&chunk(MessageType::Reasoning, "Let me think"));
+        assert_eq!(reasoning["channel"], "reasoning");
+        assert_eq!(reasoning["chunk"], "Let me think");
+        assert_eq!(
+            stream_payload(&chunk(MessageType::Think, "hmm"))["channel"],
+            "reasoning"
+        );
+        assert_eq!(
+            stream_payload(&chunk(MessageType::Text, "The answer"))["channel"],
+            "answer"
+        );
+        assert!(stream_payload(&chunk(MessageType::Step, "Searching"))
+            .get("channel")
+            .is_none());
+    }
+
+    #[test]
+    fn test_reasoning_is_routed_separately_only_when_enabled() {
+        let reasoning = chunk(MessageType::Reasoning, "Let me think");
+        let answer = chunk(MessageType::Text, "The answer");
+        let finished = chunk(MessageType::Finished, "");
+
+        assert_eq!(stream_event(&reasoning, false), CHAT_STREAM_EVENT);
+        assert_eq!(stream_event(&reasoning, true), CHAT_REASONING_STREAM_EVENT);
+        assert_eq!(stream_event(&answer, true), CHAT_STREAM_EVENT);
+        assert_eq!(stream_event(&finished, true), CHAT_STREAM_EVENT);
+
+        let channels = WindowChannels::new();
+        channels.set_separate_reasoning("main", true);
+        assert!(WindowChannels::separates_reasoning(
+            &channels.separate_reasoning,
+            "main"
+        ));
+        assert!(!WindowChannels::separates_reasoning(
+            &channels.separate_reasoning,
+            "assistant"
+        ));
+        channels.set_separate_reasoning("main", false);
+        assert!(!WindowChannels::separates_reasoning(
+            &channels.separate_reasoning,
+            "main"
+        ));
+    }
+}
@@ -1,15 +1,27 @@
-use std::sync::Arc;
+use std::{collections::HashSet, sync::Arc};
 
+use super::handler::{McpClientHandler, McpRoots};
+use super::reconnect::cancel_supervisor;
+use super::sampling::SamplingHandler;
 use super::types::{McpClientInternal, McpServerConfig, McpStatus, StatusChangeCallback};
-use rmcp::{model::InitializeRequestParams, service::RunningService, RoleClient};
+use rmcp::{service::RunningService, RoleClient};
 use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
 
 /// Core structure holding shared state and logic for McpClient implementations.
 pub struct McpClientCore {
     pub config: Arc<RwLock<McpServerConfig>>,
-    pub client_instance: Arc<RwLock<Option<RunningService<RoleClient, InitializeRequestParams>>>>,
+    pub client_instance: Arc<RwLock<Option<RunningService<RoleClient, McpClientHandler>>>>,
     pub status: RwLock<McpStatus>,
     pub status_callback: Arc<RwLock<Option<StatusChangeCallback>>>,
+    /// Tools annotated as read-only or idempotent, which may be retried after a reconnect.
+    pub retry_safe_tools: RwLock<HashSet<String>>,
+    /// Answers sampling requests of the server, if the server is allowed to send them.
+    pub sampling_handler: RwLock<Option<Arc<dyn SamplingHandler>>>,
+    /// Workspace folders advertised to the server.
+    pub roots: RwLock<McpRoots>,
+    /// Task re-establishing the connection when it is lost, cancelled on stop.
+    pub supervisor: RwLock<Option<JoinHandle<()>>>,
 }
 
 impl McpClientCore {
@@ -20,6 +32,10 @@ impl McpClientCore {
             client_instance: Arc::new(RwLock::new(None)),
             status: RwLock::new(McpStatus::Stopped),
             status_callback: Arc::new(RwLock::new(None)),
+            retry_safe_tools: RwLock::new(HashSet::new()),
+            sampling_handler: RwLock::new(None),
+            roots: RwLock::new(McpRoots::default()),
+            supervisor: RwLock::new(None),
         }
     }
 
@@ -36,7 +52,7 @@ impl McpClientCore {
     /// Gets the Arc for the client instance.
     pub fn get_client_instance_arc(
         &self,
-    ) -> Arc<RwLock<Option<RunningService<RoleClient, InitializeRequestParams>>>> {
+    ) -> Arc<RwLock<Option<RunningService<RoleClient, McpClientHandler>>>> {
         self.client_instance.clone()
     }
 
@@ -51,6 +67,26 @@ impl McpClientCore {
         *cb = Some(callback);
     }
 
+    /// Sets the handler answering sampling requests of the server.
+    pub async fn set_sampling_handler(&self, handler: Arc<dyn SamplingHandler>) {
+        *self.sampling_handler.write().await = Some(handler);
+    }
+
+    /// Sets the workspace folders advertised to the server.
+    pub async fn set_roots(&self, roots: McpRoots) {
+        *self.roots.write().await = roots;
+    }
+
+    /// Builds the rmcp client handler for a new connection.
+    pub async fn client_handler(&self) -> McpClientHandler {
+        let config = self.config.read().await;
+        McpClientHandler::new(
+            &config,
+            self.sampling_handler.read().await.clone(),
+            self.roots.read().await.clone(),
+        )
+    }
+
     /// Updates the disabled_tools list in the internal McpServerConfig.
     pub async fn update_disabled_tools(&self, tool_name: &str, is_disabled: bool) {
         let mut config_guard = self.config.write().await;
@@ -82,4 +118,22 @@ impl McpClientInternal for McpClientCore {
             callback(name, status);
         }
     }
+
+    async fn set_retry_safe_tools(&self, tools: HashSet<String>) {
+        *self.retry_safe_tools.write().await = tools;
+    }
+
+    async fn is_retry_safe(&self, tool_name: &str) -> bool {
+        self.retry_safe_tools.read().await.contains(tool_name)
+    }
+
+    async fn set_supervisor(&self, handle: JoinHandle<()>) {
+        let previous = self.supervisor.write().await.replace(handle);
+        cancel_supervisor(previous).await;
+    }
+
+    async fn cancel_supervisor(&self) {
+        let handle = self.supervisor.write().await.take();
+        cancel_supervisor(handle).await;
+    }
 }
@@ -0,0 +1,348 @@
+//! The rmcp client handler shared by all MCP transports.
+//!
+//! Besides identifying chatspeed on initialize, it answers the requests a server may
+//! send to the client: `roots/list` and `sampling/createMessage`, and keeps track of the
+//! progress reports of running tool calls.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use std::time::Instant;
+
+use rmcp::model::{
+    ClientCapabilities, ClientInfo, CreateMessageRequestParams, CreateMessageResult, ErrorCode,
+    ErrorData, Implementation, ListRootsResult, ProgressNotificationParam, ProgressToken,
+    RootsCapabilities,
+};
+use rmcp::service::{NotificationContext, RequestContext};
+use rmcp::{ClientHandler, RoleClient};
+use serde_json::{json, Value};
+
+use super::sampling::{
+    parse_sampling_request, sampling_result_json, McpSamplingPolicy, SamplingHandler,
+};
+use super::McpServerConfig;
+
+/// Workspace folders advertised to MCP servers as roots.
+///
+/// Clones share the same list, so an update is seen by every client at once.
+#[derive(Debug, Clone, Default)]
+pub struct McpRoots(Arc<RwLock<Vec<PathBuf>>>);
+
+impl McpRoots {
+    #[cfg(test)]
+    pub fn new(paths: impl IntoIterator<Item = PathBuf>) -> Self {
+        Self(Arc::new(RwLock::new(paths.into_iter().collect())))
+    }
+
+    /// Replaces the advertised folders.
+    pub fn set(&self, paths: impl IntoIterator<Item = PathBuf>) {
+        if let Ok(mut roots) = self.0.write() {
+            *roots = paths.into_iter().collect();
+        }
+    }
+
+    pub fn paths(&self) -> Vec<PathBuf> {
+        self.0.read().map(|roots| roots.clone()).unwrap_or_default()
+    }
+
+    /// Serializes the roots as a `roots/list` result.
+    /// Relative paths cannot be expressed as `file://` URIs and are skipped.
+    fn to_json(&self) -> Value {
+        let roots: Vec<Value> = self
+            .paths()
+            .iter()
+            .filter_map(|path| {
+                let uri = url::Url::from_file_path(path).ok()?;
+                let mut root = json!({ "uri": uri.as_str() });
+                if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
+                    root["name"] = json!(name);
+                }
+                Some(root)
+            })
+            .collect();
+        json!({ "roots": roots })
+    }
+}
+
+/// When the running requests of a connection last reported progress.
+///
+/// A tool that streams its result sends progress notifications, which keep its call from
+/// timing out as long as they keep coming.
+#[derive(Debug, Clone, Default)]
+pub struct ProgressActivity(Arc<RwLock<HashMap<String, Instant>>>);
+
+impl ProgressActivity {
+    fn key(token: &ProgressToken) -> String {
+        serde_json::to_string(token).unwrap_or_default()
+    }
+
+    /// Records a progress report for `token`.
+    pub fn touch(&self, token: &ProgressToken) {
+        if let Ok(mut activity) = self.0.write() {
+            activity.insert(Self::key(token), Instant::now());
+        }
+    }
+
+    /// When `token` last reported progress, `None` if it never did.
+    pub fn last(&self, token: &ProgressToken) -> Option<Instant> {
+        self.0
+            .read()
+            .ok()
+            .and_then(|activity| activity.get(&Self::key(token)).copied())
+    }
+
+    /// Drops the record of a finished request.
+    pub fn forget(&self, token: &ProgressToken) {
+        if let Ok(mut activity) = self.0.write() {
+            activity.remove(&Self::key(token));
+        }
+    }
+}
+
+/// The rmcp client handler used by all chatspeed MCP clients.
+pub struct McpClientHandler {
+    server_name: String,
+    policy: McpSamplingPolicy,
+    sampling: Option<Arc<dyn SamplingHandler>>,
+    roots: McpRoots,
+    progress: ProgressActivity,
+}
+
+impl McpClientHandler {
+    pub fn new(
+        config: &McpServerConfig,
+        sampling: Option<Arc<dyn SamplingHandler>>,
+        roots: McpRoots,
+    ) -> Self {
+        Self {
+            server_name: config.name.clone(),
+            policy: config.sampling.unwrap_or_default(),
+            sampling,
+            roots,
+            progress: ProgressActivity::default(),
+        }
+    }
+
+    /// Progress reports of the requests running on this connection.
+    pub fn progress(&self) -> &ProgressActivity {
+        &self.progress
+    }
+
+    fn sampling_handler(&self) -> Option<&Arc<dyn SamplingHandler>> {
+        if self.policy == McpSamplingPolicy::Deny {
+            return None;
+        }
+        self.sampling.as_ref()
+    }
+
+    /// Answers a `roots/list` request with the configured workspace folders.
+    pub fn list_roots_result(&self) -> Result<ListRootsResult, ErrorData> {
+        serde_json::from_value(self.roots.to_json())
+            .map_err(|e| ErrorData::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None))
+    }
+
+    /// Answers a `sampling/createMessage` request.
+    pub async fn sample(
+        &self,
+        params: CreateMessageRequestParams,
+    ) -> Result<CreateMessageResult, ErrorData> {
+        let Some(handler) = self.sampling_handler() else {
+            log::warn!(
+                "Rejected sampling request from MCP server '{}': sampling is not allowed",
+                self.server_name
+            );
+            return Err(ErrorData::new(
+                ErrorCode::INVALID_REQUEST,
+                "Sampling is not enabled for this server",
+                None,
+            ));
+        };
+
+        let params = serde_json::to_value(&params)
+            .map_err(|e| ErrorData::new(ErrorCode::INVALID_PARAMS, e.to_string(), None))?;
+        let request = parse_sampling_request(
+            &self.server_name,
+            &params,
+            self.policy == McpSamplingPolicy::Ask,
+        )
+        .ok_or_else(|| {
+            ErrorData::new(
+                ErrorCode::INVALID_PARAMS,
+                "Sampling request contains no text messages",
+                None,
+            )
+        })?;
+
+        let response = handler
+            .create_message(request)
+            .await
+            .map_err(|e| ErrorData::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None))?;
+
+        serde_json::from_value(sampling_result_json(&response))
+            .map_err(|e| ErrorData::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None))
+    }
+}
+
+impl ClientHandler for McpClientHandler {
+    async fn create_message(
+        &self,
+        params: CreateMessageRequestParams,
+        _context: RequestContext<RoleClient>,
+    ) -> Result<CreateMessageResult, ErrorData> {
+        self.sample(params).await
+    }
+
+    async fn list_roots(
+        &self,
+        _context: RequestContext<RoleClient>,
+    ) -> Result<ListRootsResult, ErrorData> {
+        self.list_roots_result()
+    }
+
+    async fn on_progress(
+        &self,
+        params: ProgressNotificationParam,
+        _context: NotificationContext<RoleClient>,
+    ) {
+        self.progress.touch(&params.progress_token);
+    }
+
+    fn get_info(&self) -> ClientInfo {
+        let mut client_info = ClientInfo::default();
+        client_info.protocol_version = Default::default();
+        client_info.capabilities = ClientCapabilities::default();
+        let mut roots = RootsCapabilities::default();
+        roots.list_changed = Some(true);
+        client_info.capabilities.roots = Some(roots);
+        if self.sampling_handler().is_some() {
+            client_info.capabilities.sampling = Some(Default::default());
+        }
+        client_info.client_info =
+            Implementation::new("Chatspeed MCP Client", env!("CARGO_PKG_VERSION"))
+                .with_title("Chatspeed")
+                .with_website_url("https://chatspeed.aidyou.ai");
+        client_info
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcp::client::{McpClientResult, SamplingRequest, SamplingResponse};
+    use crate::mcp::McpError;
+    use std::sync::Mutex;
+
+    /// Stands in for the configured chat model.
+    #[derive(Default)]
+    struct FakeModel {
+        requests: Mutex<Vec<SamplingRequest>>,
+    }
+
+    #[async_trait::async_trait]
+    impl SamplingHandler for FakeModel {
+        async fn create_message(
+            &self,
+            request: SamplingRequest,
+        ) -> McpClientResult<SamplingResponse> {
+            let last = request
+                .messages
+                .last()
+                .map(|m| m.text.clone())
+                .ok_or_else(|| McpError::General("empty request".to_string()))?;
+            if let Ok(mut requests) = self.requests.lock() {
+                requests.push(request);
+            }
+            Ok(SamplingResponse {
+                model: "fake-model".to_string(),
+                text: format!("echo: {}", last),
+                stop_reason: Some("endTurn".to_string()),
+            })
+        }
+    }
+
+    fn handler(policy: Option<McpSamplingPolicy>, model: Arc<FakeModel>) -> McpClientHandler {
+        let config = McpServerConfig {
+            name: "summarizer".into(),
+            sampling: policy,
+            ..Default::default()
+        };
+        McpClientHandler::new(&config, Some(model), McpRoots::default())
+    }
+
+    /// The params a server sends with `sampling/createMessage`.
+    fn server_request() -> CreateMessageRequestParams {
+        serde_json::from_value(json!({
+            "messages": [
+                {"role": "user", "content": {"type": "text", "text": "Summarize: hello world"}}
+            ],
+            "systemPrompt": "You are a summarizer.",
+            "maxTokens": 64
+        }))
+        .expect("valid sampling params")
+    }
+
+    #[tokio::test]
+    async fn test_server_sampling_request_receives_completion() {
+        let model = Arc::new(FakeModel::default());
+        let handler = handler(Some(McpSamplingPolicy::Allow), model.clone());
+        assert!(handler.get_info().capabilities.sampling.is_some());
+
+        let result = handler
+            .sample(server_request())
+            .await
+            .expect("sampling succeeds");
+        let result = serde_json::to_value(&result).expect("serializable result");
+        assert_eq!(result["model"], "fake-model");
+        assert_eq!(result["role"], "assistant");
+        assert_eq!(result["content"]["text"], "echo: Summarize: hello world");
+
+        let requests = model.requests.lock().map(|r| r.clone()).unwrap_or_default();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].server_name, "summarizer");
+        assert_eq!(
+            requests[0].system_prompt.as_deref(),
+            Some("You are a summarizer.")
+        );
+        assert_eq!(requests[0].max_tokens, 64);
+        assert!(!requests[0].requires_approval);
+    }
+
+    #[tokio::test]
+    async fn test_sampling_is_denied_by_default() {
+        let model = Arc::new(FakeModel::default());
+        let handler = handler(None, model.clone());
+        assert!(handler.get_info().capabilities.sampling.is_none());
+
+        assert!(handler.sample(server_request()).await.is_err());
+        assert!(model.requests.lock().map(|r| r.is_empty()).unwrap_or(false));
+    }
+
+    #[test]
+    fn test_server_receives_advertised_roots() {
+        let workspace = tempfile::tempdir().expect("temp dir");
+        let roots = McpRoots::new([workspace.path().to_path_buf(), PathBuf::from("relative")]);
+        let config = McpServerConfig {
+            name: "files".into(),
+            ..Default::default()
+        };
+        let handler = McpClientHandler::new(&config, None, roots.clone());
+
+        let capabilities = handler.get_info().capabilities;
+        assert!(capabilities.roots.is_some());
+        assert!(capabilities.sampling.is_none());
+
+        let result = serde_json::to_value(handler.list_roots_result().expect("roots"))
+            .expect("serializable roots");
+        let advertised = result["roots"].as_array().cloned().unwrap_or_default();
+        assert_eq!(advertised.len(), 1);
+        let expected_uri = url::Url::from_file_path(workspace.path()).expect("absolute path");
+        assert_eq!(advertised[0]["uri"], expected_uri.as_str());
+
+        // Updates through any clone are visible to the handler.
+        roots.set(Vec::new());
+        let result = serde_json::to_value(handler.list_roots_result().expect("roots"))
+            .expect("serializable roots");
+        assert_eq!(result["roots"], json!([]));
+    }
+}
@@ -1,9 +1,16 @@
 mod core;
+mod handler;
+pub mod process;
+mod reconnect;
+mod sampling;
 mod stdio;
 mod streamable_http;
 mod types;
 mod util;
 
+pub use handler::McpRoots;
+pub use reconnect::{spawn_reconnect_supervisor, ReconnectPolicy};
+pub use sampling::{SamplingHandler, SamplingRequest, SamplingResponse};
 pub use stdio::StdioClient;
 pub use streamable_http::StreamableHttpClient;
 pub use types::{
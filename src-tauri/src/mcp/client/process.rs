@@ -0,0 +1,236 @@
+//! Registry of the stdio MCP server processes spawned by the app.
+//!
+//! A server that hangs or outlives its client keeps its process around, and the only way out
+//! used to be restarting the app. Every spawned server is recorded here so that it can be listed
+//! with its uptime and memory and force-terminated. Entries of processes that exited are dropped
+//! when the list is read, and only recorded processes can be killed.
+
+use lazy_static::lazy_static;
+use rust_i18n::t;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Instant;
+use tokio::process::Command;
+
+use crate::mcp::McpError;
+
+use super::McpClientResult;
+
+struct SpawnedProcess {
+    server: String,
+    started_at: Instant,
+}
+
+lazy_static! {
+    static ref MCP_PROCESSES: Mutex<HashMap<u32, SpawnedProcess>> = Mutex::new(HashMap::new());
+}
+
+/// A running stdio MCP server process.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpProcessInfo {
+    pub pid: u32,
+    /// Name of the MCP server the process was started for
+    pub server: String,
+    pub uptime_secs: u64,
+    /// Resident memory, `None` when the platform did not report it
+    pub memory_bytes: Option<u64>,
+}
+
+/// Records a spawned server process.
+pub fn register(pid: u32, server: &str) {
+    if let Ok(mut processes) = MCP_PROCESSES.lock() {
+        processes.insert(
+            pid,
+            SpawnedProcess {
+                server: server.to_string(),
+                started_at: Instant::now(),
+            },
+        );
+    }
+}
+
+fn forget(pid: u32) {
+    if let Ok(mut processes) = MCP_PROCESSES.lock() {
+        processes.remove(&pid);
+    }
+}
+
+/// Probes a process, `None` when it has exited, otherwise its resident memory if known.
+#[cfg(unix)]
+async fn probe(pid: u32) -> Option<Option<u64>> {
+    let output = Command::new("ps")
+        .args(["-o", "stat=,rss=", "-p", &pid.to_string()])
+        .output()
+        .await
+        .ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut fields = stdout.split_whitespace();
+    let stat = fields.next()?;
+    // A zombie has exited and only waits to be reaped
+    if !output.status.success() || stat.starts_with('Z') {
+        return None;
+    }
+    Some(
+        fields
+            .next()
+            .and_then(|rss| rss.parse::<u64>().ok())
+            .map(|kb| kb * 1024),
+    )
+}
+
+#[cfg(windows)]
+async fn probe(pid: u32) -> Option<Option<u64>> {
+    let output = Command::new("tasklist")
+        .args(["/FI", &format!("PID eq {}", pid), "/FO", "CSV", "/NH"])
+        .creation_flags(0x08000000)
+        .output()
+        .await
+        .ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    // "node.exe","1234","Console","1","45,312 K"
+    let line = stdout
+        .lines()
+        .find(|line| line.contains(&format!("\"{}\"", pid)))?;
+    let memory = line.rsplit("\",\"").next().and_then(|field| {
+        let digits: String = field.chars().filter(char::is_ascii_digit).collect();
+        digits.parse::<u64>().ok().map(|kb| kb * 1024)
+    });
+    Some(memory)
+}
+
+/// Lists the server processes that are still running, oldest first.
+pub async fn list() -> Vec<McpProcessInfo> {
+    let spawned: Vec<(u32, String, u64)> = match MCP_PROCESSES.lock() {
+        Ok(processes) => processes
+            .iter()
+            .map(|(pid, process)| {
+                (
+                    *pid,
+                    process.server.clone(),
+                    process.started_at.elapsed().as_secs(),
+                )
+            })
+            .collect(),
+        Err(_) => return Vec::new(),
+    };
+
+    let mut running = Vec::with_capacity(spawned.len());
+    for (pid, server, uptime_secs) in spawned {
+        match probe(pid).await {
+            Some(memory_bytes) => running.push(McpProcessInfo {
+                pid,
+                server,
+                uptime_secs,
+                memory_bytes,
+            }),
+            None => forget(pid),
+        }
+    }
+    running.sort_by_key(|process| std::cmp::Reverse(process.uptime_secs));
+    running
+}
+
+/// Force-terminates a recorded server process along with the processes it started.
+///
+/// Servers run in their own process group on Unix, which is killed as a whole so that a server
+/// launched through `npx` or `uvx` does not leave its actual process behind.
+pub async fn kill(pid: u32) -> McpClientResult<()> {
+    let is_recorded = MCP_PROCESSES
+        .lock()
+        .map(|processes| processes.contains_key(&pid))
+        .unwrap_or(false);
+    if !is_recorded || probe(pid).await.is_none() {
+        forget(pid);
+        return Err(McpError::General(
+            t!("mcp.client.stdio_process_not_found", pid = pid).to_string(),
+        ));
+    }
+
+    #[cfg(unix)]
+    let status = {
+        let group = Command::new("kill")
+            .args(["-KILL", "--", &format!("-{}", pid)])
+            .status()
+            .await;
+        match group {
+            Ok(status) if status.success() => Ok(status),
+            _ => {
+                Command::new("kill")
+                    .args(["-KILL", &pid.to_string()])
+                    .status()
+                    .await
+            }
+        }
+    };
+    #[cfg(windows)]
+    let status = Command::new("taskkill")
+        .args(["/F", "/T", "/PID", &pid.to_string()])
+        .creation_flags(0x08000000)
+        .status()
+        .await;
+
+    match status {
+        Ok(status) if status.success() => {
+            log::warn!("Force-terminated MCP server process {}", pid);
+            forget(pid);
+            Ok(())
+        }
+        Ok(status) => Err(McpError::Io(
+            t!(
+                "mcp.client.stdio_process_kill_failed",
+                pid = pid,
+                error = status.to_string()
+            )
+            .to_string(),
+        )),
+        Err(e) => Err(McpError::Io(
+            t!(
+                "mcp.client.stdio_process_kill_failed",
+                pid = pid,
+                error = e.to_string()
+            )
+            .to_string(),
+        )),
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_spawned_process_is_listed_and_killed() {
+        let mut child = Command::new("sh")
+            .args(["-c", "sleep 60"])
+            .process_group(0)
+            .spawn()
+            .expect("spawn child");
+        let pid = child.id().expect("child pid");
+        register(pid, "stuck-server");
+
+        let listed = list().await;
+        let process = listed
+            .iter()
+            .find(|process| process.pid == pid)
+            .expect("listed child");
+        assert_eq!(process.server, "stuck-server");
+        assert!(process.memory_bytes.is_some());
+
+        kill(pid).await.expect("kill child");
+        let status = tokio::time::timeout(Duration::from_secs(5), child.wait())
+            .await
+            .expect("child exited")
+            .expect("child status");
+        assert!(!status.success());
+        assert!(list().await.iter().all(|process| process.pid != pid));
+    }
+
+    #[tokio::test]
+    async fn test_unknown_process_is_not_killed() {
+        let result = kill(u32::MAX).await;
+        assert!(matches!(result, Err(McpError::General(_))));
+    }
+}
@@ -0,0 +1,404 @@
+//! Automatic reconnection of MCP clients.
+//!
+//! A supervisor task probes the connection of a started client. When the server stops
+//! answering, it reconnects with an exponential backoff, lists the tools again and hands
+//! them to the owner of the client, which keeps its tool registry in sync. Stopping the
+//! client cancels the supervisor.
+
+use std::future::Future;
+use std::sync::{Arc, Weak};
+
+use futures::future::BoxFuture;
+use rmcp::ServiceError;
+use rust_i18n::t;
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, timeout, Duration};
+
+use crate::ai::traits::chat::MCPToolDeclaration;
+
+use super::types::{McpClient, McpClientResult, McpServerConfig, McpStatus};
+
+/// Seconds between two probes of a connected server
+const PROBE_INTERVAL_SECS: u64 = 30;
+/// Seconds a probe may take before the connection counts as lost
+const PROBE_TIMEOUT_SECS: u64 = 15;
+
+/// Invoked with the fresh tool list after the client reconnected.
+pub type ReconnectedCallback =
+    Box<dyn Fn(Vec<MCPToolDeclaration>) -> BoxFuture<'static, ()> + Send + Sync>;
+
+/// When and how often a lost connection is re-established.
+#[derive(Debug, Clone)]
+pub struct ReconnectPolicy {
+    pub probe_interval: Duration,
+    pub probe_timeout: Duration,
+    /// Delay before the first attempt, doubled for each further attempt
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    /// Attempts before giving up, `None` retries forever
+    pub max_attempts: Option<u32>,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            probe_interval: Duration::from_secs(PROBE_INTERVAL_SECS),
+            probe_timeout: Duration::from_secs(PROBE_TIMEOUT_SECS),
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(60),
+            max_attempts: None,
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// The policy of a server, `None` when it has automatic reconnecting turned off.
+    pub fn from_config(config: &McpServerConfig) -> Option<Self> {
+        match config.max_reconnect_attempts {
+            Some(0) => None,
+            max_attempts => Some(Self {
+                max_attempts,
+                ..Default::default()
+            }),
+        }
+    }
+
+    /// Delay before reconnect attempt `attempt`, counted from 0: 1s, 2s, 4s… up to `max_delay`.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        self.base_delay
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_delay)
+    }
+}
+
+/// Runs `connect` until it succeeds, waiting `policy.delay(n)` before attempt `n`.
+/// Returns the error of the last attempt once `policy.max_attempts` is used up.
+async fn retry_with_backoff<T, C, CFut>(
+    name: &str,
+    policy: &ReconnectPolicy,
+    mut connect: C,
+) -> McpClientResult<T>
+where
+    C: FnMut() -> CFut,
+    CFut: Future<Output = McpClientResult<T>>,
+{
+    let mut attempt = 0;
+    loop {
+        let delay = policy.delay(attempt);
+        log::info!(
+            "Reconnecting to MCP server '{}' in {:?} (attempt {}).",
+            name,
+            delay,
+            attempt + 1
+        );
+        sleep(delay).await;
+
+        match connect().await {
+            Ok(value) => return Ok(value),
+            Err(e) => {
+                attempt += 1;
+                log::warn!(
+                    "Reconnect attempt {} to MCP server '{}' failed: {}",
+                    attempt,
+                    name,
+                    e
+                );
+                if policy.max_attempts.is_some_and(|max| attempt >= max) {
+                    return Err(e);
+                }
+            }
+        }
+    }
+}
+
+/// Checks whether the server still answers. Errors returned by the server itself, such as
+/// an unsupported method, prove the connection is alive.
+async fn probe(client: &dyn McpClient, probe_timeout: Duration) -> Result<(), String> {
+    let service = client.client();
+    let guard = service.read().await;
+    let Some(service) = guard.as_ref() else {
+        return Err("the client holds no running service".to_string());
+    };
+    match timeout(probe_timeout, service.peer().list_tools(Default::default())).await {
+        Ok(Ok(_)) | Ok(Err(ServiceError::McpError(_))) => Ok(()),
+        Ok(Err(e)) => Err(e.to_string()),
+        Err(_) => Err(format!("no answer within {:?}", probe_timeout)),
+    }
+}
+
+/// Starts the reconnect supervisor of a started client and stores its handle in the client,
+/// so that `stop()` cancels it.
+pub async fn spawn_reconnect_supervisor(
+    client: Arc<dyn McpClient>,
+    policy: ReconnectPolicy,
+    on_reconnected: ReconnectedCallback,
+) {
+    let weak_client = Arc::downgrade(&client);
+    let handle = tokio::spawn(supervise(weak_client, policy, on_reconnected));
+    client.set_supervisor(handle).await;
+}
+
+async fn supervise(
+    client: Weak<dyn McpClient>,
+    policy: ReconnectPolicy,
+    on_reconnected: ReconnectedCallback,
+) {
+    loop {
+        sleep(policy.probe_interval).await;
+        // The client is gone when its owner dropped it without stopping it.
+        let Some(client) = client.upgrade() else {
+            return;
+        };
+        let name = client.name().await;
+
+        let reason = match probe(client.as_ref(), policy.probe_timeout).await {
+            Ok(()) => continue,
+            Err(reason) => reason,
+        };
+        log::warn!("Lost connection to MCP server '{}': {}", name, reason);
+        client
+            .set_status(McpStatus::Error(
+                t!("mcp.client.connection_lost", name = &name, error = &reason).to_string(),
+            ))
+            .await;
+
+        let reconnected = retry_with_backoff(&name, &policy, || async {
+            client.reconnect().await?;
+            client.list_tools().await
+        })
+        .await;
+
+        match reconnected {
+            Ok(tools) => {
+                log::info!(
+                    "Reconnected to MCP server '{}', {} tools available.",
+                    name,
+                    tools.len()
+                );
+                on_reconnected(tools).await;
+            }
+            Err(e) => {
+                log::error!("Giving up reconnecting to MCP server '{}': {}", name, e);
+                client
+                    .set_status(McpStatus::Error(
+                        t!(
+                            "mcp.client.reconnect_gave_up",
+                            name = &name,
+                            error = e.to_string()
+                        )
+                        .to_string(),
+                    ))
+                    .await;
+                return;
+            }
+        }
+    }
+}
+
+/// Aborts a supervisor task and waits until it is gone, so it cannot store a new
+/// connection after the caller stopped the client.
+pub(crate) async fn cancel_supervisor(handle: Option<JoinHandle<()>>) {
+    if let Some(handle) = handle {
+        handle.abort();
+        let _ = handle.await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcp::client::{McpProtocolType, StreamableHttpClient};
+    use crate::mcp::McpError;
+    use rmcp::{
+        model::{ServerCapabilities, ServerInfo},
+        transport::streamable_http_server::{
+            session::local::LocalSessionManager, StreamableHttpServerConfig, StreamableHttpService,
+        },
+        ServerHandler,
+    };
+    use std::net::SocketAddr;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+    use tokio::sync::{mpsc, oneshot};
+
+    #[test]
+    fn delay_doubles_up_to_the_cap() {
+        let policy = ReconnectPolicy::default();
+        let delays: Vec<u64> = (0..9).map(|n| policy.delay(n).as_secs()).collect();
+        assert_eq!(delays, vec![1, 2, 4, 8, 16, 32, 60, 60, 60]);
+        assert_eq!(policy.delay(u32::MAX), Duration::from_secs(60));
+    }
+
+    #[test]
+    fn zero_attempts_turns_reconnecting_off() {
+        let mut config = McpServerConfig::default();
+        assert!(ReconnectPolicy::from_config(&config).is_some_and(|p| p.max_attempts.is_none()));
+
+        config.max_reconnect_attempts = Some(0);
+        assert!(ReconnectPolicy::from_config(&config).is_none());
+
+        config.max_reconnect_attempts = Some(5);
+        assert_eq!(
+            ReconnectPolicy::from_config(&config).and_then(|p| p.max_attempts),
+            Some(5)
+        );
+    }
+
+    #[tokio::test]
+    async fn retry_stops_after_max_attempts() {
+        let policy = ReconnectPolicy {
+            base_delay: Duration::from_millis(1),
+            max_attempts: Some(3),
+            ..Default::default()
+        };
+        let attempts = AtomicUsize::new(0);
+
+        let result: McpClientResult<()> = retry_with_backoff("flaky", &policy, || async {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            Err(McpError::ClientStartError("Connection refused".to_string()))
+        })
+        .await;
+
+        assert!(result.is_err());
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+    }
+
+    #[derive(Clone)]
+    struct MockServer;
+
+    impl ServerHandler for MockServer {
+        fn get_info(&self) -> ServerInfo {
+            let mut info = ServerInfo::default();
+            info.capabilities = ServerCapabilities::builder().enable_tools().build();
+            info
+        }
+    }
+
+    /// A Streamable HTTP (SSE) MCP server on its own runtime. Dropping the runtime on
+    /// `stop` closes every open connection, like a crashed server would.
+    struct MockSseServer {
+        shutdown: Option<oneshot::Sender<()>>,
+        thread: Option<std::thread::JoinHandle<()>>,
+    }
+
+    impl MockSseServer {
+        fn start(addr: SocketAddr) -> Self {
+            let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+            let (ready_tx, ready_rx) = std::sync::mpsc::channel();
+            let thread = std::thread::spawn(move || {
+                let runtime = tokio::runtime::Builder::new_multi_thread()
+                    .enable_all()
+                    .build()
+                    .expect("runtime should build");
+                runtime.block_on(async move {
+                    let listener = tokio::net::TcpListener::bind(addr)
+                        .await
+                        .expect("mock server should bind");
+                    ready_tx.send(()).ok();
+                    let service = StreamableHttpService::new(
+                        || Ok(MockServer),
+                        Arc::new(LocalSessionManager::default()),
+                        StreamableHttpServerConfig::default(),
+                    );
+                    let app = axum::Router::new().nest_service("/mcp", service);
+                    tokio::select! {
+                        _ = axum::serve(listener, app) => {}
+                        _ = shutdown_rx => {}
+                    }
+                });
+            });
+            ready_rx.recv().expect("mock server should start");
+            Self {
+                shutdown: Some(shutdown_tx),
+                thread: Some(thread),
+            }
+        }
+
+        fn stop(&mut self) {
+            if let Some(shutdown) = self.shutdown.take() {
+                shutdown.send(()).ok();
+            }
+            if let Some(thread) = self.thread.take() {
+                thread.join().ok();
+            }
+        }
+    }
+
+    impl Drop for MockSseServer {
+        fn drop(&mut self) {
+            self.stop();
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn client_reconnects_after_server_restart() {
+        let addr = std::net::TcpListener::bind("127.0.0.1:0")
+            .and_then(|listener| listener.local_addr())
+            .expect("a free port should be available");
+        let mut server = MockSseServer::start(addr);
+
+        let client: Arc<dyn McpClient> = Arc::new(
+            StreamableHttpClient::new(McpServerConfig {
+                name: "mock".to_string(),
+                protocol_type: McpProtocolType::StreamableHttp,
+                url: Some(format!("http://{}/mcp", addr)),
+                timeout: Some(2),
+                ..Default::default()
+            })
+            .expect("client should be created"),
+        );
+        let statuses = Arc::new(Mutex::new(Vec::new()));
+        let recorded = statuses.clone();
+        client
+            .on_status_change(Box::new(move |_, status| {
+                recorded.lock().unwrap().push(status);
+            }))
+            .await;
+        client.start().await.expect("client should connect");
+        client.list_tools().await.expect("tools should be listed");
+
+        let (reconnected_tx, mut reconnected_rx) = mpsc::unbounded_channel();
+        spawn_reconnect_supervisor(
+            client.clone(),
+            ReconnectPolicy {
+                probe_interval: Duration::from_millis(100),
+                probe_timeout: Duration::from_secs(1),
+                base_delay: Duration::from_millis(100),
+                max_delay: Duration::from_millis(400),
+                max_attempts: None,
+            },
+            Box::new(move |tools| {
+                let reconnected_tx = reconnected_tx.clone();
+                Box::pin(async move {
+                    reconnected_tx.send(tools).ok();
+                })
+            }),
+        )
+        .await;
+
+        server.stop();
+        tokio::time::timeout(Duration::from_secs(10), async {
+            while !matches!(client.status().await, McpStatus::Error(_)) {
+                sleep(Duration::from_millis(50)).await;
+            }
+        })
+        .await
+        .expect("the lost connection should be detected");
+
+        let _server = MockSseServer::start(addr);
+        let tools = tokio::time::timeout(Duration::from_secs(20), reconnected_rx.recv())
+            .await
+            .expect("the client should reconnect")
+            .expect("the supervisor should report the tools");
+        assert!(tools.is_empty());
+        assert_eq!(client.status().await, McpStatus::Running);
+        assert!(statuses
+            .lock()
+            .unwrap()
+            .ends_with(&[McpStatus::Connected, McpStatus::Running]));
+
+        // Stopping the client cancels the supervisor right away.
+        client.stop().await.expect("client should stop");
+        assert_eq!(client.status().await, McpStatus::Stopped);
+    }
+}
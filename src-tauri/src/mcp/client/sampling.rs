@@ -0,0 +1,178 @@
+//! Support for MCP sampling, i.e. `sampling/createMessage` requests that let a server
+//! ask the client's model for a completion.
+//!
+//! Sampling is opt-in per server through `McpServerConfig::sampling`. The client only
+//! advertises the capability when the server is allowed to use it and a
+//! `SamplingHandler` is installed; the handler decides how the completion is produced.
+//! The protocol side lives in `McpClientHandler`.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use super::McpClientResult;
+
+/// Whether an MCP server may request completions from the configured model.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum McpSamplingPolicy {
+    /// Sampling requests are rejected and the capability is not advertised
+    #[default]
+    Deny,
+    /// Every sampling request needs the user's approval
+    Ask,
+    /// Sampling requests are answered without asking
+    Allow,
+}
+
+/// A text message of a sampling request.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SamplingMessage {
+    pub role: String,
+    pub text: String,
+}
+
+/// A sampling request from an MCP server, reduced to what the chat model needs.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SamplingRequest {
+    /// Name of the MCP server that sent the request
+    pub server_name: String,
+    pub system_prompt: Option<String>,
+    pub messages: Vec<SamplingMessage>,
+    pub max_tokens: u32,
+    pub temperature: Option<f32>,
+    pub stop_sequences: Vec<String>,
+    /// Whether the user has to approve the request before it is sent to the model
+    pub requires_approval: bool,
+}
+
+/// The completion returned to the MCP server.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SamplingResponse {
+    /// Name of the model that produced the completion
+    pub model: String,
+    pub text: String,
+    pub stop_reason: Option<String>,
+}
+
+/// Produces completions for sampling requests of MCP servers.
+#[async_trait::async_trait]
+pub trait SamplingHandler: Send + Sync {
+    async fn create_message(&self, request: SamplingRequest) -> McpClientResult<SamplingResponse>;
+}
+
+/// Extracts the text content of a sampling message, which is either a single content
+/// block or a list of blocks. Non-text blocks such as images are skipped.
+fn message_text(content: &Value) -> String {
+    let blocks = match content {
+        Value::Array(blocks) => blocks.iter().collect::<Vec<_>>(),
+        block => vec![block],
+    };
+    blocks
+        .into_iter()
+        .filter(|block| block.get("type").and_then(|t| t.as_str()) == Some("text"))
+        .filter_map(|block| block.get("text").and_then(|t| t.as_str()))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Builds a `SamplingRequest` from the JSON params of `sampling/createMessage`.
+///
+/// Returns `None` if the request has no text to complete.
+pub(super) fn parse_sampling_request(
+    server_name: &str,
+    params: &Value,
+    requires_approval: bool,
+) -> Option<SamplingRequest> {
+    let messages: Vec<SamplingMessage> = params
+        .get("messages")
+        .and_then(|m| m.as_array())
+        .map(|messages| {
+            messages
+                .iter()
+                .filter_map(|message| {
+                    let role = message.get("role").and_then(|r| r.as_str())?;
+                    let text = message.get("content").map(message_text)?;
+                    (!text.is_empty()).then(|| SamplingMessage {
+                        role: role.to_string(),
+                        text,
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    if messages.is_empty() {
+        return None;
+    }
+
+    Some(SamplingRequest {
+        server_name: server_name.to_string(),
+        system_prompt: params
+            .get("systemPrompt")
+            .and_then(|s| s.as_str())
+            .filter(|s| !s.trim().is_empty())
+            .map(|s| s.to_string()),
+        messages,
+        max_tokens: params
+            .get("maxTokens")
+            .and_then(|m| m.as_u64())
+            .map_or(1024, |m| m.min(u32::MAX as u64) as u32),
+        temperature: params
+            .get("temperature")
+            .and_then(|t| t.as_f64())
+            .map(|t| t as f32),
+        stop_sequences: params
+            .get("stopSequences")
+            .and_then(|s| s.as_array())
+            .map(|s| {
+                s.iter()
+                    .filter_map(|s| s.as_str().map(|s| s.to_string()))
+                    .collect()
+            })
+            .unwrap_or_default(),
+        requires_approval,
+    })
+}
+
+/// Serializes a completion in the shape of a `sampling/createMessage` result.
+pub(super) fn sampling_result_json(response: &SamplingResponse) -> Value {
+    let mut result = json!({
+        "model": response.model,
+        "role": "assistant",
+        "content": {
+            "type": "text",
+            "text": response.text,
+        },
+    });
+    if let Some(stop_reason) = &response.stop_reason {
+        result["stopReason"] = json!(stop_reason);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_sampling_request_marks_approval_and_skips_images() {
+        let params = json!({
+            "messages": [
+                {"role": "user", "content": [
+                    {"type": "image", "data": "AAAA", "mimeType": "image/png"},
+                    {"type": "text", "text": "Describe it"}
+                ]},
+                {"role": "assistant", "content": {"type": "image", "data": "AAAA", "mimeType": "image/png"}}
+            ],
+            "maxTokens": 10,
+            "stopSequences": ["END"]
+        });
+        let request = parse_sampling_request("vision", &params, true).expect("text message");
+        assert_eq!(request.messages.len(), 1);
+        assert_eq!(request.messages[0].text, "Describe it");
+        assert_eq!(request.stop_sequences, vec!["END".to_string()]);
+        assert!(request.requires_approval);
+
+        assert!(parse_sampling_request("vision", &json!({"messages": []}), false).is_none());
+    }
+}
@@ -28,22 +28,23 @@
 //! ```
 //!
 
-use std::sync::Arc;
+use std::{collections::HashSet, sync::Arc};
 
-use rmcp::model::{ClientCapabilities, ClientInfo, Implementation, InitializeRequestParams};
 use rmcp::{service::RunningService, transport::TokioChildProcess, RoleClient, ServiceExt as _};
 use rust_i18n::t;
-use tokio::{process::Command, sync::RwLock};
+use tokio::{process::Command, sync::RwLock, task::JoinHandle};
 
 #[allow(unused)]
 #[cfg(unix)]
 use std::os::unix::process::CommandExt;
 
 use crate::mcp::client::types::McpClientInternal;
-use crate::mcp::client::util::find_executable_in_common_paths;
+use crate::mcp::client::util::{expand_env_vars, find_executable_in_common_paths};
 use crate::mcp::McpError;
 
 use super::core::McpClientCore;
+use super::handler::{McpClientHandler, McpRoots};
+use super::sampling::SamplingHandler;
 use super::{
     McpClient, McpClientResult, McpProtocolType, McpServerConfig, McpStatus, StatusChangeCallback,
 };
@@ -108,6 +109,53 @@ impl McpClientInternal for StdioClient {
     async fn notify_status_change(&self, name: String, status: McpStatus) {
         self.core.notify_status_change(name, status).await;
     }
+
+    async fn set_retry_safe_tools(&self, tools: HashSet<String>) {
+        self.core.set_retry_safe_tools(tools).await;
+    }
+
+    async fn is_retry_safe(&self, tool_name: &str) -> bool {
+        self.core.is_retry_safe(tool_name).await
+    }
+
+    async fn set_supervisor(&self, handle: JoinHandle<()>) {
+        self.core.set_supervisor(handle).await;
+    }
+
+    async fn cancel_supervisor(&self) {
+        self.core.cancel_supervisor().await;
+    }
+}
+
+/// Sets the configured environment variables and working directory on the server command.
+///
+/// The child inherits the app's environment; configured variables override it, with `${VAR}`
+/// references expanded from the host. A working directory that does not exist is an error,
+/// otherwise the spawn would fail with a misleading "command not found".
+fn apply_env_and_cwd(cmd: &mut Command, config: &McpServerConfig) -> McpClientResult<()> {
+    if let Some(env) = &config.env {
+        cmd.envs(env.iter().filter_map(|(k, v)| {
+            let k = k.trim();
+            let v = v.trim();
+            (!k.is_empty() && !v.is_empty()).then(|| (k.to_string(), expand_env_vars(v)))
+        }));
+    }
+
+    if let Some(cwd) = config
+        .cwd
+        .as_deref()
+        .map(str::trim)
+        .filter(|c| !c.is_empty())
+    {
+        let cwd = expand_env_vars(cwd);
+        if !std::path::Path::new(&cwd).is_dir() {
+            return Err(McpError::ClientConfigError(
+                t!("mcp.client.stdio_cwd_not_found", cwd = cwd).to_string(),
+            ));
+        }
+        cmd.current_dir(cwd);
+    }
+    Ok(())
 }
 
 /// Implementation of McpClient trait for Stdio transport
@@ -121,7 +169,7 @@ impl McpClient for StdioClient {
     /// on success, or an error.
     async fn perform_connect(
         &self,
-    ) -> McpClientResult<RunningService<RoleClient, InitializeRequestParams>> {
+    ) -> McpClientResult<RunningService<RoleClient, McpClientHandler>> {
         let config = self.core.get_config().await;
         let original_cmd_str = config
             .command
@@ -200,13 +248,7 @@ impl McpClient for StdioClient {
             });
         cmd.args(args);
 
-        if let Some(env) = config.env {
-            cmd.envs(env.iter().filter_map(|(k, v)| {
-                let k = k.trim();
-                let v = v.trim();
-                (!k.is_empty() && !v.is_empty()).then_some((k, v))
-            }));
-        }
+        apply_env_and_cwd(&mut cmd, &config)?;
 
         // On Unix, create a new process group to prevent signals from propagating to the parent.
         // This is crucial to prevent the main application from crashing when the child process is terminated.
@@ -266,14 +308,12 @@ impl McpClient for StdioClient {
             }
         })?;
 
-        let mut client_info = ClientInfo::default();
-        client_info.protocol_version = Default::default();
-        client_info.capabilities = ClientCapabilities::default();
-        client_info.client_info =
-            Implementation::new("Chatspeed MCP Client", env!("CARGO_PKG_VERSION"))
-                .with_title("Chatspeed")
-                .with_website_url("https://chatspeed.aidyou.ai");
-        client_info.serve(process).await.map_err(|e| {
+        if let Some(pid) = process.id() {
+            super::process::register(pid, &config.name);
+        }
+
+        let client_handler = self.core.client_handler().await;
+        client_handler.serve(process).await.map_err(|e| {
             // Optional: Wrap with t!
             let detailed_error = e.to_string();
             log::error!("Start StdioClient error: {}", detailed_error);
@@ -288,7 +328,7 @@ impl McpClient for StdioClient {
         })
     }
 
-    fn client(&self) -> Arc<RwLock<Option<RunningService<RoleClient, InitializeRequestParams>>>> {
+    fn client(&self) -> Arc<RwLock<Option<RunningService<RoleClient, McpClientHandler>>>> {
         self.core.get_client_instance_arc()
     }
 
@@ -318,6 +358,14 @@ impl McpClient for StdioClient {
     async fn on_status_change(&self, callback: StatusChangeCallback) {
         self.core.set_on_status_change_callback(callback).await;
     }
+
+    async fn set_sampling_handler(&self, handler: Arc<dyn SamplingHandler>) {
+        self.core.set_sampling_handler(handler).await;
+    }
+
+    async fn set_roots(&self, roots: McpRoots) {
+        self.core.set_roots(roots).await;
+    }
 }
 
 #[cfg(test)]
@@ -325,7 +373,52 @@ mod test {
     use rmcp::{transport::TokioChildProcess, ServiceExt};
     use tokio::process::Command;
 
-    use crate::mcp::{client::McpClient as _, McpError};
+    use super::apply_env_and_cwd;
+    use crate::mcp::{
+        client::{McpClient as _, McpServerConfig},
+        McpError,
+    };
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_child_process_gets_configured_env_and_cwd() {
+        std::env::set_var("CS_MCP_TEST_HOST_TOKEN", "host-secret");
+        let cwd = std::env::temp_dir().canonicalize().unwrap();
+        let config: McpServerConfig = serde_json::from_value(serde_json::json!({
+            "name": "env-test",
+            "type": "stdio",
+            "command": "sh",
+            "args": ["-c", "printf '%s|%s|%s' \"$CS_MCP_API_KEY\" \"$CS_MCP_TOKEN\" \"$PWD\""],
+            "env": {
+                "CS_MCP_API_KEY": "sk-test",
+                "CS_MCP_TOKEN": "Bearer ${CS_MCP_TEST_HOST_TOKEN}"
+            },
+            "cwd": cwd.to_string_lossy()
+        }))
+        .unwrap();
+
+        let mut cmd = Command::new("sh");
+        cmd.args(config.args.clone().unwrap());
+        apply_env_and_cwd(&mut cmd, &config).unwrap();
+        let output = cmd.output().await.unwrap();
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let parts: Vec<&str> = stdout.split('|').collect();
+        assert_eq!(parts[0], "sk-test");
+        assert_eq!(parts[1], "Bearer host-secret");
+        assert_eq!(std::path::Path::new(parts[2]).canonicalize().unwrap(), cwd);
+    }
+
+    #[test]
+    fn test_missing_cwd_is_a_config_error() {
+        let config = McpServerConfig {
+            command: Some("sh".into()),
+            cwd: Some("/definitely/not/a/real/dir".into()),
+            ..Default::default()
+        };
+        let result = apply_env_and_cwd(&mut Command::new("sh"), &config);
+        assert!(matches!(result, Err(McpError::ClientConfigError(_))));
+    }
 
     #[tokio::test]
     async fn stdio_test() -> Result<(), McpError> {
@@ -1,10 +1,9 @@
 //! Streamable HTTP client implementation for ModelScope Control Protocol (MCP)
-use std::sync::Arc;
 use std::time::Duration;
+use std::{collections::HashSet, sync::Arc};
 
 use reqwest::{header, Client};
 use rmcp::{
-    model::{ClientCapabilities, ClientInfo, Implementation, InitializeRequestParams},
     service::RunningService,
     transport::{
         common::client_side_sse::ExponentialBackoff,
@@ -14,10 +13,13 @@ use rmcp::{
 };
 use rust_i18n::t;
 use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
 
 use crate::mcp::McpError;
 
 use super::core::McpClientCore;
+use super::handler::{McpClientHandler, McpRoots};
+use super::sampling::SamplingHandler;
 use super::{
     types::{McpClientInternal, McpStatus, StatusChangeCallback},
     McpClient, McpClientResult, McpProtocolType, McpServerConfig,
@@ -104,13 +106,29 @@ impl McpClientInternal for StreamableHttpClient {
     async fn notify_status_change(&self, name: String, status: McpStatus) {
         self.core.notify_status_change(name, status).await;
     }
+
+    async fn set_retry_safe_tools(&self, tools: HashSet<String>) {
+        self.core.set_retry_safe_tools(tools).await;
+    }
+
+    async fn is_retry_safe(&self, tool_name: &str) -> bool {
+        self.core.is_retry_safe(tool_name).await
+    }
+
+    async fn set_supervisor(&self, handle: JoinHandle<()>) {
+        self.core.set_supervisor(handle).await;
+    }
+
+    async fn cancel_supervisor(&self) {
+        self.core.cancel_supervisor().await;
+    }
 }
 
 #[async_trait::async_trait]
 impl McpClient for StreamableHttpClient {
     async fn perform_connect(
         &self,
-    ) -> McpClientResult<RunningService<RoleClient, InitializeRequestParams>> {
+    ) -> McpClientResult<RunningService<RoleClient, McpClientHandler>> {
         let config = self.core.get_config().await;
         let url_str = config.url.as_deref().filter(|s| !s.is_empty());
 
@@ -132,14 +150,8 @@ impl McpClient for StreamableHttpClient {
         transport_config.auth_header = config.bearer_token.clone();
         let transport = StreamableHttpClientTransport::with_client(http_client, transport_config);
 
-        let mut client_info = ClientInfo::default();
-        client_info.protocol_version = Default::default();
-        client_info.capabilities = ClientCapabilities::default();
-        client_info.client_info =
-            Implementation::new("Chatspeed MCP Client", env!("CARGO_PKG_VERSION"))
-                .with_title("Chatspeed")
-                .with_website_url("https://chatspeed.aidyou.ai");
-        let client_service_result = client_info
+        let client_handler = self.core.client_handler().await;
+        let client_service_result = client_handler
             .serve(transport)
             .await
             .inspect_err(|e| log::error!("MCP StreamableHttp client error: {}", e.to_string()));
@@ -162,7 +174,7 @@ impl McpClient for StreamableHttpClient {
         Ok(client_service)
     }
 
-    fn client(&self) -> Arc<RwLock<Option<RunningService<RoleClient, InitializeRequestParams>>>> {
+    fn client(&self) -> Arc<RwLock<Option<RunningService<RoleClient, McpClientHandler>>>> {
         self.core.get_client_instance_arc()
     }
 
@@ -192,6 +204,14 @@ impl McpClient for StreamableHttpClient {
     async fn on_status_change(&self, callback: StatusChangeCallback) {
         self.core.set_on_status_change_callback(callback).await;
     }
+
+    async fn set_sampling_handler(&self, handler: Arc<dyn SamplingHandler>) {
+        self.core.set_sampling_handler(handler).await;
+    }
+
+    async fn set_roots(&self, roots: McpRoots) {
+        self.core.set_roots(roots).await;
+    }
 }
 
 #[cfg(test)]
@@ -1,20 +1,25 @@
-use rmcp::model::{CallToolRequestParams, InitializeRequestParams};
-use rmcp::service::RunningService;
+use rmcp::model::{CallToolRequestParams, ClientRequest, Request, ServerResult};
+use rmcp::service::{PeerRequestOptions, RunningService, ServiceError};
 use rmcp::RoleClient;
 use rust_i18n::t;
 use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
 use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
 use tokio::time::{timeout, Duration};
 
 use std::collections::HashSet;
 use std::fmt::{Display, Formatter};
+use std::future::Future;
 use std::sync::Arc;
+use std::time::Instant;
 
 use crate::ai::traits::chat::MCPToolDeclaration;
 use crate::mcp::McpError; // Ensure this is the correct path
 
-use super::util::get_tools;
+use super::handler::{McpClientHandler, McpRoots};
+use super::sampling::{McpSamplingPolicy, SamplingHandler};
+use super::util::{get_retry_safe_tools, get_tools, is_connection_error};
 
 /// MCP protocol type
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -70,6 +75,9 @@ impl Display for McpProtocolType {
     }
 }
 
+/// Seconds a tool call may go without a result or progress report when not configured.
+pub const DEFAULT_CALL_TIMEOUT_SECS: u64 = 30;
+
 /// Configuration for MCP servers
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct McpServerConfig {
@@ -97,10 +105,20 @@ pub struct McpServerConfig {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub args: Option<Vec<String>>,
 
-    /// Environment variables for Stdio protocol
-    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Environment variables for Stdio protocol, set over the app's own environment.
+    /// Values may reference host variables as `${VAR}`. Accepts `[[key, value]]` pairs
+    /// or a `{ key: value }` object.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_env"
+    )]
     pub env: Option<Vec<(String, String)>>,
 
+    /// Working directory of the Stdio server process, the app's own when not set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
+
     /// Disabled tools
     #[serde(skip_serializing_if = "Option::is_none")]
     pub disabled_tools: Option<HashSet<String>>,
@@ -108,6 +126,20 @@ pub struct McpServerConfig {
     /// Timeout in seconds for operations like list_tools
     #[serde(skip_serializing_if = "Option::is_none")]
     pub timeout: Option<u64>,
+
+    /// Seconds a tool call may go without a result or a progress report before it is
+    /// cancelled, `DEFAULT_CALL_TIMEOUT_SECS` when not set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub call_timeout: Option<u64>,
+
+    /// Whether the server may request completions from the configured model
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sampling: Option<McpSamplingPolicy>,
+
+    /// Reconnect attempts after the connection is lost, retrying forever when not set.
+    /// `0` turns automatic reconnecting off.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_reconnect_attempts: Option<u32>,
 }
 
 impl Default for McpServerConfig {
@@ -121,12 +153,44 @@ impl Default for McpServerConfig {
             command: Default::default(),
             args: Default::default(),
             env: Default::default(),
+            cwd: Default::default(),
             disabled_tools: Default::default(),
             timeout: Some(60),
+            call_timeout: Default::default(),
+            sampling: Default::default(),
+            max_reconnect_attempts: Default::default(),
         }
     }
 }
 
+/// Reads the `env` of a server config written either as `[[key, value]]` pairs, the format
+/// the app saves, or as a `{ key: value }` object, the format of most MCP server READMEs.
+fn deserialize_env<'de, D>(deserializer: D) -> Result<Option<Vec<(String, String)>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum EnvFormat {
+        Pairs(Vec<(String, String)>),
+        Map(Map<String, Value>),
+    }
+
+    Ok(match Option::<EnvFormat>::deserialize(deserializer)? {
+        None => None,
+        Some(EnvFormat::Pairs(pairs)) => Some(pairs),
+        Some(EnvFormat::Map(map)) => Some(
+            map.into_iter()
+                .map(|(key, value)| match value {
+                    Value::String(s) => (key, s),
+                    Value::Null => (key, String::new()),
+                    other => (key, other.to_string()),
+                })
+                .collect(),
+        ),
+    })
+}
+
 pub type McpClientResult<T> = Result<T, McpError>;
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -167,6 +231,18 @@ pub(crate) trait McpClientInternal: Send + Sync {
 
     /// Internal method to notify status change
     async fn notify_status_change(&self, name: String, status: McpStatus);
+
+    /// Internal method to remember which tools may be retried after a reconnect
+    async fn set_retry_safe_tools(&self, tools: HashSet<String>);
+
+    /// Whether a tool is annotated as read-only or idempotent
+    async fn is_retry_safe(&self, tool_name: &str) -> bool;
+
+    /// Internal method to keep the reconnect supervisor task, cancelling an older one
+    async fn set_supervisor(&self, handle: JoinHandle<()>);
+
+    /// Internal method to cancel the reconnect supervisor task and wait until it is gone
+    async fn cancel_supervisor(&self);
 }
 
 /// Main trait containing methods for an MCP client.
@@ -188,20 +264,28 @@ pub trait McpClient: Send + Sync + McpClientInternal {
 
     /// Gets the running service instance
     /// Implementors should return a clone of their `Arc<RwLock<Option<RunningService<...>>>>`.
-    fn client(&self) -> Arc<RwLock<Option<RunningService<RoleClient, InitializeRequestParams>>>>;
+    fn client(&self) -> Arc<RwLock<Option<RunningService<RoleClient, McpClientHandler>>>>;
 
     async fn status(&self) -> McpStatus;
 
     /// Set callback for status changes
     async fn on_status_change(&self, callback: StatusChangeCallback);
 
+    /// Sets the handler answering sampling requests of the server.
+    /// Takes effect on the next connect.
+    async fn set_sampling_handler(&self, handler: Arc<dyn SamplingHandler>);
+
+    /// Sets the workspace folders advertised to the server as roots.
+    /// Takes effect on the next connect, later updates of `roots` are seen immediately.
+    async fn set_roots(&self, roots: McpRoots);
+
     /// Performs the client-specific connection logic.
     /// This method should establish the connection and return the running service instance
     /// upon success, or an error upon failure. It should NOT set McpStatus itself,
     /// nor should it store the running service in the shared client field.
     async fn perform_connect(
         &self,
-    ) -> McpClientResult<RunningService<RoleClient, InitializeRequestParams>>;
+    ) -> McpClientResult<RunningService<RoleClient, McpClientHandler>>;
 
     /// Starts the MCP client connection.
     /// This default implementation calls `perform_connect` and manages status updates
@@ -220,9 +304,24 @@ pub trait McpClient: Send + Sync + McpClientInternal {
         }
     }
 
+    /// Replaces the connection with a new one, keeping the reconnect supervisor running.
+    async fn reconnect(&self) -> McpClientResult<()> {
+        // Cancel a dead service without waiting on the server, it is likely gone already.
+        let previous = self.client().write().await.take();
+        if let Some(service_instance) = previous {
+            if let Err(e) = service_instance.cancel().await {
+                log::warn!("Error during stop before reconnect: {}", e);
+            }
+        }
+        self.start().await
+    }
+
     /// Stops the running MCP client.
     /// This is a default implementation.
     async fn stop(&self) -> McpClientResult<()> {
+        // Stopped on purpose, the connection must not be re-established.
+        self.cancel_supervisor().await;
+
         let client_arc = self.client();
         let mut guard = client_arc.write().await;
         if let Some(service_instance) = guard.take() {
@@ -265,6 +364,8 @@ pub trait McpClient: Send + Sync + McpClientInternal {
 
                     // set status to running
                     self.set_status(McpStatus::Running).await;
+                    self.set_retry_safe_tools(get_retry_safe_tools(&tools))
+                        .await;
 
                     Ok(get_tools(&tools))
                 }
@@ -287,64 +388,126 @@ pub trait McpClient: Send + Sync + McpClientInternal {
         }
     }
 
+    /// Tells the connected server that the advertised roots have changed,
+    /// so it can request them again with `roots/list`.
+    async fn notify_roots_changed(&self) -> McpClientResult<()> {
+        let client_arc = self.client();
+        let guard = client_arc.read().await;
+        if let Some(service_instance) = guard.as_ref() {
+            service_instance
+                .peer()
+                .notify_roots_list_changed()
+                .await
+                .map_err(|e| McpError::ClientCallError(e.to_string()))?;
+        }
+        Ok(())
+    }
+
     /// Calls a specific tool with given arguments, with automatic reconnection and retry logic.
+    ///
+    /// If the connection drops during the call, the client reconnects and retries once,
+    /// but only for tools annotated as read-only or idempotent. Other tools fail with
+    /// `McpError::CallInterrupted` so the caller can decide whether to repeat them. A call
+    /// is cancelled once it goes the server's `call_timeout` without a result or a progress
+    /// report.
     async fn call(&self, tool_name: &str, args: Value) -> McpClientResult<Value> {
-        let result = self.try_call(tool_name, args.clone()).await;
-
-        match result {
-            Ok(val) => Ok(val),
-            Err(e) => {
-                let error_string = e.to_string();
-                // Check for errors indicating a stale connection that might be fixed by reconnecting.
-                if error_string.contains("410") // HTTP 410 Gone
-                    || error_string.contains("channel closed") // Internal rmcp/tonic error
-                    || error_string.contains("Connection refused") // TCP error
-                    || error_string.contains("transport error")
-                // Generic reqwest/http error
-                {
-                    log::warn!(
-                        "MCP call to '{}' failed with connection error: {}. Attempting to reconnect and retry.",
-                        self.name().await,
-                        error_string
-                    );
-
-                    // Stop the client, ignoring errors as it's likely already dead.
-                    if let Err(stop_err) = self.stop().await {
-                        log::warn!("Error during stop before reconnect: {}", stop_err);
-                    }
-
-                    // Start a new connection. If this fails, propagate the error.
-                    self.start().await?;
-
-                    log::info!(
-                        "Successfully reconnected to MCP server '{}'. Retrying call.",
-                        self.name().await
-                    );
-
-                    // Retry the call once.
-                    self.try_call(tool_name, args).await
-                } else {
-                    Err(e)
-                }
-            }
-        }
+        let timeout = Duration::from_secs(
+            self.config()
+                .await
+                .call_timeout
+                .unwrap_or(DEFAULT_CALL_TIMEOUT_SECS),
+        );
+        let retry_safe = self.is_retry_safe(tool_name).await;
+        call_with_reconnect(
+            tool_name,
+            retry_safe,
+            || self.try_call(tool_name, args.clone(), timeout),
+            || async {
+                log::warn!(
+                    "MCP call to '{}' on '{}' was interrupted by a connection error. Attempting to reconnect.",
+                    tool_name,
+                    self.name().await
+                );
+
+                // Start a new connection. If this fails, propagate the error.
+                self.reconnect().await?;
+
+                log::info!(
+                    "Successfully reconnected to MCP server '{}'.",
+                    self.name().await
+                );
+                Ok(())
+            },
+        )
+        .await
     }
 
     /// Internal method to perform a single tool call attempt without retry logic.
-    async fn try_call(&self, tool_name: &str, args: Value) -> McpClientResult<Value> {
+    ///
+    /// When the call times out, the server is sent a cancellation and the late response,
+    /// if any, is dropped with its request id.
+    async fn try_call(
+        &self,
+        tool_name: &str,
+        args: Value,
+        timeout: Duration,
+    ) -> McpClientResult<Value> {
         let client_arc = self.client();
         let guard = client_arc.read().await; // Use read lock
         if let Some(service_instance) = guard.as_ref() {
-            let call_tool_result = service_instance
+            let mut params = CallToolRequestParams::default();
+            params.name = tool_name.to_string().into();
+            params.arguments = self.arg_parser(args);
+
+            let mut handle = service_instance
                 .peer()
-                .call_tool({
-                    let mut request = CallToolRequestParams::default();
-                    request.name = tool_name.to_string().into();
-                    request.arguments = self.arg_parser(args);
-                    request
-                })
+                .send_cancellable_request(
+                    ClientRequest::CallToolRequest(Request::new(params)),
+                    PeerRequestOptions::no_options(),
+                )
                 .await
-                .map_err(|e| McpError::ClientCallError(e.to_string()))?;
+                .map_err(call_error)?;
+
+            let progress = service_instance.service().progress().clone();
+            let progress_token = handle.progress_token.clone();
+            let response =
+                await_with_idle_timeout(&mut handle.rx, timeout, || progress.last(&progress_token))
+                    .await;
+            progress.forget(&progress_token);
+
+            let call_tool_result = match response {
+                Some(Ok(Ok(ServerResult::CallToolResult(result)))) => result,
+                Some(Ok(Ok(_))) => {
+                    return Err(McpError::ClientCallError(format!(
+                        "Unexpected response to tool call '{}'",
+                        tool_name
+                    )))
+                }
+                Some(Ok(Err(e))) => return Err(call_error(e)),
+                // The response channel closes when the connection goes away.
+                Some(Err(e)) => return Err(McpError::ConnectionLost(e.to_string())),
+                None => {
+                    let name = self.name().await;
+                    log::warn!(
+                        "MCP tool '{}' on '{}' timed out after {:?} without progress, cancelling",
+                        tool_name,
+                        name,
+                        timeout
+                    );
+                    if let Err(e) = handle.cancel(Some("timeout".to_string())).await {
+                        log::debug!("Failed to send cancellation for '{}': {}", tool_name, e);
+                    }
+                    return Err(McpError::Timeout(
+                        t!(
+                            "mcp.client.tool_call_timeout",
+                            tool = tool_name,
+                            name = name,
+                            seconds = timeout.as_secs()
+                        )
+                        .to_string(),
+                    ));
+                }
+            };
 
             // Check the `is_error` field from rmcp::model::CallToolResult
             // If `is_error` is Some(true), it indicates a tool execution error.
@@ -379,3 +542,269 @@ pub trait McpClient: Send + Sync + McpClientInternal {
         }
     }
 }
+
+/// Waits for `response` until it has gone `idle_timeout` without finishing or reporting
+/// progress. `last_activity` tells when progress was last reported.
+///
+/// Returns `None` when the time ran out.
+async fn await_with_idle_timeout<F, A>(
+    response: F,
+    idle_timeout: Duration,
+    last_activity: A,
+) -> Option<F::Output>
+where
+    F: Future,
+    A: Fn() -> Option<Instant>,
+{
+    let started = Instant::now();
+    tokio::pin!(response);
+    loop {
+        let since = last_activity().map_or(started, |last| last.max(started));
+        let remaining = idle_timeout.saturating_sub(since.elapsed());
+        if remaining.is_zero() {
+            return None;
+        }
+        if let Ok(output) = timeout(remaining, &mut response).await {
+            return Some(output);
+        }
+    }
+}
+
+/// Maps a failed request to `McpError::ConnectionLost` when the connection is gone, so that
+/// the call goes through a reconnect.
+fn call_error(error: ServiceError) -> McpError {
+    if is_connection_error(&error) {
+        McpError::ConnectionLost(error.to_string())
+    } else {
+        McpError::ClientCallError(error.to_string())
+    }
+}
+
+/// Runs a tool call, reconnecting once if the connection drops mid-call.
+///
+/// The call is repeated after a successful reconnect only when `retry_safe` is set;
+/// otherwise the connection is still restored but the call fails with
+/// `McpError::CallInterrupted`, since it may already have had side effects.
+async fn call_with_reconnect<A, AFut, R, RFut>(
+    tool_name: &str,
+    retry_safe: bool,
+    mut attempt: A,
+    reconnect: R,
+) -> McpClientResult<Value>
+where
+    A: FnMut() -> AFut,
+    AFut: Future<Output = McpClientResult<Value>>,
+    R: FnOnce() -> RFut,
+    RFut: Future<Output = McpClientResult<()>>,
+{
+    let error = match attempt().await {
+        Ok(value) => return Ok(value),
+        Err(e @ McpError::ConnectionLost(_)) => e,
+        Err(e) => return Err(e),
+    };
+
+    reconnect().await?;
+
+    if retry_safe {
+        log::info!(
+            "Retrying idempotent MCP tool '{}' after reconnect.",
+            tool_name
+        );
+        attempt().await
+    } else {
+        Err(McpError::CallInterrupted(
+            tool_name.to_string(),
+            error.to_string(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcp::client::handler::ProgressActivity;
+    use rmcp::model::{NumberOrString, ProgressToken};
+    use serde_json::json;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+    /// Simulates a server whose connection drops during the first call.
+    struct FlakyConnection {
+        connected: AtomicBool,
+        calls: AtomicUsize,
+        reconnects: AtomicUsize,
+    }
+
+    impl FlakyConnection {
+        fn new() -> Self {
+            Self {
+                connected: AtomicBool::new(true),
+                calls: AtomicUsize::new(0),
+                reconnects: AtomicUsize::new(0),
+            }
+        }
+
+        async fn call(&self) -> McpClientResult<Value> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            if self.calls.load(Ordering::SeqCst) == 1 {
+                // The connection goes away while the first call is in flight.
+                self.connected.store(false, Ordering::SeqCst);
+            }
+            if self.connected.load(Ordering::SeqCst) {
+                Ok(json!({"content": [{"type": "text", "text": "ok"}]}))
+            } else {
+                Err(McpError::ConnectionLost("channel closed".to_string()))
+            }
+        }
+
+        async fn reconnect(&self) -> McpClientResult<()> {
+            self.reconnects.fetch_add(1, Ordering::SeqCst);
+            self.connected.store(true, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_idempotent_call_is_retried_after_disconnect() {
+        let conn = FlakyConnection::new();
+
+        let result =
+            call_with_reconnect("read_resource", true, || conn.call(), || conn.reconnect()).await;
+
+        assert!(result.is_ok());
+        assert_eq!(conn.calls.load(Ordering::SeqCst), 2);
+        assert_eq!(conn.reconnects.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_non_idempotent_call_is_not_retried() {
+        let conn = FlakyConnection::new();
+
+        let result =
+            call_with_reconnect("send_email", false, || conn.call(), || conn.reconnect()).await;
+
+        assert!(
+            matches!(result, Err(McpError::CallInterrupted(ref name, _)) if name == "send_email")
+        );
+        assert_eq!(conn.calls.load(Ordering::SeqCst), 1);
+        // The connection is still restored for subsequent calls.
+        assert_eq!(conn.reconnects.load(Ordering::SeqCst), 1);
+        assert!(conn.connected.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn test_tool_errors_do_not_trigger_reconnect() {
+        let reconnects = AtomicUsize::new(0);
+
+        let result = call_with_reconnect(
+            "search",
+            true,
+            || async { Err(McpError::ClientCallError("invalid query".to_string())) },
+            || async {
+                reconnects.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            },
+        )
+        .await;
+
+        assert!(matches!(result, Err(McpError::ClientCallError(_))));
+        assert_eq!(reconnects.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn test_only_lost_connections_go_through_reconnect() {
+        use rmcp::model::ErrorData;
+        use rmcp::transport::streamable_http_client::StreamableHttpError;
+        use rmcp::transport::DynamicTransportError;
+
+        let transport_error = |error: Box<dyn std::error::Error + Send + Sync>| {
+            ServiceError::TransportSend(DynamicTransportError::from_parts(
+                "test",
+                std::any::TypeId::of::<()>(),
+                error,
+            ))
+        };
+        let lost = [
+            ServiceError::TransportClosed,
+            transport_error(Box::new(std::io::Error::from(
+                std::io::ErrorKind::BrokenPipe,
+            ))),
+            transport_error(Box::new(
+                StreamableHttpError::<reqwest::Error>::SessionExpired,
+            )),
+        ];
+        for error in lost {
+            assert!(matches!(call_error(error), McpError::ConnectionLost(_)));
+        }
+        let failed = [
+            ServiceError::McpError(ErrorData::invalid_params("bad query", None)),
+            transport_error(Box::new(std::io::Error::from(
+                std::io::ErrorKind::InvalidData,
+            ))),
+            transport_error(Box::new(
+                StreamableHttpError::<reqwest::Error>::UnexpectedContentType(None),
+            )),
+        ];
+        for error in failed {
+            assert!(matches!(call_error(error), McpError::ClientCallError(_)));
+        }
+    }
+
+    /// A tool that answers after `delay`, reporting progress every `progress_every` if set.
+    async fn sleepy_tool(
+        delay: Duration,
+        progress_every: Option<Duration>,
+        progress: &ProgressActivity,
+        token: &ProgressToken,
+    ) -> &'static str {
+        let done = tokio::time::sleep(delay);
+        tokio::pin!(done);
+        loop {
+            match progress_every {
+                Some(every) => tokio::select! {
+                    _ = &mut done => return "done",
+                    _ = tokio::time::sleep(every) => progress.touch(token),
+                },
+                None => {
+                    (&mut done).await;
+                    return "done";
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stalled_call_times_out() {
+        let progress = ProgressActivity::default();
+        let token = ProgressToken(NumberOrString::Number(1));
+
+        let result = await_with_idle_timeout(
+            sleepy_tool(Duration::from_secs(5), None, &progress, &token),
+            Duration::from_millis(100),
+            || progress.last(&token),
+        )
+        .await;
+
+        assert!(result.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_progress_keeps_streaming_call_alive() {
+        let progress = ProgressActivity::default();
+        let token = ProgressToken(NumberOrString::Number(2));
+
+        // Runs three times longer than the timeout, but never goes quiet for that long.
+        let result = await_with_idle_timeout(
+            sleepy_tool(
+                Duration::from_millis(600),
+                Some(Duration::from_millis(50)),
+                &progress,
+                &token,
+            ),
+            Duration::from_millis(200),
+            || progress.last(&token),
+        )
+        .await;
+
+        assert_eq!(result, Some("done"));
+    }
+}
@@ -1,9 +1,12 @@
 use rmcp::model::ListToolsResult;
+use rmcp::service::ServiceError;
+use rmcp::transport::streamable_http_client::StreamableHttpError;
 use serde_json::{json, Value};
-use std::{env, path::PathBuf, sync::Arc};
+use std::{collections::HashSet, env, path::PathBuf, sync::Arc};
 use tokio::process::Command as TokioCommand;
 
 use crate::ai::traits::chat::MCPToolDeclaration;
+use crate::libs::retry::{is_retryable_error, is_retryable_io_error};
 
 /// get_tools converts ListToolsResult to Vec<MCPToolDeclaration>
 ///
@@ -29,6 +32,71 @@ pub fn get_tools(list_tools_result: &ListToolsResult) -> Vec<MCPToolDeclaration>
     tools
 }
 
+/// Collects the names of tools that are safe to call again after a dropped connection,
+/// i.e. tools annotated as read-only or idempotent.
+pub fn get_retry_safe_tools(list_tools_result: &ListToolsResult) -> HashSet<String> {
+    list_tools_result
+        .tools
+        .iter()
+        .filter(|tool| {
+            tool.annotations.as_ref().is_some_and(|a| {
+                a.read_only_hint.unwrap_or(false) || a.idempotent_hint.unwrap_or(false)
+            })
+        })
+        .map(|tool| tool.name.to_string())
+        .collect()
+}
+
+/// Checks whether a failed request means the connection to the server is gone, so that
+/// reconnecting might fix it.
+pub fn is_connection_error(error: &ServiceError) -> bool {
+    match error {
+        ServiceError::TransportClosed => true,
+        ServiceError::TransportSend(error) => is_transport_lost(error.error.as_ref()),
+        _ => false,
+    }
+}
+
+/// Classifies the error of a transport with the shared retry rules. Stdio transports fail
+/// with I/O errors, Streamable HTTP ones with HTTP client errors or a closed session.
+fn is_transport_lost(error: &(dyn std::error::Error + Send + Sync + 'static)) -> bool {
+    if let Some(error) = error.downcast_ref::<StreamableHttpError<reqwest::Error>>() {
+        return match error {
+            StreamableHttpError::Client(error) => is_retryable_error(error),
+            StreamableHttpError::Io(error) => is_retryable_io_error(error),
+            StreamableHttpError::SessionExpired
+            | StreamableHttpError::TransportChannelClosed
+            | StreamableHttpError::UnexpectedEndOfStream => true,
+            _ => false,
+        };
+    }
+    error
+        .downcast_ref::<std::io::Error>()
+        .is_some_and(is_retryable_io_error)
+}
+
+/// Expands `${VAR}` references in an MCP server's env value from the host environment.
+///
+/// Unset variables expand to an empty string; a `${` without a closing brace is kept as is.
+pub fn expand_env_vars(value: &str) -> String {
+    let mut expanded = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        let Some(len) = rest[start + 2..].find('}') else {
+            break;
+        };
+        expanded.push_str(&rest[..start]);
+        let name = &rest[start + 2..start + 2 + len];
+        match env::var(name) {
+            Ok(var) => expanded.push_str(&var),
+            Err(_) => log::warn!("MCP env references unset variable '{}'", name),
+        }
+        rest = &rest[start + 3 + len..];
+    }
+    expanded.push_str(rest);
+    expanded
+}
+
 /// Attempts to find an executable by name using a multi-step cross-platform strategy.
 /// 1. Uses system-specific commands (`command -v` on Unix, `where` on Windows) for initial lookup.
 /// 2. Checks if the command name is an absolute or relative path to an existing file.
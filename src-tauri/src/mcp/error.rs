@@ -46,6 +46,13 @@ pub enum McpError {
     StateChangeFailed(String),
     #[error("{}", t!("mcp.error.timeout_error", error = _0))]
     Timeout(String),
+    /// The connection to the server was lost during a request.
+    #[error("{}", t!("mcp.error.connection_lost", error = _0))]
+    ConnectionLost(String),
+    /// The connection dropped during a call to a tool that is not safe to repeat.
+    /// The call may be retried by the caller once it has checked its side effects.
+    #[error("{}", t!("mcp.error.call_interrupted", tool_name = _0, error = _1))]
+    CallInterrupted(String, String),
 
     #[error("{0}")]
     General(String),
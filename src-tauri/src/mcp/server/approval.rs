@@ -0,0 +1,286 @@
+//! Interactive approval of tool calls made by external MCP clients.
+//!
+//! Tools matching `gatedTools` are held until the user approves the call through the
+//! `respond_mcp_tool_approval` command. In `perSession` mode an approval is remembered
+//! for the rest of the client's session, in `perCall` mode every call asks again.
+
+use rust_i18n::t;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex, RwLock};
+use tauri::{AppHandle, Emitter};
+use tokio::time::{timeout, Duration};
+use wildmatch::WildMatch;
+
+use crate::tools::{
+    ApprovalRegistry, TOOL_BASH, TOOL_EDIT_FILE, TOOL_EXECUTE_COMMAND, TOOL_PLAN_EDIT_NOTE,
+    TOOL_PLAN_WRITE_NOTE, TOOL_WRITE_FILE,
+};
+
+/// Event emitted to the frontend when an MCP client calls a tool that needs approval.
+pub const MCP_TOOL_APPROVAL_EVENT: &str = "cs://mcp-tool-approval";
+
+/// How long a gated call waits for the user before it is rejected.
+const DEFAULT_APPROVAL_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// When calls of gated tools need the user's approval.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum McpToolApprovalMode {
+    /// Gated tools run without asking
+    #[default]
+    Off,
+    /// Every call of a gated tool needs approval
+    PerCall,
+    /// The first call of a gated tool needs approval, later calls in the same session don't
+    PerSession,
+}
+
+fn default_gated_tools() -> Vec<String> {
+    [
+        TOOL_WRITE_FILE,
+        TOOL_EDIT_FILE,
+        TOOL_BASH,
+        TOOL_EXECUTE_COMMAND,
+        TOOL_PLAN_WRITE_NOTE,
+        TOOL_PLAN_EDIT_NOTE,
+    ]
+    .iter()
+    .map(|tool| tool.to_string())
+    .collect()
+}
+
+/// Approval settings of the MCP server, stored under `mcp_server_tool_approval`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpToolApprovalConfig {
+    #[serde(default)]
+    pub mode: McpToolApprovalMode,
+    /// Exposed tool names that need approval, `*` and `?` wildcards are supported
+    #[serde(default = "default_gated_tools")]
+    pub gated_tools: Vec<String>,
+}
+
+impl Default for McpToolApprovalConfig {
+    fn default() -> Self {
+        Self {
+            mode: McpToolApprovalMode::default(),
+            gated_tools: default_gated_tools(),
+        }
+    }
+}
+
+impl McpToolApprovalConfig {
+    /// Whether calling `tool_name` needs the user's approval.
+    pub fn is_gated(&self, tool_name: &str) -> bool {
+        self.mode != McpToolApprovalMode::Off
+            && self
+                .gated_tools
+                .iter()
+                .any(|pattern| WildMatch::new(pattern).matches(tool_name))
+    }
+}
+
+/// A tool call waiting for the user's decision, sent to the frontend as the event payload.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpToolApprovalRequest {
+    pub request_id: String,
+    /// Identifies the MCP session the call belongs to
+    pub session_id: String,
+    /// Name the client reported on initialize
+    pub client_name: Option<String>,
+    pub tool_name: String,
+    pub arguments: Value,
+    /// Whether an approval is remembered for the rest of the session
+    pub remember_for_session: bool,
+}
+
+/// Tracks tool calls of MCP clients until the user approves or rejects them.
+pub type McpToolApprovalRegistry = ApprovalRegistry<McpToolApprovalRequest>;
+
+/// Holds gated tool calls of one MCP session until the user decides.
+pub struct ToolCallGate {
+    app_handle: Option<AppHandle>,
+    approvals: Arc<McpToolApprovalRegistry>,
+    session_id: String,
+    client_name: RwLock<Option<String>>,
+    /// Tools approved for the rest of this session
+    approved_tools: Mutex<HashSet<String>>,
+    approval_timeout: Duration,
+}
+
+impl ToolCallGate {
+    pub fn new(app_handle: Option<AppHandle>, approvals: Arc<McpToolApprovalRegistry>) -> Self {
+        Self {
+            app_handle,
+            approvals,
+            session_id: uuid::Uuid::new_v4().to_string(),
+            client_name: RwLock::new(None),
+            approved_tools: Mutex::new(HashSet::new()),
+            approval_timeout: DEFAULT_APPROVAL_TIMEOUT,
+        }
+    }
+
+    /// Records the client name shown to the user in approval prompts.
+    pub fn set_client_name(&self, name: String) {
+        if let Ok(mut client_name) = self.client_name.write() {
+            *client_name = Some(name);
+        }
+    }
+
+    fn is_approved_for_session(&self, tool_name: &str) -> bool {
+        self.approved_tools
+            .lock()
+            .map(|tools| tools.contains(tool_name))
+            .unwrap_or(false)
+    }
+
+    /// Waits for the user's approval if the call is gated.
+    ///
+    /// Returns the message for the client if the call must not run.
+    pub async fn authorize(
+        &self,
+        config: &McpToolApprovalConfig,
+        tool_name: &str,
+        arguments: &Value,
+    ) -> Result<(), String> {
+        if !config.is_gated(tool_name) {
+            return Ok(());
+        }
+        let remember_for_session = config.mode == McpToolApprovalMode::PerSession;
+        if remember_for_session && self.is_approved_for_session(tool_name) {
+            return Ok(());
+        }
+
+        let request = McpToolApprovalRequest {
+            request_id: uuid::Uuid::new_v4().to_string(),
+            session_id: self.session_id.clone(),
+            client_name: self.client_name.read().ok().and_then(|name| name.clone()),
+            tool_name: tool_name.to_string(),
+            arguments: arguments.clone(),
+            remember_for_session,
+        };
+        let receiver = self
+            .approvals
+            .register(request.request_id.clone(), request.clone());
+        log::info!(
+            "MCP client call of '{}' requires approval, request id: {}",
+            tool_name,
+            request.request_id
+        );
+
+        if let Some(app_handle) = &self.app_handle {
+            if let Err(e) = app_handle.emit(MCP_TOOL_APPROVAL_EVENT, &request) {
+                self.approvals.cancel(&request.request_id);
+                return Err(t!("mcp.proxy.tool_execution_error", error = e.to_string()).to_string());
+            }
+        }
+
+        match timeout(self.approval_timeout, receiver).await {
+            Ok(Ok(true)) => {
+                if remember_for_session {
+                    if let Ok(mut tools) = self.approved_tools.lock() {
+                        tools.insert(tool_name.to_string());
+                    }
+                }
+                Ok(())
+            }
+            Ok(Ok(false)) | Ok(Err(_)) => {
+                Err(t!("mcp.proxy.tool_call_rejected", tool_name = tool_name).to_string())
+            }
+            Err(_) => {
+                self.approvals.cancel(&request.request_id);
+                Err(t!(
+                    "mcp.proxy.tool_approval_timeout",
+                    tool_name = tool_name,
+                    seconds = self.approval_timeout.as_secs()
+                )
+                .to_string())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    async fn wait_for_pending(approvals: &McpToolApprovalRegistry) -> McpToolApprovalRequest {
+        for _ in 0..100 {
+            if let Some(request) = approvals.pending().into_iter().next() {
+                return request;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("tool call never requested approval");
+    }
+
+    fn config(mode: McpToolApprovalMode) -> McpToolApprovalConfig {
+        McpToolApprovalConfig {
+            mode,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_gated_call_waits_for_approval() {
+        let approvals = Arc::new(McpToolApprovalRegistry::default());
+        let gate = Arc::new(ToolCallGate::new(None, approvals.clone()));
+        gate.set_client_name("ide".to_string());
+        let config = config(McpToolApprovalMode::PerSession);
+        let args = json!({"file_path": "/tmp/notes.txt", "content": "hi"});
+
+        // Ungated tools run directly.
+        assert!(gate.authorize(&config, "read_file", &args).await.is_ok());
+        assert!(approvals.pending().is_empty());
+
+        // A rejected call does not run.
+        let pending = {
+            let (gate, config, args) = (gate.clone(), config.clone(), args.clone());
+            tokio::spawn(async move { gate.authorize(&config, TOOL_WRITE_FILE, &args).await })
+        };
+        let request = wait_for_pending(&approvals).await;
+        assert_eq!(request.tool_name, TOOL_WRITE_FILE);
+        assert_eq!(request.client_name.as_deref(), Some("ide"));
+        assert!(request.remember_for_session);
+        assert!(approvals.respond(&request.request_id, false));
+        assert!(pending.await.expect("task").is_err());
+
+        // An approved call proceeds and is remembered for the session.
+        let pending = {
+            let (gate, config, args) = (gate.clone(), config.clone(), args.clone());
+            tokio::spawn(async move { gate.authorize(&config, TOOL_WRITE_FILE, &args).await })
+        };
+        let request = wait_for_pending(&approvals).await;
+        assert!(approvals.respond(&request.request_id, true));
+        assert!(pending.await.expect("task").is_ok());
+        assert!(gate
+            .authorize(&config, TOOL_WRITE_FILE, &args)
+            .await
+            .is_ok());
+        assert!(approvals.pending().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_per_call_mode_asks_every_time() {
+        let approvals = Arc::new(McpToolApprovalRegistry::default());
+        let mut gate = ToolCallGate::new(None, approvals.clone());
+        gate.approval_timeout = Duration::from_millis(20);
+        let config = config(McpToolApprovalMode::PerCall);
+        gate.approved_tools
+            .lock()
+            .expect("lock")
+            .insert(TOOL_BASH.to_string());
+
+        assert!(gate
+            .authorize(&config, TOOL_BASH, &json!({}))
+            .await
+            .is_err());
+        assert!(approvals.pending().is_empty());
+        assert!(!config.is_gated("web_search"));
+        assert!(!McpToolApprovalConfig::default().is_gated(TOOL_BASH));
+    }
+}
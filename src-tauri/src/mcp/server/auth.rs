@@ -0,0 +1,142 @@
+//! Optional authentication for the MCP server endpoints.
+//!
+//! When enabled, every request to `/mcp/*` must carry one of the ccproxy access keys
+//! (`chat_completion_proxy_keys`), checked the same way as for the proxy endpoints:
+//! `Authorization: Bearer <key>`, `x-api-key: <key>` or, for WebSocket clients that cannot
+//! set headers, the `key` query parameter. Both servers share one listener, so one set of
+//! keys manages access to both.
+
+use axum::{
+    extract::{Query, Request, State},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, RwLock};
+
+use crate::{
+    ai::interaction::chat_completion::ChatState,
+    ccproxy::{authenticate_request, CcproxyQuery},
+    constants::CFG_MCP_SERVER_AUTH,
+    db::MainStore,
+};
+
+/// Authentication settings of the MCP server, stored under `mcp_server_auth`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpServerAuthConfig {
+    /// Whether requests must carry a valid ccproxy access key
+    #[serde(default)]
+    pub enabled: bool,
+}
+
+impl McpServerAuthConfig {
+    /// Reads the settings from the store, an unreadable store falls back to the defaults.
+    pub fn load(main_store: &Arc<RwLock<MainStore>>) -> Self {
+        main_store
+            .read()
+            .map(|store| store.get_config(CFG_MCP_SERVER_AUTH, Self::default()))
+            .unwrap_or_default()
+    }
+}
+
+/// Middleware rejecting requests without a valid access key with `401 Unauthorized` when
+/// authentication is enabled.
+pub async fn require_token(
+    State((main_store, chat_state)): State<(Arc<RwLock<MainStore>>, Arc<ChatState>)>,
+    Query(query): Query<CcproxyQuery>,
+    req: Request,
+    next: Next,
+) -> Response {
+    if !McpServerAuthConfig::load(&main_store).enabled {
+        return next.run(req).await;
+    }
+
+    let headers = req.headers().clone();
+    match authenticate_request(headers, query, main_store, chat_state, false).await {
+        Ok(()) => next.run(req).await,
+        Err(e) => {
+            log::warn!(
+                "Rejected unauthenticated MCP request to {}: {:?}",
+                req.uri().path(),
+                e
+            );
+            e.into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{libs::window_channels::WindowChannels, test::spawn_mock_backend};
+    use axum::{middleware, routing::get, Router};
+    use serde_json::json;
+
+    /// Serves a protected `/mcp/http` endpoint accepting the `cs-secret` key, and returns its URL.
+    async fn protected_endpoint(enabled: bool) -> String {
+        let main_store = Arc::new(RwLock::new(
+            MainStore::new(":memory:").expect("in-memory store"),
+        ));
+        {
+            let mut store = main_store.write().expect("store lock");
+            store
+                .set_config(CFG_MCP_SERVER_AUTH, &json!({ "enabled": enabled }))
+                .expect("save config");
+            store
+                .set_config(
+                    "chat_completion_proxy_keys",
+                    &json!([{ "name": "ide", "token": "cs-secret" }]),
+                )
+                .expect("save keys");
+        }
+        let chat_state = ChatState::new(Arc::new(WindowChannels::new()), None, main_store.clone());
+
+        let app = Router::new()
+            .route("/mcp/http", get(|| async { "connected" }))
+            .layer(middleware::from_fn_with_state(
+                (main_store, chat_state),
+                require_token,
+            ));
+        format!("{}/mcp/http", spawn_mock_backend(app).await)
+    }
+
+    #[tokio::test]
+    async fn test_rejects_unauthenticated_and_accepts_authenticated_connect() {
+        let url = protected_endpoint(true).await;
+        let client = reqwest::Client::new();
+
+        let anonymous = client.get(&url).send().await.expect("request");
+        assert_eq!(anonymous.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+        let wrong = client
+            .get(&url)
+            .bearer_auth("cs-wrong")
+            .send()
+            .await
+            .expect("request");
+        assert_eq!(wrong.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+        let authenticated = client
+            .get(&url)
+            .bearer_auth("cs-secret")
+            .send()
+            .await
+            .expect("request");
+        assert_eq!(authenticated.status(), reqwest::StatusCode::OK);
+
+        let query_key = client
+            .get(format!("{}?key=cs-secret", url))
+            .send()
+            .await
+            .expect("request");
+        assert_eq!(query_key.status(), reqwest::StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_disabled_auth_allows_everyone() {
+        let url = protected_endpoint(false).await;
+        let anonymous = reqwest::get(&url).await.expect("request");
+        assert_eq!(anonymous.status(), reqwest::StatusCode::OK);
+    }
+}
@@ -3,6 +3,8 @@
 //! This module implements the MCP server handler that proxies tool calls to the internal tool manager.
 
 use crate::ai::traits::chat::MCPToolDeclaration;
+use crate::constants::CFG_MCP_SERVER_TOOL_APPROVAL;
+use crate::mcp::server::approval::{McpToolApprovalConfig, ToolCallGate};
 use crate::mcp::McpError;
 use crate::tools::ToolCallResult;
 use crate::{ai::interaction::chat_completion::ChatState, tools::MCP_TOOL_NAME_SPLIT};
@@ -23,6 +25,7 @@ use std::{
     collections::{HashMap, HashSet},
     sync::Arc,
 };
+use tauri::AppHandle;
 use tokio::sync::RwLock;
 
 /// Converts MCPToolDeclaration to rmcp Tool
@@ -66,6 +69,8 @@ pub struct McpProxyHandler {
     /// Key: display name
     /// Value: ToolReference
     tool_map: Arc<RwLock<HashMap<String, ToolReference>>>,
+    /// Holds calls of gated tools until the user approves them
+    gate: Arc<ToolCallGate>,
 }
 
 impl McpProxyHandler {
@@ -73,16 +78,42 @@ impl McpProxyHandler {
     ///
     /// # Arguments
     /// * `chat_state` - Chat state instance for accessing the tool manager
+    /// * `app_handle` - Used to ask the user for approval of gated tool calls
     ///
     /// # Returns
     /// Returns a new MCP proxy handler instance
-    pub fn new(chat_state: Arc<ChatState>) -> Self {
+    pub fn new(chat_state: Arc<ChatState>, app_handle: Option<AppHandle>) -> Self {
+        let gate = ToolCallGate::new(
+            app_handle,
+            chat_state.tool_manager.mcp_server_approvals.clone(),
+        );
         Self {
             chat_state,
             tool_map: Arc::new(RwLock::new(HashMap::new())),
+            gate: Arc::new(gate),
         }
     }
 
+    /// Reads the approval settings for tool calls of MCP clients.
+    fn approval_config(&self) -> McpToolApprovalConfig {
+        self.chat_state
+            .main_store
+            .read()
+            .map(|store| {
+                store.get_config(
+                    CFG_MCP_SERVER_TOOL_APPROVAL,
+                    McpToolApprovalConfig::default(),
+                )
+            })
+            .unwrap_or_default()
+    }
+
+    /// Maps the registered `{server}__MCP__{tool}` names to the names the tool manager
+    /// exposes them under, which are only prefixed when tool names conflict.
+    async fn exposed_mcp_tool_names(&self) -> HashMap<String, String> {
+        self.chat_state.tool_manager.exposed_tool_names().await
+    }
+
     /// Ensures the tool map is loaded, reloads if empty
     async fn ensure_tool_map_loaded(&self) -> Result<(), McpError> {
         let tool_map_guard = self.tool_map.read().await;
@@ -103,7 +134,7 @@ impl McpProxyHandler {
             .unwrap_or_default();
 
         let mut new_tool_map = HashMap::new();
-        let mut used_short_names = HashSet::new();
+        let exposed_names = self.exposed_mcp_tool_names().await;
 
         for tool_spec in all_tools {
             // Built-in tools
@@ -127,17 +158,15 @@ impl McpProxyHandler {
                 let server_name = parts[0].to_string();
                 let original_tool_name = parts[1].to_string();
 
-                let display_name = if !used_short_names.contains(&original_tool_name) {
-                    original_tool_name.clone()
-                } else {
-                    format!("{}_{}", server_name, original_tool_name)
-                };
+                let display_name = exposed_names
+                    .get(&tool_spec.name)
+                    .cloned()
+                    .unwrap_or_else(|| format!("{}_{}", server_name, original_tool_name));
 
                 if new_tool_map.contains_key(&display_name) {
                     continue;
                 }
 
-                used_short_names.insert(display_name.clone());
                 new_tool_map.insert(
                     display_name.clone(),
                     ToolReference::new(Some(server_name.clone()), original_tool_name.clone()),
@@ -167,9 +196,10 @@ impl ServerHandler for McpProxyHandler {
 
     async fn initialize(
         &self,
-        _request: InitializeRequestParams,
+        request: InitializeRequestParams,
         _context: RequestContext<RoleServer>,
     ) -> Result<InitializeResult, rmcp::model::ErrorData> {
+        self.gate.set_client_name(request.client_info.name.clone());
         Ok(self.get_info())
     }
 
@@ -188,7 +218,7 @@ impl ServerHandler for McpProxyHandler {
 
         let mut display_tools = Vec::new();
         let mut new_tool_map = HashMap::new();
-        let mut used_short_names = HashSet::new();
+        let exposed_names = self.exposed_mcp_tool_names().await;
 
         for tool_spec in all_tools {
             // Built-in tools
@@ -214,11 +244,10 @@ impl ServerHandler for McpProxyHandler {
                 let server_name = parts[0].to_string();
                 let original_tool_name = parts[1].to_string();
 
-                let display_name = if !used_short_names.contains(&original_tool_name) {
-                    original_tool_name.clone()
-                } else {
-                    format!("{}_{}", server_name, original_tool_name)
-                };
+                let display_name = exposed_names
+                    .get(&tool_spec.name)
+                    .cloned()
+                    .unwrap_or_else(|| format!("{}_{}", server_name, original_tool_name));
 
                 if new_tool_map.contains_key(&display_name) {
                     // This means the prefixed name also conflicts, which implies a duplicate tool from the same server.
@@ -226,7 +255,6 @@ impl ServerHandler for McpProxyHandler {
                     continue;
                 }
 
-                used_short_names.insert(display_name.clone());
                 new_tool_map.insert(
                     display_name.clone(),
                     ToolReference::new(Some(server_name.clone()), original_tool_name.clone()),
@@ -266,6 +294,21 @@ impl ServerHandler for McpProxyHandler {
 
         let arguments = request.arguments.unwrap_or_default();
 
+        // Destructive tools may need the user's approval before they run
+        if let Err(message) = self
+            .gate
+            .authorize(
+                &self.approval_config(),
+                tool_name,
+                &Value::Object(arguments.clone()),
+            )
+            .await
+        {
+            return Ok(CallToolResult::structured_error(
+                json!({ "error": message }),
+            ));
+        }
+
         log::debug!(
             "MCP client calling tool '{}', which maps to internal tool ('{}', '{}'), with arguments: {:?}",
             request.name,
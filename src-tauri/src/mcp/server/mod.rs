@@ -1,10 +1,16 @@
 //! MCP Server Module
 //!
 //! This module implements the MCP server functionality, allowing external
-//! clients to connect and invoke enabled MCP tools via Streamable HTTP.
+//! clients to connect and invoke enabled MCP tools via Streamable HTTP or,
+//! optionally, WebSocket.
 
+mod approval;
+mod auth;
 mod handler;
 pub mod persistent_session;
 mod standalone;
+mod websocket;
 
-pub use standalone::create_http_service;
+pub use approval::{McpToolApprovalRegistry, McpToolApprovalRequest};
+pub use auth::{require_token, McpServerAuthConfig};
+pub use standalone::{create_http_service, create_ws_router};
@@ -5,8 +5,11 @@
 use crate::ai::interaction::chat_completion::ChatState;
 use crate::mcp::server::handler::McpProxyHandler;
 use crate::mcp::server::persistent_session::PersistentSessionManager;
+use crate::mcp::server::websocket;
+use axum::Router;
 use rmcp::transport::streamable_http_server::{StreamableHttpServerConfig, StreamableHttpService};
 use std::sync::Arc;
+use tauri::AppHandle;
 
 /// Creates a service for the MCP proxy server using Streamable HTTP transport.
 ///
@@ -18,11 +21,17 @@ use std::sync::Arc;
 /// The function will not panic even if all session manager initialization attempts fail.
 pub fn create_http_service(
     chat_state: Arc<ChatState>,
+    app_handle: AppHandle,
 ) -> StreamableHttpService<McpProxyHandler, PersistentSessionManager<McpProxyHandler>> {
     log::info!("Creating MCP Streamable HTTP service component with persistent sessions.");
 
     // Create the service factory closure. It must be `Clone` to be passed to multiple places.
-    let service_factory = move || Ok(McpProxyHandler::new(chat_state.clone()));
+    let service_factory = move || {
+        Ok(McpProxyHandler::new(
+            chat_state.clone(),
+            Some(app_handle.clone()),
+        ))
+    };
 
     // The session manager needs an Arc'd version of the factory.
     let session_manager = match PersistentSessionManager::new(Arc::new(service_factory.clone())) {
@@ -65,3 +74,12 @@ pub fn create_http_service(
         StreamableHttpServerConfig::default(),
     )
 }
+
+/// Creates a router for the MCP proxy server using the WebSocket transport.
+///
+/// Unlike the Streamable HTTP service, a WebSocket session lives exactly as long as its
+/// connection, so no session manager is involved.
+pub fn create_ws_router(chat_state: Arc<ChatState>, app_handle: AppHandle) -> Router {
+    log::info!("Creating MCP WebSocket service component.");
+    websocket::router(move || McpProxyHandler::new(chat_state.clone(), Some(app_handle.clone())))
+}
@@ -0,0 +1,233 @@
+//! WebSocket transport for the MCP server.
+//!
+//! Every text (or UTF-8 binary) frame carries exactly one JSON-RPC message. Ping frames
+//! are answered by the WebSocket layer, the server additionally pings idle clients to
+//! keep proxies from dropping the connection. A close frame from either side ends the
+//! MCP session.
+
+use axum::{
+    extract::ws::{Message, WebSocket, WebSocketUpgrade},
+    routing::get,
+    Router,
+};
+use futures::{future, SinkExt, StreamExt};
+use rmcp::{
+    service::{RxJsonRpcMessage, TxJsonRpcMessage},
+    RoleServer, ServerHandler, ServiceExt,
+};
+use tokio::sync::mpsc;
+use tokio::time::{interval, Duration, MissedTickBehavior};
+
+/// How often the server pings a connected client.
+const PING_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Creates a router that serves a new MCP session for every WebSocket connection.
+pub fn router<S, F>(service_factory: F) -> Router
+where
+    S: ServerHandler,
+    F: Fn() -> S + Clone + Send + Sync + 'static,
+{
+    Router::new().route(
+        "/",
+        get(move |ws: WebSocketUpgrade| {
+            let service_factory = service_factory.clone();
+            async move { ws.on_upgrade(move |socket| serve_socket(socket, service_factory())) }
+        }),
+    )
+}
+
+/// Parses the payload of a frame as a JSON-RPC message, logging and skipping invalid ones.
+fn parse_message(payload: &str) -> Option<RxJsonRpcMessage<RoleServer>> {
+    serde_json::from_str(payload)
+        .map_err(|e| log::warn!("Ignoring invalid JSON-RPC message over WebSocket: {}", e))
+        .ok()
+}
+
+/// Runs one MCP session over an upgraded WebSocket until either side disconnects.
+async fn serve_socket<S: ServerHandler>(socket: WebSocket, service: S) {
+    let (mut ws_tx, ws_rx) = socket.split();
+    let (out_tx, mut out_rx) = mpsc::channel::<TxJsonRpcMessage<RoleServer>>(32);
+
+    // The writer owns the socket sink so responses and keep-alive pings never interleave.
+    let writer = tokio::spawn(async move {
+        let mut ping = interval(PING_INTERVAL);
+        ping.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        ping.tick().await;
+        loop {
+            tokio::select! {
+                message = out_rx.recv() => {
+                    let Some(message) = message else {
+                        // The session has ended, say goodbye to the client.
+                        let _ = ws_tx.send(Message::Close(None)).await;
+                        break;
+                    };
+                    let payload = match serde_json::to_string(&message) {
+                        Ok(payload) => payload,
+                        Err(e) => {
+                            log::error!("Failed to serialize MCP message for WebSocket: {}", e);
+                            continue;
+                        }
+                    };
+                    if ws_tx.send(Message::Text(payload.into())).await.is_err() {
+                        break;
+                    }
+                }
+                _ = ping.tick() => {
+                    if ws_tx.send(Message::Ping(Default::default())).await.is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    });
+
+    let incoming = ws_rx
+        .take_while(|frame| match frame {
+            Ok(Message::Close(_)) => future::ready(false),
+            Err(e) => {
+                log::debug!("MCP WebSocket connection closed: {}", e);
+                future::ready(false)
+            }
+            Ok(_) => future::ready(true),
+        })
+        .filter_map(|frame| {
+            future::ready(match frame {
+                Ok(Message::Text(text)) => parse_message(text.as_str()),
+                Ok(Message::Binary(bytes)) => {
+                    std::str::from_utf8(&bytes).ok().and_then(parse_message)
+                }
+                _ => None,
+            })
+        });
+    let outgoing = futures::sink::unfold(
+        out_tx,
+        |out_tx, message: TxJsonRpcMessage<RoleServer>| async move {
+            out_tx.send(message).await.map(|_| out_tx)
+        },
+    );
+
+    match service
+        .serve((Box::pin(outgoing), Box::pin(incoming)))
+        .await
+    {
+        Ok(running) => {
+            if let Err(e) = running.waiting().await {
+                log::warn!("MCP WebSocket session ended with error: {}", e);
+            }
+        }
+        Err(e) => log::warn!("Failed to initialize MCP WebSocket session: {}", e),
+    }
+
+    let _ = writer.await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::spawn_mock_backend;
+    use rmcp::model::{
+        ListToolsResult, PaginatedRequestParams, ServerCapabilities, ServerInfo, Tool,
+    };
+    use rmcp::service::RequestContext;
+    use serde_json::{json, Value};
+    use tokio_tungstenite::{connect_async, tungstenite::Message as WsMessage};
+
+    /// A server exposing a single `echo` tool.
+    #[derive(Clone)]
+    struct EchoServer;
+
+    impl ServerHandler for EchoServer {
+        fn get_info(&self) -> ServerInfo {
+            let mut info = ServerInfo::default();
+            info.capabilities = ServerCapabilities::builder().enable_tools().build();
+            info
+        }
+
+        async fn list_tools(
+            &self,
+            _request: Option<PaginatedRequestParams>,
+            _context: RequestContext<RoleServer>,
+        ) -> Result<ListToolsResult, rmcp::model::ErrorData> {
+            let tool: Tool = serde_json::from_value(json!({
+                "name": "echo",
+                "description": "Echoes its input",
+                "inputSchema": {"type": "object"}
+            }))
+            .map_err(|e| rmcp::model::ErrorData::internal_error(e.to_string(), None))?;
+            Ok(ListToolsResult::with_all_items(vec![tool]))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_list_tools_over_websocket() {
+        let base_url =
+            spawn_mock_backend(Router::new().nest("/mcp/ws", router(|| EchoServer))).await;
+
+        let (mut ws, _) = connect_async(format!("{}/mcp/ws", base_url.replacen("http", "ws", 1)))
+            .await
+            .expect("websocket handshake");
+
+        // Reads frames until the response with the given id, returning whether a pong arrived.
+        async fn response<S>(ws: &mut S, id: u64) -> (Value, bool)
+        where
+            S: futures::Stream<Item = Result<WsMessage, tokio_tungstenite::tungstenite::Error>>
+                + Unpin,
+        {
+            let mut pong = false;
+            while let Some(frame) = ws.next().await {
+                match frame.expect("websocket frame") {
+                    WsMessage::Text(text) => {
+                        let message: Value = serde_json::from_str(&text).expect("json-rpc");
+                        if message["id"] == id {
+                            return (message, pong);
+                        }
+                    }
+                    WsMessage::Pong(_) => pong = true,
+                    _ => {}
+                }
+            }
+            panic!("connection closed before response {}", id);
+        }
+
+        let initialize = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "initialize",
+            "params": {
+                "protocolVersion": "2025-03-26",
+                "capabilities": {},
+                "clientInfo": {"name": "ws-test", "version": "1.0.0"}
+            }
+        });
+        ws.send(WsMessage::text(initialize.to_string()))
+            .await
+            .expect("send initialize");
+        let (initialized, _) = response(&mut ws, 1).await;
+        assert!(initialized["result"]["capabilities"]["tools"].is_object());
+
+        ws.send(WsMessage::text(
+            json!({"jsonrpc": "2.0", "method": "notifications/initialized"}).to_string(),
+        ))
+        .await
+        .expect("send initialized");
+        ws.send(WsMessage::Ping(Default::default()))
+            .await
+            .expect("send ping");
+        ws.send(WsMessage::text(
+            json!({"jsonrpc": "2.0", "id": 2, "method": "tools/list"}).to_string(),
+        ))
+        .await
+        .expect("send tools/list");
+
+        let (tools, pong) = response(&mut ws, 2).await;
+        assert!(pong, "ping should be answered before the response");
+        assert_eq!(tools["result"]["tools"][0]["name"], "echo");
+
+        ws.close(None).await.expect("close handshake");
+        while let Some(Ok(frame)) = ws.next().await {
+            if matches!(frame, WsMessage::Close(_)) {
+                break;
+            }
+        }
+    }
+}
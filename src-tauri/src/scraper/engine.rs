@@ -8,8 +8,11 @@ use url::Url;
 
 use super::config_loader::ConfigLoader;
 use super::pool::ScraperPool;
+use super::readability;
 use crate::libs::util;
-use crate::scraper::types::{ContentOptions, GenericContentRule, ScrapeRequest};
+use crate::scraper::types::{
+    ContentOptions, GenericContentRule, ScrapeRequest, StrapeContentFormat,
+};
 
 /// The primary entry point for the scraper module.
 ///
@@ -101,6 +104,9 @@ pub async fn run(app_handle: AppHandle<Wry>, request: ScrapeRequest) -> Result<S
             keep_image,
         }) => {
             let url_obj = Url::parse(&url).context("Failed to parse content URL")?;
+            if matches!(content_format, StrapeContentFormat::Readable) {
+                return scrape_readable(scraper_pool, &url, keep_link, keep_image).await;
+            }
             let config = config_loader.load_content_config(&url_obj)?;
             let generic_content_rule = GenericContentRule {
                 r#format: content_format.to_string(),
@@ -117,6 +123,9 @@ pub async fn run(app_handle: AppHandle<Wry>, request: ScrapeRequest) -> Result<S
             keep_link,
             keep_image,
         }) => {
+            if matches!(content_format, StrapeContentFormat::Readable) {
+                return scrape_readable(scraper_pool, &url, keep_link, keep_image).await;
+            }
             let generic_content_rule = GenericContentRule {
                 r#format: content_format.to_string(),
                 keep_link,
@@ -129,6 +138,25 @@ pub async fn run(app_handle: AppHandle<Wry>, request: ScrapeRequest) -> Result<S
     }
 }
 
+/// Fetches the raw HTML of the page and extracts its main content.
+///
+/// Site schemas are skipped, the extraction works on the page as a whole.
+async fn scrape_readable(
+    scraper_pool: &ScraperPool,
+    url: &str,
+    keep_link: bool,
+    keep_image: bool,
+) -> Result<String> {
+    let rule = GenericContentRule {
+        r#format: StrapeContentFormat::Html.to_string(),
+        keep_link,
+        keep_image,
+    };
+    let html = scraper_pool.scrape(url, None, Some(rule)).await?;
+    let base_url = Url::parse(url).ok();
+    Ok(readability::extract(&html, base_url.as_ref(), keep_link).to_string())
+}
+
 fn get_time_period(time_period: &str) -> String {
     time_period
         .chars()
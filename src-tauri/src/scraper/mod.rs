@@ -2,6 +2,7 @@ pub mod config_loader;
 pub mod engine;
 mod init;
 pub mod pool;
+pub mod readability;
 pub mod types;
 pub mod url_helper;
 pub mod webview_wrapper;
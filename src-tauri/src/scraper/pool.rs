@@ -3,6 +3,9 @@ use super::webview_wrapper::WebviewScraper;
 use crate::constants::CFG_SCRAPER_DEBUG_MODE;
 use crate::db::MainStore;
 use anyhow::Result;
+use async_trait::async_trait;
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::sync::{Arc, RwLock};
 use std::time::{Duration, Instant};
 use tauri::{AppHandle, EventId, Listener, Manager, WebviewWindow, Wry};
@@ -12,6 +15,21 @@ use tokio::sync::{Mutex, Semaphore};
 const MAX_POOL_SIZE: usize = 10;
 const MAX_CONCURRENT_SCRAPES: usize = 1;
 const IDLE_TIMEOUT_SECS: u64 = 300; // 5 minutes
+/// Scrapes after which a webview is closed and replaced by a fresh one
+const MAX_USES_PER_WEBVIEW: u32 = 50;
+/// Age after which a webview is closed and replaced by a fresh one
+const MAX_WEBVIEW_AGE_SECS: u64 = 1800; // 30 minutes
+/// How long a pooled webview may take to load `about:blank` before it counts as stuck
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// An instance kept by the pool.
+#[async_trait]
+trait PoolInstance: Send + Sync + 'static {
+    /// A lightweight check that the instance still responds.
+    async fn is_healthy(&self) -> bool;
+    /// Releases the instance for good.
+    fn close(&mut self);
+}
 
 /// Represents a webview resource in the pool, including its listeners and usage metadata.
 pub struct WebViewResource {
@@ -20,9 +38,203 @@ pub struct WebViewResource {
     pub last_used: Instant,
 }
 
+#[async_trait]
+impl PoolInstance for WebViewResource {
+    /// Navigates to `about:blank` and waits for the page load to finish.
+    async fn is_healthy(&self) -> bool {
+        let (tx, rx) = tokio::sync::oneshot::channel::<()>();
+        let tx = std::sync::Mutex::new(Some(tx));
+        let listener = self.webview.listen(
+            format!("scraper_page_load_finished_{}", self.webview.label()),
+            move |_| {
+                if let Some(tx) = tx.lock().ok().and_then(|mut tx| tx.take()) {
+                    let _ = tx.send(());
+                }
+            },
+        );
+
+        let navigated = url::Url::parse("about:blank")
+            .map_err(|e| e.to_string())
+            .and_then(|blank| self.webview.navigate(blank).map_err(|e| e.to_string()));
+        let healthy = match navigated {
+            Ok(()) => matches!(
+                tokio::time::timeout(HEALTH_CHECK_TIMEOUT, rx).await,
+                Ok(Ok(()))
+            ),
+            Err(e) => {
+                log::warn!("Failed to navigate pooled webview to blank page: {}", e);
+                false
+            }
+        };
+        self.webview.unlisten(listener);
+        healthy
+    }
+
+    fn close(&mut self) {
+        for listener_id in self.listeners.drain(..) {
+            self.webview.unlisten(listener_id);
+        }
+        if let Err(e) = self.webview.close() {
+            log::error!("Failed to close webview: {}", e);
+        }
+    }
+}
+
+/// Counters of the scraper pool, for debugging.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScraperPoolMetrics {
+    /// Webviews alive, idle and in use
+    pub size: usize,
+    pub idle: usize,
+    pub in_use: usize,
+    pub max_idle: usize,
+    /// Webviews created since start
+    pub created: u64,
+    /// Webviews replaced after reaching their use count or age limit
+    pub recycled: u64,
+    /// Webviews replaced after failing the health check
+    pub discarded_unhealthy: u64,
+}
+
+/// An instance with its usage metadata.
+struct PooledEntry<R> {
+    instance: R,
+    created_at: Instant,
+    uses: u32,
+}
+
+/// Tracks the instances of a pool from creation to recycling.
+///
+/// Instances are only ever recycled while idle, so an instance that is
+/// in the middle of a scrape is never closed underneath it.
+struct InstanceLifecycle<R> {
+    idle: Mutex<Vec<PooledEntry<R>>>,
+    max_idle: usize,
+    max_uses: u32,
+    max_age: Duration,
+    in_use: AtomicUsize,
+    created: AtomicU64,
+    recycled: AtomicU64,
+    discarded_unhealthy: AtomicU64,
+}
+
+impl<R: PoolInstance> InstanceLifecycle<R> {
+    fn new(max_idle: usize, max_uses: u32, max_age: Duration) -> Self {
+        Self {
+            idle: Mutex::new(Vec::with_capacity(max_idle)),
+            max_idle,
+            max_uses,
+            max_age,
+            in_use: AtomicUsize::new(0),
+            created: AtomicU64::new(0),
+            recycled: AtomicU64::new(0),
+            discarded_unhealthy: AtomicU64::new(0),
+        }
+    }
+
+    fn is_worn_out(&self, entry: &PooledEntry<R>) -> bool {
+        entry.uses >= self.max_uses || entry.created_at.elapsed() >= self.max_age
+    }
+
+    /// Hands out a healthy idle instance, or a new one from `create` when there is none.
+    ///
+    /// Worn out and unhealthy idle instances met on the way are closed.
+    async fn checkout<F>(&self, create: F) -> Result<PooledEntry<R>>
+    where
+        F: FnOnce() -> Result<R>,
+    {
+        loop {
+            let Some(mut entry) = self.idle.lock().await.pop() else {
+                break;
+            };
+            if self.is_worn_out(&entry) {
+                log::debug!(
+                    "Recycling pooled scraper after {} uses and {:?}",
+                    entry.uses,
+                    entry.created_at.elapsed()
+                );
+                entry.instance.close();
+                self.recycled.fetch_add(1, Ordering::Relaxed);
+                continue;
+            }
+            if !entry.instance.is_healthy().await {
+                log::warn!("Pooled scraper failed its health check, replacing it");
+                entry.instance.close();
+                self.discarded_unhealthy.fetch_add(1, Ordering::Relaxed);
+                continue;
+            }
+            self.in_use.fetch_add(1, Ordering::Relaxed);
+            return Ok(entry);
+        }
+
+        let instance = create()?;
+        self.created.fetch_add(1, Ordering::Relaxed);
+        self.in_use.fetch_add(1, Ordering::Relaxed);
+        Ok(PooledEntry {
+            instance,
+            created_at: Instant::now(),
+            uses: 0,
+        })
+    }
+
+    /// Takes back an instance after use, closing it when it is worn out or the pool is full.
+    async fn checkin(&self, mut entry: PooledEntry<R>) {
+        self.in_use.fetch_sub(1, Ordering::Relaxed);
+        entry.uses += 1;
+        if self.is_worn_out(&entry) {
+            log::debug!("Recycling pooled scraper after {} uses", entry.uses);
+            entry.instance.close();
+            self.recycled.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+
+        let mut idle = self.idle.lock().await;
+        // Limit pool size to prevent memory accumulation
+        if idle.len() >= self.max_idle {
+            log::debug!("Pool size limit reached, closing webview instead of reusing");
+            entry.instance.close();
+        } else {
+            idle.push(entry);
+        }
+    }
+
+    /// Closes the idle instances `is_expired` returns true for.
+    async fn evict_idle<F>(&self, mut is_expired: F) -> usize
+    where
+        F: FnMut(&R) -> bool,
+    {
+        let mut idle = self.idle.lock().await;
+        let initial_len = idle.len();
+        idle.retain_mut(|entry| {
+            if is_expired(&entry.instance) {
+                entry.instance.close();
+                false
+            } else {
+                true
+            }
+        });
+        initial_len - idle.len()
+    }
+
+    async fn metrics(&self) -> ScraperPoolMetrics {
+        let idle = self.idle.lock().await.len();
+        let in_use = self.in_use.load(Ordering::Relaxed);
+        ScraperPoolMetrics {
+            size: idle + in_use,
+            idle,
+            in_use,
+            max_idle: self.max_idle,
+            created: self.created.load(Ordering::Relaxed),
+            recycled: self.recycled.load(Ordering::Relaxed),
+            discarded_unhealthy: self.discarded_unhealthy.load(Ordering::Relaxed),
+        }
+    }
+}
+
 /// Manages a collection of reusable `WebViewResource` instances.
 pub struct ScraperPool {
-    pool: Arc<Mutex<Vec<WebViewResource>>>,
+    lifecycle: Arc<InstanceLifecycle<WebViewResource>>,
     scraper: Arc<WebviewScraper>,
     semaphore: Arc<Semaphore>,
     app_handle: AppHandle<Wry>,
@@ -32,10 +244,14 @@ impl ScraperPool {
     /// Creates a new `ScraperPool` and starts the cleanup timer.
     pub fn new(app_handle: AppHandle<Wry>) -> Arc<Self> {
         let scraper = Arc::new(WebviewScraper::new(app_handle.clone()));
-        let pool = Arc::new(Mutex::new(Vec::with_capacity(MAX_POOL_SIZE)));
+        let lifecycle = Arc::new(InstanceLifecycle::new(
+            MAX_POOL_SIZE / 2,
+            MAX_USES_PER_WEBVIEW,
+            Duration::from_secs(MAX_WEBVIEW_AGE_SECS),
+        ));
 
         let scraper_pool = Arc::new(Self {
-            pool: pool.clone(),
+            lifecycle: lifecycle.clone(),
             scraper,
             // WebKit-backed hidden windows are not reliable when multiple searches
             // create and navigate them concurrently, especially in release builds.
@@ -44,35 +260,18 @@ impl ScraperPool {
         });
 
         // Start the cleanup timer
-        let pool_clone = pool.clone();
         tokio::spawn(async move {
             loop {
                 tokio::time::sleep(Duration::from_secs(IDLE_TIMEOUT_SECS)).await;
-                let mut pool = pool_clone.lock().await;
                 let now = Instant::now();
-                let initial_len = pool.len();
-
-                // Use retain to efficiently remove items
-                pool.retain(|resource| {
-                    if now.duration_since(resource.last_used).as_secs() > IDLE_TIMEOUT_SECS {
-                        // Cleanup before dropping
-                        for &id in &resource.listeners {
-                            resource.webview.unlisten(id);
-                        }
-                        if let Err(e) = resource.webview.close() {
-                            log::error!("Failed to close webview during cleanup: {}", e);
-                        }
-                        false // Remove from pool
-                    } else {
-                        true // Keep in pool
-                    }
-                });
-
-                let removed_count = initial_len - pool.len();
+                // Only idle webviews are considered, active ones are checked out of the pool
+                let removed_count = lifecycle
+                    .evict_idle(|resource| {
+                        now.duration_since(resource.last_used).as_secs() > IDLE_TIMEOUT_SECS
+                    })
+                    .await;
                 if removed_count > 0 {
                     log::debug!("Cleanup removed {} idle webviews from pool", removed_count);
-                    // The cleanup only removes webviews from the pool, not active ones
-                    // Active webviews have their permits held by the scrape() method
                 }
             }
         });
@@ -80,41 +279,52 @@ impl ScraperPool {
         scraper_pool
     }
 
-    /// Retrieves a webview from the pool or creates a new one if none are available.
-    async fn get(&self) -> Result<(WebViewResource, tokio::sync::OwnedSemaphorePermit)> {
-        // Acquire a permit, waiting if the pool is at max capacity
-        let permit = self.semaphore.clone().acquire_owned().await?;
-        let mut pool = self.pool.lock().await;
+    /// Returns the current size and usage counters of the pool.
+    pub async fn metrics(&self) -> ScraperPoolMetrics {
+        self.lifecycle.metrics().await
+    }
 
-        // Create a new webview if the pool is empty
-        let debug_mode = self
-            .app_handle
+    fn debug_mode(&self) -> bool {
+        self.app_handle
             .state::<Arc<RwLock<MainStore>>>()
             .read()
             .map(|store| store.get_config(CFG_SCRAPER_DEBUG_MODE, false))
-            .unwrap_or(false);
-
-        if let Some(mut resource) = pool.pop() {
-            resource.last_used = Instant::now();
-            if debug_mode {
-                let _ = resource.webview.show();
-            } else if resource.webview.is_visible().unwrap_or(false) {
-                let _ = resource.webview.hide();
-            }
-            Ok((resource, permit))
-        } else {
-            let webview = self
-                .scraper
-                .create_webview("about:blank", debug_mode, true)?;
-            Ok((
-                WebViewResource {
+            .unwrap_or(false)
+    }
+
+    /// Retrieves a healthy webview from the pool or creates a new one if none are available.
+    async fn get(
+        &self,
+    ) -> Result<(
+        PooledEntry<WebViewResource>,
+        tokio::sync::OwnedSemaphorePermit,
+    )> {
+        // Acquire a permit, waiting if the pool is at max capacity
+        let permit = self.semaphore.clone().acquire_owned().await?;
+        let debug_mode = self.debug_mode();
+
+        let mut entry = self
+            .lifecycle
+            .checkout(|| {
+                let webview = self
+                    .scraper
+                    .create_webview("about:blank", debug_mode, true)?;
+                Ok(WebViewResource {
                     webview: Arc::new(webview),
                     listeners: Vec::new(),
                     last_used: Instant::now(),
-                },
-                permit,
-            ))
+                })
+            })
+            .await?;
+
+        let resource = &mut entry.instance;
+        resource.last_used = Instant::now();
+        if debug_mode {
+            let _ = resource.webview.show();
+        } else if resource.webview.is_visible().unwrap_or(false) {
+            let _ = resource.webview.hide();
         }
+        Ok((entry, permit))
     }
 
     /// Returns a webview resource to the pool for future reuse.
@@ -122,24 +332,16 @@ impl ScraperPool {
     /// which decrements the semaphore count and allows new requests to acquire resources.
     async fn release(
         &self,
-        mut resource: WebViewResource,
+        mut entry: PooledEntry<WebViewResource>,
         _permit: tokio::sync::OwnedSemaphorePermit,
     ) {
+        let resource = &mut entry.instance;
         // Clear old listeners before releasing back to the pool
         for listener_id in resource.listeners.drain(..) {
             resource.webview.unlisten(listener_id);
         }
 
-        let debug_mode = self
-            .app_handle
-            .state::<Arc<RwLock<MainStore>>>()
-            .read()
-            .map(|store| store.get_config(CFG_SCRAPER_DEBUG_MODE, false))
-            .unwrap_or(false);
-
-        // Only navigate to blank page in debug mode or when pool is full
-        // This preserves the performance benefit of webview pooling
-        if debug_mode {
+        if self.debug_mode() {
             // In debug mode, keep the page for inspection
             log::debug!("Debug mode: keeping current page for inspection");
         } else {
@@ -162,35 +364,9 @@ impl ScraperPool {
         }
 
         resource.last_used = Instant::now();
-        let mut pool = self.pool.lock().await;
-
-        // Only clear page if we're not reusing the webview
-        let should_clear_page = pool.len() >= MAX_POOL_SIZE / 2;
-
-        if should_clear_page && !debug_mode {
-            if let Ok(blank_url) = url::Url::parse("about:blank") {
-                if let Err(e) = resource.webview.navigate(blank_url) {
-                    log::warn!("Failed to navigate to blank page: {}", e);
-                }
-            }
-        }
-
-        // Limit pool size to prevent memory accumulation
-        if pool.len() >= MAX_POOL_SIZE / 2 {
-            log::debug!("Pool size limit reached, closing webview instead of reusing");
-            for &id in &resource.listeners {
-                resource.webview.unlisten(id);
-            }
-            if let Err(e) = resource.webview.close() {
-                log::error!("Failed to close webview: {}", e);
-            }
-            // Don't push to pool, webview is closed
-            // Permit will be automatically released when it goes out of scope
-        } else {
-            pool.push(resource);
-            // Permit will be automatically released when it goes out of scope
-            // This makes the resource available for the next get() call
-        }
+        // The next checkout navigates the webview to a blank page as its health check,
+        // and the permit is released when it goes out of scope
+        self.lifecycle.checkin(entry).await;
     }
 
     /// Executes the scraping process using a webview from the pool.
@@ -200,25 +376,115 @@ impl ScraperPool {
         config: Option<FullConfig>,
         generic_content_rule: Option<GenericContentRule>,
     ) -> Result<String> {
-        let (mut resource, permit) = self.get().await?;
+        let (mut entry, permit) = self.get().await?;
 
         let (scrape_result, listeners) = self
             .scraper
-            .scrape(&resource.webview, url, config, generic_content_rule)
+            .scrape(&entry.instance.webview, url, config, generic_content_rule)
             .await;
 
-        resource.listeners = listeners; // Always assign listeners
+        entry.instance.listeners = listeners; // Always assign listeners
 
-        match scrape_result {
-            Ok(result) => {
-                self.release(resource, permit).await;
-                Ok(result)
-            }
-            Err(e) => {
-                // On error, we still need to release the resource
-                self.release(resource, permit).await;
-                Err(e)
-            }
+        // Success or not, the resource goes back to the pool
+        self.release(entry, permit).await;
+        scrape_result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicBool;
+
+    /// A stand-in for a webview that can be made to stop responding.
+    struct FakeInstance {
+        id: usize,
+        healthy: Arc<AtomicBool>,
+        closed: Arc<AtomicBool>,
+    }
+
+    #[async_trait]
+    impl PoolInstance for FakeInstance {
+        async fn is_healthy(&self) -> bool {
+            self.healthy.load(Ordering::SeqCst)
         }
+
+        fn close(&mut self) {
+            self.closed.store(true, Ordering::SeqCst);
+        }
+    }
+
+    fn factory(next_id: &AtomicUsize) -> impl FnOnce() -> Result<FakeInstance> + '_ {
+        move || {
+            Ok(FakeInstance {
+                id: next_id.fetch_add(1, Ordering::SeqCst),
+                healthy: Arc::new(AtomicBool::new(true)),
+                closed: Arc::new(AtomicBool::new(false)),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unhealthy_instance_is_rebuilt() {
+        let lifecycle = InstanceLifecycle::new(5, 50, Duration::from_secs(600));
+        let next_id = AtomicUsize::new(0);
+
+        let entry = lifecycle.checkout(factory(&next_id)).await.unwrap();
+        assert_eq!(entry.instance.id, 0);
+        let healthy = entry.instance.healthy.clone();
+        let closed = entry.instance.closed.clone();
+        assert_eq!(lifecycle.metrics().await.in_use, 1);
+        lifecycle.checkin(entry).await;
+
+        // A healthy idle instance is reused
+        let entry = lifecycle.checkout(factory(&next_id)).await.unwrap();
+        assert_eq!(entry.instance.id, 0);
+        lifecycle.checkin(entry).await;
+
+        // Once it stops responding, it is closed and replaced
+        healthy.store(false, Ordering::SeqCst);
+        let entry = lifecycle.checkout(factory(&next_id)).await.unwrap();
+        assert_eq!(entry.instance.id, 1);
+        assert!(closed.load(Ordering::SeqCst));
+
+        let metrics = lifecycle.metrics().await;
+        assert_eq!(metrics.created, 2);
+        assert_eq!(metrics.discarded_unhealthy, 1);
+        assert_eq!((metrics.size, metrics.idle, metrics.in_use), (1, 0, 1));
+    }
+
+    #[tokio::test]
+    async fn test_worn_out_instance_is_recycled_only_when_returned() {
+        let lifecycle = InstanceLifecycle::new(5, 2, Duration::from_secs(600));
+        let next_id = AtomicUsize::new(0);
+
+        let entry = lifecycle.checkout(factory(&next_id)).await.unwrap();
+        lifecycle.checkin(entry).await;
+        let entry = lifecycle.checkout(factory(&next_id)).await.unwrap();
+        let closed = entry.instance.closed.clone();
+        // Reaching the limit mid-scrape does not close the instance
+        assert!(!closed.load(Ordering::SeqCst));
+
+        lifecycle.checkin(entry).await;
+        assert!(closed.load(Ordering::SeqCst));
+        let metrics = lifecycle.metrics().await;
+        assert_eq!(metrics.recycled, 1);
+        assert_eq!(metrics.size, 0);
+
+        let entry = lifecycle.checkout(factory(&next_id)).await.unwrap();
+        assert_eq!(entry.instance.id, 1);
+    }
+
+    #[tokio::test]
+    async fn test_aged_instance_is_recycled() {
+        let lifecycle = InstanceLifecycle::new(5, 50, Duration::ZERO);
+        let next_id = AtomicUsize::new(0);
+
+        // A zero max age wears the instance out as soon as it is returned
+        let entry = lifecycle.checkout(factory(&next_id)).await.unwrap();
+        lifecycle.checkin(entry).await;
+        let entry = lifecycle.checkout(factory(&next_id)).await.unwrap();
+        assert_eq!(entry.instance.id, 1);
+        assert_eq!(lifecycle.metrics().await.recycled, 1);
     }
 }
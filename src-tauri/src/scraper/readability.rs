@@ -0,0 +1,587 @@
+//! Readability style extraction of the main content of a page.
+//!
+//! Blocks of text are scored by their length and punctuation, the score is handed up to
+//! their ancestors, and the best ancestor, penalized by its link density, is taken as the
+//! article. Navigation, sidebars, ads and similar noise are never considered.
+//!
+//! List and forum pages spread their content over many similar items, so when most of the
+//! text sits in a run of repeated siblings the whole run is kept instead of a single item.
+//! When nothing substantial is found, the whole page without its noise is returned.
+
+use std::collections::HashMap;
+
+use ::scraper::{node::Node, ElementRef, Html, Selector};
+use url::Url;
+
+use super::types::{ReadableContent, ReadableKind};
+
+/// Elements that never belong to the main content.
+const NOISE_TAGS: &[&str] = &[
+    "script", "style", "noscript", "nav", "header", "footer", "aside", "form", "iframe", "svg",
+    "button", "select", "template", "dialog", "img", "video", "audio", "canvas",
+];
+/// Class or id tokens of noise containers.
+const NEGATIVE_TOKENS: &[&str] = &[
+    "ad",
+    "ads",
+    "advert",
+    "advertisement",
+    "banner",
+    "breadcrumb",
+    "breadcrumbs",
+    "comment",
+    "comments",
+    "cookie",
+    "footer",
+    "menu",
+    "nav",
+    "navbar",
+    "newsletter",
+    "popup",
+    "promo",
+    "related",
+    "share",
+    "sidebar",
+    "social",
+    "sponsor",
+    "sponsored",
+    "subscribe",
+    "widget",
+];
+/// Class or id tokens of content containers.
+const POSITIVE_TOKENS: &[&str] = &[
+    "article", "body", "content", "entry", "hentry", "main", "post", "story", "text", "thread",
+];
+/// Elements whose text is scored as a paragraph.
+const PARAGRAPH_TAGS: &[&str] = &["p", "pre", "td", "blockquote", "dd"];
+/// Elements that start a new line when rendered.
+const BLOCK_TAGS: &[&str] = &[
+    "p",
+    "div",
+    "section",
+    "article",
+    "main",
+    "blockquote",
+    "pre",
+    "table",
+    "tr",
+    "ul",
+    "ol",
+    "dl",
+    "dt",
+    "dd",
+    "figure",
+    "figcaption",
+    "li",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "hr",
+];
+
+const MIN_PARAGRAPH_CHARS: usize = 25;
+/// Below this, the extracted article is not trusted and the whole page is returned.
+const MIN_ARTICLE_CHARS: usize = 140;
+/// Repeated siblings needed before a container is treated as a list.
+const MIN_LIST_ITEMS: usize = 4;
+
+/// Splits the class and id of an element into lowercase tokens.
+fn tokens(element: &ElementRef) -> Vec<String> {
+    let value = element.value();
+    format!(
+        "{} {}",
+        value.attr("class").unwrap_or_default(),
+        value.id().unwrap_or_default()
+    )
+    .to_lowercase()
+    .split(|c: char| !c.is_alphanumeric())
+    .filter(|t| !t.is_empty())
+    .map(str::to_string)
+    .collect()
+}
+
+/// +25 for content-like class names, -25 for noise-like ones.
+fn class_weight(element: &ElementRef) -> f64 {
+    let tokens = tokens(element);
+    let mut weight = 0.0;
+    if tokens.iter().any(|t| POSITIVE_TOKENS.contains(&t.as_str())) {
+        weight += 25.0;
+    }
+    if tokens.iter().any(|t| NEGATIVE_TOKENS.contains(&t.as_str())) {
+        weight -= 25.0;
+    }
+    weight
+}
+
+fn is_noise(element: &ElementRef) -> bool {
+    let name = element.value().name();
+    if NOISE_TAGS.contains(&name) {
+        return true;
+    }
+    if matches!(name, "html" | "body" | "article" | "main") {
+        return false;
+    }
+    let tokens = tokens(element);
+    tokens.iter().any(|t| NEGATIVE_TOKENS.contains(&t.as_str()))
+        && !tokens.iter().any(|t| POSITIVE_TOKENS.contains(&t.as_str()))
+}
+
+/// Collects the text of a node, skipping noise.
+fn collect_text(element: ElementRef, out: &mut String) {
+    for child in element.children() {
+        match child.value() {
+            Node::Text(text) => out.push_str(text),
+            Node::Element(_) => {
+                let Some(element) = ElementRef::wrap(child) else {
+                    continue;
+                };
+                if is_noise(&element) {
+                    continue;
+                }
+                if BLOCK_TAGS.contains(&element.value().name()) {
+                    out.push(' ');
+                }
+                collect_text(element, out);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The whitespace collapsed text of an element, without noise.
+fn text_of(element: &ElementRef) -> String {
+    let mut text = String::new();
+    collect_text(*element, &mut text);
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn char_len(text: &str) -> usize {
+    text.chars().count()
+}
+
+/// Share of the text of an element that sits inside links.
+fn link_density(element: &ElementRef) -> f64 {
+    let total = char_len(&text_of(element));
+    if total == 0 {
+        return 0.0;
+    }
+    let anchors = Selector::parse("a").expect("valid selector");
+    let linked: usize = element
+        .select(&anchors)
+        .map(|a| char_len(&text_of(&a)))
+        .sum();
+    (linked as f64 / total as f64).min(1.0)
+}
+
+/// The starting score of a candidate, from its tag and class names.
+fn base_score(element: &ElementRef) -> f64 {
+    let tag_score = match element.value().name() {
+        "article" | "main" => 10.0,
+        "div" | "section" => 5.0,
+        "pre" | "td" | "blockquote" => 3.0,
+        "address" | "ol" | "ul" | "dl" | "dd" | "dt" | "li" => -3.0,
+        "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "th" => -5.0,
+        _ => 0.0,
+    };
+    tag_score + class_weight(element)
+}
+
+/// Visits every element below `element` that is not inside noise.
+fn visible_elements<'a>(element: ElementRef<'a>, out: &mut Vec<ElementRef<'a>>) {
+    for child in element.children().filter_map(ElementRef::wrap) {
+        if is_noise(&child) {
+            continue;
+        }
+        out.push(child);
+        visible_elements(child, out);
+    }
+}
+
+/// Scores the ancestors of every paragraph, returning every candidate with its score.
+fn score_candidates<'a>(elements: &[ElementRef<'a>]) -> Vec<(ElementRef<'a>, f64)> {
+    let mut candidates: Vec<(ElementRef<'a>, f64)> = Vec::new();
+    for element in elements {
+        if !PARAGRAPH_TAGS.contains(&element.value().name()) {
+            continue;
+        }
+        let text = text_of(element);
+        let len = char_len(&text);
+        if len < MIN_PARAGRAPH_CHARS {
+            continue;
+        }
+        let commas = text.matches([',', '，', '、']).count() as f64;
+        let score = 1.0 + commas + (len as f64 / 100.0).min(3.0);
+
+        let ancestors = element
+            .ancestors()
+            .filter_map(ElementRef::wrap)
+            .take(2)
+            .enumerate();
+        for (level, ancestor) in ancestors {
+            let index = match candidates.iter().position(|(c, _)| *c == ancestor) {
+                Some(index) => index,
+                None => {
+                    candidates.push((ancestor, base_score(&ancestor)));
+                    candidates.len() - 1
+                }
+            };
+            candidates[index].1 += if level == 0 { score } else { score / 2.0 };
+        }
+    }
+    for (element, score) in candidates.iter_mut() {
+        *score *= 1.0 - link_density(element);
+    }
+    candidates
+}
+
+/// Finds the container whose repeated children hold most of the page text.
+///
+/// Returns the container and the text length of the repeated children.
+fn repeated_container<'a>(
+    elements: &[ElementRef<'a>],
+    page_len: usize,
+) -> Option<(ElementRef<'a>, usize)> {
+    let mut best: Option<(ElementRef<'a>, usize)> = None;
+    for element in elements {
+        let mut groups: HashMap<String, (usize, usize)> = HashMap::new();
+        for child in element.children().filter_map(ElementRef::wrap) {
+            let name = child.value().name();
+            // Runs of paragraphs are how articles are written, not lists
+            if PARAGRAPH_TAGS.contains(&name) || is_noise(&child) {
+                continue;
+            }
+            let signature = format!("{}.{}", name, child.value().attr("class").unwrap_or(""));
+            let group = groups.entry(signature).or_default();
+            group.0 += 1;
+            group.1 += char_len(&text_of(&child));
+        }
+        let Some((_, text_len)) = groups
+            .into_values()
+            .filter(|(count, _)| *count >= MIN_LIST_ITEMS)
+            .max_by_key(|(_, text_len)| *text_len)
+        else {
+            continue;
+        };
+        if text_len * 2 >= page_len && best.map_or(true, |(_, len)| text_len > len) {
+            best = Some((*element, text_len));
+        }
+    }
+    best
+}
+
+/// Appends the siblings of the top candidate that look like part of the same article.
+fn article_parts<'a>(
+    top: ElementRef<'a>,
+    top_score: f64,
+    candidates: &[(ElementRef<'a>, f64)],
+) -> Vec<ElementRef<'a>> {
+    let Some(parent) = top.parent().and_then(ElementRef::wrap) else {
+        return vec![top];
+    };
+    let threshold = (top_score * 0.2).max(10.0);
+    parent
+        .children()
+        .filter_map(ElementRef::wrap)
+        .filter(|sibling| {
+            if *sibling == top {
+                return true;
+            }
+            if is_noise(sibling) {
+                return false;
+            }
+            if candidates
+                .iter()
+                .any(|(c, score)| c == sibling && *score >= threshold)
+            {
+                return true;
+            }
+            sibling.value().name() == "p"
+                && char_len(&text_of(sibling)) > 80
+                && link_density(sibling) < 0.25
+        })
+        .collect()
+}
+
+/// Renders an element as lightweight markdown, skipping noise.
+fn render(element: ElementRef, base_url: Option<&Url>, keep_link: bool, out: &mut String) {
+    for child in element.children() {
+        match child.value() {
+            Node::Text(text) => {
+                let collapsed = text.split_whitespace().collect::<Vec<_>>().join(" ");
+                if collapsed.is_empty() {
+                    continue;
+                }
+                if text.starts_with(char::is_whitespace) && !out.ends_with([' ', '\n']) {
+                    out.push(' ');
+                }
+                out.push_str(&collapsed);
+                if text.ends_with(char::is_whitespace) {
+                    out.push(' ');
+                }
+            }
+            Node::Element(_) => {
+                let Some(element) = ElementRef::wrap(child) else {
+                    continue;
+                };
+                if is_noise(&element) {
+                    continue;
+                }
+                let name = element.value().name();
+                match name {
+                    "br" => out.push('\n'),
+                    "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                        let level = name[1..].parse::<usize>().unwrap_or(2);
+                        out.push_str("\n\n");
+                        out.push_str(&"#".repeat(level));
+                        out.push(' ');
+                        render(element, base_url, keep_link, out);
+                        out.push_str("\n\n");
+                    }
+                    "li" => {
+                        out.push_str("\n- ");
+                        render(element, base_url, keep_link, out);
+                        out.push('\n');
+                    }
+                    "a" if keep_link => {
+                        let href = element
+                            .value()
+                            .attr("href")
+                            .filter(|h| !h.is_empty() && !h.starts_with("javascript:"))
+                            .map(|h| {
+                                base_url
+                                    .and_then(|base| base.join(h).ok())
+                                    .map(|u| u.to_string())
+                                    .unwrap_or_else(|| h.to_string())
+                            });
+                        match href {
+                            Some(href) => {
+                                out.push('[');
+                                render(element, base_url, keep_link, out);
+                                out.push_str("](");
+                                out.push_str(&href);
+                                out.push(')');
+                            }
+                            None => render(element, base_url, keep_link, out),
+                        }
+                    }
+                    _ if BLOCK_TAGS.contains(&name) => {
+                        out.push_str("\n\n");
+                        render(element, base_url, keep_link, out);
+                        out.push_str("\n\n");
+                    }
+                    _ => render(element, base_url, keep_link, out),
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Trims every line and keeps at most one blank line between blocks.
+fn tidy(markdown: &str) -> String {
+    let mut lines: Vec<&str> = Vec::new();
+    for line in markdown.lines().map(str::trim) {
+        if line.is_empty() && lines.last().map_or(true, |last| last.is_empty()) {
+            continue;
+        }
+        lines.push(line);
+    }
+    while lines.last().is_some_and(|last| last.is_empty()) {
+        lines.pop();
+    }
+    lines.join("\n")
+}
+
+fn render_all(parts: &[ElementRef], base_url: Option<&Url>, keep_link: bool) -> String {
+    let mut out = String::new();
+    for part in parts {
+        out.push_str("\n\n");
+        render(*part, base_url, keep_link, &mut out);
+    }
+    tidy(&out)
+}
+
+/// Reads the title from `og:title`, `<title>` or the first `<h1>`.
+fn page_title(document: &Html) -> Option<String> {
+    let select_first = |selector: &str| {
+        let selector = Selector::parse(selector).expect("valid selector");
+        document.select(&selector).next()
+    };
+    select_first("meta[property='og:title']")
+        .and_then(|meta| meta.value().attr("content").map(str::to_string))
+        .or_else(|| select_first("title").map(|t| t.text().collect::<String>()))
+        .or_else(|| select_first("h1").map(|h| text_of(&h)))
+        .map(|title| title.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|title| !title.is_empty())
+}
+
+/// Extracts the main content of an HTML page.
+///
+/// # Arguments
+/// * `html` - The HTML of the page.
+/// * `base_url` - The page URL, used to resolve relative links.
+/// * `keep_link` - Whether links are kept as markdown links.
+pub fn extract(html: &str, base_url: Option<&Url>, keep_link: bool) -> ReadableContent {
+    let document = Html::parse_document(html);
+    let title = page_title(&document);
+    let body_selector = Selector::parse("body").expect("valid selector");
+    let root = document
+        .select(&body_selector)
+        .next()
+        .unwrap_or_else(|| document.root_element());
+
+    let mut elements = Vec::new();
+    visible_elements(root, &mut elements);
+    let page_len = char_len(&text_of(&root));
+
+    let candidates = score_candidates(&elements);
+    let top = candidates
+        .iter()
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .copied();
+    let list = repeated_container(&elements, page_len);
+
+    let (kind, parts) = match (top, list) {
+        // The list itself or a single item of it scores best, keep the whole run
+        (Some((top, _)), Some((container, items_len)))
+            if top == container
+                || (char_len(&text_of(&top)) * 2 < items_len
+                    && !container.ancestors().any(|a| a == *top)) =>
+        {
+            (ReadableKind::List, vec![container])
+        }
+        (None, Some((container, _))) => (ReadableKind::List, vec![container]),
+        (Some((top, score)), _) => (
+            ReadableKind::Article,
+            article_parts(top, score, &candidates),
+        ),
+        (None, None) => (ReadableKind::FullPage, vec![root]),
+    };
+
+    let content = render_all(&parts, base_url, keep_link);
+    if kind == ReadableKind::FullPage || char_len(&content) >= MIN_ARTICLE_CHARS {
+        return ReadableContent {
+            title,
+            text_length: char_len(&content),
+            content,
+            kind,
+        };
+    }
+
+    // Too little was found to trust the extraction, fall back to the whole page
+    let content = render_all(&[root], base_url, keep_link);
+    ReadableContent {
+        title,
+        text_length: char_len(&content),
+        content,
+        kind: ReadableKind::FullPage,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ARTICLE: &str = include_str!("fixtures/article.html");
+    const FORUM: &str = include_str!("fixtures/forum_thread.html");
+    const LISTING: &str = include_str!("fixtures/news_listing.html");
+    const SHORT: &str = include_str!("fixtures/short_page.html");
+
+    #[test]
+    fn test_article_keeps_body_and_drops_noise() {
+        let url = Url::parse("https://blog.example.com/posts/ownership").unwrap();
+        let result = extract(ARTICLE, Some(&url), true);
+
+        assert_eq!(result.kind, ReadableKind::Article);
+        assert_eq!(
+            result.title.as_deref(),
+            Some("Understanding Ownership in Rust")
+        );
+        // Every paragraph of the article survives
+        assert!(result.content.contains("Ownership is a set of rules"));
+        assert!(result.content.contains("the borrow checker"));
+        assert!(result.content.contains("## Borrowing"));
+        assert!(result.content.contains("When the owner goes out of scope"));
+        // Relative links are resolved
+        assert!(result
+            .content
+            .contains("[the book](https://blog.example.com/book/ch04)"));
+        // Navigation, sidebar, ads, comments and footer are gone
+        for noise in [
+            "Home",
+            "Subscribe to our newsletter",
+            "Sponsored",
+            "Popular posts",
+            "Great article, thanks",
+            "All rights reserved",
+            "console.log",
+        ] {
+            assert!(
+                !result.content.contains(noise),
+                "noise {:?} leaked into:\n{}",
+                noise,
+                result.content
+            );
+        }
+    }
+
+    #[test]
+    fn test_forum_thread_keeps_every_post() {
+        let result = extract(FORUM, None, false);
+
+        assert_eq!(result.kind, ReadableKind::List);
+        for post in [
+            "Has anyone managed to cross compile",
+            "You need the aarch64 linker installed",
+            "Another option is to use cross",
+            "Thanks, cross worked out of the box",
+            "For reference, the target triple",
+        ] {
+            assert!(
+                result.content.contains(post),
+                "post {:?} was cropped from:\n{}",
+                post,
+                result.content
+            );
+        }
+        assert!(!result.content.contains("Forum rules"));
+    }
+
+    #[test]
+    fn test_listing_keeps_every_item() {
+        let result = extract(LISTING, None, true);
+
+        assert_eq!(result.kind, ReadableKind::List);
+        assert!(result
+            .content
+            .contains("[Rust 1.80 brings LazyCell and LazyLock](/news/1)"));
+        for headline in [
+            "Rust 1.80 brings LazyCell",
+            "Tokio 1.39 improves",
+            "Async closures stabilised",
+            "Cargo gets a new resolver",
+            "Clippy adds 20 lints",
+        ] {
+            assert!(
+                result.content.contains(headline),
+                "item {:?} missing from:\n{}",
+                headline,
+                result.content
+            );
+        }
+        assert!(!result.content.contains("Trending tags"));
+    }
+
+    #[test]
+    fn test_short_page_falls_back_to_full_page() {
+        let result = extract(SHORT, None, false);
+
+        assert_eq!(result.kind, ReadableKind::FullPage);
+        assert!(result.content.contains("Page moved"));
+        assert!(result.content.contains("new address"));
+        assert!(result.text_length > 0);
+    }
+}
@@ -44,6 +44,10 @@ pub enum StrapeContentFormat {
     Markdown,
     Text,
     Links,
+    /// The raw HTML of the page
+    Html,
+    /// The main content of the page, see `ReadableContent`
+    Readable,
 }
 
 impl From<StrapeContentFormat> for String {
@@ -52,6 +56,8 @@ impl From<StrapeContentFormat> for String {
             StrapeContentFormat::Markdown => "markdown".to_string(),
             StrapeContentFormat::Text => "text".to_string(),
             StrapeContentFormat::Links => "links".to_string(),
+            StrapeContentFormat::Html => "html".to_string(),
+            StrapeContentFormat::Readable => "readable".to_string(),
         }
     }
 }
@@ -61,6 +67,8 @@ impl From<String> for StrapeContentFormat {
         match format.as_str() {
             "text" => StrapeContentFormat::Text,
             "links" => StrapeContentFormat::Links,
+            "html" => StrapeContentFormat::Html,
+            "readable" => StrapeContentFormat::Readable,
             _ => StrapeContentFormat::Markdown,
         }
     }
@@ -72,6 +80,8 @@ impl Display for StrapeContentFormat {
             StrapeContentFormat::Markdown => write!(f, "markdown"),
             StrapeContentFormat::Text => write!(f, "text"),
             StrapeContentFormat::Links => write!(f, "links"),
+            StrapeContentFormat::Html => write!(f, "html"),
+            StrapeContentFormat::Readable => write!(f, "readable"),
         }
     }
 }
@@ -171,3 +181,36 @@ impl Default for GenericContentRule {
         }
     }
 }
+
+/// How the main content of a page was found.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReadableKind {
+    /// A single article, surrounding noise removed
+    Article,
+    /// A list or forum page, every item kept
+    List,
+    /// Nothing substantial was found, the whole page without noise
+    FullPage,
+}
+
+/// The main content of a page, extracted by `readability::extract`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadableContent {
+    pub title: Option<String>,
+    /// The content as markdown
+    pub content: String,
+    pub kind: ReadableKind,
+    /// Length of `content` in characters
+    pub text_length: usize,
+}
+
+impl Display for ReadableContent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.title {
+            Some(title) => write!(f, "# {}\n\n{}", title, self.content),
+            None => write!(f, "{}", self.content),
+        }
+    }
+}
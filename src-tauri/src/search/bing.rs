@@ -0,0 +1,239 @@
+use crate::http::client::HttpClient;
+use crate::http::types::HttpConfig;
+use crate::search::search::{
+    SearchHttpError, SearchParams, SearchPeriod, SearchProvider, SearchResult,
+};
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use rust_i18n::t;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+const BING_API_URL: &str = "https://api.bing.microsoft.com/v7.0/search";
+const BING_DEFAULT_PAGE_SIZE: u32 = 10;
+/// The Web Search API rejects `count` values above 50.
+const BING_MAX_PAGE_SIZE: u32 = 50;
+
+/// Query parameters of the Bing Web Search API.
+#[derive(Debug, Serialize)]
+struct BingSearchRequest<'a> {
+    q: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    count: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    offset: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mkt: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    freshness: Option<String>,
+    #[serde(rename = "responseFilter")]
+    response_filter: &'static str,
+}
+
+impl<'a> From<&'a SearchParams> for BingSearchRequest<'a> {
+    fn from(params: &'a SearchParams) -> Self {
+        // `freshness` only knows Day, Week and Month, anything else must be a date range.
+        let freshness = params.period.as_ref().map(|p| match p {
+            SearchPeriod::Hour | SearchPeriod::Day => "Day".to_string(),
+            SearchPeriod::Week => "Week".to_string(),
+            SearchPeriod::Month => "Month".to_string(),
+            SearchPeriod::Year => {
+                let today = Utc::now().date_naive();
+                format!(
+                    "{}..{}",
+                    (today - Duration::days(365)).format("%Y-%m-%d"),
+                    today.format("%Y-%m-%d")
+                )
+            }
+        });
+
+        let count = params
+            .page_size(BING_DEFAULT_PAGE_SIZE)
+            .min(BING_MAX_PAGE_SIZE);
+        let offset = Some(params.start_offset(count)).filter(|&o| o > 0);
+
+        BingSearchRequest {
+            q: &params.query,
+            count: params.count.map(|_| count),
+            offset,
+            mkt: params.language.clone(),
+            freshness,
+            response_filter: "Webpages",
+        }
+    }
+}
+
+/// Represents the overall structure of the Bing Web Search API response.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct BingSearchResponse {
+    web_pages: Option<BingWebPages>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct BingWebPages {
+    value: Vec<BingWebPage>,
+}
+
+/// Represents a single web page result from the Bing Web Search API.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BingWebPage {
+    name: String,
+    url: String,
+    snippet: Option<String>,
+    site_name: Option<String>,
+    date_last_crawled: Option<String>,
+}
+
+impl BingSearchResponse {
+    fn into_results(self) -> Vec<SearchResult> {
+        self.web_pages
+            .map(|pages| pages.value)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|page| SearchResult {
+                title: page.name,
+                url: page.url,
+                snippet: page.snippet,
+                sitename: page.site_name,
+                publish_date: page.date_last_crawled,
+                ..Default::default()
+            })
+            .collect()
+    }
+}
+
+/// The Bing Web Search API provider.
+///
+/// Unlike the built-in `bing` provider, which scrapes the result page,
+/// this one calls the official API and needs a subscription key.
+pub struct BingSearch {
+    api_key: String,
+    http_client: HttpClient,
+}
+
+impl BingSearch {
+    /// Creates a new instance of the Bing Web Search API provider.
+    ///
+    /// # Arguments
+    ///
+    /// * `api_key` - The subscription key of the Bing Search resource.
+    /// * `proxy` - An optional proxy URL to be used for requests.
+    pub fn new(api_key: String, proxy: Option<String>) -> Result<Self> {
+        if api_key.trim().is_empty() {
+            return Err(anyhow!(t!("tools.search.bing_api_key_empty").to_string()));
+        }
+        let http_client = HttpClient::new(proxy)?;
+        Ok(Self {
+            api_key,
+            http_client,
+        })
+    }
+}
+
+#[async_trait]
+impl SearchProvider for BingSearch {
+    async fn search(&self, params: &Value) -> Result<Vec<SearchResult>> {
+        let search_params = SearchParams::try_from(params)?;
+
+        // The web endpoint has no separate news or image verticals.
+        search_params.search_type.resolve_for("Bing", &[]);
+
+        let request = BingSearchRequest::from(&search_params);
+        let config = HttpConfig::get(BING_API_URL)
+            .query(json!(&request))
+            .header("Ocp-Apim-Subscription-Key", &self.api_key);
+
+        let response = self.http_client.send_request(config).await?;
+
+        if !response.is_success() {
+            return Err(SearchHttpError::from_response("Bing", &response).into());
+        }
+
+        let body = response
+            .body
+            .context("Response body was empty, but a successful status was returned.")?;
+
+        let bing_response: BingSearchResponse = serde_json::from_str(&body)
+            .context("Failed to deserialize Bing response from JSON body")?;
+
+        Ok(bing_response.into_results())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bing_parses_sample_response() {
+        let body = r#"{
+            "_type": "SearchResponse",
+            "queryContext": { "originalQuery": "rust language" },
+            "webPages": {
+                "webSearchUrl": "https://www.bing.com/search?q=rust+language",
+                "totalEstimatedMatches": 1230000,
+                "value": [
+                    {
+                        "id": "https://api.bing.microsoft.com/api/v7/#WebPages.0",
+                        "name": "Rust Programming Language",
+                        "url": "https://www.rust-lang.org/",
+                        "isFamilyFriendly": true,
+                        "displayUrl": "https://www.rust-lang.org",
+                        "snippet": "A language empowering everyone to build reliable and efficient software.",
+                        "dateLastCrawled": "2024-07-20T08:12:00.0000000Z",
+                        "language": "en"
+                    },
+                    {
+                        "id": "https://api.bing.microsoft.com/api/v7/#WebPages.1",
+                        "name": "Rust (programming language) - Wikipedia",
+                        "url": "https://en.wikipedia.org/wiki/Rust_(programming_language)",
+                        "siteName": "Wikipedia"
+                    }
+                ]
+            },
+            "rankingResponse": {}
+        }"#;
+        let response: BingSearchResponse = serde_json::from_str(body).unwrap();
+        let results = response.into_results();
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].title, "Rust Programming Language");
+        assert_eq!(results[0].url, "https://www.rust-lang.org/");
+        assert_eq!(
+            results[0].publish_date.as_deref(),
+            Some("2024-07-20T08:12:00.0000000Z")
+        );
+        assert_eq!(results[1].snippet, None);
+        assert_eq!(results[1].sitename.as_deref(), Some("Wikipedia"));
+
+        // Queries without web results omit `webPages` entirely.
+        let empty: BingSearchResponse =
+            serde_json::from_str(r#"{"_type":"SearchResponse"}"#).unwrap();
+        assert!(empty.into_results().is_empty());
+    }
+
+    #[test]
+    fn test_bing_maps_period_and_paging() {
+        let params = SearchParams::try_from(
+            &json!({"query": "rust", "count": 20, "page": 3, "period": "week"}),
+        )
+        .unwrap();
+        let request = BingSearchRequest::from(&params);
+        assert_eq!(request.freshness.as_deref(), Some("Week"));
+        assert_eq!(request.count, Some(20));
+        assert_eq!(request.offset, Some(40));
+
+        let params = SearchParams::try_from(&json!({"query": "rust", "period": "year"})).unwrap();
+        let freshness = BingSearchRequest::from(&params).freshness.unwrap();
+        assert!(freshness.contains(".."), "{freshness}");
+    }
+
+    #[test]
+    fn test_bing_requires_api_key() {
+        assert!(BingSearch::new(" ".to_string(), None).is_err());
+    }
+}
@@ -4,13 +4,49 @@ use serde_json::Value;
 use tauri::{AppHandle, Wry};
 
 use crate::scraper::engine;
-use crate::scraper::types::{ScrapeRequest, SearchOptions};
+use crate::scraper::types::{ContentOptions, ScrapeRequest, SearchOptions, StrapeContentFormat};
 
-use super::{SearchParams, SearchPeriod, SearchProvider, SearchProviderName, SearchResult};
+use super::{
+    FetchLimitConfig, FetchLimiter, SearchParams, SearchPeriod, SearchProvider, SearchProviderName,
+    SearchResult,
+};
+
+const BUILTIN_DEFAULT_PAGE_SIZE: u32 = 10;
 
 pub struct BuiltInSearch {
     pub app_handle: AppHandle<Wry>,
     pub provider: SearchProviderName,
+    /// Limits for fetching result pages when `fetch_content` is requested
+    pub fetch_limits: FetchLimitConfig,
+}
+
+impl BuiltInSearch {
+    /// Fetches the page behind every result into its `content`.
+    ///
+    /// Results whose page fails or times out keep their snippet only.
+    async fn fetch_contents(&self, results: &mut [SearchResult]) {
+        let limiter = FetchLimiter::new(self.fetch_limits.clone());
+        let urls = results.iter().map(|r| r.url.clone()).collect();
+        let contents = limiter
+            .fetch_all(urls, |url| {
+                let request = ScrapeRequest::Content(ContentOptions {
+                    url,
+                    content_format: StrapeContentFormat::Markdown,
+                    keep_link: false,
+                    keep_image: false,
+                });
+                engine::run(self.app_handle.clone(), request)
+            })
+            .await;
+
+        for (result, content) in results.iter_mut().zip(contents) {
+            match content {
+                Ok(content) if !content.trim().is_empty() => result.content = Some(content),
+                Ok(_) => {}
+                Err(e) => log::warn!("Failed to fetch search result {}: {}", result.url, e),
+            }
+        }
+    }
 }
 
 #[async_trait]
@@ -18,6 +54,16 @@ impl SearchProvider for BuiltInSearch {
     async fn search(&self, params: &Value) -> Result<Vec<SearchResult>> {
         let search_params = SearchParams::try_from(params)?;
 
+        // The scraper paginates by page number only, so translate offsets into pages.
+        let page = (search_params.page.is_some() || search_params.offset.is_some())
+            .then(|| search_params.page_number(BUILTIN_DEFAULT_PAGE_SIZE));
+
+        // The scraper only understands regular result pages.
+        search_params
+            .search_type
+            .resolve_for(&self.provider.to_string(), &[]);
+
+        let fetch_content = search_params.fetch_content;
         let query = search_params.query;
         let provider = self.provider.clone();
 
@@ -33,7 +79,7 @@ impl SearchProvider for BuiltInSearch {
             provider: provider.to_string(),
             query,
             number: search_params.count,
-            page: search_params.page,
+            page,
             time_period,
         };
 
@@ -44,7 +90,11 @@ impl SearchProvider for BuiltInSearch {
             return Ok(vec![]);
         }
 
-        serde_json::from_str(&res)
-            .map_err(|e| anyhow!("Failed to parse search results from scraper: {}", e))
+        let mut results: Vec<SearchResult> = serde_json::from_str(&res)
+            .map_err(|e| anyhow!("Failed to parse search results from scraper: {}", e))?;
+        if fetch_content {
+            self.fetch_contents(&mut results).await;
+        }
+        Ok(results)
     }
 }
@@ -0,0 +1,119 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde::Serialize;
+
+use super::{pagination::normalize_url, search::SearchResult};
+
+/// A source that can be cited as `[n]` in a report.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct CitationSource {
+    pub url: String,
+    pub title: String,
+}
+
+/// Hands out citation numbers that stay stable for the whole session.
+///
+/// The same URL always gets the same number, no matter how many searches return it,
+/// and new URLs are numbered after every URL seen before.
+#[derive(Debug, Default)]
+pub struct CitationRegistry {
+    ids: HashMap<String, usize>,
+    sources: BTreeMap<usize, CitationSource>,
+}
+
+impl CitationRegistry {
+    #[cfg(test)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the `id` of every result to its citation number, registering unseen URLs.
+    pub fn assign(&mut self, results: &mut [SearchResult]) {
+        for result in results.iter_mut() {
+            let next_id = self.ids.len() + 1;
+            let id = *self
+                .ids
+                .entry(normalize_url(&result.url))
+                .or_insert(next_id);
+            self.sources.entry(id).or_insert_with(|| CitationSource {
+                url: result.url.clone(),
+                title: result.title.clone(),
+            });
+            result.id = id;
+        }
+    }
+
+    /// Returns the citation number of a URL seen before.
+    #[cfg(test)]
+    pub fn get(&self, url: &str) -> Option<usize> {
+        self.ids.get(&normalize_url(url)).copied()
+    }
+
+    /// Returns every registered source by citation number.
+    pub fn sources(&self) -> &BTreeMap<usize, CitationSource> {
+        &self.sources
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(url: &str, title: &str) -> SearchResult {
+        SearchResult {
+            title: title.to_string(),
+            url: url.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_same_url_shares_one_citation() {
+        let mut registry = CitationRegistry::new();
+        let mut results = vec![
+            result("https://example.com/a", "A"),
+            result("https://example.com/b", "B"),
+            result("https://example.com/a/#intro", "A again"),
+        ];
+        registry.assign(&mut results);
+
+        assert_eq!(
+            results.iter().map(|r| r.id).collect::<Vec<_>>(),
+            vec![1, 2, 1]
+        );
+        assert_eq!(registry.sources().len(), 2);
+        assert_eq!(registry.sources()[&1].title, "A");
+    }
+
+    #[test]
+    fn test_citations_stay_stable_across_searches() {
+        let mut registry = CitationRegistry::new();
+        let mut first = vec![
+            result("https://example.com/a", "A"),
+            result("https://example.com/b", "B"),
+        ];
+        registry.assign(&mut first);
+
+        let mut second = vec![
+            result("https://example.com/c", "C"),
+            result("https://example.com/b", "B"),
+        ];
+        registry.assign(&mut second);
+
+        assert_eq!(second[0].id, 3);
+        assert_eq!(second[1].id, 2);
+        assert_eq!(registry.get("https://example.com/a"), Some(1));
+        assert_eq!(
+            registry
+                .sources()
+                .iter()
+                .map(|(id, s)| (*id, s.url.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                (1, "https://example.com/a"),
+                (2, "https://example.com/b"),
+                (3, "https://example.com/c"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,241 @@
+use crate::http::client::HttpClient;
+use crate::http::types::HttpConfig;
+use crate::search::search::{
+    SearchHttpError, SearchParams, SearchPeriod, SearchProvider, SearchResult,
+};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use scraper::{ElementRef, Html, Selector};
+use serde::Serialize;
+use serde_json::{json, Value};
+use url::Url;
+
+const DDG_HTML_URL: &str = "https://html.duckduckgo.com/html/";
+const DDG_DEFAULT_PAGE_SIZE: u32 = 10;
+const DDG_USER_AGENT: &str =
+    "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/126.0.0.0 Safari/537.36";
+/// Markers of the bot challenge page DuckDuckGo serves instead of results when throttling.
+const DDG_ANOMALY_MARKERS: [&str; 2] = ["anomaly-modal", "challenge-form"];
+
+/// Query parameters of the DuckDuckGo html endpoint.
+#[derive(Debug, Serialize)]
+struct DuckDuckGoRequest<'a> {
+    q: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    s: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    kl: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    df: Option<&'static str>,
+}
+
+impl<'a> From<&'a SearchParams> for DuckDuckGoRequest<'a> {
+    fn from(params: &'a SearchParams) -> Self {
+        // DuckDuckGo has no hourly filter, the closest is the last day.
+        let df = params.period.as_ref().map(|p| match p {
+            SearchPeriod::Hour | SearchPeriod::Day => "d",
+            SearchPeriod::Week => "w",
+            SearchPeriod::Month => "m",
+            SearchPeriod::Year => "y",
+        });
+
+        DuckDuckGoRequest {
+            q: &params.query,
+            s: Some(params.start_offset(DDG_DEFAULT_PAGE_SIZE)).filter(|&s| s > 0),
+            kl: params.language.clone(),
+            df,
+        }
+    }
+}
+
+/// The DuckDuckGo provider backed by the key-less html endpoint.
+///
+/// Unlike the built-in `duckduckgo` provider, it needs no webview, which makes it
+/// usable in headless environments.
+pub struct DuckDuckGoSearch {
+    http_client: HttpClient,
+}
+
+impl DuckDuckGoSearch {
+    /// Creates a new instance of the DuckDuckGo provider.
+    ///
+    /// # Arguments
+    ///
+    /// * `proxy` - An optional proxy URL to be used for requests.
+    pub fn new(proxy: Option<String>) -> Result<Self> {
+        let http_client = HttpClient::new(proxy)?;
+        Ok(Self { http_client })
+    }
+}
+
+/// Returns the target of a result link, unwrapping DuckDuckGo's `/l/?uddg=` redirect.
+fn resolve_result_url(href: &str) -> Option<String> {
+    let absolute = if href.starts_with("//") {
+        format!("https:{}", href)
+    } else if href.starts_with('/') {
+        format!("https://duckduckgo.com{}", href)
+    } else {
+        href.to_string()
+    };
+    let url = Url::parse(&absolute).ok()?;
+    if url.path() == "/l/" {
+        return url
+            .query_pairs()
+            .find(|(k, _)| k == "uddg")
+            .map(|(_, v)| v.into_owned());
+    }
+    Some(absolute)
+}
+
+fn element_text(element: ElementRef) -> String {
+    element
+        .text()
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Whether the page is the bot challenge rather than a result page.
+fn is_rate_limited(html: &str) -> bool {
+    DDG_ANOMALY_MARKERS.iter().any(|m| html.contains(m))
+}
+
+/// Extracts the organic results from an html endpoint page, skipping ads.
+fn parse_results(html: &str) -> Vec<SearchResult> {
+    let document = Html::parse_document(html);
+    let result_selector = Selector::parse("div.result:not(.result--ad)").unwrap();
+    let title_selector = Selector::parse("a.result__a").unwrap();
+    let snippet_selector = Selector::parse(".result__snippet").unwrap();
+    let site_selector = Selector::parse(".result__url").unwrap();
+
+    document
+        .select(&result_selector)
+        .filter_map(|result| {
+            let link = result.select(&title_selector).next()?;
+            let url = resolve_result_url(link.value().attr("href")?)?;
+            Some(SearchResult {
+                title: element_text(link),
+                url,
+                snippet: result
+                    .select(&snippet_selector)
+                    .next()
+                    .map(element_text)
+                    .filter(|s| !s.is_empty()),
+                sitename: result
+                    .select(&site_selector)
+                    .next()
+                    .map(element_text)
+                    .filter(|s| !s.is_empty()),
+                ..Default::default()
+            })
+        })
+        .collect()
+}
+
+#[async_trait]
+impl SearchProvider for DuckDuckGoSearch {
+    async fn search(&self, params: &Value) -> Result<Vec<SearchResult>> {
+        let search_params = SearchParams::try_from(params)?;
+
+        // The html endpoint only serves regular web results.
+        search_params.search_type.resolve_for("DuckDuckGo", &[]);
+
+        let request = DuckDuckGoRequest::from(&search_params);
+        let config = HttpConfig::get(DDG_HTML_URL)
+            .query(json!(&request))
+            .header("User-Agent", DDG_USER_AGENT);
+
+        let response = self.http_client.send_request(config).await?;
+
+        if !response.is_success() {
+            return Err(SearchHttpError::from_response("DuckDuckGo", &response).into());
+        }
+
+        let body = response
+            .body
+            .context("Response body was empty, but a successful status was returned.")?;
+
+        // A throttled client gets a 202 with a challenge page instead of a 429, report it
+        // as one so the retry wrapper backs off.
+        if response.status == 202 || is_rate_limited(&body) {
+            return Err(SearchHttpError {
+                provider: "DuckDuckGo".to_string(),
+                status: 429,
+                retry_after: None,
+                message: "DuckDuckGo is rate limiting requests".to_string(),
+            }
+            .into());
+        }
+
+        let mut results = parse_results(&body);
+        if let Some(count) = search_params.count {
+            results.truncate(count as usize);
+        }
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_PAGE: &str = r#"
+        <html><body><div id="links" class="results">
+          <div class="result results_links results_links_deep result--ad">
+            <div class="links_main links_deep result__body">
+              <h2 class="result__title"><a class="result__a" href="https://duckduckgo.com/y.js?ad_domain=example.com">Sponsored</a></h2>
+            </div>
+          </div>
+          <div class="result results_links results_links_deep web-result">
+            <div class="links_main links_deep result__body">
+              <h2 class="result__title">
+                <a rel="nofollow" class="result__a" href="//duckduckgo.com/l/?uddg=https%3A%2F%2Fwww.rust%2Dlang.org%2F&amp;rut=abc">Rust Programming <b>Language</b></a>
+              </h2>
+              <div class="result__extras"><a class="result__url" href="https://www.rust-lang.org/"> www.rust-lang.org </a></div>
+              <a class="result__snippet" href="//duckduckgo.com/l/?uddg=https%3A%2F%2Fwww.rust%2Dlang.org%2F">A language empowering everyone to build reliable and efficient software.</a>
+            </div>
+          </div>
+          <div class="result results_links results_links_deep web-result">
+            <div class="links_main links_deep result__body">
+              <h2 class="result__title">
+                <a rel="nofollow" class="result__a" href="https://doc.rust-lang.org/book/">The Rust Programming Language - The Rust Book</a>
+              </h2>
+            </div>
+          </div>
+        </div></body></html>
+    "#;
+
+    #[test]
+    fn test_ddg_parses_sample_page() {
+        let results = parse_results(SAMPLE_PAGE);
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].title, "Rust Programming Language");
+        assert_eq!(results[0].url, "https://www.rust-lang.org/");
+        assert_eq!(
+            results[0].snippet.as_deref(),
+            Some("A language empowering everyone to build reliable and efficient software.")
+        );
+        assert_eq!(results[0].sitename.as_deref(), Some("www.rust-lang.org"));
+        assert_eq!(results[1].url, "https://doc.rust-lang.org/book/");
+        assert_eq!(results[1].snippet, None);
+        assert!(!is_rate_limited(SAMPLE_PAGE));
+    }
+
+    #[test]
+    fn test_ddg_detects_challenge_page() {
+        let page = r#"<html><body><div class="anomaly-modal__modal"><form id="challenge-form"></form></div></body></html>"#;
+        assert!(is_rate_limited(page));
+        assert!(parse_results(page).is_empty());
+    }
+
+    #[test]
+    fn test_ddg_maps_period_and_offset() {
+        let params =
+            SearchParams::try_from(&json!({"query": "rust", "page": 2, "period": "hour"})).unwrap();
+        let request = DuckDuckGoRequest::from(&params);
+        assert_eq!(request.df, Some("d"));
+        assert_eq!(request.s, Some(10));
+    }
+}
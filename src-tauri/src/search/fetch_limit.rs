@@ -0,0 +1,264 @@
+//! Limits for fetching the pages behind search results.
+//!
+//! Pages of different domains are fetched in parallel up to a global limit, while every
+//! domain has its own token bucket and concurrency limit so a single site is never hit
+//! with a burst of requests. A failed or timed out page only fails its own entry.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use futures::future::join_all;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Semaphore;
+use tokio::time::Instant;
+use url::Url;
+
+/// Fetch limits, stored under `search_fetch_limits`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct FetchLimitConfig {
+    /// Pages fetched at the same time across all domains
+    pub max_concurrency: usize,
+    /// Pages of one domain fetched at the same time
+    pub per_domain_concurrency: usize,
+    /// Requests per second a single domain receives once its burst is used up
+    pub per_domain_rate: f64,
+    /// Requests a domain may receive at once before the rate applies
+    pub per_domain_burst: u32,
+    /// Seconds after which a single page is given up
+    pub timeout_secs: u64,
+}
+
+impl Default for FetchLimitConfig {
+    fn default() -> Self {
+        Self {
+            max_concurrency: 4,
+            per_domain_concurrency: 1,
+            per_domain_rate: 1.0,
+            per_domain_burst: 1,
+            timeout_secs: 20,
+        }
+    }
+}
+
+/// A token bucket that hands out reservations, so concurrent callers queue up in order.
+#[derive(Debug)]
+struct TokenBucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// The bucket and the concurrency slots of one domain.
+struct DomainLimit {
+    bucket: TokenBucket,
+    slots: Arc<Semaphore>,
+}
+
+/// Fetches pages while respecting a `FetchLimitConfig`.
+pub struct FetchLimiter {
+    config: FetchLimitConfig,
+    slots: Arc<Semaphore>,
+    domains: Mutex<HashMap<String, DomainLimit>>,
+}
+
+impl FetchLimiter {
+    pub fn new(config: FetchLimitConfig) -> Self {
+        let slots = Arc::new(Semaphore::new(config.max_concurrency.max(1)));
+        Self {
+            config,
+            slots,
+            domains: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn burst(&self) -> f64 {
+        f64::from(self.config.per_domain_burst.max(1))
+    }
+
+    /// Returns the concurrency slots of the domain.
+    fn domain_slots(&self, domain: &str) -> Arc<Semaphore> {
+        let mut domains = self
+            .domains
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        domains
+            .entry(domain.to_string())
+            .or_insert_with(|| DomainLimit {
+                bucket: TokenBucket {
+                    tokens: self.burst(),
+                    updated: Instant::now(),
+                },
+                slots: Arc::new(Semaphore::new(self.config.per_domain_concurrency.max(1))),
+            })
+            .slots
+            .clone()
+    }
+
+    /// Takes a token of the domain and returns how long to wait before it may be used.
+    fn reserve(&self, domain: &str) -> Duration {
+        let rate = self.config.per_domain_rate;
+        if rate <= 0.0 {
+            return Duration::ZERO;
+        }
+        let burst = self.burst();
+        let mut domains = self
+            .domains
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let Some(limit) = domains.get_mut(domain) else {
+            return Duration::ZERO;
+        };
+        let bucket = &mut limit.bucket;
+        let now = Instant::now();
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * rate).min(burst) - 1.0;
+        bucket.updated = now;
+        if bucket.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-bucket.tokens / rate)
+        }
+    }
+
+    /// Fetches one page once its domain and the global limit allow it.
+    async fn fetch_one<F, Fut>(&self, url: String, fetch: &F) -> Result<String>
+    where
+        F: Fn(String) -> Fut,
+        Fut: Future<Output = Result<String>>,
+    {
+        let domain = Url::parse(&url)
+            .ok()
+            .and_then(|u| u.host_str().map(str::to_string))
+            .unwrap_or_default();
+
+        let _domain_slot = self.domain_slots(&domain).acquire_owned().await?;
+        let wait = self.reserve(&domain);
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+        // Waiting for the domain happens before a global slot is taken,
+        // so a throttled domain never blocks the others
+        let _slot = self.slots.clone().acquire_owned().await?;
+
+        let timeout = Duration::from_secs(self.config.timeout_secs.max(1));
+        tokio::time::timeout(timeout, fetch(url.clone()))
+            .await
+            .map_err(|_| anyhow!("Fetching {} timed out after {:?}", url, timeout))?
+    }
+
+    /// Fetches every url, returning the results in the order of `urls`.
+    pub async fn fetch_all<F, Fut>(&self, urls: Vec<String>, fetch: F) -> Vec<Result<String>>
+    where
+        F: Fn(String) -> Fut,
+        Fut: Future<Output = Result<String>>,
+    {
+        join_all(urls.into_iter().map(|url| self.fetch_one(url, &fetch))).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn config(rate: f64, timeout_secs: u64) -> FetchLimitConfig {
+        FetchLimitConfig {
+            max_concurrency: 8,
+            per_domain_concurrency: 1,
+            per_domain_rate: rate,
+            per_domain_burst: 1,
+            timeout_secs,
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_same_domain_is_throttled_while_domains_run_in_parallel() {
+        let limiter = FetchLimiter::new(config(2.0, 20));
+        let started = Instant::now();
+        let calls: Mutex<Vec<(String, Duration)>> = Mutex::new(Vec::new());
+
+        let urls = vec![
+            "https://a.example/1".to_string(),
+            "https://a.example/2".to_string(),
+            "https://a.example/3".to_string(),
+            "https://b.example/1".to_string(),
+            "https://c.example/1".to_string(),
+        ];
+        let results = limiter
+            .fetch_all(urls, |url| {
+                let host = Url::parse(&url).unwrap().host_str().unwrap().to_string();
+                calls.lock().unwrap().push((host, started.elapsed()));
+                async move {
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    Ok(url)
+                }
+            })
+            .await;
+
+        assert!(results.iter().all(|r| r.is_ok()));
+        assert_eq!(results[3].as_deref().unwrap(), "https://b.example/1");
+
+        let calls = calls.lock().unwrap();
+        let starts = |host: &str| -> Vec<Duration> {
+            calls
+                .iter()
+                .filter(|(h, _)| h == host)
+                .map(|(_, at)| *at)
+                .collect()
+        };
+        // Two requests per second: a.example gets one every 500ms
+        let a = starts("a.example");
+        assert_eq!(a.len(), 3);
+        for pair in a.windows(2) {
+            assert!(
+                pair[1] - pair[0] >= Duration::from_millis(500),
+                "a.example was hit too often: {:?}",
+                a
+            );
+        }
+        // Other domains start right away instead of queueing behind a.example
+        assert!(starts("b.example")[0] < Duration::from_millis(10));
+        assert!(starts("c.example")[0] < Duration::from_millis(10));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_failed_and_slow_pages_keep_partial_results() {
+        let limiter = FetchLimiter::new(config(0.0, 1));
+        let calls = AtomicUsize::new(0);
+
+        let urls = vec![
+            "https://ok.example/".to_string(),
+            "https://broken.example/".to_string(),
+            "https://slow.example/".to_string(),
+        ];
+        let results = limiter
+            .fetch_all(urls, |url| {
+                calls.fetch_add(1, Ordering::SeqCst);
+                async move {
+                    if url.contains("broken") {
+                        return Err(anyhow!("connection reset"));
+                    }
+                    if url.contains("slow") {
+                        tokio::time::sleep(Duration::from_secs(5)).await;
+                    }
+                    Ok(format!("content of {}", url))
+                }
+            })
+            .await;
+
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        assert_eq!(
+            results[0].as_deref().unwrap(),
+            "content of https://ok.example/"
+        );
+        assert!(results[1].is_err());
+        assert!(results[2]
+            .as_ref()
+            .unwrap_err()
+            .to_string()
+            .contains("timed out"));
+    }
+}
@@ -1,6 +1,4 @@
-use std::cmp::max;
-
-use anyhow::{anyhow, Context, Result};
+use anyhow::{Context, Result};
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
@@ -8,9 +6,13 @@ use serde_json::{json, Value};
 use crate::http::client::HttpClient;
 use crate::http::types::HttpConfig;
 
-use super::search::{SearchParams, SearchPeriod, SearchProvider, SearchResult};
+use super::search::{
+    SearchHttpError, SearchParams, SearchPeriod, SearchProvider, SearchResult, SearchType,
+};
 
 const GOOGLE_API_URL: &str = "https://www.googleapis.com/customsearch/v1";
+/// The Custom Search API returns at most 10 items per request.
+const GOOGLE_MAX_PAGE_SIZE: u32 = 10;
 
 #[derive(Debug, Serialize)]
 struct GoogleSearchRequest<'a> {
@@ -25,6 +27,8 @@ struct GoogleSearchRequest<'a> {
     hl: Option<String>, // language
     #[serde(skip_serializing_if = "Option::is_none")]
     sort: Option<String>, // time_range, e.g., "date:r:20230101:20231231"
+    #[serde(rename = "searchType", skip_serializing_if = "Option::is_none")]
+    search_type: Option<&'static str>, // "image" for image search, web otherwise
 }
 
 impl From<GoogleSearchRequest<'_>> for String {
@@ -35,10 +39,45 @@ impl From<GoogleSearchRequest<'_>> for String {
 }
 
 #[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
 struct GoogleItem {
     title: String,
     link: String,
-    snippet: String,
+    #[serde(default)]
+    snippet: Option<String>,
+    display_link: Option<String>,
+    image: Option<GoogleImage>,
+}
+
+/// Image metadata returned when `searchType=image` is requested.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GoogleImage {
+    context_link: Option<String>,
+    thumbnail_link: Option<String>,
+}
+
+impl From<GoogleItem> for SearchResult {
+    fn from(item: GoogleItem) -> Self {
+        match item.image {
+            // For image results `link` is the image itself, the page lives in `contextLink`.
+            Some(image) => SearchResult {
+                title: item.title,
+                url: image.context_link.unwrap_or_else(|| item.link.clone()),
+                snippet: item.snippet,
+                image_url: Some(item.link),
+                thumbnail: image.thumbnail_link,
+                source: item.display_link,
+                ..Default::default()
+            },
+            None => SearchResult {
+                title: item.title,
+                url: item.link,
+                snippet: item.snippet,
+                ..Default::default()
+            },
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -61,13 +100,10 @@ impl GoogleSearch {
             http_client,
         })
     }
-}
-
-#[async_trait]
-impl SearchProvider for GoogleSearch {
-    async fn search(&self, params: &Value) -> Result<Vec<SearchResult>> {
-        let search_params = SearchParams::try_from(params)?;
 
+    /// Maps the common search params to the Custom Search query, including the
+    /// 1-based `start` index used for pagination.
+    fn build_request<'a>(&'a self, search_params: &'a SearchParams) -> GoogleSearchRequest<'a> {
         let sort_param = search_params.period.as_ref().map(|p| match p {
             SearchPeriod::Hour => "date:h".to_string(), // Google Custom Search API doesn't directly support "hour" for sorting, using a general date sort.
             SearchPeriod::Day => "date:d".to_string(),
@@ -76,20 +112,36 @@ impl SearchProvider for GoogleSearch {
             SearchPeriod::Year => "date:y".to_string(),
         });
 
-        let num = search_params.count.unwrap_or(10);
-        let start = search_params
-            .page
-            .and_then(|p| Some(max(0, p - 1) * num + 1));
+        let num = search_params
+            .page_size(GOOGLE_MAX_PAGE_SIZE)
+            .min(GOOGLE_MAX_PAGE_SIZE);
+        let offset = search_params.start_offset(num);
+        let start = (offset > 0).then_some(offset + 1);
+
+        // The Custom Search API has no news vertical.
+        let search_type = search_params
+            .search_type
+            .resolve_for("Google", &[SearchType::Images]);
 
-        let request_body = GoogleSearchRequest {
+        GoogleSearchRequest {
             q: &search_params.query,
             key: &self.api_key,
             cx: &self.cx,
-            num: search_params.count,
+            num: search_params.count.map(|_| num),
             start,
             hl: search_params.language.clone(),
             sort: sort_param,
-        };
+            search_type: (search_type == SearchType::Images).then_some("image"),
+        }
+    }
+}
+
+#[async_trait]
+impl SearchProvider for GoogleSearch {
+    async fn search(&self, params: &Value) -> Result<Vec<SearchResult>> {
+        let search_params = SearchParams::try_from(params)?;
+
+        let request_body = self.build_request(&search_params);
 
         // build request config
         let config = HttpConfig::get(GOOGLE_API_URL)
@@ -100,15 +152,7 @@ impl SearchProvider for GoogleSearch {
         let response = self.http_client.send_request(config).await?;
 
         if !response.is_success() {
-            return Err(anyhow!(
-                "Google Custom Search API request failed with status {}: {}",
-                response.status,
-                response.error.unwrap_or_else(|| response
-                    .body
-                    .as_deref()
-                    .unwrap_or("No error details")
-                    .to_string()),
-            ));
+            return Err(SearchHttpError::from_response("Google Custom Search", &response).into());
         }
 
         let body = response
@@ -122,12 +166,7 @@ impl SearchProvider for GoogleSearch {
             .items
             .unwrap_or_default()
             .into_iter()
-            .map(|item| SearchResult {
-                title: item.title,
-                url: item.link,
-                snippet: Some(item.snippet), // The snippet field in the Google API is of type String, converted to Option<String> here
-                ..Default::default()
-            })
+            .map(SearchResult::from)
             .collect();
 
         Ok(results)
@@ -141,6 +180,60 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn test_google_page_two_start_index() {
+        let client = GoogleSearch::new("key".to_string(), "cx".to_string(), None).unwrap();
+
+        let params =
+            SearchParams::try_from(&json!({"query": "rust", "count": 10, "page": 2})).unwrap();
+        assert_eq!(client.build_request(&params).start, Some(11));
+
+        let params =
+            SearchParams::try_from(&json!({"query": "rust", "count": 30, "offset": 20})).unwrap();
+        let request = client.build_request(&params);
+        assert_eq!(request.num, Some(10));
+        assert_eq!(request.start, Some(21));
+
+        let params = SearchParams::try_from(&json!({"query": "rust"})).unwrap();
+        assert_eq!(client.build_request(&params).start, None);
+    }
+
+    #[test]
+    fn test_google_image_search() {
+        let client = GoogleSearch::new("key".to_string(), "cx".to_string(), None).unwrap();
+        let params =
+            SearchParams::try_from(&json!({"query": "ferris", "search_type": "images"})).unwrap();
+        assert_eq!(client.build_request(&params).search_type, Some("image"));
+
+        // News is not supported and falls back to a web search.
+        let params =
+            SearchParams::try_from(&json!({"query": "rust", "search_type": "news"})).unwrap();
+        assert_eq!(client.build_request(&params).search_type, None);
+
+        let body = json!({
+            "items": [{
+                "title": "Ferris",
+                "link": "https://rustacean.net/assets/rustacean-flat-happy.png",
+                "displayLink": "rustacean.net",
+                "image": {
+                    "contextLink": "https://rustacean.net/",
+                    "thumbnailLink": "https://encrypted-tbn0.gstatic.com/images?q=tbn:ferris"
+                }
+            }]
+        });
+        let response: GoogleSearchResponse = serde_json::from_value(body).unwrap();
+        let result = SearchResult::from(response.items.unwrap().remove(0));
+        assert_eq!(result.url, "https://rustacean.net/");
+        assert_eq!(
+            result.thumbnail.as_deref(),
+            Some("https://encrypted-tbn0.gstatic.com/images?q=tbn:ferris")
+        );
+        assert_eq!(
+            result.image_url.as_deref(),
+            Some("https://rustacean.net/assets/rustacean-flat-happy.png")
+        );
+    }
+
     #[tokio::test]
     async fn test_google_search() {
         let api_key = match env::var("GOOGLE_API_KEY") {
@@ -1,13 +1,26 @@
+pub mod bing;
 pub mod builtin;
+pub mod citation;
+pub mod ddg;
+pub mod fetch_limit;
 pub mod google;
+pub mod pagination;
+pub mod ranking;
 pub mod search;
 pub mod serper;
 pub mod tavily;
 
+pub use bing::BingSearch;
 pub use builtin::BuiltInSearch;
+pub use citation::CitationRegistry;
+pub use ddg::DuckDuckGoSearch;
+pub use fetch_limit::{FetchLimitConfig, FetchLimiter};
 pub use google::GoogleSearch;
+pub use pagination::SearchPaginator;
+pub use ranking::apply_recency_boost;
 pub use search::{
     SearchFactory, SearchParams, SearchPeriod, SearchProvider, SearchProviderName, SearchResult,
+    SearchType,
 };
 pub use serper::SerperSearch;
 pub use tavily::TavilySearch;
@@ -0,0 +1,183 @@
+use std::collections::HashSet;
+
+use serde_json::{json, Value};
+
+use super::search::SearchResult;
+
+/// Drives multi-page searches: builds per-page params, deduplicates results by URL
+/// across pages and stops once `max_results` or `max_pages` is reached.
+#[derive(Debug)]
+pub struct SearchPaginator {
+    next_page: u32,
+    last_page: u32,
+    page_size: u32,
+    max_results: usize,
+    exhausted: bool,
+    seen_urls: HashSet<String>,
+    results: Vec<SearchResult>,
+}
+
+impl SearchPaginator {
+    /// Creates a paginator starting at the 1-based `start_page`.
+    ///
+    /// A `max_pages` of 1 disables auto-pagination.
+    pub fn new(start_page: u32, page_size: u32, max_pages: u32, max_results: usize) -> Self {
+        let start_page = start_page.max(1);
+        Self {
+            next_page: start_page,
+            last_page: start_page.saturating_add(max_pages.max(1) - 1),
+            page_size: page_size.max(1),
+            max_results,
+            exhausted: false,
+            seen_urls: HashSet::new(),
+            results: Vec::with_capacity(max_results),
+        }
+    }
+
+    /// Returns the params for the next page, or `None` once pagination is done.
+    ///
+    /// `base` must be a JSON object holding the page independent params
+    /// (query, period, ...); `page` and `count` are filled in here.
+    pub fn next_params(&self, base: &Value) -> Option<Value> {
+        if self.exhausted || self.is_full() || self.next_page > self.last_page {
+            return None;
+        }
+        let mut params = base.clone();
+        if let Some(obj) = params.as_object_mut() {
+            obj.insert("page".to_string(), json!(self.next_page));
+            obj.insert("count".to_string(), json!(self.page_size));
+        }
+        Some(params)
+    }
+
+    /// Records that a page with `len` raw results was fetched.
+    ///
+    /// Returns `false` when the provider returned an empty page, which ends pagination.
+    pub fn begin_page(&mut self, len: usize) -> bool {
+        self.next_page += 1;
+        if len == 0 {
+            self.exhausted = true;
+        }
+        !self.exhausted
+    }
+
+    /// Adds a result unless its URL was already collected or the cap is reached.
+    pub fn push(&mut self, result: SearchResult) -> bool {
+        if self.is_full() || !self.seen_urls.insert(normalize_url(&result.url)) {
+            return false;
+        }
+        self.results.push(result);
+        true
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.results.len() >= self.max_results
+    }
+
+    pub fn into_results(self) -> Vec<SearchResult> {
+        self.results
+    }
+}
+
+/// Normalizes a URL for duplicate detection by ignoring fragments and trailing slashes.
+pub(crate) fn normalize_url(url: &str) -> String {
+    let without_fragment = url.split('#').next().unwrap_or(url);
+    without_fragment.trim_end_matches('/').to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use anyhow::Result;
+    use async_trait::async_trait;
+
+    use super::*;
+    use crate::search::{SearchParams, SearchProvider};
+
+    /// Returns `page_size` results per page; every page repeats the last URL of the previous one.
+    struct PagedProvider {
+        requested: Mutex<Vec<(u32, u32)>>,
+    }
+
+    #[async_trait]
+    impl SearchProvider for PagedProvider {
+        async fn search(&self, params: &Value) -> Result<Vec<SearchResult>> {
+            let params = SearchParams::try_from(params)?;
+            let offset = params.start_offset(10);
+            let size = params.page_size(10);
+            self.requested
+                .lock()
+                .unwrap()
+                .push((params.page.unwrap_or(1), offset));
+
+            let first = offset.saturating_sub(1);
+            Ok((first..first + size)
+                .map(|i| SearchResult {
+                    title: format!("result {}", i),
+                    url: format!("https://example.com/{}", i),
+                    ..Default::default()
+                })
+                .collect())
+        }
+    }
+
+    async fn run(provider: &PagedProvider, paginator: &mut SearchPaginator) {
+        let base = json!({"query": "rust"});
+        while let Some(params) = paginator.next_params(&base) {
+            let page = provider.search(&params).await.unwrap();
+            if !paginator.begin_page(page.len()) {
+                break;
+            }
+            for result in page {
+                paginator.push(result);
+                if paginator.is_full() {
+                    break;
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_auto_pagination_stops_at_cap() {
+        let provider = PagedProvider {
+            requested: Mutex::new(vec![]),
+        };
+        let mut paginator = SearchPaginator::new(1, 10, 5, 15);
+        run(&provider, &mut paginator).await;
+
+        let results = paginator.into_results();
+        assert_eq!(results.len(), 15);
+        // Two pages are enough to reach the cap, the remaining pages are never requested.
+        assert_eq!(*provider.requested.lock().unwrap(), vec![(1, 0), (2, 10)]);
+
+        let unique: HashSet<_> = results.iter().map(|r| r.url.clone()).collect();
+        assert_eq!(unique.len(), results.len());
+    }
+
+    #[tokio::test]
+    async fn test_single_page_when_auto_pagination_disabled() {
+        let provider = PagedProvider {
+            requested: Mutex::new(vec![]),
+        };
+        let mut paginator = SearchPaginator::new(2, 10, 1, 30);
+        run(&provider, &mut paginator).await;
+
+        assert_eq!(paginator.into_results().len(), 10);
+        assert_eq!(*provider.requested.lock().unwrap(), vec![(2, 10)]);
+    }
+
+    #[test]
+    fn test_push_dedupes_equivalent_urls() {
+        let mut paginator = SearchPaginator::new(1, 10, 1, 10);
+        let result = |url: &str| SearchResult {
+            url: url.to_string(),
+            ..Default::default()
+        };
+
+        assert!(paginator.push(result("https://example.com/a")));
+        assert!(!paginator.push(result("https://example.com/a/")));
+        assert!(!paginator.push(result("https://example.com/a#top")));
+        assert!(paginator.push(result("https://example.com/b")));
+    }
+}
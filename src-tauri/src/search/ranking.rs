@@ -0,0 +1,245 @@
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, TimeZone, Utc};
+
+use super::search::{SearchPeriod, SearchResult};
+
+/// Age horizon used when no explicit `SearchPeriod` is requested.
+const DEFAULT_RECENCY_HORIZON_DAYS: i64 = 365;
+
+/// Date layouts commonly seen in `publish_date` values returned by providers.
+const DATE_FORMATS: &[&str] = &[
+    "%Y-%m-%d",
+    "%Y/%m/%d",
+    "%Y.%m.%d",
+    "%b %d, %Y",
+    "%B %d, %Y",
+    "%d %b %Y",
+    "%d %B %Y",
+    "%Y年%m月%d日",
+];
+
+const DATETIME_FORMATS: &[&str] = &["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S", "%Y/%m/%d %H:%M"];
+
+/// Parses a provider supplied publish date into a UTC timestamp.
+///
+/// Supports RFC 3339 / RFC 2822 timestamps, a set of common absolute date layouts
+/// and relative expressions such as `3 days ago`, `1 hour ago` or `2 天前`.
+/// Returns `None` when the value can not be interpreted.
+pub fn parse_publish_date(raw: &str, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let value = raw.trim();
+    if value.is_empty() {
+        return None;
+    }
+
+    if let Ok(dt) = DateTime::parse_from_rfc3339(value) {
+        return Some(dt.with_timezone(&Utc));
+    }
+    if let Ok(dt) = DateTime::parse_from_rfc2822(value) {
+        return Some(dt.with_timezone(&Utc));
+    }
+    for fmt in DATETIME_FORMATS {
+        if let Ok(dt) = NaiveDateTime::parse_from_str(value, fmt) {
+            return Some(Utc.from_utc_datetime(&dt));
+        }
+    }
+    for fmt in DATE_FORMATS {
+        if let Ok(date) = NaiveDate::parse_from_str(value, fmt) {
+            return date
+                .and_hms_opt(0, 0, 0)
+                .map(|dt| Utc.from_utc_datetime(&dt));
+        }
+    }
+
+    parse_relative_date(value, now)
+}
+
+/// Parses relative expressions like `5 hours ago` or `3 天前`.
+fn parse_relative_date(value: &str, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let lower = value.to_lowercase();
+    let digits: String = lower.chars().take_while(|c| c.is_ascii_digit()).collect();
+    let amount: i64 = digits.parse().ok()?;
+    let unit = lower[digits.len()..].trim();
+
+    let duration = if unit.starts_with("min") || unit.starts_with("分钟") {
+        Duration::minutes(amount)
+    } else if unit.starts_with("hour") || unit.starts_with("小时") {
+        Duration::hours(amount)
+    } else if unit.starts_with("day") || unit.starts_with('天') {
+        Duration::days(amount)
+    } else if unit.starts_with("week") || unit.starts_with('周') {
+        Duration::weeks(amount)
+    } else if unit.starts_with("month") || unit.starts_with("个月") {
+        Duration::days(amount * 30)
+    } else if unit.starts_with("year") || unit.starts_with('年') {
+        Duration::days(amount * 365)
+    } else {
+        return None;
+    };
+
+    Some(now - duration)
+}
+
+/// Returns the age window used to normalize recency scores for a period.
+fn horizon_for(period: Option<&SearchPeriod>) -> Duration {
+    match period {
+        Some(SearchPeriod::Hour) => Duration::hours(1),
+        Some(SearchPeriod::Day) => Duration::days(1),
+        Some(SearchPeriod::Week) => Duration::weeks(1),
+        Some(SearchPeriod::Month) => Duration::days(30),
+        Some(SearchPeriod::Year) => Duration::days(365),
+        None => Duration::days(DEFAULT_RECENCY_HORIZON_DAYS),
+    }
+}
+
+/// Re-ranks search results so that newer results rise to the top.
+///
+/// Each dated result receives a blended score of its original position and its
+/// freshness, where `weight` (clamped to `0.0..=1.0`) controls how much freshness
+/// matters. Results without a parseable `publish_date` keep their original slot;
+/// only dated results are reordered among the slots they already occupy.
+///
+/// A `weight` of `0.0` leaves the list untouched.
+pub fn apply_recency_boost(
+    results: &mut [SearchResult],
+    weight: f32,
+    period: Option<&SearchPeriod>,
+    now: DateTime<Utc>,
+) {
+    let weight = weight.clamp(0.0, 1.0) as f64;
+    if weight <= 0.0 || results.len() < 2 {
+        return;
+    }
+
+    let total = results.len() as f64;
+    let horizon_secs = horizon_for(period).num_seconds().max(1) as f64;
+
+    // (slot index, blended score) for every result with a usable date
+    let mut dated: Vec<(usize, f64)> = results
+        .iter()
+        .enumerate()
+        .filter_map(|(index, result)| {
+            let published = parse_publish_date(result.publish_date.as_deref()?, now)?;
+            let age_secs = (now - published).num_seconds().max(0) as f64;
+            let freshness = (-age_secs / horizon_secs).exp();
+            let positional = 1.0 - index as f64 / total;
+            Some((index, (1.0 - weight) * positional + weight * freshness))
+        })
+        .collect();
+
+    if dated.len() < 2 {
+        return;
+    }
+
+    let slots: Vec<usize> = dated.iter().map(|(index, _)| *index).collect();
+    dated.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+
+    let original = results.to_vec();
+    for (slot, (source, _)) in slots.into_iter().zip(dated) {
+        results[slot] = original[source].clone();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(url: &str, date: Option<&str>) -> SearchResult {
+        SearchResult {
+            title: url.to_string(),
+            url: url.to_string(),
+            publish_date: date.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    fn urls(results: &[SearchResult]) -> Vec<&str> {
+        results.iter().map(|r| r.url.as_str()).collect()
+    }
+
+    fn fixed_now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 6, 1, 12, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_parse_publish_date_formats() {
+        let now = fixed_now();
+        assert_eq!(
+            parse_publish_date("2024-05-30", now),
+            Some(Utc.with_ymd_and_hms(2024, 5, 30, 0, 0, 0).unwrap())
+        );
+        assert_eq!(
+            parse_publish_date("May 30, 2024", now),
+            Some(Utc.with_ymd_and_hms(2024, 5, 30, 0, 0, 0).unwrap())
+        );
+        assert_eq!(
+            parse_publish_date("2024-05-30T08:00:00Z", now),
+            Some(Utc.with_ymd_and_hms(2024, 5, 30, 8, 0, 0).unwrap())
+        );
+        assert_eq!(
+            parse_publish_date("3 days ago", now),
+            Some(now - Duration::days(3))
+        );
+        assert_eq!(
+            parse_publish_date("2 天前", now),
+            Some(now - Duration::days(2))
+        );
+        assert_eq!(parse_publish_date("sometime", now), None);
+    }
+
+    #[test]
+    fn test_recency_boost_promotes_newer_results() {
+        let now = fixed_now();
+        let mut results = vec![
+            result("https://old.example", Some("2021-01-01")),
+            result("https://mid.example", Some("2024-01-01")),
+            result("https://new.example", Some("2 days ago")),
+        ];
+
+        apply_recency_boost(&mut results, 0.8, None, now);
+
+        assert_eq!(
+            urls(&results),
+            vec![
+                "https://new.example",
+                "https://mid.example",
+                "https://old.example"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_recency_boost_keeps_undated_slots() {
+        let now = fixed_now();
+        let mut results = vec![
+            result("https://old.example", Some("2020-01-01")),
+            result("https://undated.example", None),
+            result("https://new.example", Some("1 hour ago")),
+        ];
+
+        apply_recency_boost(&mut results, 1.0, Some(&SearchPeriod::Year), now);
+
+        assert_eq!(
+            urls(&results),
+            vec![
+                "https://new.example",
+                "https://undated.example",
+                "https://old.example"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_zero_weight_preserves_order() {
+        let now = fixed_now();
+        let mut results = vec![
+            result("https://old.example", Some("2020-01-01")),
+            result("https://new.example", Some("1 hour ago")),
+        ];
+
+        apply_recency_boost(&mut results, 0.0, None, now);
+
+        assert_eq!(
+            urls(&results),
+            vec!["https://old.example", "https://new.example"]
+        );
+    }
+}
@@ -1,11 +1,16 @@
-use std::{fmt::Display, str::FromStr};
+use std::{fmt::Display, str::FromStr, time::Duration};
 
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use thiserror::Error;
 
-use crate::search::{BuiltInSearch, GoogleSearch, SerperSearch, TavilySearch};
+use crate::http::types::HttpResponse;
+use crate::libs::retry::is_retryable_status;
+use crate::search::{
+    BingSearch, BuiltInSearch, DuckDuckGoSearch, GoogleSearch, SerperSearch, TavilySearch,
+};
 
 /// Defines a single search result item.
 /// The `skip_serializing_if` attribute ensures that `Option` fields
@@ -26,6 +31,14 @@ pub struct SearchResult {
     pub publish_date: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none", skip_deserializing)]
     pub score: Option<f32>,
+    /// Direct link to the image, only set for image searches.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thumbnail: Option<String>,
+    /// Publisher of a news article or host of an image.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
 }
 
 impl Display for SearchResult {
@@ -60,6 +73,15 @@ impl Display for SearchResult {
         if let Some(pb) = self.publish_date.as_ref() {
             write!(f, "<publish_date>{}</publish_date>\n", pb)?;
         }
+        if let Some(s) = self.source.as_ref() {
+            write!(f, "<source>{}</source>\n", s)?;
+        }
+        if let Some(i) = self.image_url.as_ref() {
+            write!(f, "<image_url>{}</image_url>\n", i)?;
+        }
+        if let Some(t) = self.thumbnail.as_ref() {
+            write!(f, "<thumbnail>{}</thumbnail>\n", t)?;
+        }
         if let Some(s) = self.score.as_ref() {
             write!(f, "<score>{}</score>\n", s)?;
         }
@@ -82,6 +104,10 @@ pub enum SearchProviderName {
     Google,
     Serper,
     Tavily,
+    /// Bing through the official Web Search API instead of the scraper.
+    BingApi,
+    /// DuckDuckGo through its html endpoint instead of the scraper.
+    DuckDuckGoLite,
 }
 
 impl Display for SearchProviderName {
@@ -95,6 +121,8 @@ impl Display for SearchProviderName {
             Self::Google => write!(f, "google"),
             Self::Serper => write!(f, "serper"),
             Self::Tavily => write!(f, "tavily"),
+            Self::BingApi => write!(f, "bing_api"),
+            Self::DuckDuckGoLite => write!(f, "duckduckgo_lite"),
         }
     }
 }
@@ -126,6 +154,8 @@ impl TryFrom<&str> for SearchProviderName {
             "tavily" => Ok(Self::Tavily),
             "google" => Ok(Self::Google),
             "serper" => Ok(Self::Serper),
+            "bing_api" => Ok(Self::BingApi),
+            "duckduckgo_lite" => Ok(Self::DuckDuckGoLite),
             _ => Err(format!("Invalid search provider: {}", provider)),
         }
     }
@@ -150,6 +180,32 @@ pub enum SearchPeriod {
     Year,
 }
 
+/// Defines the vertical a search query targets.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum SearchType {
+    #[default]
+    Web,
+    News,
+    Images,
+}
+
+impl SearchType {
+    /// Returns the requested type if `supported` contains it, otherwise logs a
+    /// warning and falls back to a plain web search.
+    pub fn resolve_for(self, provider: &str, supported: &[SearchType]) -> SearchType {
+        if self == SearchType::Web || supported.contains(&self) {
+            return self;
+        }
+        log::warn!(
+            "{} does not support {:?} search, falling back to web search",
+            provider,
+            self
+        );
+        SearchType::Web
+    }
+}
+
 /// Defines the parameters for a search query.
 /// This struct is deserialized from a `serde_json::Value` for flexibility,
 /// especially for AI tool use cases.
@@ -163,7 +219,39 @@ pub struct SearchParams {
     pub language: Option<String>,
     /// The time period for the search, e.g., "week", "month".
     pub period: Option<SearchPeriod>,
+    /// The 1-based page number, sized by `count`.
     pub page: Option<u32>,
+    /// The zero-based index of the first result; takes precedence over `page`.
+    pub offset: Option<u32>,
+    /// The search vertical, defaults to a web search.
+    #[serde(default)]
+    pub search_type: SearchType,
+    /// Whether the page behind each result is fetched into `content`,
+    /// only honored by the built-in providers.
+    #[serde(default)]
+    pub fetch_content: bool,
+}
+
+impl SearchParams {
+    /// Returns the requested page size, falling back to `default` when unset.
+    pub fn page_size(&self, default: u32) -> u32 {
+        self.count.unwrap_or(default).max(1)
+    }
+
+    /// Returns the zero-based offset of the first requested result.
+    pub fn start_offset(&self, default_page_size: u32) -> u32 {
+        self.offset.unwrap_or_else(|| {
+            self.page
+                .unwrap_or(1)
+                .saturating_sub(1)
+                .saturating_mul(self.page_size(default_page_size))
+        })
+    }
+
+    /// Returns the 1-based page number that contains `start_offset`.
+    pub fn page_number(&self, default_page_size: u32) -> u32 {
+        self.start_offset(default_page_size) / self.page_size(default_page_size) + 1
+    }
 }
 
 impl TryFrom<&Value> for SearchParams {
@@ -192,21 +280,159 @@ pub trait SearchProvider {
     async fn search(&self, params: &Value) -> Result<Vec<SearchResult>>;
 }
 
+/// A non-success HTTP response returned by a search provider API.
+///
+/// Providers return this error (wrapped in `anyhow::Error`) so that the shared
+/// retry wrapper can tell transient failures apart from permanent ones.
+#[derive(Debug, Error)]
+#[error("{provider} API request failed with status {status}: {message}")]
+pub struct SearchHttpError {
+    pub provider: String,
+    pub status: u16,
+    /// Delay requested by the server through the `Retry-After` header.
+    pub retry_after: Option<Duration>,
+    pub message: String,
+}
+
+impl SearchHttpError {
+    /// Builds the error from a failed provider response.
+    pub fn from_response(provider: &str, response: &HttpResponse) -> Self {
+        let message = response
+            .error
+            .clone()
+            .or_else(|| response.body.clone())
+            .filter(|m| !m.is_empty())
+            .unwrap_or_else(|| "No error details".to_string());
+        let retry_after = response
+            .headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case("retry-after"))
+            .and_then(|(_, v)| parse_retry_after(v));
+
+        Self {
+            provider: provider.to_string(),
+            status: response.status,
+            retry_after,
+            message,
+        }
+    }
+
+    /// Timeouts, rate limits and server errors are worth retrying,
+    /// everything else (auth, validation, not found) fails fast.
+    pub fn is_retryable(&self) -> bool {
+        is_retryable_status(self.status)
+    }
+}
+
+/// Parses a `Retry-After` header given either as delta-seconds or as an HTTP date.
+fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    chrono::DateTime::parse_from_rfc2822(value).ok().map(|at| {
+        (at.with_timezone(&chrono::Utc) - chrono::Utc::now())
+            .to_std()
+            .unwrap_or_default()
+    })
+}
+
+/// Retry policy shared by all search providers.
+#[derive(Debug, Clone)]
+pub struct SearchRetryPolicy {
+    /// Maximum number of retries after the first attempt.
+    pub max_retries: u32,
+    /// Backoff before the first retry, doubled on every further retry.
+    pub initial_backoff: Duration,
+    /// Upper bound for the exponential backoff.
+    pub max_backoff: Duration,
+    /// Upper bound for a server supplied `Retry-After` delay.
+    pub max_retry_after: Duration,
+}
+
+impl Default for SearchRetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 2,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(8),
+            max_retry_after: Duration::from_secs(30),
+        }
+    }
+}
+
+impl SearchRetryPolicy {
+    fn backoff(&self, retry: u32) -> Duration {
+        self.initial_backoff
+            .saturating_mul(2u32.saturating_pow(retry.saturating_sub(1)))
+            .min(self.max_backoff)
+    }
+}
+
+/// Runs a provider search, retrying transient HTTP failures with exponential backoff.
+///
+/// A `Retry-After` header on the failed response takes precedence over the computed
+/// backoff. Errors that are not a retryable `SearchHttpError` are returned immediately;
+/// transport level retries are already handled by `HttpClient`.
+pub async fn search_with_retry<P>(
+    provider: &P,
+    params: &Value,
+    policy: &SearchRetryPolicy,
+) -> Result<Vec<SearchResult>>
+where
+    P: SearchProvider + Sync + ?Sized,
+{
+    let mut retry = 0;
+    loop {
+        let error = match provider.search(params).await {
+            Ok(results) => return Ok(results),
+            Err(e) => e,
+        };
+
+        let Some(http_error) = error.downcast_ref::<SearchHttpError>() else {
+            return Err(error);
+        };
+        if !http_error.is_retryable() || retry >= policy.max_retries {
+            return Err(error);
+        }
+
+        retry += 1;
+        let delay = http_error
+            .retry_after
+            .map(|d| d.min(policy.max_retry_after))
+            .unwrap_or_else(|| policy.backoff(retry));
+        log::warn!(
+            "{} search returned status {}, retrying in {:?} ({}/{})",
+            http_error.provider,
+            http_error.status,
+            delay,
+            retry,
+            policy.max_retries
+        );
+        tokio::time::sleep(delay).await;
+    }
+}
+
 pub enum SearchFactory {
     Google(GoogleSearch),
     Serper(SerperSearch),
     Tavily(TavilySearch),
+    Bing(BingSearch),
+    DuckDuckGo(DuckDuckGoSearch),
     Builtin(BuiltInSearch),
 }
 
 #[async_trait]
 impl SearchProvider for SearchFactory {
     async fn search(&self, params: &Value) -> Result<Vec<SearchResult>> {
+        let policy = SearchRetryPolicy::default();
         let result = match self {
-            Self::Google(gs) => gs.search(params).await,
-            Self::Serper(sp) => sp.search(params).await,
-            Self::Tavily(t) => t.search(params).await,
-            Self::Builtin(b) => b.search(params).await,
+            Self::Google(gs) => search_with_retry(gs, params, &policy).await,
+            Self::Serper(sp) => search_with_retry(sp, params, &policy).await,
+            Self::Tavily(t) => search_with_retry(t, params, &policy).await,
+            Self::Bing(b) => search_with_retry(b, params, &policy).await,
+            Self::DuckDuckGo(d) => search_with_retry(d, params, &policy).await,
+            Self::Builtin(b) => search_with_retry(b, params, &policy).await,
         };
         result
     }
@@ -218,7 +444,124 @@ impl Display for SearchFactory {
             Self::Google(_) => write!(f, "Google"),
             Self::Serper(_) => write!(f, "Serper"),
             Self::Tavily(_) => write!(f, "Tavily"),
+            Self::Bing(_) => write!(f, "Bing"),
+            Self::DuckDuckGo(_) => write!(f, "DuckDuckGo"),
             Self::Builtin(b) => write!(f, "Builtin({})", b.provider),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+
+    use serde_json::json;
+
+    use super::*;
+
+    /// A provider that replays a scripted list of HTTP statuses.
+    struct MockProvider {
+        statuses: Mutex<Vec<u16>>,
+        calls: AtomicUsize,
+    }
+
+    impl MockProvider {
+        fn new(statuses: Vec<u16>) -> Self {
+            Self {
+                statuses: Mutex::new(statuses),
+                calls: AtomicUsize::new(0),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl SearchProvider for MockProvider {
+        async fn search(&self, _params: &Value) -> Result<Vec<SearchResult>> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            let status = self.statuses.lock().unwrap().remove(0);
+            if status == 200 {
+                return Ok(vec![SearchResult {
+                    title: "ok".to_string(),
+                    url: "https://example.com".to_string(),
+                    ..Default::default()
+                }]);
+            }
+            let response = HttpResponse {
+                status,
+                headers: HashMap::from([("retry-after".to_string(), "0".to_string())]),
+                body: Some("error".to_string()),
+                error: None,
+                progress: None,
+            };
+            Err(SearchHttpError::from_response("Mock", &response).into())
+        }
+    }
+
+    fn fast_policy() -> SearchRetryPolicy {
+        SearchRetryPolicy {
+            max_retries: 2,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(5),
+            max_retry_after: Duration::from_millis(5),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retry_on_429_then_success() {
+        let provider = MockProvider::new(vec![429, 200]);
+        let results = search_with_retry(&provider, &json!({"query": "rust"}), &fast_policy())
+            .await
+            .expect("search should succeed after retry");
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(provider.calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_unauthorized_fails_fast() {
+        let provider = MockProvider::new(vec![401, 200]);
+        let error = search_with_retry(&provider, &json!({"query": "rust"}), &fast_policy())
+            .await
+            .expect_err("401 must not be retried");
+
+        assert_eq!(provider.calls.load(Ordering::SeqCst), 1);
+        assert_eq!(
+            error.downcast_ref::<SearchHttpError>().map(|e| e.status),
+            Some(401)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_retries_are_bounded() {
+        let provider = MockProvider::new(vec![503, 503, 503, 200]);
+        let result = search_with_retry(&provider, &json!({"query": "rust"}), &fast_policy()).await;
+
+        assert!(result.is_err());
+        assert_eq!(provider.calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_page_two_offset() {
+        let params =
+            SearchParams::try_from(&json!({"query": "rust", "count": 10, "page": 2})).unwrap();
+        assert_eq!(params.start_offset(10), 10);
+        assert_eq!(params.page_number(10), 2);
+
+        let params =
+            SearchParams::try_from(&json!({"query": "rust", "count": 5, "offset": 12})).unwrap();
+        assert_eq!(params.start_offset(10), 12);
+        assert_eq!(params.page_number(10), 3);
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        assert_eq!(parse_retry_after("7"), Some(Duration::from_secs(7)));
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(parse_retry_after("soon"), None);
+    }
+}
@@ -1,12 +1,17 @@
 use crate::http::client::HttpClient;
 use crate::http::types::HttpConfig;
-use crate::search::search::{SearchParams, SearchPeriod, SearchProvider, SearchResult};
-use anyhow::{anyhow, Context, Result};
+use crate::search::search::{
+    SearchHttpError, SearchParams, SearchPeriod, SearchProvider, SearchResult, SearchType,
+};
+use anyhow::{Context, Result};
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
 const SERPER_API_URL: &str = "https://google.serper.dev/search";
+const SERPER_NEWS_API_URL: &str = "https://google.serper.dev/news";
+const SERPER_IMAGES_API_URL: &str = "https://google.serper.dev/images";
+const SERPER_DEFAULT_PAGE_SIZE: u32 = 10;
 
 /// Represents the structure of the request body for the Serper API.
 #[derive(Debug, Serialize)]
@@ -41,7 +46,7 @@ impl<'a> From<&'a SearchParams> for SerperSearchRequest {
 
         // Serper's page parameter is 1-based and defaults to 1.
         // We only send it if it's greater than 1.
-        let page = params.count.filter(|&p| p > 1);
+        let page = Some(params.page_number(SERPER_DEFAULT_PAGE_SIZE)).filter(|&p| p > 1);
 
         SerperSearchRequest {
             q: params.query.clone(),
@@ -54,9 +59,13 @@ impl<'a> From<&'a SearchParams> for SerperSearchRequest {
 }
 
 /// Represents the overall structure of the Serper API response.
-#[derive(Debug, Deserialize)]
+/// Only the list matching the requested endpoint is present.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
 struct SerperSearchResponse {
     organic: Vec<SerperOrganicResult>,
+    news: Vec<SerperNewsResult>,
+    images: Vec<SerperImageResult>,
 }
 
 /// Represents a single organic search result from the Serper API.
@@ -68,6 +77,75 @@ struct SerperOrganicResult {
     date: Option<String>,
 }
 
+/// Represents a single result from the Serper news endpoint.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SerperNewsResult {
+    title: String,
+    link: String,
+    snippet: Option<String>,
+    date: Option<String>,
+    source: Option<String>,
+    image_url: Option<String>,
+}
+
+/// Represents a single result from the Serper images endpoint.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SerperImageResult {
+    title: String,
+    image_url: String,
+    thumbnail_url: Option<String>,
+    link: String,
+    source: Option<String>,
+    domain: Option<String>,
+}
+
+impl SerperSearchResponse {
+    /// Maps the endpoint specific results to the common `SearchResult` format.
+    fn into_results(self, search_type: SearchType) -> Vec<SearchResult> {
+        match search_type {
+            SearchType::Web => self
+                .organic
+                .into_iter()
+                .map(|item| SearchResult {
+                    title: item.title,
+                    url: item.link,
+                    snippet: Some(item.snippet),
+                    // The `date` field from Serper can be used as `content`.
+                    publish_date: item.date,
+                    ..Default::default()
+                })
+                .collect(),
+            SearchType::News => self
+                .news
+                .into_iter()
+                .map(|item| SearchResult {
+                    title: item.title,
+                    url: item.link,
+                    snippet: item.snippet,
+                    publish_date: item.date,
+                    source: item.source,
+                    thumbnail: item.image_url,
+                    ..Default::default()
+                })
+                .collect(),
+            SearchType::Images => self
+                .images
+                .into_iter()
+                .map(|item| SearchResult {
+                    title: item.title,
+                    url: item.link,
+                    image_url: Some(item.image_url),
+                    thumbnail: item.thumbnail_url,
+                    source: item.source.or(item.domain),
+                    ..Default::default()
+                })
+                .collect(),
+        }
+    }
+}
+
 /// The Serper search provider.
 pub struct SerperSearch {
     api_key: String,
@@ -101,7 +179,13 @@ impl SearchProvider for SerperSearch {
 
         // 3. Perform the HTTP request using the instance's client.
         // Build the request configuration as per the custom HttpClient's design.
-        let config = HttpConfig::post(SERPER_API_URL, request_body)
+        let search_type = search_params.search_type;
+        let url = match search_type {
+            SearchType::Web => SERPER_API_URL,
+            SearchType::News => SERPER_NEWS_API_URL,
+            SearchType::Images => SERPER_IMAGES_API_URL,
+        };
+        let config = HttpConfig::post(url, request_body)
             .header("X-API-KEY", &self.api_key)
             .header("Content-Type", "application/json");
 
@@ -109,15 +193,7 @@ impl SearchProvider for SerperSearch {
         let response = self.http_client.send_request(config).await?;
 
         if !response.is_success() {
-            return Err(anyhow!(
-                "Serper API request failed with status {}: {}",
-                response.status,
-                response.error.unwrap_or_else(|| response
-                    .body
-                    .as_deref()
-                    .unwrap_or("No error details")
-                    .to_string()),
-            ));
+            return Err(SearchHttpError::from_response("Serper", &response).into());
         }
 
         // 4. Deserialize the response and map it to our common SearchResult format.
@@ -128,18 +204,7 @@ impl SearchProvider for SerperSearch {
         let serper_response: SerperSearchResponse = serde_json::from_str(&body)
             .context("Failed to deserialize Serper response from JSON body")?;
 
-        let results = serper_response
-            .organic
-            .into_iter()
-            .map(|item| SearchResult {
-                title: item.title,
-                url: item.link,
-                snippet: Some(item.snippet),
-                // The `date` field from Serper can be used as `content`.
-                publish_date: item.date,
-                ..Default::default()
-            })
-            .collect();
+        let results = serper_response.into_results(search_type);
 
         Ok(results)
     }
@@ -152,6 +217,67 @@ mod tests {
     use super::*;
     use std::env;
 
+    #[test]
+    fn test_serper_page_mapping() {
+        let params =
+            SearchParams::try_from(&json!({"query": "rust", "count": 10, "page": 2})).unwrap();
+        let request = SerperSearchRequest::from(&params);
+        assert_eq!(request.page, Some(2));
+        assert_eq!(request.num, Some(10));
+
+        let params = SearchParams::try_from(&json!({"query": "rust", "count": 10})).unwrap();
+        assert_eq!(SerperSearchRequest::from(&params).page, None);
+    }
+
+    #[test]
+    fn test_serper_news_results_are_dated() {
+        let body = json!({
+            "news": [{
+                "title": "Rust 1.80 released",
+                "link": "https://blog.rust-lang.org/2024/07/25/Rust-1.80.0.html",
+                "snippet": "The Rust team is happy to announce a new version.",
+                "date": "2 days ago",
+                "source": "Rust Blog",
+                "imageUrl": "https://example.com/rust.png",
+                "position": 1
+            }]
+        });
+        let response: SerperSearchResponse = serde_json::from_value(body).unwrap();
+        let results = response.into_results(SearchType::News);
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].publish_date.as_deref(), Some("2 days ago"));
+        assert_eq!(results[0].source.as_deref(), Some("Rust Blog"));
+    }
+
+    #[test]
+    fn test_serper_image_results_have_thumbnails() {
+        let body = json!({
+            "images": [{
+                "title": "Ferris the crab",
+                "imageUrl": "https://rustacean.net/assets/rustacean-flat-happy.png",
+                "thumbnailUrl": "https://encrypted-tbn0.gstatic.com/images?q=tbn:ferris",
+                "source": "rustacean.net",
+                "domain": "rustacean.net",
+                "link": "https://rustacean.net/",
+                "position": 1
+            }]
+        });
+        let response: SerperSearchResponse = serde_json::from_value(body).unwrap();
+        let results = response.into_results(SearchType::Images);
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].url, "https://rustacean.net/");
+        assert_eq!(
+            results[0].image_url.as_deref(),
+            Some("https://rustacean.net/assets/rustacean-flat-happy.png")
+        );
+        assert_eq!(
+            results[0].thumbnail.as_deref(),
+            Some("https://encrypted-tbn0.gstatic.com/images?q=tbn:ferris")
+        );
+    }
+
     #[tokio::test]
     async fn test_serper_search() {
         // Read api key from SERPER_API_KEY environment variable.
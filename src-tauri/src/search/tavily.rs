@@ -1,13 +1,18 @@
-use anyhow::{anyhow, Context, Result};
+use anyhow::{Context, Result};
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
 use crate::http::{client::HttpClient, types::HttpConfig};
 
-use super::search::{SearchParams, SearchPeriod, SearchProvider, SearchResult};
+use super::search::{
+    SearchHttpError, SearchParams, SearchPeriod, SearchProvider, SearchResult, SearchType,
+};
 
 const TAVILY_API_URL: &str = "https://api.tavily.com/search";
+/// Tavily caps `max_results` at 20 and has no native pagination.
+const TAVILY_MAX_RESULTS: u32 = 20;
+const TAVILY_DEFAULT_PAGE_SIZE: u32 = 5;
 
 /// https://app.tavily.com/playground
 #[derive(Debug, Serialize)]
@@ -44,6 +49,8 @@ struct TavilyResult {
     url: String,
     content: String,
     score: Option<f32>,
+    /// Only returned for the `news` topic.
+    published_date: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -82,10 +89,32 @@ impl SearchProvider for TavilySearch {
             SearchPeriod::Year => "year".to_string(),
         });
 
+        // Tavily has no pagination, so emulate it by requesting everything up to the
+        // end of the requested page and skipping the leading offset.
+        let page_size = search_params.page_size(TAVILY_DEFAULT_PAGE_SIZE);
+        let offset = search_params.start_offset(TAVILY_DEFAULT_PAGE_SIZE);
+        if offset >= TAVILY_MAX_RESULTS {
+            return Ok(vec![]);
+        }
+        let max_results = if offset > 0 {
+            Some((offset + page_size).min(TAVILY_MAX_RESULTS) as usize)
+        } else {
+            search_params.count.map(|c| c as usize)
+        };
+
+        // Tavily supports a news topic but has no image vertical.
+        let topic = match search_params
+            .search_type
+            .resolve_for("Tavily", &[SearchType::News])
+        {
+            SearchType::News => "news",
+            _ => "general",
+        };
+
         let request_body = TavilySearchRequest {
             query: &search_params.query,
-            max_results: search_params.count.map(|c| c as usize),
-            topic: Some("general".to_string()), // 默认使用通用搜索
+            max_results,
+            topic: Some(topic.to_string()),
             search_depth: Some("basic".to_string()),
             include_answer: Some(false),
             include_raw_content: Some(true), // 添加此行以获取完整内容
@@ -102,13 +131,7 @@ impl SearchProvider for TavilySearch {
         let response = self.http_client.send_request(config).await?;
 
         if !response.is_success() {
-            let status = response.status;
-            let error_body = response.error.unwrap_or_default();
-            return Err(anyhow!(
-                "Tavily API request failed with status: {}, body: {}",
-                status,
-                error_body
-            ));
+            return Err(SearchHttpError::from_response("Tavily", &response).into());
         }
 
         let body = response
@@ -121,11 +144,13 @@ impl SearchProvider for TavilySearch {
         let results = serper_response
             .results
             .into_iter()
+            .skip(offset as usize)
             .map(|item| SearchResult {
                 title: item.title,
                 url: item.url,
                 content: Some(item.content),
                 score: item.score,
+                publish_date: item.published_date,
                 ..Default::default()
             })
             .collect();
@@ -60,6 +60,21 @@ pub fn get_app_handle() -> tauri::AppHandle<tauri::test::MockRuntime> {
     app.unwrap().handle().clone()
 }
 
+/// Serves `app` on a free local port in the background and returns its base URL.
+pub async fn spawn_mock_backend(app: axum::Router) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("bind mock backend");
+    let base_url = format!(
+        "http://{}",
+        listener.local_addr().expect("mock backend address")
+    );
+    tokio::spawn(async move {
+        let _ = axum::serve(listener, app).await;
+    });
+    base_url
+}
+
 // lazy_static! {
 //     pub static ref MOCK_APP_HANDLE: Arc<tauri::AppHandle<tauri::test::MockRuntime>> =
 //         Arc::new(get_app_handle());
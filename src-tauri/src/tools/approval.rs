@@ -0,0 +1,63 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tokio::sync::oneshot;
+
+struct PendingApproval<T> {
+    request: T,
+    responder: oneshot::Sender<bool>,
+}
+
+/// Tracks requests waiting for the user's approval until they are answered.
+///
+/// The request payload is what the frontend shows to the user, e.g. a gated shell
+/// command or a sampling request from an MCP server.
+pub struct ApprovalRegistry<T> {
+    pending: Mutex<HashMap<String, PendingApproval<T>>>,
+}
+
+impl<T> Default for ApprovalRegistry<T> {
+    fn default() -> Self {
+        Self {
+            pending: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl<T: Clone> ApprovalRegistry<T> {
+    /// Adds a request and returns the receiver that resolves with the user's decision.
+    pub(crate) fn register(&self, request_id: String, request: T) -> oneshot::Receiver<bool> {
+        let (responder, receiver) = oneshot::channel();
+        if let Ok(mut pending) = self.pending.lock() {
+            pending.insert(request_id, PendingApproval { request, responder });
+        }
+        receiver
+    }
+
+    /// Resolves a pending request, returns `false` if it is unknown or already resolved.
+    pub fn respond(&self, request_id: &str, approved: bool) -> bool {
+        let entry = self
+            .pending
+            .lock()
+            .ok()
+            .and_then(|mut pending| pending.remove(request_id));
+        match entry {
+            Some(entry) => entry.responder.send(approved).is_ok(),
+            None => false,
+        }
+    }
+
+    /// Lists the requests currently waiting for a decision.
+    pub fn pending(&self) -> Vec<T> {
+        self.pending
+            .lock()
+            .map(|pending| pending.values().map(|p| p.request.clone()).collect())
+            .unwrap_or_default()
+    }
+
+    /// Drops a request that is no longer waited for, e.g. after a timeout.
+    pub(crate) fn cancel(&self, request_id: &str) {
+        if let Ok(mut pending) = self.pending.lock() {
+            pending.remove(request_id);
+        }
+    }
+}
@@ -0,0 +1,540 @@
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use std::fmt;
+
+use crate::{
+    ai::traits::chat::MCPToolDeclaration,
+    tools::{
+        error::ToolError, NativeToolResult, ToolCallResult, ToolCategory, ToolDefinition,
+        ToolScope, TOOL_CALCULATE,
+    },
+};
+
+/// Maximum accepted expression length, keeps the recursive parser bounded.
+const MAX_EXPRESSION_LEN: usize = 1024;
+/// Maximum nesting depth of parentheses and function calls.
+const MAX_DEPTH: usize = 64;
+
+/// Errors produced while evaluating an expression.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CalcError {
+    Syntax(String),
+    DivisionByZero,
+    UnknownIdentifier(String),
+    InvalidArguments(String),
+    NotFinite,
+}
+
+impl fmt::Display for CalcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Syntax(detail) => write!(f, "syntax error: {}", detail),
+            Self::DivisionByZero => write!(f, "division by zero"),
+            Self::UnknownIdentifier(name) => write!(f, "unknown function or constant '{}'", name),
+            Self::InvalidArguments(detail) => write!(f, "invalid arguments: {}", detail),
+            Self::NotFinite => write!(f, "result is not a finite number"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Ident(String),
+    Op(char),
+    LParen,
+    RParen,
+    Comma,
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, CalcError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            c if c.is_whitespace() => i += 1,
+            '0'..='9' | '.' => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                    i += 1;
+                }
+                // Scientific notation, e.g. 1.5e-3
+                if i < chars.len() && (chars[i] == 'e' || chars[i] == 'E') {
+                    let mut j = i + 1;
+                    if j < chars.len() && (chars[j] == '+' || chars[j] == '-') {
+                        j += 1;
+                    }
+                    if j < chars.len() && chars[j].is_ascii_digit() {
+                        i = j;
+                        while i < chars.len() && chars[i].is_ascii_digit() {
+                            i += 1;
+                        }
+                    }
+                }
+                let literal: String = chars[start..i].iter().collect();
+                let number = literal
+                    .parse::<f64>()
+                    .map_err(|_| CalcError::Syntax(format!("invalid number '{}'", literal)))?;
+                tokens.push(Token::Number(number));
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+                tokens.push(Token::Ident(
+                    chars[start..i].iter().collect::<String>().to_lowercase(),
+                ));
+            }
+            '+' | '-' | '*' | '/' | '%' | '^' => {
+                // Treat `**` as power for Python style input.
+                if c == '*' && chars.get(i + 1) == Some(&'*') {
+                    tokens.push(Token::Op('^'));
+                    i += 2;
+                } else {
+                    tokens.push(Token::Op(c));
+                    i += 1;
+                }
+            }
+            '×' => {
+                tokens.push(Token::Op('*'));
+                i += 1;
+            }
+            '÷' => {
+                tokens.push(Token::Op('/'));
+                i += 1;
+            }
+            '(' => {
+                tokens.push(Token::LParen);
+                i += 1;
+            }
+            ')' => {
+                tokens.push(Token::RParen);
+                i += 1;
+            }
+            ',' => {
+                tokens.push(Token::Comma);
+                i += 1;
+            }
+            other => {
+                return Err(CalcError::Syntax(format!(
+                    "unexpected character '{}'",
+                    other
+                )))
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// A recursive descent parser that evaluates while parsing.
+///
+/// Grammar (lowest to highest precedence):
+/// `expr := term (('+' | '-') term)*`
+/// `term := unary (('*' | '/' | '%') unary)*`
+/// `unary := ('+' | '-') unary | power`
+/// `power := primary ('^' unary)?` (right associative)
+/// `primary := number | ident | ident '(' args ')' | '(' expr ')'`
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn enter(&mut self) -> Result<(), CalcError> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(CalcError::Syntax("expression is nested too deeply".into()));
+        }
+        Ok(())
+    }
+
+    fn expr(&mut self) -> Result<f64, CalcError> {
+        let mut value = self.term()?;
+        while let Some(Token::Op(op @ ('+' | '-'))) = self.peek().cloned() {
+            self.pos += 1;
+            let rhs = self.term()?;
+            value = if op == '+' { value + rhs } else { value - rhs };
+        }
+        Ok(value)
+    }
+
+    fn term(&mut self) -> Result<f64, CalcError> {
+        let mut value = self.unary()?;
+        while let Some(Token::Op(op @ ('*' | '/' | '%'))) = self.peek().cloned() {
+            self.pos += 1;
+            let rhs = self.unary()?;
+            value = match op {
+                '*' => value * rhs,
+                _ if rhs == 0.0 => return Err(CalcError::DivisionByZero),
+                '/' => value / rhs,
+                _ => value % rhs,
+            };
+        }
+        Ok(value)
+    }
+
+    fn unary(&mut self) -> Result<f64, CalcError> {
+        match self.peek() {
+            Some(Token::Op('-')) => {
+                self.pos += 1;
+                self.enter()?;
+                let value = -self.unary()?;
+                self.depth -= 1;
+                Ok(value)
+            }
+            Some(Token::Op('+')) => {
+                self.pos += 1;
+                self.unary()
+            }
+            _ => self.power(),
+        }
+    }
+
+    fn power(&mut self) -> Result<f64, CalcError> {
+        let base = self.primary()?;
+        if let Some(Token::Op('^')) = self.peek() {
+            self.pos += 1;
+            self.enter()?;
+            let exponent = self.unary()?;
+            self.depth -= 1;
+            return Ok(base.powf(exponent));
+        }
+        Ok(base)
+    }
+
+    fn primary(&mut self) -> Result<f64, CalcError> {
+        match self.next() {
+            Some(Token::Number(n)) => Ok(n),
+            Some(Token::LParen) => {
+                self.enter()?;
+                let value = self.expr()?;
+                self.depth -= 1;
+                match self.next() {
+                    Some(Token::RParen) => Ok(value),
+                    _ => Err(CalcError::Syntax("missing closing parenthesis".into())),
+                }
+            }
+            Some(Token::Ident(name)) => {
+                if let Some(Token::LParen) = self.peek() {
+                    self.pos += 1;
+                    self.enter()?;
+                    let args = self.arguments()?;
+                    self.depth -= 1;
+                    call_function(&name, &args)
+                } else {
+                    constant(&name)
+                }
+            }
+            Some(token) => Err(CalcError::Syntax(format!("unexpected token {:?}", token))),
+            None => Err(CalcError::Syntax("unexpected end of expression".into())),
+        }
+    }
+
+    fn arguments(&mut self) -> Result<Vec<f64>, CalcError> {
+        let mut args = Vec::new();
+        if let Some(Token::RParen) = self.peek() {
+            self.pos += 1;
+            return Ok(args);
+        }
+        loop {
+            args.push(self.expr()?);
+            match self.next() {
+                Some(Token::Comma) => continue,
+                Some(Token::RParen) => return Ok(args),
+                _ => return Err(CalcError::Syntax("expected ',' or ')'".into())),
+            }
+        }
+    }
+}
+
+fn constant(name: &str) -> Result<f64, CalcError> {
+    match name {
+        "pi" | "π" => Ok(std::f64::consts::PI),
+        "e" => Ok(std::f64::consts::E),
+        "tau" => Ok(std::f64::consts::TAU),
+        _ => Err(CalcError::UnknownIdentifier(name.to_string())),
+    }
+}
+
+fn call_function(name: &str, args: &[f64]) -> Result<f64, CalcError> {
+    let expect = |count: usize| -> Result<(), CalcError> {
+        if args.len() == count {
+            Ok(())
+        } else {
+            Err(CalcError::InvalidArguments(format!(
+                "{}() takes {} argument(s), got {}",
+                name,
+                count,
+                args.len()
+            )))
+        }
+    };
+    let positive = |value: f64| -> Result<f64, CalcError> {
+        if value > 0.0 {
+            Ok(value)
+        } else {
+            Err(CalcError::InvalidArguments(format!(
+                "{}() requires a positive argument",
+                name
+            )))
+        }
+    };
+
+    match name {
+        "sqrt" => {
+            expect(1)?;
+            if args[0] < 0.0 {
+                return Err(CalcError::InvalidArguments(
+                    "sqrt() of a negative number".into(),
+                ));
+            }
+            Ok(args[0].sqrt())
+        }
+        "cbrt" => expect(1).map(|_| args[0].cbrt()),
+        "abs" => expect(1).map(|_| args[0].abs()),
+        "sin" => expect(1).map(|_| args[0].sin()),
+        "cos" => expect(1).map(|_| args[0].cos()),
+        "tan" => expect(1).map(|_| args[0].tan()),
+        "asin" => expect(1).map(|_| args[0].asin()),
+        "acos" => expect(1).map(|_| args[0].acos()),
+        "atan" => expect(1).map(|_| args[0].atan()),
+        "exp" => expect(1).map(|_| args[0].exp()),
+        "floor" => expect(1).map(|_| args[0].floor()),
+        "ceil" => expect(1).map(|_| args[0].ceil()),
+        "round" => expect(1).map(|_| args[0].round()),
+        "ln" => {
+            expect(1)?;
+            Ok(positive(args[0])?.ln())
+        }
+        "log2" => {
+            expect(1)?;
+            Ok(positive(args[0])?.log2())
+        }
+        "log10" => {
+            expect(1)?;
+            Ok(positive(args[0])?.log10())
+        }
+        "log" if args.len() == 1 => Ok(positive(args[0])?.log10()),
+        "log" => {
+            expect(2)?;
+            Ok(positive(args[0])?.log(positive(args[1])?))
+        }
+        "pow" => expect(2).map(|_| args[0].powf(args[1])),
+        "min" | "max" if args.is_empty() => Err(CalcError::InvalidArguments(format!(
+            "{}() needs at least one argument",
+            name
+        ))),
+        "min" => Ok(args.iter().copied().fold(f64::INFINITY, f64::min)),
+        "max" => Ok(args.iter().copied().fold(f64::NEG_INFINITY, f64::max)),
+        _ => Err(CalcError::UnknownIdentifier(name.to_string())),
+    }
+}
+
+/// Safely evaluates an arithmetic expression without executing any code.
+pub fn evaluate(expression: &str) -> Result<f64, CalcError> {
+    if expression.trim().is_empty() {
+        return Err(CalcError::Syntax("expression is empty".into()));
+    }
+    if expression.len() > MAX_EXPRESSION_LEN {
+        return Err(CalcError::Syntax(format!(
+            "expression is longer than {} characters",
+            MAX_EXPRESSION_LEN
+        )));
+    }
+
+    let mut parser = Parser {
+        tokens: tokenize(expression)?,
+        pos: 0,
+        depth: 0,
+    };
+    let value = parser.expr()?;
+    if let Some(token) = parser.peek() {
+        return Err(CalcError::Syntax(format!("unexpected token {:?}", token)));
+    }
+    if !value.is_finite() {
+        return Err(CalcError::NotFinite);
+    }
+    Ok(value)
+}
+
+/// Formats a result without float noise such as `0.30000000000000004`.
+fn format_number(value: f64) -> String {
+    if value.fract() == 0.0 && value.abs() < 1e15 {
+        return format!("{}", value as i64);
+    }
+    let rounded = format!("{:.12}", value);
+    rounded
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+        .to_string()
+}
+
+/// Evaluates math expressions for models, which are unreliable at arithmetic.
+#[derive(Default)]
+pub struct Calculator;
+
+#[async_trait]
+impl ToolDefinition for Calculator {
+    fn name(&self) -> &str {
+        TOOL_CALCULATE
+    }
+
+    fn description(&self) -> &str {
+        "Evaluate a math expression and return the exact numeric result. \
+        Use this instead of doing arithmetic yourself.\n\n\
+        Supports + - * / % ^ (or **), parentheses, the constants pi, e and tau, and the functions \
+        sqrt, cbrt, abs, sin, cos, tan, asin, acos, atan, exp, ln, log (base 10 or log(x, base)), \
+        log2, pow, floor, ceil, round, min and max. Trigonometric functions use radians."
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::System
+    }
+
+    fn scope(&self) -> ToolScope {
+        ToolScope::Both
+    }
+
+    fn tool_calling_spec(&self) -> MCPToolDeclaration {
+        MCPToolDeclaration {
+            name: self.name().to_string(),
+            description: self.description().to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "expression": {
+                        "type": "string",
+                        "description": "The math expression to evaluate, e.g. '(3 + 4) * sqrt(16) / 2'."
+                    }
+                },
+                "required": ["expression"]
+            }),
+            output_schema: None,
+            disabled: false,
+            scope: Some(self.scope()),
+        }
+    }
+
+    async fn call(&self, params: Value) -> NativeToolResult {
+        let expression = params
+            .get("expression")
+            .and_then(Value::as_str)
+            .ok_or_else(|| ToolError::InvalidParams("expression is required".to_string()))?;
+
+        let value = evaluate(expression).map_err(|e| {
+            ToolError::InvalidParams(format!("Failed to evaluate '{}': {}", expression, e))
+        })?;
+        let formatted = format_number(value);
+
+        Ok(ToolCallResult::success(
+            Some(formatted.clone()),
+            Some(json!({
+                "expression": expression,
+                "result": value,
+                "formatted": formatted,
+            })),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn approx(expression: &str, expected: f64) {
+        let value = evaluate(expression).unwrap_or_else(|e| panic!("{}: {}", expression, e));
+        assert!(
+            (value - expected).abs() < 1e-9,
+            "{} = {}, expected {}",
+            expression,
+            value,
+            expected
+        );
+    }
+
+    #[test]
+    fn test_basic_arithmetic() {
+        approx("1 + 2 * 3", 7.0);
+        approx("(1 + 2) * 3", 9.0);
+        approx("10 / 4", 2.5);
+        approx("10 % 4", 2.0);
+        approx("-3 + 5", 2.0);
+        approx("2 ^ 3 ^ 2", 512.0);
+        approx("-2 ^ 2", -4.0);
+        approx("2 ** 10", 1024.0);
+        approx("1.5e3 + 0.5", 1500.5);
+        approx("6 × 7 ÷ 2", 21.0);
+    }
+
+    #[test]
+    fn test_functions_and_constants() {
+        approx("sqrt(16)", 4.0);
+        approx("abs(-3.5)", 3.5);
+        approx("max(1, 7, 3) - min(4, 2)", 5.0);
+        approx("log(1000)", 3.0);
+        approx("log(8, 2)", 3.0);
+        approx("ln(e)", 1.0);
+        approx("sin(pi / 2)", 1.0);
+        approx("round(2.6) + floor(2.6) + ceil(2.1)", 8.0);
+        approx("pow(2, 0.5) ^ 2", 2.0);
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(evaluate("1 / 0"), Err(CalcError::DivisionByZero));
+        assert_eq!(evaluate("5 % (2 - 2)"), Err(CalcError::DivisionByZero));
+        assert!(matches!(evaluate("1 +"), Err(CalcError::Syntax(_))));
+        assert!(matches!(evaluate("(1 + 2"), Err(CalcError::Syntax(_))));
+        assert!(matches!(evaluate("2 3"), Err(CalcError::Syntax(_))));
+        assert!(matches!(evaluate(""), Err(CalcError::Syntax(_))));
+        assert!(matches!(
+            evaluate("system(1)"),
+            Err(CalcError::UnknownIdentifier(_))
+        ));
+        assert!(matches!(
+            evaluate("sqrt(-1)"),
+            Err(CalcError::InvalidArguments(_))
+        ));
+        assert!(matches!(
+            evaluate("sqrt(1, 2)"),
+            Err(CalcError::InvalidArguments(_))
+        ));
+        assert_eq!(evaluate("10 ^ 400"), Err(CalcError::NotFinite));
+        assert!(matches!(
+            evaluate(&"(".repeat(100)),
+            Err(CalcError::Syntax(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_calculator_tool_call() {
+        let tool = Calculator;
+        let result = tool
+            .call(json!({ "expression": "0.1 + 0.2" }))
+            .await
+            .expect("tool call should succeed");
+        assert_eq!(result.content.as_deref(), Some("0.3"));
+
+        let error = tool.call(json!({ "expression": "1 / 0" })).await;
+        assert!(matches!(error, Err(ToolError::InvalidParams(_))));
+
+        let error = tool.call(json!({})).await;
+        assert!(matches!(error, Err(ToolError::InvalidParams(_))));
+    }
+}
@@ -15,6 +15,9 @@ pub const TOOL_GIT_DIFF: &str = "git_diff";
 pub const TOOL_GIT_INSPECT: &str = "git_inspect";
 pub const TOOL_WEB_SEARCH: &str = "web_search";
 pub const TOOL_WEB_FETCH: &str = "web_fetch";
+pub const TOOL_GET_CURRENT_TIME: &str = "get_current_time";
+pub const TOOL_CALCULATE: &str = "calculate";
+pub const TOOL_EXECUTE_COMMAND: &str = "execute_command";
 
 // These tools are internal tools for the agent, usually do not require review
 pub const TOOL_SUB_AGENT_RUN: &str = "sub_agent_run";
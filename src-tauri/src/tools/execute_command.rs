@@ -0,0 +1,591 @@
+use async_trait::async_trait;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::{Arc, RwLock};
+use tauri::{AppHandle, Emitter};
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::process::Command;
+use tokio::time::{timeout, Duration};
+
+use crate::{
+    ai::traits::chat::MCPToolDeclaration,
+    constants::CFG_SHELL_AUTHORIZED_PATHS,
+    db::MainStore,
+    tools::{
+        approval::ApprovalRegistry, error::ToolError, NativeToolResult, ShellDecision,
+        ShellPolicyEngine, ToolCallResult, ToolCategory, ToolDefinition, ToolOutputSink,
+        ToolOutputStream, ToolScope, TOOL_EXECUTE_COMMAND,
+    },
+    workflow::react::security::PathGuard,
+};
+
+/// Event emitted to the frontend when a command needs the user's approval.
+pub const COMMAND_APPROVAL_EVENT: &str = "cs://command-approval";
+
+const DEFAULT_TIMEOUT_SECS: u64 = 60;
+const MAX_TIMEOUT_SECS: u64 = 600;
+/// How long a gated command waits for the user before it is abandoned.
+const DEFAULT_APPROVAL_TIMEOUT: Duration = Duration::from_secs(300);
+/// Maximum bytes kept per output stream, the rest is drained and discarded.
+const MAX_OUTPUT_BYTES: usize = 32 * 1024;
+
+/// A command waiting for the user's decision, sent to the frontend as the event payload.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandApprovalRequest {
+    pub request_id: String,
+    pub command: String,
+    pub cwd: String,
+    pub reason: String,
+}
+
+/// Tracks gated commands until the user approves or rejects them.
+pub type CommandApprovalRegistry = ApprovalRegistry<CommandApprovalRequest>;
+
+/// Reads a stream to the end, keeping at most `cap` bytes.
+///
+/// Every chunk is also forwarded to `sink` as it arrives, split on UTF-8 boundaries.
+/// Returns the kept bytes and the total number of bytes produced.
+async fn read_capped<R: AsyncRead + Unpin>(
+    reader: Option<R>,
+    cap: usize,
+    sink: Option<(&ToolOutputSink, ToolOutputStream)>,
+) -> (Vec<u8>, usize) {
+    let Some(mut reader) = reader else {
+        return (Vec::new(), 0);
+    };
+    let mut kept = Vec::new();
+    let mut total = 0;
+    let mut chunk = [0u8; 8192];
+    // Bytes of a multi-byte character split across two reads
+    let mut pending = Vec::new();
+    while let Ok(n) = reader.read(&mut chunk).await {
+        if n == 0 {
+            break;
+        }
+        total += n;
+        let room = cap.saturating_sub(kept.len());
+        kept.extend_from_slice(&chunk[..n.min(room)]);
+
+        if let Some((sink, stream)) = sink {
+            pending.extend_from_slice(&chunk[..n]);
+            let valid = match std::str::from_utf8(&pending) {
+                Ok(_) => pending.len(),
+                // Keep an incomplete trailing sequence for the next read, flush invalid bytes
+                Err(e) if e.error_len().is_none() => e.valid_up_to(),
+                Err(_) => pending.len(),
+            };
+            let rest = pending.split_off(valid);
+            sink.send(stream, String::from_utf8_lossy(&pending));
+            pending = rest;
+        }
+    }
+    if let Some((sink, stream)) = sink {
+        sink.send(stream, String::from_utf8_lossy(&pending));
+    }
+    (kept, total)
+}
+
+fn capped_text(bytes: &[u8], total: usize) -> String {
+    let mut text = String::from_utf8_lossy(bytes).to_string();
+    if total > bytes.len() {
+        text.push_str(&format!(
+            "\n[output truncated: {} of {} bytes omitted]",
+            total - bytes.len(),
+            total
+        ));
+    }
+    text
+}
+
+/// Runs a shell command in a working directory for chat agents.
+///
+/// Commands are checked by the same `ShellPolicyEngine` as the workflow shell tool, with the
+/// working directory and the folders of the `shell_authorized_paths` setting as the authorized
+/// roots. Denied commands fail, commands under review are held until the user approves them
+/// through the `respond_command_approval` command, and output is size capped.
+pub struct ExecuteCommand {
+    app_handle: Option<AppHandle>,
+    approvals: Arc<CommandApprovalRegistry>,
+    main_store: Option<Arc<RwLock<MainStore>>>,
+    approval_timeout: Duration,
+}
+
+impl ExecuteCommand {
+    pub fn new(
+        app_handle: Option<AppHandle>,
+        approvals: Arc<CommandApprovalRegistry>,
+        main_store: Option<Arc<RwLock<MainStore>>>,
+    ) -> Self {
+        Self {
+            app_handle,
+            approvals,
+            main_store,
+            approval_timeout: DEFAULT_APPROVAL_TIMEOUT,
+        }
+    }
+
+    /// Reads the folders commands may run in without asking, empty without a store.
+    fn authorized_paths(&self) -> Vec<PathBuf> {
+        let Some(store) = self.main_store.as_ref().and_then(|store| store.read().ok()) else {
+            return Vec::new();
+        };
+        store
+            .get_config(CFG_SHELL_AUTHORIZED_PATHS, Vec::<String>::new())
+            .into_iter()
+            .map(|path| path.trim().to_string())
+            .filter(|path| !path.is_empty())
+            .map(PathBuf::from)
+            .collect()
+    }
+
+    /// Asks the shell policy whether `command` may run in `cwd`.
+    ///
+    /// A working directory outside the authorized folders always needs the user's approval.
+    fn check_policy(&self, command: &str, cwd: &Path) -> ShellDecision {
+        let authorized = self.authorized_paths();
+        let in_authorized_root = PathGuard::new(authorized.clone(), vec![], vec![])
+            .validate(cwd, false, false, false)
+            .is_ok();
+
+        let mut roots = vec![cwd.to_path_buf()];
+        roots.extend(authorized);
+        let guard = PathGuard::new(roots, vec![], vec![]);
+        if let Err(e) = guard.validate(cwd, false, false, false) {
+            return ShellDecision::Deny(e.to_string());
+        }
+
+        match ShellPolicyEngine::new(Arc::new(RwLock::new(guard)), vec![]).check(command, false) {
+            ShellDecision::Allow if !in_authorized_root => ShellDecision::Review(format!(
+                "Working directory {} is outside the authorized folders.",
+                cwd.display()
+            )),
+            decision => decision,
+        }
+    }
+
+    /// Validates the params, waits for approval if needed and runs the command.
+    async fn run(&self, params: Value, sink: Option<&ToolOutputSink>) -> NativeToolResult {
+        let command = params
+            .get("command")
+            .and_then(Value::as_str)
+            .map(str::trim)
+            .filter(|c| !c.is_empty())
+            .ok_or_else(|| ToolError::InvalidParams("command is required".to_string()))?;
+        let cwd = params
+            .get("cwd")
+            .and_then(Value::as_str)
+            .ok_or_else(|| ToolError::InvalidParams("cwd is required".to_string()))?;
+        let cwd_path = Path::new(cwd);
+        if !cwd_path.is_absolute() || !cwd_path.is_dir() {
+            return Err(ToolError::InvalidParams(format!(
+                "cwd must be an existing absolute directory: {}",
+                cwd
+            )));
+        }
+        let timeout_secs = params
+            .get("timeout_secs")
+            .and_then(Value::as_u64)
+            .unwrap_or(DEFAULT_TIMEOUT_SECS)
+            .clamp(1, MAX_TIMEOUT_SECS);
+
+        let approval_reason = match self.check_policy(command, cwd_path) {
+            ShellDecision::Allow => None,
+            ShellDecision::Review(reason) => Some(reason),
+            ShellDecision::Deny(reason) => {
+                return Err(ToolError::Security(format!(
+                    "Command denied ({}): {}",
+                    reason, command
+                )))
+            }
+        };
+        if let Some(reason) = &approval_reason {
+            self.await_approval(command, cwd, reason).await?;
+        }
+
+        let mut process = if cfg!(target_os = "windows") {
+            let mut process = Command::new("cmd");
+            process.args(["/C", command]);
+            process
+        } else {
+            let mut process = Command::new("sh");
+            process.args(["-c", command]);
+            process
+        };
+        let mut child = process
+            .current_dir(cwd_path)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| ToolError::ExecutionFailed(format!("Failed to spawn: {}", e)))?;
+
+        let stdout = child.stdout.take();
+        let stderr = child.stderr.take();
+        let execution = async {
+            tokio::join!(
+                read_capped(
+                    stdout,
+                    MAX_OUTPUT_BYTES,
+                    sink.map(|sink| (sink, ToolOutputStream::Stdout))
+                ),
+                read_capped(
+                    stderr,
+                    MAX_OUTPUT_BYTES,
+                    sink.map(|sink| (sink, ToolOutputStream::Stderr))
+                ),
+                child.wait()
+            )
+        };
+
+        let ((stdout, stdout_total), (stderr, stderr_total), status) =
+            match timeout(Duration::from_secs(timeout_secs), execution).await {
+                Ok(output) => output,
+                Err(_) => {
+                    return Err(ToolError::Timeout(format!(
+                        "Command timed out after {} seconds: {}",
+                        timeout_secs, command
+                    )))
+                }
+            };
+        let exit_code = status
+            .map_err(|e| ToolError::ExecutionFailed(format!("Failed to wait for command: {}", e)))?
+            .code()
+            .unwrap_or(-1);
+
+        let stdout = capped_text(&stdout, stdout_total);
+        let stderr = capped_text(&stderr, stderr_total);
+        let mut content = format!("Exit code: {}", exit_code);
+        if !stdout.is_empty() {
+            content.push_str(&format!("\nstdout:\n{}", stdout));
+        }
+        if !stderr.is_empty() {
+            content.push_str(&format!("\nstderr:\n{}", stderr));
+        }
+
+        Ok(ToolCallResult::success(
+            Some(content),
+            Some(json!({
+                "command": command,
+                "cwd": cwd,
+                "exit_code": exit_code,
+                "stdout": stdout,
+                "stderr": stderr,
+                "approved_by_user": approval_reason.is_some(),
+            })),
+        ))
+    }
+
+    /// Waits for the user to approve a gated command.
+    async fn await_approval(
+        &self,
+        command: &str,
+        cwd: &str,
+        reason: &str,
+    ) -> Result<(), ToolError> {
+        let request = CommandApprovalRequest {
+            request_id: uuid::Uuid::new_v4().to_string(),
+            command: command.to_string(),
+            cwd: cwd.to_string(),
+            reason: reason.to_string(),
+        };
+        let receiver = self
+            .approvals
+            .register(request.request_id.clone(), request.clone());
+        log::info!(
+            "Command requires approval ({}): {}, request id: {}",
+            reason,
+            command,
+            request.request_id
+        );
+
+        if let Some(app_handle) = &self.app_handle {
+            if let Err(e) = app_handle.emit(COMMAND_APPROVAL_EVENT, &request) {
+                self.approvals.cancel(&request.request_id);
+                return Err(ToolError::ExecutionFailed(format!(
+                    "Failed to request approval for command: {}",
+                    e
+                )));
+            }
+        }
+
+        match timeout(self.approval_timeout, receiver).await {
+            Ok(Ok(true)) => Ok(()),
+            Ok(Ok(false)) | Ok(Err(_)) => Err(ToolError::Security(format!(
+                "The user rejected the command ({}): {}",
+                reason, command
+            ))),
+            Err(_) => {
+                self.approvals.cancel(&request.request_id);
+                Err(ToolError::Timeout(format!(
+                    "No approval received within {} seconds for command: {}",
+                    self.approval_timeout.as_secs(),
+                    command
+                )))
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl ToolDefinition for ExecuteCommand {
+    fn name(&self) -> &str {
+        TOOL_EXECUTE_COMMAND
+    }
+
+    fn description(&self) -> &str {
+        "Execute a shell command in the given working directory and return its exit code, stdout and stderr. \
+        Use it for builds, tests, package managers and inspecting files.\n\n\
+        System-critical commands (sudo, dd, mkfs, ...) and paths outside the working directory and the \
+        authorized folders are refused. Sensitive commands (rm, mv, curl, interpreters, ...) are held until \
+        the user approves them. Output is truncated to 32KB per stream, so prefer \
+        commands with focused output. Interactive commands are not supported."
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::System
+    }
+
+    fn scope(&self) -> ToolScope {
+        ToolScope::Chat
+    }
+
+    fn tool_calling_spec(&self) -> MCPToolDeclaration {
+        MCPToolDeclaration {
+            name: self.name().to_string(),
+            description: self.description().to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "command": {
+                        "type": "string",
+                        "description": "The shell command to execute."
+                    },
+                    "cwd": {
+                        "type": "string",
+                        "description": "Absolute path of the working directory to run the command in."
+                    },
+                    "timeout_secs": {
+                        "type": "integer",
+                        "description": format!("Execution timeout in seconds (default {}, max {}).", DEFAULT_TIMEOUT_SECS, MAX_TIMEOUT_SECS)
+                    }
+                },
+                "required": ["command", "cwd"]
+            }),
+            output_schema: None,
+            disabled: false,
+            scope: Some(self.scope()),
+        }
+    }
+
+    async fn call(&self, params: Value) -> NativeToolResult {
+        self.run(params, None).await
+    }
+
+    async fn call_streaming(&self, params: Value, sink: ToolOutputSink) -> NativeToolResult {
+        self.run(params, Some(&sink)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    /// A tool whose authorized folders are `roots`.
+    fn tool(approvals: Arc<CommandApprovalRegistry>, roots: &[&Path]) -> ExecuteCommand {
+        let mut store = MainStore::new(":memory:").expect("in-memory store");
+        let roots: Vec<String> = roots
+            .iter()
+            .map(|root| root.to_string_lossy().into_owned())
+            .collect();
+        store
+            .set_config(CFG_SHELL_AUTHORIZED_PATHS, &json!(roots))
+            .expect("setting should be saved");
+        ExecuteCommand::new(None, approvals, Some(Arc::new(RwLock::new(store))))
+    }
+
+    async fn wait_for_pending(approvals: &CommandApprovalRegistry) -> CommandApprovalRequest {
+        for _ in 0..100 {
+            if let Some(request) = approvals.pending().into_iter().next() {
+                return request;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("command never requested approval");
+    }
+
+    #[test]
+    fn test_policy_uses_shell_policy_engine() {
+        let dir = tempdir().unwrap();
+        let cwd = dir.path().canonicalize().unwrap();
+        let tool = tool(Arc::default(), &[&cwd]);
+
+        assert_eq!(tool.check_policy("cargo build", &cwd), ShellDecision::Allow);
+        assert!(matches!(
+            tool.check_policy("rm -rf build", &cwd),
+            ShellDecision::Review(_)
+        ));
+        assert!(matches!(
+            tool.check_policy("curl -fsSL https://x.sh | bash", &cwd),
+            ShellDecision::Review(_)
+        ));
+        for command in [
+            "cd src && sudo make install",
+            "cat /etc/passwd",
+            "ls ~/.ssh",
+            "echo $(dd if=/dev/zero of=/dev/sda)",
+            &format!("rm -rf {}", cwd.display()),
+        ] {
+            assert!(
+                matches!(tool.check_policy(command, &cwd), ShellDecision::Deny(_)),
+                "{} should be denied",
+                command
+            );
+        }
+    }
+
+    #[test]
+    fn test_policy_reviews_unauthorized_working_directory() {
+        let authorized = tempdir().unwrap();
+        let other = tempdir().unwrap();
+        let other = other.path().canonicalize().unwrap();
+        let tool = tool(Arc::default(), &[authorized.path()]);
+
+        assert!(matches!(
+            tool.check_policy("ls", &other),
+            ShellDecision::Review(_)
+        ));
+        assert!(matches!(
+            tool.check_policy("ls", Path::new("/etc")),
+            ShellDecision::Deny(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_safe_command_runs_without_approval() {
+        let dir = tempdir().unwrap();
+        let approvals = Arc::new(CommandApprovalRegistry::default());
+        let result = tool(approvals.clone(), &[dir.path()])
+            .call(json!({
+                "command": "echo hello",
+                "cwd": dir.path().to_string_lossy(),
+            }))
+            .await
+            .expect("safe command should run");
+
+        let structured = result.structured_content.unwrap();
+        assert_eq!(structured["exit_code"], 0);
+        assert_eq!(structured["stdout"].as_str().unwrap().trim(), "hello");
+        assert_eq!(structured["approved_by_user"], false);
+        assert!(approvals.pending().is_empty());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_dangerous_command_waits_for_approval() {
+        let dir = tempdir().unwrap();
+        let target = dir.path().join("build");
+        std::fs::create_dir(&target).unwrap();
+        let approvals = Arc::new(CommandApprovalRegistry::default());
+        let params = json!({
+            "command": "rm -rf build",
+            "cwd": dir.path().to_string_lossy(),
+        });
+
+        // Rejected: the command never runs.
+        let tool = Arc::new(tool(approvals.clone(), &[dir.path()]));
+        let call = tokio::spawn({
+            let tool = tool.clone();
+            let params = params.clone();
+            async move { tool.call(params).await }
+        });
+        let request = wait_for_pending(&approvals).await;
+        assert_eq!(request.command, "rm -rf build");
+        assert!(target.exists(), "command must not run before approval");
+        assert!(approvals.respond(&request.request_id, false));
+        assert!(matches!(call.await.unwrap(), Err(ToolError::Security(_))));
+        assert!(target.exists());
+
+        // Approved: the command runs.
+        let call = tokio::spawn({
+            let tool = tool.clone();
+            async move { tool.call(params).await }
+        });
+        let request = wait_for_pending(&approvals).await;
+        assert!(approvals.respond(&request.request_id, true));
+        let result = call.await.unwrap().expect("approved command should run");
+        assert_eq!(result.structured_content.unwrap()["approved_by_user"], true);
+        assert!(!target.exists());
+        assert!(!approvals.respond(&request.request_id, true));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_output_is_streamed_to_sink() {
+        let dir = tempdir().unwrap();
+        let (sink, mut output) = ToolOutputSink::channel();
+        let result = tool(Arc::default(), &[dir.path()])
+            .call_streaming(
+                json!({
+                    "command": "ls missing; echo one",
+                    "cwd": dir.path().to_string_lossy(),
+                }),
+                sink,
+            )
+            .await
+            .unwrap();
+        assert_eq!(result.structured_content.unwrap()["exit_code"], 0);
+
+        let mut streamed = Vec::new();
+        while let Ok(chunk) = output.try_recv() {
+            streamed.push(chunk);
+        }
+        assert!(streamed
+            .iter()
+            .any(|c| c.stream == ToolOutputStream::Stdout && c.text == "one\n"));
+        assert!(streamed
+            .iter()
+            .any(|c| c.stream == ToolOutputStream::Stderr && c.text.contains("missing")));
+    }
+
+    #[tokio::test]
+    async fn test_unanswered_approval_times_out() {
+        let dir = tempdir().unwrap();
+        let approvals = Arc::new(CommandApprovalRegistry::default());
+        let mut tool = tool(approvals.clone(), &[dir.path()]);
+        tool.approval_timeout = Duration::from_millis(20);
+
+        let result = tool
+            .call(json!({
+                "command": "rm -rf build",
+                "cwd": dir.path().to_string_lossy(),
+            }))
+            .await;
+        assert!(matches!(result, Err(ToolError::Timeout(_))));
+        assert!(approvals.pending().is_empty());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_output_is_capped() {
+        let dir = tempdir().unwrap();
+        let result = tool(Arc::default(), &[dir.path()])
+            .call(json!({
+                "command": "yes a | head -c 100000",
+                "cwd": dir.path().to_string_lossy(),
+            }))
+            .await
+            .unwrap();
+
+        let stdout = result.structured_content.unwrap()["stdout"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        assert!(stdout.len() < MAX_OUTPUT_BYTES + 100);
+        assert!(stdout.contains("output truncated"));
+    }
+}
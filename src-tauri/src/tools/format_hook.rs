@@ -0,0 +1,329 @@
+//! Optional formatter run on a file after `write_file` or `edit_file` changed it.
+//!
+//! The formatter is chosen by file extension and skipped when it is not installed. Whether
+//! formatting changed the file is reported back in the tool result, so the model re-reads the
+//! file instead of editing stale content.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::path::Path;
+use std::process::Stdio;
+use tokio::process::Command;
+use tokio::time::{timeout, Duration};
+
+use crate::tools::ToolCallResult;
+
+/// Placeholder in formatter arguments replaced by the file path.
+const FILE_PLACEHOLDER: &str = "{file}";
+/// Longest formatter error kept in the tool result.
+const MAX_ERROR_CHARS: usize = 1_000;
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_timeout_secs() -> u64 {
+    10
+}
+
+fn default_formatters() -> Vec<FormatterConfig> {
+    let formatter = |extensions: &[&str], command: &str, args: &[&str]| FormatterConfig {
+        extensions: extensions.iter().map(|ext| ext.to_string()).collect(),
+        command: command.to_string(),
+        args: args.iter().map(|arg| arg.to_string()).collect(),
+        enabled: true,
+    };
+    vec![
+        formatter(&["rs"], "rustfmt", &["--edition", "2021", FILE_PLACEHOLDER]),
+        formatter(&["go"], "gofmt", &["-w", FILE_PLACEHOLDER]),
+        formatter(
+            &[
+                "js", "jsx", "mjs", "cjs", "ts", "tsx", "vue", "json", "css", "scss", "less",
+                "html", "yaml", "yml",
+            ],
+            "prettier",
+            &["--write", FILE_PLACEHOLDER],
+        ),
+    ]
+}
+
+/// A formatter for one group of file types.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FormatterConfig {
+    /// File extensions without the dot, compared case-insensitively
+    pub extensions: Vec<String>,
+    pub command: String,
+    /// Arguments, `{file}` is replaced by the file path; the path is appended when absent
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+/// Settings of the format hook, stored under `workflow_format_on_edit`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FormatHookConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+    #[serde(default = "default_formatters")]
+    pub formatters: Vec<FormatterConfig>,
+}
+
+impl Default for FormatHookConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            timeout_secs: default_timeout_secs(),
+            formatters: default_formatters(),
+        }
+    }
+}
+
+/// What formatting a file did.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct FormatOutcome {
+    pub formatter: String,
+    pub changed: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl FormatOutcome {
+    fn note(&self) -> String {
+        match (&self.error, self.changed) {
+            (Some(error), _) => format!(
+                "<SYSTEM_REMINDER>Formatter {} failed on the file: {}</SYSTEM_REMINDER>",
+                self.formatter, error
+            ),
+            (None, true) => format!(
+                "<SYSTEM_REMINDER>Formatter {} reformatted the file. Re-read it before editing it again.</SYSTEM_REMINDER>",
+                self.formatter
+            ),
+            (None, false) => format!("Formatter {} made no changes.", self.formatter),
+        }
+    }
+
+    /// Adds the outcome to the result of the file tool.
+    pub fn attach_to(&self, result: &mut ToolCallResult) {
+        let outcome = json!(self);
+        if let Some(Value::Object(structured)) = result.structured_content.as_mut() {
+            structured.insert("formatting".to_string(), outcome.clone());
+        }
+        result.content = Some(match result.content.take() {
+            Some(content) => match serde_json::from_str::<Value>(&content) {
+                Ok(Value::Object(mut object)) => {
+                    object.insert("formatting".to_string(), outcome);
+                    Value::Object(object).to_string()
+                }
+                _ => format!("{}\n{}", content, self.note()),
+            },
+            None => self.note(),
+        });
+    }
+}
+
+/// Runs the configured formatter on files changed by the file tools.
+#[derive(Debug, Clone, Default)]
+pub struct FormatHook {
+    config: FormatHookConfig,
+}
+
+impl FormatHook {
+    pub fn new(config: FormatHookConfig) -> Self {
+        Self { config }
+    }
+
+    fn formatter_for(&self, path: &Path) -> Option<&FormatterConfig> {
+        let extension = path.extension()?.to_str()?;
+        self.config.formatters.iter().find(|formatter| {
+            formatter.enabled
+                && formatter
+                    .extensions
+                    .iter()
+                    .any(|ext| ext.trim_start_matches('.').eq_ignore_ascii_case(extension))
+        })
+    }
+
+    /// Formats `path`, returns `None` when no formatter applies or it is not installed.
+    pub async fn run(&self, path: &Path) -> Option<FormatOutcome> {
+        if !self.config.enabled {
+            return None;
+        }
+        let formatter = self.formatter_for(path)?;
+        let before = std::fs::read(path).ok()?;
+
+        let file = path.to_string_lossy();
+        let mut args: Vec<String> = formatter
+            .args
+            .iter()
+            .map(|arg| arg.replace(FILE_PLACEHOLDER, &file))
+            .collect();
+        if !formatter
+            .args
+            .iter()
+            .any(|arg| arg.contains(FILE_PLACEHOLDER))
+        {
+            args.push(file.to_string());
+        }
+
+        let mut command = Command::new(&formatter.command);
+        command
+            .args(&args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        if let Some(parent) = path.parent().filter(|parent| parent.is_dir()) {
+            command.current_dir(parent);
+        }
+
+        let outcome = |changed: bool, error: Option<String>| FormatOutcome {
+            formatter: formatter.command.clone(),
+            changed,
+            error,
+        };
+        let output = match timeout(
+            Duration::from_secs(self.config.timeout_secs),
+            command.output(),
+        )
+        .await
+        {
+            Ok(Ok(output)) => output,
+            Ok(Err(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+                log::debug!(
+                    "Formatter {} is not installed, skipped {}",
+                    &formatter.command,
+                    path.display()
+                );
+                return None;
+            }
+            Ok(Err(e)) => return Some(outcome(false, Some(e.to_string()))),
+            Err(_) => {
+                return Some(outcome(
+                    false,
+                    Some(format!(
+                        "timed out after {} seconds",
+                        self.config.timeout_secs
+                    )),
+                ))
+            }
+        };
+
+        let changed = std::fs::read(path).is_ok_and(|after| after != before);
+        let error = (!output.status.success()).then(|| {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let stderr = stderr.trim();
+            if stderr.is_empty() {
+                format!("exit status {}", output.status)
+            } else {
+                stderr.chars().take(MAX_ERROR_CHARS).collect()
+            }
+        });
+        Some(outcome(changed, error))
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::tools::{EditFile, ToolDefinition, WriteFile};
+    use std::os::unix::fs::PermissionsExt;
+    use tempfile::tempdir;
+
+    /// Writes an executable shell script acting as a formatter.
+    fn stub_formatter(dir: &Path, name: &str, script: &str) -> String {
+        let path = dir.join(name);
+        std::fs::write(&path, format!("#!/bin/sh\n{}\n", script)).expect("write stub");
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))
+            .expect("make stub executable");
+        path.to_string_lossy().to_string()
+    }
+
+    fn hook(command: String) -> FormatHook {
+        FormatHook::new(FormatHookConfig {
+            formatters: vec![FormatterConfig {
+                extensions: vec!["txt".to_string()],
+                command,
+                args: vec![],
+                enabled: true,
+            }],
+            ..Default::default()
+        })
+    }
+
+    #[tokio::test]
+    async fn test_write_file_reports_reformatted_file() {
+        let dir = tempdir().expect("temp dir");
+        let formatter = stub_formatter(dir.path(), "fmt.sh", "printf 'a\\nb\\n' > \"$1\"");
+        let file = dir.path().join("code.txt");
+
+        let tool = WriteFile::new(None).with_format_hook(hook(formatter));
+        let result = tool
+            .call(json!({"file_path": file.to_string_lossy(), "content": "a b"}))
+            .await
+            .expect("write");
+
+        assert_eq!(std::fs::read_to_string(&file).expect("read"), "a\nb\n");
+        let formatting = &result.structured_content.expect("structured")["formatting"];
+        assert_eq!(formatting["changed"], true);
+        assert!(result
+            .content
+            .expect("content")
+            .contains("reformatted the file"));
+    }
+
+    #[tokio::test]
+    async fn test_edit_file_reports_unchanged_and_failed_formatting() {
+        let dir = tempdir().expect("temp dir");
+        let file = dir.path().join("code.txt");
+        std::fs::write(&file, "old\n").expect("seed");
+
+        let noop = stub_formatter(dir.path(), "noop.sh", "exit 0");
+        let tool = EditFile::new(None).with_format_hook(hook(noop));
+        let result = tool
+            .call(json!({"file_path": file.to_string_lossy(), "old_string": "old", "new_string": "new"}))
+            .await
+            .expect("edit");
+        let content: Value =
+            serde_json::from_str(&result.content.expect("content")).expect("JSON content");
+        assert_eq!(content["formatting"]["changed"], false);
+
+        let failing = stub_formatter(dir.path(), "fail.sh", "echo 'syntax error' >&2; exit 2");
+        let tool = EditFile::new(None).with_format_hook(hook(failing));
+        let result = tool
+            .call(json!({"file_path": file.to_string_lossy(), "old_string": "new", "new_string": "newer"}))
+            .await
+            .expect("edit");
+        let formatting = &result.structured_content.expect("structured")["formatting"];
+        assert_eq!(formatting["error"], "syntax error");
+        assert_eq!(std::fs::read_to_string(&file).expect("read"), "newer\n");
+    }
+
+    #[tokio::test]
+    async fn test_hook_skips_missing_or_disabled_formatters() {
+        let dir = tempdir().expect("temp dir");
+        let file = dir.path().join("code.txt");
+        std::fs::write(&file, "text").expect("seed");
+
+        let missing = hook(
+            dir.path()
+                .join("missing-formatter")
+                .to_string_lossy()
+                .to_string(),
+        );
+        assert_eq!(missing.run(&file).await, None);
+
+        let formatter = stub_formatter(dir.path(), "fmt.sh", "printf 'x' > \"$1\"");
+        let mut disabled = hook(formatter.clone());
+        disabled.config.enabled = false;
+        assert_eq!(disabled.run(&file).await, None);
+
+        let other_type = hook(formatter);
+        assert_eq!(other_type.run(&dir.path().join("code.rs")).await, None);
+        assert_eq!(std::fs::read_to_string(&file).expect("read"), "text");
+    }
+}
@@ -1,5 +1,6 @@
 use crate::ai::traits::chat::MCPToolDeclaration;
 use crate::libs::ai_temp::{display_ai_temp_path, resolve_ai_temp_path};
+use crate::tools::format_hook::FormatHook;
 use crate::tools::llm_output::preview_path_lines_for_llm;
 use crate::tools::{NativeToolResult, ToolCallResult, ToolCategory, ToolDefinition, ToolError};
 use crate::workflow::react::security::PathGuard;
@@ -624,11 +625,21 @@ impl ToolDefinition for ReadFile {
 #[derive(Clone, Default)]
 pub struct WriteFile {
     path_guard: Option<Arc<RwLock<PathGuard>>>,
+    format_hook: Option<FormatHook>,
 }
 
 impl WriteFile {
     pub fn new(path_guard: Option<Arc<RwLock<PathGuard>>>) -> Self {
-        Self { path_guard }
+        Self {
+            path_guard,
+            format_hook: None,
+        }
+    }
+
+    /// Formats the file after a successful change.
+    pub fn with_format_hook(mut self, format_hook: FormatHook) -> Self {
+        self.format_hook = Some(format_hook);
+        self
     }
 }
 
@@ -733,7 +744,7 @@ impl ToolDefinition for WriteFile {
             .map_err(|e| ToolError::IoError(format!("Write failed: {}", e)))?;
 
         let display_path = display_path_for_tool_output(&path, self.path_guard.as_ref());
-        Ok(ToolCallResult::success(
+        let mut result = ToolCallResult::success(
             Some(if overwritten {
                 "File written successfully; existing file was backed up first.".to_string()
             } else {
@@ -751,18 +762,34 @@ impl ToolDefinition for WriteFile {
                         .unwrap_or_else(|| value.to_string_lossy().to_string())
                 })
             })),
-        ))
+        );
+        if let Some(format_hook) = &self.format_hook {
+            if let Some(outcome) = format_hook.run(&path).await {
+                outcome.attach_to(&mut result);
+            }
+        }
+        Ok(result)
     }
 }
 
 #[derive(Clone, Default)]
 pub struct EditFile {
     path_guard: Option<Arc<RwLock<PathGuard>>>,
+    format_hook: Option<FormatHook>,
 }
 
 impl EditFile {
     pub fn new(path_guard: Option<Arc<RwLock<PathGuard>>>) -> Self {
-        Self { path_guard }
+        Self {
+            path_guard,
+            format_hook: None,
+        }
+    }
+
+    /// Formats the file after a successful change.
+    pub fn with_format_hook(mut self, format_hook: FormatHook) -> Self {
+        self.format_hook = Some(format_hook);
+        self
     }
 }
 
@@ -832,13 +859,20 @@ impl ToolDefinition for EditFile {
                 "new_string is required".to_string(),
             ))?;
         let replace_all = params["replace_all"].as_bool().unwrap_or(false);
-        execute_edit_file(
+        let mut result = execute_edit_file(
             path_str,
             old_str_unix,
             new_str_unix,
             replace_all,
             self.path_guard.as_ref(),
-        )
+        )?;
+        if let Some(format_hook) = &self.format_hook {
+            let path = resolve_tool_path(path_str, self.path_guard.as_ref());
+            if let Some(outcome) = format_hook.run(&path).await {
+                outcome.attach_to(&mut result);
+            }
+        }
+        Ok(result)
     }
 }
 
@@ -0,0 +1,271 @@
+//! Coerces tool call arguments to the input schema of an MCP tool.
+//!
+//! Models often send numbers as strings, `"true"` for a boolean or `null` for an optional
+//! argument they do not use, and many MCP servers reject such calls outright. Before a call is
+//! sent, arguments are converted where the conversion cannot change their meaning: numeric
+//! strings become numbers and the reverse, JSON strings become the object or array the schema
+//! asks for, defaults fill in missing optional arguments and keys the schema does not declare
+//! are dropped unless it allows additional properties. Anything else is left for the server to
+//! judge, except for values that cannot be converted and missing required arguments, which fail
+//! with a message naming the argument so that the model can correct its call.
+
+use serde_json::{Map, Number, Value};
+
+/// Coerces `args` to the JSON schema of a tool's input.
+///
+/// Returns the message for the model when an argument cannot be converted to its type or a
+/// required argument is missing.
+pub(crate) fn coerce_tool_arguments(args: Value, schema: &Value) -> Result<Value, String> {
+    if schema.get("properties").is_none() {
+        return Ok(args);
+    }
+    let args = match args {
+        Value::Null => Value::Object(Map::new()),
+        Value::String(text) if text.trim().is_empty() => Value::Object(Map::new()),
+        Value::String(text) => match serde_json::from_str::<Value>(&text) {
+            Ok(parsed @ Value::Object(_)) => parsed,
+            _ => return Err("arguments must be a JSON object".to_string()),
+        },
+        args => args,
+    };
+    coerce_value(args, schema, "")
+}
+
+/// The types a schema allows, empty when it does not say.
+fn schema_types(schema: &Value) -> Vec<&str> {
+    match schema.get("type") {
+        Some(Value::String(kind)) => vec![kind.as_str()],
+        Some(Value::Array(kinds)) => kinds.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn matches_type(value: &Value, kind: &str) -> bool {
+    match kind {
+        "string" => value.is_string(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "number" => value.is_number(),
+        "boolean" => value.is_boolean(),
+        "array" => value.is_array(),
+        "object" => value.is_object(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+/// Converts `value` to `kind`, `None` when there is no conversion that keeps its meaning.
+fn convert(value: &Value, kind: &str) -> Option<Value> {
+    match (kind, value) {
+        ("integer", Value::String(text)) => text.trim().parse::<i64>().ok().map(Value::from),
+        ("integer", Value::Number(number)) => number
+            .as_f64()
+            .filter(|n| n.fract() == 0.0 && n.abs() < i64::MAX as f64)
+            .map(|n| Value::from(n as i64)),
+        ("number", Value::String(text)) => text
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .and_then(Number::from_f64)
+            .map(Value::Number),
+        ("boolean", Value::String(text)) => match text.trim().to_ascii_lowercase().as_str() {
+            "true" => Some(Value::Bool(true)),
+            "false" => Some(Value::Bool(false)),
+            _ => None,
+        },
+        ("string", Value::Number(number)) => Some(Value::String(number.to_string())),
+        ("string", Value::Bool(flag)) => Some(Value::String(flag.to_string())),
+        ("array", Value::String(text)) | ("object", Value::String(text)) => {
+            serde_json::from_str::<Value>(text)
+                .ok()
+                .filter(|parsed| matches_type(parsed, kind))
+        }
+        _ => None,
+    }
+}
+
+fn type_name(kind: &str) -> &str {
+    match kind {
+        "integer" => "an integer",
+        "array" => "an array",
+        "object" => "an object",
+        "boolean" => "a boolean",
+        "number" => "a number",
+        "string" => "a string",
+        other => other,
+    }
+}
+
+fn coerce_value(value: Value, schema: &Value, path: &str) -> Result<Value, String> {
+    let kinds = schema_types(schema);
+    let value = if kinds.is_empty() || kinds.iter().any(|kind| matches_type(&value, kind)) {
+        value
+    } else {
+        match kinds.iter().find_map(|kind| convert(&value, kind)) {
+            Some(converted) => converted,
+            None => {
+                let subject = if path.is_empty() {
+                    "arguments".to_string()
+                } else {
+                    format!("argument '{}'", path)
+                };
+                let expected: Vec<&str> = kinds.iter().map(|kind| type_name(kind)).collect();
+                return Err(format!(
+                    "{} must be {}, got {}",
+                    subject,
+                    expected.join(" or "),
+                    value
+                ));
+            }
+        }
+    };
+
+    match value {
+        Value::Object(object) => coerce_object(object, schema, path).map(Value::Object),
+        Value::Array(items) => match schema.get("items").filter(|items| items.is_object()) {
+            Some(item_schema) => items
+                .into_iter()
+                .enumerate()
+                .map(|(i, item)| coerce_value(item, item_schema, &format!("{}[{}]", path, i)))
+                .collect::<Result<Vec<_>, _>>()
+                .map(Value::Array),
+            None => Ok(Value::Array(items)),
+        },
+        value => Ok(value),
+    }
+}
+
+fn coerce_object(
+    mut object: Map<String, Value>,
+    schema: &Value,
+    path: &str,
+) -> Result<Map<String, Value>, String> {
+    let Some(properties) = schema.get("properties").and_then(Value::as_object) else {
+        return Ok(object);
+    };
+    let required: Vec<&str> = schema
+        .get("required")
+        .and_then(Value::as_array)
+        .map(|required| required.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+    let keeps_unknown = matches!(
+        schema.get("additionalProperties"),
+        Some(Value::Bool(true)) | Some(Value::Object(_))
+    );
+
+    let mut coerced = Map::new();
+    for (key, property) in properties {
+        let key_path = if path.is_empty() {
+            key.clone()
+        } else {
+            format!("{}.{}", path, key)
+        };
+        let is_required = required.contains(&key.as_str());
+        let value = match object.remove(key) {
+            // An unused optional argument sent as null is the same as leaving it out
+            Some(Value::Null) if !is_required && !schema_types(property).contains(&"null") => None,
+            value => value,
+        };
+        match value.or_else(|| property.get("default").cloned()) {
+            Some(value) => {
+                coerced.insert(key.clone(), coerce_value(value, property, &key_path)?);
+            }
+            None if is_required => {
+                return Err(format!("missing required argument '{}'", key_path));
+            }
+            None => {}
+        }
+    }
+
+    if keeps_unknown {
+        coerced.extend(object);
+    } else if !object.is_empty() {
+        log::debug!(
+            "Dropped undeclared tool arguments: {:?}",
+            object.keys().collect::<Vec<_>>()
+        );
+    }
+    Ok(coerced)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "query": {"type": "string"},
+                "limit": {"type": "integer", "default": 10},
+                "score": {"type": "number"},
+                "exact": {"type": "boolean", "default": false},
+                "tags": {"type": "array", "items": {"type": "string"}},
+                "filter": {
+                    "type": "object",
+                    "properties": {"year": {"type": "integer"}}
+                }
+            },
+            "required": ["query"]
+        })
+    }
+
+    #[test]
+    fn test_stringified_number_is_coerced() {
+        let args = coerce_tool_arguments(
+            json!({"query": "rust", "limit": "5", "score": " 0.5 ", "filter": {"year": "2024"}}),
+            &schema(),
+        )
+        .expect("coerced arguments");
+        assert_eq!(args["limit"], json!(5));
+        assert_eq!(args["score"], json!(0.5));
+        assert_eq!(args["filter"]["year"], json!(2024));
+    }
+
+    #[test]
+    fn test_defaulted_optional_is_filled() {
+        let args = coerce_tool_arguments(json!({"query": "rust", "exact": null}), &schema())
+            .expect("coerced arguments");
+        assert_eq!(args, json!({"query": "rust", "limit": 10, "exact": false}));
+    }
+
+    #[test]
+    fn test_other_conversions() {
+        let args = coerce_tool_arguments(
+            json!({"query": 42, "exact": "TRUE", "tags": "[\"a\", 1]", "unknown": 1}),
+            &schema(),
+        )
+        .expect("coerced arguments");
+        assert_eq!(args["query"], json!("42"));
+        assert_eq!(args["exact"], json!(true));
+        assert_eq!(args["tags"], json!(["a", "1"]));
+        assert!(args.get("unknown").is_none());
+
+        let args = coerce_tool_arguments(
+            json!("{\"query\": \"rust\"}"),
+            &json!({"type": "object", "properties": {}, "additionalProperties": true}),
+        )
+        .expect("arguments from a JSON string");
+        assert_eq!(args, json!({"query": "rust"}));
+    }
+
+    #[test]
+    fn test_invalid_arguments_name_the_argument() {
+        let error = coerce_tool_arguments(json!({"query": "rust", "limit": "ten"}), &schema())
+            .expect_err("not a number");
+        assert_eq!(error, "argument 'limit' must be an integer, got \"ten\"");
+
+        let error =
+            coerce_tool_arguments(json!({"limit": 3}), &schema()).expect_err("missing query");
+        assert_eq!(error, "missing required argument 'query'");
+    }
+
+    #[test]
+    fn test_schema_without_properties_is_not_touched() {
+        let args = json!({"anything": "5"});
+        assert_eq!(
+            coerce_tool_arguments(args.clone(), &json!({"type": "object"})),
+            Ok(args)
+        );
+    }
+}
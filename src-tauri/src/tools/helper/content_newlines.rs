@@ -0,0 +1,204 @@
+//! Repairs file content whose line breaks a model wrote as literal `\n` escapes.
+//!
+//! Some models double-escape the content argument of file tools, so a whole file arrives as
+//! one line full of `\n` sequences. The repair is deliberately conservative: content that
+//! already has real line breaks is left alone, escapes inside string literals are kept, and a
+//! few escapes are required before anything is rewritten.
+
+use serde_json::Value;
+
+use crate::tools::{TOOL_EDIT_FILE, TOOL_WRITE_FILE};
+
+/// Tool names of common clients that write or edit files, compared case-insensitively.
+const FILE_TOOL_NAMES: &[&str] = &[
+    TOOL_WRITE_FILE,
+    TOOL_EDIT_FILE,
+    "write",
+    "edit",
+    "multiedit",
+    "notebookedit",
+    "create_file",
+    "write_to_file",
+    "replace_in_file",
+    "str_replace_editor",
+    "str_replace_based_edit_tool",
+    "apply_patch",
+];
+
+/// Argument keys holding file content, searched at any depth (e.g. `edits[].new_string`).
+const CONTENT_KEYS: &[&str] = &[
+    "content",
+    "file_text",
+    "new_content",
+    "new_string",
+    "old_string",
+    "new_str",
+    "old_str",
+    "new_source",
+    "code",
+];
+
+/// Fewest escaped line breaks outside string literals before the content is rewritten.
+const MIN_ESCAPED_LINE_BREAKS: usize = 2;
+
+pub(crate) fn is_file_tool(name: &str) -> bool {
+    FILE_TOOL_NAMES
+        .iter()
+        .any(|file_tool| name.eq_ignore_ascii_case(file_tool))
+}
+
+/// Turns escaped line breaks in the content arguments of a file tool into real ones.
+///
+/// Returns whether any argument was changed.
+pub(crate) fn normalize_file_tool_arguments(tool_name: &str, args: &mut Value) -> bool {
+    if !is_file_tool(tool_name) {
+        return false;
+    }
+    normalize_value(args)
+}
+
+fn normalize_value(value: &mut Value) -> bool {
+    match value {
+        Value::Object(map) => {
+            let mut changed = false;
+            for (key, value) in map.iter_mut() {
+                if let Value::String(text) = value {
+                    if CONTENT_KEYS.contains(&key.as_str()) {
+                        if let Some(normalized) = normalize_content_newlines(text) {
+                            *text = normalized;
+                            changed = true;
+                        }
+                    }
+                } else {
+                    changed |= normalize_value(value);
+                }
+            }
+            changed
+        }
+        Value::Array(items) => items
+            .iter_mut()
+            .fold(false, |changed, item| normalize_value(item) | changed),
+        _ => false,
+    }
+}
+
+/// Returns `text` with escaped line breaks and tabs outside string literals made real, or
+/// `None` when the text does not look like collapsed content.
+fn normalize_content_newlines(text: &str) -> Option<String> {
+    if text.contains('\n') || !text.contains("\\n") {
+        return None;
+    }
+
+    let mut output = String::with_capacity(text.len());
+    let mut quote: Option<char> = None;
+    let mut previous: Option<char> = None;
+    let mut line_breaks = 0;
+    let mut chars = text.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\\' => {
+                let Some(next) = chars.next() else {
+                    output.push(ch);
+                    break;
+                };
+                match next {
+                    'n' if quote.is_none() => {
+                        output.push('\n');
+                        line_breaks += 1;
+                    }
+                    'r' if quote.is_none() && chars.peek() == Some(&'\\') => {
+                        let mut lookahead = chars.clone();
+                        lookahead.next();
+                        if lookahead.next() == Some('n') {
+                            chars.next();
+                            chars.next();
+                            output.push_str("\r\n");
+                            line_breaks += 1;
+                        } else {
+                            output.push(ch);
+                            output.push(next);
+                        }
+                    }
+                    't' if quote.is_none() => output.push('\t'),
+                    _ => {
+                        output.push(ch);
+                        output.push(next);
+                    }
+                }
+                previous = output.chars().next_back();
+                continue;
+            }
+            '"' | '`' => match quote {
+                None => quote = Some(ch),
+                Some(open) if open == ch => quote = None,
+                Some(_) => {}
+            },
+            // An apostrophe inside a word (`it's`) does not open a string literal
+            '\'' => match quote {
+                None if !previous.is_some_and(char::is_alphanumeric) => quote = Some(ch),
+                Some('\'') => quote = None,
+                _ => {}
+            },
+            _ => {}
+        }
+        output.push(ch);
+        previous = Some(ch);
+    }
+
+    (line_breaks >= MIN_ESCAPED_LINE_BREAKS).then_some(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_collapsed_content_is_normalized() {
+        let mut args = json!({
+            "file_path": "src/main.rs",
+            "content": "fn main() {\\n    println!(\"a\\nb\");\\n}\\n"
+        });
+        assert!(normalize_file_tool_arguments("write_file", &mut args));
+        assert_eq!(
+            args["content"],
+            "fn main() {\n    println!(\"a\\nb\");\n}\n"
+        );
+        assert_eq!(args["file_path"], "src/main.rs");
+
+        let mut args = json!({
+            "edits": [{"old_string": "a", "new_string": "# It's fine\\nprint('x\\ty')\\nx = 1"}]
+        });
+        assert!(normalize_file_tool_arguments("MultiEdit", &mut args));
+        assert_eq!(
+            args["edits"][0]["new_string"],
+            "# It's fine\nprint('x\\ty')\nx = 1"
+        );
+    }
+
+    #[test]
+    fn test_intended_literals_are_preserved() {
+        // Real line breaks mean the escapes are part of the code
+        let mut args = json!({"content": "let s = \"a\\nb\";\nlet t = 1;\n"});
+        assert!(!normalize_file_tool_arguments("edit_file", &mut args));
+        assert_eq!(args["content"], "let s = \"a\\nb\";\nlet t = 1;\n");
+
+        // Escapes inside string literals
+        let mut args = json!({"content": "printf(\"%d\\n\", x); puts('\\n');"});
+        assert!(!normalize_file_tool_arguments("write_file", &mut args));
+
+        // A single escape is too little evidence
+        let mut args = json!({"new_string": "a\\nb"});
+        assert!(!normalize_file_tool_arguments("Edit", &mut args));
+
+        // Escaped backslashes stay as they are
+        let mut args = json!({"content": "C:\\\\new\\\\next\\\\nothing"});
+        assert!(!normalize_file_tool_arguments("write_file", &mut args));
+
+        // Other tools are never touched
+        let mut args = json!({"command": "printf 'a\\nb\\nc'", "content": "a\\nb\\nc"});
+        assert!(!normalize_file_tool_arguments("bash", &mut args));
+        assert_eq!(args["content"], "a\\nb\\nc");
+    }
+}
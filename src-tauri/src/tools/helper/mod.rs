@@ -1,5 +1,9 @@
+mod argument_coercion;
+mod content_newlines;
 mod output_reducer;
 mod shell_parse;
 
+pub(crate) use argument_coercion::*;
+pub(crate) use content_newlines::*;
 pub(crate) use output_reducer::*;
 pub(crate) use shell_parse::*;
@@ -51,7 +51,7 @@ impl ToolDefinition for McpToolLoad {
                 "properties": {
                     "tool_name": {
                         "type": "string",
-                        "description": "The combined name of the MCP tool (format: server__MCP__tool)"
+                        "description": "The name of the MCP tool as listed"
                     }
                 },
                 "required": ["tool_name"]
@@ -67,10 +67,11 @@ impl ToolDefinition for McpToolLoad {
             .as_str()
             .ok_or_else(|| ToolError::InvalidParams("tool_name is required".to_string()))?;
 
+        let registered_name = self.tool_manager.resolve_tool_name(tool_name).await;
         if self
             .allowed_tools
             .as_ref()
-            .is_some_and(|tools| !tools.contains(tool_name))
+            .is_some_and(|tools| !tools.contains(tool_name) && !tools.contains(&registered_name))
         {
             return Err(ToolError::Security(format!(
                 "MCP tool '{}' is not available in this workflow",
@@ -0,0 +1,206 @@
+use rust_i18n::t;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::time::{timeout, Duration};
+
+use crate::{
+    ai::{
+        chat::openai::OpenAIChat,
+        interaction::chat_completion::{AiChatEnum, ChatState},
+        traits::chat::{ChatMetadata, MessageType},
+    },
+    ccproxy::ChatProtocol,
+    constants::CFG_MCP_SAMPLING_MODEL,
+    db::MainStore,
+    mcp::{
+        client::{McpClientResult, SamplingHandler, SamplingRequest, SamplingResponse},
+        McpError,
+    },
+    tools::ApprovalRegistry,
+};
+
+/// Event emitted to the frontend when an MCP server asks for a completion that needs approval.
+pub const MCP_SAMPLING_APPROVAL_EVENT: &str = "cs://mcp-sampling-approval";
+
+/// How long a sampling request waits for the user before it is rejected.
+const APPROVAL_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// A sampling request waiting for the user's decision, sent as the event payload.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SamplingApprovalRequest {
+    pub request_id: String,
+    #[serde(flatten)]
+    pub request: SamplingRequest,
+}
+
+/// Tracks sampling requests until the user approves or rejects them.
+pub type SamplingApprovalRegistry = ApprovalRegistry<SamplingApprovalRequest>;
+
+/// Answers MCP sampling requests with the model selected for MCP sampling,
+/// falling back to the title generation model.
+pub struct ChatSamplingHandler {
+    app_handle: AppHandle,
+    approvals: Arc<SamplingApprovalRegistry>,
+}
+
+impl ChatSamplingHandler {
+    pub fn new(app_handle: AppHandle, approvals: Arc<SamplingApprovalRegistry>) -> Self {
+        Self {
+            app_handle,
+            approvals,
+        }
+    }
+
+    /// Returns the provider id and model name used for sampling.
+    fn sampling_model(main_store: &Arc<std::sync::RwLock<MainStore>>) -> Option<(i64, String)> {
+        let store = main_store.read().ok()?;
+        [CFG_MCP_SAMPLING_MODEL, "conversation_title_gen_model"]
+            .into_iter()
+            .find_map(|key| {
+                let config: Value = store.get_config(key, json!({}));
+                let provider_id = config["id"].as_i64().filter(|id| *id > 0)?;
+                let model = config["model"].as_str().filter(|m| !m.trim().is_empty())?;
+                Some((provider_id, model.to_string()))
+            })
+    }
+
+    /// Waits for the user to approve a sampling request.
+    async fn await_approval(&self, request: &SamplingRequest) -> McpClientResult<()> {
+        let approval = SamplingApprovalRequest {
+            request_id: uuid::Uuid::new_v4().to_string(),
+            request: request.clone(),
+        };
+        let receiver = self
+            .approvals
+            .register(approval.request_id.clone(), approval.clone());
+
+        if let Err(e) = self.app_handle.emit(MCP_SAMPLING_APPROVAL_EVENT, &approval) {
+            self.approvals.cancel(&approval.request_id);
+            return Err(McpError::ClientCallError(e.to_string()));
+        }
+
+        match timeout(APPROVAL_TIMEOUT, receiver).await {
+            Ok(Ok(true)) => Ok(()),
+            Ok(Ok(false)) | Ok(Err(_)) => Err(McpError::ClientCallError(
+                t!(
+                    "mcp.client.sampling_rejected",
+                    server_name = request.server_name
+                )
+                .to_string(),
+            )),
+            Err(_) => {
+                self.approvals.cancel(&approval.request_id);
+                Err(McpError::Timeout(
+                    t!(
+                        "mcp.client.sampling_approval_timeout",
+                        server_name = request.server_name,
+                        seconds = APPROVAL_TIMEOUT.as_secs()
+                    )
+                    .to_string(),
+                ))
+            }
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl SamplingHandler for ChatSamplingHandler {
+    async fn create_message(&self, request: SamplingRequest) -> McpClientResult<SamplingResponse> {
+        if request.requires_approval {
+            self.await_approval(&request).await?;
+        }
+
+        let chat_state = self
+            .app_handle
+            .try_state::<Arc<ChatState>>()
+            .map(|state| state.inner().clone())
+            .ok_or_else(|| {
+                McpError::General(t!("mcp.client.sampling_chat_state_unavailable").to_string())
+            })?;
+        let (provider_id, model_name) =
+            Self::sampling_model(&chat_state.main_store).ok_or_else(|| {
+                McpError::ClientConfigError(
+                    t!("mcp.client.sampling_model_not_configured").to_string(),
+                )
+            })?;
+
+        let mut messages = Vec::with_capacity(request.messages.len() + 1);
+        if let Some(system_prompt) = &request.system_prompt {
+            messages.push(json!({"role": "system", "content": system_prompt}));
+        }
+        messages.extend(
+            request
+                .messages
+                .iter()
+                .map(|m| json!({"role": m.role, "content": m.text})),
+        );
+
+        let chat_id = format!("mcp_sampling_{}", request.server_name);
+        let chat_interface = {
+            let mut chats_guard = chat_state.chats.lock().await;
+            chats_guard
+                .entry(ChatProtocol::OpenAI)
+                .or_default()
+                .entry(chat_id.clone())
+                .or_insert_with(|| crate::create_chat!(chat_state.main_store))
+                .clone()
+        };
+
+        let metadata = ChatMetadata {
+            max_tokens: Some(request.max_tokens),
+            temperature: request.temperature,
+            stop: (!request.stop_sequences.is_empty()).then(|| request.stop_sequences.clone()),
+            ..Default::default()
+        };
+
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let sampling_failed = |error: String| {
+            McpError::ClientCallError(
+                t!(
+                    "mcp.client.sampling_failed",
+                    server_name = request.server_name,
+                    error = error
+                )
+                .to_string(),
+            )
+        };
+        chat_interface
+            .chat(
+                provider_id,
+                &model_name,
+                chat_id,
+                messages,
+                None,
+                Some(metadata),
+                move |chunk| {
+                    let _ = tx.send(chunk);
+                },
+            )
+            .await
+            .map_err(|e| sampling_failed(e.to_string()))?;
+
+        let mut text = String::new();
+        while let Some(chunk) = rx.recv().await {
+            match chunk.r#type {
+                MessageType::Text => text.push_str(&chunk.chunk),
+                MessageType::Finished => break,
+                MessageType::Error => return Err(sampling_failed(chunk.chunk.clone())),
+                _ => {}
+            }
+        }
+
+        log::info!(
+            "Answered sampling request from MCP server '{}' with model {}",
+            request.server_name,
+            model_name
+        );
+        Ok(SamplingResponse {
+            model: model_name,
+            text: text.trim().to_string(),
+            stop_reason: Some("endTurn".to_string()),
+        })
+    }
+}
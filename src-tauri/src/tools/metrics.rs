@@ -0,0 +1,206 @@
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::constants::{CFG_PERSIST_TOOL_METRICS, CFG_TOOL_METRICS_SNAPSHOT};
+use crate::db::MainStore;
+
+/// How often persisted metrics are written back to the store.
+const PERSIST_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Call statistics of a single tool.
+#[derive(Debug, Default)]
+pub struct ToolCallStats {
+    /// Number of finished calls, successful or not
+    pub calls: AtomicU64,
+    /// Calls that returned an error, an error result or panicked
+    pub errors: AtomicU64,
+    /// Total execution latency in ms
+    pub total_latency_ms: AtomicU64,
+    /// Latency of the most recent call in ms
+    pub last_latency_ms: AtomicU64,
+}
+
+/// Per-tool invocation metrics collected by the `ToolManager`.
+#[derive(Debug, Default)]
+pub struct ToolMetrics {
+    per_tool: DashMap<String, Arc<ToolCallStats>>,
+}
+
+impl ToolMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a finished call of `tool_name`.
+    pub fn record(&self, tool_name: &str, latency: Duration, failed: bool) {
+        let stats = self
+            .per_tool
+            .entry(tool_name.to_string())
+            .or_default()
+            .clone();
+        let latency_ms = latency.as_millis() as u64;
+        stats.calls.fetch_add(1, Ordering::Relaxed);
+        if failed {
+            stats.errors.fetch_add(1, Ordering::Relaxed);
+        }
+        stats
+            .total_latency_ms
+            .fetch_add(latency_ms, Ordering::Relaxed);
+        stats.last_latency_ms.store(latency_ms, Ordering::Relaxed);
+    }
+
+    /// Total number of recorded calls over all tools.
+    pub fn total_calls(&self) -> u64 {
+        self.per_tool
+            .iter()
+            .map(|entry| entry.value().calls.load(Ordering::Relaxed))
+            .sum()
+    }
+
+    /// Returns the metrics of every tool that has been called, sorted by tool name.
+    pub fn snapshot(&self) -> Vec<ToolMetricsSnapshot> {
+        let mut snapshot: Vec<ToolMetricsSnapshot> = self
+            .per_tool
+            .iter()
+            .map(|entry| {
+                let stats = entry.value();
+                let calls = stats.calls.load(Ordering::Relaxed);
+                let errors = stats.errors.load(Ordering::Relaxed);
+                let total_latency_ms = stats.total_latency_ms.load(Ordering::Relaxed);
+                let (avg_latency_ms, error_rate) = if calls > 0 {
+                    (
+                        total_latency_ms as f64 / calls as f64,
+                        errors as f64 / calls as f64,
+                    )
+                } else {
+                    (0.0, 0.0)
+                };
+                ToolMetricsSnapshot {
+                    tool_name: entry.key().clone(),
+                    calls,
+                    errors,
+                    error_rate,
+                    total_latency_ms,
+                    avg_latency_ms,
+                    last_latency_ms: stats.last_latency_ms.load(Ordering::Relaxed),
+                }
+            })
+            .collect();
+        snapshot.sort_by(|a, b| a.tool_name.cmp(&b.tool_name));
+        snapshot
+    }
+
+    /// Loads previously persisted metrics, replacing the stats of the same tools.
+    pub fn restore(&self, snapshot: Vec<ToolMetricsSnapshot>) {
+        for entry in snapshot {
+            self.per_tool.insert(
+                entry.tool_name,
+                Arc::new(ToolCallStats {
+                    calls: AtomicU64::new(entry.calls),
+                    errors: AtomicU64::new(entry.errors),
+                    total_latency_ms: AtomicU64::new(entry.total_latency_ms),
+                    last_latency_ms: AtomicU64::new(entry.last_latency_ms),
+                }),
+            );
+        }
+    }
+
+    pub fn reset(&self) {
+        self.per_tool.clear();
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ToolMetricsSnapshot {
+    pub tool_name: String,
+    pub calls: u64,
+    pub errors: u64,
+    pub error_rate: f64,
+    pub total_latency_ms: u64,
+    pub avg_latency_ms: f64,
+    pub last_latency_ms: u64,
+}
+
+/// Restores persisted metrics and keeps writing them back while the app runs.
+///
+/// Does nothing unless `persist_tool_metrics` is enabled in the settings.
+pub fn spawn_metrics_persistence(
+    metrics: Arc<ToolMetrics>,
+    main_store: Arc<std::sync::RwLock<MainStore>>,
+) {
+    let persisted = match main_store.read() {
+        Ok(store) if store.get_config(CFG_PERSIST_TOOL_METRICS, false) => {
+            store.get_config(CFG_TOOL_METRICS_SNAPSHOT, Vec::<ToolMetricsSnapshot>::new())
+        }
+        _ => return,
+    };
+    metrics.restore(persisted);
+
+    tokio::spawn(async move {
+        let mut saved_calls = metrics.total_calls();
+        let mut interval = tokio::time::interval(PERSIST_INTERVAL);
+        loop {
+            interval.tick().await;
+            let total_calls = metrics.total_calls();
+            if total_calls == saved_calls {
+                continue;
+            }
+            let value = match serde_json::to_value(metrics.snapshot()) {
+                Ok(value) => value,
+                Err(e) => {
+                    log::warn!("Failed to serialize tool metrics: {}", e);
+                    continue;
+                }
+            };
+            match main_store.write() {
+                Ok(mut store) => {
+                    if let Err(e) = store.set_config(CFG_TOOL_METRICS_SNAPSHOT, &value) {
+                        log::warn!("Failed to persist tool metrics: {}", e);
+                    } else {
+                        saved_calls = total_calls;
+                    }
+                }
+                Err(e) => log::warn!("Failed to lock main store for tool metrics: {}", e),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_aggregates_calls() {
+        let metrics = ToolMetrics::new();
+        metrics.record("search", Duration::from_millis(100), false);
+        metrics.record("search", Duration::from_millis(300), true);
+        metrics.record("calculate", Duration::from_millis(1), false);
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.len(), 2);
+        assert_eq!(snapshot[0].tool_name, "calculate");
+
+        let search = &snapshot[1];
+        assert_eq!(search.calls, 2);
+        assert_eq!(search.errors, 1);
+        assert_eq!(search.error_rate, 0.5);
+        assert_eq!(search.avg_latency_ms, 200.0);
+        assert_eq!(search.last_latency_ms, 300);
+        assert_eq!(metrics.total_calls(), 3);
+    }
+
+    #[test]
+    fn test_restore_round_trip() {
+        let metrics = ToolMetrics::new();
+        metrics.record("search", Duration::from_millis(50), true);
+        let snapshot = metrics.snapshot();
+
+        let restored = ToolMetrics::new();
+        restored.restore(snapshot.clone());
+        assert_eq!(restored.snapshot(), snapshot);
+    }
+}
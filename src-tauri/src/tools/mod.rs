@@ -1,5 +1,9 @@
+mod approval;
+mod calculator;
 mod constants;
 mod error;
+mod execute_command;
+mod format_hook;
 mod fs;
 mod git_diff;
 mod git_inspect;
@@ -7,29 +11,42 @@ pub(crate) mod helper;
 mod interaction;
 mod llm_output;
 mod mcp_loader;
+mod mcp_sampling;
+mod metrics;
 mod search;
 mod shell;
 mod shell_output;
 mod skill;
+mod time;
 mod todo_manager;
 mod tool_manager;
 mod types;
 mod web_fetch;
 mod web_search;
 
+pub use approval::ApprovalRegistry;
+pub use calculator::Calculator;
 pub use constants::*;
 pub use error::ToolError;
+pub use execute_command::{CommandApprovalRegistry, CommandApprovalRequest, ExecuteCommand};
+pub use format_hook::{FormatHook, FormatHookConfig};
 pub use fs::*;
 pub use git_diff::GitDiff;
 pub use git_inspect::GitInspect;
 pub use interaction::*;
 pub use mcp_loader::McpToolLoad;
+pub use mcp_sampling::{ChatSamplingHandler, SamplingApprovalRegistry, SamplingApprovalRequest};
+pub use metrics::{spawn_metrics_persistence, ToolMetrics, ToolMetricsSnapshot};
 pub use search::*;
 pub use shell::*;
 pub use skill::*;
+pub use time::{format_current_time, GetCurrentTime, TimeZoneSpec};
 pub use todo_manager::*;
 pub use tool_manager::{NativeToolResult, ToolDefinition, ToolManager};
 pub use types::ToolScope;
-pub use types::{ToolCallResult, ToolCategory};
+pub use types::{
+    AvailableTool, ToolCallResult, ToolCategory, ToolOutputSink, ToolOutputStream, ToolRerunResult,
+    ToolSource,
+};
 pub use web_fetch::WebFetch;
 pub use web_search::WebSearch;
@@ -0,0 +1,230 @@
+use async_trait::async_trait;
+use chrono::{DateTime, FixedOffset, Local, Utc};
+use rust_i18n::t;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+use crate::{
+    ai::traits::chat::MCPToolDeclaration,
+    constants::CFG_TIMEZONE,
+    db::MainStore,
+    tools::{
+        error::ToolError, NativeToolResult, ToolCallResult, ToolCategory, ToolDefinition,
+        ToolScope, TOOL_GET_CURRENT_TIME,
+    },
+};
+
+/// A timezone accepted by the time tool and the chat time injection.
+///
+/// Only the system local zone and fixed UTC offsets are supported, which covers
+/// `UTC`, `GMT+8`, `+05:30` and similar spellings without a tz database.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TimeZoneSpec {
+    Local,
+    Fixed(FixedOffset),
+}
+
+impl TimeZoneSpec {
+    /// Parses a timezone string, an empty string or `local` selects the system zone.
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        let lower = value.to_ascii_lowercase();
+        if lower.is_empty() || lower == "local" {
+            return Some(Self::Local);
+        }
+
+        let offset = lower
+            .strip_prefix("utc")
+            .or_else(|| lower.strip_prefix("gmt"))
+            .unwrap_or(&lower);
+        if offset.is_empty() || offset == "z" || lower == "z" {
+            return FixedOffset::east_opt(0).map(Self::Fixed);
+        }
+
+        let (sign, rest) = match offset.as_bytes().first()? {
+            b'+' => (1, &offset[1..]),
+            b'-' => (-1, &offset[1..]),
+            _ => return None,
+        };
+        let (hours, minutes) = match rest.split_once(':') {
+            Some((h, m)) => (h, m),
+            None if rest.len() == 4 => rest.split_at(2),
+            None => (rest, "0"),
+        };
+        let hours: i32 = hours.parse().ok()?;
+        let minutes: i32 = minutes.parse().ok()?;
+        if hours > 14 || minutes >= 60 {
+            return None;
+        }
+        FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60)).map(Self::Fixed)
+    }
+
+    /// Converts a UTC instant into this timezone.
+    pub fn at(&self, now: DateTime<Utc>) -> DateTime<FixedOffset> {
+        match self {
+            Self::Local => now.with_timezone(&Local).fixed_offset(),
+            Self::Fixed(offset) => now.with_timezone(offset),
+        }
+    }
+}
+
+/// Formats the current time for the chat system prompt, e.g. `2024-06-01 20:00:00 +08:00 (Saturday)`.
+pub fn format_current_time(timezone: &TimeZoneSpec, now: DateTime<Utc>) -> String {
+    timezone
+        .at(now)
+        .format("%Y-%m-%d %H:%M:%S %:z (%A)")
+        .to_string()
+}
+
+/// Returns the current date and time so models do not have to guess it.
+#[derive(Default)]
+pub struct GetCurrentTime {
+    /// Used to read the configured default timezone, `None` falls back to the local zone.
+    pub main_store: Option<Arc<std::sync::RwLock<MainStore>>>,
+}
+
+impl GetCurrentTime {
+    pub fn new(main_store: Arc<std::sync::RwLock<MainStore>>) -> Self {
+        Self {
+            main_store: Some(main_store),
+        }
+    }
+
+    fn configured_timezone(&self) -> String {
+        self.main_store
+            .as_ref()
+            .and_then(|store| store.read().ok())
+            .map(|store| store.get_config(CFG_TIMEZONE, String::new()))
+            .unwrap_or_default()
+    }
+}
+
+#[async_trait]
+impl ToolDefinition for GetCurrentTime {
+    fn name(&self) -> &str {
+        TOOL_GET_CURRENT_TIME
+    }
+
+    fn description(&self) -> &str {
+        "Get the current date and time. Use this whenever the answer depends on today's date, \
+        the current time, or the day of the week instead of guessing."
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::System
+    }
+
+    fn scope(&self) -> ToolScope {
+        ToolScope::Both
+    }
+
+    fn tool_calling_spec(&self) -> MCPToolDeclaration {
+        MCPToolDeclaration {
+            name: self.name().to_string(),
+            description: self.description().to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "timezone": {
+                        "type": "string",
+                        "description": "Optional timezone as 'UTC', 'local' or a UTC offset such as '+08:00' or 'UTC-5'. Defaults to the configured timezone."
+                    }
+                }
+            }),
+            output_schema: None,
+            disabled: false,
+            scope: Some(self.scope()),
+        }
+    }
+
+    async fn call(&self, params: Value) -> NativeToolResult {
+        let timezone = params
+            .get("timezone")
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .unwrap_or_else(|| self.configured_timezone());
+        let spec = TimeZoneSpec::parse(&timezone).ok_or_else(|| {
+            ToolError::InvalidParams(
+                t!("tools.time.invalid_timezone", timezone = timezone).to_string(),
+            )
+        })?;
+
+        let now = spec.at(Utc::now());
+        let rfc3339 = now.to_rfc3339();
+        let structured = json!({
+            "datetime": rfc3339,
+            "timezone": now.format("%:z").to_string(),
+            "weekday": now.format("%A").to_string(),
+            "unix_timestamp": now.timestamp(),
+        });
+
+        Ok(ToolCallResult::success(Some(rfc3339), Some(structured)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_parse_timezone() {
+        let east8 = FixedOffset::east_opt(8 * 3600).unwrap();
+        assert_eq!(TimeZoneSpec::parse(""), Some(TimeZoneSpec::Local));
+        assert_eq!(
+            TimeZoneSpec::parse("UTC"),
+            Some(TimeZoneSpec::Fixed(FixedOffset::east_opt(0).unwrap()))
+        );
+        assert_eq!(
+            TimeZoneSpec::parse("+08:00"),
+            Some(TimeZoneSpec::Fixed(east8))
+        );
+        assert_eq!(
+            TimeZoneSpec::parse("GMT+8"),
+            Some(TimeZoneSpec::Fixed(east8))
+        );
+        assert_eq!(
+            TimeZoneSpec::parse("-0530"),
+            Some(TimeZoneSpec::Fixed(
+                FixedOffset::west_opt(5 * 3600 + 30 * 60).unwrap()
+            ))
+        );
+        assert_eq!(TimeZoneSpec::parse("Mars/Olympus"), None);
+        assert_eq!(TimeZoneSpec::parse("+25:00"), None);
+    }
+
+    #[test]
+    fn test_format_current_time() {
+        let now = Utc.with_ymd_and_hms(2024, 6, 1, 12, 0, 0).unwrap();
+        let spec = TimeZoneSpec::parse("+08:00").unwrap();
+        assert_eq!(
+            format_current_time(&spec, now),
+            "2024-06-01 20:00:00 +08:00 (Saturday)"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_current_time_returns_rfc3339() {
+        let tool = GetCurrentTime::default();
+        let before = Utc::now();
+        let result = tool
+            .call(json!({ "timezone": "+02:00" }))
+            .await
+            .expect("tool call should succeed");
+
+        let content = result.content.expect("content should be set");
+        let parsed = DateTime::parse_from_rfc3339(&content).expect("content must be RFC3339");
+        assert_eq!(parsed.offset().local_minus_utc(), 2 * 3600);
+        assert!((parsed.with_timezone(&Utc) - before).num_seconds().abs() < 5);
+
+        let structured = result.structured_content.unwrap();
+        assert_eq!(structured["timezone"], "+02:00");
+    }
+
+    #[tokio::test]
+    async fn test_get_current_time_rejects_unknown_timezone() {
+        let tool = GetCurrentTime::default();
+        let result = tool.call(json!({ "timezone": "Mars/Olympus" })).await;
+        assert!(matches!(result, Err(ToolError::InvalidParams(_))));
+    }
+}
@@ -2,6 +2,7 @@ use crate::ai::traits::chat::MCPToolDeclaration;
 use crate::db::MainStore;
 use crate::tools::{NativeToolResult, ToolCallResult, ToolCategory, ToolDefinition, ToolError};
 use async_trait::async_trait;
+use serde::Deserialize;
 use serde_json::{json, Value};
 use std::sync::Arc;
 
@@ -33,6 +34,148 @@ fn single_item_bootstrap_reminder() -> String {
     "Todo tracking is usually for multi-step or interruption-prone work. If this request is a single direct step, continue without expanding the todo list unless the user explicitly asked for tracking.".to_string()
 }
 
+/// What happens to the rest of the plan when a todo fails.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TodoFailurePolicy {
+    /// Only the todos depending on the failed one fail, independent todos keep running
+    #[default]
+    Continue,
+    /// Every unfinished todo fails with it
+    FailFast,
+}
+
+fn todo_id(item: &Value) -> &str {
+    item["id"].as_str().unwrap_or("")
+}
+
+fn todo_dependencies(item: &Value) -> Vec<&str> {
+    item["depends_on"]
+        .as_array()
+        .map(|ids| ids.iter().filter_map(|id| id.as_str()).collect())
+        .unwrap_or_default()
+}
+
+/// Whether a todo no longer blocks the todos depending on it.
+fn is_todo_done(item: &Value) -> bool {
+    matches!(
+        item["status"].as_str(),
+        Some("completed") | Some("data_missing")
+    )
+}
+
+fn is_todo_open(item: &Value) -> bool {
+    matches!(
+        item["status"].as_str(),
+        Some("pending") | Some("in_progress")
+    )
+}
+
+/// Reads the `depends_on` of a new task, accepting string or numeric ids.
+fn parse_todo_dependencies(task: &Value, known_ids: &[String]) -> Result<Vec<String>, ToolError> {
+    let Some(raw) = task.get("depends_on").filter(|value| !value.is_null()) else {
+        return Ok(Vec::new());
+    };
+    let raw = raw.as_array().ok_or_else(|| {
+        ToolError::InvalidParams("depends_on must be an array of todo IDs".to_string())
+    })?;
+
+    let mut ids: Vec<String> = Vec::new();
+    for value in raw {
+        let id = match value {
+            Value::String(id) => id.trim().to_string(),
+            Value::Number(id) => id.to_string(),
+            other => {
+                return Err(ToolError::InvalidParams(format!(
+                    "depends_on must contain todo IDs, got {}",
+                    other
+                )))
+            }
+        };
+        // Only earlier todos can be depended on, which keeps the plan free of cycles
+        if !known_ids.contains(&id) {
+            return Err(ToolError::InvalidParams(format!(
+                "depends_on references todo '{}', which does not exist before this task",
+                id
+            )));
+        }
+        if !ids.contains(&id) {
+            ids.push(id);
+        }
+    }
+    Ok(ids)
+}
+
+/// Dependencies of `item` that are not done yet.
+fn unfinished_dependencies<'a>(list: &'a [Value], item: &Value) -> Vec<&'a str> {
+    todo_dependencies(item)
+        .into_iter()
+        .filter_map(|dep| list.iter().find(|other| todo_id(other) == dep))
+        .filter(|dep| !is_todo_done(dep))
+        .map(todo_id)
+        .collect()
+}
+
+/// Groups the open todos into layers by their dependencies. The todos of a layer don't
+/// depend on each other and can run in parallel once the earlier layers are done, so the
+/// first layer is what can run right now. Todos waiting on a failed todo are left out.
+pub(crate) fn todo_execution_layers(list: &[Value]) -> Vec<Vec<String>> {
+    let mut remaining: Vec<&Value> = list.iter().filter(|item| is_todo_open(item)).collect();
+    let mut scheduled: Vec<&str> = Vec::new();
+    let mut layers = Vec::new();
+
+    loop {
+        let (ready, blocked): (Vec<&Value>, Vec<&Value>) =
+            remaining.into_iter().partition(|item| {
+                todo_dependencies(item).into_iter().all(|dep| {
+                    scheduled.contains(&dep)
+                        || list
+                            .iter()
+                            .find(|other| todo_id(other) == dep)
+                            .map_or(true, is_todo_done)
+                })
+            });
+        if ready.is_empty() {
+            break;
+        }
+        scheduled.extend(ready.iter().map(|item| todo_id(item)));
+        layers.push(ready.iter().map(|item| todo_id(item).to_string()).collect());
+        remaining = blocked;
+    }
+    layers
+}
+
+/// Fails the open todos affected by the failure of `failed_id` and returns their IDs.
+fn propagate_todo_failure(
+    list: &mut [Value],
+    failed_id: &str,
+    policy: TodoFailurePolicy,
+) -> Vec<String> {
+    let mut failed = vec![failed_id.to_string()];
+    let mut affected = Vec::new();
+
+    // Dependencies always point at earlier todos, so one pass in list order reaches
+    // every transitive dependent
+    for item in list.iter_mut() {
+        if !is_todo_open(item) {
+            continue;
+        }
+        let hit = match policy {
+            TodoFailurePolicy::FailFast => true,
+            TodoFailurePolicy::Continue => todo_dependencies(item)
+                .iter()
+                .any(|dep| failed.iter().any(|id| id == dep)),
+        };
+        if hit {
+            let id = todo_id(item).to_string();
+            item["status"] = json!("failed");
+            failed.push(id.clone());
+            affected.push(id);
+        }
+    }
+    affected
+}
+
 /// Helper to get and set todo list in DB
 async fn get_db_todo_list(
     store: &Arc<std::sync::RwLock<MainStore>>,
@@ -107,6 +250,7 @@ impl ToolDefinition for TodoCreateTool {
         ## Task Fields\n\
         - **subject**: A brief, actionable title in imperative form (e.g., \"Fix authentication bug in login flow\")\n\
         - **description**: Detailed description of what needs to be done, including context and acceptance criteria\n\
+        - **depends_on**: Optional IDs of earlier tasks that must be completed first. Tasks whose dependencies are done can run in parallel, e.g. independent searches in one response\n\
         - New tasks are created with status `pending` and numeric string IDs assigned in list order."
     }
 
@@ -131,7 +275,12 @@ impl ToolDefinition for TodoCreateTool {
                             "type": "object",
                             "properties": {
                                 "subject": { "type": "string", "description": "Brief title of the task" },
-                                "description": { "type": "string", "description": "Detailed description of the task" }
+                                "description": { "type": "string", "description": "Detailed description of the task" },
+                                "depends_on": {
+                                    "type": "array",
+                                    "items": { "type": "string" },
+                                    "description": "IDs of earlier tasks that must be completed before this one"
+                                }
                             },
                             "required": ["subject", "description"]
                         },
@@ -144,7 +293,12 @@ impl ToolDefinition for TodoCreateTool {
                         "description": "append: add follow-up tasks to the current active plan; replace: clear the current todo list and reset it with a fresh plan"
                     },
                     "subject": { "type": "string", "description": "Brief title (if creating a single task)" },
-                    "description": { "type": "string", "description": "Detailed description (if creating a single task)" }
+                    "description": { "type": "string", "description": "Detailed description (if creating a single task)" },
+                    "depends_on": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "IDs of existing tasks that must be completed first (if creating a single task)"
+                    }
                 },
                 "description": "Provide 'tasks' array for bulk creation, or 'subject'/'description' for a single item."
             }),
@@ -184,13 +338,19 @@ impl ToolDefinition for TodoCreateTool {
 
         for task in tasks_to_create {
             let new_id = (list.len() + 1).to_string();
-            let new_item = json!({
+            let known_ids: Vec<String> =
+                list.iter().map(|item| todo_id(item).to_string()).collect();
+            let depends_on = parse_todo_dependencies(&task, &known_ids)?;
+            let mut new_item = json!({
                 "id": new_id,
                 "subject": task["subject"].as_str().unwrap_or("Untitled"),
                 "description": task["description"].as_str().unwrap_or(""),
                 "status": "pending",
                 "created_at": chrono::Local::now().to_rfc3339()
             });
+            if !depends_on.is_empty() {
+                new_item["depends_on"] = json!(depends_on);
+            }
             list.push(new_item);
             created_ids.push(new_id);
         }
@@ -251,7 +411,9 @@ impl ToolDefinition for TodoListTool {
         - To check overall progress on the project\n\
         - After completing a task, to check whether any pending work remains\n\n\
         ## Output\n\
-        Returns one line per task in this format: `[status] subject (ID: id)`.\n\
+        Returns one line per task in this format: `[status] subject (ID: id)`, followed by \
+        `blocked by: ...` for tasks waiting on unfinished dependencies, and a final line with \
+        the tasks that are ready and can run in parallel.\n\
         - **id**: Task identifier (use with todo_get, todo_update)\n\
         - **subject**: Brief description of the task\n\
         - **status**: `pending`, `in_progress`, `completed`, `deleted`, `failed`, or `data_missing`"
@@ -285,24 +447,37 @@ impl ToolDefinition for TodoListTool {
                 })),
             ));
         }
-        let output = list
+        let mut output = list
             .iter()
             .map(|item| {
-                format!(
+                let line = format!(
                     "[{}] {} (ID: {})",
                     item["status"].as_str().unwrap_or("?"),
                     item["subject"].as_str().unwrap_or("Untitled"),
                     item["id"].as_str().unwrap_or("?")
-                )
+                );
+                let blocked_by = unfinished_dependencies(&list, item);
+                if is_todo_open(item) && !blocked_by.is_empty() {
+                    format!("{} blocked by: {}", line, blocked_by.join(", "))
+                } else {
+                    line
+                }
             })
             .collect::<Vec<_>>()
             .join("\n");
+        let layers = todo_execution_layers(&list);
+        let ready = layers.first().cloned().unwrap_or_default();
+        if ready.len() > 1 {
+            output.push_str(&format!("\nReady to run in parallel: {}", ready.join(", ")));
+        }
         let count = list.len();
         Ok(ToolCallResult::success(
             Some(output),
             Some(json!({
                 "items": list,
-                "count": count
+                "count": count,
+                "ready": ready,
+                "layers": layers
             })),
         ))
     }
@@ -332,7 +507,9 @@ impl ToolDefinition for TodoUpdateTool {
         Status progresses: `pending` → `in_progress` → `completed`.\n\
         Use `data_missing` when the required data could not be obtained but the task can be skipped.\n\
         Use `failed` when the task encountered an unrecoverable error.\n\
-        Use `deleted` to permanently remove a task.\n\n\
+        Use `deleted` to permanently remove a task.\n\
+        A task can only start or complete once the tasks it depends on are completed. \
+        When a task fails, the tasks depending on it fail too.\n\n\
         **Important**: When a task's data cannot be obtained after reasonable attempts, \
         mark it as `data_missing` rather than retrying indefinitely.\n\n\
         This tool only updates task status. It does not edit subject or description."
@@ -381,18 +558,46 @@ impl ToolDefinition for TodoUpdateTool {
 
         let mut list = get_db_todo_list(&self.main_store, &self.session_id).await?;
         let mut found = false;
+        let mut also_failed = Vec::new();
 
         if status == "deleted" {
             list.retain(|item| item["id"].as_str().map_or(false, |id| id != todo_id));
-            found = true;
-        } else {
+            // Dependents of a removed task no longer wait for it
             for item in list.iter_mut() {
-                if item["id"].as_str().map_or(false, |id| id == todo_id) {
-                    item["status"] = json!(status);
-                    found = true;
-                    break;
+                if let Some(deps) = item["depends_on"].as_array_mut() {
+                    deps.retain(|dep| dep.as_str() != Some(todo_id));
+                }
+            }
+            found = true;
+        } else if let Some(index) = list
+            .iter()
+            .position(|item| item["id"].as_str().map_or(false, |id| id == todo_id))
+        {
+            if matches!(status, "in_progress" | "completed") {
+                let blocked_by = unfinished_dependencies(&list, &list[index]);
+                if !blocked_by.is_empty() {
+                    return Err(ToolError::InvalidParams(format!(
+                        "Todo item {} depends on unfinished task(s) {}. Finish them first, or mark them data_missing if they cannot be done.",
+                        todo_id,
+                        blocked_by.join(", ")
+                    )));
                 }
             }
+            list[index]["status"] = json!(status);
+            if status == "failed" {
+                let policy = self
+                    .main_store
+                    .read()
+                    .map(|store| {
+                        store.get_config(
+                            crate::constants::CFG_WORKFLOW_TODO_FAILURE_POLICY,
+                            TodoFailurePolicy::default(),
+                        )
+                    })
+                    .unwrap_or_default();
+                also_failed = propagate_todo_failure(&mut list, todo_id, policy);
+            }
+            found = true;
         }
 
         if !found {
@@ -404,11 +609,22 @@ impl ToolDefinition for TodoUpdateTool {
         }
 
         save_db_todo_list(&self.main_store, &self.session_id, list).await?;
+        let content = if also_failed.is_empty() {
+            format!("Updated todo item {} to {}", todo_id, status)
+        } else {
+            format!(
+                "Updated todo item {} to {}. Also marked failed: {}",
+                todo_id,
+                status,
+                also_failed.join(", ")
+            )
+        };
         Ok(ToolCallResult::success(
-            Some(format!("Updated todo item {} to {}", todo_id, status)),
+            Some(content),
             Some(json!({
                 "todo_id": todo_id,
-                "status": status
+                "status": status,
+                "also_failed": also_failed
             })),
         ))
     }
@@ -713,4 +929,105 @@ mod tests {
         assert!(message.contains("status must be one of"));
         assert!(message.contains("done"));
     }
+
+    fn todo(id: &str, status: &str, depends_on: &[&str]) -> Value {
+        json!({ "id": id, "subject": id, "status": status, "depends_on": depends_on })
+    }
+
+    #[test]
+    fn execution_layers_group_independent_todos() {
+        let list = vec![
+            todo("1", "completed", &[]),
+            todo("2", "pending", &["1"]),
+            todo("3", "pending", &[]),
+            todo("4", "pending", &["2", "3"]),
+            todo("5", "pending", &["4"]),
+            todo("6", "failed", &[]),
+            todo("7", "pending", &["6"]),
+        ];
+
+        assert_eq!(
+            todo_execution_layers(&list),
+            vec![
+                vec!["2".to_string(), "3".to_string()],
+                vec!["4".to_string()],
+                vec!["5".to_string()],
+            ]
+        );
+    }
+
+    #[test]
+    fn failure_policy_decides_which_todos_fail_with_it() {
+        let plan = vec![
+            todo("1", "failed", &[]),
+            todo("2", "pending", &["1"]),
+            todo("3", "in_progress", &[]),
+            todo("4", "pending", &["2"]),
+            todo("5", "completed", &[]),
+        ];
+
+        let mut list = plan.clone();
+        assert_eq!(
+            propagate_todo_failure(&mut list, "1", TodoFailurePolicy::Continue),
+            vec!["2", "4"]
+        );
+        assert_eq!(list[2]["status"], "in_progress");
+
+        let mut list = plan;
+        assert_eq!(
+            propagate_todo_failure(&mut list, "1", TodoFailurePolicy::FailFast),
+            vec!["2", "3", "4"]
+        );
+        assert_eq!(list[4]["status"], "completed");
+    }
+
+    #[tokio::test]
+    async fn test_todo_dependencies_gate_updates() {
+        let (store, session_id) = setup_test_db().await;
+
+        let create_tool = TodoCreateTool {
+            session_id: session_id.clone(),
+            main_store: store.clone(),
+        };
+        create_tool
+            .call(json!({
+                "tasks": [
+                    { "subject": "Search A", "description": "First" },
+                    { "subject": "Search B", "description": "Second" },
+                    { "subject": "Summarize", "description": "Third", "depends_on": ["1", 2] }
+                ]
+            }))
+            .await
+            .unwrap();
+
+        let error = create_tool
+            .call(json!({ "subject": "Later", "description": "", "depends_on": ["9"] }))
+            .await
+            .expect_err("unknown dependency should be rejected");
+        assert!(error.to_string().contains("does not exist"));
+
+        let list_tool = TodoListTool {
+            session_id: session_id.clone(),
+            main_store: store.clone(),
+        };
+        let content = list_tool.call(json!({})).await.unwrap().content.unwrap();
+        assert!(content.contains("(ID: 3) blocked by: 1, 2"));
+        assert!(content.contains("Ready to run in parallel: 1, 2"));
+
+        let update_tool = TodoUpdateTool {
+            session_id,
+            main_store: store,
+        };
+        let error = update_tool
+            .call(json!({ "todo_id": "3", "status": "in_progress" }))
+            .await
+            .expect_err("blocked todo should not start");
+        assert!(error.to_string().contains("unfinished task(s) 1, 2"));
+
+        let res = update_tool
+            .call(json!({ "todo_id": "2", "status": "failed" }))
+            .await
+            .unwrap();
+        assert!(res.content.unwrap().contains("Also marked failed: 3"));
+    }
 }
@@ -4,18 +4,26 @@ use rust_i18n::t;
 use serde_json::{json, Value};
 use std::collections::{HashMap, HashSet};
 use std::panic::AssertUnwindSafe;
+use std::path::PathBuf;
 use std::sync::Arc;
 use tauri::{AppHandle, Manager};
 use tokio::sync::{broadcast, RwLock};
 
 use crate::ai::traits::chat::MCPToolDeclaration;
-use crate::constants::CFG_SEARCH_ENGINE;
+use crate::constants::{CFG_MCP_ROOTS, CFG_SEARCH_ENGINE};
 use crate::db::MainStore;
 use crate::mcp::client::{
-    McpClient, McpProtocolType, McpServerConfig, McpStatus, StdioClient, StreamableHttpClient,
+    spawn_reconnect_supervisor, McpClient, McpProtocolType, McpRoots, McpServerConfig, McpStatus,
+    ReconnectPolicy, SamplingHandler, StdioClient, StreamableHttpClient,
 };
+use crate::mcp::server::McpToolApprovalRegistry;
+use crate::mcp::McpError;
 use crate::tools::error::ToolError;
-use crate::tools::{ToolCallResult, ToolCategory, ToolScope, MCP_TOOL_NAME_SPLIT};
+use crate::tools::helper::coerce_tool_arguments;
+use crate::tools::{
+    AvailableTool, CommandApprovalRegistry, SamplingApprovalRegistry, ToolCallResult, ToolCategory,
+    ToolMetrics, ToolOutputSink, ToolRerunResult, ToolScope, ToolSource, MCP_TOOL_NAME_SPLIT,
+};
 
 // use super::tools::SearchDedup;
 // use super::tools::{ChatCompletion, ModelName};
@@ -60,6 +68,14 @@ pub trait ToolDefinition: Send + Sync {
     /// # Returns
     /// * `ToolResult` - The result of the function execution.
     async fn call(&self, params: Value) -> NativeToolResult;
+
+    /// Executes the function while streaming incremental output to `sink`.
+    ///
+    /// Long-running tools override this to report progress before they complete;
+    /// the default implementation ignores the sink and delegates to `call`.
+    async fn call_streaming(&self, params: Value, _sink: ToolOutputSink) -> NativeToolResult {
+        self.call(params).await
+    }
 }
 
 /// A wrapper that adapts an MCP tool to the ToolDefinition trait.
@@ -96,26 +112,98 @@ impl ToolDefinition for McpToolWrapper {
     }
 
     async fn call(&self, params: Value) -> NativeToolResult {
-        let res = self
-            .client
-            .call(&self.tool_decl.name, params)
-            .await
-            .map_err(|e| {
-                ToolError::ExecutionFailed(format!(
-                    "MCP call to server '{}' tool '{}' failed: {}",
-                    self.server_name, self.tool_decl.name, e
-                ))
-            })?;
+        call_mcp_tool(
+            &self.server_name,
+            &self.client,
+            &self.tool_decl.name,
+            &self.tool_decl.input_schema,
+            params,
+        )
+        .await
+    }
+}
 
-        // MCP results often come back as a JSON object with a 'content' field for display
-        // We extract that if present for high-signal text observations.
-        Ok(ToolCallResult::success(
-            res.get("content")
-                .and_then(|v| v.as_str())
-                .map(|s| s.to_string()),
-            Some(res),
-        ))
+/// Calls `tool_name` on an MCP server, cancelling it after the server's configured call
+/// timeout without progress.
+///
+/// The arguments are first coerced to the tool's `input_schema`, and a call whose arguments
+/// cannot be fails with `InvalidParams` without reaching the server.
+async fn call_mcp_tool(
+    server_name: &str,
+    client: &Arc<dyn McpClient>,
+    tool_name: &str,
+    input_schema: &Value,
+    params: Value,
+) -> NativeToolResult {
+    let params = coerce_tool_arguments(params, input_schema).map_err(|e| {
+        ToolError::InvalidParams(format!("Invalid arguments for tool '{}': {}", tool_name, e))
+    })?;
+    let res = client.call(tool_name, params).await.map_err(|e| match e {
+        McpError::Timeout(details) => ToolError::Timeout(details),
+        e => ToolError::ExecutionFailed(format!(
+            "MCP call to server '{}' tool '{}' failed: {}",
+            server_name, tool_name, e
+        )),
+    })?;
+
+    // MCP results often come back as a JSON object with a 'content' field for display
+    // We extract that if present for high-signal text observations.
+    Ok(ToolCallResult::success(
+        res.get("content")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string()),
+        Some(res),
+    ))
+}
+
+/// Whether a serialized `CallToolResult` reports that the tool failed.
+fn is_error_result(result: &Value) -> bool {
+    result.get("isError").and_then(Value::as_bool) == Some(true)
+}
+
+/// Picks the names MCP tools are exposed under from all registered tool names, keyed by
+/// the exposed name and mapped to (server, original name).
+///
+/// A tool keeps its own name when no other server and no native tool has a tool of that
+/// name, so prompts written for it keep working. Tools sharing a name are all exposed as
+/// `{server}_{tool}`, falling back to the registered name if even that is taken.
+fn resolve_mcp_tool_names<'a>(
+    tool_names: impl IntoIterator<Item = &'a str>,
+) -> HashMap<String, (String, String)> {
+    let mut native_names = HashSet::new();
+    let mut mcp_tools = Vec::new();
+    for name in tool_names {
+        match name.split_once(MCP_TOOL_NAME_SPLIT) {
+            Some((server, tool)) => mcp_tools.push((server, tool)),
+            None => {
+                native_names.insert(name);
+            }
+        }
+    }
+    mcp_tools.sort_unstable();
+
+    let mut servers_per_name: HashMap<&str, usize> = HashMap::new();
+    for (_, tool) in &mcp_tools {
+        *servers_per_name.entry(*tool).or_default() += 1;
     }
+    let conflicts = |tool: &str| servers_per_name[tool] > 1 || native_names.contains(tool);
+
+    // Unique names are claimed first so a prefixed name can never take one of them.
+    let mut exposed: HashMap<String, (String, String)> = mcp_tools
+        .iter()
+        .filter(|(_, tool)| !conflicts(tool))
+        .map(|(server, tool)| (tool.to_string(), (server.to_string(), tool.to_string())))
+        .collect();
+    for (server, tool) in mcp_tools.iter().filter(|(_, tool)| conflicts(tool)) {
+        let prefixed = format!("{}_{}", server, tool);
+        let name = if exposed.contains_key(&prefixed) || native_names.contains(prefixed.as_str()) {
+            format!("{}{}{}", server, MCP_TOOL_NAME_SPLIT, tool)
+        } else {
+            prefixed
+        };
+        exposed.insert(name, (server.to_string(), tool.to_string()));
+    }
+    exposed
 }
 
 /// Manages the registration and execution of workflow functions.
@@ -133,6 +221,21 @@ pub struct ToolManager {
     /// A set to track MCP server IDs with ongoing operations (start, stop, restart, refresh).
     /// This is used to prevent race conditions from rapid UI clicks.
     pub ops_in_progress: tokio::sync::Mutex<HashSet<i64>>,
+    /// Commands from `execute_command` waiting for the user's approval.
+    pub command_approvals: Arc<CommandApprovalRegistry>,
+    /// Per-tool call counts, latencies and error rates.
+    pub metrics: Arc<ToolMetrics>,
+    /// Sampling requests from MCP servers waiting for the user's approval.
+    pub sampling_approvals: Arc<SamplingApprovalRegistry>,
+    /// Answers sampling requests of MCP servers, installed on every client before it starts.
+    sampling_handler: std::sync::RwLock<Option<Arc<dyn SamplingHandler>>>,
+    /// Workspace folders advertised to MCP servers as roots.
+    pub mcp_roots: McpRoots,
+    /// Tool calls of external MCP clients waiting for the user's approval.
+    pub mcp_server_approvals: Arc<McpToolApprovalRegistry>,
+    /// The names MCP tools are exposed under, mapped to their (server, original name).
+    /// Rebuilt whenever tools are registered or removed.
+    mcp_tool_names: RwLock<HashMap<String, (String, String)>>,
 }
 
 impl ToolManager {
@@ -145,6 +248,42 @@ impl ToolManager {
             mcp_tools: RwLock::new(HashMap::new()),
             mcp_status_event_sender: sender,
             ops_in_progress: tokio::sync::Mutex::new(HashSet::new()),
+            command_approvals: Arc::new(CommandApprovalRegistry::default()),
+            metrics: Arc::new(ToolMetrics::new()),
+            sampling_approvals: Arc::new(SamplingApprovalRegistry::default()),
+            sampling_handler: std::sync::RwLock::new(None),
+            mcp_roots: McpRoots::default(),
+            mcp_server_approvals: Arc::new(McpToolApprovalRegistry::default()),
+            mcp_tool_names: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Updates the workspace folders advertised to MCP servers and notifies the running ones.
+    pub async fn set_mcp_roots(&self, paths: Vec<String>) {
+        self.mcp_roots.set(
+            paths
+                .iter()
+                .map(|p| p.trim())
+                .filter(|p| !p.is_empty())
+                .map(PathBuf::from),
+        );
+        let clients: Vec<Arc<dyn McpClient>> =
+            self.mcp_servers.read().await.values().cloned().collect();
+        for client in clients {
+            if let Err(e) = client.notify_roots_changed().await {
+                log::warn!(
+                    "Failed to notify MCP server {} about changed roots: {}",
+                    client.name().await,
+                    e
+                );
+            }
+        }
+    }
+
+    /// Sets the handler used for sampling requests of MCP servers registered afterwards.
+    pub fn set_sampling_handler(&self, handler: Arc<dyn SamplingHandler>) {
+        if let Ok(mut guard) = self.sampling_handler.write() {
+            *guard = Some(handler);
         }
     }
 
@@ -154,6 +293,63 @@ impl ToolManager {
             self.mcp_servers.write().await.clear();
             self.mcp_tools.write().await.clear();
         }
+        self.refresh_mcp_tool_names().await;
+    }
+
+    /// Rebuilds the exposed names of MCP tools from the registered tools.
+    async fn refresh_mcp_tool_names(&self) {
+        let names = {
+            let tools = self.tools.read().await;
+            resolve_mcp_tool_names(tools.keys().map(String::as_str))
+        };
+        *self.mcp_tool_names.write().await = names;
+    }
+
+    /// The names MCP tools are exposed under, mapped to their (server, original name).
+    ///
+    /// A tool is exposed under its own name unless that name is shared with a tool of
+    /// another server or a native tool, in which case it is `{server}_{tool}`.
+    #[cfg(test)]
+    pub async fn mcp_tool_names(&self) -> HashMap<String, (String, String)> {
+        self.mcp_tool_names.read().await.clone()
+    }
+
+    /// Maps the registered `{server}__MCP__{tool}` names to the names they are exposed under.
+    pub async fn exposed_tool_names(&self) -> HashMap<String, String> {
+        self.mcp_tool_names
+            .read()
+            .await
+            .iter()
+            .map(|(exposed, (server, tool))| {
+                (
+                    format!("{}{}{}", server, MCP_TOOL_NAME_SPLIT, tool),
+                    exposed.clone(),
+                )
+            })
+            .collect()
+    }
+
+    /// Renames the MCP tools in `specs` to their exposed names, so a model sees a tool under
+    /// its own name unless it conflicts. Calls by the exposed name resolve back to the tool.
+    pub async fn expose_tool_names(&self, specs: &mut [MCPToolDeclaration]) {
+        let exposed = self.exposed_tool_names().await;
+        for spec in specs {
+            if let Some(name) = exposed.get(&spec.name) {
+                spec.name = name.clone();
+            }
+        }
+    }
+
+    /// Maps an exposed MCP tool name to its registered `{server}__MCP__{tool}` name.
+    /// Registered names and unknown names are returned unchanged.
+    pub async fn resolve_tool_name(&self, name: &str) -> String {
+        if self.tools.read().await.contains_key(name) {
+            return name.to_string();
+        }
+        match self.mcp_tool_names.read().await.get(name) {
+            Some((server, tool)) => format!("{}{}{}", server, MCP_TOOL_NAME_SPLIT, tool),
+            None => name.to_string(),
+        }
     }
 
     /// Register tools for DAG Workflow
@@ -195,6 +391,42 @@ impl ToolManager {
         self.register_tool(Arc::new(crate::tools::WebFetch::new(app_handle.clone())))
             .await?;
 
+        // Register current time tool
+        self.register_tool(Arc::new(crate::tools::GetCurrentTime::new(
+            main_store.clone(),
+        )))
+        .await?;
+
+        // Register calculator tool
+        self.register_tool(Arc::new(crate::tools::Calculator))
+            .await?;
+
+        // Advertise the configured workspace folders to MCP servers
+        let mcp_roots = main_store
+            .read()
+            .map_err(|e| {
+                ToolError::Store(
+                    t!("db.failed_to_lock_main_store", error = e.to_string()).to_string(),
+                )
+            })?
+            .get_config(CFG_MCP_ROOTS, Vec::<String>::new());
+        self.set_mcp_roots(mcp_roots).await;
+
+        // Route sampling requests of MCP servers through the configured model
+        self.set_sampling_handler(Arc::new(crate::tools::ChatSamplingHandler::new(
+            app_handle.clone(),
+            self.sampling_approvals.clone(),
+        )));
+
+        // Register shell command tool, confined to the authorized folders and gated by the
+        // user's approval for sensitive commands
+        self.register_tool(Arc::new(crate::tools::ExecuteCommand::new(
+            Some(app_handle.clone()),
+            self.command_approvals.clone(),
+            Some(main_store.clone()),
+        )))
+        .await?;
+
         // =================================================
         // FileSystem & Search tools
         // =================================================
@@ -332,6 +564,8 @@ impl ToolManager {
         }
 
         tools.insert(name, tool);
+        drop(tools);
+        self.refresh_mcp_tool_names().await;
         Ok(())
     }
 
@@ -343,10 +577,11 @@ impl ToolManager {
     /// # Returns
     /// * `Result<Arc<dyn FunctionDefinition>, ToolError>` - The result of the function retrieval.
     pub async fn get_tool(&self, name: &str) -> Result<Arc<dyn ToolDefinition>, ToolError> {
+        let resolved_name = self.resolve_tool_name(name).await;
         let tools = self.tools.read().await;
 
         tools
-            .get(name)
+            .get(&resolved_name)
             .cloned()
             .ok_or_else(|| ToolError::FunctionNotFound(name.to_string()))
     }
@@ -359,8 +594,9 @@ impl ToolManager {
     /// # Returns
     /// * `bool` - True if the tool exists, false otherwise.
     pub async fn has_tool(&self, name: &str) -> bool {
+        let resolved_name = self.resolve_tool_name(name).await;
         let tools = self.tools.read().await;
-        tools.contains_key(name)
+        tools.contains_key(&resolved_name)
     }
 
     /// Returns metadata for all registered native tools.
@@ -379,6 +615,43 @@ impl ToolManager {
         meta
     }
 
+    /// Lists every registered tool, native and MCP, including disabled MCP tools, under the
+    /// names the model calls them by.
+    /// This lets the UI show what the model can currently call and why a tool is missing.
+    pub async fn list_available_tools(&self) -> Vec<AvailableTool> {
+        let exposed = self.exposed_tool_names().await;
+        let tools = self.tools.read().await;
+        let mut available: Vec<AvailableTool> = tools
+            .values()
+            .map(|tool| {
+                let spec = tool.tool_calling_spec();
+                let source = match tool.name().split_once(MCP_TOOL_NAME_SPLIT) {
+                    Some((server, name)) if tool.category() == ToolCategory::Mcp => {
+                        ToolSource::Mcp {
+                            server: server.to_string(),
+                            tool: name.to_string(),
+                        }
+                    }
+                    _ => ToolSource::Native,
+                };
+                AvailableTool {
+                    name: exposed
+                        .get(tool.name())
+                        .cloned()
+                        .unwrap_or_else(|| tool.name().to_string()),
+                    source,
+                    description: spec.description,
+                    category: tool.category().to_string(),
+                    scope: tool.scope(),
+                    input_schema: spec.input_schema,
+                    enabled: !spec.disabled,
+                }
+            })
+            .collect();
+        available.sort_by(|a, b| a.name.cmp(&b.name));
+        available
+    }
+
     /// Call a native tool by its name.
     ///
     /// # Arguments
@@ -388,8 +661,28 @@ impl ToolManager {
     /// # Returns
     /// * `ToolResult` - The result of the function execution.
     pub async fn native_tool_call(&self, name: &str, params: Value) -> NativeToolResult {
+        self.native_tool_call_with_sink(name, params, None).await
+    }
+
+    /// Execute a native tool, streaming its incremental output to `sink` when provided.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the function to execute.
+    /// * `params` - The parameters to pass to the function.
+    /// * `sink` - Optional receiver of output produced before the tool completes.
+    pub async fn native_tool_call_with_sink(
+        &self,
+        name: &str,
+        params: Value,
+        sink: Option<ToolOutputSink>,
+    ) -> NativeToolResult {
         let tool = self.get_tool(name).await?;
-        match AssertUnwindSafe(tool.call(params)).catch_unwind().await {
+        let call = match sink {
+            Some(sink) => tool.call_streaming(params, sink),
+            None => tool.call(params),
+        };
+        let started = std::time::Instant::now();
+        let result = match AssertUnwindSafe(call).catch_unwind().await {
             Ok(result) => result,
             Err(payload) => {
                 let panic_message = if let Some(message) = payload.downcast_ref::<&str>() {
@@ -405,7 +698,15 @@ impl ToolManager {
                     name, panic_message
                 )))
             }
-        }
+        };
+
+        let failed = match &result {
+            Ok(call_result) => call_result.is_error.unwrap_or(false),
+            Err(_) => true,
+        };
+        // Keyed by the registered name, so calls by the exposed name count for the same tool
+        self.metrics.record(tool.name(), started.elapsed(), failed);
+        result
     }
 
     /// Call a native tool or mcp tool by its name.
@@ -414,6 +715,74 @@ impl ToolManager {
         self.native_tool_call(name, params).await.map(|v| v.into())
     }
 
+    /// Call a tool by its name, streaming incremental output to `sink`.
+    pub async fn tool_call_with_sink(
+        &self,
+        name: &str,
+        params: Value,
+        sink: ToolOutputSink,
+    ) -> ToolResult {
+        self.native_tool_call_with_sink(name, params, Some(sink))
+            .await
+            .map(|v| v.into())
+    }
+
+    /// Re-executes a tool call recorded in a message with the same name and arguments.
+    ///
+    /// Failures, including a tool that is no longer registered, are reported in the
+    /// returned `error` instead of failing, so one stale call does not hide the others.
+    ///
+    /// # Arguments
+    /// * `tool_call` - The recorded call, `{ id, function: { name, arguments } }`, where
+    ///   `arguments` is either a JSON string or an object.
+    pub async fn rerun_recorded_tool_call(&self, tool_call: &Value) -> ToolRerunResult {
+        let tool_call_id = tool_call
+            .get("id")
+            .and_then(Value::as_str)
+            .map(str::to_string);
+        let function = tool_call.get("function");
+        let name = function
+            .and_then(|f| f.get("name"))
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string();
+
+        let params = match function.and_then(|f| f.get("arguments")) {
+            Some(Value::String(args)) if args.trim().is_empty() => Ok(json!({})),
+            Some(Value::String(args)) => serde_json::from_str::<Value>(args)
+                .map_err(|e| ToolError::InvalidParams(e.to_string())),
+            Some(Value::Null) | None => Ok(json!({})),
+            Some(args) => Ok(args.clone()),
+        };
+
+        let outcome = match params {
+            Ok(_) if name.is_empty() => Err(ToolError::InvalidParams(
+                "recorded tool call has no function name".to_string(),
+            )),
+            Ok(_) if !self.has_tool(&name).await => Err(ToolError::FunctionNotFound(name.clone())),
+            Ok(params) => self.tool_call(&name, params).await,
+            Err(e) => Err(e),
+        };
+
+        match outcome {
+            Ok(result) => ToolRerunResult {
+                tool_call_id,
+                name,
+                result: Some(result),
+                error: None,
+            },
+            Err(e) => {
+                log::warn!("Re-running recorded tool call '{}' failed: {}", name, e);
+                ToolRerunResult {
+                    tool_call_id,
+                    name,
+                    result: None,
+                    error: Some(e.to_string()),
+                }
+            }
+        }
+    }
+
     /// Get the calling spec of all registered tools, filtered by scope and exclusions.
     /// This includes both native tools and MCP tools (via wrappers).
     pub async fn get_tool_calling_spec(
@@ -470,25 +839,29 @@ impl ToolManager {
     /// Returns the full MCPToolDeclaration including input_schema and output_schema.
     ///
     /// # Arguments
-    /// * `tool_name` - The combined name of the MCP tool (format: {server_name}__MCP__{tool_name})
+    /// * `tool_name` - The exposed or the combined name of the MCP tool
+    ///   (format: {server_name}__MCP__{tool_name})
     ///
     /// # Returns
-    /// The complete MCPToolDeclaration for the specified tool.
+    /// The complete MCPToolDeclaration for the specified tool, named as it was asked for.
     pub async fn get_mcp_tool_declaration(
         &self,
         tool_name: &str,
     ) -> Result<MCPToolDeclaration, ToolError> {
+        let registered_name = self.resolve_tool_name(tool_name).await;
         let tools = self.tools.read().await;
         let tool = tools
-            .get(tool_name)
+            .get(&registered_name)
             .ok_or_else(|| ToolError::FunctionNotFound(tool_name.to_string()))?;
 
         // Verify it's an MCP tool
-        if !tool_name.contains(MCP_TOOL_NAME_SPLIT) {
+        if !registered_name.contains(MCP_TOOL_NAME_SPLIT) {
             return Err(ToolError::InvalidParams("Not an MCP tool".to_string()));
         }
 
-        Ok(tool.tool_calling_spec())
+        let mut spec = tool.tool_calling_spec();
+        spec.name = tool_name.to_string();
+        Ok(spec)
     }
 
     // =================================================
@@ -602,6 +975,16 @@ impl ToolManager {
             }))
             .await;
 
+        let sampling_handler = self
+            .sampling_handler
+            .read()
+            .ok()
+            .and_then(|guard| guard.clone());
+        if let Some(handler) = sampling_handler {
+            client_arc.set_sampling_handler(handler).await;
+        }
+        client_arc.set_roots(self.mcp_roots.clone()).await;
+
         let name = client_arc.name().await;
         #[cfg(debug_assertions)]
         {
@@ -616,6 +999,33 @@ impl ToolManager {
             .map_err(|e_mcp_start| ToolError::Initialization(e_mcp_start.to_string()))?;
         log::info!("MCP client {} started successfully.", &name);
 
+        // Re-establish the connection when it is lost, and keep the registered tools in
+        // sync with what the server offers after reconnecting.
+        if let Some(policy) = ReconnectPolicy::from_config(&client_arc.config().await) {
+            let tool_manager = Arc::downgrade(&self);
+            let supervised_client = Arc::downgrade(&client_arc);
+            let server_name = name.clone();
+            spawn_reconnect_supervisor(
+                client_arc.clone(),
+                policy,
+                Box::new(move |tools| {
+                    let tool_manager = tool_manager.clone();
+                    let supervised_client = supervised_client.clone();
+                    let server_name = server_name.clone();
+                    Box::pin(async move {
+                        if let (Some(tool_manager), Some(client)) =
+                            (tool_manager.upgrade(), supervised_client.upgrade())
+                        {
+                            tool_manager
+                                .sync_reconnected_mcp_tools(&server_name, client, tools)
+                                .await;
+                        }
+                    })
+                }),
+            )
+            .await;
+        }
+
         // 3. Spawn a task to wait for status, list tools, and register
         // This allows the main registration flow to return quickly, while the tool discovery
         // and registration happens in the background.
@@ -772,6 +1182,11 @@ impl ToolManager {
             }
         }
 
+        drop(tools_guard);
+        drop(servers_guard);
+        drop(mcp_tools_guard);
+        self.refresh_mcp_tool_names().await;
+
         #[cfg(debug_assertions)]
         {
             log::debug!("MCP server {} inner registration process completed.", name);
@@ -799,6 +1214,7 @@ impl ToolManager {
             let prefix = format!("{}{}", name, MCP_TOOL_NAME_SPLIT);
             tools_guard.retain(|tool_name, _| !tool_name.starts_with(&prefix));
         }
+        self.refresh_mcp_tool_names().await;
 
         // Get the server Arc to stop it *after* removing it from the map
         // and releasing the FunctionManager's mcp_servers lock.
@@ -872,41 +1288,7 @@ impl ToolManager {
                     tools.len(),
                     name
                 );
-                let config = client.config().await;
-                let disabled_tool_names: HashSet<String> =
-                    config.disabled_tools.unwrap_or_default();
-
-                let tools_with_disabled_flag: Vec<MCPToolDeclaration> = tools
-                    .into_iter()
-                    .map(|mut tool_decl| {
-                        tool_decl.disabled = disabled_tool_names.contains(&tool_decl.name);
-                        tool_decl
-                    })
-                    .collect();
-
-                {
-                    let mut mcp_tools_guard = self.mcp_tools.write().await;
-                    let mut tools_guard = self.tools.write().await;
-
-                    // 1. Update legacy map
-                    mcp_tools_guard.insert(name.to_string(), tools_with_disabled_flag.clone());
-
-                    // 2. Update unified tools map (Remove old and add new)
-                    let prefix = format!("{}{}", name, MCP_TOOL_NAME_SPLIT);
-                    tools_guard.retain(|tool_name, _| !tool_name.starts_with(&prefix));
-
-                    for decl in tools_with_disabled_flag {
-                        let combined_name =
-                            format!("{}{}{}", name, MCP_TOOL_NAME_SPLIT, &decl.name);
-                        let wrapper = Arc::new(McpToolWrapper {
-                            server_name: name.to_string(),
-                            tool_decl: decl,
-                            client: client.clone(),
-                            combined_name: combined_name.clone(),
-                        });
-                        tools_guard.insert(combined_name, wrapper);
-                    }
-                }
+                self.replace_mcp_server_tools(name, &client, tools).await;
 
                 // On success, notify that it's "Running" again.
                 self.mcp_status_event_sender
@@ -933,6 +1315,78 @@ impl ToolManager {
         }
     }
 
+    /// Replaces the registered tools of an MCP server with `tools`, flagging the ones
+    /// disabled in its config.
+    async fn replace_mcp_server_tools(
+        &self,
+        name: &str,
+        client: &Arc<dyn McpClient>,
+        tools: Vec<MCPToolDeclaration>,
+    ) {
+        let config = client.config().await;
+        let disabled_tool_names: HashSet<String> = config.disabled_tools.unwrap_or_default();
+
+        let tools_with_disabled_flag: Vec<MCPToolDeclaration> = tools
+            .into_iter()
+            .map(|mut tool_decl| {
+                tool_decl.disabled = disabled_tool_names.contains(&tool_decl.name);
+                tool_decl
+            })
+            .collect();
+
+        let mut mcp_tools_guard = self.mcp_tools.write().await;
+        let mut tools_guard = self.tools.write().await;
+
+        // 1. Update legacy map
+        mcp_tools_guard.insert(name.to_string(), tools_with_disabled_flag.clone());
+
+        // 2. Update unified tools map (Remove old and add new)
+        let prefix = format!("{}{}", name, MCP_TOOL_NAME_SPLIT);
+        tools_guard.retain(|tool_name, _| !tool_name.starts_with(&prefix));
+
+        for decl in tools_with_disabled_flag {
+            let combined_name = format!("{}{}{}", name, MCP_TOOL_NAME_SPLIT, &decl.name);
+            let wrapper = Arc::new(McpToolWrapper {
+                server_name: name.to_string(),
+                tool_decl: decl,
+                client: client.clone(),
+                combined_name: combined_name.clone(),
+            });
+            tools_guard.insert(combined_name, wrapper);
+        }
+        drop(tools_guard);
+        drop(mcp_tools_guard);
+        self.refresh_mcp_tool_names().await;
+    }
+
+    /// Registers the tools listed after an automatic reconnect, unless the server was
+    /// unregistered or replaced by a new client in the meantime.
+    async fn sync_reconnected_mcp_tools(
+        &self,
+        name: &str,
+        client: Arc<dyn McpClient>,
+        tools: Vec<MCPToolDeclaration>,
+    ) {
+        let is_registered = self
+            .mcp_servers
+            .read()
+            .await
+            .get(name)
+            .is_some_and(|registered| Arc::ptr_eq(registered, &client));
+        if !is_registered {
+            log::debug!(
+                "MCP server {} reconnected but is no longer registered, skipping its tools.",
+                name
+            );
+            return;
+        }
+        self.replace_mcp_server_tools(name, &client, tools).await;
+        log::info!(
+            "Tools of MCP server {} re-registered after reconnecting.",
+            name
+        );
+    }
+
     /// Gets the status of all registered MCP servers.
     ///
     /// # Returns
@@ -1107,21 +1561,29 @@ impl ToolManager {
         params: Value,
     ) -> ToolResult {
         let client_arc = self.get_mcp_server(mcp_name).await?;
-        client_arc
-            .call(tool_name, params.clone())
-            .await
-            .map_err(|e| {
-                ToolError::ExecutionFailed(
-                    t!(
-                        "mcp.client.failed_to_call_tool",
-                        server_name = mcp_name,
-                        tool_name = tool_name,
-                        args = params.to_string(),
-                        error = e
-                    )
-                    .to_string(),
+        let started = std::time::Instant::now();
+        let result = client_arc.call(tool_name, params.clone()).await;
+        let failed = match &result {
+            Ok(value) => is_error_result(value),
+            Err(_) => true,
+        };
+        self.metrics.record(
+            &format!("{}{}{}", mcp_name, MCP_TOOL_NAME_SPLIT, tool_name),
+            started.elapsed(),
+            failed,
+        );
+        result.map_err(|e| {
+            ToolError::ExecutionFailed(
+                t!(
+                    "mcp.client.failed_to_call_tool",
+                    server_name = mcp_name,
+                    tool_name = tool_name,
+                    args = params.to_string(),
+                    error = e
                 )
-            })
+                .to_string(),
+            )
+        })
     }
 
     pub fn subscribe_mcp_status_events(&self) -> broadcast::Receiver<(String, McpStatus)> {
@@ -1220,6 +1682,209 @@ mod tests {
         assert!(names.contains("both"));
     }
 
+    /// Emits output, then blocks until released so the test can observe it mid-call.
+    struct StreamingMockTool {
+        release: tokio::sync::Mutex<Option<tokio::sync::oneshot::Receiver<()>>>,
+    }
+
+    #[async_trait]
+    impl ToolDefinition for StreamingMockTool {
+        fn name(&self) -> &str {
+            "streaming"
+        }
+        fn description(&self) -> &str {
+            "Mock"
+        }
+        fn category(&self) -> ToolCategory {
+            ToolCategory::System
+        }
+        fn tool_calling_spec(&self) -> MCPToolDeclaration {
+            MCPToolDeclaration {
+                name: "streaming".into(),
+                description: "Mock".into(),
+                input_schema: json!({}),
+                output_schema: None,
+                disabled: false,
+                scope: Some(self.scope()),
+            }
+        }
+        async fn call(&self, _params: Value) -> NativeToolResult {
+            Ok(ToolCallResult::success(Some("done".into()), None))
+        }
+        async fn call_streaming(&self, params: Value, sink: ToolOutputSink) -> NativeToolResult {
+            sink.send(crate::tools::ToolOutputStream::Stdout, "step 1\n");
+            if let Some(release) = self.release.lock().await.take() {
+                let _ = release.await;
+            }
+            self.call(params).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_streaming_output_reaches_sink_before_completion() {
+        let manager = Arc::new(ToolManager::new());
+        let (release_tx, release_rx) = tokio::sync::oneshot::channel();
+        manager
+            .register_tool(Arc::new(StreamingMockTool {
+                release: tokio::sync::Mutex::new(Some(release_rx)),
+            }))
+            .await
+            .unwrap();
+
+        let (sink, mut output) = ToolOutputSink::channel();
+        let call = tokio::spawn({
+            let manager = manager.clone();
+            async move {
+                manager
+                    .tool_call_with_sink("streaming", json!({}), sink)
+                    .await
+            }
+        });
+
+        let chunk = tokio::time::timeout(std::time::Duration::from_secs(5), output.recv())
+            .await
+            .expect("output should be streamed before the tool completes")
+            .expect("sink should stay open while the tool runs");
+        assert_eq!(chunk.text, "step 1\n");
+        assert!(!call.is_finished());
+
+        release_tx.send(()).unwrap();
+        let result = call.await.unwrap().unwrap();
+        assert_eq!(result["content"], "done");
+
+        // Without a sink the tool falls back to the plain call.
+        let result = manager.tool_call("streaming", json!({})).await.unwrap();
+        assert_eq!(result["content"], "done");
+    }
+
+    /// Always fails, used to check error accounting.
+    struct FailingMockTool;
+
+    #[async_trait]
+    impl ToolDefinition for FailingMockTool {
+        fn name(&self) -> &str {
+            "failing"
+        }
+        fn description(&self) -> &str {
+            "Mock"
+        }
+        fn category(&self) -> ToolCategory {
+            ToolCategory::System
+        }
+        fn tool_calling_spec(&self) -> MCPToolDeclaration {
+            MCPToolDeclaration {
+                name: "failing".into(),
+                description: "Mock".into(),
+                input_schema: json!({}),
+                output_schema: None,
+                disabled: false,
+                scope: Some(self.scope()),
+            }
+        }
+        async fn call(&self, _params: Value) -> NativeToolResult {
+            Err(ToolError::NetworkError("upstream unavailable".into()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tool_calls_update_metrics() {
+        let manager = ToolManager::new();
+        manager
+            .register_tool(Arc::new(MockTool {
+                name: "ok_tool".into(),
+                scope: ToolScope::Both,
+            }))
+            .await
+            .unwrap();
+        manager
+            .register_tool(Arc::new(FailingMockTool))
+            .await
+            .unwrap();
+
+        let registered_name = format!("files{}read", MCP_TOOL_NAME_SPLIT);
+        manager
+            .register_tool(Arc::new(MockTool {
+                name: registered_name.clone(),
+                scope: ToolScope::Both,
+            }))
+            .await
+            .unwrap();
+
+        manager.tool_call("ok_tool", json!({})).await.unwrap();
+        manager.tool_call("ok_tool", json!({})).await.unwrap();
+        assert!(manager.tool_call("failing", json!({})).await.is_err());
+        // Calls by the exposed name and the registered name count for the same tool
+        manager.tool_call("read", json!({})).await.unwrap();
+        manager
+            .tool_call(&registered_name, json!({}))
+            .await
+            .unwrap();
+        // Unknown tools are not counted
+        assert!(manager.tool_call("missing", json!({})).await.is_err());
+
+        let snapshot = manager.metrics.snapshot();
+        assert_eq!(snapshot.len(), 3);
+        let read = snapshot
+            .iter()
+            .find(|m| m.tool_name == registered_name)
+            .unwrap();
+        assert_eq!(read.calls, 2);
+
+        let failing = snapshot.iter().find(|m| m.tool_name == "failing").unwrap();
+        assert_eq!(failing.calls, 1);
+        assert_eq!(failing.errors, 1);
+        assert_eq!(failing.error_rate, 1.0);
+
+        let ok = snapshot.iter().find(|m| m.tool_name == "ok_tool").unwrap();
+        assert_eq!(ok.calls, 2);
+        assert_eq!(ok.errors, 0);
+        assert_eq!(ok.error_rate, 0.0);
+    }
+
+    #[test]
+    fn test_is_error_result() {
+        assert!(is_error_result(
+            &json!({"content": [{"type": "text", "text": "boom"}], "isError": true})
+        ));
+        assert!(!is_error_result(&json!({"content": [], "isError": false})));
+        assert!(!is_error_result(&json!({"content": []})));
+    }
+
+    #[tokio::test]
+    async fn test_rerun_recorded_tool_call() {
+        let manager = ToolManager::new();
+        manager
+            .register_tool(Arc::new(MockTool {
+                name: "echo".into(),
+                scope: ToolScope::Both,
+            }))
+            .await
+            .unwrap();
+
+        let rerun = manager
+            .rerun_recorded_tool_call(&json!({
+                "id": "call_1",
+                "type": "function",
+                "function": { "name": "echo", "arguments": "{\"text\":\"hi\"}" }
+            }))
+            .await;
+        assert_eq!(rerun.tool_call_id.as_deref(), Some("call_1"));
+        assert_eq!(rerun.name, "echo");
+        assert!(rerun.error.is_none());
+        assert_eq!(rerun.result.unwrap()["content"], "ok");
+
+        // A tool that was removed since the call was recorded
+        let stale = manager
+            .rerun_recorded_tool_call(&json!({
+                "id": "call_2",
+                "function": { "name": "gone", "arguments": {} }
+            }))
+            .await;
+        assert!(stale.result.is_none());
+        assert!(stale.error.is_some());
+        assert_eq!(manager.metrics.snapshot().len(), 1);
+    }
+
     #[tokio::test]
     async fn test_mcp_wrapper_integration() {
         let manager = ToolManager::new();
@@ -1264,6 +1929,194 @@ mod tests {
         let names: HashSet<_> = specs.iter().map(|s| s.name.as_str()).collect();
         assert!(names.contains(combined_name.as_str()));
     }
+
+    fn mcp_wrapper(server_name: &str, tool_name: &str) -> Arc<McpToolWrapper> {
+        Arc::new(McpToolWrapper {
+            server_name: server_name.into(),
+            tool_decl: MCPToolDeclaration {
+                name: tool_name.into(),
+                description: "Desc".into(),
+                input_schema: json!({}),
+                output_schema: None,
+                disabled: false,
+                scope: Some(ToolScope::Both),
+            },
+            client: Arc::new(
+                crate::mcp::client::StdioClient::new(McpServerConfig {
+                    name: server_name.into(),
+                    command: Some("ls".into()),
+                    ..Default::default()
+                })
+                .unwrap(),
+            ),
+            combined_name: format!("{}{}{}", server_name, MCP_TOOL_NAME_SPLIT, tool_name),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_conflicting_mcp_tool_names_are_prefixed() {
+        let manager = ToolManager::new();
+        manager
+            .register_tool(Arc::new(MockTool {
+                name: "calculator".into(),
+                scope: ToolScope::Both,
+            }))
+            .await
+            .unwrap();
+        for (server, tool) in [
+            ("alpha", "search"),
+            ("beta", "search"),
+            ("beta", "fetch_page"),
+            ("gamma", "calculator"),
+        ] {
+            manager
+                .register_tool(mcp_wrapper(server, tool))
+                .await
+                .unwrap();
+        }
+
+        let names = manager.mcp_tool_names().await;
+        let pair = |server: &str, tool: &str| (server.to_string(), tool.to_string());
+        assert_eq!(names.len(), 4);
+        assert_eq!(names.get("alpha_search"), Some(&pair("alpha", "search")));
+        assert_eq!(names.get("beta_search"), Some(&pair("beta", "search")));
+        // A tool without a conflict keeps its own name.
+        assert_eq!(names.get("fetch_page"), Some(&pair("beta", "fetch_page")));
+        // So does the native tool an MCP tool clashes with.
+        assert_eq!(
+            names.get("gamma_calculator"),
+            Some(&pair("gamma", "calculator"))
+        );
+        assert!(!names.contains_key("search"));
+
+        // Exposed names resolve to the registered tools.
+        let tool = manager.get_tool("beta_search").await.unwrap();
+        assert_eq!(tool.name(), format!("beta{}search", MCP_TOOL_NAME_SPLIT));
+        assert_eq!(
+            manager.get_tool("calculator").await.unwrap().name(),
+            "calculator"
+        );
+        assert!(manager.has_tool("fetch_page").await);
+        assert!(manager.get_tool("search").await.is_err());
+
+        // Once the conflict is gone, the remaining tool gets its own name back.
+        manager.unregister_mcp_server("alpha").await.unwrap();
+        let names = manager.mcp_tool_names().await;
+        assert_eq!(names.get("search"), Some(&pair("beta", "search")));
+        assert!(!names.contains_key("beta_search"));
+    }
+
+    #[tokio::test]
+    async fn test_chat_specs_use_exposed_names() {
+        let manager = ToolManager::new();
+        for (server, tool) in [
+            ("alpha", "search"),
+            ("beta", "search"),
+            ("beta", "fetch_page"),
+        ] {
+            manager
+                .register_tool(mcp_wrapper(server, tool))
+                .await
+                .unwrap();
+        }
+
+        let mut specs = manager
+            .get_tool_calling_spec(Some(ToolScope::Chat), None)
+            .await
+            .unwrap();
+        manager.expose_tool_names(&mut specs).await;
+        let mut names: Vec<&str> = specs.iter().map(|spec| spec.name.as_str()).collect();
+        names.sort_unstable();
+        assert_eq!(names, vec!["alpha_search", "beta_search", "fetch_page"]);
+
+        let declaration = manager
+            .get_mcp_tool_declaration("fetch_page")
+            .await
+            .unwrap();
+        assert_eq!(declaration.name, "fetch_page");
+        assert_eq!(
+            manager.resolve_tool_name("fetch_page").await,
+            format!("beta{}fetch_page", MCP_TOOL_NAME_SPLIT)
+        );
+    }
+
+    #[test]
+    fn test_prefixed_name_never_takes_an_unconflicted_name() {
+        let split = MCP_TOOL_NAME_SPLIT;
+        let registered = [
+            format!("a{split}b_c"),
+            format!("b{split}c"),
+            format!("x{split}c"),
+        ];
+        let names = resolve_mcp_tool_names(registered.iter().map(String::as_str));
+
+        // `b_c` is taken by a tool without a conflict, so `c` of `b` keeps its full name.
+        assert_eq!(
+            names.get("b_c"),
+            Some(&("a".to_string(), "b_c".to_string()))
+        );
+        assert_eq!(
+            names.get(&format!("b{split}c")),
+            Some(&("b".to_string(), "c".to_string()))
+        );
+        assert_eq!(names.get("x_c"), Some(&("x".to_string(), "c".to_string())));
+    }
+
+    #[tokio::test]
+    async fn test_list_available_tools_labels_sources() {
+        let manager = ToolManager::new();
+        manager
+            .register_tool(Arc::new(MockTool {
+                name: "native_tool".into(),
+                scope: ToolScope::Both,
+            }))
+            .await
+            .unwrap();
+
+        let combined_name = format!("{}{}{}", "files", MCP_TOOL_NAME_SPLIT, "read");
+        let client = crate::mcp::client::StdioClient::new(McpServerConfig {
+            name: "files".into(),
+            protocol_type: McpProtocolType::Stdio,
+            command: Some("ls".into()),
+            ..Default::default()
+        })
+        .unwrap();
+        manager
+            .register_tool(Arc::new(McpToolWrapper {
+                server_name: "files".into(),
+                tool_decl: MCPToolDeclaration {
+                    name: "read".into(),
+                    description: "Read a file".into(),
+                    input_schema: json!({"type": "object"}),
+                    output_schema: None,
+                    disabled: true,
+                    scope: None,
+                },
+                client: Arc::new(client),
+                combined_name,
+            }))
+            .await
+            .unwrap();
+
+        let tools = manager.list_available_tools().await;
+        assert_eq!(tools.len(), 2);
+
+        let mcp = tools.iter().find(|t| t.name == "read").unwrap();
+        assert_eq!(
+            mcp.source,
+            ToolSource::Mcp {
+                server: "files".into(),
+                tool: "read".into()
+            }
+        );
+        assert_eq!(mcp.description, "Read a file");
+        assert_eq!(mcp.input_schema, json!({"type": "object"}));
+        assert!(!mcp.enabled);
+
+        let native = tools.iter().find(|t| t.name == "native_tool").unwrap();
+        assert_eq!(native.source, ToolSource::Native);
+        assert!(native.enabled);
+    }
 }
 
 /// A default implementation of `FunctionManager`.
@@ -106,3 +106,82 @@ impl From<ToolCallResult> for Value {
         serde_json::to_value(value).unwrap_or_default()
     }
 }
+
+/// Where an available tool comes from.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ToolSource {
+    Native,
+    /// `tool` is the tool's own name on `server`, which the exposed name may prefix.
+    Mcp {
+        server: String,
+        tool: String,
+    },
+}
+
+/// A registered tool as listed by the tool introspection command of the chat UI.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AvailableTool {
+    /// The name the model calls the tool by; MCP tools get a server prefix only on conflicts.
+    pub name: String,
+    pub source: ToolSource,
+    pub description: String,
+    pub category: String,
+    pub scope: ToolScope,
+    pub input_schema: Value,
+    /// `false` for MCP tools disabled by the user, which are never sent to the model.
+    pub enabled: bool,
+}
+
+/// The fresh outcome of re-running a tool call recorded in a past message.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolRerunResult {
+    /// The id of the recorded call, if it had one.
+    pub tool_call_id: Option<String>,
+    pub name: String,
+    /// The tool result, `None` when the call could not be executed.
+    pub result: Option<Value>,
+    /// Why the call could not be executed, e.g. the tool no longer exists.
+    pub error: Option<String>,
+}
+
+/// The stream a piece of incremental tool output came from.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ToolOutputStream {
+    Stdout,
+    Stderr,
+}
+
+/// A piece of output produced by a tool while it is still running.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ToolOutputChunk {
+    pub stream: ToolOutputStream,
+    pub text: String,
+}
+
+/// Receives incremental output from long-running tools, e.g. to stream it to the UI.
+///
+/// The final `ToolCallResult` is still returned to the model as usual; the sink is
+/// only for progress display and sending never blocks the tool.
+#[derive(Debug, Clone)]
+pub struct ToolOutputSink {
+    sender: tokio::sync::mpsc::UnboundedSender<ToolOutputChunk>,
+}
+
+impl ToolOutputSink {
+    pub fn channel() -> (Self, tokio::sync::mpsc::UnboundedReceiver<ToolOutputChunk>) {
+        let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+        (Self { sender }, receiver)
+    }
+
+    /// Sends a chunk, silently dropping it when nobody is listening anymore.
+    pub fn send(&self, stream: ToolOutputStream, text: impl Into<String>) {
+        let text = text.into();
+        if !text.is_empty() {
+            let _ = self.sender.send(ToolOutputChunk { stream, text });
+        }
+    }
+}
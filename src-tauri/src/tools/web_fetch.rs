@@ -1,20 +1,26 @@
 use async_trait::async_trait;
-use reqwest::Url;
+use reqwest::{
+    cookie::Jar,
+    header::{HeaderMap, HeaderName, HeaderValue, ACCEPT, CONTENT_TYPE, USER_AGENT},
+    Url,
+};
 use rust_i18n::t;
 use serde_json::{json, Value};
-use std::time::Duration;
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
 use tauri::{AppHandle, Manager};
 
 use crate::{
     ai::traits::chat::MCPToolDeclaration,
-    constants::RESTRICTED_EXTENSIONS,
+    constants::{CFG_WEB_FETCH_ROTATE_USER_AGENT, RESTRICTED_EXTENSIONS},
     db::MainStore,
-    http::{
-        client::{HttpClient, HttpProxyConfig},
-        types::HttpConfig,
-    },
     scraper::{
-        engine,
+        engine, readability,
         types::{ContentOptions, ScrapeRequest},
     },
     tools::{error::ToolError, NativeToolResult, ToolCallResult, ToolCategory, ToolDefinition},
@@ -62,6 +68,22 @@ const DIRECT_TEXT_FETCH_EXTENSIONS: &[&str] = &[
     "yml",
 ];
 const BROWSER_USER_AGENT: &str = "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/137.0.0.0 Safari/537.36";
+/// User agents that direct requests rotate through, so consecutive fetches do not share one
+/// fingerprint.
+const USER_AGENT_POOL: &[&str] = &[
+    BROWSER_USER_AGENT,
+    "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/137.0.0.0 Safari/537.36",
+    "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/137.0.0.0 Safari/537.36 Edg/137.0.0.0",
+    "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/18.5 Safari/605.1.15",
+    "Mozilla/5.0 (Windows NT 10.0; Win64; x64; rv:139.0) Gecko/20100101 Firefox/139.0",
+    "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/137.0.0.0 Safari/537.36",
+];
+/// Statuses after which a request is sent once more with another user agent.
+const USER_AGENT_RETRY_STATUSES: &[u16] = &[403, 429];
+/// Parts of header names whose values are masked in logs.
+const SENSITIVE_HEADER_MARKERS: &[&str] = &[
+    "auth", "cookie", "key", "password", "secret", "session", "token",
+];
 const DIRECT_TEXT_FETCH_ACCEPT: &str = "text/plain,text/markdown,text/css,application/javascript,text/javascript,application/json,text/csv,text/html,application/xhtml+xml,application/xml,text/xml,*/*;q=0.8";
 const HTML_CONTENT_TYPES: &[&str] = &["text/html", "application/xhtml+xml"];
 const DIRECT_TEXT_CONTENT_TYPES: &[&str] = &[
@@ -95,6 +117,162 @@ struct WebFetchProxyConfig {
     password: Option<String>,
 }
 
+static NEXT_USER_AGENT: AtomicUsize = AtomicUsize::new(0);
+
+/// Request headers, cookies and user agent of a fetch, taken from the tool parameters.
+#[derive(Debug, Clone, Default)]
+struct FetchOptions {
+    headers: HeaderMap,
+    /// `name=value` pairs, only sent to the host of the fetched URL
+    cookies: Vec<String>,
+    /// The user agent set through `headers`, which is never rotated
+    user_agent: Option<HeaderValue>,
+    rotate_user_agent: bool,
+}
+
+impl FetchOptions {
+    fn from_params(params: &Value, rotate_user_agent: bool) -> Result<Self, ToolError> {
+        let mut options = Self {
+            rotate_user_agent,
+            ..Default::default()
+        };
+
+        if let Some(headers) = params["headers"].as_object() {
+            for (name, value) in headers {
+                let value = param_string(value);
+                if name.eq_ignore_ascii_case("cookie") {
+                    options.cookies.extend(split_cookies(&value));
+                    continue;
+                }
+
+                let invalid = |details: String| {
+                    ToolError::InvalidParams(
+                        t!(
+                            "tools.web_fetch_invalid_header",
+                            name = name,
+                            details = details
+                        )
+                        .to_string(),
+                    )
+                };
+                let header_name =
+                    HeaderName::from_bytes(name.as_bytes()).map_err(|e| invalid(e.to_string()))?;
+                let mut header_value =
+                    HeaderValue::from_str(&value).map_err(|e| invalid(e.to_string()))?;
+                if header_name == USER_AGENT {
+                    options.user_agent = Some(header_value);
+                    continue;
+                }
+                header_value.set_sensitive(is_sensitive_header(name));
+                options.headers.insert(header_name, header_value);
+            }
+        }
+
+        match &params["cookies"] {
+            Value::String(cookies) => options.cookies.extend(split_cookies(cookies)),
+            Value::Object(cookies) => options.cookies.extend(
+                cookies
+                    .iter()
+                    .map(|(name, value)| format!("{}={}", name, param_string(value))),
+            ),
+            _ => {}
+        }
+
+        Ok(options)
+    }
+
+    /// Whether the fetch carries anything the browser scraper cannot send.
+    fn is_custom(&self) -> bool {
+        !self.headers.is_empty() || !self.cookies.is_empty() || self.user_agent.is_some()
+    }
+
+    fn user_agent(&self) -> HeaderValue {
+        if let Some(user_agent) = &self.user_agent {
+            return user_agent.clone();
+        }
+        if !self.rotate_user_agent {
+            return HeaderValue::from_static(BROWSER_USER_AGENT);
+        }
+        let index = NEXT_USER_AGENT.fetch_add(1, Ordering::Relaxed) % USER_AGENT_POOL.len();
+        HeaderValue::from_static(USER_AGENT_POOL[index])
+    }
+
+    /// The user agent to retry a rejected request with, unless the caller chose one.
+    fn other_user_agent(&self, current: &HeaderValue) -> Option<HeaderValue> {
+        if self.user_agent.is_some() {
+            return None;
+        }
+        let index = USER_AGENT_POOL
+            .iter()
+            .position(|user_agent| current == *user_agent)
+            .unwrap_or(0);
+        Some(HeaderValue::from_static(
+            USER_AGENT_POOL[(index + 1) % USER_AGENT_POOL.len()],
+        ))
+    }
+
+    fn request_headers(&self, user_agent: HeaderValue) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(ACCEPT, HeaderValue::from_static(DIRECT_TEXT_FETCH_ACCEPT));
+        for (name, value) in &self.headers {
+            headers.insert(name.clone(), value.clone());
+        }
+        headers.insert(USER_AGENT, user_agent);
+        headers
+    }
+
+    /// A cookie jar that holds the cookies for the host of `url` only, so they are not sent on
+    /// to other domains the request is redirected to.
+    fn cookie_jar(&self, url: &Url) -> Arc<Jar> {
+        let jar = Jar::default();
+        for cookie in &self.cookies {
+            jar.add_cookie_str(&format!("{}; Path=/", cookie), url);
+        }
+        Arc::new(jar)
+    }
+
+    /// Describes the custom headers for logs, with sensitive values and cookies masked.
+    fn describe(&self) -> String {
+        let mut parts: Vec<String> = self
+            .headers
+            .iter()
+            .map(|(name, value)| {
+                if value.is_sensitive() {
+                    format!("{}: ***", name)
+                } else {
+                    format!("{}: {}", name, value.to_str().unwrap_or("<binary>"))
+                }
+            })
+            .collect();
+        if !self.cookies.is_empty() {
+            parts.push(format!("{} cookie(s)", self.cookies.len()));
+        }
+        parts.join(", ")
+    }
+}
+
+fn param_string(value: &Value) -> String {
+    value
+        .as_str()
+        .map(str::to_string)
+        .unwrap_or_else(|| value.to_string())
+}
+
+fn split_cookies(cookies: &str) -> impl Iterator<Item = String> + '_ {
+    cookies
+        .split(';')
+        .map(str::trim)
+        .filter(|cookie| cookie.contains('='))
+        .map(str::to_string)
+}
+
+fn is_sensitive_header(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    SENSITIVE_HEADER_MARKERS
+        .iter()
+        .any(|marker| name.contains(marker))
+}
+
 /// A web scraper tool that uses Tauri's Webview to extract content from URLs
 pub struct WebFetch {
     app_handle: AppHandle<tauri::Wry>,
@@ -191,27 +369,44 @@ impl WebFetch {
         }
     }
 
-    async fn probe_direct_fetch_decision(
-        &self,
+    /// Whether direct requests rotate through `USER_AGENT_POOL`, on by default.
+    fn user_agent_rotation_enabled(&self) -> bool {
+        let main_store = self
+            .app_handle
+            .state::<std::sync::Arc<std::sync::RwLock<MainStore>>>()
+            .inner();
+        main_store
+            .read()
+            .map(|store| store.get_config(CFG_WEB_FETCH_ROTATE_USER_AGENT, true))
+            .unwrap_or(true)
+    }
+
+    fn is_html(content_type: &str) -> bool {
+        let mime = content_type
+            .split(';')
+            .next()
+            .map(str::trim)
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        HTML_CONTENT_TYPES.contains(&mime.as_str())
+    }
+
+    fn build_client(
         url: &str,
-    ) -> Result<DirectFetchDecision, ToolError> {
-        let is_direct_text_candidate = Self::is_direct_text_candidate(url);
-        let proxy = self.get_proxy()?;
+        proxy: &WebFetchProxyConfig,
+        options: &FetchOptions,
+        timeout: Duration,
+    ) -> reqwest::Result<reqwest::Client> {
         let mut client_builder = reqwest::Client::builder()
             .connect_timeout(Duration::from_secs(10))
-            .timeout(Duration::from_secs(15))
-            .user_agent(BROWSER_USER_AGENT);
+            .timeout(timeout);
+        if !options.cookies.is_empty() {
+            if let Ok(parsed) = Url::parse(url) {
+                client_builder = client_builder.cookie_provider(options.cookie_jar(&parsed));
+            }
+        }
         if let Some(proxy_server) = proxy.server.as_ref() {
-            let mut reqwest_proxy = reqwest::Proxy::all(proxy_server).map_err(|e| {
-                ToolError::ExecutionFailed(
-                    t!(
-                        "tools.web_scraper_failed",
-                        url = url,
-                        details = e.to_string()
-                    )
-                    .to_string(),
-                )
-            })?;
+            let mut reqwest_proxy = reqwest::Proxy::all(proxy_server)?;
             if let (Some(username), Some(password)) =
                 (proxy.username.as_ref(), proxy.password.as_ref())
             {
@@ -219,25 +414,63 @@ impl WebFetch {
             }
             client_builder = client_builder.proxy(reqwest_proxy);
         }
-        let client = client_builder.build().map_err(|e| {
+        client_builder.build()
+    }
+
+    /// Sends a GET request, and once more with another user agent when the site answers 403
+    /// or 429.
+    async fn send_get(
+        client: &reqwest::Client,
+        url: &str,
+        options: &FetchOptions,
+    ) -> reqwest::Result<reqwest::Response> {
+        let user_agent = options.user_agent();
+        let response = client
+            .get(url)
+            .headers(options.request_headers(user_agent.clone()))
+            .send()
+            .await?;
+        if !USER_AGENT_RETRY_STATUSES.contains(&response.status().as_u16()) {
+            return Ok(response);
+        }
+        let Some(other_user_agent) = options.other_user_agent(&user_agent) else {
+            return Ok(response);
+        };
+        log::debug!(
+            "WebFetch got status {} from {}, retrying with another user agent",
+            response.status(),
+            url
+        );
+        client
+            .get(url)
+            .headers(options.request_headers(other_user_agent))
+            .send()
+            .await
+    }
+
+    async fn probe_direct_fetch_decision(
+        &self,
+        url: &str,
+        options: &FetchOptions,
+    ) -> Result<DirectFetchDecision, ToolError> {
+        let is_direct_text_candidate = Self::is_direct_text_candidate(url);
+        let proxy = self.get_proxy()?;
+        let failed = |details: String| {
             ToolError::ExecutionFailed(
-                t!(
-                    "tools.web_scraper_failed",
-                    url = url,
-                    details = e.to_string()
-                )
-                .to_string(),
+                t!("tools.web_scraper_failed", url = url, details = details).to_string(),
             )
-        })?;
+        };
+        let client = Self::build_client(url, &proxy, options, Duration::from_secs(15))
+            .map_err(|e| failed(e.to_string()))?;
 
         let head_response = client
             .head(url)
-            .header("Accept", DIRECT_TEXT_FETCH_ACCEPT)
+            .headers(options.request_headers(options.user_agent()))
             .send()
             .await;
 
         if let Ok(response) = head_response {
-            if let Some(content_type) = response.headers().get(reqwest::header::CONTENT_TYPE) {
+            if let Some(content_type) = response.headers().get(CONTENT_TYPE) {
                 if let Ok(content_type) = content_type.to_str() {
                     if let Some(decision) =
                         Self::classify_content_type(content_type, is_direct_text_candidate)
@@ -248,23 +481,11 @@ impl WebFetch {
             }
         }
 
-        let get_response = client
-            .get(url)
-            .header("Accept", DIRECT_TEXT_FETCH_ACCEPT)
-            .send()
+        let get_response = Self::send_get(&client, url, options)
             .await
-            .map_err(|e| {
-                ToolError::ExecutionFailed(
-                    t!(
-                        "tools.web_scraper_failed",
-                        url = url,
-                        details = e.to_string()
-                    )
-                    .to_string(),
-                )
-            })?;
+            .map_err(|e| failed(e.to_string()))?;
 
-        if let Some(content_type) = get_response.headers().get(reqwest::header::CONTENT_TYPE) {
+        if let Some(content_type) = get_response.headers().get(CONTENT_TYPE) {
             if let Ok(content_type) = content_type.to_str() {
                 if let Some(decision) =
                     Self::classify_content_type(content_type, is_direct_text_candidate)
@@ -277,54 +498,53 @@ impl WebFetch {
         Ok(DirectFetchDecision::Browser)
     }
 
-    async fn fetch_text_directly(&self, url: &str) -> Result<String, ToolError> {
-        let proxy = self.get_proxy()?;
-        let client = HttpClient::new_with_proxy_config(HttpProxyConfig {
-            server: proxy.server,
-            username: proxy.username,
-            password: proxy.password,
-        })
-        .map_err(|e| {
+    /// Fetches `url` over HTTP instead of the browser. HTML pages are reduced to their readable
+    /// content.
+    async fn fetch_directly(
+        url: &str,
+        proxy: &WebFetchProxyConfig,
+        options: &FetchOptions,
+        keep_link: bool,
+    ) -> Result<String, ToolError> {
+        let failed = |details: String| {
             ToolError::ExecutionFailed(
-                t!(
-                    "tools.web_scraper_failed",
-                    url = url,
-                    details = e.to_string()
-                )
-                .to_string(),
-            )
-        })?;
-
-        let response = client
-            .send_request(
-                HttpConfig::get(url)
-                    .header("User-Agent", BROWSER_USER_AGENT)
-                    .header("Accept", DIRECT_TEXT_FETCH_ACCEPT),
+                t!("tools.web_scraper_failed", url = url, details = details).to_string(),
             )
+        };
+        let client = Self::build_client(url, proxy, options, Duration::from_secs(30))
+            .map_err(|e| failed(e.to_string()))?;
+
+        if options.is_custom() {
+            log::debug!(
+                "WebFetch requesting {} with custom headers: {}",
+                url,
+                options.describe()
+            );
+        }
+        let response = Self::send_get(&client, url, options)
             .await
-            .map_err(|e| {
-                ToolError::ExecutionFailed(
-                    t!(
-                        "tools.web_scraper_failed",
-                        url = url,
-                        details = e.to_string()
-                    )
-                    .to_string(),
-                )
-            })?;
-
-        if !response.is_success() {
-            return Err(ToolError::ExecutionFailed(
-                t!(
-                    "tools.web_scraper_failed",
-                    url = url,
-                    details = format!("unexpected status {}", response.status)
-                )
-                .to_string(),
-            ));
+            .map_err(|e| failed(e.to_string()))?;
+
+        if !response.status().is_success() {
+            return Err(failed(format!(
+                "unexpected status {}",
+                response.status().as_u16()
+            )));
         }
 
-        Ok(response.body.unwrap_or_default())
+        let is_html = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|content_type| content_type.to_str().ok())
+            .is_some_and(Self::is_html);
+        let body = response.text().await.map_err(|e| failed(e.to_string()))?;
+
+        if is_html {
+            let base_url = Url::parse(url).ok();
+            Ok(readability::extract(&body, base_url.as_ref(), keep_link).to_string())
+        } else {
+            Ok(body)
+        }
     }
 }
 
@@ -349,7 +569,8 @@ impl ToolDefinition for WebFetch {
 
 **Usage Guidelines:**
 -  **For News/List/Portal pages**: Use `format: \"links\"` or set `keep_link: true` to discover the content you need.
--  **For specific articles/content**: Use `format: \"markdown\"` (default) to get the main text.
+-  **For specific articles/content**: Use `format: \"markdown\"` (default) to get the main text, or `format: \"readable\"` to get only the title and main body without navigation, ads, and comments.
+-  **For pages that need a login or specific headers**: Pass the session cookies in `cookies` and extra headers such as Authorization in `headers`.
 -  Prioritize content from this tool over your internal knowledge when answering questions about a specific URL.
 -  When using information from this tool, cite the source URL in your answer.
 
@@ -377,13 +598,23 @@ impl ToolDefinition for WebFetch {
                     },
                     "format": {
                         "type": "string",
-                        "enum": ["markdown", "text", "links"],
-                        "description": "Format for the extracted content. Use 'markdown' for articles, 'text' for plain text, or 'links' for news/list/portal pages to discover more URLs. Defaults to 'markdown'."
+                        "enum": ["markdown", "text", "links", "readable"],
+                        "description": "Format for the extracted content. Use 'markdown' for articles, 'text' for plain text, 'links' for news/list/portal pages to discover more URLs, or 'readable' for just the title and main content of the page with navigation, ads, and other boilerplate removed. Defaults to 'markdown'."
                     },
                     "keep_link": {
                         "type": "boolean",
                         "description": "Whether to include hyperlinks in the output. Only effective for 'markdown' format. MUST be set to true for news/list/portal pages if using 'markdown' format. Defaults to false."
                     },
+                    "headers": {
+                        "type": "object",
+                        "additionalProperties": {"type": "string"},
+                        "description": "Extra request headers such as Authorization or Referer, for pages that require them. Pages fetched with custom headers or cookies are requested directly instead of rendered in the browser."
+                    },
+                    "cookies": {
+                        "type": "object",
+                        "additionalProperties": {"type": "string"},
+                        "description": "Cookies to send as name/value pairs, e.g. the session cookie of a page that requires login. They are only sent to the host of the URL."
+                    },
                     "keep_image": {
                         "type": "boolean",
                         "description": "Whether to include images in the output. Only effective when format is 'markdown'. Only set this to true if you have image-understanding capabilities and the user's query requires analyzing images. Defaults to false."
@@ -443,10 +674,16 @@ impl ToolDefinition for WebFetch {
         }
 
         let keep_image = params["keep_image"].as_bool().unwrap_or(false);
-        let content = if Self::is_direct_text_candidate(url)
-            && self.probe_direct_fetch_decision(url).await? == DirectFetchDecision::Direct
+        let options = FetchOptions::from_params(&params, self.user_agent_rotation_enabled())?;
+
+        // The browser scraper cannot send custom headers or cookies, so such pages are
+        // requested directly
+        let content = if options.is_custom()
+            || (Self::is_direct_text_candidate(url)
+                && self.probe_direct_fetch_decision(url, &options).await?
+                    == DirectFetchDecision::Direct)
         {
-            self.fetch_text_directly(url).await?
+            Self::fetch_directly(url, &self.get_proxy()?, &options, keep_link).await?
         } else {
             let request = ScrapeRequest::Content(ContentOptions {
                 url: url.to_string(),
@@ -511,10 +748,76 @@ impl ToolDefinition for WebFetch {
 
 #[cfg(test)]
 mod tests {
+    use crate::test::spawn_mock_backend;
     use crate::tools::web_fetch::DirectFetchDecision;
 
-    use super::WebFetch;
-    use serde_json::json;
+    use super::{FetchOptions, WebFetch, WebFetchProxyConfig, BROWSER_USER_AGENT};
+    use axum::{
+        http::{header, HeaderMap, StatusCode},
+        response::{IntoResponse, Redirect, Response},
+        routing::get,
+        Router,
+    };
+    use serde_json::{json, Map, Value};
+
+    fn echo_headers(headers: HeaderMap) -> Response {
+        let headers: Map<String, Value> = headers
+            .iter()
+            .map(|(name, value)| {
+                (
+                    name.to_string(),
+                    Value::String(value.to_str().unwrap_or_default().to_string()),
+                )
+            })
+            .collect();
+        (
+            [(header::CONTENT_TYPE, "text/plain")],
+            Value::Object(headers).to_string(),
+        )
+            .into_response()
+    }
+
+    /// Starts a site that echoes request headers back, and returns its base URL.
+    async fn mock_site() -> String {
+        let app = Router::new()
+            .route(
+                "/echo.txt",
+                get(|headers: HeaderMap| async move { echo_headers(headers) }),
+            )
+            .route(
+                "/picky.txt",
+                get(|headers: HeaderMap| async move {
+                    if headers
+                        .get(header::USER_AGENT)
+                        .is_some_and(|ua| ua == BROWSER_USER_AGENT)
+                    {
+                        StatusCode::FORBIDDEN.into_response()
+                    } else {
+                        echo_headers(headers)
+                    }
+                }),
+            )
+            .route(
+                "/elsewhere",
+                get(|headers: HeaderMap| async move {
+                    // Same server, but under another host name
+                    let host = headers
+                        .get(header::HOST)
+                        .and_then(|host| host.to_str().ok())
+                        .unwrap_or_default()
+                        .replace("127.0.0.1", "localhost");
+                    Redirect::temporary(&format!("http://{}/echo.txt", host))
+                }),
+            );
+        spawn_mock_backend(app).await
+    }
+
+    async fn fetch_echo(url: &str, options: &FetchOptions) -> Value {
+        let body = WebFetch::fetch_directly(url, &WebFetchProxyConfig::default(), options, false)
+            .await
+            .expect("fetch");
+        serde_json::from_str(&body).expect("echoed headers")
+    }
 
     // Note: These tests require a Tauri app handle and are integration tests
     // They should be run with proper test setup
@@ -617,4 +920,88 @@ mod tests {
         );
         assert_eq!(WebFetch::classify_content_type("", true), None);
     }
+
+    #[tokio::test]
+    async fn test_custom_headers_and_cookies_are_sent() {
+        let base_url = mock_site().await;
+        let params = json!({
+            "url": format!("{}/echo.txt", base_url),
+            "headers": {
+                "Authorization": "Bearer page-token",
+                "Referer": "https://example.com/",
+                "Cookie": "theme=dark"
+            },
+            "cookies": {"session": "abc123"}
+        });
+        let options = FetchOptions::from_params(&params, true).expect("options");
+        assert!(options.is_custom());
+
+        let echoed = fetch_echo(params["url"].as_str().unwrap(), &options).await;
+        assert_eq!(echoed["authorization"], "Bearer page-token");
+        assert_eq!(echoed["referer"], "https://example.com/");
+        let cookie = echoed["cookie"].as_str().expect("cookie header");
+        assert!(cookie.contains("theme=dark"));
+        assert!(cookie.contains("session=abc123"));
+        assert!(echoed["user-agent"].as_str().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_rejected_request_is_retried_with_another_user_agent() {
+        let base_url = mock_site().await;
+        let url = format!("{}/picky.txt", base_url);
+        let options = FetchOptions::from_params(&json!({}), false).expect("options");
+        let echoed = fetch_echo(&url, &options).await;
+        assert_ne!(echoed["user-agent"], BROWSER_USER_AGENT);
+
+        // A user agent chosen by the caller is never swapped out
+        let pinned = FetchOptions::from_params(
+            &json!({"headers": {"User-Agent": BROWSER_USER_AGENT}}),
+            true,
+        )
+        .expect("options");
+        let result =
+            WebFetch::fetch_directly(&url, &WebFetchProxyConfig::default(), &pinned, false).await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_cookies_are_not_sent_to_other_hosts() {
+        let base_url = mock_site().await;
+        let options = FetchOptions::from_params(&json!({"cookies": "session=abc123"}), true)
+            .expect("options");
+
+        let echoed = fetch_echo(&format!("{}/echo.txt", base_url), &options).await;
+        assert_eq!(echoed["cookie"], "session=abc123");
+
+        let redirected = fetch_echo(&format!("{}/elsewhere", base_url), &options).await;
+        assert!(redirected["host"]
+            .as_str()
+            .unwrap()
+            .starts_with("localhost"));
+        assert!(redirected.get("cookie").is_none());
+    }
+
+    #[test]
+    fn test_sensitive_headers_are_masked_in_logs() {
+        let options = FetchOptions::from_params(
+            &json!({
+                "headers": {"Authorization": "Bearer page-token", "X-Api-Key": "k-123", "Accept-Language": "en"},
+                "cookies": {"session": "abc123"}
+            }),
+            true,
+        )
+        .expect("options");
+        let described = options.describe();
+        assert!(described.contains("accept-language: en"));
+        assert!(described.contains("authorization: ***"));
+        assert!(!described.contains("page-token"));
+        assert!(!described.contains("k-123"));
+        assert!(!described.contains("abc123"));
+    }
+
+    #[test]
+    fn test_invalid_header_is_rejected() {
+        let result = FetchOptions::from_params(&json!({"headers": {"Bad Header": "x"}}), true);
+        assert!(result.is_err());
+    }
 }
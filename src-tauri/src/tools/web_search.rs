@@ -1,17 +1,26 @@
 use async_trait::async_trait;
+use lru::LruCache;
 use rust_i18n::t;
 use serde_json::{json, Value};
-use std::{str::FromStr as _, sync::Arc};
+use std::{
+    num::NonZeroUsize,
+    str::FromStr as _,
+    sync::{Arc, Mutex},
+};
 use url::Url;
 
 use crate::{
     ai::traits::chat::MCPToolDeclaration,
-    constants::{CFG_SEARCH_ENGINE, RESTRICTED_EXTENSIONS, VIDEO_AND_IMAGE_DOMAINS},
+    constants::{
+        CFG_SEARCH_ENGINE, CFG_SEARCH_FETCH_LIMITS, CFG_SEARCH_RECENCY_WEIGHT,
+        INTERNAL_PARAM_SESSION_ID, RESTRICTED_EXTENSIONS, VIDEO_AND_IMAGE_DOMAINS,
+    },
     db::MainStore,
     scraper::url_helper::{decode_bing_url, get_meta_refresh_url},
     search::{
-        BuiltInSearch, GoogleSearch, SearchFactory, SearchProvider, SearchProviderName,
-        SerperSearch, TavilySearch,
+        apply_recency_boost, BingSearch, BuiltInSearch, CitationRegistry, DuckDuckGoSearch,
+        FetchLimitConfig, GoogleSearch, SearchFactory, SearchPaginator, SearchProvider,
+        SearchProviderName, SearchType, SerperSearch, TavilySearch,
     },
     tools::{error::ToolError, NativeToolResult, ToolCallResult, ToolCategory, ToolDefinition},
 };
@@ -30,13 +39,22 @@ impl Auth {
     }
 }
 
+/// Number of workflow sessions whose citation numbers are remembered
+const CITATION_SESSIONS: usize = 32;
+
 pub struct WebSearch {
     app_handle: AppHandle<Wry>,
+    /// Citation numbers of each workflow session, so that `[n]` keeps pointing to the
+    /// same source across the searches of one session
+    citations: Mutex<LruCache<String, CitationRegistry>>,
 }
 
 impl WebSearch {
     pub fn new(app_handle: AppHandle<Wry>) -> Arc<Self> {
-        Arc::new(Self { app_handle })
+        Arc::new(Self {
+            app_handle,
+            citations: Mutex::new(LruCache::new(NonZeroUsize::new(CITATION_SESSIONS).unwrap())),
+        })
     }
 
     fn create_searcher(
@@ -91,6 +109,17 @@ impl WebSearch {
                         .map_err(|e| ToolError::Initialization(e.to_string()))?,
                 )
             }
+            SearchProviderName::BingApi => {
+                let auth = Self::get_and_check_auth(&search_engine, main_store.clone())?;
+                SearchFactory::Bing(
+                    BingSearch::new(auth.api_key, proxy)
+                        .map_err(|e| ToolError::Initialization(e.to_string()))?,
+                )
+            }
+            SearchProviderName::DuckDuckGoLite => SearchFactory::DuckDuckGo(
+                DuckDuckGoSearch::new(proxy)
+                    .map_err(|e| ToolError::Initialization(e.to_string()))?,
+            ),
             p @ (SearchProviderName::Bing
             | SearchProviderName::Brave
             | SearchProviderName::DuckDuckGo
@@ -98,11 +127,25 @@ impl WebSearch {
             | SearchProviderName::Sogou) => SearchFactory::Builtin(BuiltInSearch {
                 app_handle: self.app_handle.clone(),
                 provider: p,
+                fetch_limits: store
+                    .get_config(CFG_SEARCH_FETCH_LIMITS, FetchLimitConfig::default()),
             }),
         };
         Ok((searcher, provider_name))
     }
 
+    /// Reads the configured recency weight used to re-rank results, `0.0` when disabled.
+    fn configured_recency_weight(&self) -> f32 {
+        let main_store = self
+            .app_handle
+            .state::<Arc<std::sync::RwLock<MainStore>>>()
+            .inner();
+        main_store
+            .read()
+            .map(|store| store.get_config(CFG_SEARCH_RECENCY_WEIGHT, 0.0_f32))
+            .unwrap_or(0.0)
+    }
+
     /// Get and check authentication for the search engine.
     pub fn get_and_check_auth(
         search_engine: &str,
@@ -147,12 +190,22 @@ impl WebSearch {
                 }
                 Auth::new(api_key)
             }
+            SearchProviderName::BingApi => {
+                let api_key = store.get_config("bing_api_key", "".to_string());
+                if api_key.is_empty() {
+                    return Err(ToolError::Config(
+                        t!("tools.search.bing_api_key_empty").to_string(),
+                    ));
+                }
+                Auth::new(api_key)
+            }
             SearchProviderName::Bing
             | SearchProviderName::Brave
             | SearchProviderName::DuckDuckGo
+            | SearchProviderName::DuckDuckGoLite
             | SearchProviderName::So
             | SearchProviderName::Sogou => {
-                // Built-in providers don't need auth.
+                // Built-in providers and DuckDuckGo lite don't need auth.
                 return Ok(Auth::new("".to_string()));
             }
         };
@@ -257,11 +310,33 @@ impl ToolDefinition for WebSearch {
                             "maximum": 30,
                             "description": "Number of results to return, between 1 and 30. For more results, use the 'page' parameter."
                         },
+                        "search_type": {
+                            "type": "string",
+                            "enum": ["web", "news", "images"],
+                            "default": "web",
+                            "description": "The search vertical. 'news' returns dated articles with their source, 'images' returns image URLs and thumbnails. Providers without the requested vertical fall back to web search."
+                        },
+                        "auto_paginate": {
+                            "type": "boolean",
+                            "default": true,
+                            "description": "Whether to fetch following pages (up to 3) until 'number' unique results are collected. Set to false to fetch only the starting page."
+                        },
                         "time_period": {
                             "type": "string",
                             "enum": ["day", "week", "month", "year"],
                             "description": "Filters search results to a specific time range. Use this to find recent or timely information. If omitted, no time filter is applied."
                         },
+                        "recency_weight": {
+                            "type": "number",
+                            "minimum": 0,
+                            "maximum": 1,
+                            "description": "Optional weight between 0 and 1 that boosts newer results when re-ranking. Results without a publish date keep their original position. Defaults to the configured value."
+                        },
+                        "fetch_content": {
+                            "type": "boolean",
+                            "default": false,
+                            "description": "Whether to also fetch the page behind each result into its `content`. Only the built-in providers support it, and it makes the search noticeably slower."
+                        },
                         "response_format": {
                             "type": "string",
                             "enum": ["json", "xml"],
@@ -296,9 +371,20 @@ impl ToolDefinition for WebSearch {
             .map(String::from);
         let query = Self::extract_keywords(&params)?;
         let desired_count = params["number"].as_u64().unwrap_or(5).min(30).max(1) as usize;
-        let start_page = params["page"].as_u64().unwrap_or(1).max(1);
+        let start_page = params["page"].as_u64().unwrap_or(1).max(1) as u32;
+        let auto_paginate = params["auto_paginate"].as_bool().unwrap_or(true);
         let time_period = params["time_period"].as_str().unwrap_or("");
+        let search_type = match params["search_type"].as_str().unwrap_or("web") {
+            "news" => SearchType::News,
+            "images" => SearchType::Images,
+            _ => SearchType::Web,
+        };
         let response_format = params["response_format"].as_str().unwrap_or("json");
+        let fetch_content = params["fetch_content"].as_bool().unwrap_or(false);
+        let recency_weight = params["recency_weight"]
+            .as_f64()
+            .map(|w| w as f32)
+            .unwrap_or_else(|| self.configured_recency_weight());
 
         let period = match time_period {
             "day" => Some(crate::search::SearchPeriod::Day),
@@ -310,27 +396,29 @@ impl ToolDefinition for WebSearch {
 
         // 2. Setup for pagination loop
         let (searcher, search_provider_name) = self.create_searcher(provider_param)?;
-        let mut final_results: Vec<crate::search::SearchResult> = Vec::with_capacity(desired_count);
-        let mut current_page = start_page;
-        const MAX_PAGES_TO_FETCH: u64 = 3; // Limit to prevent excessive requests
-        const PAGE_SIZE: u64 = 10; // Most engines default to 10
-        let max_page = start_page.saturating_add(MAX_PAGES_TO_FETCH - 1);
+        const MAX_PAGES_TO_FETCH: u32 = 3; // Limit to prevent excessive requests
+        const PAGE_SIZE: u32 = 10; // Most engines default to 10
+        let max_pages = if auto_paginate { MAX_PAGES_TO_FETCH } else { 1 };
+        let mut paginator = SearchPaginator::new(start_page, PAGE_SIZE, max_pages, desired_count);
+        let base_params = json!({
+            "query": query,
+            "period": period,
+            "search_type": search_type,
+            "fetch_content": fetch_content,
+        });
 
         // 3. Pagination-Pipeline Loop
-        while final_results.len() < desired_count && current_page <= max_page {
-            let search_params = json!({
-                "query": query.clone(),
-                "count": PAGE_SIZE,
-                "period": period,
-                "page": current_page,
-            });
-
+        while let Some(search_params) = paginator.next_params(&base_params) {
             let raw_results = searcher.search(&search_params).await.map_err(|e| {
-                log::error!("Failed to fetch search page {}: {}", current_page, e);
+                log::error!(
+                    "Failed to fetch search page {}: {}",
+                    search_params["page"],
+                    e
+                );
                 ToolError::ExecutionFailed(e.to_string())
             })?;
 
-            if raw_results.is_empty() {
+            if !paginator.begin_page(raw_results.len()) {
                 break; // No more results from the search engine
             }
 
@@ -354,13 +442,15 @@ impl ToolDefinition for WebSearch {
                     continue;
                 };
 
-                // Domain filter
+                // Domain filter, image results are expected to come from media sites
                 let host = parsed_url.host_str().unwrap_or_default();
                 let parts: Vec<&str> = host.split('.').collect();
-                if (0..parts.len().saturating_sub(1)).any(|i| {
-                    let domain_to_check = parts[i..].join(".");
-                    VIDEO_AND_IMAGE_DOMAINS.contains(domain_to_check.as_str())
-                }) {
+                if search_type != SearchType::Images
+                    && (0..parts.len().saturating_sub(1)).any(|i| {
+                        let domain_to_check = parts[i..].join(".");
+                        VIDEO_AND_IMAGE_DOMAINS.contains(domain_to_check.as_str())
+                    })
+                {
                     continue;
                 }
 
@@ -373,25 +463,45 @@ impl ToolDefinition for WebSearch {
                     continue;
                 }
 
-                // c. Add to final list
-                final_results.push(result);
-                if final_results.len() >= desired_count {
+                // c. Add to final list, skipping URLs already seen on earlier pages
+                paginator.push(result);
+                if paginator.is_full() {
                     break; // We have enough results
                 }
             }
-            current_page += 1;
         }
+        let mut final_results = paginator.into_results();
 
         // 4. Final processing
-        let results_with_id = final_results
-            .iter()
-            .enumerate()
-            .map(|(index, r)| {
-                let mut sr = r.clone();
-                sr.id = index + 1;
-                sr
-            })
-            .collect::<Vec<_>>();
+        apply_recency_boost(
+            &mut final_results,
+            recency_weight,
+            period.as_ref(),
+            chrono::Utc::now(),
+        );
+
+        // Inside a workflow session, ids are citation numbers shared by all its searches
+        let session_id = params[INTERNAL_PARAM_SESSION_ID]
+            .as_str()
+            .filter(|id| !id.is_empty());
+        let mut results_with_id = final_results;
+        let citations = match session_id {
+            Some(session_id) => {
+                let mut sessions = self
+                    .citations
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner());
+                let registry = sessions.get_or_insert_mut(session_id.to_string(), Default::default);
+                registry.assign(&mut results_with_id);
+                Some(json!(registry.sources()))
+            }
+            None => {
+                for (index, r) in results_with_id.iter_mut().enumerate() {
+                    r.id = index + 1;
+                }
+                None
+            }
+        };
 
         let result_string = if results_with_id.is_empty() {
             match response_format {
@@ -412,9 +522,17 @@ impl ToolDefinition for WebSearch {
             }
         };
 
+        let structured = match citations {
+            Some(citations) => json!({
+                "results": results_with_id,
+                "citations": citations,
+            }),
+            None => json!(results_with_id),
+        };
+
         Ok(ToolCallResult::success(
             Some(result_string),
-            Some(json!(results_with_id)),
+            Some(structured),
         ))
     }
 }
@@ -42,6 +42,10 @@ pub enum UpdateError {
     #[error("{}", t!("updater.errors.version_mismatch"))]
     VersionMismatch,
 
+    /// The downloaded package does not match the signature of the release.
+    #[error("{}", t!("updater.errors.signature", error = _0))]
+    SignatureError(String),
+
     /// No update was found on the server when one was expected.
     #[error("{}", t!("updater.errors.update_not_found"))]
     UpdateNotFound,
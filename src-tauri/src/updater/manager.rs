@@ -3,16 +3,19 @@
 //! Provides functionality for checking and installing application updates.
 
 use super::error::{Result, UpdateError};
-use super::types::VersionInfo;
+use super::mirror::{self, DownloadLimits};
+use super::types::{UpdateMirrors, VersionInfo};
 use log::{info, warn};
 use reqwest::Proxy;
 use semver::Version;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use tauri::async_runtime::spawn;
 use tauri::{AppHandle, Emitter, Manager};
 use tauri_plugin_updater::{Update, UpdaterExt};
 
+use crate::constants::CFG_UPDATE_MIRRORS;
 use crate::db::MainStore;
 
 const EVENT_UPDATE_PROGRESS: &str = "update://download-progress";
@@ -27,6 +30,14 @@ pub enum UpdateCheckOutcome {
     ReadyToInstall,
 }
 
+/// Proxy settings updates are checked and downloaded with.
+enum UpdateProxy {
+    None,
+    Http(Proxy),
+    /// Follow the system and environment proxy settings
+    System,
+}
+
 #[derive(Clone)]
 struct UpdateState {
     update: Update,
@@ -75,6 +86,8 @@ impl UpdateManager {
                 if self.should_install(&version_info)? {
                     self.notify_update_available(&version_info);
 
+                    let client = self.build_download_client()?;
+                    let sources = self.download_sources(&update);
                     let app_clone = self.app.clone();
                     let latest_update_clone = self.latest_update.clone();
                     let check_in_progress_clone = self.check_in_progress.clone();
@@ -84,6 +97,8 @@ impl UpdateManager {
                             latest_update_clone,
                             check_in_progress_clone.clone(),
                             update,
+                            client,
+                            sources,
                         )
                         .await
                         {
@@ -107,43 +122,50 @@ impl UpdateManager {
         result
     }
 
-    fn build_updater(&self) -> Result<tauri_plugin_updater::Updater> {
-        let mut builder = self.app.updater_builder();
+    fn update_proxy(&self) -> Result<UpdateProxy> {
+        let Some(main_store) = self.app.try_state::<Arc<std::sync::RwLock<MainStore>>>() else {
+            return Ok(UpdateProxy::System);
+        };
+        let store = main_store
+            .read()
+            .map_err(|_| UpdateError::LockError("Failed to read MainStore state".to_string()))?;
+
+        let proxy_type = store.get_config("proxy_type", "none".to_string());
+        match proxy_type.as_str() {
+            "none" => Ok(UpdateProxy::None),
+            "http" => {
+                let proxy_server = store.get_config("proxy_server", String::new());
+                if proxy_server.trim().is_empty() {
+                    return Err(UpdateError::ConfigError(
+                        "HTTP proxy is enabled for updates, but proxy_server is empty".to_string(),
+                    ));
+                }
 
-        if let Some(main_store) = self.app.try_state::<Arc<std::sync::RwLock<MainStore>>>() {
-            let store = main_store.read().map_err(|_| {
-                UpdateError::LockError("Failed to read MainStore state".to_string())
-            })?;
+                let mut proxy = Proxy::all(proxy_server.as_str())
+                    .map_err(|e| UpdateError::ConfigError(e.to_string()))?;
+                let proxy_username = store.get_config("proxy_username", String::new());
+                let proxy_password = store.get_config("proxy_password", String::new());
 
-            let proxy_type = store.get_config("proxy_type", "none".to_string());
-            match proxy_type.as_str() {
-                "none" => {
-                    builder = builder.no_proxy();
-                }
-                "http" => {
-                    let proxy_server = store.get_config("proxy_server", String::new());
-                    if proxy_server.trim().is_empty() {
-                        return Err(UpdateError::ConfigError(
-                            "HTTP proxy is enabled for updates, but proxy_server is empty"
-                                .to_string(),
-                        ));
-                    }
-
-                    let mut proxy = Proxy::all(proxy_server.as_str())
-                        .map_err(|e| UpdateError::ConfigError(e.to_string()))?;
-                    let proxy_username = store.get_config("proxy_username", String::new());
-                    let proxy_password = store.get_config("proxy_password", String::new());
-
-                    if !proxy_username.is_empty() && !proxy_password.is_empty() {
-                        proxy = proxy.basic_auth(&proxy_username, &proxy_password);
-                    }
-
-                    builder = builder.configure_client(move |client| client.proxy(proxy.clone()));
-                }
-                _ => {
-                    // Keep reqwest default behavior so updater follows system/env proxy settings.
+                if !proxy_username.is_empty() && !proxy_password.is_empty() {
+                    proxy = proxy.basic_auth(&proxy_username, &proxy_password);
                 }
+                Ok(UpdateProxy::Http(proxy))
             }
+            // Keep reqwest default behavior so updater follows system/env proxy settings.
+            _ => Ok(UpdateProxy::System),
+        }
+    }
+
+    fn build_updater(&self) -> Result<tauri_plugin_updater::Updater> {
+        let mut builder = self.app.updater_builder();
+        match self.update_proxy()? {
+            UpdateProxy::None => {
+                builder = builder.no_proxy();
+            }
+            UpdateProxy::Http(proxy) => {
+                builder = builder.configure_client(move |client| client.proxy(proxy.clone()));
+            }
+            UpdateProxy::System => {}
         }
 
         builder
@@ -151,6 +173,35 @@ impl UpdateManager {
             .map_err(|e| UpdateError::ConfigError(e.to_string()))
     }
 
+    /// Builds the client update packages are downloaded with, through the same proxy as the
+    /// update check.
+    fn build_download_client(&self) -> Result<reqwest::Client> {
+        let mut builder = reqwest::Client::builder().connect_timeout(Duration::from_secs(15));
+        match self.update_proxy()? {
+            UpdateProxy::None => builder = builder.no_proxy(),
+            UpdateProxy::Http(proxy) => builder = builder.proxy(proxy),
+            UpdateProxy::System => {}
+        }
+        builder
+            .build()
+            .map_err(|e| UpdateError::ConfigError(e.to_string()))
+    }
+
+    /// The announced download URL of `update` followed by its configured mirrors.
+    fn download_sources(&self, update: &Update) -> Vec<String> {
+        let mirrors = self
+            .app
+            .try_state::<Arc<std::sync::RwLock<MainStore>>>()
+            .and_then(|store| {
+                store
+                    .read()
+                    .ok()
+                    .map(|store| store.get_config(CFG_UPDATE_MIRRORS, UpdateMirrors::default()))
+            })
+            .unwrap_or_default();
+        mirrors.source_urls(update.download_url.as_str())
+    }
+
     /// Installs the latest downloaded update and restarts the application.
     pub async fn install_and_restart(&self) -> Result<()> {
         let update_state = self
@@ -232,34 +283,27 @@ fn set_check_in_progress(check_in_progress: &Arc<Mutex<bool>>, value: bool) {
     }
 }
 
+/// Downloads `update` from the fastest of `sources` and verifies it against the release
+/// signature before it is kept for installation.
 async fn download_update_to_file(
     app: AppHandle,
     latest_update: Arc<Mutex<Option<UpdateState>>>,
     check_in_progress: Arc<Mutex<bool>>,
     update: Update,
+    client: reqwest::Client,
+    sources: Vec<String>,
 ) -> Result<()> {
     info!(
-        "Starting background download for version: {}",
-        update.version
+        "Starting background download for version: {} from {} source(s)",
+        update.version,
+        sources.len()
     );
 
-    let downloaded_bytes = Arc::new(Mutex::new(0_u64));
-    let downloaded_bytes_clone = downloaded_bytes.clone();
     let progress_app = app.clone();
-    let on_chunk = move |chunk_size, total| {
-        let progress = if let Some(total) = total {
-            if let Ok(mut downloaded) = downloaded_bytes_clone.lock() {
-                *downloaded = (*downloaded + chunk_size as u64).min(total);
-                if total == 0 {
-                    0.0
-                } else {
-                    ((*downloaded as f64 / total as f64) * 99.0).min(99.0)
-                }
-            } else {
-                0.0
-            }
-        } else {
-            0.0
+    let on_progress = move |downloaded: u64, total: Option<u64>| {
+        let progress = match total {
+            Some(total) if total > 0 => ((downloaded as f64 / total as f64) * 99.0).min(99.0),
+            _ => 0.0,
         };
         let _ = progress_app.emit(
             EVENT_UPDATE_PROGRESS,
@@ -267,17 +311,28 @@ async fn download_update_to_file(
         );
     };
 
-    let on_finish = || {
-        info!(
-            "Background download completed for version: {}",
-            update.version
-        );
-    };
+    let bytes = mirror::download(
+        &client,
+        &sources,
+        &update.headers,
+        &DownloadLimits::default(),
+        on_progress,
+    )
+    .await?;
+    info!(
+        "Background download completed for version: {}",
+        update.version
+    );
 
-    let bytes = update
-        .download(on_chunk, on_finish)
-        .await
-        .map_err(|e| UpdateError::DownloadError(e.to_string()))?;
+    let pubkey = app
+        .config()
+        .plugins
+        .0
+        .get("updater")
+        .and_then(|updater| updater.get("pubkey"))
+        .and_then(|pubkey| pubkey.as_str())
+        .ok_or_else(|| UpdateError::ConfigError("The updater has no public key".to_string()))?;
+    mirror::verify_signature(&bytes, &update.signature, pubkey)?;
 
     let cache_dir = app
         .path()
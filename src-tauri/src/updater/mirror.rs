@@ -0,0 +1,554 @@
+//! Mirror selection and resumable download of update packages.
+//!
+//! The package announced by the update server may also be fetched from the mirrors configured
+//! under `update_mirrors`. All sources are probed in parallel with a small range request and the
+//! download starts from the fastest one. When that source fails, stalls or falls well below its
+//! probed speed, the download moves on to the next fastest source, resuming from the bytes
+//! received so far if the source serves ranges. The package is verified against the release
+//! signature whichever sources it came from. The headers of the update server may carry
+//! credentials, so they are only sent to its own origin and never to a mirror.
+
+use base64::Engine;
+use futures_util::{future::join_all, StreamExt};
+use log::{info, warn};
+use minisign_verify::{PublicKey, Signature};
+use reqwest::header::{HeaderMap, HeaderValue, ACCEPT, CONTENT_RANGE, RANGE};
+use reqwest::{Client, StatusCode, Url};
+use rust_i18n::t;
+use std::time::{Duration, Instant};
+use tokio::time::timeout;
+
+use super::error::{Result, UpdateError};
+
+/// Limits of the speed test and of switching sources during the download.
+#[derive(Debug, Clone)]
+pub(super) struct DownloadLimits {
+    /// Bytes requested from each source to measure its speed
+    pub probe_bytes: u64,
+    pub probe_timeout: Duration,
+    /// Time without any data after which a source is given up
+    pub stall_timeout: Duration,
+    /// Span the download speed is measured over
+    pub speed_window: Duration,
+    /// Share of the probed speed below which a source is left for the next one
+    pub slow_ratio: f64,
+}
+
+impl Default for DownloadLimits {
+    fn default() -> Self {
+        Self {
+            probe_bytes: 64 * 1024,
+            probe_timeout: Duration::from_secs(10),
+            stall_timeout: Duration::from_secs(20),
+            speed_window: Duration::from_secs(10),
+            slow_ratio: 0.2,
+        }
+    }
+}
+
+/// A source that answered the speed test.
+#[derive(Debug, Clone)]
+pub(super) struct RankedSource {
+    pub url: String,
+    pub bytes_per_sec: f64,
+    pub total_size: Option<u64>,
+}
+
+/// Start and total size of a `Content-Range: bytes 0-65535/1048576` header.
+fn parse_content_range(value: &str) -> Option<(u64, Option<u64>)> {
+    let (range, total) = value.trim().strip_prefix("bytes ")?.split_once('/')?;
+    let start = range.split('-').next()?.trim().parse().ok()?;
+    Some((start, total.trim().parse().ok()))
+}
+
+fn content_range(headers: &HeaderMap) -> Option<(u64, Option<u64>)> {
+    headers
+        .get(CONTENT_RANGE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(parse_content_range)
+}
+
+/// The headers of a request to `url`. The update server's `headers` only go to the origin of
+/// `primary`, the URL it announced.
+fn source_headers(url: &str, primary: &str, headers: &HeaderMap) -> HeaderMap {
+    let same_origin = match (Url::parse(url), Url::parse(primary)) {
+        (Ok(url), Ok(primary)) => url.origin() == primary.origin(),
+        _ => false,
+    };
+    let mut source_headers = if same_origin {
+        headers.clone()
+    } else {
+        HeaderMap::new()
+    };
+    if !source_headers.contains_key(ACCEPT) {
+        source_headers.insert(ACCEPT, HeaderValue::from_static("application/octet-stream"));
+    }
+    source_headers
+}
+
+/// Measures how fast a source delivers the first `probe_bytes` of the package.
+async fn probe(
+    client: &Client,
+    url: &str,
+    headers: &HeaderMap,
+    limits: &DownloadLimits,
+) -> std::result::Result<RankedSource, String> {
+    let started = Instant::now();
+    let measure = async {
+        let response = client
+            .get(url)
+            .headers(headers.clone())
+            .header(RANGE, format!("bytes=0-{}", limits.probe_bytes.max(1) - 1))
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(format!("status {}", status));
+        }
+        let total_size = if status == StatusCode::PARTIAL_CONTENT {
+            content_range(response.headers()).and_then(|(_, total)| total)
+        } else {
+            response.content_length()
+        };
+
+        let mut received = 0_u64;
+        let mut body = response.bytes_stream();
+        while received < limits.probe_bytes {
+            match body.next().await {
+                Some(chunk) => received += chunk.map_err(|e| e.to_string())?.len() as u64,
+                None => break,
+            }
+        }
+        Ok((received, total_size))
+    };
+
+    let (received, total_size) = timeout(limits.probe_timeout, measure)
+        .await
+        .map_err(|_| "speed test timed out".to_string())??;
+    let elapsed = started.elapsed().as_secs_f64().max(0.001);
+    Ok(RankedSource {
+        url: url.to_string(),
+        bytes_per_sec: received as f64 / elapsed,
+        total_size,
+    })
+}
+
+/// Probes all sources in parallel and returns those that answered, fastest first, along
+/// with the errors of the others. `headers` are the update server's, for the first URL.
+pub(super) async fn rank_sources(
+    client: &Client,
+    urls: &[String],
+    headers: &HeaderMap,
+    limits: &DownloadLimits,
+) -> (Vec<RankedSource>, Vec<String>) {
+    let primary = urls.first().map(String::as_str).unwrap_or_default();
+    let results = join_all(urls.iter().map(|url| async move {
+        probe(client, url, &source_headers(url, primary, headers), limits).await
+    }))
+    .await;
+
+    let mut ranked = Vec::new();
+    let mut errors = Vec::new();
+    for (url, result) in urls.iter().zip(results) {
+        match result {
+            Ok(source) => ranked.push(source),
+            Err(e) => {
+                warn!("Update source {} failed the speed test: {}", url, e);
+                errors.push(format!("{}: {}", url, e));
+            }
+        }
+    }
+    ranked.sort_by(|a, b| b.bytes_per_sec.total_cmp(&a.bytes_per_sec));
+    (ranked, errors)
+}
+
+/// Downloads from `source` into `buffer`, continuing after the bytes already in it when the
+/// source serves ranges and starting over otherwise.
+///
+/// Unless it is the last source left (`may_switch`), a source that delivers less than
+/// `slow_ratio` of its probed speed over a `speed_window` is given up as well.
+async fn fetch_from(
+    client: &Client,
+    source: &RankedSource,
+    headers: &HeaderMap,
+    limits: &DownloadLimits,
+    may_switch: bool,
+    buffer: &mut Vec<u8>,
+    on_progress: &mut impl FnMut(u64, Option<u64>),
+) -> std::result::Result<(), String> {
+    let offset = buffer.len() as u64;
+    let mut request = client.get(&source.url).headers(headers.clone());
+    if offset > 0 {
+        request = request.header(RANGE, format!("bytes={}-", offset));
+    }
+    let response = timeout(limits.stall_timeout, request.send())
+        .await
+        .map_err(|_| "no response".to_string())?
+        .map_err(|e| e.to_string())?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("status {}", status));
+    }
+
+    let mut total = source.total_size;
+    if status == StatusCode::PARTIAL_CONTENT {
+        let (start, range_total) = content_range(response.headers())
+            .ok_or_else(|| "partial content without a content range".to_string())?;
+        if start != offset {
+            return Err(format!("resumed at byte {} instead of {}", start, offset));
+        }
+        total = total.or(range_total);
+    } else {
+        if offset > 0 {
+            info!(
+                "Update source {} does not resume downloads, starting over",
+                source.url
+            );
+        }
+        buffer.clear();
+        total = total.or(response.content_length());
+    }
+
+    let mut body = response.bytes_stream();
+    let mut window_started = Instant::now();
+    let mut window_bytes = 0_u64;
+    loop {
+        let chunk = match timeout(limits.stall_timeout, body.next()).await {
+            Err(_) => return Err("download stalled".to_string()),
+            Ok(None) => break,
+            Ok(Some(chunk)) => chunk.map_err(|e| e.to_string())?,
+        };
+        buffer.extend_from_slice(&chunk);
+        window_bytes += chunk.len() as u64;
+        on_progress(buffer.len() as u64, total);
+
+        let elapsed = window_started.elapsed();
+        if elapsed >= limits.speed_window {
+            let speed = window_bytes as f64 / elapsed.as_secs_f64();
+            if may_switch && speed < source.bytes_per_sec * limits.slow_ratio {
+                return Err(format!("download slowed down to {:.0} B/s", speed));
+            }
+            window_started = Instant::now();
+            window_bytes = 0;
+        }
+    }
+
+    match total {
+        Some(total) if buffer.len() as u64 != total => {
+            Err(format!("received {} of {} bytes", buffer.len(), total))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Downloads the package from the fastest of `urls`, switching to the next fastest source
+/// whenever one fails or slows down.
+///
+/// `headers` are the update server's, for the first URL. `on_progress` receives the bytes
+/// downloaded so far and the package size if known. Fails with the errors of all sources when
+/// none of them could deliver the package.
+pub(super) async fn download(
+    client: &Client,
+    urls: &[String],
+    headers: &HeaderMap,
+    limits: &DownloadLimits,
+    mut on_progress: impl FnMut(u64, Option<u64>),
+) -> Result<Vec<u8>> {
+    let (ranked, mut errors) = rank_sources(client, urls, headers, limits).await;
+
+    let primary = urls.first().map(String::as_str).unwrap_or_default();
+    let mut buffer = Vec::new();
+    for (i, source) in ranked.iter().enumerate() {
+        info!(
+            "Downloading update from {} ({:.0} KB/s in the speed test)",
+            source.url,
+            source.bytes_per_sec / 1024.0
+        );
+        let may_switch = i + 1 < ranked.len();
+        match fetch_from(
+            client,
+            source,
+            &source_headers(&source.url, primary, headers),
+            limits,
+            may_switch,
+            &mut buffer,
+            &mut on_progress,
+        )
+        .await
+        {
+            Ok(()) => return Ok(buffer),
+            Err(e) => {
+                warn!("Update download from {} failed: {}", source.url, e);
+                errors.push(format!("{}: {}", source.url, e));
+            }
+        }
+    }
+
+    Err(UpdateError::DownloadError(
+        t!(
+            "updater.errors.all_sources_failed",
+            errors = errors.join("; ")
+        )
+        .to_string(),
+    ))
+}
+
+/// Verifies a package against the minisign signature of the release, as the updater plugin
+/// does for the packages it downloads itself. Both the signature and the public key are
+/// base64 encoded.
+pub(super) fn verify_signature(data: &[u8], signature: &str, pubkey: &str) -> Result<()> {
+    let decode = |value: &str| -> Result<String> {
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(value.trim())
+            .map_err(|e| UpdateError::SignatureError(e.to_string()))?;
+        String::from_utf8(bytes).map_err(|e| UpdateError::SignatureError(e.to_string()))
+    };
+    let public_key = PublicKey::decode(&decode(pubkey)?)
+        .map_err(|e| UpdateError::SignatureError(e.to_string()))?;
+    let signature = Signature::decode(&decode(signature)?)
+        .map_err(|e| UpdateError::SignatureError(e.to_string()))?;
+    public_key
+        .verify(data, &signature, true)
+        .map_err(|e| UpdateError::SignatureError(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::spawn_mock_backend;
+    use axum::{
+        body::Body,
+        extract::State,
+        http::{header, HeaderMap as AxumHeaderMap, StatusCode as AxumStatus},
+        response::{IntoResponse, Response},
+        routing::get,
+        Router,
+    };
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone)]
+    struct Mirror {
+        delay: Duration,
+        package: Arc<Vec<u8>>,
+        /// Bytes after which a download from the start breaks off
+        fail_after: Option<usize>,
+        ranges: Arc<Mutex<Vec<String>>>,
+        /// `Authorization` headers of the requests received
+        authorizations: Arc<Mutex<Vec<String>>>,
+    }
+
+    async fn serve_package(State(mirror): State<Mirror>, headers: AxumHeaderMap) -> Response {
+        tokio::time::sleep(mirror.delay).await;
+        if let Some(authorization) = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+        {
+            mirror
+                .authorizations
+                .lock()
+                .unwrap()
+                .push(authorization.to_string());
+        }
+        let range = headers
+            .get(header::RANGE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("bytes="))
+            .map(str::to_string);
+        if let Some(range) = &range {
+            mirror.ranges.lock().unwrap().push(range.clone());
+        }
+
+        let len = mirror.package.len();
+        let (start, end) = match range.as_deref().and_then(|r| r.split_once('-')) {
+            Some((start, end)) => (
+                start.parse().unwrap_or(0),
+                end.parse::<usize>()
+                    .map(|e| e.min(len - 1))
+                    .unwrap_or(len - 1),
+            ),
+            None => (0, len - 1),
+        };
+        let bytes = mirror.package[start..=end].to_vec();
+        let is_full_download = start == 0 && end == len - 1;
+
+        let body = match mirror.fail_after.filter(|_| is_full_download) {
+            Some(fail_after) => {
+                let head = bytes::Bytes::from(bytes[..fail_after].to_vec());
+                // The head is flushed before the connection breaks off
+                let broken = futures_util::stream::once(async {
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    Err(std::io::Error::other("connection reset"))
+                });
+                Body::from_stream(futures_util::stream::once(async move { Ok(head) }).chain(broken))
+            }
+            None => Body::from(bytes),
+        };
+        if range.is_some() {
+            (
+                AxumStatus::PARTIAL_CONTENT,
+                [(
+                    header::CONTENT_RANGE,
+                    format!("bytes {}-{}/{}", start, end, len),
+                )],
+                body,
+            )
+                .into_response()
+        } else {
+            body.into_response()
+        }
+    }
+
+    async fn mirror(mirror: Mirror) -> String {
+        let app = Router::new()
+            .route("/update.bin", get(serve_package))
+            .route(
+                "/missing.bin",
+                get(|| async { AxumStatus::NOT_FOUND.into_response() }),
+            )
+            .with_state(mirror);
+        spawn_mock_backend(app).await
+    }
+
+    fn package() -> Arc<Vec<u8>> {
+        Arc::new((0..200_000).map(|i| (i % 251) as u8).collect())
+    }
+
+    fn limits() -> DownloadLimits {
+        DownloadLimits {
+            probe_bytes: 1024,
+            probe_timeout: Duration::from_secs(5),
+            ..Default::default()
+        }
+    }
+
+    fn mirror_with(delay_ms: u64, fail_after: Option<usize>) -> Mirror {
+        Mirror {
+            delay: Duration::from_millis(delay_ms),
+            package: package(),
+            fail_after,
+            ranges: Arc::new(Mutex::new(Vec::new())),
+            authorizations: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fastest_mirror_is_chosen() {
+        let slow = format!("{}/update.bin", mirror(mirror_with(600, None)).await);
+        let fast = format!("{}/update.bin", mirror(mirror_with(0, None)).await);
+        let medium = format!("{}/update.bin", mirror(mirror_with(300, None)).await);
+        let urls = vec![slow.clone(), fast.clone(), medium.clone()];
+
+        let (ranked, errors) =
+            rank_sources(&Client::new(), &urls, &HeaderMap::new(), &limits()).await;
+        assert!(errors.is_empty());
+        let order: Vec<&str> = ranked.iter().map(|s| s.url.as_str()).collect();
+        assert_eq!(order, vec![fast.as_str(), medium.as_str(), slow.as_str()]);
+        assert_eq!(ranked[0].total_size, Some(200_000));
+    }
+
+    #[tokio::test]
+    async fn test_download_resumes_on_next_mirror() {
+        let breaking = mirror_with(0, Some(50_000));
+        let backup = mirror_with(200, None);
+        let backup_ranges = backup.ranges.clone();
+        let urls = vec![
+            format!("{}/update.bin", mirror(backup).await),
+            format!("{}/update.bin", mirror(breaking).await),
+        ];
+
+        let mut progress = Vec::new();
+        let bytes = download(
+            &Client::new(),
+            &urls,
+            &HeaderMap::new(),
+            &limits(),
+            |downloaded, total| progress.push((downloaded, total)),
+        )
+        .await
+        .expect("package");
+
+        assert_eq!(bytes, *package());
+        // The backup continued where the broken download stopped
+        assert!(backup_ranges
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|range| range.ends_with('-') && range != "0-"));
+        assert_eq!(progress.last(), Some(&(200_000, Some(200_000))));
+    }
+
+    #[tokio::test]
+    async fn test_update_server_headers_are_not_sent_to_mirrors() {
+        let origin = mirror_with(0, None);
+        let other = mirror_with(0, None);
+        let (origin_auth, other_auth) =
+            (origin.authorizations.clone(), other.authorizations.clone());
+        let urls = vec![
+            format!("{}/update.bin", mirror(origin).await),
+            format!("{}/update.bin", mirror(other).await),
+        ];
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            reqwest::header::AUTHORIZATION,
+            HeaderValue::from_static("Bearer release-token"),
+        );
+
+        download(&Client::new(), &urls, &headers, &limits(), |_, _| {})
+            .await
+            .expect("package");
+
+        assert!(!origin_auth.lock().unwrap().is_empty());
+        assert!(origin_auth
+            .lock()
+            .unwrap()
+            .iter()
+            .all(|auth| auth == "Bearer release-token"));
+        assert!(other_auth.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_all_failed_sources_are_reported() {
+        let base = mirror(mirror_with(0, None)).await;
+        let urls = vec![
+            format!("{}/missing.bin", base),
+            "http://127.0.0.1:1/update.bin".to_string(),
+        ];
+        let error = download(
+            &Client::new(),
+            &urls,
+            &HeaderMap::new(),
+            &limits(),
+            |_, _| {},
+        )
+        .await
+        .expect_err("no source");
+        let message = error.to_string();
+        assert!(message.contains("missing.bin"), "{}", message);
+        assert!(message.contains("127.0.0.1:1"), "{}", message);
+    }
+
+    #[test]
+    fn test_tampered_package_fails_verification() {
+        // The updater key of the app from tauri.conf.json
+        let pubkey = "dW50cnVzdGVkIGNvbW1lbnQ6IG1pbmlzaWduIHB1YmxpYyBrZXk6IEVDODQ4QUFEQzc1QzY2QTIKUldTaVpsekhyWXFFN0tUZHdVM2wxcnB1SzIyU1BZeFY5ZnNXN09LMzR1R2w5VHh2V011ODNXOW4K";
+        let signature = base64::engine::general_purpose::STANDARD.encode("not a signature");
+        assert!(matches!(
+            verify_signature(b"package", &signature, pubkey),
+            Err(UpdateError::SignatureError(_))
+        ));
+    }
+
+    #[test]
+    fn test_content_range_is_parsed() {
+        assert_eq!(
+            parse_content_range("bytes 0-1023/200000"),
+            Some((0, Some(200_000)))
+        );
+        assert_eq!(
+            parse_content_range("bytes 50000-199999/*"),
+            Some((50_000, None))
+        );
+        assert_eq!(parse_content_range("items 0-1/2"), None);
+    }
+}
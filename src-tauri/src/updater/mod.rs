@@ -6,6 +6,7 @@
 
 mod error;
 mod manager;
+mod mirror;
 mod types;
 
 pub use error::*;
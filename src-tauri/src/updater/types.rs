@@ -13,3 +13,37 @@ pub struct VersionInfo {
     /// Update description and release notes
     pub notes: String,
 }
+
+/// Download mirrors of update packages, stored under `update_mirrors`.
+///
+/// A mirror is either a URL with a `{url}` placeholder for the announced download URL, as
+/// used by download proxies, or a base URL the package file name is appended to.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct UpdateMirrors(pub Vec<String>);
+
+impl UpdateMirrors {
+    /// The URLs the package can be downloaded from, the announced one first.
+    pub fn source_urls(&self, download_url: &str) -> Vec<String> {
+        let file_name = download_url
+            .split(['?', '#'])
+            .next()
+            .and_then(|path| path.rsplit('/').next())
+            .unwrap_or_default();
+        let mut urls = vec![download_url.to_string()];
+        for mirror in self.0.iter().map(|mirror| mirror.trim()) {
+            if mirror.is_empty() {
+                continue;
+            }
+            let url = if mirror.contains("{url}") {
+                mirror.replace("{url}", download_url)
+            } else {
+                format!("{}/{}", mirror.trim_end_matches('/'), file_name)
+            };
+            if !urls.contains(&url) {
+                urls.push(url);
+            }
+        }
+        urls
+    }
+}
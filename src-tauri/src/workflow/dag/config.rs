@@ -111,6 +111,8 @@ pub struct WorkflowNode {
     #[serde(default)]
     pub dependencies: Option<Vec<String>>,
     pub tool: ToolConfig,
+    #[serde(flatten)]
+    pub execution: NodeExecutionConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -160,6 +162,8 @@ pub struct WorkflowLoop {
     pub dependencies: Option<Vec<String>>,
     #[serde(rename = "loop")]
     pub r#loop: LoopConfig,
+    #[serde(flatten)]
+    pub execution: NodeExecutionConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -195,9 +199,9 @@ pub struct NodeConfig {
     pub id: String,
     /// Type of the node (function, ai, etc.)
     pub r#type: NodeType,
-    /// Timeout in seconds
-    #[serde(default = "default_timeout")]
-    pub timeout_secs: u64,
+    /// Retry, timeout and failure handling of the node
+    #[serde(flatten)]
+    pub execution: NodeExecutionConfig,
     /// Description of the node
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
@@ -230,8 +234,54 @@ pub struct GroupConfig {
     pub nodes: Vec<String>,
 }
 
-fn default_timeout() -> u64 {
-    30
+/// What the executor does once a node has failed all of its attempts
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum NodeFailurePolicy {
+    /// Stop the whole workflow and cancel the nodes still running
+    #[default]
+    Abort,
+    /// Mark the node as failed, skip its dependents and keep running the other nodes
+    Continue,
+}
+
+/// Per-node retry and timeout settings
+///
+/// These fields sit directly on a node or loop item of the workflow json, e.g.
+/// `{"id": "fetch", "tool": {...}, "max_retries": 2, "timeout_ms": 5000, "on_failure": "continue"}`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct NodeExecutionConfig {
+    /// Number of retries after the first failed attempt
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+    /// Timeout of a single attempt in milliseconds, 0 means no timeout
+    #[serde(default)]
+    pub timeout_ms: u64,
+    /// Delay before the first retry in milliseconds, doubled on every further retry
+    #[serde(default = "default_retry_backoff")]
+    pub retry_backoff: u64,
+    /// Behaviour once all attempts have failed
+    #[serde(default)]
+    pub on_failure: NodeFailurePolicy,
+}
+
+fn default_max_retries() -> u32 {
+    3
+}
+
+fn default_retry_backoff() -> u64 {
+    100
+}
+
+impl Default for NodeExecutionConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: default_max_retries(),
+            timeout_ms: 0,
+            retry_backoff: default_retry_backoff(),
+            on_failure: NodeFailurePolicy::default(),
+        }
+    }
 }
 
 impl Default for NodeConfig {
@@ -239,7 +289,7 @@ impl Default for NodeConfig {
         Self {
             id: String::new(),
             r#type: NodeType::Task(ToolConfig::default()),
-            timeout_secs: default_timeout(),
+            execution: NodeExecutionConfig::default(),
             description: None,
         }
     }
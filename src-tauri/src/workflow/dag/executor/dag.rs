@@ -91,7 +91,7 @@ use crate::{tools::ToolDefinition, workflow::error::WorkflowError};
 use crate::{
     tools::ToolManager,
     workflow::dag::{
-        config::{LoopConfig, NodeConfig, NodeType, ToolConfig},
+        config::{LoopConfig, NodeConfig, NodeFailurePolicy, NodeType, ToolConfig},
         context::{Context, NodeState},
         executor::channel::{SharedChannel, WatchChannel},
         graph::WorkflowGraph,
@@ -123,6 +123,8 @@ pub struct WorkflowExecutor {
     ready_queue: Arc<Mutex<VecDeque<NodeConfig>>>,
     /// 记录已完成的节点
     completed_nodes: Arc<Mutex<HashSet<String>>>,
+    /// 记录按 `on_failure: continue` 策略失败的节点
+    failed_nodes: Arc<Mutex<HashSet<String>>>,
 }
 
 impl WorkflowExecutor {
@@ -168,6 +170,7 @@ impl WorkflowExecutor {
             cancel_channel,
             ready_queue: Arc::new(Mutex::new(VecDeque::new())),
             completed_nodes: Arc::new(Mutex::new(HashSet::new())),
+            failed_nodes: Arc::new(Mutex::new(HashSet::new())),
         })
     }
 
@@ -365,6 +368,7 @@ impl WorkflowExecutor {
 
             // 准备任务上下文
             let node_id = node.id.clone();
+            let on_failure = node.execution.on_failure;
             let self_ref = self.clone();
 
             // 生成异步任务
@@ -388,7 +392,7 @@ impl WorkflowExecutor {
 
                 // 处理任务结果
                 self_ref
-                    .handle_task_result(result, start_time, &node_id)
+                    .handle_task_result(result, start_time, &node_id, on_failure)
                     .await
             });
         }
@@ -404,6 +408,7 @@ impl WorkflowExecutor {
     /// * `result` - The result of the task
     /// * `start_time` - The start time of the task
     /// * `node_id` - The ID of the node
+    /// * `on_failure` - Whether a failure aborts the workflow or only skips the node's dependents
     ///
     /// # Returns
     /// * `WorkflowResult<()>` - Returns `Ok(())` if the task result is processed successfully,
@@ -418,6 +423,7 @@ impl WorkflowExecutor {
         result: WorkflowResult<()>,
         start_time: chrono::DateTime<Utc>,
         node_id: &str,
+        on_failure: NodeFailurePolicy,
     ) -> WorkflowResult<()> {
         match result {
             Ok(()) => {
@@ -446,10 +452,23 @@ impl WorkflowExecutor {
             Err(e) => {
                 error!("Node {} failed: {}", node_id, e);
 
+                // 覆盖重试过程中的 Retrying 状态
                 self.context
-                    .update_node_with_count(&node_id, NodeState::Failed(1))
+                    .update_node_state(&node_id, NodeState::Failed(1))
                     .await;
-                Err(e)
+
+                match on_failure {
+                    NodeFailurePolicy::Abort => Err(e),
+                    NodeFailurePolicy::Continue => {
+                        // 后继节点的入度不再递减，因此它们不会被调度，其余可执行节点继续运行
+                        warn!(
+                            "Node {} failed with on_failure=continue, skipping its dependents",
+                            node_id
+                        );
+                        self.failed_nodes.lock().await.insert(node_id.to_string());
+                        Ok(())
+                    }
+                }
             }
         }
     }
@@ -552,7 +571,9 @@ impl WorkflowExecutor {
 
     /// Execute a task node with retry
     ///
-    /// This method executes a task node with retry logic in case of failure.
+    /// This method executes a task node according to its `max_retries`, `timeout_ms`
+    /// and `retry_backoff` settings. An attempt that exceeds the timeout is dropped,
+    /// which cancels the underlying tool call.
     ///
     /// # Arguments
     /// * `node` - The configuration of the task node
@@ -564,31 +585,49 @@ impl WorkflowExecutor {
     /// # Errors
     /// * Returns `WorkflowError` if:
     ///   - The task fails after all retries
+    ///   - The task fails with a non-retriable error
     async fn execute_task_node_with_retry(&self, node: NodeConfig) -> WorkflowResult<()> {
-        // 重试逻辑
-        const MAX_RETRIES: usize = 3;
-        const BASE_DELAY_MS: u64 = 100;
+        let max_retries = node.execution.max_retries;
+        let timeout_ms = node.execution.timeout_ms;
 
         let mut retries = 0;
-        let mut current_delay = BASE_DELAY_MS;
+        let mut current_delay = node.execution.retry_backoff;
         let tool_manager = self.tool_manager.clone();
 
         loop {
-            match self
-                .execute_task_node(node.clone(), tool_manager.clone())
-                .await
-            {
+            let attempt = self.execute_task_node(node.clone(), tool_manager.clone());
+            let result = if timeout_ms > 0 {
+                time::timeout(Duration::from_millis(timeout_ms), attempt)
+                    .await
+                    .unwrap_or_else(|_| {
+                        Err(WorkflowError::Execution(
+                            t!(
+                                "workflow.node_timeout",
+                                node_id = node.id,
+                                timeout_ms = timeout_ms.to_string()
+                            )
+                            .to_string(),
+                        ))
+                    })
+            } else {
+                attempt.await
+            };
+
+            match result {
                 Ok(()) => return Ok(()),
-                Err(e) if e.is_retriable() && retries < MAX_RETRIES => {
+                Err(e) if e.is_retriable() && retries < max_retries => {
                     log::warn!(
                         "Retrying node {} (attempt {}/{}), error: {}",
                         node.id,
                         retries + 1,
-                        MAX_RETRIES,
+                        max_retries,
                         e
                     );
+                    self.context
+                        .update_node_state(&node.id, NodeState::Retrying(retries + 1))
+                        .await;
                     time::sleep(Duration::from_millis(current_delay)).await;
-                    current_delay *= 2;
+                    current_delay = current_delay.saturating_mul(2);
                     retries += 1;
                 }
                 Err(e) => return Err(e),
@@ -931,17 +970,32 @@ impl WorkflowExecutor {
     ///   - The workflow state cannot be update
     async fn finalize_execution(&self, nodes: &[NodeConfig]) -> WorkflowResult<()> {
         let completed = self.completed_nodes.lock().await;
+        let failed = self.failed_nodes.lock().await;
         info!("Finalizing execution. Completed nodes: {:?}", completed);
         info!(
-            "Total nodes: {}, Completed nodes: {}",
+            "Total nodes: {}, Completed nodes: {}, Failed nodes: {}",
             nodes.len(),
-            completed.len()
+            completed.len(),
+            failed.len()
         );
 
         if completed.len() == nodes.len() {
             self.update_state(WorkflowState::Completed).await?;
             info!("Workflow execution completed");
             Ok(())
+        } else if !failed.is_empty() {
+            // Nodes that neither completed nor failed depend on a failed node
+            let skipped = nodes
+                .iter()
+                .filter(|n| !completed.contains(&n.id) && !failed.contains(&n.id))
+                .map(|n| n.id.as_str())
+                .collect::<Vec<_>>();
+            warn!(
+                "Workflow execution completed with failed nodes {:?}, skipped nodes: {:?}",
+                failed, skipped
+            );
+            self.update_state(WorkflowState::Completed).await?;
+            Ok(())
         } else {
             let missing = nodes
                 .iter()
@@ -1335,3 +1389,152 @@ impl WorkflowExecutor {
         output
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+    use serde_json::json;
+
+    use super::*;
+    use crate::ai::traits::chat::MCPToolDeclaration;
+    use crate::tools::{NativeToolResult, ToolCallResult, ToolCategory};
+    use crate::workflow::dag::config::{EdgeConfig, NodeExecutionConfig};
+
+    /// A mock tool that sleeps for `delay_ms` before answering
+    struct SleepTool {
+        name: String,
+        delay_ms: u64,
+    }
+
+    #[async_trait]
+    impl ToolDefinition for SleepTool {
+        fn name(&self) -> &str {
+            &self.name
+        }
+        fn description(&self) -> &str {
+            "Mock"
+        }
+        fn category(&self) -> ToolCategory {
+            ToolCategory::System
+        }
+        fn tool_calling_spec(&self) -> MCPToolDeclaration {
+            MCPToolDeclaration {
+                name: self.name.clone(),
+                description: "Mock".into(),
+                input_schema: json!({}),
+                output_schema: None,
+                disabled: false,
+                scope: Some(self.scope()),
+            }
+        }
+        async fn call(&self, _params: Value) -> NativeToolResult {
+            time::sleep(Duration::from_millis(self.delay_ms)).await;
+            Ok(ToolCallResult::success(Some("ok".into()), None))
+        }
+    }
+
+    fn task_node(id: &str, function: &str, execution: NodeExecutionConfig) -> NodeConfig {
+        NodeConfig {
+            id: id.to_string(),
+            r#type: NodeType::Task(ToolConfig {
+                function: function.to_string(),
+                param: json!({}),
+                output: None,
+            }),
+            execution,
+            description: None,
+        }
+    }
+
+    async fn executor(
+        nodes: Vec<NodeConfig>,
+        edges: Vec<EdgeConfig>,
+    ) -> (WorkflowExecutor, Arc<Context>) {
+        let tool_manager = Arc::new(ToolManager::new());
+        for (name, delay_ms) in [("slow", 5_000), ("fast", 0)] {
+            tool_manager
+                .register_tool(Arc::new(SleepTool {
+                    name: name.to_string(),
+                    delay_ms,
+                }))
+                .await
+                .unwrap();
+        }
+        let context = Arc::new(Context::new());
+        let graph = Arc::new(WorkflowGraph::new(nodes, edges).unwrap());
+        let executor = WorkflowExecutor::create(context.clone(), tool_manager, 4, graph).unwrap();
+        (executor, context)
+    }
+
+    fn timeout_config(on_failure: NodeFailurePolicy) -> NodeExecutionConfig {
+        NodeExecutionConfig {
+            max_retries: 1,
+            timeout_ms: 50,
+            retry_backoff: 10,
+            on_failure,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_node_timeout_aborts_workflow() {
+        let nodes = vec![
+            task_node("slow", "slow", timeout_config(NodeFailurePolicy::Abort)),
+            task_node("after", "fast", NodeExecutionConfig::default()),
+        ];
+        let edges = vec![EdgeConfig {
+            from: "slow".into(),
+            to: "after".into(),
+        }];
+        let (mut executor, context) = executor(nodes, edges).await;
+
+        let started = std::time::Instant::now();
+        let result = executor.execute().await;
+
+        // Two attempts of 50ms plus the backoff, far below the 5s the tool would take
+        assert!(started.elapsed() < Duration::from_secs(2));
+        assert!(matches!(result, Err(WorkflowError::Execution(_))));
+        assert_eq!(context.get_node_state("slow").await, NodeState::Failed(1));
+        assert_eq!(context.get_node_state("after").await, NodeState::Pending);
+    }
+
+    #[tokio::test]
+    async fn test_node_timeout_continues_with_other_nodes() {
+        let nodes = vec![
+            task_node("slow", "slow", timeout_config(NodeFailurePolicy::Continue)),
+            task_node("after_slow", "fast", NodeExecutionConfig::default()),
+            task_node("independent", "fast", NodeExecutionConfig::default()),
+        ];
+        let edges = vec![EdgeConfig {
+            from: "slow".into(),
+            to: "after_slow".into(),
+        }];
+        let (mut executor, context) = executor(nodes, edges).await;
+
+        executor.execute().await.unwrap();
+
+        assert_eq!(context.get_node_state("slow").await, NodeState::Failed(1));
+        assert_eq!(
+            context.get_node_state("after_slow").await,
+            NodeState::Pending
+        );
+        assert_eq!(
+            context.get_node_state("independent").await,
+            NodeState::Completed
+        );
+    }
+
+    #[test]
+    fn test_node_execution_config_from_workflow_json() {
+        let node: crate::workflow::dag::config::WorkflowNode = serde_json::from_value(json!({
+            "id": "fetch",
+            "tool": { "function": "fast", "param": {} },
+            "timeout_ms": 5000,
+            "on_failure": "continue"
+        }))
+        .unwrap();
+
+        assert_eq!(node.execution.timeout_ms, 5000);
+        assert_eq!(node.execution.max_retries, 3);
+        assert_eq!(node.execution.on_failure, NodeFailurePolicy::Continue);
+    }
+}
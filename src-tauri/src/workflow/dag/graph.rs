@@ -359,7 +359,7 @@ mod tests {
         NodeConfig {
             id: id.to_string(),
             r#type: NodeType::Task(ToolConfig::default()),
-            timeout_secs: 0,
+            execution: Default::default(),
             description: None,
         }
     }
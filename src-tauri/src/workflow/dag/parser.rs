@@ -165,7 +165,7 @@ use std::collections::HashSet;
 
 use super::config::{WorkflowGroup, WorkflowItem, WorkflowLoop, WorkflowNode};
 use crate::workflow::dag::{
-    config::{EdgeConfig, GroupConfig, NodeConfig, NodeType},
+    config::{EdgeConfig, GroupConfig, NodeConfig, NodeExecutionConfig, NodeType},
     types::WorkflowResult,
 };
 use crate::workflow::error::WorkflowError;
@@ -604,7 +604,7 @@ impl WorkflowParser {
         nodes.push(NodeConfig {
             id: node.id,
             r#type: NodeType::Task(node.tool),
-            timeout_secs: 0,
+            execution: node.execution,
             description: node.desc,
         });
         Ok(())
@@ -641,7 +641,7 @@ impl WorkflowParser {
         nodes.push(NodeConfig {
             id: loop_item.id.clone(),
             r#type: NodeType::Loop(loop_item.r#loop),
-            timeout_secs: 0,
+            execution: loop_item.execution,
             description: loop_item.desc,
         });
 
@@ -672,7 +672,7 @@ impl WorkflowParser {
                 parallel,
                 nodes: child_nodes,
             }),
-            timeout_secs: 0,
+            execution: NodeExecutionConfig::default(),
             description,
         }
     }
@@ -3,6 +3,7 @@ use crate::ai::error::AiError;
 use crate::ai::interaction::chat_completion::{AiChatEnum, ChatState};
 use crate::ai::traits::chat::ChatMetadata;
 use crate::db::WorkflowMessage;
+use crate::libs::retry::is_retryable_status;
 use crate::tools::TOOL_ASK_USER;
 use crate::tools::TOOL_COMPLETE_WORKFLOW;
 use crate::workflow::react::context::ContextManager;
@@ -336,9 +337,7 @@ impl ContextCompressor {
 
     fn should_retry_compression_error(error: &AiError) -> bool {
         match error {
-            AiError::ApiRequestFailed { status_code, .. } => {
-                *status_code == 408 || *status_code == 429 || *status_code >= 500
-            }
+            AiError::ApiRequestFailed { status_code, .. } => is_retryable_status(*status_code),
             AiError::InitFailed(_)
             | AiError::InvalidInput(_)
             | AiError::ToolCallSerializationFailed { .. } => false,
@@ -480,6 +480,37 @@ impl WorkflowExecutor {
             .collect()
     }
 
+    fn loop_detector(main_store: &Arc<std::sync::RwLock<MainStore>>) -> LoopDetector {
+        let threshold = match main_store.read() {
+            Ok(store) => store.get_config(
+                crate::constants::CFG_WORKFLOW_LOOP_REPEAT_THRESHOLD,
+                crate::workflow::react::loop_detector::LOOP_IDENTICAL_CONSECUTIVE_THRESHOLD,
+            ),
+            Err(e) => {
+                log::warn!("Failed to read loop repeat threshold config: {}", e);
+                crate::workflow::react::loop_detector::LOOP_IDENTICAL_CONSECUTIVE_THRESHOLD
+            }
+        };
+        LoopDetector::with_identical_threshold(threshold)
+    }
+
+    fn format_hook_config(&self) -> crate::tools::FormatHookConfig {
+        match self.context.main_store.read() {
+            Ok(store) => store.get_config(
+                crate::constants::CFG_WORKFLOW_FORMAT_ON_EDIT,
+                crate::tools::FormatHookConfig::default(),
+            ),
+            Err(e) => {
+                log::warn!(
+                    "WorkflowExecutor {}: Failed to read format hook config: {}",
+                    self.session_id,
+                    e
+                );
+                crate::tools::FormatHookConfig::default()
+            }
+        }
+    }
+
     fn available_tools_allowlist(raw_tools: Option<&str>) -> Option<HashSet<String>> {
         raw_tools.map(|tools| serde_json::from_str::<HashSet<String>>(tools).unwrap_or_default())
     }
@@ -905,7 +936,7 @@ impl WorkflowExecutor {
         self.pending_approval_queue.clear();
         self.smart_approved_bash_commands.clear();
         self.smart_approved_tool_call_ids.clear();
-        self.loop_detector = LoopDetector::new();
+        self.loop_detector = Self::loop_detector(&self.context.main_store);
         self.recovery_failed = false;
         self.recovery_error = None;
         self.queued_user_messages.clear();
@@ -1073,7 +1104,7 @@ impl WorkflowExecutor {
             background_compression_retry_state: HashMap::new(),
             auto_compress_enabled,
             policy,
-            loop_detector: LoopDetector::new(),
+            loop_detector: Self::loop_detector(&main_store),
             pending_approvals: Arc::new(dashmap::DashMap::new()),
             pending_approval_queue: VecDeque::new(),
             smart_approved_bash_commands: HashSet::new(),
@@ -1616,6 +1647,7 @@ impl WorkflowExecutor {
             .contains(&ToolCategory::FileSystem)
         {
             let path_guard = Some(self.path_guard.clone());
+            let format_hook = FormatHook::new(self.format_hook_config());
             if is_allowed(TOOL_READ_FILE) {
                 tm.register_tool(Arc::new(ReadFile::new(path_guard.clone())))
                     .await?;
@@ -1623,12 +1655,16 @@ impl WorkflowExecutor {
 
             if self.policy.allows_generic_workspace_mutation_tools() && is_allowed(TOOL_WRITE_FILE)
             {
-                tm.register_tool(Arc::new(WriteFile::new(path_guard.clone())))
-                    .await?;
+                tm.register_tool(Arc::new(
+                    WriteFile::new(path_guard.clone()).with_format_hook(format_hook.clone()),
+                ))
+                .await?;
             }
             if self.policy.allows_generic_workspace_mutation_tools() && is_allowed(TOOL_EDIT_FILE) {
-                tm.register_tool(Arc::new(EditFile::new(path_guard.clone())))
-                    .await?;
+                tm.register_tool(Arc::new(
+                    EditFile::new(path_guard.clone()).with_format_hook(format_hook),
+                ))
+                .await?;
             }
             if is_allowed(TOOL_LIST_DIR) {
                 tm.register_tool(Arc::new(ListDir::new(path_guard.clone())))
@@ -2662,13 +2698,14 @@ impl WorkflowExecutor {
                                     }
 
                                     // 2. Execution: MCP tools use global, native tools use local
-                                    // Inject internal tool_call_id for streaming tools
+                                    // Inject internal tool_call_id for streaming tools and session_id for search citations
                                     // Ensure tool_args is an object (parse if it's a JSON string from fallback)
                                     let tool_args_obj =
                                         Self::normalize_tool_arguments_value(tool_args.clone());
-                                    let enriched_args = Self::enrich_tool_arguments_with_call_id(
+                                    let enriched_args = Self::enrich_tool_arguments(
                                         &tool_args_obj,
                                         &tool_call_id,
+                                        &self.session_id,
                                     );
 
                                     self.append_tool_started_event(
@@ -3161,12 +3198,15 @@ impl WorkflowExecutor {
                             }
 
                             // 2. Execution: MCP tools use global, native tools use local
-                            // Inject internal tool_call_id for streaming tools
+                            // Inject internal tool_call_id for streaming tools and session_id for search citations
                             // Ensure tool_args is an object (parse if it's a JSON string from fallback)
                             let tool_args_obj =
                                 Self::normalize_tool_arguments_value(tool_args.clone());
-                            let enriched_args =
-                                Self::enrich_tool_arguments_with_call_id(&tool_args_obj, signal_id);
+                            let enriched_args = Self::enrich_tool_arguments(
+                                &tool_args_obj,
+                                signal_id,
+                                &self.session_id,
+                            );
 
                             self.append_tool_started_event(signal_id, &tool_name, &tool_args_obj);
                             self.dispatch_tool_started_payload(
@@ -4282,9 +4322,10 @@ impl WorkflowExecutor {
         }
     }
 
-    fn enrich_tool_arguments_with_call_id(
+    fn enrich_tool_arguments(
         args: &serde_json::Value,
         tool_call_id: &str,
+        session_id: &str,
     ) -> serde_json::Value {
         let mut enriched_args = match args {
             serde_json::Value::Object(map) => serde_json::Value::Object(map.clone()),
@@ -4294,6 +4335,7 @@ impl WorkflowExecutor {
         };
         enriched_args[crate::constants::INTERNAL_PARAM_TOOL_CALL_ID] =
             serde_json::json!(tool_call_id);
+        enriched_args[crate::constants::INTERNAL_PARAM_SESSION_ID] = serde_json::json!(session_id);
         enriched_args
     }
 
@@ -4747,7 +4789,15 @@ impl WorkflowExecutor {
                 .cloned()
                 .or_else(|| func.get("input").cloned())
                 .unwrap_or(serde_json::json!({}));
-            let args = Self::normalize_tool_arguments_value(args_raw);
+            let mut args = Self::normalize_tool_arguments_value(args_raw);
+            if crate::tools::helper::normalize_file_tool_arguments(&name, &mut args) {
+                log::debug!(
+                    "WorkflowExecutor {}: Restored escaped line breaks in arguments of {} ({})",
+                    self.session_id,
+                    name,
+                    id
+                );
+            }
 
             call_order.push(id.clone());
 
@@ -4899,8 +4949,8 @@ impl WorkflowExecutor {
                 let gtm_clone = gtm.clone();
                 let semaphore_clone = semaphore.clone();
 
-                // Inject internal tool_call_id for streaming tools
-                let enriched_args = Self::enrich_tool_arguments_with_call_id(&args, &id);
+                // Inject internal tool_call_id for streaming tools and session_id for search citations
+                let enriched_args = Self::enrich_tool_arguments(&args, &id, &self.session_id);
 
                 tool_futures.push_back(async move {
                     let _permit = semaphore_clone.acquire().await.ok();
@@ -4938,8 +4988,8 @@ impl WorkflowExecutor {
             self.append_tool_started_event(&id, &name, &args);
             self.dispatch_tool_started_payload(&id, &name, &args).await;
 
-            // Inject internal tool_call_id for streaming tools
-            let enriched_args = Self::enrich_tool_arguments_with_call_id(&args, &id);
+            // Inject internal tool_call_id for streaming tools and session_id for search citations
+            let enriched_args = Self::enrich_tool_arguments(&args, &id, &self.session_id);
 
             let final_res = if name.contains(crate::tools::MCP_TOOL_NAME_SPLIT) {
                 if self.is_mcp_tool_allowed(&name) {
@@ -5268,6 +5318,36 @@ impl WorkflowExecutor {
 
         // 2.1 Loop Detection
         if let Some(warning) = self.loop_detector.record_and_check(name, args) {
+            // The agent already got the same observation and a warning, another identical
+            // call means it cannot make progress on its own.
+            if self.loop_detector.is_stuck() {
+                log::error!(
+                    "WorkflowExecutor {}: Tool '{}' repeated with identical arguments after a loop warning, stopping the workflow",
+                    self.session_id,
+                    name
+                );
+                let message = format!(
+                    "Workflow stopped: '{}' was called again with identical arguments after a loop warning.",
+                    name
+                );
+                let _ = self
+                    .dispatch_ui_payload(GatewayPayload::Error {
+                        message: message.clone(),
+                    })
+                    .await;
+                self.update_state(WorkflowState::Error).await?;
+                return Ok(Some(ReinforcedResult {
+                    content: format!("ERROR: LOOP ABORTED\n{}", message),
+                    llm_content: None,
+                    title: format!("Loop Check: {}", name),
+                    summary: "Stopped after repeated loop".to_string(),
+                    is_error: true,
+                    error_type: Some("LoopAborted".to_string()),
+                    display_type: "text".to_string(),
+                    approval_status: None,
+                    observation_kind: None,
+                }));
+            }
             log::warn!(
                 "WorkflowExecutor {}: Loop detected for tool '{}'. Intercepting...",
                 self.session_id,
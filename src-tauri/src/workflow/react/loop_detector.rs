@@ -3,8 +3,9 @@ use std::collections::VecDeque;
 /// Window size for the repetition detector (number of recent tool calls to inspect).
 const LOOP_DETECT_WINDOW: usize = 18;
 
-/// Minimum consecutive identical tool calls that trigger a loop warning.
-const LOOP_IDENTICAL_CONSECUTIVE_THRESHOLD: usize = 3;
+/// Default minimum consecutive identical tool calls that trigger a loop warning, overridden by
+/// `workflow_loop_repeat_threshold`.
+pub(crate) const LOOP_IDENTICAL_CONSECUTIVE_THRESHOLD: usize = 3;
 
 /// Minimum consecutive repeated tool-call sequence count before surfacing a warning.
 const LOOP_SEQUENCE_REPEAT_THRESHOLD: usize = 3;
@@ -23,14 +24,24 @@ pub(crate) struct LoopDetector {
     last_no_tool_response: Option<String>,
     /// Consecutive count of identical no-tool assistant responses.
     consecutive_no_tool_responses: usize,
+    /// Consecutive identical tool calls that trigger a loop warning.
+    identical_threshold: usize,
 }
 
 impl LoopDetector {
+    #[cfg(test)]
     pub fn new() -> Self {
+        Self::with_identical_threshold(LOOP_IDENTICAL_CONSECUTIVE_THRESHOLD)
+    }
+
+    /// A detector that warns after `threshold` consecutive identical tool calls. A single
+    /// call is never a loop, so the threshold is at least 2.
+    pub fn with_identical_threshold(threshold: usize) -> Self {
         Self {
             recent_calls: VecDeque::with_capacity(LOOP_DETECT_WINDOW),
             last_no_tool_response: None,
             consecutive_no_tool_responses: 0,
+            identical_threshold: threshold.max(2),
         }
     }
 
@@ -92,16 +103,9 @@ impl LoopDetector {
             self.recent_calls.pop_front();
         }
 
-        let consecutive_count = self
-            .recent_calls
-            .iter()
-            .rev()
-            .take_while(|(recent_tool_name, recent_args_hash)| {
-                recent_tool_name == tool_name && *recent_args_hash == args_hash
-            })
-            .count();
+        let consecutive_count = self.consecutive_identical_calls();
 
-        if consecutive_count >= LOOP_IDENTICAL_CONSECUTIVE_THRESHOLD {
+        if consecutive_count >= self.identical_threshold {
             let task_output_guidance = if tool_name == crate::tools::TOOL_SUB_AGENT_OUTPUT {
                 "\nFor sub_agent_output specifically: do NOT call sub_agent_output again for the same missing or unavailable task_id. sub_agent_output only retrieves results for sub-agent IDs returned by sub_agent_run; it is not a final-answer/output tool. If no valid sub-agent exists, continue with another appropriate tool or report the limitation."
             } else {
@@ -124,6 +128,24 @@ impl LoopDetector {
         }
     }
 
+    /// Whether the latest tool call repeated an identical call the agent was already warned
+    /// about. Warning again is pointless then, the caller should stop the loop instead.
+    pub fn is_stuck(&self) -> bool {
+        self.consecutive_identical_calls() > self.identical_threshold
+    }
+
+    /// Number of identical calls at the end of the window, the latest call included.
+    fn consecutive_identical_calls(&self) -> usize {
+        let Some(latest) = self.recent_calls.back() else {
+            return 0;
+        };
+        self.recent_calls
+            .iter()
+            .rev()
+            .take_while(|call| *call == latest)
+            .count()
+    }
+
     /// Records a no-tool assistant response and returns a warning if the exact same
     /// normalized response has been repeated consecutively for too long.
     pub fn record_no_tool_response_and_check(&mut self, response_text: &str) -> Option<String> {
@@ -387,4 +409,47 @@ mod tests {
             assert!(detector.record_and_check("bash", &bash_args).is_none());
         }
     }
+
+    #[test]
+    fn repeated_call_after_warning_is_stuck() {
+        let mut detector = LoopDetector::new();
+        let args = json!({"query":"rust async runtime comparison"});
+
+        for _ in 0..2 {
+            assert!(detector.record_and_check("tavily_search", &args).is_none());
+            assert!(!detector.is_stuck());
+        }
+        assert!(detector.record_and_check("tavily_search", &args).is_some());
+        assert!(
+            !detector.is_stuck(),
+            "the first warning gives the agent a chance"
+        );
+
+        assert!(detector.record_and_check("tavily_search", &args).is_some());
+        assert!(detector.is_stuck());
+
+        // A different call breaks the streak
+        assert!(detector
+            .record_and_check("tavily_search", &json!({"query":"tokio vs async-std"}))
+            .is_none());
+        assert!(!detector.is_stuck());
+    }
+
+    #[test]
+    fn identical_threshold_is_configurable() {
+        let mut detector = LoopDetector::with_identical_threshold(5);
+        let args = json!({"query":"same"});
+
+        for _ in 0..4 {
+            assert!(detector.record_and_check("tavily_search", &args).is_none());
+        }
+        assert!(detector.record_and_check("tavily_search", &args).is_some());
+        assert!(!detector.is_stuck());
+        detector.record_and_check("tavily_search", &args);
+        assert!(detector.is_stuck());
+
+        // A threshold of one would flag every call
+        let mut detector = LoopDetector::with_identical_threshold(1);
+        assert!(detector.record_and_check("tavily_search", &args).is_none());
+    }
 }
@@ -220,6 +220,7 @@ The single chosen report must clearly state:
 
 If there are no known remaining issues, say so explicitly.
 If verification was skipped, impossible, partial, or only reasoned through, state that clearly.
+When the report relies on `web_search` results, mark each sourced claim with `[n]`, where `n` is the result `id`. Ids are stable for the whole session, so the same source keeps the same number across searches.
 Reasoning/thinking text does not count as a report.
 
 ## Pre-Completion Checklist
@@ -19,8 +19,10 @@ const CHINESE_CHARS_REGEX = /[\u4e00-\u9fa5]/
 const CHINESE_CHARS_GROUP_REGEX = /([\u4e00-\u9fa5]+)/g
 
 import { getLanguageByCode } from '@/i18n/langUtils'
+import { useMcpStore } from '@/stores/mcp'
 import { useSettingStore } from '@/stores/setting'
 
+const mcpStore = useMcpStore()
 const settingStore = useSettingStore()
 
 /**
@@ -665,6 +667,10 @@ export const handleChatMessage = (payload, chatStateRef, refs, onComplete) => {
       }
       break
 
+    case 'toolOutput':
+      // Live output of a running tool, the latest line is shown as progress
+      chatState.step = (payload?.chunk || '').trim().split('\n').pop() || chatState.step
+      return false
     case 'step':
       chatState.step = payload?.chunk || ''
       return false
@@ -739,16 +745,7 @@ const createToolCallHtml = (content, toolCalls) => {
           args = []
         }
       }
-      if (functionName.includes('__MCP__')) {
-        const names = functionName.split('__MCP__')
-        if (names.length === 2) {
-          tools += `<div class="tool-name ${status}"><span>${toolName(`${names[0]}::${names[1]}`, args)}</span></div>`
-        } else {
-          tools += `<div class="tool-name ${status}"><span>${toolName(functionName, args)}</span></div>`
-        }
-      } else {
-        tools += `<div class="tool-name ${status}"><span>${toolName(functionName, args)}</span></div>`
-      }
+      tools += `<div class="tool-name ${status}"><span>${toolName(toolDisplayName(functionName), args)}</span></div>`
       const result =
         typeof call.result === 'string' ? call.result : JSON.stringify(call.result, null, 2)
       // Escape HTML in arguments and result to prevent XSS and highlight.js warnings
@@ -771,6 +768,35 @@ const createToolCallHtml = (content, toolCalls) => {
   return content
 }
 
+/**
+ * Finds the MCP server of a called tool.
+ * MCP tools are called by their own name, or `{server}_{tool}` when the name conflicts;
+ * messages saved before carry `{server}__MCP__{tool}`.
+ * @param {string} functionName - The name the model called the tool by
+ * @returns {{ server: string, name: string }} The server (empty for native tools) and the tool's own name
+ */
+export const parseToolName = functionName => {
+  const source = mcpStore.toolSources[functionName]
+  if (source) {
+    return { server: source.server, name: source.tool }
+  }
+  const names = functionName.split('__MCP__')
+  if (names.length === 2) {
+    return { server: names[0], name: names[1] }
+  }
+  return { server: '', name: functionName }
+}
+
+/**
+ * The tool name shown in the chat, `server::tool` for MCP tools.
+ * @param {string} functionName - The name the model called the tool by
+ * @returns {string}
+ */
+export const toolDisplayName = functionName => {
+  const { server, name } = parseToolName(functionName)
+  return server ? `${server}::${name}` : name
+}
+
 export const toolName = (functionName, args) => {
   switch (functionName) {
     case 'WebSearch':
@@ -94,7 +94,7 @@ export const useChatStore = defineStore('chat', () => {
       invokeWrapper('add_conversation', { title }).then((conversationId) => {
         if (conversationId) {
           setCurrentConversationId(conversationId)
-          const conversation = { id: conversationId, title, isFavorite: false, createdAt: new Date().toLocaleString() }
+          const conversation = { id: conversationId, title, isFavorite: false, isPinned: false, isArchived: false, createdAt: new Date().toLocaleString() }
           conversations.value.unshift(conversation)
 
           // clear messages
@@ -146,6 +146,36 @@ export const useChatStore = defineStore('chat', () => {
     })
   }
 
+  /**
+   * Pins or unpins a conversation and reloads the list, as pinned conversations come first.
+   * @param {number} id - The ID of the conversation to update.
+   * @param {boolean} pinned - The new pinned state.
+   * @returns {Promise<void>} A promise that resolves when the list is reloaded.
+   */
+  const setConversationPinned = (id, pinned) => {
+    return invokeWrapper('set_conversation_pinned', { id, pinned })
+      .then(() => loadConversations())
+      .catch((error) => {
+        console.error('Error pinning conversation:', error);
+        throw error
+      })
+  }
+
+  /**
+   * Archives or restores a conversation and reloads the list, which leaves archived ones out.
+   * @param {number} id - The ID of the conversation to update.
+   * @param {boolean} archived - The new archived state.
+   * @returns {Promise<void>} A promise that resolves when the list is reloaded.
+   */
+  const setConversationArchived = (id, archived) => {
+    return invokeWrapper('set_conversation_archived', { id, archived })
+      .then(() => loadConversations())
+      .catch((error) => {
+        console.error('Error archiving conversation:', error);
+        throw error
+      })
+  }
+
   /**
    * Deletes a conversation by its ID and updates the state.
    * @param {number} id - The ID of the conversation to delete.
@@ -269,6 +299,35 @@ export const useChatStore = defineStore('chat', () => {
     })
   }
 
+  /**
+   * Finishes an assistant message that `chat_completion` persisted while it streamed.
+   *
+   * @param {number} messageId - The ID returned by `chat_completion`.
+   * @param {number} conversationId - The ID of the conversation of the message.
+   * @param {string} content - The final content of the message.
+   * @param {object} metadata - The final metadata, `incomplete: true` keeps it flagged truncated.
+   * @returns {Promise<number>} A promise that resolves to the ID of the message.
+   */
+  const saveStreamedMessage = (messageId, conversationId, content, metadata = {}) => {
+    return invokeWrapper('update_message', { id: messageId, content, metadata })
+      .then(() => {
+        if (currentConversationId.value === conversationId) {
+          messages.value = [...messages.value, {
+            id: messageId,
+            conversationId,
+            role: 'assistant',
+            content,
+            metadata
+          }]
+        }
+        return messageId
+      })
+      .catch((error) => {
+        console.error('Error saving streamed message:', error);
+        throw error
+      })
+  }
+
   /**
    * Deletes a message by its ID and updates the state.
    * @param {Array<number>} ids - The IDs of the messages to delete.
@@ -291,6 +350,58 @@ export const useChatStore = defineStore('chat', () => {
     })
   }
 
+  /**
+   * Deletes messages, with the messages sharing a tool call with them, and updates the state.
+   * @param {Array<number>} ids - The IDs of the messages to delete.
+   * @returns {Promise<Array<number>>} The IDs of all deleted messages.
+   */
+  const deleteMessages = (ids) => {
+    return invokeWrapper('delete_messages', { ids })
+      .then((deletedIds) => {
+        messages.value = messages.value.filter((message) => !deletedIds.includes(message.id))
+        return deletedIds
+      })
+      .catch((error) => {
+        console.error('Error deleting messages:', error);
+        throw error
+      })
+  }
+
+  /**
+   * Deletes every message after the given one, keeping the results of its tool calls.
+   * @param {number} messageId - The ID of the last message to keep.
+   * @returns {Promise<Array<number>>} The IDs of the deleted messages.
+   */
+  const deleteMessagesAfter = (messageId) => {
+    return invokeWrapper('delete_messages_after', { messageId })
+      .then((deletedIds) => {
+        messages.value = messages.value.filter((message) => !deletedIds.includes(message.id))
+        return deletedIds
+      })
+      .catch((error) => {
+        console.error('Error deleting messages:', error);
+        throw error
+      })
+  }
+
+  /**
+   * Deletes all messages of a conversation, keeping the conversation.
+   * @param {number} id - The ID of the conversation to clear.
+   * @returns {Promise<void>} A promise that resolves when the messages are deleted.
+   */
+  const clearConversation = (id) => {
+    return invokeWrapper('clear_conversation', { id })
+      .then(() => {
+        if (currentConversationId.value === id) {
+          messages.value = []
+        }
+      })
+      .catch((error) => {
+        console.error('Error clearing conversation:', error);
+        throw error
+      })
+  }
+
   const clearContext = () => {
     return new Promise((resolve, reject) => {
       // if the last message is context cleared or messages is empty, then do nothing
@@ -323,11 +434,17 @@ export const useChatStore = defineStore('chat', () => {
     createConversation,
     deleteConversation,
     updateConversation,
+    setConversationPinned,
+    setConversationArchived,
     messages,
     appendMessage,
     loadMessages,
     addChatMessage,
+    saveStreamedMessage,
     deleteMessage,
+    deleteMessages,
+    deleteMessagesAfter,
+    clearConversation,
     clearContext
   }
 });
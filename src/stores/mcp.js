@@ -53,6 +53,11 @@ export const useMcpStore = defineStore('mcp', () => {
    * @type {import('vue').Ref<Record<number, { expanded: boolean, loading: boolean }>>}
   */
   const serverUiStates = ref({});
+  /**
+   * The server of each MCP tool and the tool's own name, keyed by the name the model calls it by
+   * @type {import('vue').Ref<Record<string, { server: string, tool: string }>>}
+   */
+  const toolSources = ref({});
 
   const loading = ref(false);
   const error = ref(null);
@@ -279,6 +284,24 @@ export const useMcpStore = defineStore('mcp', () => {
     }
   };
 
+  /**
+   * Fetches which server each MCP tool comes from.
+   * The model calls MCP tools by their own name and only gets a server prefix on name conflicts,
+   * so the server can't be read from the tool name.
+   */
+  const fetchToolSources = async () => {
+    try {
+      const tools = await invokeWrapper('list_available_tools');
+      toolSources.value = Object.fromEntries(
+        (tools || [])
+          .filter(tool => tool.source?.type === 'mcp')
+          .map(tool => [tool.name, { server: tool.source.server, tool: tool.source.tool }])
+      );
+    } catch (err) {
+      console.error('Failed to fetch MCP tool sources:', err);
+    }
+  };
+
   /**
    * Toggles the disabled status of a specific tool for a server by updating the server's configuration.
    * This function modifies the server's `config.disabled_tools` array and calls the backend
@@ -368,12 +391,14 @@ export const useMcpStore = defineStore('mcp', () => {
       if (status === 'running') {
         console.debug(`Server "${serverName}" is now running, refreshing its tool list.`);
         fetchMcpServerTools(server.id);
+        fetchToolSources();
       }
     }
   };
 
 
   fetchMcpServers();
+  fetchToolSources();
 
   // Helper to get or initialize UI state for a server, meant for internal store use or direct component use
   const getOrInitServerUiState = (serverId) => {
@@ -486,6 +511,7 @@ export const useMcpStore = defineStore('mcp', () => {
   return {
     servers,
     serverTools,
+    toolSources,
     loading,
     error,
     fetchMcpServers,
@@ -498,6 +524,7 @@ export const useMcpStore = defineStore('mcp', () => {
     restartMcpServer,
     refreshMcpTools,
     fetchMcpServerTools,
+    fetchToolSources,
     toggleDisableTool,
     handleSyncStateUpdate,
     serverUiStates,
@@ -323,6 +323,18 @@ export const useModelStore = defineStore('modelProvider', () => {
     })
   }
 
+  /**
+   * Guess the api protocol of a provider from its base url
+   * @param {string} baseUrl - The base url entered by the user
+   * @returns {Promise<string|null>} The guessed protocol, or null if the url is not recognized
+   */
+  const detectApiProtocol = (baseUrl) => {
+    return invokeWrapper('detect_api_protocol', { baseUrl }).catch(err => {
+      console.error('detect_api_protocol error:', err);
+      return null
+    })
+  }
+
   // =================================================
   // Initialize the model store and export the functions
   // =================================================
@@ -346,6 +358,7 @@ export const useModelStore = defineStore('modelProvider', () => {
     setDefaultModelProvider,
     deleteModelProvider,
     updateModelProviderOrder,
-    listModels
+    listModels,
+    detectApiProtocol
   };
 })
@@ -30,6 +30,7 @@ const defaultSettings = {
   historyMessages: 5,
   conversationTitleGenModel: { id: '', model: '' },
   sendMessageKey: 'Enter',
+  shellAuthorizedPaths: [],
   // shortcut settings
   mainWindowVisibleShortcut: null,
   noteWindowVisibleShortcut: null,
@@ -49,6 +50,7 @@ const defaultSettings = {
   wordSelectionToolbar: false,
   autoStart: false,
   autoUpdate: true,
+  updateMirrors: [],
   backupDir: '',
   // chat completion proxy settings
   // Allows defining grouped model aliases.
@@ -96,12 +98,14 @@ const defaultSettings = {
   googleApiKey: '',
   googleSearchId: '',
   serperApiKey: '',
+  bingApiKey: '',
   tavilyApiKey: '',
   websearchModel: { id: '', model: '' },
   // vision model settings
   visionModel: { id: '', model: '' },
   searchEngine: '',
-  scraperConcurrencyCount: 5
+  scraperConcurrencyCount: 5,
+  webFetchRotateUserAgent: true
 }
 
 /**